@@ -0,0 +1,28 @@
+-- every change to a global UDF creates a new immutable version
+CREATE TABLE udf_versions (
+    pub_id VARCHAR PRIMARY KEY,
+    udf_id VARCHAR NOT NULL REFERENCES udfs(pub_id) ON DELETE CASCADE,
+    organization_id VARCHAR NOT NULL,
+    version INTEGER NOT NULL,
+    created_by VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    definition TEXT NOT NULL,
+    description TEXT,
+
+    UNIQUE(udf_id, version)
+);
+
+ALTER TABLE udfs ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- backfill the existing definitions as their first version
+INSERT INTO udf_versions (pub_id, udf_id, organization_id, version, created_by, created_at, definition, description)
+SELECT pub_id || '_v1', pub_id, organization_id, 1, created_by, updated_at, definition, description
+FROM udfs;
+
+-- the UDF versions that each pipeline was compiled with
+CREATE TABLE pipeline_udf_versions (
+    pipeline_id BIGINT NOT NULL REFERENCES pipelines(id) ON DELETE CASCADE,
+    udf_version_id VARCHAR NOT NULL REFERENCES udf_versions(pub_id) ON DELETE CASCADE,
+
+    PRIMARY KEY (pipeline_id, udf_version_id)
+);
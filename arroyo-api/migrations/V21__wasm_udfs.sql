@@ -0,0 +1,3 @@
+-- prebuilt WASM modules for UDFs that are called through the WASM UDF ABI instead of being compiled
+ALTER TABLE udfs ADD COLUMN wasm BYTEA;
+ALTER TABLE udf_versions ADD COLUMN wasm BYTEA;
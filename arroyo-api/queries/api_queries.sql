@@ -165,6 +165,15 @@ VALUES (:pub_id, :pipeline_id, :connection_table_id);
 DELETE FROM pipelines
 WHERE pub_id = :pub_id AND organization_id = :organization_id;
 
+--! update_pipeline_program
+UPDATE pipelines
+SET
+    updated_at = :updated_at,
+    updated_by = :updated_by,
+    program = :program
+WHERE pub_id = :pub_id AND organization_id = :organization_id
+RETURNING id;
+
 
 ----------- jobs -----------------------
 
@@ -286,27 +295,75 @@ LIMIT :limit::integer;
 
 ----------- udfs -----------------------
 
---: DbUdf (description?)
+--: DbUdf (description?, wasm?)
 
---! create_udf
-INSERT INTO udfs (pub_id, organization_id, created_by, prefix, name, definition, description)
-VALUES (:pub_id, :organization_id, :created_by, :prefix, :name, :definition, :description);
+--! create_udf (wasm?)
+INSERT INTO udfs (pub_id, organization_id, created_by, prefix, name, definition, description, wasm)
+VALUES (:pub_id, :organization_id, :created_by, :prefix, :name, :definition, :description, :wasm);
+
+--! update_udf (description?, wasm?)
+UPDATE udfs
+SET
+    updated_at = :updated_at,
+    definition = :definition,
+    description = COALESCE(:description, description),
+    wasm = :wasm,
+    version = version + 1
+WHERE organization_id = :organization_id AND pub_id = :pub_id;
 
 --! get_udf: DbUdf
-SELECT pub_id, prefix, name, definition, created_at, updated_at, description
+SELECT pub_id, prefix, name, definition, created_at, updated_at, description, version, wasm
 FROM udfs
 WHERE organization_id = :organization_id AND pub_id = :pub_id;
 
 --! get_udf_by_name: DbUdf
-SELECT pub_id, prefix, name, definition, created_at, updated_at, description
+SELECT pub_id, prefix, name, definition, created_at, updated_at, description, version, wasm
 FROM udfs
 WHERE organization_id = :organization_id AND name = :name;
 
 --! get_udfs: DbUdf
-SELECT pub_id, prefix, name, definition, created_at, updated_at, description
+SELECT pub_id, prefix, name, definition, created_at, updated_at, description, version, wasm
 FROM udfs
 WHERE organization_id = :organization_id;
 
 --! delete_udf
 DELETE FROM udfs
 WHERE organization_id = :organization_id AND pub_id = :pub_id;
+
+--: DbUdfVersion (description?, wasm?)
+
+--! create_udf_version (description?, wasm?)
+INSERT INTO udf_versions (pub_id, udf_id, organization_id, version, created_by, definition, description, wasm)
+VALUES (:pub_id, :udf_id, :organization_id, :version, :created_by, :definition, :description, :wasm);
+
+--! get_udf_versions: DbUdfVersion
+SELECT pub_id, udf_id, version, created_by, created_at, definition, description, wasm
+FROM udf_versions
+WHERE organization_id = :organization_id AND udf_id = :udf_id
+ORDER BY version DESC;
+
+--! get_udf_version: DbUdfVersion
+SELECT pub_id, udf_id, version, created_by, created_at, definition, description, wasm
+FROM udf_versions
+WHERE organization_id = :organization_id AND udf_id = :udf_id AND version = :version;
+
+--! add_pipeline_udf_version
+INSERT INTO pipeline_udf_versions (pipeline_id, udf_version_id)
+SELECT :pipeline_id, pub_id
+FROM udf_versions
+WHERE udf_id = :udf_id AND version = :version;
+
+--! delete_pipeline_udf_versions
+DELETE FROM pipeline_udf_versions
+WHERE pipeline_id = :pipeline_id;
+
+--: DbPipelineUdfVersion ()
+
+--! get_pipeline_udf_versions: DbPipelineUdfVersion
+SELECT udfs.pub_id AS udf_id, udfs.name, udf_versions.version, udfs.version AS latest_version
+FROM pipeline_udf_versions
+    INNER JOIN pipelines ON pipelines.id = pipeline_udf_versions.pipeline_id
+    INNER JOIN udf_versions ON udf_versions.pub_id = pipeline_udf_versions.udf_version_id
+    INNER JOIN udfs ON udfs.pub_id = udf_versions.udf_id
+WHERE pipelines.organization_id = :organization_id AND pipelines.pub_id = :pub_id
+ORDER BY udfs.name;
@@ -23,14 +23,14 @@ use arroyo_rpc::api_types::connections::{
     SchemaDefinition,
 };
 use arroyo_rpc::api_types::{ConnectionTableCollection, PaginationQueryParams};
-use arroyo_rpc::formats::{AvroFormat, Format, JsonFormat};
+use arroyo_rpc::formats::{AvroFormat, Format, JsonFormat, ProtobufFormat};
 use arroyo_rpc::public_ids::{generate_id, IdTypes};
 use arroyo_rpc::schema_resolver::{
     ConfluentSchemaRegistry, ConfluentSchemaSubjectResponse, ConfluentSchemaType,
 };
-use arroyo_sql::avro;
 use arroyo_sql::json_schema::convert_json_schema;
 use arroyo_sql::types::{StructField, TypeDef};
+use arroyo_sql::{avro, protobuf};
 
 use crate::rest::AppState;
 use crate::rest_utils::{
@@ -463,11 +463,89 @@ pub(crate) async fn expand_schema(
             )
             .await
         }
+        Format::Protobuf(_) => {
+            expand_protobuf_schema(
+                name,
+                connector,
+                connection_type,
+                schema,
+                profile_config,
+                table_config,
+            )
+            .await
+        }
         Format::Parquet(_) => Ok(schema),
         Format::RawString(_) => Ok(schema),
+        Format::Csv(_) => Ok(schema),
     }
 }
 
+async fn expand_protobuf_schema(
+    name: &str,
+    connector: &str,
+    connection_type: ConnectionType,
+    mut schema: ConnectionSchema,
+    profile_config: &Value,
+    table_config: &Value,
+) -> Result<ConnectionSchema, ErrorResp> {
+    if let Some(Format::Protobuf(ProtobufFormat {
+        confluent_schema_registry: true,
+        ..
+    })) = &schema.format
+    {
+        let schema_response = get_schema(connector, table_config, profile_config).await?;
+        match connection_type {
+            ConnectionType::Source => {
+                let schema_response = schema_response.ok_or_else(|| bad_request(
+                    format!("No schema was found; ensure that the topic exists and has a value schema configured in the schema registry")))?;
+
+                if schema_response.schema_type != ConfluentSchemaType::Protobuf {
+                    return Err(bad_request(format!(
+                        "Format configured is protobuf, but confluent schema repository returned a {:?} schema",
+                        schema_response.schema_type
+                    )));
+                }
+
+                schema.definition = Some(SchemaDefinition::ProtobufSchema(schema_response.schema));
+            }
+            ConnectionType::Sink | ConnectionType::Lookup => {
+                // don't fetch schemas for sinks or lookup tables for now
+            }
+        }
+    }
+
+    let Some(SchemaDefinition::ProtobufSchema(definition)) = schema.definition.as_ref() else {
+        return match connection_type {
+            ConnectionType::Source | ConnectionType::Lookup => Err(bad_request(
+                "protobuf format requires a protobuf schema be set for sources and lookup tables",
+            )),
+            ConnectionType::Sink => {
+                schema.inferred = Some(true);
+                Ok(schema)
+            }
+        };
+    };
+
+    let compiled = arroyo_formats::proto::compile_schema(definition)
+        .map_err(|e| bad_request(format!("Protobuf schema is invalid: {}", e)))?;
+
+    let Some(Format::Protobuf(format)) = &mut schema.format else {
+        unreachable!("format must be protobuf");
+    };
+    format.add_compiled_schema(compiled);
+
+    let fields: Result<_, String> =
+        protobuf::convert_protobuf_schema(&name, &definition, format.message_name.as_deref())
+            .map_err(|e| bad_request(format!("Invalid protobuf schema: {}", e)))?
+            .into_iter()
+            .map(|f| f.try_into())
+            .collect();
+
+    schema.fields = fields.map_err(|e| bad_request(format!("Failed to convert schema: {}", e)))?;
+
+    Ok(schema)
+}
+
 async fn expand_avro_schema(
     name: &str,
     connector: &str,
@@ -496,16 +574,16 @@ async fn expand_avro_schema(
 
                 schema.definition = Some(SchemaDefinition::AvroSchema(schema_response.schema));
             }
-            ConnectionType::Sink => {
-                // don't fetch schemas for sinks for now
+            ConnectionType::Sink | ConnectionType::Lookup => {
+                // don't fetch schemas for sinks or lookup tables for now
             }
         }
     }
 
     let Some(SchemaDefinition::AvroSchema(definition)) = schema.definition.as_ref() else {
         return match connection_type {
-            ConnectionType::Source => Err(bad_request(
-                "avro format requires an avro schema be set for sources",
+            ConnectionType::Source | ConnectionType::Lookup => Err(bad_request(
+                "avro format requires an avro schema be set for sources and lookup tables",
             )),
             ConnectionType::Sink => {
                 schema.inferred = Some(true);
@@ -514,14 +592,16 @@ async fn expand_avro_schema(
         };
     };
 
+    let mut debezium = false;
     if let Some(Format::Avro(format)) = &mut schema.format {
         format.add_reader_schema(
             apache_avro::Schema::parse_str(&definition)
                 .map_err(|e| bad_request(format!("Avro schema is invalid: {:?}", e)))?,
         );
+        debezium = format.debezium;
     }
 
-    let fields: Result<_, String> = avro::convert_avro_schema(&name, &definition)
+    let fields: Result<_, String> = avro::convert_avro_schema(&name, &definition, debezium)
         .map_err(|e| bad_request(format!("Invalid avro schema: {}", e)))?
         .into_iter()
         .map(|f| f.try_into())
@@ -567,6 +647,9 @@ async fn expand_json_schema(
                 // don't fetch schemas for sinks for now until we're better able to conform our output to the schema
                 schema.inferred = Some(true);
             }
+            ConnectionType::Lookup => {
+                // schemas for lookup tables must be provided directly
+            }
         }
     }
 
@@ -627,6 +710,12 @@ async fn get_schema(
         ));
     };
 
+    // we don't know the record name before fetching the schema, so this only works for the
+    // topic-based subject strategy
+    let subject = table
+        .value_subject(None)
+        .map_err(|e| bad_request(e.to_string()))?;
+
     let resolver =
         ConfluentSchemaRegistry::new(&endpoint, &table.topic, api_key.clone(), api_secret.clone())
             .map_err(|e| {
@@ -634,7 +723,8 @@ async fn get_schema(
                     "failed to fetch schemas from schema repository: {}",
                     e
                 ))
-            })?;
+            })?
+            .with_subject(subject);
 
     resolver.get_schema_for_version(None).await.map_err(|e| {
         bad_request(format!(
@@ -673,6 +763,18 @@ pub(crate) async fn test_schema(
                 Ok(())
             }
         }
+        SchemaDefinition::ProtobufSchema(schema) => {
+            let message_name = match &req.format {
+                Some(Format::Protobuf(proto)) => proto.message_name.as_deref(),
+                _ => None,
+            };
+
+            if let Err(e) = protobuf::convert_protobuf_schema("test", &schema, message_name) {
+                Err(bad_request(e.to_string()))
+            } else {
+                Ok(())
+            }
+        }
         _ => {
             // TODO: add testing for other schema types
             Ok(())
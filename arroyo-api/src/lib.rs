@@ -22,12 +22,16 @@ use crate::metrics::__path_get_operator_metric_groups;
 use crate::pipelines::__path_get_pipelines;
 use crate::pipelines::__path_post_pipeline;
 use crate::pipelines::{
-    __path_delete_pipeline, __path_get_pipeline, __path_get_pipeline_jobs, __path_patch_pipeline,
-    __path_restart_pipeline, __path_validate_query,
+    __path_delete_pipeline, __path_get_pipeline, __path_get_pipeline_jobs,
+    __path_get_pipeline_udf_versions, __path_patch_pipeline, __path_restart_pipeline,
+    __path_upgrade_pipeline_udfs, __path_validate_query,
 };
 use crate::rest::__path_ping;
 use crate::rest_utils::{bad_request, log_and_map, ErrorResp};
-use crate::udfs::{__path_create_udf, __path_delete_udf, __path_get_udfs, __path_validate_udf};
+use crate::udfs::{
+    __path_create_udf, __path_delete_udf, __path_get_udf_diff, __path_get_udf_versions,
+    __path_get_udfs, __path_patch_udf, __path_validate_udf,
+};
 use arroyo_rpc::api_types::{checkpoints::*, connections::*, metrics::*, pipelines::*, udfs::*, *};
 use arroyo_rpc::formats::*;
 
@@ -136,6 +140,8 @@ pub(crate) fn to_micros(dt: OffsetDateTime) -> u64 {
         post_pipeline,
         patch_pipeline,
         restart_pipeline,
+        get_pipeline_udf_versions,
+        upgrade_pipeline_udfs,
         get_pipeline,
         delete_pipeline,
         get_pipelines,
@@ -159,7 +165,10 @@ pub(crate) fn to_micros(dt: OffsetDateTime) -> u64 {
         get_checkpoint_details,
         create_udf,
         get_udfs,
-        delete_udf
+        patch_udf,
+        delete_udf,
+        get_udf_versions,
+        get_udf_diff
     ),
     components(schemas(
         PipelinePost,
@@ -200,17 +209,26 @@ pub(crate) fn to_micros(dt: OffsetDateTime) -> u64 {
         SourceFieldType,
         FieldType,
         StructType,
+        DecimalType,
+        ListType,
         PrimitiveType,
         SchemaDefinition,
         TestSourceMessage,
         JsonFormat,
+        JsonFieldPath,
         AvroFormat,
         ParquetFormat,
         RawStringFormat,
+        ProtobufFormat,
+        CsvFormat,
+        CsvHeader,
         TimestampFormat,
         Framing,
         FramingMethod,
         NewlineDelimitedFraming,
+        LengthPrefixedFraming,
+        DelimiterFraming,
+        Endianness,
         PaginationQueryParams,
         CheckpointEventSpan,
         CheckpointSpanType,
@@ -225,7 +243,17 @@ pub(crate) fn to_micros(dt: OffsetDateTime) -> u64 {
         UdfPost,
         GlobalUdf,
         GlobalUdfCollection,
+        UdfPatch,
+        UdfVersion,
+        UdfWasmModule,
+        UdfVersionCollection,
+        UdfDiff,
+        UdfDiffLine,
+        UdfDiffChange,
+        PipelineUdfVersion,
+        PipelineUdfVersionCollection,
         BadData,
+        DeadLetterSink,
     )),
     tags(
         (name = "ping", description = "Ping endpoint"),
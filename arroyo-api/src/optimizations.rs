@@ -476,6 +476,7 @@ mod tests {
                 },
                 agg: None,
                 flatten: false,
+                allowed_lateness: Duration::ZERO,
             },
             parallelism: 5,
         });
@@ -566,6 +567,7 @@ mod tests {
                 },
                 agg: Some(WindowAgg::Count),
                 flatten: false,
+                allowed_lateness: Duration::ZERO,
             },
             graph.node_weight(window).unwrap().operator
         );
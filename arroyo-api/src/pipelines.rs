@@ -9,7 +9,7 @@ use deadpool_postgres::{Object, Transaction};
 use http::StatusCode;
 
 use petgraph::Direction;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use std::time::Duration;
 
@@ -19,16 +19,20 @@ use arroyo_rpc::api_types::pipelines::{
     Job, Pipeline, PipelineEdge, PipelineGraph, PipelineNode, PipelinePatch, PipelinePost,
     PipelineRestart, QueryValidationResult, StopType, ValidateQueryPost,
 };
-use arroyo_rpc::api_types::udfs::{GlobalUdf, Udf};
-use arroyo_rpc::api_types::{JobCollection, PaginationQueryParams, PipelineCollection};
+use arroyo_rpc::api_types::udfs::{GlobalUdf, PipelineUdfVersion, Udf};
+use arroyo_rpc::api_types::{
+    JobCollection, PaginationQueryParams, PipelineCollection, PipelineUdfVersionCollection,
+};
 use arroyo_rpc::grpc::api as api_proto;
 use arroyo_rpc::grpc::api::{
     create_pipeline_req, CreateJobReq, CreatePipelineReq, CreateSqlJob, PipelineProgram,
 };
 
 use arroyo_connectors::kafka::{KafkaConfig, KafkaTable, SchemaRegistry};
-use arroyo_formats::avro::arrow_to_avro_schema;
+use arroyo_formats::avro::{arrow_to_avro_schema, schema_diff};
+use arroyo_formats::debezium_fields;
 use arroyo_formats::json::arrow_to_json_schema;
+use arroyo_formats::proto::arrow_to_protobuf_schema;
 use arroyo_rpc::formats::Format;
 use arroyo_rpc::public_ids::{generate_id, IdTypes};
 use arroyo_rpc::schema_resolver::{ConfluentSchemaRegistry, ConfluentSchemaType};
@@ -44,7 +48,9 @@ use tracing::warn;
 
 use crate::jobs::get_action;
 use crate::queries::api_queries;
-use crate::queries::api_queries::{DbPipeline, DbPipelineJob, GetPipelinesParams};
+use crate::queries::api_queries::{
+    DbPipeline, DbPipelineJob, DbPipelineUdfVersion, GetPipelinesParams,
+};
 use crate::rest::AppState;
 use crate::rest_utils::{
     authenticate, bad_request, client, log_and_map, not_found, paginate_results, required_field,
@@ -57,13 +63,15 @@ use create_pipeline_req::Config::Sql;
 
 const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
 
+/// Compiles the query against the current versions of the organization's global UDFs, returning
+/// the program along with the global UDFs that it uses
 async fn compile_sql<'e, E>(
     query: String,
     local_udfs: &Vec<Udf>,
     parallelism: usize,
     auth_data: &AuthData,
     tx: &E,
-) -> anyhow::Result<CompiledSql>
+) -> anyhow::Result<(CompiledSql, Vec<GlobalUdf>)>
 where
     E: GenericClient,
 {
@@ -73,10 +81,7 @@ where
         .bind(tx, &auth_data.organization_id)
         .all()
         .await
-        .map_err(|e| anyhow!("Error global global UDFs: {}", e))?
-        .into_iter()
-        .map(|u| u.into())
-        .collect::<Vec<GlobalUdf>>();
+        .map_err(|e| anyhow!("Error global global UDFs: {}", e))?;
 
     // error if there are duplicate local or duplicate global UDF names,
     // but allow  global UDFs to override local ones
@@ -89,8 +94,13 @@ where
         bail!("Local UDFs have duplicate function names");
     }
 
-    for udf in global_udfs {
-        let _ = schema_provider.add_rust_udf(&udf.definition).map_err(|e| {
+    for udf in &global_udfs {
+        let result = match &udf.wasm {
+            Some(wasm) => schema_provider.add_wasm_udf(&udf.definition, wasm),
+            None => schema_provider.add_rust_udf(&udf.definition),
+        };
+
+        let _ = result.map_err(|e| {
             warn!(
                 "Could not process global UDF {}: {:?}",
                 udf.name,
@@ -99,10 +109,13 @@ where
         });
     }
 
+    let mut local_udf_names = HashSet::new();
     for udf in local_udfs.iter() {
-        schema_provider
-            .add_rust_udf(&udf.definition)
-            .map_err(|e| anyhow!(format!("Could not process local UDF: {:?}", e.root_cause())))?;
+        local_udf_names.insert(
+            schema_provider.add_rust_udf(&udf.definition).map_err(|e| {
+                anyhow!(format!("Could not process local UDF: {:?}", e.root_cause()))
+            })?,
+        );
     }
 
     let tables = connection_tables::get_all_connection_tables(auth_data, tx)
@@ -139,7 +152,7 @@ where
         schema_provider.add_connection_profile(profile);
     }
 
-    arroyo_sql::parse_and_get_program(
+    let compiled = arroyo_sql::parse_and_get_program(
         &query,
         schema_provider,
         SqlConfig {
@@ -151,7 +164,18 @@ where
     .map_err(|err| {
         warn!("{:?}", err);
         anyhow!(format!("{}", err.root_cause()))
-    })
+    })?;
+
+    // local UDFs take precedence over global UDFs with the same name
+    let used_global_udfs = global_udfs
+        .into_iter()
+        .filter(|udf| {
+            !local_udf_names.contains(&udf.name) && compiled.used_udfs.contains(&udf.name)
+        })
+        .map(|udf| udf.into())
+        .collect();
+
+    Ok((compiled, used_global_udfs))
 }
 
 fn set_parallelism(program: &mut Program, parallelism: usize) {
@@ -183,21 +207,42 @@ async fn try_register_confluent_schema(
         return Ok(());
     };
 
-    let schema_registry =
-        ConfluentSchemaRegistry::new(&endpoint, &table.topic, api_key, api_secret)?;
+    let registry = |record_name: &str| -> anyhow::Result<ConfluentSchemaRegistry> {
+        Ok(ConfluentSchemaRegistry::new(
+            &endpoint,
+            &table.topic,
+            api_key.clone(),
+            api_secret.clone(),
+        )?
+        .with_subject(table.value_subject(Some(record_name))?))
+    };
 
     match config.format.clone() {
         Some(Format::Avro(mut avro)) => {
             if avro.confluent_schema_registry && avro.schema_id.is_none() {
-                let fields: Vec<Field> = schema.fields.iter().map(|f| f.clone().into()).collect();
+                let mut fields: Vec<Field> =
+                    schema.fields.iter().map(|f| f.clone().into()).collect();
+
+                if avro.debezium {
+                    // debezium sinks write change-event envelopes containing the rows
+                    fields = debezium_fields(&fields.into());
+                }
 
-                let schema = arrow_to_avro_schema(&schema.struct_name_ident(), &fields.into());
+                let schema = arrow_to_avro_schema(&schema.struct_name_ident(), &fields.into())?;
 
-                let id = schema_registry
-                    .write_schema(schema.canonical_form(), ConfluentSchemaType::Avro)
-                    .await?;
+                let apache_avro::Schema::Record(record) = &schema else {
+                    unreachable!("avro schemas for sinks are always records");
+                };
 
-                avro.schema_id = Some(id as u32);
+                let id = register_schema(
+                    &registry(&record.name.fullname(None))?,
+                    schema.canonical_form(),
+                    ConfluentSchemaType::Avro,
+                    Some(&schema),
+                )
+                .await?;
+
+                avro.schema_id = Some(id);
                 config.format = Some(Format::Avro(avro))
             }
         }
@@ -205,16 +250,43 @@ async fn try_register_confluent_schema(
             if json.confluent_schema_registry && json.schema_id.is_none() {
                 let fields: Vec<Field> = schema.fields.iter().map(|f| f.clone().into()).collect();
 
-                let schema = arrow_to_json_schema(&fields.into());
+                let json_schema = arrow_to_json_schema(&fields.into());
 
-                let id = schema_registry
-                    .write_schema(schema.to_string(), ConfluentSchemaType::Json)
-                    .await?;
+                let id = register_schema(
+                    &registry(&schema.struct_name_ident())?,
+                    json_schema.to_string(),
+                    ConfluentSchemaType::Json,
+                    None,
+                )
+                .await?;
 
-                json.schema_id = Some(id as u32);
+                json.schema_id = Some(id);
                 config.format = Some(Format::Json(json))
             }
         }
+        Some(Format::Protobuf(mut proto)) => {
+            if proto.confluent_schema_registry && proto.schema_id.is_none() {
+                if proto.compiled_schema.is_some() {
+                    bail!("protobuf sinks using the schema registry must not set a schema; it will be generated from the output of the query");
+                }
+
+                let fields: Vec<Field> = schema.fields.iter().map(|f| f.clone().into()).collect();
+
+                let proto_schema =
+                    arrow_to_protobuf_schema(&schema.struct_name_ident(), &fields.into())?;
+
+                let id = register_schema(
+                    &registry(&schema.struct_name_ident())?,
+                    proto_schema,
+                    ConfluentSchemaType::Protobuf,
+                    None,
+                )
+                .await?;
+
+                proto.schema_id = Some(id);
+                config.format = Some(Format::Protobuf(proto))
+            }
+        }
         _ => {
             // unsupported for schema registry
         }
@@ -225,6 +297,56 @@ async fn try_register_confluent_schema(
     Ok(())
 }
 
+/// Registers the schema with the registry, first checking that it's compatible with the latest
+/// registered version so that incompatible pipelines fail before they start
+async fn register_schema(
+    registry: &ConfluentSchemaRegistry,
+    schema: String,
+    schema_type: ConfluentSchemaType,
+    avro_schema: Option<&apache_avro::Schema>,
+) -> anyhow::Result<u32> {
+    let compatibility = registry
+        .check_compatibility(schema.clone(), schema_type.clone())
+        .await?;
+
+    if !compatibility.is_compatible {
+        let mut message = format!(
+            "the schema generated for the sink is not compatible with the latest schema \
+            registered for subject '{}'",
+            registry.subject()
+        );
+
+        // for avro we can show which fields have changed
+        let existing = match avro_schema {
+            Some(_) => registry.get_schema_for_version(None).await.ok().flatten(),
+            None => None,
+        };
+
+        if let (Some(new), Some(existing)) = (avro_schema, existing) {
+            if let Ok(existing) = apache_avro::Schema::parse_str(&existing.schema) {
+                let diff = schema_diff(&existing, new);
+                if !diff.is_empty() {
+                    message.push_str("\n\nChanges from the registered schema:\n");
+                    for line in diff {
+                        message.push_str(&format!("  {}\n", line));
+                    }
+                }
+            }
+        }
+
+        if !compatibility.messages.is_empty() {
+            message.push_str("\nThe schema registry reported:\n");
+            for m in compatibility.messages {
+                message.push_str(&format!("  {}\n", m));
+            }
+        }
+
+        bail!("{}", message.trim_end());
+    }
+
+    Ok(registry.write_schema(schema, schema_type).await? as u32)
+}
+
 async fn register_schemas(compiled_sql: &mut CompiledSql) -> anyhow::Result<()> {
     for node in compiled_sql.program.graph.node_indices() {
         let Some(input) = compiled_sql
@@ -260,6 +382,7 @@ pub(crate) async fn create_pipeline<'a>(
     let mut compiled;
     let text;
     let udfs: Option<Vec<Udf>>;
+    let global_udfs: Vec<GlobalUdf>;
     let is_preview;
 
     match req.config.clone().ok_or_else(|| required_field("config"))? {
@@ -277,9 +400,11 @@ pub(crate) async fn create_pipeline<'a>(
                     .map_err(log_and_map)?,
                 connection_ids: vec![],
                 schemas: HashMap::new(),
+                used_udfs: HashSet::new(),
             };
             text = None;
             udfs = None;
+            global_udfs = vec![];
             is_preview = false;
         }
         Sql(sql) => {
@@ -294,7 +419,7 @@ pub(crate) async fn create_pipeline<'a>(
             let api_udfs = sql.udfs.into_iter().map(|t| t.into()).collect::<Vec<Udf>>();
 
             pipeline_type = PipelineType::sql;
-            compiled = compile_sql(
+            (compiled, global_udfs) = compile_sql(
                 sql.query.clone(),
                 &api_udfs,
                 sql.parallelism as usize,
@@ -309,50 +434,8 @@ pub(crate) async fn create_pipeline<'a>(
         }
     };
 
-    optimizations::optimize(&mut compiled.program.graph);
-
-    if compiled.program.graph.node_count() > auth.org_metadata.max_operators as usize {
-        return Err(bad_request(
-            format!("This pipeline is too large to create under your plan, which only allows pipelines up to {} nodes;
-                contact support@arroyo.systems for an increase", auth.org_metadata.max_operators)));
-    }
-
-    let errors = compiled.program.validate_graph();
-    if !errors.is_empty() {
-        let errs: Vec<String> = errors.iter().map(|s| format!("  * {}\n", s)).collect();
-
-        return Err(bad_request(format!(
-            "Program validation failed:\n{}",
-            errs.join("")
-        )));
-    }
-
-    set_parallelism(&mut compiled.program, 1);
-
-    if is_preview {
-        for node in compiled.program.graph.node_weights_mut() {
-            // replace all sink connectors with websink for preview
-            if let Operator::ConnectorSink { .. } = node.operator {
-                node.operator = Operator::ConnectorSink(ConnectorOp::web_sink());
-            }
-        }
-    }
-
-    register_schemas(&mut compiled)
-        .await
-        .map_err(|e| ErrorResp {
-            status_code: StatusCode::BAD_REQUEST,
-            message: format!(
-                "Failed to register schemas with the schema registry. Make sure \
-            that the schema_registry is configured correctly and running.\nDetails: {}",
-                error_chain(e)
-            ),
-        })?;
-
-    let proto_program: PipelineProgram =
-        compiled.program.clone().try_into().map_err(log_and_map)?;
-
-    let program_bytes = proto_program.encode_to_vec();
+    plan_program(&mut compiled, &auth, is_preview)?;
+    let program_bytes = prepare_program(&mut compiled).await?;
 
     if req.name.is_empty() {
         return Err(required_field("name"));
@@ -388,9 +471,82 @@ pub(crate) async fn create_pipeline<'a>(
         }
     }
 
+    pin_udf_versions(pipeline_id, &global_udfs, tx).await?;
+
     Ok((pipeline_id, compiled.program))
 }
 
+/// Records the versions of the global UDFs that the pipeline was compiled with
+async fn pin_udf_versions(
+    pipeline_id: i64,
+    global_udfs: &[GlobalUdf],
+    tx: &impl GenericClient,
+) -> Result<(), ErrorResp> {
+    for udf in global_udfs {
+        api_queries::add_pipeline_udf_version()
+            .bind(tx, &pipeline_id, &udf.id, &(udf.version as i32))
+            .await
+            .map_err(log_and_map)?;
+    }
+
+    Ok(())
+}
+
+/// Optimizes and validates a compiled program, without any external side effects
+fn plan_program(
+    compiled: &mut CompiledSql,
+    auth: &AuthData,
+    is_preview: bool,
+) -> Result<(), ErrorResp> {
+    optimizations::optimize(&mut compiled.program.graph);
+
+    if compiled.program.graph.node_count() > auth.org_metadata.max_operators as usize {
+        return Err(bad_request(
+            format!("This pipeline is too large to create under your plan, which only allows pipelines up to {} nodes;
+                contact support@arroyo.systems for an increase", auth.org_metadata.max_operators)));
+    }
+
+    let errors = compiled.program.validate_graph();
+    if !errors.is_empty() {
+        let errs: Vec<String> = errors.iter().map(|s| format!("  * {}\n", s)).collect();
+
+        return Err(bad_request(format!(
+            "Program validation failed:\n{}",
+            errs.join("")
+        )));
+    }
+
+    set_parallelism(&mut compiled.program, 1);
+
+    if is_preview {
+        for node in compiled.program.graph.node_weights_mut() {
+            // replace all sink connectors with websink for preview
+            if let Operator::ConnectorSink { .. } = node.operator {
+                node.operator = Operator::ConnectorSink(ConnectorOp::web_sink());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Registers the schemas of a planned program and encodes it for storage
+async fn prepare_program(compiled: &mut CompiledSql) -> Result<Vec<u8>, ErrorResp> {
+    register_schemas(compiled).await.map_err(|e| ErrorResp {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!(
+            "Failed to register schemas with the schema registry. Make sure \
+            that the schema_registry is configured correctly and running.\nDetails: {}",
+            error_chain(e)
+        ),
+    })?;
+
+    let proto_program: PipelineProgram =
+        compiled.program.clone().try_into().map_err(log_and_map)?;
+
+    Ok(proto_program.encode_to_vec())
+}
+
 impl TryInto<Pipeline> for DbPipeline {
     type Error = ErrorResp;
 
@@ -456,6 +612,17 @@ impl Into<Job> for DbPipelineJob {
     }
 }
 
+impl Into<PipelineUdfVersion> for DbPipelineUdfVersion {
+    fn into(self) -> PipelineUdfVersion {
+        PipelineUdfVersion {
+            udf_id: self.udf_id,
+            name: self.name,
+            version: self.version as u32,
+            latest_version: self.latest_version as u32,
+        }
+    }
+}
+
 /// Get a pipeline graph
 #[utoipa::path(
     post,
@@ -478,7 +645,7 @@ pub async fn validate_query(
 
     let pipeline_graph_validation_result =
         match compile_sql(validate_query_post.query, &udfs, 1, &auth_data, &client).await {
-            Ok(CompiledSql { mut program, .. }) => {
+            Ok((CompiledSql { mut program, .. }, _)) => {
                 optimizations::optimize(&mut program.graph);
                 let nodes = program
                     .graph
@@ -755,6 +922,169 @@ pub async fn restart_pipeline(
     Ok(Json(pipeline))
 }
 
+/// List the global UDF versions used by a pipeline
+#[utoipa::path(
+    get,
+    path = "/v1/pipelines/{id}/udf_versions",
+    tag = "pipelines",
+    params(
+        ("id" = String, Path, description = "Pipeline id")
+    ),
+    responses(
+        (status = 200, description = "Got UDF versions collection", body = PipelineUdfVersionCollection),
+    ),
+)]
+pub async fn get_pipeline_udf_versions(
+    State(state): State<AppState>,
+    bearer_auth: BearerAuth,
+    Path(pipeline_pub_id): Path<String>,
+) -> Result<Json<PipelineUdfVersionCollection>, ErrorResp> {
+    let client = client(&state.pool).await?;
+    let auth_data = authenticate(&state.pool, bearer_auth).await?;
+
+    query_pipeline_by_pub_id(&pipeline_pub_id, &client, &auth_data).await?;
+
+    let versions: Vec<DbPipelineUdfVersion> = api_queries::get_pipeline_udf_versions()
+        .bind(&client, &auth_data.organization_id, &pipeline_pub_id)
+        .all()
+        .await
+        .map_err(log_and_map)?;
+
+    Ok(Json(PipelineUdfVersionCollection {
+        data: versions.into_iter().map(|v| v.into()).collect(),
+    }))
+}
+
+/// Upgrade a pipeline to the latest versions of its global UDFs
+///
+/// The pipeline's query is recompiled against the current global UDFs, and the versions it uses
+/// are pinned. The pipeline must be stopped, and runs the upgraded program once it is restarted.
+#[utoipa::path(
+    post,
+    path = "/v1/pipelines/{id}/upgrade_udfs",
+    tag = "pipelines",
+    params(
+        ("id" = String, Path, description = "Pipeline id")
+    ),
+    responses(
+        (status = 200, description = "Upgraded pipeline", body = Pipeline),
+    ),
+)]
+pub async fn upgrade_pipeline_udfs(
+    State(state): State<AppState>,
+    bearer_auth: BearerAuth,
+    Path(pipeline_pub_id): Path<String>,
+) -> Result<Json<Pipeline>, ErrorResp> {
+    let mut client = client(&state.pool).await?;
+    let auth_data = authenticate(&state.pool, bearer_auth).await?;
+
+    let transaction = client.transaction().await.map_err(log_and_map)?;
+    transaction
+        .execute("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE", &[])
+        .await
+        .map_err(log_and_map)?;
+
+    let pipeline = api_queries::get_pipeline()
+        .bind(&transaction, &pipeline_pub_id, &auth_data.organization_id)
+        .opt()
+        .await
+        .map_err(log_and_map)?
+        .ok_or_else(|| not_found("Pipeline"))?;
+
+    if pipeline.r#type != PipelineType::sql {
+        return Err(bad_request(
+            "Only SQL pipelines can be upgraded".to_string(),
+        ));
+    }
+
+    if pipeline.ttl_micros.is_some() {
+        return Err(bad_request(
+            "Preview pipelines cannot be upgraded".to_string(),
+        ));
+    }
+
+    let jobs: Vec<Job> = api_queries::get_pipeline_jobs()
+        .bind(&transaction, &auth_data.organization_id, &pipeline_pub_id)
+        .all()
+        .await
+        .map_err(log_and_map)?
+        .into_iter()
+        .map(|j| j.into())
+        .collect();
+
+    if jobs
+        .iter()
+        .any(|job| job.state != "Stopped" && job.state != "Finished" && job.state != "Failed")
+    {
+        return Err(bad_request("Pipeline's jobs must be in a terminal state (stopped, finished, or failed) before its UDFs can be upgraded"
+                .to_string()
+        ));
+    }
+
+    let local_udfs: Vec<Udf> = serde_json::from_value(pipeline.udfs).map_err(log_and_map)?;
+
+    let (mut compiled, global_udfs) = compile_sql(
+        pipeline.textual_repr,
+        &local_udfs,
+        1,
+        &auth_data,
+        &transaction,
+    )
+    .await
+    .map_err(|e| bad_request(e.to_string()))?;
+
+    plan_program(&mut compiled, &auth_data, false)?;
+
+    // state is restored by operator, so the upgraded program must have the same operators
+    let current_program: Program = PipelineProgram::decode(&pipeline.program[..])
+        .map_err(log_and_map)?
+        .try_into()
+        .map_err(log_and_map)?;
+
+    let operator_ids = |program: &Program| -> HashSet<String> {
+        program
+            .graph
+            .node_weights()
+            .map(|node| node.operator_id.clone())
+            .collect()
+    };
+
+    if operator_ids(&current_program) != operator_ids(&compiled.program) {
+        return Err(bad_request(
+            "The latest UDFs change the pipeline's operators, so it could not be restored from its state; \
+            create a new pipeline instead"
+                .to_string(),
+        ));
+    }
+
+    let program_bytes = prepare_program(&mut compiled).await?;
+
+    let pipeline_id = api_queries::update_pipeline_program()
+        .bind(
+            &transaction,
+            &OffsetDateTime::now_utc(),
+            &auth_data.user_id,
+            &program_bytes,
+            &pipeline_pub_id,
+            &auth_data.organization_id,
+        )
+        .one()
+        .await
+        .map_err(log_and_map)?;
+
+    api_queries::delete_pipeline_udf_versions()
+        .bind(&transaction, &pipeline_id)
+        .await
+        .map_err(log_and_map)?;
+
+    pin_udf_versions(pipeline_id, &global_udfs, &transaction).await?;
+
+    transaction.commit().await.map_err(log_and_map)?;
+
+    let pipeline = query_pipeline_by_pub_id(&pipeline_pub_id, &client, &auth_data).await?;
+    Ok(Json(pipeline))
+}
+
 /// List all pipelines
 #[utoipa::path(
     get,
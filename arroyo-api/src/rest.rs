@@ -31,11 +31,13 @@ use crate::jobs::{
 };
 use crate::metrics::get_operator_metric_groups;
 use crate::pipelines::{
-    delete_pipeline, get_pipeline, get_pipeline_jobs, get_pipelines, patch_pipeline, post_pipeline,
-    restart_pipeline, validate_query,
+    delete_pipeline, get_pipeline, get_pipeline_jobs, get_pipeline_udf_versions, get_pipelines,
+    patch_pipeline, post_pipeline, restart_pipeline, upgrade_pipeline_udfs, validate_query,
 };
 use crate::rest_utils::not_found;
-use crate::udfs::{create_udf, delete_udf, get_udfs, validate_udf};
+use crate::udfs::{
+    create_udf, delete_udf, get_udf_diff, get_udf_versions, get_udfs, patch_udf, validate_udf,
+};
 use crate::ApiDoc;
 use arroyo_types::{telemetry_enabled, API_ENDPOINT_ENV, ASSET_DIR_ENV};
 
@@ -129,7 +131,10 @@ pub fn create_rest_app(pool: Pool, controller_addr: &str) -> Router {
         .route("/udfs", post(create_udf))
         .route("/udfs", get(get_udfs))
         .route("/udfs/validate", post(validate_udf))
+        .route("/udfs/:id", patch(patch_udf))
         .route("/udfs/:id", delete(delete_udf))
+        .route("/udfs/:id/versions", get(get_udf_versions))
+        .route("/udfs/:id/diff", get(get_udf_diff))
         .route("/pipelines", post(post_pipeline))
         .route("/pipelines", get(get_pipelines))
         .route("/jobs", get(get_jobs))
@@ -137,6 +142,11 @@ pub fn create_rest_app(pool: Pool, controller_addr: &str) -> Router {
         .route("/pipelines/:id", patch(patch_pipeline))
         .route("/pipelines/:id", get(get_pipeline))
         .route("/pipelines/:id/restart", post(restart_pipeline))
+        .route(
+            "/pipelines/:id/udf_versions",
+            get(get_pipeline_udf_versions),
+        )
+        .route("/pipelines/:id/upgrade_udfs", post(upgrade_pipeline_udfs))
         .route("/pipelines/:id", delete(delete_pipeline))
         .nest("/pipelines/:id/jobs", jobs_routes)
         .fallback(api_fallback);
@@ -1,22 +1,31 @@
 use crate::queries::api_queries;
 use crate::queries::api_queries::{
-    CreateUdfParams, DbUdf, DeleteUdfParams, GetUdfByNameParams, GetUdfParams,
+    CreateUdfParams, CreateUdfVersionParams, DbUdf, DbUdfVersion, DeleteUdfParams,
+    GetUdfByNameParams, GetUdfParams, GetUdfVersionParams, GetUdfVersionsParams, UpdateUdfParams,
 };
 use crate::rest::AppState;
 use crate::rest_utils::{
     authenticate, bad_request, client, internal_server_error, log_and_map, not_found,
     service_unavailable, ApiError, BearerAuth, ErrorResp,
 };
-use crate::to_micros;
-use arroyo_rpc::api_types::udfs::{GlobalUdf, UdfPost, UdfValidationResult, ValidateUdfPost};
-use arroyo_rpc::api_types::GlobalUdfCollection;
+use crate::{to_micros, AuthData};
+use arroyo_rpc::api_types::udfs::{
+    GlobalUdf, UdfDiff, UdfDiffChange, UdfDiffLine, UdfDiffQueryParams, UdfPatch, UdfPost,
+    UdfValidationResult, UdfVersion, UdfWasmModule, ValidateUdfPost,
+};
+use arroyo_rpc::api_types::{GlobalUdfCollection, UdfVersionCollection};
 use arroyo_rpc::grpc::controller_grpc_client::ControllerGrpcClient;
 use arroyo_rpc::grpc::{CheckUdfsReq, CheckUdfsResp};
 use arroyo_rpc::public_ids::{generate_id, IdTypes};
-use axum::extract::{Path, State};
+use arroyo_sql::ArroyoSchemaProvider;
+use axum::extract::{Path, Query, State};
 use axum::Json;
 use axum_extra::extract::WithRejection;
-use cornucopia_async::Params;
+use base64::engine::general_purpose;
+use base64::Engine;
+use cornucopia_async::{GenericClient, Params};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
 use tracing::error;
 
 impl Into<GlobalUdf> for DbUdf {
@@ -29,10 +38,62 @@ impl Into<GlobalUdf> for DbUdf {
             definition: self.definition,
             updated_at: to_micros(self.updated_at),
             description: self.description,
+            version: self.version as u32,
+            wasm: self.wasm.is_some(),
         }
     }
 }
 
+impl Into<UdfVersion> for DbUdfVersion {
+    fn into(self) -> UdfVersion {
+        UdfVersion {
+            id: self.pub_id,
+            udf_id: self.udf_id,
+            version: self.version as u32,
+            created_by: self.created_by,
+            created_at: to_micros(self.created_at),
+            definition: self.definition,
+            description: self.description,
+            wasm: self.wasm.as_deref().map(wasm_module_summary),
+        }
+    }
+}
+
+/// Describes a WASM module by its size and digest, so that versions can be told apart without
+/// returning the module itself
+fn wasm_module_summary(wasm: &[u8]) -> UdfWasmModule {
+    UdfWasmModule {
+        size_bytes: wasm.len() as u64,
+        sha256: format!("{:x}", Sha256::digest(wasm)),
+    }
+}
+
+/// Records the current definition of the UDF as an immutable version
+async fn create_udf_version(
+    client: &impl GenericClient,
+    auth_data: &AuthData,
+    udf: &DbUdf,
+) -> Result<(), ErrorResp> {
+    api_queries::create_udf_version()
+        .params(
+            client,
+            &CreateUdfVersionParams {
+                pub_id: &generate_id(IdTypes::UdfVersion),
+                udf_id: &udf.pub_id,
+                organization_id: &auth_data.organization_id,
+                version: udf.version,
+                created_by: &auth_data.user_id,
+                definition: &udf.definition,
+                description: udf.description.as_deref(),
+                wasm: udf.wasm.as_deref(),
+            },
+        )
+        .await
+        .map_err(log_and_map)?;
+
+    Ok(())
+}
+
 /// Create a global UDF
 #[utoipa::path(
     post,
@@ -58,8 +119,9 @@ pub async fn create_udf(
         .map_err(log_and_map)?;
 
     // validate udf
+    let wasm = decode_wasm_module(&req.wasm_module)?;
     let check_udfs_resp =
-        validate_udf_with_controller(&state.controller_addr, &req.definition).await?;
+        validate_udf_definition(&state.controller_addr, &req.definition, wasm.as_deref()).await?;
 
     if check_udfs_resp.errors.len() > 0 {
         return Err(bad_request(format!("UDF is invalid.",)));
@@ -102,6 +164,7 @@ pub async fn create_udf(
                 name: &udf_name,
                 definition: &req.definition,
                 description: &req.description.unwrap_or_default(),
+                wasm: wasm.as_deref(),
             },
         )
         .await
@@ -117,12 +180,275 @@ pub async fn create_udf(
         )
         .one()
         .await
+        .map_err(log_and_map)?;
+
+    create_udf_version(&transaction, &auth_data, &created_udf).await?;
+
+    transaction.commit().await.map_err(log_and_map)?;
+
+    Ok(Json(created_udf.into()))
+}
+
+/// Update a global UDF
+///
+/// The new definition is stored as a new version of the UDF; pipelines keep the versions they
+/// were created with until they are upgraded.
+#[utoipa::path(
+    patch,
+    path = "/v1/udfs/{id}",
+    tag = "udfs",
+    params(
+        ("id" = String, Path, description = "UDF id")
+    ),
+    request_body = UdfPatch,
+    responses(
+        (status = 200, description = "Updated UDF", body = GlobalUdf),
+    ),
+)]
+pub async fn patch_udf(
+    State(state): State<AppState>,
+    bearer_auth: BearerAuth,
+    Path(udf_pub_id): Path<String>,
+    WithRejection(Json(req), _): WithRejection<Json<UdfPatch>, ApiError>,
+) -> Result<Json<GlobalUdf>, ErrorResp> {
+    let mut client = client(&state.pool).await?;
+    let auth_data = authenticate(&state.pool, bearer_auth).await?;
+
+    let transaction = client.transaction().await.map_err(log_and_map)?;
+    transaction
+        .execute("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE", &[])
+        .await
+        .map_err(log_and_map)?;
+
+    let get_udf_params = GetUdfParams {
+        organization_id: &auth_data.organization_id,
+        pub_id: &udf_pub_id,
+    };
+
+    let existing = api_queries::get_udf()
+        .params(&transaction, &get_udf_params)
+        .opt()
+        .await
         .map_err(log_and_map)?
-        .into();
+        .ok_or_else(|| not_found("UDF"))?;
+
+    let wasm = decode_wasm_module(&req.wasm_module)?;
+
+    if existing.definition == req.definition
+        && existing.wasm == wasm
+        && (req.description.is_none() || req.description == existing.description)
+    {
+        return Ok(Json(existing.into()));
+    }
+
+    let check_udfs_resp =
+        validate_udf_definition(&state.controller_addr, &req.definition, wasm.as_deref()).await?;
+
+    if !check_udfs_resp.errors.is_empty() {
+        return Err(bad_request("UDF is invalid.".to_string()));
+    }
+
+    let Some(udf_name) = check_udfs_resp.udf_name else {
+        return Err(internal_server_error("UDF name not found"));
+    };
+
+    // pipelines refer to UDFs by name, so a new version must keep it
+    if udf_name != existing.name {
+        return Err(bad_request(format!(
+            "UDF name cannot be changed from {} to {}; create a new UDF instead",
+            existing.name, udf_name
+        )));
+    }
+
+    api_queries::update_udf()
+        .params(
+            &transaction,
+            &UpdateUdfParams {
+                updated_at: OffsetDateTime::now_utc(),
+                definition: &req.definition,
+                description: req.description.as_deref(),
+                wasm: wasm.as_deref(),
+                organization_id: &auth_data.organization_id,
+                pub_id: &udf_pub_id,
+            },
+        )
+        .await
+        .map_err(log_and_map)?;
+
+    let updated_udf = api_queries::get_udf()
+        .params(&transaction, &get_udf_params)
+        .one()
+        .await
+        .map_err(log_and_map)?;
+
+    create_udf_version(&transaction, &auth_data, &updated_udf).await?;
 
     transaction.commit().await.map_err(log_and_map)?;
 
-    Ok(Json(created_udf))
+    Ok(Json(updated_udf.into()))
+}
+
+/// List the versions of a global UDF, newest first
+#[utoipa::path(
+    get,
+    path = "/v1/udfs/{id}/versions",
+    tag = "udfs",
+    params(
+        ("id" = String, Path, description = "UDF id")
+    ),
+    responses(
+        (status = 200, description = "List of UDF versions", body = UdfVersionCollection),
+    ),
+)]
+pub async fn get_udf_versions(
+    State(state): State<AppState>,
+    bearer_auth: BearerAuth,
+    Path(udf_pub_id): Path<String>,
+) -> Result<Json<UdfVersionCollection>, ErrorResp> {
+    let client = client(&state.pool).await?;
+    let auth_data = authenticate(&state.pool, bearer_auth).await?;
+
+    let versions = api_queries::get_udf_versions()
+        .params(
+            &client,
+            &GetUdfVersionsParams {
+                organization_id: &auth_data.organization_id,
+                udf_id: &udf_pub_id,
+            },
+        )
+        .all()
+        .await
+        .map_err(log_and_map)?;
+
+    if versions.is_empty() {
+        return Err(not_found("UDF"));
+    }
+
+    Ok(Json(UdfVersionCollection {
+        data: versions.into_iter().map(|v| v.into()).collect(),
+    }))
+}
+
+/// Diff two versions of a global UDF
+///
+/// Compares the definitions line by line, and the modules of WASM UDFs by their size and digest;
+/// `to` defaults to the latest version.
+#[utoipa::path(
+    get,
+    path = "/v1/udfs/{id}/diff",
+    tag = "udfs",
+    params(
+        ("id" = String, Path, description = "UDF id"),
+        UdfDiffQueryParams
+    ),
+    responses(
+        (status = 200, description = "Diff of the UDF definitions", body = UdfDiff),
+    ),
+)]
+pub async fn get_udf_diff(
+    State(state): State<AppState>,
+    bearer_auth: BearerAuth,
+    Path(udf_pub_id): Path<String>,
+    query_params: Query<UdfDiffQueryParams>,
+) -> Result<Json<UdfDiff>, ErrorResp> {
+    let client = client(&state.pool).await?;
+    let auth_data = authenticate(&state.pool, bearer_auth).await?;
+
+    let udf = api_queries::get_udf()
+        .params(
+            &client,
+            &GetUdfParams {
+                organization_id: &auth_data.organization_id,
+                pub_id: &udf_pub_id,
+            },
+        )
+        .opt()
+        .await
+        .map_err(log_and_map)?
+        .ok_or_else(|| not_found("UDF"))?;
+
+    let from_version = query_params.from;
+    let to_version = query_params.to.unwrap_or(udf.version as u32);
+
+    let mut versions = vec![];
+    for version in [from_version, to_version] {
+        let udf_version = api_queries::get_udf_version()
+            .params(
+                &client,
+                &GetUdfVersionParams {
+                    organization_id: &auth_data.organization_id,
+                    udf_id: &udf_pub_id,
+                    version: version as i32,
+                },
+            )
+            .opt()
+            .await
+            .map_err(log_and_map)?
+            .ok_or_else(|| not_found("UDF version"))?;
+
+        versions.push(udf_version);
+    }
+
+    Ok(Json(UdfDiff {
+        udf_id: udf_pub_id,
+        from_version,
+        to_version,
+        lines: diff_definitions(&versions[0].definition, &versions[1].definition),
+        from_wasm: versions[0].wasm.as_deref().map(wasm_module_summary),
+        to_wasm: versions[1].wasm.as_deref().map(wasm_module_summary),
+    }))
+}
+
+/// Computes a line-by-line diff between two UDF definitions from the longest common subsequence
+/// of their lines
+fn diff_definitions(from: &str, to: &str) -> Vec<UdfDiffLine> {
+    let from: Vec<&str> = from.lines().collect();
+    let to: Vec<&str> = to.lines().collect();
+
+    // lcs[i][j] is the length of the longest common subsequence of from[i..] and to[j..]
+    let mut lcs = vec![vec![0usize; to.len() + 1]; from.len() + 1];
+    for i in (0..from.len()).rev() {
+        for j in (0..to.len()).rev() {
+            lcs[i][j] = if from[i] == to[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let diff_line = |change, line: &str| UdfDiffLine {
+        change,
+        line: line.to_string(),
+    };
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < from.len() && j < to.len() {
+        if from[i] == to[j] {
+            lines.push(diff_line(UdfDiffChange::Unchanged, from[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(diff_line(UdfDiffChange::Removed, from[i]));
+            i += 1;
+        } else {
+            lines.push(diff_line(UdfDiffChange::Added, to[j]));
+            j += 1;
+        }
+    }
+
+    lines.extend(
+        from[i..]
+            .iter()
+            .map(|line| diff_line(UdfDiffChange::Removed, line)),
+    );
+    lines.extend(
+        to[j..]
+            .iter()
+            .map(|line| diff_line(UdfDiffChange::Added, line)),
+    );
+    lines
 }
 
 /// Get Global UDFs
@@ -221,6 +547,42 @@ async fn validate_udf_with_controller(
     Ok(check_udfs_resp)
 }
 
+fn decode_wasm_module(wasm_module: &Option<String>) -> Result<Option<Vec<u8>>, ErrorResp> {
+    wasm_module
+        .as_ref()
+        .map(|module| {
+            general_purpose::STANDARD
+                .decode(module)
+                .map_err(|e| bad_request(format!("WASM module is not valid base64: {}", e)))
+        })
+        .transpose()
+}
+
+/// Validates a UDF, returning its name on success and its errors otherwise. Rust UDFs are compiled
+/// by the controller, while WASM UDFs are prebuilt, so only their signatures need to be checked.
+async fn validate_udf_definition(
+    controller_addr: &str,
+    udf_definition: &str,
+    wasm: Option<&[u8]>,
+) -> Result<CheckUdfsResp, ErrorResp> {
+    let Some(wasm) = wasm else {
+        return validate_udf_with_controller(controller_addr, udf_definition).await;
+    };
+
+    Ok(
+        match ArroyoSchemaProvider::new().add_wasm_udf(udf_definition, wasm) {
+            Ok(udf_name) => CheckUdfsResp {
+                errors: vec![],
+                udf_name: Some(udf_name),
+            },
+            Err(e) => CheckUdfsResp {
+                errors: vec![e.to_string()],
+                udf_name: None,
+            },
+        },
+    )
+}
+
 /// Validate UDFs
 #[utoipa::path(
     post,
@@ -235,11 +597,56 @@ pub async fn validate_udf(
     State(state): State<AppState>,
     WithRejection(Json(req), _): WithRejection<Json<ValidateUdfPost>, ApiError>,
 ) -> Result<Json<UdfValidationResult>, ErrorResp> {
+    let wasm = decode_wasm_module(&req.wasm_module)?;
     let check_udfs_resp =
-        validate_udf_with_controller(&state.controller_addr, &req.definition).await?;
+        validate_udf_definition(&state.controller_addr, &req.definition, wasm.as_deref()).await?;
 
     Ok(Json(UdfValidationResult {
         udf_name: check_udfs_resp.udf_name,
         errors: check_udfs_resp.errors,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_definitions, wasm_module_summary};
+    use arroyo_rpc::api_types::udfs::{UdfDiffChange, UdfWasmModule};
+
+    #[test]
+    fn test_diff_definitions() {
+        let from = "fn double(x: i64) -> i64 {\n    x * 2\n}";
+        let to = "/// doubles x\nfn double(x: i64) -> i64 {\n    x + x\n}";
+
+        let diff: Vec<String> = diff_definitions(from, to)
+            .into_iter()
+            .map(|line| match line.change {
+                UdfDiffChange::Unchanged => format!("  {}", line.line),
+                UdfDiffChange::Added => format!("+ {}", line.line),
+                UdfDiffChange::Removed => format!("- {}", line.line),
+            })
+            .collect();
+
+        assert_eq!(
+            diff,
+            vec![
+                "+ /// doubles x",
+                "  fn double(x: i64) -> i64 {",
+                "-     x * 2",
+                "+     x + x",
+                "  }",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wasm_module_summary() {
+        assert_eq!(
+            wasm_module_summary(b"abc"),
+            UdfWasmModule {
+                size_bytes: 3,
+                sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+                    .to_string(),
+            }
+        );
+    }
+}
@@ -355,10 +355,11 @@ impl CompilerGrpc for CompileService {
             .await
             .map_err(|e| Status::internal(format!("Writing UDFs failed: {}", e)))?;
 
-        // udfs depend on arroyo-types::UdfContext, so the types crate needs to export it
+        // udfs depend on arroyo-types::{ProcessContext, UdfContext}, so the types crate needs to
+        // export them
         tokio::fs::write(
             self.build_dir.join("types/src/lib.rs"),
-            "pub use arroyo_types::UdfContext;",
+            "pub use arroyo_types::{ProcessContext, UdfContext};",
         )
         .await?;
 
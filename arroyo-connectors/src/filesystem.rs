@@ -6,13 +6,13 @@ use std::convert::Infallible;
 use typify::import_types;
 
 use arroyo_rpc::api_types::connections::{
-    ConnectionProfile, ConnectionSchema, ConnectionType, TestSourceMessage,
+    ConnectionProfile, ConnectionSchema, ConnectionType, PrimitiveType, TestSourceMessage,
 };
-use arroyo_rpc::formats::Format;
+use arroyo_rpc::formats::{CsvHeader, Format};
 use arroyo_rpc::OperatorConfig;
 use serde::{Deserialize, Serialize};
 
-use crate::{pull_opt, pull_option_to_i64, Connection, EmptyConfig};
+use crate::{pull_opt, pull_option_to_i64, Connection, EmptyConfig, MetadataDef};
 
 use super::Connector;
 
@@ -76,6 +76,20 @@ impl Connector for FileSystemConnector {
         }
     }
 
+    fn metadata_defs(&self) -> &'static [MetadataDef] {
+        &[
+            MetadataDef {
+                key: "file_path",
+                data_type: PrimitiveType::String,
+            },
+            // the 1-based index of the record within its file
+            MetadataDef {
+                key: "line_number",
+                data_type: PrimitiveType::Int64,
+            },
+        ]
+    }
+
     fn from_config(
         &self,
         id: Option<i64>,
@@ -127,6 +141,14 @@ impl Connector for FileSystemConnector {
                         "FileSystem<JSON>".to_string(),
                         "connectors::filesystem::JsonFileSystemSink::<#in_k, #in_t>"
                     ),
+                    (Some(FormatSettings::Csv { .. }), true) => (
+                        "LocalFileSystem<CSV>".to_string(),
+                        "connectors::filesystem::LocalCsvFileSystemSink::<#in_k, #in_t>"
+                    ),
+                    (Some(FormatSettings::Csv { .. }), false) => (
+                        "FileSystem<CSV>".to_string(),
+                        "connectors::filesystem::CsvFileSystemSink::<#in_k, #in_t>"
+                    ),
                     (None, _) => bail!("have to have some format settings"),
                 };
                 (description, operator, ConnectionType::Sink)
@@ -172,6 +194,9 @@ impl Connector for FileSystemConnector {
     ) -> anyhow::Result<Connection> {
         match options.remove("type") {
             Some(t) if t == "source" => {
+                if let Some(Format::Protobuf(_)) = schema.and_then(|s| s.format.as_ref()) {
+                    bail!("filesystem source does not support protobuf format");
+                }
                 let (storage_url, storage_options) = get_storage_url_and_options(options)?;
                 let compression_format = options
                     .remove("compression_format")
@@ -283,7 +308,7 @@ pub fn file_system_sink_from_options(
         .format
         .as_ref()
         .ok_or(anyhow!(
-            "filesystem sink requires a format, such as json or parquet"
+            "filesystem sink requires a format, such as json, csv, or parquet"
         ))? {
         Format::Parquet(..) => {
             let compression = opts
@@ -305,6 +330,13 @@ pub fn file_system_sink_from_options(
         Format::Json(..) => Some(FormatSettings::Json {
             json_format: JsonFormat::Json,
         }),
+        Format::Csv(csv) => Some(FormatSettings::Csv {
+            delimiter: csv.delimiter.to_string(),
+            quote: Some(csv.quote.to_string()),
+            escape: csv.escape.map(|c| c.to_string()),
+            include_header: Some(csv.header != CsvHeader::None),
+            null_string: csv.null_string.clone(),
+        }),
         other => bail!("Unsupported format: {:?}", other),
     };
     Ok(FileSystemTable {
@@ -1,8 +1,13 @@
 use anyhow::{anyhow, bail};
 use arroyo_formats::avro::deserialize_slice_avro;
-use arroyo_rpc::api_types::connections::{ConnectionProfile, ConnectionSchema, TestSourceMessage};
+use arroyo_formats::proto::deserialize_slice_proto;
+use arroyo_rpc::api_types::connections::{
+    ConnectionProfile, ConnectionSchema, PrimitiveType, TestSourceMessage,
+};
 use arroyo_rpc::formats::{Format, JsonFormat};
-use arroyo_rpc::schema_resolver::{ConfluentSchemaRegistryClient, FailingSchemaResolver};
+use arroyo_rpc::schema_resolver::{
+    ConfluentSchemaRegistryClient, FailingSchemaResolver, SchemaResolver,
+};
 use arroyo_rpc::{schema_resolver, var_str::VarStr, OperatorConfig};
 use axum::response::sse::Event;
 use futures::TryFutureExt;
@@ -23,7 +28,7 @@ use tonic::Status;
 use tracing::{error, info, warn};
 use typify::import_types;
 
-use crate::{pull_opt, send, Connection, ConnectionType};
+use crate::{pull_opt, send, Connection, ConnectionType, MetadataDef};
 
 use super::Connector;
 
@@ -107,6 +112,18 @@ impl KafkaConnector {
                         Some("exactly_once") => SinkCommitMode::ExactlyOnce,
                         Some(other) => bail!("invalid value for commit_mode '{}'", other),
                     },
+                    key_field: options.remove("sink.key_field"),
+                    key_format: match options
+                        .remove("sink.key_format")
+                        .as_ref()
+                        .map(|f| f.as_str())
+                    {
+                        Some("raw_string") => Some(KeyFormat::RawString),
+                        Some("json") => Some(KeyFormat::Json),
+                        None => None,
+                        Some(other) => bail!("invalid value for sink.key_format '{}'", other),
+                    },
+                    headers_field: options.remove("sink.headers_field"),
                 }
             }
             _ => {
@@ -114,14 +131,55 @@ impl KafkaConnector {
             }
         };
 
+        let subject_strategy = match options
+            .remove("schema_registry.subject_strategy")
+            .as_ref()
+            .map(|f| f.as_str())
+        {
+            Some("topic_name") => Some(SubjectNameStrategy::TopicName),
+            Some("record_name") => Some(SubjectNameStrategy::RecordName),
+            Some("topic_record_name") => Some(SubjectNameStrategy::TopicRecordName),
+            None => None,
+            Some(other) => bail!(
+                "invalid value for schema_registry.subject_strategy '{}'",
+                other
+            ),
+        };
+
         Ok(KafkaTable {
             topic: pull_opt("topic", options)?,
             type_: table_type,
+            subject_strategy,
             client_configs: HashMap::new(),
         })
     }
 }
 
+impl KafkaTable {
+    /// Returns the schema registry subject for values in this table, according to the
+    /// configured subject name strategy. The record name is required for the record-based
+    /// strategies.
+    pub fn value_subject(&self, record_name: Option<&str>) -> anyhow::Result<String> {
+        let record_name = || {
+            record_name.ok_or_else(|| {
+                anyhow!(
+                    "the subject for topic '{}' cannot be determined without a record name; \
+                    use the topic_name subject strategy or provide a schema",
+                    self.topic
+                )
+            })
+        };
+
+        Ok(match &self.subject_strategy {
+            None | Some(SubjectNameStrategy::TopicName) => format!("{}-value", self.topic),
+            Some(SubjectNameStrategy::RecordName) => record_name()?.to_string(),
+            Some(SubjectNameStrategy::TopicRecordName) => {
+                format!("{}-{}", self.topic, record_name()?)
+            }
+        })
+    }
+}
+
 impl Connector for KafkaConnector {
     type ProfileT = KafkaConfig;
     type TableT = KafkaTable;
@@ -176,6 +234,24 @@ impl Connector for KafkaConnector {
             .map(|s| s.to_owned())
             .ok_or_else(|| anyhow!("No schema defined for Kafka connection"))?;
 
+        if let TableType::Sink {
+            key_field,
+            headers_field,
+            ..
+        } = &table.type_
+        {
+            // if the schema is inferred from the query, the fields are checked when writing
+            for (option, field) in [("key_field", key_field), ("headers_field", headers_field)] {
+                if let Some(field) = field {
+                    if !schema.fields.is_empty()
+                        && !schema.fields.iter().any(|f| &f.field_name == field)
+                    {
+                        bail!("{} '{}' is not a column of the table", option, field);
+                    }
+                }
+            }
+        }
+
         let format = schema
             .format
             .as_ref()
@@ -281,6 +357,32 @@ impl Connector for KafkaConnector {
         }
     }
 
+    fn metadata_defs(&self) -> &'static [MetadataDef] {
+        &[
+            MetadataDef {
+                key: "key",
+                data_type: PrimitiveType::String,
+            },
+            // headers are exposed as a JSON object from header name to value
+            MetadataDef {
+                key: "headers",
+                data_type: PrimitiveType::Json,
+            },
+            MetadataDef {
+                key: "partition",
+                data_type: PrimitiveType::Int32,
+            },
+            MetadataDef {
+                key: "offset",
+                data_type: PrimitiveType::Int64,
+            },
+            MetadataDef {
+                key: "timestamp",
+                data_type: PrimitiveType::UnixMillis,
+            },
+        ]
+    }
+
     fn from_options(
         &self,
         name: &str,
@@ -504,6 +606,49 @@ impl KafkaTester {
                             })?;
                 }
             }
+            Format::Protobuf(proto) => {
+                let schema_resolver: Arc<dyn SchemaResolver + Sync> = if proto
+                    .confluent_schema_registry
+                {
+                    if msg[0] != 0 {
+                        bail!("Message appears to be encoded as normal Protobuf, rather than SR-Protobuf, but the schema registry is enabled. Ensure that the format and schema type are correct.");
+                    }
+
+                    match &self.connection.schema_registry_enum {
+                        Some(SchemaRegistry::ConfluentSchemaRegistry {
+                            endpoint,
+                            api_key,
+                            api_secret,
+                        }) => Arc::new(
+                            schema_resolver::ConfluentSchemaRegistry::new(
+                                endpoint,
+                                &table.topic,
+                                api_key.clone(),
+                                api_secret.clone(),
+                            )
+                            .map_err(|e| anyhow!("Failed to construct schema registry: {:?}", e))?,
+                        ),
+                        _ => {
+                            bail!(
+                                "schema registry is enabled, but no schema registry is configured"
+                            );
+                        }
+                    }
+                } else {
+                    Arc::new(FailingSchemaResolver::new())
+                };
+
+                let registry = Arc::new(Mutex::new(HashMap::new()));
+
+                let mut messages =
+                    deserialize_slice_proto::<Value>(proto, registry, schema_resolver, &msg)
+                        .await
+                        .map_err(|e| anyhow!("Failed to parse message as Protobuf: {}. Ensure that the format and schema type are correct.", e))?;
+
+                if let Some(Err(e)) = messages.next() {
+                    bail!("Failed to parse message as Protobuf: {:?}. Ensure that the format and schema type are correct.", e);
+                }
+            }
             Format::Parquet(_) => {
                 unreachable!()
             }
@@ -511,6 +656,10 @@ impl KafkaTester {
                 String::from_utf8(msg).map_err(|e|
                     anyhow!("Failed to parse message as UTF-8: {:?}. Ensure that the format and schema type are correct.", e))?;
             }
+            Format::Csv(csv) => {
+                arroyo_formats::csv::parse_header(csv, &msg).map_err(|e|
+                    anyhow!("Failed to parse message as CSV: {}. Ensure that the format and schema type are correct.", e))?;
+            }
         };
 
         Ok(())
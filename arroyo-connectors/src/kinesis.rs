@@ -4,12 +4,13 @@ use std::collections::HashMap;
 use std::convert::Infallible;
 use typify::import_types;
 
-use arroyo_rpc::api_types::connections::{ConnectionProfile, TestSourceMessage};
+use arroyo_rpc::api_types::connections::{ConnectionProfile, PrimitiveType, TestSourceMessage};
 use arroyo_rpc::{api_types, OperatorConfig};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     pull_opt, pull_option_to_i64, Connection, ConnectionSchema, ConnectionType, EmptyConfig,
+    MetadataDef,
 };
 
 use super::Connector;
@@ -74,6 +75,23 @@ impl Connector for KinesisConnector {
         };
     }
 
+    fn metadata_defs(&self) -> &'static [MetadataDef] {
+        &[
+            MetadataDef {
+                key: "shard_id",
+                data_type: PrimitiveType::String,
+            },
+            MetadataDef {
+                key: "sequence_number",
+                data_type: PrimitiveType::String,
+            },
+            MetadataDef {
+                key: "arrival_time",
+                data_type: PrimitiveType::UnixMillis,
+            },
+        ]
+    }
+
     fn from_config(
         &self,
         id: Option<i64>,
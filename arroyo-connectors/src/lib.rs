@@ -1,7 +1,7 @@
 use anyhow::{anyhow, bail, Context};
 use arroyo_rpc::api_types::connections::{
-    ConnectionProfile, ConnectionSchema, ConnectionType, FieldType, SourceField, SourceFieldType,
-    TestSourceMessage,
+    ConnectionProfile, ConnectionSchema, ConnectionType, FieldType, PrimitiveType, SourceField,
+    SourceFieldType, TestSourceMessage,
 };
 use arroyo_rpc::primitive_to_sql;
 use arroyo_types::string_to_map;
@@ -79,6 +79,14 @@ pub struct Connection {
     pub description: String,
 }
 
+/// Metadata about the messages read by a source that can be exposed as a column, declared in SQL
+/// as `METADATA FROM '<key>'`
+#[derive(Debug, Clone)]
+pub struct MetadataDef {
+    pub key: &'static str,
+    pub data_type: PrimitiveType,
+}
+
 pub trait Connector: Send {
     type ProfileT: DeserializeOwned + Serialize;
     type TableT: DeserializeOwned + Serialize;
@@ -102,6 +110,10 @@ pub trait Connector: Send {
 
     fn table_type(&self, config: Self::ProfileT, table: Self::TableT) -> ConnectionType;
 
+    fn metadata_defs(&self) -> &'static [MetadataDef] {
+        &[]
+    }
+
     #[allow(unused)]
     fn get_schema(
         &self,
@@ -172,6 +184,8 @@ pub trait ErasedConnector: Send {
         table: &serde_json::Value,
     ) -> Result<ConnectionType, serde_json::Error>;
 
+    fn metadata_defs(&self) -> &'static [MetadataDef];
+
     fn config_description(&self, s: &serde_json::Value) -> Result<String, serde_json::Error>;
 
     fn get_schema(
@@ -252,6 +266,10 @@ impl<C: Connector> ErasedConnector for C {
         Ok(self.table_type(self.parse_config(config)?, self.parse_table(table)?))
     }
 
+    fn metadata_defs(&self) -> &'static [MetadataDef] {
+        self.metadata_defs()
+    }
+
     fn get_schema(
         &self,
         config: &serde_json::Value,
@@ -377,7 +395,7 @@ pub(crate) fn source_field(name: &str, field_type: FieldType) -> SourceField {
         field_type: SourceFieldType {
             sql_name: match field_type.clone() {
                 FieldType::Primitive(p) => Some(primitive_to_sql(p).to_string()),
-                FieldType::Struct(_) => None,
+                FieldType::Struct(_) | FieldType::Decimal(_) | FieldType::List(_) => None,
             },
             r#type: field_type,
         },
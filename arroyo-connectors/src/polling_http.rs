@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::convert::Infallible;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use arroyo_rpc::{var_str::VarStr, OperatorConfig};
 use arroyo_types::string_to_map;
 use axum::response::sse::Event;
@@ -112,8 +112,12 @@ impl Connector for PollingHTTPConnector {
         }
     }
 
-    fn table_type(&self, _: Self::ProfileT, _: Self::TableT) -> ConnectionType {
-        return ConnectionType::Source;
+    fn table_type(&self, _: Self::ProfileT, table: Self::TableT) -> ConnectionType {
+        if table.lookup == Some(true) {
+            ConnectionType::Lookup
+        } else {
+            ConnectionType::Source
+        }
     }
 
     fn test(
@@ -151,6 +155,15 @@ impl Connector for PollingHTTPConnector {
         schema: Option<&ConnectionSchema>,
         _profile: Option<&ConnectionProfile>,
     ) -> anyhow::Result<Connection> {
+        let lookup = match options.remove("type").as_deref() {
+            None | Some("source") => None,
+            Some("lookup") => Some(true),
+            Some(t) => bail!(
+                "'{}' is not a valid type; must be one of `source` or `lookup`",
+                t
+            ),
+        };
+
         let endpoint = pull_opt("endpoint", options)?;
         let headers = options.remove("headers");
         let method: Option<Method> = options
@@ -162,6 +175,7 @@ impl Connector for PollingHTTPConnector {
         let body = options.remove("body");
 
         let interval = pull_option_to_i64("poll_interval_ms", options)?;
+        let lookup_max_requests = pull_option_to_i64("lookup.max_requests", options)?;
         let emit_behavior: Option<EmitBehavior> = options
             .remove("emit_behavior")
             .map(|s| s.try_into())
@@ -179,6 +193,8 @@ impl Connector for PollingHTTPConnector {
                 body,
                 poll_interval_ms: interval,
                 emit_behavior,
+                lookup,
+                lookup_max_requests,
             },
             schema,
         )
@@ -192,7 +208,25 @@ impl Connector for PollingHTTPConnector {
         table: Self::TableT,
         schema: Option<&ConnectionSchema>,
     ) -> anyhow::Result<crate::Connection> {
-        let description = format!("PollingHTTPSource<{}>", table.endpoint);
+        let lookup = table.lookup == Some(true);
+        if lookup && !table.endpoint.contains("{key}") {
+            bail!("the endpoint for an HTTP lookup table must contain `{{key}}`, which is replaced with the join key");
+        }
+
+        if let Some(max_requests) = table.lookup_max_requests {
+            if !lookup {
+                bail!("lookup.max_requests may only be set on lookup tables");
+            }
+            if max_requests < 1 {
+                bail!("lookup.max_requests must be at least 1");
+            }
+        }
+
+        let description = if lookup {
+            format!("HTTPLookup<{}>", table.endpoint)
+        } else {
+            format!("PollingHTTPSource<{}>", table.endpoint)
+        };
 
         if let Some(headers) = &table.headers {
             string_to_map(&headers.sub_env_vars()?).ok_or_else(|| {
@@ -225,9 +259,17 @@ impl Connector for PollingHTTPConnector {
         Ok(Connection {
             id,
             name: name.to_string(),
-            connection_type: ConnectionType::Source,
+            connection_type: if lookup {
+                ConnectionType::Lookup
+            } else {
+                ConnectionType::Source
+            },
             schema,
-            operator: "connectors::polling_http::PollingHttpSourceFunc".to_string(),
+            operator: if lookup {
+                "connectors::polling_http::HttpLookup::<#lookup_t>".to_string()
+            } else {
+                "connectors::polling_http::PollingHttpSourceFunc".to_string()
+            },
             config: serde_json::to_string(&config).unwrap(),
             description,
         })
@@ -14,6 +14,7 @@ use arroyo_rpc::api_types::connections::{
     ConnectionProfile, ConnectionSchema, ConnectionType, FieldType, PrimitiveType,
     TestSourceMessage,
 };
+use arroyo_rpc::formats::Format;
 use arroyo_rpc::OperatorConfig;
 
 use crate::{pull_opt, pull_option_to_u64, Connection, Connector};
@@ -151,7 +152,7 @@ impl Connector for RedisConnector {
             id: "redis".to_string(),
             name: "Redis".to_string(),
             icon: ICON.to_string(),
-            description: "Write results to Redis".to_string(),
+            description: "Write results to Redis or look up rows for joins".to_string(),
             enabled: true,
             source: false,
             sink: true,
@@ -163,8 +164,11 @@ impl Connector for RedisConnector {
         }
     }
 
-    fn table_type(&self, _: Self::ProfileT, _: Self::TableT) -> ConnectionType {
-        return ConnectionType::Source;
+    fn table_type(&self, _: Self::ProfileT, table: Self::TableT) -> ConnectionType {
+        match table.connector_type {
+            TableType::Target(_) => ConnectionType::Sink,
+            TableType::Lookup(_) => ConnectionType::Lookup,
+        }
     }
 
     fn get_schema(
@@ -284,7 +288,7 @@ impl Connector for RedisConnector {
             Ok(column)
         }
 
-        let sink = match typ.as_str() {
+        let connector_type = match typ.as_str() {
             "sink" => TableType::Target(match pull_opt("target", options)?.as_str() {
                 "string" => Target::StringTable {
                     key_prefix: pull_opt("target.key_prefix", options)?,
@@ -335,8 +339,21 @@ impl Connector for RedisConnector {
                     bail!("'{}' is not a valid redis target", s);
                 }
             }),
+            "lookup" => TableType::Lookup(match pull_opt("lookup", options)?.as_str() {
+                "string" => LookupSource::KeyPrefix(pull_opt("lookup.key_prefix", options)?),
+                "hash" => LookupSource::HashKeyPrefix(pull_opt("lookup.key_prefix", options)?),
+                s => {
+                    bail!(
+                        "'{}' is not a valid redis lookup; must be one of 'string' or 'hash'",
+                        s
+                    );
+                }
+            }),
             s => {
-                bail!("'{}' is not a valid type; must be `sink`", s);
+                bail!(
+                    "'{}' is not a valid type; must be one of `sink` or `lookup`",
+                    s
+                );
             }
         };
 
@@ -344,9 +361,7 @@ impl Connector for RedisConnector {
             None,
             name,
             connection_config,
-            RedisTable {
-                connector_type: sink,
-            },
+            RedisTable { connector_type },
             s,
         )
     }
@@ -371,6 +386,26 @@ impl Connector for RedisConnector {
 
         let _ = RedisClient::new(&config)?;
 
+        let (connection_type, operator, description) = match &table.connector_type {
+            TableType::Target(_) => (
+                ConnectionType::Sink,
+                "connectors::redis::sink::RedisSinkFunc::<#in_k, #in_t>",
+                "RedisSink",
+            ),
+            TableType::Lookup(lookup) => {
+                if let LookupSource::HashKeyPrefix(_) = lookup {
+                    if !matches!(format, Format::Json(_)) {
+                        bail!("hash lookups are only supported for tables with format 'json'");
+                    }
+                }
+                (
+                    ConnectionType::Lookup,
+                    "connectors::redis::lookup::RedisLookup::<#lookup_t>",
+                    "RedisLookup",
+                )
+            }
+        };
+
         let config = OperatorConfig {
             connection: serde_json::to_value(config).unwrap(),
             table: serde_json::to_value(table).unwrap(),
@@ -383,11 +418,11 @@ impl Connector for RedisConnector {
         Ok(Connection {
             id,
             name: name.to_string(),
-            connection_type: ConnectionType::Sink,
+            connection_type,
             schema,
-            operator: "connectors::redis::sink::RedisSinkFunc::<#in_k, #in_t>".to_string(),
+            operator: operator.to_string(),
             config: serde_json::to_string(&config).unwrap(),
-            description: "RedisSink".to_string(),
+            description: description.to_string(),
         })
     }
 }
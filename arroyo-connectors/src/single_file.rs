@@ -91,11 +91,11 @@ impl Connector for SingleFileConnector {
             .map(|t| t.to_owned())
             .ok_or_else(|| anyhow!("'format' must be set for Single File Source connection"))?;
         let connection_type = (&table.table_type).into();
-        let operator = match connection_type {
-            ConnectionType::Source => {
+        let operator = match table.table_type {
+            TableType::Source => {
                 "connectors::filesystem::single_file::source::FileSourceFunc".to_string()
             }
-            ConnectionType::Sink => {
+            TableType::Sink => {
                 "connectors::filesystem::single_file::sink::FileSink::<#in_k, #in_t>".to_string()
             }
         };
@@ -11,18 +11,6 @@ import {
 import { ConnectionTable, SourceField } from '../../lib/data_fetching';
 
 function CatalogField({ field, nesting }: { field: SourceField; nesting: number }) {
-  if (field.fieldType!.type.primitive) {
-    return (
-      <Flex>
-        <Box flex="1" textAlign="left">
-          {field.fieldName}
-        </Box>
-        <Box flex="1" textAlign="right">
-          {field.fieldType.sqlName}
-        </Box>
-      </Flex>
-    );
-  }
   if (field.fieldType!.type.struct) {
     return (
       <Box mr={1} mb={2}>
@@ -36,9 +24,19 @@ function CatalogField({ field, nesting }: { field: SourceField; nesting: number
         })}
       </Box>
     );
-  } else {
-    return <Box></Box>;
   }
+
+  // primitives, decimals and lists are shown with their SQL type
+  return (
+    <Flex>
+      <Box flex="1" textAlign="left">
+        {field.fieldName}
+      </Box>
+      <Box flex="1" textAlign="right">
+        {field.fieldType!.sqlName}
+      </Box>
+    </Flex>
+  );
 }
 
 export function Catalog({ tables }: { tables: Array<ConnectionTable> }) {
@@ -91,7 +91,7 @@ impl ProgramCompiler {
 
         quote! {
             use std::time::SystemTime;
-            pub use arroyo_types::UdfContext;
+            pub use arroyo_types::{ProcessContext, UdfContext};
 
             #(#structs )*
         }
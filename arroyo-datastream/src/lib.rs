@@ -274,13 +274,30 @@ pub struct NonWindowAggregator {
     pub bin_type: String,
 }
 
+#[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize, PartialEq)]
+pub struct LookupJoin {
+    // the lookup connector, whose operator contains `#lookup_t` in place of the row type
+    pub connector: ConnectorOp,
+    // V, the type of the rows in the lookup table
+    pub lookup_type: String,
+    // body of fn(&InT) -> Option<String>, with the input bound to `arg`
+    pub key_expression: String,
+    // body of fn(&InT, Option<&V>) -> OutT, with the inputs bound to `left` and `lookup`
+    pub merge_expression: String,
+    pub join_type: JoinType,
+    pub cache_max_rows: u64,
+    pub cache_ttl: Duration,
+    pub batch_size: u64,
+    pub max_concurrency: u64,
+}
+
 #[derive(Copy, Clone, Debug, Encode, Decode, Serialize, Deserialize, PartialEq)]
 pub enum ImpulseSpec {
     Delay(Duration),
     EventsPerSecond(f32),
 }
 
-#[derive(Clone, Encode, Decode, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize, PartialEq)]
 pub struct ConnectorOp {
     // path of the operator that this will compile into (like `crate::sources::kafka::KafkaSource`)
     pub operator: String,
@@ -332,6 +349,9 @@ pub enum Operator {
         typ: WindowType,
         agg: Option<WindowAgg>,
         flatten: bool,
+        // how long windows keep accepting records after the watermark has passed them; windows
+        // that allow lateness emit their results as updates
+        allowed_lateness: Duration,
     },
     Count,
     Aggregate(AggregateBehavior),
@@ -382,6 +402,35 @@ pub enum Operator {
         max_concurrency: u64,
         has_context: bool,
     },
+    LookupJoin(LookupJoin),
+    TemporalJoin {
+        join_type: JoinType,
+        // body of fn(&T1, Option<&T2>) -> OutT, with the inputs bound to `left` and `right`
+        merge_expression: String,
+    },
+    IntervalJoin {
+        // bounds on the event time of the right side minus that of the left side
+        lower_bound_micros: i64,
+        upper_bound_micros: i64,
+        // body of fn(&T1, &T2) -> OutT, with the inputs bound to `left` and `right`
+        merge_expression: String,
+    },
+    // emits the records that are too late for a window operator with the same configuration
+    LateRecordFilter {
+        window: WindowType,
+        allowed_lateness: Duration,
+    },
+    ProcessFunction {
+        name: String,
+        // the type of the state kept for each key
+        state_type: String,
+        // body of fn(&T, ProcessContext<S>) -> (ProcessContext<S>, Vec<OutT>), with the inputs
+        // bound to `arg` and `context`
+        process_expression: String,
+        // body of fn(&K, ProcessContext<S>) -> (ProcessContext<S>, Vec<OutT>), with the inputs
+        // bound to `key` and `context`, if the function handles timers
+        timer_expression: Option<String>,
+    },
 }
 
 #[derive(Clone, Encode, Decode, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -508,6 +557,31 @@ impl Debug for Operator {
                 expression: _,
             } => write!(f, "updating_key<{}>", name),
             Operator::AsyncMapOperator { name, .. } => write!(f, "async_map<{}>", name),
+            Operator::LookupJoin(LookupJoin {
+                connector,
+                join_type,
+                ..
+            }) => write!(
+                f,
+                "LookupJoin<{}, join_type: {:?}>",
+                connector.description, join_type
+            ),
+            Operator::TemporalJoin { join_type, .. } => {
+                write!(f, "TemporalJoin<join_type: {:?}>", join_type)
+            }
+            Operator::IntervalJoin {
+                lower_bound_micros,
+                upper_bound_micros,
+                ..
+            } => write!(
+                f,
+                "IntervalJoin<lower_micros: {}, upper_micros: {}>",
+                lower_bound_micros, upper_bound_micros
+            ),
+            Operator::LateRecordFilter { window, .. } => {
+                write!(f, "LateRecordFilter<{:?}>", window)
+            }
+            Operator::ProcessFunction { name, .. } => write!(f, "process_function<{}>", name),
         }
     }
 }
@@ -756,6 +830,7 @@ impl<K: Key, T: Data> KeyedWindowFun<K, T> for TumblingWindow<K, T> {
             typ: WindowType::Tumbling { width: self.width },
             agg: None,
             flatten: false,
+            allowed_lateness: Duration::ZERO,
         }
     }
 }
@@ -785,6 +860,7 @@ impl<K: Key, T: Data> KeyedWindowFun<K, T> for SlidingWindow<K, T> {
             },
             agg: None,
             flatten: false,
+            allowed_lateness: Duration::ZERO,
         }
     }
 }
@@ -813,6 +889,7 @@ impl<K: Key, T: Data> KeyedWindowFun<K, T> for InstantWindow<K, T> {
             typ: WindowType::Instant,
             agg: None,
             flatten: false,
+            allowed_lateness: Duration::ZERO,
         }
     }
 }
@@ -1138,8 +1215,15 @@ impl Program {
                             .to_string(),
                     );
                 }
-                Operator::Window { typ, .. } => {
+                Operator::Window {
+                    typ,
+                    allowed_lateness,
+                    ..
+                } => {
                     s.insert(format!("{:?} window", typ));
+                    if !allowed_lateness.is_zero() {
+                        s.insert(format!("allowed lateness"));
+                    }
                 }
                 Operator::WindowJoin { window } => {
                     s.insert(format!("{:?} window join", window));
@@ -1162,6 +1246,21 @@ impl Program {
                 Operator::NonWindowAggregator(_) => {
                     s.insert(format!("non-window aggregator"));
                 }
+                Operator::LookupJoin(_) => {
+                    s.insert(format!("lookup join"));
+                }
+                Operator::TemporalJoin { .. } => {
+                    s.insert(format!("temporal join"));
+                }
+                Operator::IntervalJoin { .. } => {
+                    s.insert(format!("interval join"));
+                }
+                Operator::LateRecordFilter { .. } => {
+                    s.insert(format!("late record filter"));
+                }
+                Operator::ProcessFunction { .. } => {
+                    s.insert(format!("process function"));
+                }
                 _ => {}
             }
         }
@@ -1186,6 +1285,23 @@ impl Program {
             )
         }
 
+        for node in self.graph.node_weights() {
+            if let Operator::Window {
+                typ,
+                flatten,
+                allowed_lateness,
+                ..
+            } = &node.operator
+            {
+                if !allowed_lateness.is_zero() && (*flatten || matches!(typ, WindowType::Instant)) {
+                    errors.push(format!(
+                        "Allowed lateness is only supported for tumbling, sliding and session window aggregates, not {:?}",
+                        typ
+                    ));
+                }
+            }
+        }
+
         for stream_node in self.graph.node_indices() {
             if let Err(error) = self.check_incoming_edges_for_node(stream_node) {
                 errors.push(error.to_string());
@@ -1408,66 +1524,101 @@ impl Program {
                         Box::new(WasmOperator::<#in_k, #in_t, #out_k, #out_t>::new(#name).unwrap())
                     }
                 }
-                Operator::Window { typ, agg, flatten } => {
+                Operator::Window { typ, agg, flatten, allowed_lateness } => {
                     let in_k = parse_type(&input.unwrap().weight().key);
                     let in_t = parse_type(&input.unwrap().weight().value);
                     let out_t = parse_type(&output.unwrap().weight().value);
 
-                    let agg = match agg {
-                        None => quote! { WindowOperation::Aggregate(aggregators::vec_aggregator) },
-                        Some(WindowAgg::Count) => quote! { WindowOperation::Aggregate(aggregators::count_aggregator) },
-                        Some(WindowAgg::Min) => quote! { WindowOperation::Aggregate(aggregators::min_aggregator) },
-                        Some(WindowAgg::Max) => quote! { WindowOperation::Aggregate(aggregators::max_aggregator) },
-                        Some(WindowAgg::Sum) => quote! { WindowOperation::Aggregate(aggregators::sum_aggregator) },
+                    let aggregator = match agg {
+                        None => quote! { aggregators::vec_aggregator },
+                        Some(WindowAgg::Count) => quote! { aggregators::count_aggregator },
+                        Some(WindowAgg::Min) => quote! { aggregators::min_aggregator },
+                        Some(WindowAgg::Max) => quote! { aggregators::max_aggregator },
+                        Some(WindowAgg::Sum) => quote! { aggregators::sum_aggregator },
                         Some(WindowAgg::Expression {
                                  expression,
                                  ..
                              }) => {
                             let expr: syn::Expr = parse_str(expression).unwrap();
-                            let operation = if *flatten {
-                                format_ident!("Flatten")
-                            } else {
-                                format_ident!("Aggregate")
-                            };
-
                             quote! {
-                                WindowOperation::#operation(|key: &#in_k, window: arroyo_types::Window, mut arg: Vec<_>| {
+                                |key: &#in_k, window: arroyo_types::Window, mut arg: Vec<_>| {
                                     #expr
-                                })
+                                }
                             }
                         }
                     };
 
-                    match typ {
-                        WindowType::Tumbling { width } => {
-                            let width = duration_to_syn_expr(*width);
-
-                            quote! {
-                                Box::new(KeyedWindowFunc::<#in_k, #in_t, #out_t, TumblingWindowAssigner>::
-                                    tumbling_window(#width, #agg))
+                    if !allowed_lateness.is_zero() {
+                        let out_t = extract_container_type("UpdatingData", &out_t)
+                            .expect("windows that allow lateness must have updating outputs");
+                        let allowed_lateness = duration_to_syn_expr(*allowed_lateness);
+
+                        match typ {
+                            WindowType::Tumbling { width } => {
+                                let width = duration_to_syn_expr(*width);
+                                quote! {
+                                    Box::new(UpdatingWindowFunc::<#in_k, #in_t, #out_t, TumblingWindowAssigner>::
+                                        tumbling_window(#width, #allowed_lateness, #aggregator))
+                                }
+                            }
+                            WindowType::Sliding { width, slide } => {
+                                let width = duration_to_syn_expr(*width);
+                                let slide = duration_to_syn_expr(*slide);
+                                quote! {
+                                    Box::new(UpdatingWindowFunc::<#in_k, #in_t, #out_t, SlidingWindowAssigner>::
+                                        sliding_window(#width, #slide, #allowed_lateness, #aggregator))
+                                }
+                            }
+                            WindowType::Session { gap } => {
+                                let gap = duration_to_syn_expr(*gap);
+                                quote! {
+                                    Box::new(UpdatingSessionWindowFunc::<#in_k, #in_t, #out_t>::new(
+                                        #gap, #allowed_lateness, #aggregator
+                                    ))
+                                }
+                            }
+                            WindowType::Instant => {
+                                unreachable!("allowed lateness is not supported for instant windows")
                             }
                         }
-                        WindowType::Sliding { width, slide } => {
-                            let width = duration_to_syn_expr(*width);
-                            let slide = duration_to_syn_expr(*slide);
+                    } else {
+                        let agg = if *flatten {
+                            quote! { WindowOperation::Flatten(#aggregator) }
+                        } else {
+                            quote! { WindowOperation::Aggregate(#aggregator) }
+                        };
+
+                        match typ {
+                            WindowType::Tumbling { width } => {
+                                let width = duration_to_syn_expr(*width);
+
+                                quote! {
+                                    Box::new(KeyedWindowFunc::<#in_k, #in_t, #out_t, TumblingWindowAssigner>::
+                                        tumbling_window(#width, #agg))
+                                }
+                            }
+                            WindowType::Sliding { width, slide } => {
+                                let width = duration_to_syn_expr(*width);
+                                let slide = duration_to_syn_expr(*slide);
 
-                            quote! {
-                                Box::new(KeyedWindowFunc::<#in_k, #in_t, #out_t, SlidingWindowAssigner>::
-                                    sliding_window(#width, #slide, #agg))
+                                quote! {
+                                    Box::new(KeyedWindowFunc::<#in_k, #in_t, #out_t, SlidingWindowAssigner>::
+                                        sliding_window(#width, #slide, #agg))
+                                }
                             }
-                        }
-                        WindowType::Instant => {
-                            quote! {
-                                Box::new(KeyedWindowFunc::<#in_k, #in_t, #out_t, InstantWindowAssigner>::
-                                    instant_window(#agg))
+                            WindowType::Instant => {
+                                quote! {
+                                    Box::new(KeyedWindowFunc::<#in_k, #in_t, #out_t, InstantWindowAssigner>::
+                                        instant_window(#agg))
+                                }
                             }
-                        }
-                        WindowType::Session { gap } => {
-                            let gap = duration_to_syn_expr(*gap);
-                            quote! {
-                                Box::new(SessionWindowFunc::<#in_k, #in_t, #out_t>::new(
-                                    #agg, #gap
-                                ))
+                            WindowType::Session { gap } => {
+                                let gap = duration_to_syn_expr(*gap);
+                                quote! {
+                                    Box::new(SessionWindowFunc::<#in_k, #in_t, #out_t>::new(
+                                        #agg, #gap
+                                    ))
+                                }
                             }
                         }
                     }
@@ -1847,6 +1998,10 @@ impl Program {
                         JoinType::Left => "left_join",
                         JoinType::Right => "right_join",
                         JoinType::Full => "full_join",
+                        JoinType::LeftSemi => "left_semi_join",
+                        JoinType::RightSemi => "right_semi_join",
+                        JoinType::LeftAnti => "left_anti_join",
+                        JoinType::RightAnti => "right_anti_join",
                     }.to_string();
 
                     let join_fn_tail: String = match (t1_updating, t2_updating) {
@@ -1903,6 +2058,148 @@ impl Program {
                         new(#name.to_string(), #expr))
                     }
                 }
+                Operator::LookupJoin(LookupJoin {
+                    connector,
+                    lookup_type,
+                    key_expression,
+                    merge_expression,
+                    join_type,
+                    cache_max_rows,
+                    cache_ttl,
+                    batch_size,
+                    max_concurrency,
+                }) => {
+                    let in_k = parse_type(&input.unwrap().weight().key);
+                    let in_t = parse_type(&input.unwrap().weight().value);
+                    let out_t = parse_type(&output.unwrap().weight().value);
+                    let lookup_t = parse_type(lookup_type);
+                    let connector_t = parse_type(&connector.operator.replace("#lookup_t", lookup_type));
+                    let config = &connector.config;
+                    let key_expr: syn::Expr = parse_str(key_expression).expect(key_expression);
+                    let merge_expr: syn::Expr = parse_str(merge_expression).expect(merge_expression);
+                    let emit_misses = match join_type {
+                        JoinType::Inner => false,
+                        JoinType::Left => true,
+                        _ => unreachable!("lookup joins must be inner or left joins"),
+                    };
+                    let cache_max_rows = *cache_max_rows as usize;
+                    let cache_ttl = duration_to_syn_expr(*cache_ttl);
+                    let batch_size = *batch_size as usize;
+                    quote! {
+                        Box::new(arroyo_worker::operators::lookup_join::
+                            LookupJoinOperator::<#in_k, #in_t, #lookup_t, #out_t, _>::
+                        new(#connector_t::from_config(#config),
+                            Box::new(|arg: &#in_t| -> Option<String> { #key_expr }),
+                            Box::new(|left: &#in_t, lookup: Option<&#lookup_t>| -> #out_t { #merge_expr }),
+                            #emit_misses,
+                            #cache_max_rows,
+                            #cache_ttl,
+                            #batch_size,
+                            #max_concurrency))
+                    }
+                }
+                Operator::TemporalJoin { join_type, merge_expression } => {
+                    let mut inputs: Vec<_> = self.graph.edges_directed(idx, Direction::Incoming)
+                        .collect();
+                    inputs.sort_by_key(|e| e.weight().typ.clone());
+                    assert_eq!(2, inputs.len(), "TemporalJoin should have 2 inputs, but has {}", inputs.len());
+                    assert_eq!(inputs[0].weight().key, inputs[1].weight().key, "TemporalJoin inputs must have the same key type");
+
+                    let in_k = parse_type(&inputs[0].weight().key);
+                    let in_t1 = parse_type(&inputs[0].weight().value);
+                    let in_t2 = parse_type(&inputs[1].weight().value);
+                    let out_t = parse_type(&output.unwrap().weight().value);
+
+                    let (processor, t2) = match extract_container_type("UpdatingData", &in_t2) {
+                        Some(t) => (quote!(arroyo_worker::operators::join_with_expiration::NoOpProcessor<#t>), t),
+                        None => (quote!(arroyo_worker::operators::join_with_expiration::Coercer<#in_t2>), in_t2.clone()),
+                    };
+
+                    let merge_expr: syn::Expr = parse_str(merge_expression).expect(merge_expression);
+                    let emit_misses = match join_type {
+                        JoinType::Inner => false,
+                        JoinType::Left => true,
+                        _ => unreachable!("temporal joins must be inner or left joins"),
+                    };
+
+                    quote! {
+                        Box::new(arroyo_worker::operators::temporal_join::
+                            TemporalJoin::<#in_k, #in_t1, #in_t2, #processor, #t2, #out_t>::
+                        new(#emit_misses,
+                            Box::new(|left: &#in_t1, right: Option<&#t2>| -> #out_t { #merge_expr })))
+                    }
+                }
+                Operator::IntervalJoin { lower_bound_micros, upper_bound_micros, merge_expression } => {
+                    let mut inputs: Vec<_> = self.graph.edges_directed(idx, Direction::Incoming)
+                        .collect();
+                    inputs.sort_by_key(|e| e.weight().typ.clone());
+                    assert_eq!(2, inputs.len(), "IntervalJoin should have 2 inputs, but has {}", inputs.len());
+                    assert_eq!(inputs[0].weight().key, inputs[1].weight().key, "IntervalJoin inputs must have the same key type");
+
+                    let in_k = parse_type(&inputs[0].weight().key);
+                    let in_t1 = parse_type(&inputs[0].weight().value);
+                    let in_t2 = parse_type(&inputs[1].weight().value);
+                    let out_t = parse_type(&output.unwrap().weight().value);
+                    let merge_expr: syn::Expr = parse_str(merge_expression).expect(merge_expression);
+
+                    quote! {
+                        Box::new(arroyo_worker::operators::interval_join::
+                            IntervalJoin::<#in_k, #in_t1, #in_t2, #out_t>::
+                        new(#lower_bound_micros,
+                            #upper_bound_micros,
+                            Box::new(|left: &#in_t1, right: &#in_t2| -> #out_t { #merge_expr })))
+                    }
+                }
+                Operator::LateRecordFilter { window, allowed_lateness } => {
+                    let in_k = parse_type(&input.unwrap().weight().key);
+                    let in_t = parse_type(&input.unwrap().weight().value);
+                    let allowed_lateness = duration_to_syn_expr(*allowed_lateness);
+
+                    let constructor = match window {
+                        WindowType::Tumbling { width } => {
+                            let width = duration_to_syn_expr(*width);
+                            quote! { tumbling_window(#width, #allowed_lateness) }
+                        }
+                        WindowType::Sliding { width, slide } => {
+                            let width = duration_to_syn_expr(*width);
+                            let slide = duration_to_syn_expr(*slide);
+                            quote! { sliding_window(#width, #slide, #allowed_lateness) }
+                        }
+                        WindowType::Instant => quote! { instant_window() },
+                        WindowType::Session { gap } => {
+                            let gap = duration_to_syn_expr(*gap);
+                            quote! { session_window(#gap, #allowed_lateness) }
+                        }
+                    };
+
+                    quote! {
+                        Box::new(LateRecordFilter::<#in_k, #in_t>::#constructor)
+                    }
+                }
+                Operator::ProcessFunction { name, state_type, process_expression, timer_expression } => {
+                    let in_k = parse_type(&input.unwrap().weight().key);
+                    let in_t = parse_type(&input.unwrap().weight().value);
+                    let out_t = parse_type(&output.unwrap().weight().value);
+                    let state_t = parse_type(state_type);
+                    let process_expr: syn::Expr = parse_str(process_expression).expect(process_expression);
+                    let timer_fn = match timer_expression {
+                        Some(timer_expression) => {
+                            let timer_expr: syn::Expr = parse_str(timer_expression).expect(timer_expression);
+                            quote! {
+                                Some(Box::new(|key: &#in_k, context: arroyo_types::ProcessContext<#state_t>| -> (arroyo_types::ProcessContext<#state_t>, Vec<#out_t>) { #timer_expr }))
+                            }
+                        }
+                        None => quote! { None },
+                    };
+
+                    quote! {
+                        Box::new(arroyo_worker::operators::process_function::
+                            ProcessFunctionOperator::<#in_k, #in_t, #state_t, #out_t>::
+                        new(#name.to_string(),
+                            Box::new(|arg: &#in_t, context: arroyo_types::ProcessContext<#state_t>| -> (arroyo_types::ProcessContext<#state_t>, Vec<#out_t>) { #process_expr }),
+                            #timer_fn))
+                    }
+                }
             };
 
             (node.operator_id.clone(), description, body, node.parallelism)
@@ -2062,28 +2359,32 @@ impl From<Operator> for GrpcApi::operator::Operator {
                 name,
                 wasm_functions: udfs.into_iter().map(|udf| udf.into()).collect(),
             }),
-            Operator::Window { typ, agg, flatten } => {
-                GrpcOperator::Window(GrpcApi::WindowOperator {
-                    aggregator: match &agg {
-                        Some(WindowAgg::Count) => Some(GrpcApi::Aggregator::CountAggregate.into()),
-                        Some(WindowAgg::Max) => Some(GrpcApi::Aggregator::MaxAggregate.into()),
-                        Some(WindowAgg::Min) => Some(GrpcApi::Aggregator::MinAggregate.into()),
-                        Some(WindowAgg::Sum) => Some(GrpcApi::Aggregator::SumAggregate.into()),
-                        Some(WindowAgg::Expression { .. }) => None,
-                        None => None,
-                    },
-                    expression_aggregator: match agg {
-                        Some(WindowAgg::Expression { name, expression }) => {
-                            Some(GrpcApi::ExpressionAggregator { name, expression })
-                        }
-                        _ => None,
-                    },
-                    flatten,
-                    window: Some(GrpcApi::Window {
-                        window: Some(typ.into()),
-                    }),
-                })
-            }
+            Operator::Window {
+                typ,
+                agg,
+                flatten,
+                allowed_lateness,
+            } => GrpcOperator::Window(GrpcApi::WindowOperator {
+                aggregator: match &agg {
+                    Some(WindowAgg::Count) => Some(GrpcApi::Aggregator::CountAggregate.into()),
+                    Some(WindowAgg::Max) => Some(GrpcApi::Aggregator::MaxAggregate.into()),
+                    Some(WindowAgg::Min) => Some(GrpcApi::Aggregator::MinAggregate.into()),
+                    Some(WindowAgg::Sum) => Some(GrpcApi::Aggregator::SumAggregate.into()),
+                    Some(WindowAgg::Expression { .. }) => None,
+                    None => None,
+                },
+                expression_aggregator: match agg {
+                    Some(WindowAgg::Expression { name, expression }) => {
+                        Some(GrpcApi::ExpressionAggregator { name, expression })
+                    }
+                    _ => None,
+                },
+                flatten,
+                window: Some(GrpcApi::Window {
+                    window: Some(typ.into()),
+                }),
+                allowed_lateness_micros: allowed_lateness.as_micros() as u64,
+            }),
             Operator::Count => GrpcOperator::Aggregator(GrpcApi::Aggregator::CountAggregate.into()),
             Operator::Aggregate(AggregateBehavior::Min) => {
                 GrpcOperator::Aggregator(GrpcApi::Aggregator::MinAggregate.into())
@@ -2234,13 +2535,7 @@ impl From<Operator> for GrpcApi::operator::Operator {
             } => GrpcOperator::JoinWithExpiration(GrpcApi::JoinWithExpiration {
                 left_expiration_micros: left_expiration.as_micros() as u64,
                 right_expiration_micros: right_expiration.as_micros() as u64,
-                join_type: match join_type {
-                    JoinType::Inner => GrpcApi::JoinType::Inner,
-                    JoinType::Left => GrpcApi::JoinType::Left,
-                    JoinType::Right => GrpcApi::JoinType::Right,
-                    JoinType::Full => GrpcApi::JoinType::Full,
-                }
-                .into(),
+                join_type: join_type_to_grpc(join_type),
             }),
             Operator::UpdatingOperator { name, expression } => {
                 GrpcOperator::UpdatingOperator(GrpcApi::UpdatingOperator { name, expression })
@@ -2259,10 +2554,95 @@ impl From<Operator> for GrpcApi::operator::Operator {
             Operator::UpdatingKeyOperator { name, expression } => {
                 GrpcOperator::UpdatingKeyOperator(GrpcApi::UpdatingKeyOperator { name, expression })
             }
+            Operator::LookupJoin(LookupJoin {
+                connector,
+                lookup_type,
+                key_expression,
+                merge_expression,
+                join_type,
+                cache_max_rows,
+                cache_ttl,
+                batch_size,
+                max_concurrency,
+            }) => GrpcOperator::LookupJoin(GrpcApi::LookupJoin {
+                connector: Some(connector.into()),
+                lookup_type,
+                key_expression,
+                merge_expression,
+                join_type: join_type_to_grpc(join_type),
+                cache_max_rows,
+                cache_ttl_micros: cache_ttl.as_micros() as u64,
+                batch_size,
+                max_concurrency,
+            }),
+            Operator::TemporalJoin {
+                join_type,
+                merge_expression,
+            } => GrpcOperator::TemporalJoin(GrpcApi::TemporalJoin {
+                join_type: join_type_to_grpc(join_type),
+                merge_expression,
+            }),
+            Operator::IntervalJoin {
+                lower_bound_micros,
+                upper_bound_micros,
+                merge_expression,
+            } => GrpcOperator::IntervalJoin(GrpcApi::IntervalJoin {
+                lower_bound_micros,
+                upper_bound_micros,
+                merge_expression,
+            }),
+            Operator::LateRecordFilter {
+                window,
+                allowed_lateness,
+            } => GrpcOperator::LateRecordFilter(GrpcApi::LateRecordFilter {
+                window: Some(GrpcApi::Window {
+                    window: Some(window.into()),
+                }),
+                allowed_lateness_micros: allowed_lateness.as_micros() as u64,
+            }),
+            Operator::ProcessFunction {
+                name,
+                state_type,
+                process_expression,
+                timer_expression,
+            } => GrpcOperator::ProcessFunction(GrpcApi::ProcessFunction {
+                name,
+                state_type,
+                process_expression,
+                timer_expression,
+            }),
         }
     }
 }
 
+fn join_type_to_grpc(join_type: JoinType) -> i32 {
+    match join_type {
+        JoinType::Inner => GrpcApi::JoinType::Inner,
+        JoinType::Left => GrpcApi::JoinType::Left,
+        JoinType::Right => GrpcApi::JoinType::Right,
+        JoinType::Full => GrpcApi::JoinType::Full,
+        JoinType::LeftSemi => GrpcApi::JoinType::LeftSemi,
+        JoinType::RightSemi => GrpcApi::JoinType::RightSemi,
+        JoinType::LeftAnti => GrpcApi::JoinType::LeftAnti,
+        JoinType::RightAnti => GrpcApi::JoinType::RightAnti,
+    }
+    .into()
+}
+
+fn join_type_from_grpc(join_type: i32) -> JoinType {
+    match GrpcApi::JoinType::from_i32(join_type) {
+        Some(GrpcApi::JoinType::Inner) => JoinType::Inner,
+        Some(GrpcApi::JoinType::Left) => JoinType::Left,
+        Some(GrpcApi::JoinType::Right) => JoinType::Right,
+        Some(GrpcApi::JoinType::Full) => JoinType::Full,
+        Some(GrpcApi::JoinType::LeftSemi) => JoinType::LeftSemi,
+        Some(GrpcApi::JoinType::RightSemi) => JoinType::RightSemi,
+        Some(GrpcApi::JoinType::LeftAnti) => JoinType::LeftAnti,
+        Some(GrpcApi::JoinType::RightAnti) => JoinType::RightAnti,
+        None => JoinType::Inner,
+    }
+}
+
 impl From<WasmUDF> for WasmFunction {
     fn from(udf: WasmUDF) -> Self {
         WasmFunction {
@@ -2410,6 +2790,7 @@ impl TryFrom<arroyo_rpc::grpc::api::Operator> for Operator {
                             .into(),
                         agg,
                         flatten: window.flatten,
+                        allowed_lateness: Duration::from_micros(window.allowed_lateness_micros),
                     }
                 }
                 GrpcOperator::Aggregator(agg) => {
@@ -2554,13 +2935,7 @@ impl TryFrom<arroyo_rpc::grpc::api::Operator> for Operator {
                 }) => Operator::JoinWithExpiration {
                     left_expiration: Duration::from_micros(left_expiration_micros),
                     right_expiration: Duration::from_micros(right_expiration_micros),
-                    join_type: match GrpcApi::JoinType::from_i32(join_type) {
-                        Some(GrpcApi::JoinType::Inner) => JoinType::Inner,
-                        Some(GrpcApi::JoinType::Left) => JoinType::Left,
-                        Some(GrpcApi::JoinType::Right) => JoinType::Right,
-                        Some(GrpcApi::JoinType::Full) => JoinType::Full,
-                        None => JoinType::Inner,
-                    },
+                    join_type: join_type_from_grpc(join_type),
                 },
                 GrpcOperator::UpdatingOperator(GrpcApi::UpdatingOperator { name, expression }) => {
                     Operator::UpdatingOperator { name, expression }
@@ -2580,6 +2955,63 @@ impl TryFrom<arroyo_rpc::grpc::api::Operator> for Operator {
                     name,
                     expression,
                 }) => Operator::UpdatingKeyOperator { name, expression },
+                GrpcOperator::LookupJoin(GrpcApi::LookupJoin {
+                    connector,
+                    lookup_type,
+                    key_expression,
+                    merge_expression,
+                    join_type,
+                    cache_max_rows,
+                    cache_ttl_micros,
+                    batch_size,
+                    max_concurrency,
+                }) => Operator::LookupJoin(LookupJoin {
+                    connector: connector
+                        .ok_or_else(|| anyhow!("lookup join is missing its connector"))?
+                        .into(),
+                    lookup_type,
+                    key_expression,
+                    merge_expression,
+                    join_type: join_type_from_grpc(join_type),
+                    cache_max_rows,
+                    cache_ttl: Duration::from_micros(cache_ttl_micros),
+                    batch_size,
+                    max_concurrency,
+                }),
+                GrpcOperator::TemporalJoin(GrpcApi::TemporalJoin {
+                    join_type,
+                    merge_expression,
+                }) => Operator::TemporalJoin {
+                    join_type: join_type_from_grpc(join_type),
+                    merge_expression,
+                },
+                GrpcOperator::IntervalJoin(GrpcApi::IntervalJoin {
+                    lower_bound_micros,
+                    upper_bound_micros,
+                    merge_expression,
+                }) => Operator::IntervalJoin {
+                    lower_bound_micros,
+                    upper_bound_micros,
+                    merge_expression,
+                },
+                GrpcOperator::LateRecordFilter(GrpcApi::LateRecordFilter {
+                    window,
+                    allowed_lateness_micros,
+                }) => Operator::LateRecordFilter {
+                    window: window.ok_or_else(|| anyhow!("missing window type"))?.into(),
+                    allowed_lateness: Duration::from_micros(allowed_lateness_micros),
+                },
+                GrpcOperator::ProcessFunction(GrpcApi::ProcessFunction {
+                    name,
+                    state_type,
+                    process_expression,
+                    timer_expression,
+                }) => Operator::ProcessFunction {
+                    name,
+                    state_type,
+                    process_expression,
+                    timer_expression,
+                },
             },
             None => bail!("unset on operator {:?}", operator),
         };
@@ -1,4 +1,5 @@
 use crate::SchemaData;
+use anyhow::{anyhow, bail};
 use apache_avro::types::{Value as AvroValue, Value};
 use apache_avro::{from_avro_datum, Reader, Schema, Writer};
 use arrow::datatypes::{DataType, Field, Fields, TimeUnit};
@@ -57,30 +58,37 @@ pub async fn deserialize_slice_avro<'a, T: DeserializeOwned>(
             registry.get(&id).unwrap()
         };
 
+        // decimals can only be decoded with the writer schema, so we convert to json while we
+        // have it, and then resolve that into the shape of the reader schema
+        let reader_schema: Option<&Schema> = format.reader_schema.as_ref().map(|t| t.into());
         let mut buf = &msg[..];
-        vec![from_avro_datum(
-            schema,
-            &mut buf,
-            format.reader_schema.as_ref().map(|t| t.into()),
-        )]
+        vec![from_avro_datum(schema, &mut buf, None)
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|v| resolve_json(avro_to_json(v, Some(schema)), reader_schema))]
     } else {
-        Reader::new(&msg[..])
-            .map_err(|e| format!("invalid Avro schema in message: {:?}", e))?
+        let reader = Reader::new(&msg[..])
+            .map_err(|e| format!("invalid Avro schema in message: {:?}", e))?;
+        let schema = reader.writer_schema().clone();
+        reader
+            .map(|v| {
+                v.map(|v| avro_to_json(v, Some(&schema)))
+                    .map_err(|e| format!("{:?}", e))
+            })
             .collect()
     };
 
     let into_json = format.into_unstructured_json;
     Ok(messages.into_iter().map(move |record| {
         let value = record.map_err(|e| {
-            SourceError::bad_data(format!("Failed to deserialize from avro: {:?}", e))
+            SourceError::bad_data(format!("Failed to deserialize from avro: {}", e))
         })?;
 
         if into_json {
-            Ok(serde_json::from_value(json!({"value": avro_to_json(value).to_string()})).unwrap())
+            Ok(serde_json::from_value(json!({"value": value.to_string()})).unwrap())
         } else {
             // for now round-trip through json in order to handle unsupported avro features
             // as that allows us to rely on raw json deserialization
-            serde_json::from_value(avro_to_json(value)).map_err(|e| {
+            serde_json::from_value(value).map_err(|e| {
                 SourceError::bad_data(format!(
                     "Failed to convert avro message into struct type: {:?}",
                     e
@@ -124,6 +132,197 @@ pub fn to_vec<T: SchemaData>(
     }
 }
 
+/// Resolves a value decoded with the writer's schema into the shape of the reader schema.
+///
+/// Unlike strict avro schema resolution, this tolerates the common ways that producer schemas
+/// evolve: fields that the reader doesn't know about are dropped, and fields that the writer
+/// no longer includes are filled in from the reader's default or, for optional fields, with null.
+fn resolve_json(value: JsonValue, reader: Option<&Schema>) -> Result<JsonValue, String> {
+    let Some(reader) = reader else {
+        return Ok(value);
+    };
+
+    Ok(match (reader, value) {
+        (Schema::Record(record), JsonValue::Object(mut object)) => {
+            let mut resolved = serde_json::Map::new();
+            for field in &record.fields {
+                let value = std::iter::once(&field.name)
+                    .chain(field.aliases.iter().flatten())
+                    .find_map(|name| object.remove(name));
+
+                let value = match (value, &field.default) {
+                    (Some(value), _) => resolve_json(value, Some(&field.schema))?,
+                    (None, Some(default)) => default.clone(),
+                    (None, None) if is_nullable(&field.schema) => JsonValue::Null,
+                    (None, None) => {
+                        return Err(format!(
+                            "field '{}' is missing from the writer's schema and has no default",
+                            field.name
+                        ));
+                    }
+                };
+                resolved.insert(field.name.clone(), value);
+            }
+            JsonValue::Object(resolved)
+        }
+        (Schema::Union(union), value) if !value.is_null() => {
+            let mut not_null = union
+                .variants()
+                .iter()
+                .filter(|v| !matches!(v, Schema::Null));
+            match (not_null.next(), not_null.next()) {
+                (Some(schema), None) => resolve_json(value, Some(schema))?,
+                // we can't tell which variant of a multi-variant union this is
+                _ => value,
+            }
+        }
+        (Schema::Array(items), JsonValue::Array(values)) => JsonValue::Array(
+            values
+                .into_iter()
+                .map(|v| resolve_json(v, Some(items)))
+                .collect::<Result<_, _>>()?,
+        ),
+        (Schema::Map(values), JsonValue::Object(object)) => JsonValue::Object(
+            object
+                .into_iter()
+                .map(|(k, v)| Ok((k, resolve_json(v, Some(values))?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        (_, value) => value,
+    })
+}
+
+fn is_nullable(schema: &Schema) -> bool {
+    match schema {
+        Schema::Null => true,
+        Schema::Union(union) => union.variants().iter().any(|v| matches!(v, Schema::Null)),
+        _ => false,
+    }
+}
+
+/// Describes an avro schema type for display to users
+fn describe_schema(schema: &Schema) -> String {
+    match schema {
+        Schema::Record(r) => r.name.fullname(None),
+        Schema::Enum(e) => e.name.fullname(None),
+        Schema::Fixed(f) => f.name.fullname(None),
+        Schema::Ref { name } => name.fullname(None),
+        Schema::Array(items) => format!("array<{}>", describe_schema(items)),
+        Schema::Map(values) => format!("map<{}>", describe_schema(values)),
+        Schema::Union(union) => union
+            .variants()
+            .iter()
+            .map(describe_schema)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        Schema::Decimal(d) => format!("decimal({}, {})", d.precision, d.scale),
+        other => {
+            let kind = format!("{:?}", apache_avro::schema::SchemaKind::from(other));
+            // convert the kind (e.g., TimestampMillis) into the avro name (timestamp-millis)
+            let mut name = String::new();
+            for (i, c) in kind.chars().enumerate() {
+                if c.is_uppercase() && i > 0 {
+                    name.push('-');
+                }
+                name.push(c.to_ascii_lowercase());
+            }
+            name
+        }
+    }
+}
+
+fn diff_records(path: &str, existing: &Schema, new: &Schema, diff: &mut Vec<String>) {
+    let (Schema::Record(existing), Schema::Record(new)) = (existing, new) else {
+        if existing != new {
+            diff.push(format!(
+                "~ {}: {} -> {}",
+                path,
+                describe_schema(existing),
+                describe_schema(new)
+            ));
+        }
+        return;
+    };
+
+    let field_path = |name: &str| {
+        if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", path, name)
+        }
+    };
+
+    for field in &existing.fields {
+        if !new.lookup.contains_key(&field.name) {
+            diff.push(format!(
+                "- {}: {}",
+                field_path(&field.name),
+                describe_schema(&field.schema)
+            ));
+        }
+    }
+
+    for field in &new.fields {
+        match existing.lookup.get(&field.name) {
+            Some(i) => diff_records(
+                &field_path(&field.name),
+                &existing.fields[*i].schema,
+                &field.schema,
+                diff,
+            ),
+            None => diff.push(format!(
+                "+ {}: {}{}",
+                field_path(&field.name),
+                describe_schema(&field.schema),
+                if field.default.is_none() {
+                    " (no default)"
+                } else {
+                    ""
+                }
+            )),
+        }
+    }
+}
+
+/// Returns a human-readable, line-by-line description of the differences between two avro
+/// schemas, where removed fields are prefixed by `-`, added fields by `+` and fields whose
+/// types have changed by `~`
+pub fn schema_diff(existing: &Schema, new: &Schema) -> Vec<String> {
+    let mut diff = vec![];
+    diff_records("", existing, new, &mut diff);
+    diff
+}
+
+/// Extracts the schema of the rows from a debezium change-event envelope, which has nullable
+/// `before` and `after` fields containing the row
+pub fn debezium_row_schema(envelope: &Schema) -> anyhow::Result<Schema> {
+    let Schema::Record(record) = envelope else {
+        bail!("debezium avro schema must be a record");
+    };
+
+    for name in ["before", "after"] {
+        let Some(field) = record.lookup.get(name).map(|i| &record.fields[*i]) else {
+            continue;
+        };
+
+        let variants = match &field.schema {
+            Schema::Union(union) => union.variants(),
+            schema => std::slice::from_ref(schema),
+        };
+
+        // debezium defines the row record in `before` and references it by name in `after`
+        if let Some(row) = variants.iter().find(|v| matches!(v, Schema::Record(_))) {
+            return Ok(row.clone());
+        }
+    }
+
+    bail!(
+        "avro schema {} is not a debezium envelope; expected a record with nullable 'before' \
+        and 'after' record fields",
+        record.name.fullname(None)
+    )
+}
+
 fn convert_float(f: f64) -> JsonValue {
     match serde_json::Number::from_f64(f) {
         Some(n) => JsonValue::Number(n),
@@ -144,7 +343,41 @@ fn encode_vec(v: Vec<u8>) -> JsonValue {
     JsonValue::String(v.into_iter().map(char::from).collect())
 }
 
-fn avro_to_json(value: AvroValue) -> JsonValue {
+/// Decodes an avro decimal (a big-endian two's-complement unscaled integer) into json. Decimals
+/// are converted to numbers when those represent them exactly, and otherwise to decimal strings.
+fn decimal_to_json(bytes: Vec<u8>, scale: usize) -> JsonValue {
+    let Ok(scale) = i8::try_from(scale) else {
+        return encode_vec(bytes);
+    };
+    if bytes.len() > 16 {
+        // too large to decode into an i128, so pass it through like other bytes
+        return encode_vec(bytes);
+    }
+
+    // sign-extend negative values
+    let fill = match bytes.first() {
+        Some(b) if b & 0x80 != 0 => 0xff,
+        _ => 0,
+    };
+    let mut buf = [fill; 16];
+    buf[16 - bytes.len()..].copy_from_slice(&bytes);
+    let value = i128::from_be_bytes(buf);
+
+    let decimal = arroyo_types::format_decimal(value, scale);
+    match decimal
+        .parse::<f64>()
+        .ok()
+        .filter(|f| arroyo_types::parse_decimal(&f.to_string(), scale) == Some(value))
+        .and_then(serde_json::Number::from_f64)
+    {
+        Some(n) => JsonValue::Number(n),
+        None => JsonValue::String(decimal),
+    }
+}
+
+/// Converts an avro value into json. The schema (if provided) is used to decode logical types
+/// that can't be interpreted from the value alone, like decimals.
+fn avro_to_json(value: AvroValue, schema: Option<&Schema>) -> JsonValue {
     match value {
         Value::Null => JsonValue::Null,
         Value::Boolean(b) => JsonValue::Bool(b),
@@ -162,18 +395,54 @@ fn avro_to_json(value: AvroValue) -> JsonValue {
         Value::String(s) | Value::Enum(_, s) => JsonValue::String(s),
         // this isn't the standard Avro json encoding, which just
         Value::Bytes(b) | Value::Fixed(_, b) => encode_vec(b),
-        Value::Union(_, b) => avro_to_json(*b),
-        Value::Array(a) => JsonValue::Array(a.into_iter().map(|v| avro_to_json(v)).collect()),
+        Value::Union(i, b) => {
+            let schema = match schema {
+                Some(Schema::Union(union)) => union.variants().get(i as usize),
+                _ => None,
+            };
+            avro_to_json(*b, schema)
+        }
+        Value::Array(a) => {
+            let schema = match schema {
+                Some(Schema::Array(items)) => Some(&**items),
+                _ => None,
+            };
+            JsonValue::Array(a.into_iter().map(|v| avro_to_json(v, schema)).collect())
+        }
         Value::Map(m) => {
-            JsonValue::Object(m.into_iter().map(|(k, v)| (k, avro_to_json(v))).collect())
+            let schema = match schema {
+                Some(Schema::Map(values)) => Some(&**values),
+                _ => None,
+            };
+            JsonValue::Object(
+                m.into_iter()
+                    .map(|(k, v)| (k, avro_to_json(v, schema)))
+                    .collect(),
+            )
         }
         Value::Record(rec) => {
-            JsonValue::Object(rec.into_iter().map(|(k, v)| (k, avro_to_json(v))).collect())
+            let record_schema = match schema {
+                Some(Schema::Record(r)) => Some(r),
+                _ => None,
+            };
+            JsonValue::Object(
+                rec.into_iter()
+                    .map(|(k, v)| {
+                        let schema = record_schema
+                            .and_then(|r| r.lookup.get(&k).map(|i| &r.fields[*i].schema));
+                        let v = avro_to_json(v, schema);
+                        (k, v)
+                    })
+                    .collect(),
+            )
         }
 
         Value::Decimal(d) => {
             let b: Vec<u8> = d.try_into().unwrap_or_else(|_| vec![]);
-            encode_vec(b)
+            match schema {
+                Some(Schema::Decimal(decimal)) => decimal_to_json(b, decimal.scale),
+                _ => encode_vec(b),
+            }
         }
         Value::Duration(d) => {
             json!({
@@ -186,9 +455,9 @@ fn avro_to_json(value: AvroValue) -> JsonValue {
     }
 }
 
-fn arrow_to_avro(name: &str, dt: &DataType) -> serde_json::value::Value {
+fn arrow_to_avro(name: &str, dt: &DataType) -> anyhow::Result<JsonValue> {
     let typ = match dt {
-        DataType::Null => unreachable!("null fields are not supported"),
+        DataType::Null => "null",
         DataType::Boolean => "boolean",
         DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::UInt8 | DataType::UInt16 => {
             "int"
@@ -205,88 +474,294 @@ fn arrow_to_avro(name: &str, dt: &DataType) -> serde_json::value::Value {
                 (TimeUnit::Millisecond | TimeUnit::Second, Some(_)) => "local-timestamp-millis",
             };
 
-            return json!({
+            return Ok(json!({
                 "type": "long",
                 "logicalType": logical
-            });
+            }));
         }
         DataType::Date32 | DataType::Date64 => {
-            return json!({
+            return Ok(json!({
                 "type": "int",
                 "logicalType": "date"
-            });
+            }));
         }
-        DataType::Time64(_) | DataType::Time32(_) => {
-            todo!("time is not supported")
+        DataType::Time32(_) => {
+            return Ok(json!({
+                "type": "int",
+                "logicalType": "time-millis"
+            }));
+        }
+        DataType::Time64(_) => {
+            return Ok(json!({
+                "type": "long",
+                "logicalType": "time-micros"
+            }));
+        }
+        DataType::Duration(_) | DataType::Interval(_) => {
+            // avro durations are a fixed(12) of little-endian months, days, and millis
+            return Ok(json!({
+                "type": "fixed",
+                "name": name,
+                "size": 12,
+                "logicalType": "duration"
+            }));
         }
-        DataType::Duration(_) => todo!("duration is not supported"),
-        DataType::Interval(_) => todo!("interval is not supported"),
         DataType::Binary | DataType::FixedSizeBinary(_) | DataType::LargeBinary => "bytes",
         DataType::Utf8 | DataType::LargeUtf8 => "string",
         DataType::List(t) | DataType::FixedSizeList(t, _) | DataType::LargeList(t) => {
-            return json!({
+            let mut items = arrow_to_avro(name, t.data_type())?;
+            if t.is_nullable() {
+                items = json!(["null", items]);
+            }
+
+            return Ok(json!({
                 "type": "array",
-                "items": arrow_to_avro(name, t.data_type())
-            });
-        }
-        DataType::Struct(fields) => {
-            let schema = arrow_to_avro_schema(name, fields).canonical_form();
-            return serde_json::from_str(&schema).unwrap();
-        }
-        DataType::Union(_, _) => unimplemented!("unions are not supported"),
-        DataType::Dictionary(_, _) => unimplemented!("dictionaries are not supported"),
-        DataType::Decimal128(_, _) => unimplemented!("decimal128 is not supported"),
-        DataType::Decimal256(_, _) => unimplemented!("decimal256 is not supported"),
-        DataType::Map(_, _) => unimplemented!("maps are not supported"),
-        DataType::RunEndEncoded(_, _) => unimplemented!("run end encoded is not supported"),
+                "items": items
+            }));
+        }
+        DataType::Struct(fields) => return record_to_avro(name, fields),
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            if *scale < 0 {
+                bail!(
+                    "decimals with negative scale ({}) cannot be represented in avro",
+                    scale
+                );
+            }
+
+            return Ok(json!({
+                "type": "bytes",
+                "logicalType": "decimal",
+                "precision": precision,
+                "scale": scale
+            }));
+        }
+        DataType::Map(entries, _) => {
+            let DataType::Struct(kv) = entries.data_type() else {
+                bail!("invalid map type {}; entries must be a struct", dt);
+            };
+
+            let (Some(key), Some(value)) = (kv.first(), kv.get(1)) else {
+                bail!(
+                    "invalid map type {}; entries must have a key and a value",
+                    dt
+                );
+            };
+
+            if !matches!(key.data_type(), DataType::Utf8 | DataType::LargeUtf8) {
+                bail!(
+                    "avro maps must have string keys, but found key type {}",
+                    key.data_type()
+                );
+            }
+
+            let mut values = arrow_to_avro(name, value.data_type())?;
+            if value.is_nullable() {
+                values = json!(["null", values]);
+            }
+
+            return Ok(json!({
+                "type": "map",
+                "values": values
+            }));
+        }
+        DataType::Union(fields, _) => {
+            let variants = fields
+                .iter()
+                .map(|(_, f)| {
+                    if matches!(f.data_type(), DataType::Union(_, _)) {
+                        bail!("avro unions cannot directly contain other unions");
+                    }
+                    let next_name = format!("{}_{}", name, f.name());
+                    arrow_to_avro(&AvroFormat::sanitize_field(&next_name), f.data_type())
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            return Ok(JsonValue::Array(variants));
+        }
+        DataType::Dictionary(_, values) => return arrow_to_avro(name, values),
+        DataType::RunEndEncoded(_, values) => return arrow_to_avro(name, values.data_type()),
     };
 
-    json!({
+    Ok(json!({
         "type": typ
-    })
+    }))
 }
 
-fn field_to_avro(name: &str, field: &Field) -> serde_json::value::Value {
+fn field_to_avro(name: &str, field: &Field) -> anyhow::Result<JsonValue> {
     let next_name = format!("{}_{}", name, &field.name());
-    let mut schema = arrow_to_avro(&AvroFormat::sanitize_field(&next_name), field.data_type());
+    let mut schema = arrow_to_avro(&AvroFormat::sanitize_field(&next_name), field.data_type())?;
 
-    if field.is_nullable() {
+    // null and union types are already nullable, and avro does not allow nested unions
+    if field.is_nullable() && !matches!(field.data_type(), DataType::Null | DataType::Union(_, _)) {
         schema = json!({
             "type": ["null", schema]
         })
     }
 
-    json!({
+    Ok(json!({
         "name": AvroFormat::sanitize_field(field.name()),
         "type": schema
-    })
+    }))
 }
 
-/// Computes an avro schema from an arrow schema
-///
-/// Note this must align with the generated code created in
-/// `arroyo_sql::avro::generate_serializer_items`!
-pub fn arrow_to_avro_schema(name: &str, fields: &Fields) -> Schema {
-    let fields: Vec<_> = fields.iter().map(|f| field_to_avro(name, &**f)).collect();
+fn record_to_avro(name: &str, fields: &Fields) -> anyhow::Result<JsonValue> {
+    let fields = fields
+        .iter()
+        .map(|f| field_to_avro(name, f))
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
-    let schema = json!({
+    Ok(json!({
         "type": "record",
         "name": name,
         "fields": fields,
-    });
+    }))
+}
 
-    Schema::parse_str(&schema.to_string()).unwrap()
+/// Computes an avro schema from an arrow schema, returning an error if any of the fields
+/// have types that cannot be represented in avro
+///
+/// Note this must align with the generated code created in
+/// `arroyo_sql::avro::generate_serializer_items`!
+pub fn arrow_to_avro_schema(name: &str, fields: &Fields) -> anyhow::Result<Schema> {
+    let schema = record_to_avro(name, fields)?;
+
+    Schema::parse_str(&schema.to_string())
+        .map_err(|e| anyhow!("could not construct avro schema for {}: {}", name, e))
+}
+
+// Avro timestamp-micros values are converted to json as numbers of microseconds since the epoch
+// (timestamp-millis values can use `json::timestamp_as_millis`)
+pub mod timestamp_as_micros {
+    use std::time::SystemTime;
+
+    use arroyo_types::{from_micros, to_micros};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(t: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(to_micros(*t))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let micros = i64::deserialize(deserializer)?;
+        u64::try_from(micros)
+            .map(from_micros)
+            .map_err(|_| de::Error::custom("micros must be positive"))
+    }
+}
+
+pub mod opt_timestamp_as_micros {
+    use std::time::SystemTime;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(t: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match t {
+            Some(t) => super::timestamp_as_micros::serialize(t, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<SystemTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Micros(#[serde(with = "super::timestamp_as_micros")] SystemTime);
+
+        Ok(Option::<Micros>::deserialize(deserializer)?.map(|m| m.0))
+    }
+}
+
+// Avro durations are converted to json as objects with months, days and milliseconds. As months
+// have no fixed length, only durations without months can be represented as a std Duration.
+pub mod duration {
+    use std::time::Duration;
+
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct AvroDuration {
+        months: u32,
+        days: u32,
+        milliseconds: u32,
+    }
+
+    pub fn serialize<S>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        AvroDuration {
+            months: 0,
+            days: (d.as_secs() / 86_400) as u32,
+            milliseconds: (d.as_millis() % 86_400_000) as u32,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let d = AvroDuration::deserialize(deserializer)?;
+        if d.months != 0 {
+            return Err(de::Error::custom(format!(
+                "durations with months ({}) are not supported",
+                d.months
+            )));
+        }
+        Ok(Duration::from_secs(d.days as u64 * 86_400)
+            + Duration::from_millis(d.milliseconds as u64))
+    }
+}
+
+pub mod opt_duration {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(d: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match d {
+            Some(d) => super::duration::serialize(d, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct AvroDuration(#[serde(with = "super::duration")] Duration);
+
+        Ok(Option::<AvroDuration>::deserialize(deserializer)?.map(|d| d.0))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{arrow_to_avro_schema, to_vec};
+    use super::{arrow_to_avro_schema, schema_diff, to_vec};
     use crate::{DataDeserializer, SchemaData};
-    use apache_avro::Schema;
+    use apache_avro::schema::DecimalSchema;
+    use apache_avro::types::Value;
+    use apache_avro::{Decimal, Schema};
+    use arrow::datatypes::{DataType, Field, Fields, TimeUnit};
     use arroyo_rpc::formats::{AvroFormat, Format};
-    use arroyo_rpc::schema_resolver::{FailingSchemaResolver, FixedSchemaResolver};
-    use arroyo_types::RawJson;
+    use arroyo_rpc::schema_resolver::{FailingSchemaResolver, FixedSchemaResolver, SchemaResolver};
+    use arroyo_types::{Debezium, DebeziumOp, RawJson};
     use serde_json::json;
+    use std::collections::HashMap;
     use std::sync::Arc;
 
     const SCHEMA: &str = r#"
@@ -462,6 +937,121 @@ mod tests {
         );
     }
 
+    /// A schema registry that serves a fixed set of schemas by id
+    struct MockRegistry(HashMap<u32, &'static str>);
+
+    #[async_trait::async_trait]
+    impl SchemaResolver for MockRegistry {
+        async fn resolve_schema(&self, id: u32) -> Result<Option<String>, String> {
+            Ok(self.0.get(&id).map(|s| s.to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evolved_writer_schemas() {
+        let reader_schema = r#"{"type": "record", "name": "User", "fields": [
+            {"name": "name", "type": "string"},
+            {"name": "favorite_number", "type": ["null", "int"]},
+            {"name": "favorite_color", "type": ["null", "string"], "default": null},
+            {"name": "country", "type": "string", "default": "unknown"}
+        ]}"#;
+
+        // the producer has since removed favorite_number and added age
+        let writer_schema = r#"{"type": "record", "name": "User", "fields": [
+            {"name": "name", "type": "string"},
+            {"name": "age", "type": "long"},
+            {"name": "favorite_color", "type": ["null", "string"]}
+        ]}"#;
+
+        let mut data = vec![0, 0, 0, 0, 2];
+        data.extend(
+            apache_avro::to_avro_datum(
+                &Schema::parse_str(writer_schema).unwrap(),
+                Value::Record(vec![
+                    ("name".to_string(), Value::String("Alyssa".to_string())),
+                    ("age".to_string(), Value::Long(30)),
+                    (
+                        "favorite_color".to_string(),
+                        Value::Union(1, Box::new(Value::String("red".to_string()))),
+                    ),
+                ]),
+            )
+            .unwrap(),
+        );
+
+        let mut format = AvroFormat::new(true, false, true);
+        format.add_reader_schema(Schema::parse_str(reader_schema).unwrap());
+
+        let mut deserializer = DataDeserializer::with_schema_resolver(
+            Format::Avro(format),
+            None,
+            Arc::new(MockRegistry(HashMap::from([
+                (1, reader_schema),
+                (2, writer_schema),
+            ]))),
+        );
+
+        let v: Result<Vec<RawJson>, _> = deserializer.deserialize_slice(&data[..]).await.collect();
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&v.unwrap()[0].value).unwrap(),
+            json!({ "name": "Alyssa", "favorite_number": null, "favorite_color": "red", "country": "unknown" })
+        );
+
+        // fields without defaults that aren't nullable must be provided by the writer
+        let reader_schema = r#"{"type": "record", "name": "User", "fields": [
+            {"name": "name", "type": "string"},
+            {"name": "favorite_number", "type": "int"}
+        ]}"#;
+        let mut format = AvroFormat::new(true, false, true);
+        format.add_reader_schema(Schema::parse_str(reader_schema).unwrap());
+
+        let mut deserializer = DataDeserializer::<RawJson>::with_schema_resolver(
+            Format::Avro(format),
+            None,
+            Arc::new(MockRegistry(HashMap::from([(2, writer_schema)]))),
+        );
+
+        let v: Vec<_> = deserializer.deserialize_slice(&data[..]).await.collect();
+        assert!(v[0].is_err());
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let existing = Schema::parse_str(
+            r#"{"type": "record", "name": "Order", "fields": [
+                {"name": "id", "type": "long"},
+                {"name": "price", "type": "double"},
+                {"name": "customer", "type": {"type": "record", "name": "Customer", "fields": [
+                    {"name": "name", "type": "string"}
+                ]}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let new = Schema::parse_str(
+            r#"{"type": "record", "name": "Order", "fields": [
+                {"name": "id", "type": "string"},
+                {"name": "customer", "type": {"type": "record", "name": "Customer", "fields": [
+                    {"name": "name", "type": "string"},
+                    {"name": "email", "type": ["null", "string"], "default": null}
+                ]}},
+                {"name": "created_at", "type": {"type": "long", "logicalType": "timestamp-millis"}}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            schema_diff(&existing, &new),
+            vec![
+                "- price: double",
+                "~ id: long -> string",
+                "+ customer.email: null | string",
+                "+ created_at: timestamp-millis (no default)",
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_backwards_compatible() {
         #[derive(
@@ -611,6 +1201,252 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_decimal_deserialization() {
+        let schema_str = r#"{
+            "type": "record",
+            "name": "Order",
+            "fields": [
+                {"name": "price", "type": {"type": "bytes", "logicalType": "decimal", "precision": 10, "scale": 2}},
+                {"name": "discounts", "type": {"type": "array", "items": {"type": "bytes", "logicalType": "decimal", "precision": 10, "scale": 2}}}
+            ]
+        }"#;
+        let schema = Schema::parse_str(schema_str).unwrap();
+
+        let data = apache_avro::to_avro_datum(
+            &schema,
+            Value::Record(vec![
+                (
+                    "price".to_string(),
+                    Value::Decimal(Decimal::from(vec![0x30, 0x39])),
+                ),
+                (
+                    "discounts".to_string(),
+                    Value::Array(vec![Value::Decimal(Decimal::from(vec![0xcf, 0xc7]))]),
+                ),
+            ]),
+        )
+        .unwrap();
+
+        let mut format = AvroFormat::new(false, true, true);
+        format.add_reader_schema(schema);
+        let mut deserializer = DataDeserializer::new(Format::Avro(format), None);
+
+        let v: Result<Vec<RawJson>, _> = deserializer.deserialize_slice(&data[..]).await.collect();
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&v.unwrap()[0].value).unwrap(),
+            json!({ "price": 123.45, "discounts": [-123.45] })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_debezium() {
+        #[derive(
+            Clone,
+            Debug,
+            bincode::Encode,
+            bincode::Decode,
+            PartialEq,
+            PartialOrd,
+            serde::Serialize,
+            serde::Deserialize,
+        )]
+        pub struct Row {
+            pub id: i64,
+            pub name: String,
+        }
+
+        impl SchemaData for Row {
+            fn name() -> &'static str {
+                "Row"
+            }
+            fn schema() -> arrow::datatypes::Schema {
+                arrow::datatypes::Schema::new(vec![
+                    Field::new("id", DataType::Int64, false),
+                    Field::new("name", DataType::Utf8, false),
+                ])
+            }
+            fn to_raw_string(&self) -> Option<Vec<u8>> {
+                unimplemented!("to_raw_string is not implemented for this type")
+            }
+            fn to_avro(&self, _schema: &apache_avro::Schema) -> apache_avro::types::Value {
+                Value::Record(vec![
+                    ("id".to_string(), Value::Long(self.id)),
+                    ("name".to_string(), Value::String(self.name.clone())),
+                ])
+            }
+        }
+
+        // the envelope as produced by debezium, with the row record referenced by name in `after`
+        let schema_str = r#"{"type": "record", "name": "Envelope", "namespace": "db.users", "fields": [
+            {"name": "before", "type": ["null", {"type": "record", "name": "Value", "fields": [
+                {"name": "id", "type": "long"},
+                {"name": "name", "type": "string"}
+            ]}], "default": null},
+            {"name": "after", "type": ["null", "Value"], "default": null},
+            {"name": "source", "type": {"type": "record", "name": "Source", "fields": [
+                {"name": "db", "type": "string"}
+            ]}},
+            {"name": "op", "type": "string"},
+            {"name": "ts_ms", "type": ["null", "long"], "default": null}
+        ]}"#;
+        let schema = Schema::parse_str(schema_str).unwrap();
+
+        let Schema::Record(row_schema) = super::debezium_row_schema(&schema).unwrap() else {
+            panic!("expected record schema");
+        };
+        assert_eq!(row_schema.name.fullname(None), "db.users.Value");
+        assert!(super::debezium_row_schema(
+            &Schema::parse_str(
+                r#"{"type": "record",
+            "name": "User", "fields": [{"name": "id", "type": "long"}]}"#
+            )
+            .unwrap()
+        )
+        .is_err());
+
+        let row = |id: i64, name: &str| {
+            Value::Record(vec![
+                ("id".to_string(), Value::Long(id)),
+                ("name".to_string(), Value::String(name.to_string())),
+            ])
+        };
+
+        let event = |before: Option<Value>, after: Option<Value>, op: &str| {
+            let nullable = |v: Option<Value>| match v {
+                Some(v) => Value::Union(1, Box::new(v)),
+                None => Value::Union(0, Box::new(Value::Null)),
+            };
+            apache_avro::to_avro_datum(
+                &schema,
+                Value::Record(vec![
+                    ("before".to_string(), nullable(before)),
+                    ("after".to_string(), nullable(after)),
+                    (
+                        "source".to_string(),
+                        Value::Record(vec![("db".to_string(), Value::String("db".to_string()))]),
+                    ),
+                    ("op".to_string(), Value::String(op.to_string())),
+                    (
+                        "ts_ms".to_string(),
+                        Value::Union(1, Box::new(Value::Long(1700000000000))),
+                    ),
+                ]),
+            )
+            .unwrap()
+        };
+
+        let mut format = AvroFormat::new(false, true, false);
+        format.debezium = true;
+        format.add_reader_schema(schema.clone());
+        let mut deserializer = DataDeserializer::new(Format::Avro(format), None);
+
+        let read: Vec<Debezium<Row>> = deserializer
+            .deserialize_slice(&event(None, Some(row(1, "bob")), "r"))
+            .await
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read[0].op, DebeziumOp::Create);
+        assert_eq!(
+            read[0].after,
+            Some(Row {
+                id: 1,
+                name: "bob".to_string()
+            })
+        );
+        assert_eq!(read[0].ts_ms, Some(1700000000000));
+
+        let deleted: Vec<Debezium<Row>> = deserializer
+            .deserialize_slice(&event(Some(row(1, "bob")), None, "d"))
+            .await
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(deleted[0].op, DebeziumOp::Delete);
+        assert_eq!(deleted[0].before, read[0].after);
+
+        // tombstones that follow deletes are skipped
+        assert_eq!(deserializer.deserialize_slice(&[]).await.count(), 0);
+    }
+
+    #[test]
+    fn test_arrow_to_avro_schema_types() {
+        let map_entries = Field::new(
+            "entries",
+            DataType::Struct(Fields::from(vec![
+                Field::new("key", DataType::Utf8, false),
+                Field::new("value", DataType::Int64, true),
+            ])),
+            false,
+        );
+
+        let fields = Fields::from(vec![
+            Field::new("time", DataType::Time64(TimeUnit::Microsecond), false),
+            Field::new("elapsed", DataType::Duration(TimeUnit::Millisecond), true),
+            Field::new("price", DataType::Decimal128(10, 2), false),
+            Field::new(
+                "tags",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                false,
+            ),
+            Field::new(
+                "counts",
+                DataType::Map(Arc::new(map_entries.clone()), false),
+                false,
+            ),
+        ]);
+
+        let Schema::Record(record) = arrow_to_avro_schema("ArroyoAvroRoot", &fields).unwrap()
+        else {
+            panic!("expected a record schema");
+        };
+
+        let field_schema = |name: &str| &record.fields[record.lookup[name]].schema;
+
+        assert_eq!(field_schema("time"), &Schema::TimeMicros);
+        let Schema::Union(elapsed) = field_schema("elapsed") else {
+            panic!("nullable fields should be unions");
+        };
+        assert_eq!(elapsed.variants()[1], Schema::Duration);
+        assert!(matches!(
+            field_schema("price"),
+            Schema::Decimal(DecimalSchema {
+                precision: 10,
+                scale: 2,
+                ..
+            })
+        ));
+        let Schema::Array(items) = field_schema("tags") else {
+            panic!("lists should be arrays");
+        };
+        assert!(matches!(&**items, Schema::Union(_)));
+        assert!(matches!(field_schema("counts"), Schema::Map(_)));
+
+        // these can't be represented in avro, so should return errors rather than panicking
+        let invalid_map = Field::new(
+            "entries",
+            DataType::Struct(Fields::from(vec![
+                Field::new("key", DataType::Int32, false),
+                Field::new("value", DataType::Int64, true),
+            ])),
+            false,
+        );
+        assert!(arrow_to_avro_schema(
+            "ArroyoAvroRoot",
+            &Fields::from(vec![Field::new(
+                "m",
+                DataType::Map(Arc::new(invalid_map), false),
+                false
+            )])
+        )
+        .is_err());
+        assert!(arrow_to_avro_schema(
+            "ArroyoAvroRoot",
+            &Fields::from(vec![Field::new("d", DataType::Decimal128(10, -2), false)])
+        )
+        .is_err());
+    }
+
     #[tokio::test]
     async fn test_writing() {
         #[derive(
@@ -703,9 +1539,11 @@ mod tests {
             into_unstructured_json: false,
             reader_schema: None,
             schema_id: None,
+            debezium: false,
         };
 
-        let schema = arrow_to_avro_schema("ArroyoAvroRoot", &ArroyoAvroRoot::schema().fields());
+        let schema =
+            arrow_to_avro_schema("ArroyoAvroRoot", &ArroyoAvroRoot::schema().fields()).unwrap();
 
         let record = ArroyoAvroRoot {
             name: "Alyssa".to_string(),
@@ -0,0 +1,346 @@
+use arrow::datatypes::{DataType, Field, Fields};
+use arroyo_rpc::formats::CsvFormat;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+fn reader_builder(format: &CsvFormat) -> ::csv::ReaderBuilder {
+    let mut builder = ::csv::ReaderBuilder::new();
+    builder
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(format.delimiter as u8)
+        .quote(format.quote as u8);
+
+    if let Some(escape) = format.escape {
+        builder.escape(Some(escape as u8)).double_quote(false);
+    }
+
+    builder
+}
+
+fn writer_builder(format: &CsvFormat) -> ::csv::WriterBuilder {
+    let mut builder = ::csv::WriterBuilder::new();
+    builder
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(format.delimiter as u8)
+        .quote(format.quote as u8)
+        .terminator(::csv::Terminator::Any(b'\n'));
+
+    if let Some(escape) = format.escape {
+        builder.escape(escape as u8).double_quote(false);
+    }
+
+    builder
+}
+
+fn read_record(format: &CsvFormat, msg: &[u8]) -> Result<::csv::StringRecord, String> {
+    let mut reader = reader_builder(format).from_reader(msg);
+    let mut record = ::csv::StringRecord::new();
+    match reader.read_record(&mut record) {
+        Ok(true) => Ok(record),
+        Ok(false) => Err("CSV record is empty".to_string()),
+        Err(e) => Err(format!("invalid CSV record: {}", e)),
+    }
+}
+
+/// Returns true if the line ends inside of a quoted field, meaning the record continues
+/// on the next line
+pub fn has_unterminated_quote(format: &CsvFormat, line: &str) -> bool {
+    let mut in_quote = false;
+    let mut escaped = false;
+    for c in line.chars() {
+        if escaped {
+            escaped = false;
+        } else if in_quote && Some(c) == format.escape {
+            escaped = true;
+        } else if c == format.quote {
+            // doubled quotes toggle twice, leaving us in the quoted state
+            in_quote = !in_quote;
+        }
+    }
+
+    in_quote
+}
+
+/// Parses a header row into the list of column names
+pub fn parse_header(format: &CsvFormat, msg: &[u8]) -> Result<Vec<String>, String> {
+    Ok(read_record(format, msg)?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect())
+}
+
+fn parse_timestamp(value: &str) -> Option<String> {
+    if DateTime::parse_from_rfc3339(value).is_ok() {
+        return Some(value.to_string());
+    }
+
+    let naive = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })?;
+
+    Some(
+        DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc)
+            .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+    )
+}
+
+fn parse_value(format: &CsvFormat, field: &Field, value: &str) -> Result<Value, String> {
+    let is_string = matches!(field.data_type(), DataType::Utf8 | DataType::LargeUtf8);
+
+    if format.null_string.as_deref() == Some(value)
+        || (value.is_empty() && field.is_nullable() && !is_string)
+    {
+        return Ok(Value::Null);
+    }
+
+    let invalid = || {
+        format!(
+            "invalid value '{}' for field '{}' of type {}",
+            value,
+            field.name(),
+            field.data_type()
+        )
+    };
+
+    let trimmed = value.trim();
+
+    Ok(match field.data_type() {
+        DataType::Utf8 | DataType::LargeUtf8 => Value::String(value.to_string()),
+        DataType::Boolean => match trimmed.to_lowercase().as_str() {
+            "true" | "t" | "yes" | "1" => Value::Bool(true),
+            "false" | "f" | "no" | "0" => Value::Bool(false),
+            _ => return Err(invalid()),
+        },
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            Value::Number(trimmed.parse::<i64>().map_err(|_| invalid())?.into())
+        }
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
+            Value::Number(trimmed.parse::<u64>().map_err(|_| invalid())?.into())
+        }
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => Value::Number(
+            trimmed
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .ok_or_else(invalid)?,
+        ),
+        DataType::Timestamp(_, _) => Value::String(parse_timestamp(trimmed).ok_or_else(invalid)?),
+        // other types are passed through as strings and left to the generated deserializer
+        _ => Value::String(value.to_string()),
+    })
+}
+
+/// Deserializes a single CSV record into T. If `headers` are provided, columns are matched
+/// to fields by name, otherwise they are matched by position.
+pub fn deserialize_slice_csv<T: DeserializeOwned>(
+    format: &CsvFormat,
+    fields: &Fields,
+    headers: Option<&[String]>,
+    msg: &[u8],
+) -> Result<T, String> {
+    let record = read_record(format, msg)?;
+
+    let mut object = Map::new();
+    for (i, field) in fields.iter().enumerate() {
+        let idx = match headers {
+            Some(headers) => headers.iter().position(|h| h == field.name()),
+            None => Some(i),
+        };
+
+        let value = match idx.and_then(|idx| record.get(idx)) {
+            Some(value) => parse_value(format, field, value)?,
+            None => Value::Null,
+        };
+
+        object.insert(field.name().clone(), value);
+    }
+
+    serde_json::from_value(Value::Object(object))
+        .map_err(|e| format!("Failed to deserialize CSV into schema: {:?}", e))
+}
+
+fn write_row<'a>(format: &CsvFormat, row: impl IntoIterator<Item = &'a str>) -> Vec<u8> {
+    let mut writer = writer_builder(format).from_writer(vec![]);
+    writer
+        .write_record(row)
+        .expect("writing to a vec cannot fail");
+    let mut buf = writer
+        .into_inner()
+        .unwrap_or_else(|_| unreachable!("flushing a vec cannot fail"));
+
+    // the caller is responsible for record separators
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+    }
+    buf
+}
+
+/// Produces the header row for the fields, without a trailing newline
+pub fn header_row(format: &CsvFormat, fields: &Fields) -> Vec<u8> {
+    write_row(format, fields.iter().map(|f| f.name().as_str()))
+}
+
+/// Serializes the record as a CSV row with columns in schema order, without a trailing newline
+pub fn to_vec<T: Serialize>(record: &T, format: &CsvFormat, fields: &Fields) -> Vec<u8> {
+    let Value::Object(mut object) = serde_json::to_value(record).unwrap() else {
+        unreachable!("records must serialize to objects");
+    };
+
+    let null_string = format.null_string.as_deref().unwrap_or("");
+
+    let values: Vec<String> = fields
+        .iter()
+        .map(|f| match object.remove(f.name()) {
+            None | Some(Value::Null) => null_string.to_string(),
+            Some(Value::String(s)) => s,
+            Some(Value::Bool(b)) => b.to_string(),
+            Some(Value::Number(n)) => n.to_string(),
+            // nested values are written as JSON
+            Some(v) => v.to_string(),
+        })
+        .collect();
+
+    write_row(format, values.iter().map(|v| v.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::TimeUnit;
+    use arroyo_rpc::formats::CsvHeader;
+    use serde::Deserialize;
+    use std::time::SystemTime;
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Row {
+        name: String,
+        count: i64,
+        ratio: Option<f64>,
+        active: bool,
+        #[serde(with = "crate::json::timestamp_as_rfc3339")]
+        ts: SystemTime,
+    }
+
+    fn fields() -> Fields {
+        vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("count", DataType::Int64, false),
+            Field::new("ratio", DataType::Float64, true),
+            Field::new("active", DataType::Boolean, false),
+            Field::new(
+                "ts",
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+                false,
+            ),
+        ]
+        .into()
+    }
+
+    #[test]
+    fn test_deserialize_by_position() {
+        let format = CsvFormat::default();
+
+        let row: Row = deserialize_slice_csv(
+            &format,
+            &fields(),
+            None,
+            b"\"hello, world\",5,,true,2023-10-01 12:00:00",
+        )
+        .unwrap();
+
+        assert_eq!(row.name, "hello, world");
+        assert_eq!(row.count, 5);
+        assert_eq!(row.ratio, None);
+        assert!(row.active);
+        assert_eq!(
+            row.ts,
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1696161600)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_with_headers_and_nulls() {
+        let format = CsvFormat {
+            delimiter: '\t',
+            header: CsvHeader::Use,
+            null_string: Some("NULL".to_string()),
+            ..Default::default()
+        };
+
+        let headers = parse_header(&format, b"ts\tactive\tname\tratio\tcount").unwrap();
+
+        let row: Row = deserialize_slice_csv(
+            &format,
+            &fields(),
+            Some(&headers),
+            b"2023-10-01T12:00:00Z\tfalse\tbob\tNULL\t7",
+        )
+        .unwrap();
+
+        assert_eq!(row.name, "bob");
+        assert_eq!(row.count, 7);
+        assert_eq!(row.ratio, None);
+        assert!(!row.active);
+
+        assert!(deserialize_slice_csv::<Row>(
+            &format,
+            &fields(),
+            Some(&headers),
+            b"2023-10-01T12:00:00Z\tfalse\tbob\tNULL\tseven",
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let format = CsvFormat {
+            null_string: Some("\\N".to_string()),
+            ..Default::default()
+        };
+
+        let row = Row {
+            name: "a \"quoted\" value".to_string(),
+            count: -3,
+            ratio: None,
+            active: true,
+            ts: SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(1696161600123),
+        };
+
+        let bytes = to_vec(&row, &format, &fields());
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            "\"a \"\"quoted\"\" value\",-3,\\N,true,2023-10-01T12:00:00.123+00:00"
+        );
+
+        let result: Row = deserialize_slice_csv(&format, &fields(), None, &bytes).unwrap();
+        assert_eq!(result, row);
+
+        assert_eq!(
+            String::from_utf8(header_row(&format, &fields())).unwrap(),
+            "name,count,ratio,active,ts"
+        );
+    }
+
+    #[test]
+    fn test_unterminated_quote() {
+        let format = CsvFormat::default();
+        assert!(!has_unterminated_quote(&format, "a,\"b\",c"));
+        assert!(has_unterminated_quote(&format, "a,\"b"));
+        assert!(!has_unterminated_quote(&format, "a,\"b\"\"c\""));
+
+        let format = CsvFormat {
+            escape: Some('\\'),
+            ..Default::default()
+        };
+        assert!(has_unterminated_quote(&format, "a,\"b\\\""));
+    }
+}
@@ -1,11 +1,220 @@
-use arrow::datatypes::{Field, Fields};
-use arroyo_rpc::formats::JsonFormat;
+use arrow::datatypes::{DataType, Field, Fields};
+use arroyo_rpc::formats::{JsonFormat, TimestampFormat};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
 use serde::de::DeserializeOwned;
-use serde_json::{json, Value};
+use serde::Serialize;
+use serde_json::value::RawValue;
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Returns true if records need to be converted to or from the representation used by the
+/// generated structs (which always use top-level fields, and RFC3339 or millisecond timestamps)
+pub fn needs_conversion(format: &JsonFormat) -> bool {
+    converts_timestamps(format) || format.decimal_as_string || !format.field_paths.is_empty()
+}
+
+fn converts_timestamps(format: &JsonFormat) -> bool {
+    !matches!(
+        format.timestamp_format,
+        TimestampFormat::RFC3339 | TimestampFormat::UnixMillis
+    )
+}
+
+fn timestamp_to_rfc3339(format: &TimestampFormat, value: &Value) -> Result<String, String> {
+    let invalid = || format!("invalid timestamp for format {:?}: {}", format, value);
+
+    let nanos = match (format, value) {
+        (TimestampFormat::UnixSeconds, Value::Number(n)) => {
+            // parse fractional seconds from the decimal representation, which (unlike going
+            // through an f64) is exact
+            let n = n.to_string();
+            let (secs, fraction) = n.split_once('.').unwrap_or((n.as_str(), ""));
+            let nanos: i64 = format!("{:0<9}", fraction)
+                .get(..9)
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(invalid)?;
+            secs.parse::<i64>()
+                .ok()
+                .and_then(|s| s.checked_mul(1_000_000_000))
+                .and_then(|s| {
+                    if n.starts_with('-') {
+                        s.checked_sub(nanos)
+                    } else {
+                        s.checked_add(nanos)
+                    }
+                })
+        }
+        (TimestampFormat::UnixMicros, Value::Number(n)) => {
+            n.as_i64().and_then(|t| t.checked_mul(1_000))
+        }
+        (TimestampFormat::UnixNanos, Value::Number(n)) => n.as_i64(),
+        (TimestampFormat::Custom(pattern), Value::String(s)) => {
+            let time = DateTime::parse_from_str(s, pattern)
+                .map(|t| t.with_timezone(&Utc))
+                .or_else(|_| {
+                    NaiveDateTime::parse_from_str(s, pattern).map(|t| Utc.from_utc_datetime(&t))
+                })
+                .or_else(|_| {
+                    NaiveDate::parse_from_str(s, pattern)
+                        .map(|d| Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap()))
+                })
+                .map_err(|e| {
+                    format!("could not parse '{}' with pattern '{}': {}", s, pattern, e)
+                })?;
+            time.timestamp_nanos_opt()
+        }
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+
+    Ok(Utc
+        .timestamp_nanos(nanos)
+        .to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+fn timestamp_from_rfc3339(format: &TimestampFormat, value: &str) -> Result<Value, String> {
+    let time = DateTime::parse_from_rfc3339(value)
+        .map_err(|e| format!("invalid timestamp '{}': {}", value, e))?
+        .with_timezone(&Utc);
+    let nanos = || {
+        time.timestamp_nanos_opt()
+            .ok_or_else(|| format!("timestamp '{}' is out of range", value))
+    };
+
+    Ok(match format {
+        TimestampFormat::RFC3339 => Value::String(value.to_string()),
+        TimestampFormat::UnixSeconds => json!(time.timestamp()),
+        TimestampFormat::UnixMillis => json!(time.timestamp_millis()),
+        TimestampFormat::UnixMicros => json!(nanos()? / 1_000),
+        TimestampFormat::UnixNanos => json!(nanos()?),
+        TimestampFormat::Custom(pattern) => {
+            // formatting with an invalid pattern fails, rather than panicking as to_string would
+            let mut s = String::new();
+            write!(s, "{}", time.format(pattern))
+                .map_err(|_| format!("invalid timestamp pattern '{}'", pattern))?;
+            Value::String(s)
+        }
+    })
+}
+
+/// Converts the values in a record to the form expected by the generated structs
+fn read_value(format: &JsonFormat, data_type: &DataType, value: &mut Value) -> Result<(), String> {
+    match (data_type, value) {
+        (_, Value::Null) => {}
+        (DataType::Timestamp(_, _), value) if converts_timestamps(format) => {
+            *value = Value::String(timestamp_to_rfc3339(&format.timestamp_format, value)?);
+        }
+        (DataType::Struct(fields), Value::Object(o)) => read_object(format, fields, o)?,
+        (
+            DataType::List(f) | DataType::LargeList(f) | DataType::FixedSizeList(f, _),
+            Value::Array(items),
+        ) => {
+            for item in items {
+                read_value(format, f.data_type(), item)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn read_object(
+    format: &JsonFormat,
+    fields: &Fields,
+    object: &mut Map<String, Value>,
+) -> Result<(), String> {
+    for field in fields {
+        if let Some(value) = object.get_mut(field.name()) {
+            read_value(format, field.data_type(), value)
+                .map_err(|e| format!("field '{}': {}", field.name(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Converts the values in a serialized record to the form configured by the format; the
+/// inverse of `read_value`
+fn write_value(format: &JsonFormat, data_type: &DataType, value: &mut Value) -> Result<(), String> {
+    match (data_type, &mut *value) {
+        (_, Value::Null) => {}
+        (DataType::Timestamp(_, _), Value::String(s)) if converts_timestamps(format) => {
+            let timestamp = timestamp_from_rfc3339(&format.timestamp_format, s)?;
+            *value = timestamp;
+        }
+        (DataType::Struct(fields), Value::Object(o)) => {
+            for field in fields {
+                if let Some(value) = o.get_mut(field.name()) {
+                    write_value(format, field.data_type(), value)?;
+                }
+            }
+        }
+        (
+            DataType::List(f) | DataType::LargeList(f) | DataType::FixedSizeList(f, _),
+            Value::Array(items),
+        ) => {
+            for item in items {
+                write_value(format, f.data_type(), item)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Moves the values at the configured field paths to the top-level fields named for their columns
+fn read_field_paths(format: &JsonFormat, object: &mut Map<String, Value>) {
+    let values: Vec<_> = format
+        .field_paths
+        .iter()
+        .map(|p| {
+            let (last, parents) = p.path.split_last().expect("field paths must not be empty");
+            let value = parents
+                .iter()
+                .try_fold(&*object, |o, key| o.get(key)?.as_object())
+                .and_then(|o| o.get(last))
+                .cloned();
+            (p.column.clone(), value)
+        })
+        .collect();
+
+    for (column, value) in values {
+        match value {
+            Some(value) => object.insert(column, value),
+            None => object.remove(&column),
+        };
+    }
+}
+
+/// Moves the top-level fields for columns with configured field paths to those paths
+fn write_field_paths(format: &JsonFormat, object: &mut Map<String, Value>) {
+    let values: Vec<_> = format
+        .field_paths
+        .iter()
+        .map(|p| (p, object.remove(&p.column).unwrap_or(Value::Null)))
+        .collect();
+
+    for (p, value) in values {
+        let (last, parents) = p.path.split_last().expect("field paths must not be empty");
+        let mut current = &mut *object;
+        for key in parents {
+            let entry = current
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            current = entry.as_object_mut().unwrap();
+        }
+        current.insert(last.clone(), value);
+    }
+}
 
 pub fn deserialize_slice_json<T: DeserializeOwned>(
     format: &JsonFormat,
+    fields: impl FnOnce() -> Fields,
     msg: &[u8],
 ) -> Result<T, String> {
     let msg = if format.confluent_schema_registry {
@@ -36,12 +245,106 @@ pub fn deserialize_slice_json<T: DeserializeOwned>(
         //  produce that value. However, without specialization I don't know how to get the compiler to emit
         //  the optimized code for that case.
         Ok(serde_json::from_value(j).unwrap())
+    } else if needs_conversion(format) {
+        let mut value: Value = serde_json::from_slice(msg)
+            .map_err(|e| format!("Failed to deserialize json: {:?}", e))?;
+
+        if let Value::Object(object) = &mut value {
+            read_field_paths(format, object);
+            read_object(format, &fields(), object)?;
+        }
+
+        serde_json::from_value(value)
+            .map_err(|e| format!("Failed to deserialize JSON into schema: {:?}", e))
     } else {
         serde_json::from_slice(msg)
             .map_err(|e| format!("Failed to deserialize JSON into schema: {:?}", e))
     }
 }
 
+/// Parses a serialized value, replacing the numbers in decimal columns with their exact decimal
+/// strings
+fn decimals_to_strings(data_type: &DataType, raw: &RawValue) -> Result<Value, String> {
+    let parse_err = |e: serde_json::Error| e.to_string();
+
+    Ok(match data_type {
+        DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => {
+            match serde_json::from_str(raw.get()).map_err(parse_err)? {
+                Value::Number(_) => Value::String(raw.get().to_string()),
+                value => value,
+            }
+        }
+        DataType::Struct(fields) => {
+            let Some(mut object): Option<HashMap<String, Box<RawValue>>> =
+                serde_json::from_str(raw.get()).map_err(parse_err)?
+            else {
+                return Ok(Value::Null);
+            };
+
+            let mut values = Map::new();
+            for field in fields {
+                if let Some(raw) = object.remove(field.name()) {
+                    values.insert(
+                        field.name().clone(),
+                        decimals_to_strings(field.data_type(), &raw)?,
+                    );
+                }
+            }
+            for (name, raw) in object {
+                values.insert(name, serde_json::from_str(raw.get()).map_err(parse_err)?);
+            }
+            Value::Object(values)
+        }
+        DataType::List(f) | DataType::LargeList(f) | DataType::FixedSizeList(f, _) => {
+            let Some(items): Option<Vec<Box<RawValue>>> =
+                serde_json::from_str(raw.get()).map_err(parse_err)?
+            else {
+                return Ok(Value::Null);
+            };
+
+            Value::Array(
+                items
+                    .iter()
+                    .map(|item| decimals_to_strings(f.data_type(), item))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        _ => serde_json::from_str(raw.get()).map_err(parse_err)?,
+    })
+}
+
+/// Serializes a record to JSON, applying the timestamp, decimal and field path options of the
+/// format
+pub fn to_value<T: Serialize>(
+    format: &JsonFormat,
+    fields: &Fields,
+    record: &T,
+) -> Result<Value, String> {
+    let mut value = if format.decimal_as_string {
+        // decimals are converted from the serialized text, as they would lose precision as the
+        // f64s that serde_json parses numbers into
+        let raw = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        let raw = RawValue::from_string(raw).map_err(|e| e.to_string())?;
+        decimals_to_strings(&DataType::Struct(fields.clone()), &raw)?
+    } else {
+        serde_json::to_value(record).map_err(|e| e.to_string())?
+    };
+
+    if needs_conversion(format) {
+        if let Value::Object(object) = &mut value {
+            for field in fields {
+                if let Some(value) = object.get_mut(field.name()) {
+                    write_value(format, field.data_type(), value)
+                        .map_err(|e| format!("field '{}': {}", field.name(), e))?;
+                }
+            }
+            write_field_paths(format, object);
+        }
+    }
+
+    Ok(value)
+}
+
 #[derive(Debug)]
 pub struct MilliSecondsSystemTimeVisitor;
 
@@ -217,6 +520,179 @@ pub mod opt_timestamp_as_rfc3339 {
     }
 }
 
+#[derive(Debug)]
+pub struct DecimalVisitor<const SCALE: i8>;
+
+impl<'de, const SCALE: i8> serde::de::Visitor<'de> for DecimalVisitor<SCALE> {
+    type Value = i128;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a decimal number or string")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        arroyo_types::parse_decimal(v, SCALE)
+            .ok_or_else(|| E::custom(format!("invalid decimal '{}' for scale {}", v, SCALE)))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        // the shortest representation that round-trips, which is never in exponent notation
+        self.visit_str(&v.to_string())
+    }
+}
+
+// Decimal columns are represented by their unscaled i128 value, and serialized as json numbers
+// with the scale of the column; as the scale is part of the type, these take it as a const
+// parameter and must be used with serialize_with and deserialize_with. Deserialization also
+// accepts decimal strings, which (unlike numbers) can be read without going through an f64.
+pub mod decimal {
+    use super::DecimalVisitor;
+    use serde::{Deserializer, Serialize, Serializer};
+    use serde_json::value::RawValue;
+
+    pub fn serialize<const SCALE: i8, S>(v: &i128, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        RawValue::from_string(arroyo_types::format_decimal(*v, SCALE))
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, const SCALE: i8, D>(deserializer: D) -> Result<i128, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DecimalVisitor::<SCALE>)
+    }
+}
+
+pub mod opt_decimal {
+    use super::DecimalVisitor;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    struct Decimal<const SCALE: i8>(i128);
+
+    impl<'de, const SCALE: i8> Deserialize<'de> for Decimal<SCALE> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer
+                .deserialize_any(DecimalVisitor::<SCALE>)
+                .map(Decimal)
+        }
+    }
+
+    pub fn serialize<const SCALE: i8, S>(v: &Option<i128>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match v {
+            Some(v) => super::decimal::serialize::<SCALE, S>(v, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, const SCALE: i8, D>(deserializer: D) -> Result<Option<i128>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<Decimal<SCALE>>::deserialize(deserializer)?.map(|d| d.0))
+    }
+}
+
+// Date columns are serialized as ISO 8601 dates, and can be deserialized from those or from a
+// number of days since the epoch (as avro encodes them)
+pub mod date {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use chrono::{DateTime, NaiveDate, Utc};
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S>(t: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let dt: DateTime<Utc> = (*t).into();
+        serializer.serialize_str(&dt.format("%Y-%m-%d").to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DateVisitor)
+    }
+
+    pub(super) struct DateVisitor;
+
+    impl<'de> de::Visitor<'de> for DateVisitor {
+        type Value = SystemTime;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a date string or a number of days since the epoch")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            let date = NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                .map_err(|e| E::custom(format!("invalid date '{}': {}", v, e)))?;
+            let days = date
+                .signed_duration_since(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap())
+                .num_days();
+            self.visit_i64(days)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+            u64::try_from(v)
+                .map(|days| UNIX_EPOCH + Duration::from_secs(days * 86_400))
+                .map_err(|_| E::custom(format!("date {} days before the epoch", -v)))
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+            Ok(UNIX_EPOCH + Duration::from_secs(v * 86_400))
+        }
+    }
+}
+
+pub mod opt_date {
+    use std::time::SystemTime;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    struct Date(SystemTime);
+
+    impl<'de> Deserialize<'de> for Date {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer
+                .deserialize_any(super::date::DateVisitor)
+                .map(Date)
+        }
+    }
+
+    pub fn serialize<S>(t: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match t {
+            Some(t) => super::date::serialize(t, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<SystemTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<Date>::deserialize(deserializer)?.map(|d| d.0))
+    }
+}
+
 pub fn field_to_json_schema(field: &Field) -> Value {
     match field.data_type() {
         arrow::datatypes::DataType::Null => {
@@ -244,10 +720,10 @@ pub fn field_to_json_schema(field: &Field) -> Value {
         arrow::datatypes::DataType::Timestamp(_, _) => {
             json! {{ "type": "string", "format": "date-time" }}
         }
-        arrow::datatypes::DataType::Date32
-        | arrow::datatypes::DataType::Date64
-        | arrow::datatypes::DataType::Time32(_)
-        | arrow::datatypes::DataType::Time64(_) => {
+        arrow::datatypes::DataType::Date32 | arrow::datatypes::DataType::Date64 => {
+            json! {{ "type": "string", "format": "date" }}
+        }
+        arrow::datatypes::DataType::Time32(_) | arrow::datatypes::DataType::Time64(_) => {
             todo!()
         }
         arrow::datatypes::DataType::Duration(_) => todo!(),
@@ -268,7 +744,9 @@ pub fn field_to_json_schema(field: &Field) -> Value {
         arrow::datatypes::DataType::Struct(s) => arrow_to_json_schema(s),
         arrow::datatypes::DataType::Union(_, _) => todo!(),
         arrow::datatypes::DataType::Dictionary(_, _) => todo!(),
-        arrow::datatypes::DataType::Decimal128(_, _) => todo!(),
+        arrow::datatypes::DataType::Decimal128(_, _) => {
+            json! {{ "type": "number" }}
+        }
         arrow::datatypes::DataType::Decimal256(_, _) => todo!(),
         arrow::datatypes::DataType::Map(_, _) => todo!(),
         arrow::datatypes::DataType::RunEndEncoded(_, _) => todo!(),
@@ -346,7 +824,7 @@ pub fn field_to_kafka_json(field: &Field) -> Value {
         }
         Union(_, _) => todo!(),
         Dictionary(_, _) => todo!(),
-        Decimal128(_, _) => todo!(),
+        Decimal128(_, _) => "double",
         Decimal256(_, _) => todo!(),
         Map(_, _) => todo!(),
         RunEndEncoded(_, _) => todo!(),
@@ -370,3 +848,168 @@ pub fn arrow_to_kafka_json(name: &str, fields: &Fields) -> Value {
         "optional": false,
     }}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::TimeUnit;
+    use arroyo_rpc::formats::JsonFieldPath;
+    use serde::Deserialize;
+    use std::time::{Duration, SystemTime};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Row {
+        user_id: i64,
+        price: f64,
+        #[serde(with = "crate::json::timestamp_as_rfc3339")]
+        ts: SystemTime,
+        #[serde(default, with = "crate::json::opt_timestamp_as_rfc3339")]
+        updated: Option<SystemTime>,
+    }
+
+    fn fields() -> Fields {
+        let ts = DataType::Timestamp(TimeUnit::Microsecond, None);
+        vec![
+            Field::new("user_id", DataType::Int64, false),
+            Field::new("price", DataType::Float64, false),
+            Field::new("ts", ts.clone(), false),
+            Field::new("updated", ts, true),
+        ]
+        .into()
+    }
+
+    fn format(timestamp_format: TimestampFormat) -> JsonFormat {
+        JsonFormat {
+            timestamp_format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        let expected = SystemTime::UNIX_EPOCH + Duration::from_millis(1696161600123);
+
+        for (timestamp_format, ts) in [
+            (TimestampFormat::UnixSeconds, "1696161600.123"),
+            (TimestampFormat::UnixMicros, "1696161600123000"),
+            (TimestampFormat::UnixNanos, "1696161600123000000"),
+            (
+                TimestampFormat::Custom("%Y/%m/%d %H:%M:%S%.3f".to_string()),
+                "\"2023/10/01 12:00:00.123\"",
+            ),
+        ] {
+            let format = format(timestamp_format);
+            let msg = format!(
+                "{{\"user_id\": 1, \"price\": 1.5, \"ts\": {}, \"updated\": null}}",
+                ts
+            );
+
+            let row: Row = deserialize_slice_json(&format, fields, msg.as_bytes()).unwrap();
+            assert_eq!(row.ts, expected, "{:?}", format.timestamp_format);
+            assert_eq!(row.updated, None);
+        }
+
+        let row = Row {
+            user_id: 1,
+            price: 1.5,
+            ts: expected,
+            updated: Some(expected),
+        };
+
+        let value = to_value(&format(TimestampFormat::UnixMicros), &fields(), &row).unwrap();
+        assert_eq!(value["ts"], json!(1696161600123000i64));
+        assert_eq!(value["updated"], json!(1696161600123000i64));
+
+        let format = format(TimestampFormat::Custom("%d.%m.%Y %H:%M".to_string()));
+        let value = to_value(&format, &fields(), &row).unwrap();
+        assert_eq!(value["ts"], json!("01.10.2023 12:00"));
+
+        assert!(deserialize_slice_json::<Row>(
+            &format,
+            fields,
+            b"{\"user_id\": 1, \"price\": 1.5, \"ts\": \"yesterday\"}"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_decimals_and_field_paths() {
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Order {
+            user_id: i64,
+            #[serde(
+                serialize_with = "crate::json::decimal::serialize::<2, _>",
+                deserialize_with = "crate::json::decimal::deserialize::<2, _>"
+            )]
+            price: i128,
+            #[serde(
+                serialize_with = "crate::json::opt_decimal::serialize::<2, _>",
+                deserialize_with = "crate::json::opt_decimal::deserialize::<2, _>"
+            )]
+            discount: Option<i128>,
+            weight: f64,
+            #[serde(with = "crate::json::timestamp_as_rfc3339")]
+            ts: SystemTime,
+        }
+
+        let fields = || -> Fields {
+            vec![
+                Field::new("user_id", DataType::Int64, false),
+                Field::new("price", DataType::Decimal128(20, 2), false),
+                Field::new("discount", DataType::Decimal128(20, 2), true),
+                Field::new("weight", DataType::Float64, false),
+                Field::new(
+                    "ts",
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    false,
+                ),
+            ]
+            .into()
+        };
+
+        let format = JsonFormat {
+            decimal_as_string: true,
+            field_paths: JsonFieldPath::parse_list(
+                "$.payload.user.id AS user_id, $.createdAt as ts",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let msg = br#"{"payload": {"user": {"id": 5}}, "price": "123456789012345678.25", "discount": null, "weight": 1.5, "createdAt": "2023-10-01T12:00:00Z"}"#;
+        let order: Order = deserialize_slice_json(&format, fields, msg).unwrap();
+        assert_eq!(
+            order,
+            Order {
+                user_id: 5,
+                price: 12345678901234567825,
+                discount: None,
+                weight: 1.5,
+                ts: SystemTime::UNIX_EPOCH + Duration::from_secs(1696161600),
+            }
+        );
+
+        // only decimal columns are written as strings, without going through an f64
+        let order = Order {
+            discount: Some(1050),
+            ..order
+        };
+        assert_eq!(
+            to_value(&format, &fields(), &order).unwrap(),
+            json!({
+                "payload": {"user": {"id": 5}},
+                "price": "123456789012345678.25",
+                "discount": "10.50",
+                "weight": 1.5,
+                "createdAt": "2023-10-01T12:00:00+00:00",
+            })
+        );
+
+        // floats are not read from strings
+        let msg = br#"{"payload": {"user": {"id": 5}}, "price": 1, "discount": null, "weight": "1.5", "createdAt": "2023-10-01T12:00:00Z"}"#;
+        assert!(deserialize_slice_json::<Order>(&format, fields, msg).is_err());
+
+        assert!(JsonFieldPath::parse_list("payload.id AS id").is_err());
+        assert!(JsonFieldPath::parse_list("$.payload..id AS id").is_err());
+    }
+}
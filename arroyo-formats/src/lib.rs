@@ -1,22 +1,26 @@
 extern crate core;
 
 use anyhow::bail;
-use arrow::datatypes::{DataType, Field, Schema};
+use arrow::datatypes::{DataType, Field, Fields, Schema};
 use arrow_array::cast::AsArray;
 use arrow_array::{RecordBatch, StringArray};
-use arroyo_rpc::formats::{AvroFormat, Format, Framing, FramingMethod};
+use arroyo_rpc::formats::{AvroFormat, CsvHeader, Endianness, Format, Framing, FramingMethod};
 use arroyo_rpc::schema_resolver::{FailingSchemaResolver, FixedSchemaResolver, SchemaResolver};
-use arroyo_types::{Data, Debezium, RawJson, SourceError};
-use serde::de::DeserializeOwned;
+use arroyo_types::{Data, Debezium, RawJson, SourceError, SourceMetadata, UserError};
+use serde::de::{self, DeserializeOwned, SeqAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::fmt;
 use std::marker::PhantomData;
 use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
 use tokio::sync::Mutex;
 
 pub mod avro;
+pub mod csv;
 pub mod json;
+pub mod proto;
 
 pub trait SchemaData: Data + Serialize + DeserializeOwned {
     fn name() -> &'static str;
@@ -36,18 +40,52 @@ pub trait SchemaData: Data + Serialize + DeserializeOwned {
     fn to_raw_string(&self) -> Option<Vec<u8>>;
 
     fn to_avro(&self, schema: &apache_avro::Schema) -> apache_avro::types::Value;
+
+    /// Returns true if the field is a metadata column, which is populated from the message
+    /// metadata by the source rather than read from the message itself
+    fn is_metadata_field(_name: &str) -> bool {
+        false
+    }
+
+    /// Sets the metadata columns of this record from the metadata of the message it was read from
+    fn set_metadata(&mut self, _metadata: &dyn SourceMetadata) {}
 }
 
+/// Returns the fields of a debezium envelope for rows with the given fields
+pub fn debezium_fields(row: &Fields) -> Vec<Field> {
+    vec![
+        Field::new(
+            "before",
+            arrow::datatypes::DataType::Struct(row.clone()),
+            true,
+        ),
+        Field::new(
+            "after",
+            arrow::datatypes::DataType::Struct(row.clone()),
+            true,
+        ),
+        Field::new("op", arrow::datatypes::DataType::Utf8, false),
+    ]
+}
+
+/// Returns the record schema for the before/after fields of a debezium envelope, which are
+/// nullable and so represented as unions
 fn get_subschema<'a>(schema: &'a apache_avro::Schema, field: &str) -> &'a apache_avro::Schema {
     let apache_avro::schema::Schema::Record(record_schmema) = schema else {
         unreachable!();
     };
 
-    let Some(idx) = record_schmema.lookup.get("before") else {
+    let Some(idx) = record_schmema.lookup.get(field) else {
         panic!("field {} not found in avro schema", field);
     };
 
-    &record_schmema.fields[*idx].schema
+    match &record_schmema.fields[*idx].schema {
+        apache_avro::Schema::Union(union) => union
+            .variants()
+            .get(1)
+            .unwrap_or_else(|| panic!("invalid avro schema for debezium field {}", field)),
+        schema => schema,
+    }
 }
 
 impl<T: SchemaData> SchemaData for Debezium<T> {
@@ -56,23 +94,7 @@ impl<T: SchemaData> SchemaData for Debezium<T> {
     }
 
     fn schema() -> arrow::datatypes::Schema {
-        let subschema = T::schema();
-
-        let fields = vec![
-            Field::new(
-                "before",
-                arrow::datatypes::DataType::Struct(subschema.fields.clone()),
-                true,
-            ),
-            Field::new(
-                "after",
-                arrow::datatypes::DataType::Struct(subschema.fields),
-                true,
-            ),
-            Field::new("op", arrow::datatypes::DataType::Utf8, false),
-        ];
-
-        arrow::datatypes::Schema::new(fields)
+        arrow::datatypes::Schema::new(debezium_fields(T::schema().fields()))
     }
 
     fn to_raw_string(&self) -> Option<Vec<u8>> {
@@ -80,14 +102,15 @@ impl<T: SchemaData> SchemaData for Debezium<T> {
     }
 
     fn to_avro(&self, schema: &apache_avro::Schema) -> apache_avro::types::Value {
-        let mut record = apache_avro::types::Record::new(schema).unwrap();
-        if let Some(before) = &self.before {
-            record.put("before", before.to_avro(get_subschema(schema, "before")));
-        }
+        use apache_avro::types::Value;
+        let nullable = |v: &Option<T>, field: &str| match v {
+            Some(v) => Value::Union(1, Box::new(v.to_avro(get_subschema(schema, field)))),
+            None => Value::Union(0, Box::new(Value::Null)),
+        };
 
-        if let Some(after) = &self.after {
-            record.put("after", after.to_avro(get_subschema(schema, "after")));
-        }
+        let mut record = apache_avro::types::Record::new(schema).unwrap();
+        record.put("before", nullable(&self.before, "before"));
+        record.put("after", nullable(&self.after, "after"));
 
         record.put("op", apache_avro::types::Value::String(self.op.to_string()));
 
@@ -190,6 +213,66 @@ where
     Ok(Some(raw.to_string()))
 }
 
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of bytes or a string with one character per byte")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.chars()
+            .map(|c| {
+                u8::try_from(c)
+                    .map_err(|_| E::custom(format!("'{}' is not a valid byte character", c)))
+            })
+            .collect()
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(b) = seq.next_element()? {
+            bytes.push(b);
+        }
+        Ok(bytes)
+    }
+}
+
+struct Bytes(Vec<u8>);
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(f: D) -> Result<Self, D::Error> {
+        f.deserialize_any(BytesVisitor).map(Bytes)
+    }
+}
+
+// A custom deserializer for binary fields, which accepts both the serde representation of a
+// Vec<u8> (an array of numbers) and the avro json encoding of bytes (a string with one character
+// per byte)
+pub fn deserialize_bytes<'de, D>(f: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Bytes::deserialize(f)?.0)
+}
+
+pub fn deserialize_bytes_opt<'de, D>(f: D) -> Result<Option<Vec<u8>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Bytes>::deserialize(f)?.map(|b| b.0))
+}
+
 fn deserialize_raw_string<T: DeserializeOwned>(msg: &[u8]) -> Result<T, String> {
     let json = json! {
         { "value": String::from_utf8_lossy(msg) }
@@ -197,6 +280,50 @@ fn deserialize_raw_string<T: DeserializeOwned>(msg: &[u8]) -> Result<T, String>
     Ok(serde_json::from_value(json).unwrap())
 }
 
+fn read_length_prefix(prefix: &[u8], endianness: Endianness) -> u64 {
+    let fold = |acc: u64, b: &u8| (acc << 8) | *b as u64;
+    match endianness {
+        Endianness::Big => prefix.iter().fold(0, fold),
+        Endianness::Little => prefix.iter().rev().fold(0, fold),
+    }
+}
+
+fn frame(framing: &Framing, mut buf: Vec<u8>) -> Result<Vec<u8>, UserError> {
+    Ok(match &framing.method {
+        FramingMethod::Newline(_) => {
+            buf.push(b'\n');
+            buf
+        }
+        FramingMethod::Delimiter(delimiter) => {
+            buf.extend_from_slice(&delimiter.bytes);
+            buf
+        }
+        FramingMethod::LengthPrefixed(prefixed) => {
+            let width = prefixed.width as usize;
+            let length = buf.len() as u64;
+            if width < 8 && length >> (8 * width) != 0 {
+                return Err(UserError::new(
+                    "record too large for length prefix",
+                    format!(
+                        "record of {} bytes is too large for a {}-byte length prefix",
+                        length, width
+                    ),
+                ));
+            }
+
+            let prefix = match prefixed.endianness {
+                Endianness::Big => length.to_be_bytes()[8 - width..].to_vec(),
+                Endianness::Little => length.to_le_bytes()[..width].to_vec(),
+            };
+
+            let mut framed = Vec::with_capacity(width + buf.len());
+            framed.extend(prefix);
+            framed.extend(buf);
+            framed
+        }
+    })
+}
+
 pub struct FramingIterator<'a> {
     framing: Option<Arc<Framing>>,
     buf: &'a [u8],
@@ -238,6 +365,41 @@ impl<'a> Iterator for FramingIterator<'a> {
 
                         Some(&self.buf[prev..(prev + length)])
                     }
+                    FramingMethod::LengthPrefixed(prefixed) => {
+                        let width = prefixed.width as usize;
+                        let remaining = &self.buf[self.offset..];
+
+                        if remaining.len() < width {
+                            // there isn't a full length prefix left; return the remainder so
+                            // that it's reported as bad data
+                            self.offset = self.buf.len();
+                            return Some(remaining);
+                        }
+
+                        let length = read_length_prefix(&remaining[..width], prefixed.endianness);
+                        let start = self.offset + width;
+                        let end = start
+                            .saturating_add(usize::try_from(length).unwrap_or(usize::MAX))
+                            .min(self.buf.len());
+
+                        self.offset = end;
+                        Some(&self.buf[start..end])
+                    }
+                    FramingMethod::Delimiter(delimiter) => {
+                        let prev = self.offset;
+                        let remaining = &self.buf[prev..];
+
+                        match memchr::memmem::find(remaining, &delimiter.bytes) {
+                            Some(i) => {
+                                self.offset = prev + i + delimiter.bytes.len();
+                                Some(&remaining[..i])
+                            }
+                            None => {
+                                self.offset = self.buf.len();
+                                Some(remaining)
+                            }
+                        }
+                    }
                 }
             }
             None => {
@@ -248,12 +410,92 @@ impl<'a> Iterator for FramingIterator<'a> {
     }
 }
 
+/// Splits a stream into the records of a framing as it is read, rather than requiring all of it
+/// to be in memory as [FramingIterator] does
+pub struct FrameReader<R> {
+    framing: Arc<Framing>,
+    reader: R,
+}
+
+impl<R: AsyncBufRead + Unpin> FrameReader<R> {
+    pub fn new(framing: Arc<Framing>, reader: R) -> Self {
+        Self { framing, reader }
+    }
+
+    /// Reads the next record, without its framing, returning None at the end of the stream
+    pub async fn next_frame(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        let mut buf = vec![];
+
+        match &self.framing.method {
+            FramingMethod::Newline(newline) => {
+                if self.reader.read_until(b'\n', &mut buf).await? == 0 {
+                    return Ok(None);
+                }
+                if buf.last() == Some(&b'\n') {
+                    buf.pop();
+                }
+                if let Some(max) = newline.max_line_length {
+                    buf.truncate(max as usize);
+                }
+            }
+            FramingMethod::LengthPrefixed(prefixed) => {
+                let mut prefix = vec![0; prefixed.width as usize];
+                let mut filled = 0;
+                while filled < prefix.len() {
+                    match self.reader.read(&mut prefix[filled..]).await? {
+                        0 => break,
+                        n => filled += n,
+                    }
+                }
+
+                if filled == 0 {
+                    return Ok(None);
+                }
+                if filled < prefix.len() {
+                    // there isn't a full length prefix left; return the remainder so that it's
+                    // reported as bad data
+                    prefix.truncate(filled);
+                    return Ok(Some(prefix));
+                }
+
+                let length = read_length_prefix(&prefix, prefixed.endianness);
+                (&mut self.reader)
+                    .take(length)
+                    .read_to_end(&mut buf)
+                    .await?;
+            }
+            FramingMethod::Delimiter(delimiter) => {
+                let last = *delimiter
+                    .bytes
+                    .last()
+                    .expect("delimiters must not be empty");
+                loop {
+                    if self.reader.read_until(last, &mut buf).await? == 0 {
+                        if buf.is_empty() {
+                            return Ok(None);
+                        }
+                        break;
+                    }
+                    if buf.ends_with(&delimiter.bytes) {
+                        buf.truncate(buf.len() - delimiter.bytes.len());
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(Some(buf))
+    }
+}
+
 #[derive(Clone)]
 pub struct DataDeserializer<T: SchemaData> {
     format: Arc<Format>,
     framing: Option<Arc<Framing>>,
     schema_registry: Arc<Mutex<HashMap<u32, apache_avro::schema::Schema>>>,
+    proto_registry: Arc<Mutex<HashMap<u32, prost_reflect::DescriptorPool>>>,
     schema_resolver: Arc<dyn SchemaResolver + Sync>,
+    csv_headers: Option<Arc<Vec<String>>>,
     _t: PhantomData<T>,
 }
 
@@ -282,15 +524,29 @@ impl<T: SchemaData> DataDeserializer<T> {
             format: Arc::new(format),
             framing: framing.map(|f| Arc::new(f)),
             schema_registry: Arc::new(Mutex::new(HashMap::new())),
+            proto_registry: Arc::new(Mutex::new(HashMap::new())),
             schema_resolver,
+            csv_headers: None,
             _t: PhantomData,
         }
     }
 
+    /// Sets the column names used to map CSV records onto fields, as read from a header row
+    pub fn set_csv_headers(&mut self, headers: Vec<String>) {
+        self.csv_headers = Some(Arc::new(headers));
+    }
+
     pub async fn deserialize_slice<'a>(
         &mut self,
         msg: &'a [u8],
     ) -> impl Iterator<Item = Result<T, SourceError>> + 'a + Send {
+        if msg.is_empty() && self.format.is_updating() {
+            // debezium emits empty tombstone records after deletes to allow compaction, which
+            // don't carry any change data
+            return Box::new(std::iter::empty())
+                as Box<dyn Iterator<Item = Result<T, SourceError>> + Send>;
+        }
+
         match &*self.format {
             Format::Avro(avro) => {
                 let schema_registry = self.schema_registry.clone();
@@ -298,7 +554,7 @@ impl<T: SchemaData> DataDeserializer<T> {
                 match avro::deserialize_slice_avro(avro, schema_registry, schema_resolver, msg)
                     .await
                 {
-                    Ok(iter) => Box::new(iter),
+                    Ok(iter) => Box::new(iter.map(move |r| r.map_err(|e| e.with_raw(msg)))),
                     Err(e) => Box::new(
                         vec![Err(SourceError::other(
                             "Avro error",
@@ -309,6 +565,23 @@ impl<T: SchemaData> DataDeserializer<T> {
                         as Box<dyn Iterator<Item = Result<T, SourceError>> + Send>,
                 }
             }
+            Format::Protobuf(proto) => {
+                let proto_registry = self.proto_registry.clone();
+                let schema_resolver = self.schema_resolver.clone();
+                match proto::deserialize_slice_proto(proto, proto_registry, schema_resolver, msg)
+                    .await
+                {
+                    Ok(iter) => Box::new(iter.map(move |r| r.map_err(|e| e.with_raw(msg)))),
+                    Err(e) => Box::new(
+                        vec![Err(SourceError::other(
+                            "Protobuf error",
+                            format!("Protobuf deserialization failed: {}", e),
+                        ))]
+                        .into_iter(),
+                    )
+                        as Box<dyn Iterator<Item = Result<T, SourceError>> + Send>,
+                }
+            }
             _ => {
                 let new_self = self.clone();
                 Box::new(
@@ -319,18 +592,50 @@ impl<T: SchemaData> DataDeserializer<T> {
         }
     }
 
+    /// Deserializes a record read by a [FrameReader], which has already had its framing removed
+    pub async fn deserialize_frame(&mut self, frame: &[u8]) -> Vec<Result<T, SourceError>> {
+        match &*self.format {
+            Format::Avro(_) | Format::Protobuf(_) => self.deserialize_slice(frame).await.collect(),
+            _ if frame.is_empty() && self.format.is_updating() => vec![],
+            _ => vec![self.deserialize_single(frame)],
+        }
+    }
+
     pub fn get_format(&self) -> Arc<Format> {
         self.format.clone()
     }
 
+    pub fn get_framing(&self) -> Option<Arc<Framing>> {
+        self.framing.clone()
+    }
+
     pub fn deserialize_single(&self, msg: &[u8]) -> Result<T, SourceError> {
         match &*self.format {
-            Format::Json(json) => json::deserialize_slice_json(json, msg),
+            Format::Json(json) => {
+                json::deserialize_slice_json(json, || T::schema().fields().clone(), msg)
+            }
             Format::Avro(_) => unreachable!("avro should be handled by here"),
+            Format::Protobuf(_) => unreachable!("protobuf should be handled by here"),
             Format::Parquet(_) => todo!("parquet is not supported as an input format"),
             Format::RawString(_) => deserialize_raw_string(msg),
+            Format::Csv(csv) => {
+                // metadata columns don't appear in the record, so they must not take up a position
+                let fields: Fields = T::schema()
+                    .fields()
+                    .iter()
+                    .filter(|f| !T::is_metadata_field(f.name()))
+                    .cloned()
+                    .collect();
+
+                csv::deserialize_slice_csv(
+                    csv,
+                    &fields,
+                    self.csv_headers.as_ref().map(|h| h.as_slice()),
+                    msg,
+                )
+            }
         }
-        .map_err(|e| SourceError::bad_data(format!("Failed to deserialize: {:?}", e)))
+        .map_err(|e| SourceError::bad_data(format!("Failed to deserialize: {:?}", e)).with_raw(msg))
     }
 }
 
@@ -338,29 +643,67 @@ pub struct DataSerializer<T: SchemaData> {
     kafka_schema: Value,
     #[allow(unused)]
     json_schema: Value,
-    avro_schema: apache_avro::schema::Schema,
+    avro_schema: Option<apache_avro::schema::Schema>,
+    proto_encoder: Option<Result<proto::ProtobufEncoder, UserError>>,
     schema_id: Option<u32>,
     format: Format,
+    framing: Option<Framing>,
     _t: PhantomData<T>,
 }
 
 impl<T: SchemaData> DataSerializer<T> {
-    pub fn new(format: Format) -> Self {
+    pub fn new(format: Format, framing: Option<Framing>) -> Self {
         Self {
             kafka_schema: json::arrow_to_kafka_json(T::name(), T::schema().fields()),
             json_schema: json::arrow_to_json_schema(T::schema().fields()),
-            avro_schema: avro::arrow_to_avro_schema(T::name(), T::schema().fields()),
+            avro_schema: match &format {
+                // unsupported types are rejected during planning, so this should not fail
+                Format::Avro(_) => Some(
+                    avro::arrow_to_avro_schema(T::name(), T::schema().fields())
+                        .expect("could not construct avro schema"),
+                ),
+                _ => None,
+            },
+            // errors constructing the encoder are surfaced when serializing, where the sink
+            // is able to report them
+            proto_encoder: match &format {
+                Format::Protobuf(proto) => Some(
+                    proto::ProtobufEncoder::new(proto, T::name(), T::schema().fields()).map_err(
+                        |e| UserError::new("could not construct protobuf schema", e.to_string()),
+                    ),
+                ),
+                _ => None,
+            },
             schema_id: match &format {
                 Format::Avro(avro) => avro.schema_id,
                 _ => None,
             },
             format,
+            framing,
             _t: PhantomData,
         }
     }
 
-    pub fn to_vec(&self, record: &T) -> Option<Vec<u8>> {
-        match &self.format {
+    /// Returns true if framing has been configured, in which case serialized records already
+    /// include their separators
+    pub fn has_framing(&self) -> bool {
+        self.framing.is_some()
+    }
+
+    /// Serializes a record, returning None if the record produces no output (as for a
+    /// null raw string) or an error if it cannot be represented in the sink's format
+    pub fn to_vec(&self, record: &T) -> Result<Option<Vec<u8>>, UserError> {
+        let Some(buf) = self.serialize(record)? else {
+            return Ok(None);
+        };
+        Ok(Some(match &self.framing {
+            Some(framing) => frame(framing, buf)?,
+            None => buf,
+        }))
+    }
+
+    fn serialize(&self, record: &T) -> Result<Option<Vec<u8>>, UserError> {
+        Ok(match &self.format {
             Format::Json(json) => {
                 let mut writer: Vec<u8> = Vec::with_capacity(128);
                 if json.confluent_schema_registry {
@@ -370,29 +713,63 @@ impl<T: SchemaData> DataSerializer<T> {
                     writer.push(0);
                     writer.extend(json.schema_id.expect("must have computed id version to write using confluent schema registry").to_be_bytes());
                 }
+                let to_value = || {
+                    json::to_value(json, T::schema().fields(), record)
+                        .map_err(|e| UserError::new("could not serialize record as JSON", e))
+                };
                 if json.include_schema {
                     let record = json! {{
                         "schema": self.kafka_schema,
-                        "payload": record
+                        "payload": to_value()?
                     }};
 
                     serde_json::to_writer(&mut writer, &record).unwrap();
+                } else if json::needs_conversion(json) {
+                    serde_json::to_writer(&mut writer, &to_value()?).unwrap();
                 } else {
                     serde_json::to_writer(&mut writer, record).unwrap();
                 };
                 Some(writer)
             }
-            Format::Avro(f) => Some(avro::to_vec(record, f, &self.avro_schema, self.schema_id)),
+            Format::Avro(f) => Some(avro::to_vec(
+                record,
+                f,
+                self.avro_schema.as_ref().unwrap(),
+                self.schema_id,
+            )),
             Format::Parquet(_) => todo!(),
             Format::RawString(_) => record.to_raw_string(),
+            Format::Protobuf(f) => {
+                let encoder = self
+                    .proto_encoder
+                    .as_ref()
+                    .expect("protobuf encoder must be constructed for protobuf format")
+                    .as_ref()
+                    .map_err(|e| e.clone())?;
+                Some(proto::to_vec(record, f, encoder)?)
+            }
+            Format::Csv(f) => Some(csv::to_vec(record, f, T::schema().fields())),
+        })
+    }
+
+    /// Returns the header row that should start each file, if the format has one
+    pub fn header(&self) -> Option<Vec<u8>> {
+        match &self.format {
+            Format::Csv(f) if f.header != CsvHeader::None => {
+                Some(csv::header_row(f, T::schema().fields()))
+            }
+            _ => None,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::FramingIterator;
-    use arroyo_rpc::formats::{Framing, FramingMethod, NewlineDelimitedFraming};
+    use crate::{frame, FrameReader, FramingIterator};
+    use arroyo_rpc::formats::{
+        DelimiterFraming, Endianness, Framing, FramingMethod, LengthPrefixedFraming,
+        NewlineDelimitedFraming,
+    };
     use std::sync::Arc;
 
     #[test]
@@ -464,4 +841,114 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn test_length_prefixed_framing() {
+        let framing = Framing {
+            method: FramingMethod::LengthPrefixed(LengthPrefixedFraming {
+                width: 4,
+                endianness: Endianness::Big,
+            }),
+        };
+
+        let mut buf = vec![];
+        for record in ["one", "", "three"] {
+            buf.extend(frame(&framing, record.as_bytes().to_vec()).unwrap());
+        }
+
+        assert_eq!(&buf[..7], &[0, 0, 0, 3, b'o', b'n', b'e']);
+
+        let result: Vec<_> = FramingIterator::new(Some(Arc::new(framing)), &buf)
+            .map(|t| String::from_utf8(t.to_vec()).unwrap())
+            .collect();
+
+        assert_eq!(
+            vec!["one".to_string(), "".to_string(), "three".to_string()],
+            result
+        );
+
+        let framing = Framing {
+            method: FramingMethod::LengthPrefixed(LengthPrefixedFraming {
+                width: 2,
+                endianness: Endianness::Little,
+            }),
+        };
+
+        let buf = frame(&framing, vec![7; 300]).unwrap();
+        assert_eq!(&buf[..2], &[44, 1]);
+
+        // records that don't fit in the prefix are errors, rather than being truncated
+        assert!(frame(&framing, vec![7; 1 << 16]).is_err());
+
+        let result: Vec<_> = FramingIterator::new(Some(Arc::new(framing)), &buf).collect();
+        assert_eq!(result, vec![&[7; 300][..]]);
+    }
+
+    #[test]
+    fn test_delimiter_framing() {
+        let framing = Framing {
+            method: FramingMethod::Delimiter(DelimiterFraming {
+                bytes: b"\r\n".to_vec(),
+            }),
+        };
+
+        let mut buf = vec![];
+        for record in ["one\n", "two", "three"] {
+            buf.extend(frame(&framing, record.as_bytes().to_vec()).unwrap());
+        }
+
+        let result: Vec<_> = FramingIterator::new(Some(Arc::new(framing.clone())), &buf)
+            .map(|t| String::from_utf8(t.to_vec()).unwrap())
+            .collect();
+
+        assert_eq!(
+            vec!["one\n".to_string(), "two".to_string(), "three".to_string()],
+            result
+        );
+
+        let result: Vec<_> = FramingIterator::new(Some(Arc::new(framing)), "a\r\nb".as_bytes())
+            .map(|t| String::from_utf8(t.to_vec()).unwrap())
+            .collect();
+
+        assert_eq!(vec!["a".to_string(), "b".to_string()], result);
+    }
+
+    #[tokio::test]
+    async fn test_frame_reader() {
+        let framings = [
+            FramingMethod::Newline(NewlineDelimitedFraming {
+                max_line_length: Some(4),
+            }),
+            FramingMethod::LengthPrefixed(LengthPrefixedFraming {
+                width: 2,
+                endianness: Endianness::Little,
+            }),
+            FramingMethod::Delimiter(DelimiterFraming {
+                bytes: b"\r\n".to_vec(),
+            }),
+        ];
+
+        for method in framings {
+            let framing = Arc::new(Framing { method });
+            let mut buf = vec![];
+            for record in ["one", "", "three", "fou\rr\n"] {
+                buf.extend(frame(&framing, record.as_bytes().to_vec()).unwrap());
+            }
+
+            // including streams that end part way through a record
+            for len in [buf.len(), buf.len() - 1, 1, 0] {
+                let expected: Vec<_> = FramingIterator::new(Some(framing.clone()), &buf[..len])
+                    .map(|t| t.to_vec())
+                    .collect();
+
+                let mut reader = FrameReader::new(framing.clone(), &buf[..len]);
+                let mut frames = vec![];
+                while let Some(frame) = reader.next_frame().await.unwrap() {
+                    frames.push(frame);
+                }
+
+                assert_eq!(frames, expected, "{:?} ({} bytes)", framing, len);
+            }
+        }
+    }
 }
@@ -0,0 +1,593 @@
+use crate::SchemaData;
+use anyhow::{anyhow, bail};
+use arrow::datatypes::{DataType, Fields};
+use arroyo_rpc::formats::ProtobufFormat;
+use arroyo_rpc::schema_resolver::SchemaResolver;
+use arroyo_types::{SourceError, UserError};
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor, SerializeOptions,
+};
+use protox::file::{ChainFileResolver, File, FileResolver, GoogleFileResolver};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// The name under which user-provided schemas are compiled; imports of the well-known
+/// google/protobuf types are resolved separately
+pub const SCHEMA_FILE_NAME: &str = "arroyo_schema.proto";
+
+struct InlineFileResolver {
+    schema: String,
+}
+
+impl FileResolver for InlineFileResolver {
+    fn open_file(&self, name: &str) -> Result<File, protox::Error> {
+        if name == SCHEMA_FILE_NAME {
+            File::from_source(name, &self.schema)
+        } else {
+            Err(protox::Error::file_not_found(name))
+        }
+    }
+}
+
+/// Compiles a .proto schema into an encoded FileDescriptorSet, which can be stored in a
+/// [`ProtobufFormat`] and later loaded with [`get_pool`]
+pub fn compile_schema(schema: &str) -> anyhow::Result<Vec<u8>> {
+    let mut resolver = ChainFileResolver::new();
+    resolver.add(InlineFileResolver {
+        schema: schema.to_string(),
+    });
+    resolver.add(GoogleFileResolver::new());
+
+    let mut compiler = protox::Compiler::with_file_resolver(resolver);
+    compiler.include_imports(true);
+    compiler
+        .open_file(SCHEMA_FILE_NAME)
+        .map_err(|e| anyhow!("invalid protobuf schema: {}", e))?;
+
+    Ok(compiler.encode_file_descriptor_set())
+}
+
+pub fn get_pool(compiled_schema: &[u8]) -> anyhow::Result<DescriptorPool> {
+    DescriptorPool::decode(compiled_schema)
+        .map_err(|e| anyhow!("invalid protobuf descriptor: {}", e))
+}
+
+/// Finds the message that should be used to decode data, either by name, or by taking
+/// the first message defined in the schema file
+pub fn get_message_descriptor(
+    pool: &DescriptorPool,
+    message_name: Option<&str>,
+) -> anyhow::Result<MessageDescriptor> {
+    match message_name {
+        Some(name) => pool
+            .get_message_by_name(name)
+            .ok_or_else(|| anyhow!("message '{}' not found in protobuf schema", name)),
+        None => pool
+            .get_file_by_name(SCHEMA_FILE_NAME)
+            .and_then(|f| f.messages().next())
+            .ok_or_else(|| anyhow!("protobuf schema does not define any messages")),
+    }
+}
+
+/// Looks up a message by the message indexes used in the Confluent Schema Registry wire format,
+/// where the first index is into the top-level messages of the file, and each subsequent index
+/// is into the nested messages of the previous one
+fn message_for_indexes(
+    pool: &DescriptorPool,
+    indexes: &[usize],
+) -> Result<MessageDescriptor, String> {
+    let file = pool
+        .get_file_by_name(SCHEMA_FILE_NAME)
+        .ok_or_else(|| "schema registry schema was not compiled correctly".to_string())?;
+
+    let (first, rest) = indexes
+        .split_first()
+        .ok_or_else(|| "message indexes are empty".to_string())?;
+
+    let mut message = file
+        .messages()
+        .nth(*first)
+        .ok_or_else(|| format!("no message with index {} in schema", first))?;
+
+    for idx in rest {
+        let child = message
+            .child_messages()
+            .nth(*idx)
+            .ok_or_else(|| format!("no nested message with index {} in schema", idx))?;
+        message = child;
+    }
+
+    Ok(message)
+}
+
+fn read_varint(msg: &mut &[u8]) -> Result<i64, String> {
+    let mut value: u64 = 0;
+    for i in 0..10 {
+        let Some((b, rest)) = msg.split_first() else {
+            return Err("unexpected end of message while reading message indexes".to_string());
+        };
+        *msg = rest;
+        value |= ((b & 0x7f) as u64) << (7 * i);
+        if b & 0x80 == 0 {
+            // message indexes are zig-zag encoded
+            return Ok(((value >> 1) as i64) ^ -((value & 1) as i64));
+        }
+    }
+
+    Err("invalid varint in message indexes".to_string())
+}
+
+fn read_message_indexes(msg: &mut &[u8]) -> Result<Vec<usize>, String> {
+    let count = read_varint(msg)?;
+    if count == 0 {
+        // the common case of the first message in the file is encoded as a single 0
+        return Ok(vec![0]);
+    }
+
+    (0..count)
+        .map(|_| {
+            let idx = read_varint(msg)?;
+            usize::try_from(idx).map_err(|_| format!("invalid message index {}", idx))
+        })
+        .collect()
+}
+
+fn write_varint(buf: &mut Vec<u8>, value: i64) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    loop {
+        if value < 0x80 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push(((value & 0x7f) | 0x80) as u8);
+        value >>= 7;
+    }
+}
+
+fn message_indexes(message: &MessageDescriptor) -> Vec<usize> {
+    let mut indexes = vec![];
+    let mut current = message.clone();
+    loop {
+        match current.parent_message() {
+            Some(parent) => {
+                indexes.push(
+                    parent
+                        .child_messages()
+                        .position(|m| m == current)
+                        .expect("message must be a child of its parent"),
+                );
+                current = parent;
+            }
+            None => {
+                indexes.push(
+                    current
+                        .parent_file()
+                        .messages()
+                        .position(|m| m == current)
+                        .expect("message must be in its file"),
+                );
+                break;
+            }
+        }
+    }
+    indexes.reverse();
+    indexes
+}
+
+fn serialize_options() -> SerializeOptions {
+    SerializeOptions::new()
+        .stringify_64_bit_integers(false)
+        .use_proto_field_name(true)
+        .skip_default_fields(false)
+}
+
+pub async fn deserialize_slice_proto<'a, T: DeserializeOwned + 'a>(
+    format: &ProtobufFormat,
+    schema_registry: Arc<Mutex<HashMap<u32, DescriptorPool>>>,
+    resolver: Arc<dyn SchemaResolver + Sync>,
+    mut msg: &'a [u8],
+) -> Result<impl Iterator<Item = Result<T, SourceError>> + 'a, String> {
+    let (id, indexes) = if format.confluent_schema_registry {
+        if msg.len() < 5 {
+            return Err(
+                "message is too short to be encoded with the schema registry wire format"
+                    .to_string(),
+            );
+        }
+
+        let magic_byte = msg[0];
+        if magic_byte != 0 {
+            return Err(format!("data was not encoded with schema registry wire format; magic byte has unexpected value: {}", magic_byte));
+        }
+
+        let id = u32::from_be_bytes([msg[1], msg[2], msg[3], msg[4]]);
+        msg = &msg[5..];
+
+        (id, Some(read_message_indexes(&mut msg)?))
+    } else {
+        // without the schema registry, the compiled schema from the format is cached under id 0
+        (0, None)
+    };
+
+    let mut registry = schema_registry.lock().await;
+
+    if !registry.contains_key(&id) {
+        let compiled_schema = if format.confluent_schema_registry {
+            let new_schema = resolver
+                .resolve_schema(id)
+                .await?
+                .ok_or_else(|| format!("could not resolve schema for message with id {}", id))?;
+
+            info!(
+                "Loaded new protobuf schema with id {} from Schema Registry",
+                id
+            );
+
+            compile_schema(&new_schema).map_err(|e| {
+                format!(
+                    "schema from Confluent Schema registry is not valid: {:?}",
+                    e
+                )
+            })?
+        } else {
+            format
+                .compiled_schema
+                .clone()
+                .ok_or_else(|| "no protobuf schema configured for this source".to_string())?
+        };
+
+        registry.insert(id, get_pool(&compiled_schema).map_err(|e| e.to_string())?);
+    }
+
+    let pool = registry.get(&id).unwrap();
+
+    let descriptor = match indexes {
+        Some(indexes) => message_for_indexes(pool, &indexes)?,
+        None => get_message_descriptor(pool, format.message_name.as_deref())
+            .map_err(|e| e.to_string())?,
+    };
+
+    let into_json = format.into_unstructured_json;
+    let result = DynamicMessage::decode(descriptor, msg)
+        .map_err(|e| SourceError::bad_data(format!("Failed to deserialize from protobuf: {:?}", e)))
+        .and_then(|message| {
+            let value = message
+                .serialize_with_options(serde_json::value::Serializer, &serialize_options())
+                .map_err(|e| {
+                    SourceError::bad_data(format!(
+                        "Failed to convert protobuf message to json: {:?}",
+                        e
+                    ))
+                })?;
+
+            if into_json {
+                Ok(serde_json::from_value(json!({"value": value.to_string()})).unwrap())
+            } else {
+                // as with avro, we round-trip through json so that we can rely on the
+                // generated serde deserializers
+                serde_json::from_value(value).map_err(|e| {
+                    SourceError::bad_data(format!(
+                        "Failed to convert protobuf message into struct type: {:?}",
+                        e
+                    ))
+                })
+            }
+        });
+
+    Ok(std::iter::once(result))
+}
+
+pub fn sanitize_field(s: &str) -> String {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"[^a-zA-Z0-9_]").unwrap());
+
+    let s = re.replace_all(s, "_");
+    if s.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", s)
+    } else {
+        s.to_string()
+    }
+}
+
+fn sanitize_keys(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(o) => JsonValue::Object(
+            o.into_iter()
+                .map(|(k, v)| (sanitize_field(&k), sanitize_keys(v)))
+                .collect(),
+        ),
+        JsonValue::Array(a) => JsonValue::Array(a.into_iter().map(sanitize_keys).collect()),
+        v => v,
+    }
+}
+
+fn arrow_to_protobuf_type(
+    name: &str,
+    dt: &DataType,
+    nested: &mut Vec<String>,
+) -> anyhow::Result<String> {
+    Ok(match dt {
+        DataType::Boolean => "bool".to_string(),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 => "int32".to_string(),
+        DataType::Int64 => "int64".to_string(),
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 => "uint32".to_string(),
+        DataType::UInt64 => "uint64".to_string(),
+        DataType::Float16 | DataType::Float32 => "float".to_string(),
+        DataType::Float64 => "double".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 => "string".to_string(),
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => {
+            "bytes".to_string()
+        }
+        DataType::Timestamp(_, _) | DataType::Date32 | DataType::Date64 => {
+            "google.protobuf.Timestamp".to_string()
+        }
+        DataType::Struct(fields) => {
+            let message_name = sanitize_field(name);
+            nested.push(message_def(&message_name, fields)?);
+            message_name
+        }
+        dt => bail!("data type {:?} cannot be written as protobuf", dt),
+    })
+}
+
+fn message_def(name: &str, fields: &Fields) -> anyhow::Result<String> {
+    let mut nested = vec![];
+    let mut lines = vec![];
+
+    for (i, field) in fields.iter().enumerate() {
+        let field_name = sanitize_field(field.name());
+        let (label, typ) = match field.data_type() {
+            DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+                if matches!(
+                    item.data_type(),
+                    DataType::List(_) | DataType::LargeList(_) | DataType::FixedSizeList(_, _)
+                ) {
+                    bail!("nested lists cannot be written as protobuf");
+                }
+                (
+                    "repeated ",
+                    arrow_to_protobuf_type(
+                        &format!("{}_{}", name, field_name),
+                        item.data_type(),
+                        &mut nested,
+                    )?,
+                )
+            }
+            dt => (
+                if field.is_nullable() && !matches!(dt, DataType::Struct(_)) {
+                    "optional "
+                } else {
+                    ""
+                },
+                arrow_to_protobuf_type(&format!("{}_{}", name, field_name), dt, &mut nested)?,
+            ),
+        };
+
+        lines.push(format!("  {}{} {} = {};", label, typ, field_name, i + 1));
+    }
+
+    Ok(format!(
+        "message {} {{\n{}\n{}\n}}",
+        name,
+        nested.join("\n"),
+        lines.join("\n")
+    ))
+}
+
+/// Generates a proto3 schema with a single top-level message that corresponds to the arrow
+/// schema, for use in sinks that do not have a user-provided schema
+pub fn arrow_to_protobuf_schema(name: &str, fields: &Fields) -> anyhow::Result<String> {
+    Ok(format!(
+        "syntax = \"proto3\";\n\nimport \"google/protobuf/timestamp.proto\";\n\n{}\n",
+        message_def(&sanitize_field(name), fields)?
+    ))
+}
+
+pub struct ProtobufEncoder {
+    descriptor: MessageDescriptor,
+    indexes: Vec<usize>,
+}
+
+impl ProtobufEncoder {
+    pub fn new(format: &ProtobufFormat, name: &str, fields: &Fields) -> anyhow::Result<Self> {
+        let pool = match &format.compiled_schema {
+            Some(compiled_schema) => get_pool(compiled_schema)?,
+            None => get_pool(&compile_schema(&arrow_to_protobuf_schema(name, fields)?)?)?,
+        };
+
+        let descriptor = get_message_descriptor(&pool, format.message_name.as_deref())?;
+
+        Ok(Self {
+            indexes: message_indexes(&descriptor),
+            descriptor,
+        })
+    }
+}
+
+pub fn to_vec<T: SchemaData>(
+    record: &T,
+    format: &ProtobufFormat,
+    encoder: &ProtobufEncoder,
+) -> Result<Vec<u8>, UserError> {
+    let value = sanitize_keys(serde_json::to_value(record).unwrap());
+
+    let message = DynamicMessage::deserialize_with_options(
+        encoder.descriptor.clone(),
+        value,
+        &DeserializeOptions::new().deny_unknown_fields(false),
+    )
+    .map_err(|e| {
+        UserError::new(
+            "record could not be converted to protobuf",
+            format!(
+                "record does not match message '{}': {}",
+                encoder.descriptor.full_name(),
+                e
+            ),
+        )
+    })?;
+
+    let mut buf = Vec::with_capacity(message.encoded_len() + 6);
+    if format.confluent_schema_registry {
+        let schema_id = format.schema_id.ok_or_else(|| {
+            UserError::new(
+                "no schema id for protobuf sink",
+                "writing protobuf with confluent schema registry requires a registered schema id",
+            )
+        })?;
+        buf.push(0);
+        buf.extend(schema_id.to_be_bytes());
+
+        if encoder.indexes == [0] {
+            buf.push(0);
+        } else {
+            write_varint(&mut buf, encoder.indexes.len() as i64);
+            for idx in &encoder.indexes {
+                write_varint(&mut buf, *idx as i64);
+            }
+        }
+    }
+
+    message.encode(&mut buf).unwrap();
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::Field;
+    use arroyo_rpc::schema_resolver::FailingSchemaResolver;
+    use serde::{Deserialize, Serialize};
+
+    const SCHEMA: &str = r#"
+syntax = "proto3";
+
+message Order {
+  int64 id = 1;
+  string customer = 2;
+  optional double amount = 3;
+  repeated string tags = 4;
+  Address address = 5;
+
+  message Address {
+    string city = 1;
+  }
+}
+"#;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Address {
+        city: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Order {
+        id: i64,
+        customer: String,
+        amount: Option<f64>,
+        tags: Vec<String>,
+        address: Option<Address>,
+    }
+
+    fn encode(pool: &DescriptorPool, value: JsonValue) -> Vec<u8> {
+        let descriptor = get_message_descriptor(pool, Some("Order")).unwrap();
+        DynamicMessage::deserialize(descriptor, value)
+            .unwrap()
+            .encode_to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_deserialize_proto() {
+        let compiled = compile_schema(SCHEMA).unwrap();
+        let pool = get_pool(&compiled).unwrap();
+
+        let msg = encode(
+            &pool,
+            json!({"id": 5, "customer": "bob", "tags": ["a", "b"], "address": {"city": "nyc"}}),
+        );
+
+        let mut format = ProtobufFormat::new(false, false, None);
+        format.add_compiled_schema(compiled);
+
+        let result: Vec<Order> = deserialize_slice_proto(
+            &format,
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::new(FailingSchemaResolver::new()),
+            &msg,
+        )
+        .await
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+        assert_eq!(
+            result,
+            vec![Order {
+                id: 5,
+                customer: "bob".to_string(),
+                amount: None,
+                tags: vec!["a".to_string(), "b".to_string()],
+                address: Some(Address {
+                    city: "nyc".to_string()
+                }),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_message_indexes() {
+        let pool = get_pool(&compile_schema(SCHEMA).unwrap()).unwrap();
+        let address = pool.get_message_by_name("Order.Address").unwrap();
+        let indexes = message_indexes(&address);
+        assert_eq!(indexes, vec![0, 0]);
+
+        let mut buf = vec![];
+        write_varint(&mut buf, indexes.len() as i64);
+        for idx in &indexes {
+            write_varint(&mut buf, *idx as i64);
+        }
+
+        let mut slice = &buf[..];
+        assert_eq!(read_message_indexes(&mut slice).unwrap(), indexes);
+        assert!(slice.is_empty());
+
+        assert_eq!(message_for_indexes(&pool, &indexes).unwrap(), address);
+        assert_eq!(read_message_indexes(&mut &[0u8][..]).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn test_arrow_to_protobuf_schema() {
+        let fields: Fields = vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("count(*)", DataType::UInt64, true),
+            Field::new(
+                "nested",
+                DataType::Struct(vec![Field::new("value", DataType::Utf8, false)].into()),
+                true,
+            ),
+            Field::new(
+                "times",
+                DataType::List(Arc::new(Field::new(
+                    "item",
+                    DataType::Timestamp(arrow::datatypes::TimeUnit::Microsecond, None),
+                    false,
+                ))),
+                false,
+            ),
+        ]
+        .into();
+
+        let schema = arrow_to_protobuf_schema("generated_struct", &fields).unwrap();
+        let pool = get_pool(&compile_schema(&schema).unwrap()).unwrap();
+        let message = get_message_descriptor(&pool, None).unwrap();
+        assert_eq!(message.name(), "generated_struct");
+        assert!(message.get_field_by_name("count___").is_some());
+        assert!(message.get_field_by_name("nested").is_some());
+    }
+}
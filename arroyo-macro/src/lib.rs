@@ -229,10 +229,10 @@ impl Parse for StreamTypesAttr {
                 futures = Some(input.parse()?);
             } else {
                 let v: Type = input.parse()?;
-
-                let _ = input.parse::<Token![,]>();
                 fields.insert(k, v);
             }
+
+            let _ = input.parse::<Token![,]>();
         }
 
         Ok(StreamTypesAttr {
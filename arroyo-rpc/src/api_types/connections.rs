@@ -45,6 +45,7 @@ pub struct ConnectionProfilePost {
 pub enum ConnectionType {
     Source,
     Sink,
+    Lookup,
 }
 
 impl Display for ConnectionType {
@@ -52,6 +53,7 @@ impl Display for ConnectionType {
         match self {
             ConnectionType::Source => write!(f, "SOURCE"),
             ConnectionType::Sink => write!(f, "SINK"),
+            ConnectionType::Lookup => write!(f, "LOOKUP"),
         }
     }
 }
@@ -63,6 +65,7 @@ impl TryFrom<String> for ConnectionType {
         match value.to_lowercase().as_str() {
             "source" => Ok(ConnectionType::Source),
             "sink" => Ok(ConnectionType::Sink),
+            "lookup" => Ok(ConnectionType::Lookup),
             _ => Err(format!("Invalid connection type: {}", value)),
         }
     }
@@ -85,6 +88,8 @@ pub enum PrimitiveType {
     UnixNanos,
     DateTime,
     Json,
+    Date,
+    Duration,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema, PartialEq, Eq)]
@@ -94,11 +99,27 @@ pub struct StructType {
     pub fields: Vec<SourceField>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecimalType {
+    pub precision: u8,
+    pub scale: i8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ListType {
+    pub items: Box<SourceFieldType>,
+    pub items_nullable: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum FieldType {
     Primitive(PrimitiveType),
     Struct(StructType),
+    Decimal(DecimalType),
+    List(ListType),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema, PartialEq, Eq)]
@@ -108,6 +129,18 @@ pub struct SourceFieldType {
     pub sql_name: Option<String>,
 }
 
+impl SourceFieldType {
+    fn has_list_of_structs(&self) -> bool {
+        match &self.r#type {
+            FieldType::Primitive(_) | FieldType::Decimal(_) => false,
+            FieldType::Struct(s) => s.fields.iter().any(|f| f.field_type.has_list_of_structs()),
+            FieldType::List(l) => {
+                matches!(l.items.r#type, FieldType::Struct(_)) || l.items.has_list_of_structs()
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceField {
@@ -173,6 +206,17 @@ impl ConnectionSchema {
             _ => {}
         }
 
+        if let Some(field) = self
+            .fields
+            .iter()
+            .find(|f| f.field_type.has_list_of_structs())
+        {
+            bail!(
+                "field '{}' contains a list of structs, which is not supported",
+                field.field_name
+            );
+        }
+
         Ok(self)
     }
 }
@@ -35,6 +35,8 @@ pub struct PaginatedCollection<T> {
     ConnectorCollection = NonPaginatedCollection<Connector>,
     ConnectionProfileCollection = NonPaginatedCollection<ConnectionProfile>,
     GlobalUdfCollection = NonPaginatedCollection<GlobalUdf>,
+    UdfVersionCollection = NonPaginatedCollection<UdfVersion>,
+    PipelineUdfVersionCollection = NonPaginatedCollection<PipelineUdfVersion>,
 )]
 pub struct NonPaginatedCollection<T> {
     pub data: Vec<T>,
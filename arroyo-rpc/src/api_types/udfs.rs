@@ -1,6 +1,6 @@
 use crate::grpc::api as api_proto;
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -28,6 +28,7 @@ impl From<api_proto::Udf> for Udf {
 #[serde(rename_all = "camelCase")]
 pub struct ValidateUdfPost {
     pub definition: String,
+    pub wasm_module: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
@@ -43,6 +44,8 @@ pub struct UdfPost {
     pub prefix: String,
     pub definition: String,
     pub description: Option<String>,
+    /// A base64-encoded WASM module implementing the UDF, whose definition is then its signature
+    pub wasm_module: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
@@ -55,4 +58,80 @@ pub struct GlobalUdf {
     pub updated_at: u64,
     pub definition: String,
     pub description: Option<String>,
+    pub version: u32,
+    pub wasm: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UdfPatch {
+    pub definition: String,
+    pub description: Option<String>,
+    /// A base64-encoded WASM module implementing the UDF, whose definition is then its signature
+    pub wasm_module: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UdfWasmModule {
+    pub size_bytes: u64,
+    /// Hex-encoded SHA-256 digest of the module
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UdfVersion {
+    pub id: String,
+    pub udf_id: String,
+    pub version: u32,
+    pub created_by: String,
+    pub created_at: u64,
+    pub definition: String,
+    pub description: Option<String>,
+    /// The WASM module implementing this version, if it is a WASM UDF
+    pub wasm: Option<UdfWasmModule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "snake_case")]
+pub struct UdfDiffQueryParams {
+    pub from: u32,
+    pub to: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum UdfDiffChange {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UdfDiffLine {
+    pub change: UdfDiffChange,
+    pub line: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UdfDiff {
+    pub udf_id: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub lines: Vec<UdfDiffLine>,
+    pub from_wasm: Option<UdfWasmModule>,
+    pub to_wasm: Option<UdfWasmModule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineUdfVersion {
+    pub udf_id: String,
+    pub name: String,
+    pub version: u32,
+    pub latest_version: u32,
 }
@@ -1,3 +1,4 @@
+use chrono::format::{Item, StrftimeItems};
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::Ordering;
@@ -15,7 +16,12 @@ pub enum TimestampFormat {
     #[default]
     #[serde(rename = "rfc3339")]
     RFC3339,
+    UnixSeconds,
     UnixMillis,
+    UnixMicros,
+    UnixNanos,
+    /// A strftime-style pattern, like `%Y-%m-%d %H:%M:%S`; times without an offset are in UTC
+    Custom(String),
 }
 
 impl TryFrom<&str> for TimestampFormat {
@@ -23,13 +29,61 @@ impl TryFrom<&str> for TimestampFormat {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
-            "RFC3339" => Ok(TimestampFormat::RFC3339),
+            "RFC3339" | "rfc3339" => Ok(TimestampFormat::RFC3339),
+            "UnixSeconds" | "unix_seconds" => Ok(TimestampFormat::UnixSeconds),
             "UnixMillis" | "unix_millis" => Ok(TimestampFormat::UnixMillis),
+            "UnixMicros" | "unix_micros" => Ok(TimestampFormat::UnixMicros),
+            "UnixNanos" | "unix_nanos" => Ok(TimestampFormat::UnixNanos),
             _ => Err(()),
         }
     }
 }
 
+/// Maps a column to a value nested within the JSON record, or to a field with a different name
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonFieldPath {
+    pub column: String,
+    /// The object keys leading to the value, starting at the root of the record
+    pub path: Vec<String>,
+}
+
+impl JsonFieldPath {
+    /// Parses a comma-separated list of mappings like `$.payload.user.id AS user_id`
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',')
+            .filter(|m| !m.trim().is_empty())
+            .map(|m| {
+                let invalid = || {
+                    format!(
+                        "invalid field path '{}'; expected a mapping like '$.payload.id AS id'",
+                        m.trim()
+                    )
+                };
+
+                let split = m.to_ascii_lowercase().rfind(" as ").ok_or_else(invalid)?;
+                let (path, column) = (m[..split].trim(), m[split + 4..].trim());
+
+                let path: Vec<String> = path
+                    .strip_prefix("$.")
+                    .ok_or_else(invalid)?
+                    .split('.')
+                    .map(|k| k.to_string())
+                    .collect();
+
+                if column.is_empty() || path.iter().any(|k| k.is_empty()) {
+                    return Err(invalid());
+                }
+
+                Ok(Self {
+                    column: column.to_string(),
+                    path,
+                })
+            })
+            .collect()
+    }
+}
+
 #[derive(
     Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default, Hash, PartialOrd, ToSchema,
 )]
@@ -52,6 +106,16 @@ pub struct JsonFormat {
 
     #[serde(default)]
     pub timestamp_format: TimestampFormat,
+
+    /// If set, decimal columns are written as strings to avoid losing precision in other
+    /// systems; they can be read from either strings or numbers
+    #[serde(default)]
+    pub decimal_as_string: bool,
+
+    /// Columns that are read from and written to somewhere other than the top-level field of
+    /// the same name
+    #[serde(default)]
+    pub field_paths: Vec<JsonFieldPath>,
 }
 
 impl JsonFormat {
@@ -75,18 +139,51 @@ impl JsonFormat {
             .filter(|t| t == "true")
             .is_some();
 
-        let timestamp_format: TimestampFormat = opts
-            .remove("json.timestamp_format")
-            .map(|t| t.as_str().try_into())
-            .transpose()
-            .map_err(|_| "json.timestamp_format".to_string())?
-            .unwrap_or_else(|| {
-                if debezium {
-                    TimestampFormat::UnixMillis
-                } else {
-                    TimestampFormat::default()
+        let timestamp_pattern = opts.remove("json.timestamp_pattern");
+
+        let timestamp_format: TimestampFormat = match opts.remove("json.timestamp_format") {
+            Some(t) if t == "custom" => {
+                let pattern = timestamp_pattern.ok_or(
+                    "json.timestamp_pattern must be set when json.timestamp_format is 'custom'",
+                )?;
+                if StrftimeItems::new(&pattern).any(|item| matches!(item, Item::Error)) {
+                    return Err(format!(
+                        "json.timestamp_pattern '{}' is not a valid strftime pattern",
+                        pattern
+                    ));
                 }
-            });
+                TimestampFormat::Custom(pattern)
+            }
+            _ if timestamp_pattern.is_some() => {
+                return Err(
+                    "json.timestamp_pattern requires json.timestamp_format to be 'custom'"
+                        .to_string(),
+                );
+            }
+            Some(t) => t
+                .as_str()
+                .try_into()
+                .map_err(|_| "json.timestamp_format".to_string())?,
+            None if debezium => TimestampFormat::UnixMillis,
+            None => TimestampFormat::default(),
+        };
+
+        let decimal_as_string = opts
+            .remove("json.decimal_as_string")
+            .filter(|t| t == "true")
+            .is_some();
+
+        let field_paths = opts
+            .remove("json.field_paths")
+            .map(|t| JsonFieldPath::parse_list(&t))
+            .transpose()?
+            .unwrap_or_default();
+
+        if !field_paths.is_empty() && (debezium || unstructured) {
+            return Err(
+                "json.field_paths is not supported for debezium or unstructured json".to_string(),
+            );
+        }
 
         Ok(Self {
             confluent_schema_registry,
@@ -95,6 +192,8 @@ impl JsonFormat {
             debezium,
             unstructured,
             timestamp_format,
+            decimal_as_string,
+            field_paths,
         })
     }
 }
@@ -179,6 +278,10 @@ pub struct AvroFormat {
     #[serde(default)]
     #[schema(read_only)]
     pub schema_id: Option<u32>,
+
+    /// If set, records are Debezium change events with `before`, `after` and `op` fields
+    #[serde(default)]
+    pub debezium: bool,
 }
 
 impl AvroFormat {
@@ -193,21 +296,32 @@ impl AvroFormat {
             into_unstructured_json,
             reader_schema: None,
             schema_id: None,
+            debezium: false,
         }
     }
 
-    pub fn from_opts(opts: &mut HashMap<String, String>) -> Result<Self, String> {
-        Ok(Self::new(
-            opts.remove("avro.confluent_schema_registry")
-                .filter(|t| t == "true")
-                .is_some(),
-            opts.remove("avro.raw_datums")
-                .filter(|t| t == "true")
-                .is_some(),
-            opts.remove("avro.into_unstructured_json")
-                .filter(|t| t == "true")
-                .is_some(),
-        ))
+    pub fn from_opts(debezium: bool, opts: &mut HashMap<String, String>) -> Result<Self, String> {
+        let into_unstructured_json = opts
+            .remove("avro.into_unstructured_json")
+            .filter(|t| t == "true")
+            .is_some();
+
+        if debezium && into_unstructured_json {
+            return Err("debezium avro cannot be read into unstructured json".to_string());
+        }
+
+        Ok(Self {
+            debezium,
+            ..Self::new(
+                opts.remove("avro.confluent_schema_registry")
+                    .filter(|t| t == "true")
+                    .is_some(),
+                opts.remove("avro.raw_datums")
+                    .filter(|t| t == "true")
+                    .is_some(),
+                into_unstructured_json,
+            )
+        })
     }
 
     pub fn add_reader_schema(&mut self, schema: apache_avro::Schema) {
@@ -226,6 +340,183 @@ impl AvroFormat {
 #[serde(rename_all = "camelCase")]
 pub struct ParquetFormat {}
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtobufFormat {
+    #[serde(default)]
+    pub confluent_schema_registry: bool,
+
+    #[serde(default)]
+    pub into_unstructured_json: bool,
+
+    /// The fully-qualified name of the message to decode; if not set, the first message
+    /// in the schema file is used
+    #[serde(default)]
+    pub message_name: Option<String>,
+
+    /// The encoded FileDescriptorSet for the schema
+    #[serde(default)]
+    #[schema(read_only, value_type = Option<Vec<u8>>)]
+    pub compiled_schema: Option<Vec<u8>>,
+
+    #[serde(default)]
+    #[schema(read_only)]
+    pub schema_id: Option<u32>,
+}
+
+impl ProtobufFormat {
+    pub fn new(
+        confluent_schema_registry: bool,
+        into_unstructured_json: bool,
+        message_name: Option<String>,
+    ) -> Self {
+        Self {
+            confluent_schema_registry,
+            into_unstructured_json,
+            message_name,
+            compiled_schema: None,
+            schema_id: None,
+        }
+    }
+
+    pub fn from_opts(opts: &mut HashMap<String, String>) -> Result<Self, String> {
+        Ok(Self::new(
+            opts.remove("protobuf.confluent_schema_registry")
+                .filter(|t| t == "true")
+                .is_some(),
+            opts.remove("protobuf.into_unstructured_json")
+                .filter(|t| t == "true")
+                .is_some(),
+            opts.remove("protobuf.message_name"),
+        ))
+    }
+
+    pub fn add_compiled_schema(&mut self, compiled_schema: Vec<u8>) {
+        self.compiled_schema = Some(compiled_schema);
+    }
+}
+
+#[derive(
+    Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default, Hash, PartialOrd, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvHeader {
+    /// The file has no header row; columns are mapped to fields by position
+    #[default]
+    None,
+    /// The first row is a header, but is ignored; columns are mapped to fields by position
+    Skip,
+    /// The first row is a header, and columns are mapped to fields by name
+    Use,
+}
+
+impl TryFrom<&str> for CsvHeader {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "none" | "false" => Ok(CsvHeader::None),
+            "skip" => Ok(CsvHeader::Skip),
+            "use" | "true" => Ok(CsvHeader::Use),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvFormat {
+    #[serde(default = "CsvFormat::default_delimiter")]
+    pub delimiter: char,
+
+    #[serde(default = "CsvFormat::default_quote")]
+    pub quote: char,
+
+    /// If set, quotes within quoted fields are escaped with this character; otherwise
+    /// they are escaped by doubling them
+    #[serde(default)]
+    pub escape: Option<char>,
+
+    #[serde(default)]
+    pub header: CsvHeader,
+
+    /// A string that should be read as (and written for) null values; if not set, empty
+    /// values are treated as null for nullable non-string fields
+    #[serde(default)]
+    pub null_string: Option<String>,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self {
+            delimiter: Self::default_delimiter(),
+            quote: Self::default_quote(),
+            escape: None,
+            header: CsvHeader::default(),
+            null_string: None,
+        }
+    }
+}
+
+impl CsvFormat {
+    fn default_delimiter() -> char {
+        ','
+    }
+
+    fn default_quote() -> char {
+        '"'
+    }
+
+    fn parse_char(opts: &mut HashMap<String, String>, key: &str) -> Result<Option<char>, String> {
+        let Some(value) = opts.remove(key) else {
+            return Ok(None);
+        };
+
+        let c = match value.as_str() {
+            "\\t" | "tab" => '\t',
+            v => {
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii() => c,
+                    _ => return Err(format!("{} must be a single ASCII character", key)),
+                }
+            }
+        };
+
+        Ok(Some(c))
+    }
+
+    pub fn from_opts(
+        default_delimiter: char,
+        opts: &mut HashMap<String, String>,
+    ) -> Result<Self, String> {
+        let delimiter = Self::parse_char(opts, "csv.delimiter")?.unwrap_or(default_delimiter);
+        let quote = Self::parse_char(opts, "csv.quote")?.unwrap_or_else(Self::default_quote);
+        let escape = Self::parse_char(opts, "csv.escape")?;
+
+        if delimiter == quote {
+            return Err("csv.delimiter and csv.quote must be different characters".to_string());
+        }
+
+        let header = opts
+            .remove("csv.header")
+            .map(|t| t.as_str().try_into())
+            .transpose()
+            .map_err(|_| "csv.header must be one of 'none', 'skip', or 'use'".to_string())?
+            .unwrap_or_default();
+
+        let null_string = opts.remove("csv.null_string");
+
+        Ok(Self {
+            delimiter,
+            quote,
+            escape,
+            header,
+            null_string,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Format {
@@ -233,6 +524,8 @@ pub enum Format {
     Avro(AvroFormat),
     Parquet(ParquetFormat),
     RawString(RawStringFormat),
+    Protobuf(ProtobufFormat),
+    Csv(CsvFormat),
 }
 
 impl Format {
@@ -244,8 +537,11 @@ impl Format {
         Ok(Some(match name.as_str() {
             "json" => Format::Json(JsonFormat::from_opts(false, opts)?),
             "debezium_json" => Format::Json(JsonFormat::from_opts(true, opts)?),
-            "protobuf" => return Err("protobuf is not yet supported".to_string()),
-            "avro" => Format::Avro(AvroFormat::from_opts(opts)?),
+            "protobuf" => Format::Protobuf(ProtobufFormat::from_opts(opts)?),
+            "csv" => Format::Csv(CsvFormat::from_opts(',', opts)?),
+            "tsv" => Format::Csv(CsvFormat::from_opts('\t', opts)?),
+            "avro" => Format::Avro(AvroFormat::from_opts(false, opts)?),
+            "debezium_avro" => Format::Avro(AvroFormat::from_opts(true, opts)?),
             "raw_string" => Format::RawString(RawStringFormat {}),
             "parquet" => Format::Parquet(ParquetFormat {}),
             f => return Err(format!("Unknown format '{}'", f)),
@@ -255,7 +551,13 @@ impl Format {
     pub fn is_updating(&self) -> bool {
         match self {
             Format::Json(JsonFormat { debezium: true, .. }) => true,
-            Format::Json(_) | Format::Avro(_) | Format::Parquet(_) | Format::RawString(_) => false,
+            Format::Avro(AvroFormat { debezium: true, .. }) => true,
+            Format::Json(_)
+            | Format::Avro(_)
+            | Format::Parquet(_)
+            | Format::RawString(_)
+            | Format::Protobuf(_)
+            | Format::Csv(_) => false,
         }
     }
 }
@@ -264,7 +566,32 @@ impl Format {
 #[serde(rename_all = "snake_case")]
 pub enum BadData {
     Fail {},
-    Drop {},
+    Drop {
+        /// If set, the job fails once more than this many records are dropped within a minute
+        #[serde(default)]
+        max_per_minute: Option<u32>,
+    },
+    DeadLetter {
+        /// The name of the sink connection table that undecodable records are written to
+        table: String,
+        /// If set, the job fails once more than this many records are dead-lettered within a
+        /// minute
+        #[serde(default)]
+        max_per_minute: Option<u32>,
+        /// The dead-letter table, resolved when the pipeline is planned
+        #[serde(default)]
+        #[schema(read_only)]
+        sink: Option<DeadLetterSink>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterSink {
+    /// The connector of the dead-letter table; currently either `kafka` or `filesystem`
+    pub connector: String,
+    /// The operator config of the dead-letter table
+    pub config: String,
 }
 
 impl BadData {
@@ -273,9 +600,31 @@ impl BadData {
             return Ok(None);
         };
 
+        let max_per_minute = opts
+            .remove("bad_data.max_per_minute")
+            .map(|t| {
+                t.parse()
+                    .map_err(|_| format!("invalid bad_data.max_per_minute '{}'", t))
+            })
+            .transpose()?;
+
         let method = match method.as_str() {
-            "drop" => BadData::Drop {},
-            "fail" => BadData::Fail {},
+            "drop" => BadData::Drop { max_per_minute },
+            "fail" => {
+                if max_per_minute.is_some() {
+                    return Err(
+                        "bad_data.max_per_minute cannot be set when bad_data is 'fail'".to_string(),
+                    );
+                }
+                BadData::Fail {}
+            }
+            "dead_letter" => BadData::DeadLetter {
+                table: opts.remove("bad_data.table").ok_or_else(|| {
+                    "bad_data.table must be set to the name of the dead-letter table".to_string()
+                })?,
+                max_per_minute,
+                sink: None,
+            },
             f => return Err(format!("Unknown invalid data behavior '{}'", f)),
         };
 
@@ -297,6 +646,10 @@ impl Framing {
 
         let method = match method.as_str() {
             "newline" => FramingMethod::Newline(NewlineDelimitedFraming::from_opts(opts)?),
+            "length_prefixed" => {
+                FramingMethod::LengthPrefixed(LengthPrefixedFraming::from_opts(opts)?)
+            }
+            "delimiter" => FramingMethod::Delimiter(DelimiterFraming::from_opts(opts)?),
             f => return Err(format!("Unknown framing method '{}'", f)),
         };
 
@@ -324,8 +677,137 @@ impl NewlineDelimitedFraming {
     }
 }
 
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, Hash, PartialOrd, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    #[default]
+    Big,
+    Little,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LengthPrefixedFraming {
+    /// The width of the length prefix in bytes; one of 1, 2, 4, or 8
+    pub width: u8,
+    #[serde(default)]
+    pub endianness: Endianness,
+}
+
+impl LengthPrefixedFraming {
+    pub fn from_opts(opts: &mut HashMap<String, String>) -> Result<Self, String> {
+        let width = opts
+            .remove("framing.length_prefixed.width")
+            .map(|t| {
+                u8::from_str(&t)
+                    .ok()
+                    .filter(|w| matches!(w, 1 | 2 | 4 | 8))
+                    .ok_or_else(|| {
+                        "invalid value for framing.length_prefixed.width; must be one of 1, 2, 4, or 8"
+                            .to_string()
+                    })
+            })
+            .transpose()?
+            .unwrap_or(4);
+
+        let endianness = match opts.remove("framing.length_prefixed.endianness").as_deref() {
+            None | Some("big") => Endianness::Big,
+            Some("little") => Endianness::Little,
+            Some(e) => {
+                return Err(format!(
+                    "invalid value '{}' for framing.length_prefixed.endianness; must be 'big' or 'little'",
+                    e
+                ))
+            }
+        };
+
+        Ok(LengthPrefixedFraming { width, endianness })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DelimiterFraming {
+    pub bytes: Vec<u8>,
+}
+
+impl DelimiterFraming {
+    /// Parses a delimiter, supporting the escapes `\0`, `\n`, `\r`, `\t`, `\\`, and `\xNN`
+    fn parse_delimiter(s: &str) -> Result<Vec<u8>, String> {
+        let mut bytes = vec![];
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                continue;
+            }
+
+            match chars.next() {
+                Some('0') => bytes.push(0),
+                Some('n') => bytes.push(b'\n'),
+                Some('r') => bytes.push(b'\r'),
+                Some('t') => bytes.push(b'\t'),
+                Some('\\') => bytes.push(b'\\'),
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    bytes
+                        .push(u8::from_str_radix(&hex, 16).map_err(|_| {
+                            format!("invalid hex escape '\\x{}' in delimiter", hex)
+                        })?);
+                }
+                Some(c) => return Err(format!("invalid escape '\\{}' in delimiter", c)),
+                None => return Err("delimiter cannot end with '\\'".to_string()),
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn from_opts(opts: &mut HashMap<String, String>) -> Result<Self, String> {
+        let bytes = opts
+            .remove("framing.delimiter.bytes")
+            .ok_or_else(|| "framing.delimiter.bytes must be set for delimiter framing".to_string())
+            .and_then(|s| Self::parse_delimiter(&s))?;
+
+        if bytes.is_empty() {
+            return Err("framing.delimiter.bytes cannot be empty".to_string());
+        }
+
+        Ok(DelimiterFraming { bytes })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum FramingMethod {
     Newline(NewlineDelimitedFraming),
+    LengthPrefixed(LengthPrefixedFraming),
+    Delimiter(DelimiterFraming),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_timestamp_pattern() {
+        let opts = |pattern: &str| {
+            HashMap::from([
+                ("json.timestamp_format".to_string(), "custom".to_string()),
+                ("json.timestamp_pattern".to_string(), pattern.to_string()),
+            ])
+        };
+
+        assert_eq!(
+            JsonFormat::from_opts(false, &mut opts("%d.%m.%Y %H:%M"))
+                .unwrap()
+                .timestamp_format,
+            TimestampFormat::Custom("%d.%m.%Y %H:%M".to_string())
+        );
+        assert!(JsonFormat::from_opts(false, &mut opts("%Y-%Q")).is_err());
+        assert!(JsonFormat::from_opts(false, &mut opts("%Y-%m-%")).is_err());
+    }
 }
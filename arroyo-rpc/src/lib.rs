@@ -152,6 +152,8 @@ pub fn primitive_to_sql(primitive_type: PrimitiveType) -> &'static str {
         | PrimitiveType::UnixNanos
         | PrimitiveType::DateTime => "TIMESTAMP",
         PrimitiveType::Json => "JSONB",
+        PrimitiveType::Date => "DATE",
+        PrimitiveType::Duration => "INTERVAL",
     }
 }
 
@@ -208,4 +210,8 @@ pub struct UdfOpts {
     pub async_timeout_seconds: u64,
     #[serde(default = "default_async_max_concurrency")]
     pub async_max_concurrency: u64,
+    // whether the function is a window function, which is called with the ordered rows of each
+    // partition and returns a value for each of them
+    #[serde(default = "bool::default")]
+    pub window: bool,
 }
@@ -104,6 +104,13 @@ pub struct PostSchemaResponse {
     pub id: i32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompatibilityResponse {
+    pub is_compatible: bool,
+    #[serde(default)]
+    pub messages: Vec<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct RegistryErrorResponse {
     error_code: i32,
@@ -260,6 +267,61 @@ impl ConfluentSchemaRegistryClient {
         Ok(resp.id)
     }
 
+    async fn check_compatibility(
+        &self,
+        url: Url,
+        schema: impl Into<String>,
+        schema_type: ConfluentSchemaType,
+    ) -> anyhow::Result<CompatibilityResponse> {
+        let req = PostSchemaRequest {
+            schema: schema.into(),
+            schema_type,
+        };
+
+        let resp = self.client.post(url).json(&req).send().await.map_err(|e| {
+            warn!("Got error response from schema registry: {:?}", e);
+            anyhow!(
+                "Could not connect to Schema Registry at {}: unknown error",
+                self.endpoint
+            )
+        })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let bytes = resp.bytes().await.map(|b| b.to_vec()).unwrap_or_default();
+
+            match serde_json::from_slice::<RegistryErrorResponse>(&bytes) {
+                // there is no existing schema for the subject, so anything is compatible
+                Ok(RegistryErrorResponse {
+                    error_code: 40401 | 40402,
+                    ..
+                }) => {
+                    return Ok(CompatibilityResponse {
+                        is_compatible: true,
+                        messages: vec![],
+                    });
+                }
+                Ok(RegistryErrorResponse { message, .. }) if status.as_u16() == 422 => {
+                    bail!("invalid schema: {}", message);
+                }
+                _ => {
+                    bail!(
+                        "schema registry returned error {}: {}",
+                        status.as_u16(),
+                        String::from_utf8_lossy(&bytes)
+                    );
+                }
+            }
+        }
+
+        resp.json().await.map_err(|e| {
+            anyhow!(
+                "could not parse compatibility response from schema registry: {}",
+                e
+            )
+        })
+    }
+
     pub async fn test(&self) -> anyhow::Result<()> {
         let resp = self
             .client
@@ -307,6 +369,7 @@ impl ConfluentSchemaRegistryClient {
 pub struct ConfluentSchemaRegistry {
     client: ConfluentSchemaRegistryClient,
     topic: String,
+    subject: String,
 }
 
 impl ConfluentSchemaRegistry {
@@ -319,13 +382,25 @@ impl ConfluentSchemaRegistry {
         Ok(Self {
             client: ConfluentSchemaRegistryClient::new(endpoint, api_key, api_secret)?,
             topic: topic.to_string(),
+            subject: format!("{}-value", topic),
         })
     }
 
-    fn topic_endpoint(&self) -> Url {
+    /// Overrides the subject used for reading and writing schemas, which by default is
+    /// derived from the topic name (`<topic>-value`)
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = subject.into();
+        self
+    }
+
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    fn subject_endpoint(&self) -> Url {
         self.client
             .endpoint
-            .join(&format!("subjects/{}-value/versions/", self.topic))
+            .join(&format!("subjects/{}/versions/", self.subject))
             .unwrap()
     }
 
@@ -335,9 +410,38 @@ impl ConfluentSchemaRegistry {
         schema_type: ConfluentSchemaType,
     ) -> anyhow::Result<i32> {
         self.client
-            .write_schema(self.topic_endpoint(), schema, schema_type)
+            .write_schema(self.subject_endpoint(), schema, schema_type)
             .await
-            .context(format!("topic '{}'", self.topic))
+            .context(format!(
+                "topic '{}' (subject '{}')",
+                self.topic, self.subject
+            ))
+    }
+
+    /// Checks whether the schema is compatible with the latest version registered for the
+    /// subject, according to the compatibility level configured in the registry. Subjects
+    /// without any registered schemas are always compatible.
+    pub async fn check_compatibility(
+        &self,
+        schema: impl Into<String>,
+        schema_type: ConfluentSchemaType,
+    ) -> anyhow::Result<CompatibilityResponse> {
+        let url = self
+            .client
+            .endpoint
+            .join(&format!(
+                "compatibility/subjects/{}/versions/latest?verbose=true",
+                self.subject
+            ))
+            .unwrap();
+
+        self.client
+            .check_compatibility(url, schema, schema_type)
+            .await
+            .context(format!(
+                "failed to check schema compatibility for topic '{}' (subject '{}')",
+                self.topic, self.subject
+            ))
     }
 
     pub async fn get_schema_for_id(
@@ -364,11 +468,11 @@ impl ConfluentSchemaRegistry {
             .map(|v| format!("{}", v))
             .unwrap_or_else(|| "latest".to_string());
 
-        let url = self.topic_endpoint().join(&version).unwrap();
+        let url = self.subject_endpoint().join(&version).unwrap();
 
         self.client.get_schema_for_url(url).await.context(format!(
-            "failed to fetch schema for topic '{}' with version {}",
-            self.topic, version
+            "failed to fetch schema for topic '{}' (subject '{}') with version {}",
+            self.topic, self.subject, version
         ))
     }
 }
@@ -382,3 +486,202 @@ impl SchemaResolver for ConfluentSchemaRegistry {
             .map_err(|e| e.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Path, State};
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+
+    type Subjects = Arc<Mutex<HashMap<String, Vec<(u32, String)>>>>;
+
+    fn not_found() -> Response {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error_code": 40401, "message": "Subject not found."})),
+        )
+            .into_response()
+    }
+
+    fn fields(schema: &str) -> Vec<(String, bool)> {
+        let Schema::Record(record) = Schema::parse_str(schema).unwrap() else {
+            panic!("expected a record");
+        };
+        record
+            .fields
+            .into_iter()
+            .map(|f| (f.name, f.default.is_some()))
+            .collect()
+    }
+
+    /// A minimal in-memory registry, which treats new schemas as (backwards) compatible if all
+    /// of their added fields have defaults
+    async fn mock_registry() -> (String, Subjects) {
+        let subjects: Subjects = Arc::new(Mutex::new(HashMap::new()));
+
+        let app = Router::new()
+            .route(
+                "/subjects/:subject/versions/",
+                post(
+                    |State(subjects): State<Subjects>,
+                     Path(subject): Path<String>,
+                     Json(req): Json<PostSchemaRequest>| async move {
+                        let mut subjects = subjects.lock().unwrap();
+                        let id = subjects.values().map(|v| v.len() as u32).sum::<u32>() + 1;
+                        subjects.entry(subject).or_default().push((id, req.schema));
+                        Json(json!({ "id": id }))
+                    },
+                ),
+            )
+            .route(
+                "/subjects/:subject/versions/:version",
+                get(
+                    |State(subjects): State<Subjects>,
+                     Path((subject, _)): Path<(String, String)>| async move {
+                        let subjects = subjects.lock().unwrap();
+                        let Some(versions) = subjects.get(&subject) else {
+                            return not_found();
+                        };
+                        let (id, schema) = versions.last().unwrap();
+                        Json(json!({
+                            "id": id,
+                            "schema": schema,
+                            "subject": subject,
+                            "version": versions.len(),
+                        }))
+                        .into_response()
+                    },
+                ),
+            )
+            .route(
+                "/compatibility/subjects/:subject/versions/latest",
+                post(
+                    |State(subjects): State<Subjects>,
+                     Path(subject): Path<String>,
+                     Json(req): Json<PostSchemaRequest>| async move {
+                        let subjects = subjects.lock().unwrap();
+                        let Some(versions) = subjects.get(&subject) else {
+                            return not_found();
+                        };
+                        let existing = fields(&versions.last().unwrap().1);
+                        let messages: Vec<_> = fields(&req.schema)
+                            .into_iter()
+                            .filter(|(name, has_default)| {
+                                !has_default && !existing.iter().any(|(e, _)| e == name)
+                            })
+                            .map(|(name, _)| {
+                                format!("READER_FIELD_MISSING_DEFAULT_VALUE: {}", name)
+                            })
+                            .collect();
+
+                        Json(json!({
+                            "is_compatible": messages.is_empty(),
+                            "messages": messages,
+                        }))
+                        .into_response()
+                    },
+                ),
+            )
+            .with_state(subjects.clone());
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        (format!("http://{}", addr), subjects)
+    }
+
+    const V1: &str = r#"{"type": "record", "name": "Order", "namespace": "com.example", "fields": [
+        {"name": "id", "type": "long"}
+    ]}"#;
+
+    const V2: &str = r#"{"type": "record", "name": "Order", "namespace": "com.example", "fields": [
+        {"name": "id", "type": "long"},
+        {"name": "note", "type": ["null", "string"], "default": null}
+    ]}"#;
+
+    const V3: &str = r#"{"type": "record", "name": "Order", "namespace": "com.example", "fields": [
+        {"name": "id", "type": "long"},
+        {"name": "customer", "type": "string"}
+    ]}"#;
+
+    #[tokio::test]
+    async fn test_subjects() {
+        let (endpoint, subjects) = mock_registry().await;
+
+        let topic = ConfluentSchemaRegistry::new(&endpoint, "orders", None, None).unwrap();
+        assert_eq!(topic.subject(), "orders-value");
+        topic
+            .write_schema(V1, ConfluentSchemaType::Avro)
+            .await
+            .unwrap();
+
+        let record = ConfluentSchemaRegistry::new(&endpoint, "orders", None, None)
+            .unwrap()
+            .with_subject("com.example.Order");
+        record
+            .write_schema(V1, ConfluentSchemaType::Avro)
+            .await
+            .unwrap();
+
+        let mut registered: Vec<_> = subjects.lock().unwrap().keys().cloned().collect();
+        registered.sort();
+        assert_eq!(registered, vec!["com.example.Order", "orders-value"]);
+
+        let latest = record.get_schema_for_version(None).await.unwrap().unwrap();
+        assert_eq!(latest.subject, "com.example.Order");
+        assert_eq!(latest.schema, V1);
+    }
+
+    #[tokio::test]
+    async fn test_compatibility() {
+        let (endpoint, _) = mock_registry().await;
+
+        let registry = ConfluentSchemaRegistry::new(&endpoint, "orders", None, None).unwrap();
+
+        // nothing is registered yet, so any schema is compatible
+        assert!(
+            registry
+                .check_compatibility(V3, ConfluentSchemaType::Avro)
+                .await
+                .unwrap()
+                .is_compatible
+        );
+
+        registry
+            .write_schema(V1, ConfluentSchemaType::Avro)
+            .await
+            .unwrap();
+
+        assert!(
+            registry
+                .check_compatibility(V2, ConfluentSchemaType::Avro)
+                .await
+                .unwrap()
+                .is_compatible
+        );
+
+        let result = registry
+            .check_compatibility(V3, ConfluentSchemaType::Avro)
+            .await
+            .unwrap();
+        assert!(!result.is_compatible);
+        assert_eq!(
+            result.messages,
+            vec!["READER_FIELD_MISSING_DEFAULT_VALUE: customer"]
+        );
+    }
+}
@@ -78,6 +78,8 @@ pub fn get_json_schema_source() -> Result<Connection> {
             debezium: false,
             unstructured: false,
             timestamp_format: TimestampFormat::RFC3339,
+            decimal_as_string: false,
+            field_paths: vec![],
         })),
         None,
         None,
@@ -101,6 +103,7 @@ pub fn get_json_schema_source() -> Result<Connection> {
             offset: arroyo_connectors::kafka::SourceOffset::Earliest,
             read_mode: Some(ReadMode::ReadUncommitted),
         },
+        subject_strategy: None,
         client_configs: HashMap::new(),
     };
     KafkaConnector {}.from_config(
@@ -142,6 +145,33 @@ pub fn get_avro_source() -> Result<Connection> {
   "name": "status",
   "type": "string"
 },
+{
+  "name": "total",
+  "type": ["null", {
+    "logicalType": "decimal",
+    "precision": 10,
+    "scale": 2,
+    "type": "bytes"
+  }]
+},
+{
+  "name": "receipt",
+  "type": "bytes"
+},
+{
+  "name": "created_at",
+  "type": {
+    "logicalType": "timestamp-micros",
+    "type": "long"
+  }
+},
+{
+  "name": "tags",
+  "type": {
+    "items": "string",
+    "type": "array"
+  }
+},
 {
   "name": "order_lines",
   "type": {
@@ -181,7 +211,7 @@ pub fn get_avro_source() -> Result<Connection> {
 "type": "record"
 }"#;
     let definition = SchemaDefinition::AvroSchema(avro_schema.to_string());
-    let struct_fields = convert_avro_schema("kafka_avro_schema", avro_schema).unwrap();
+    let struct_fields = convert_avro_schema("kafka_avro_schema", avro_schema, false).unwrap();
     let mut format = AvroFormat::new(true, false, false);
     format.add_reader_schema(apache_avro::Schema::parse_str(avro_schema).unwrap());
     let connection_schema = ConnectionSchema::try_new(
@@ -208,6 +238,7 @@ pub fn get_avro_source() -> Result<Connection> {
             offset: arroyo_connectors::kafka::SourceOffset::Earliest,
             read_mode: Some(ReadMode::ReadUncommitted),
         },
+        subject_strategy: None,
         client_configs: HashMap::new(),
     };
     KafkaConnector {}.from_config(
@@ -4,7 +4,7 @@ use arroyo_sql::{
     get_test_expression, parse_and_get_program_sync, ArroyoSchemaProvider, SqlConfig,
 };
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
+use quote::quote;
 use syn::parse::{Parse, ParseStream};
 use syn::{parse_str, Expr, LitInt, LitStr, Token};
 
@@ -159,15 +159,21 @@ fn get_pipeline_module(
     schema_provider.add_connector_table(connectors::get_avro_source().unwrap());
 
     let file = syn::parse_file(&udfs.unwrap_or_default()).unwrap();
+    // structs are defined along with the function that follows them, which may return them
+    let mut structs = vec![];
     for item in file.items.into_iter() {
         match item {
+            syn::Item::Struct(_) => {
+                structs.push(item);
+            }
             syn::Item::Fn(_) => {
                 schema_provider
-                    .add_rust_udf(&item.to_token_stream().to_string())
+                    .add_rust_udf(&quote!(#(#structs)* #item).to_string())
                     .unwrap();
+                structs.clear();
             }
             _ => {
-                panic!("Expected only functions.")
+                panic!("Expected only functions and structs.")
             }
         }
     }
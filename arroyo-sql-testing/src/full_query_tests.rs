@@ -141,6 +141,14 @@ GROUP BY 2
 )
 WHERE auction % 2 = 0"}
 
+full_pipeline_codegen! {"approx_distinct_over_updating", "
+SELECT bids, approx_distinct(auction) as auctions
+FROM (
+SELECT count(*) as bids, bid.auction as auction from nexmark where bid is not null
+GROUP BY 2
+)
+GROUP BY 1"}
+
 full_pipeline_codegen! {"create_filesystem_s3_source",
 "CREATE TABLE events (
   id bigint,
@@ -179,6 +187,12 @@ full_pipeline_codegen! {"session_window",
 from nexmark
 group by window, auction.id; "}
 
+full_pipeline_codegen! {"session_window_allowed_lateness",
+"SET allowed_lateness_micros = 60000000;
+SELECT count(*), session(INTERVAL '10' SECOND) AS window
+from nexmark
+group by window, auction.id; "}
+
 full_pipeline_codegen! {"virtual_field_implicit_cast",
 "create table demo_stream (
   timestamp BIGINT NOT NULL,
@@ -558,6 +572,30 @@ select cast(bid.datetime as TIMESTAMP), bid.auction as u64, bid.auction as i64,
 from nexmark;"
 }
 
+full_pipeline_codegen! {
+  "avro_writes_decimals",
+  "CREATE TABLE avro_decimals (
+    price DECIMAL(10, 2),
+    precise_price DECIMAL(12, 3),
+    total DECIMAL(10, 2),
+    date DATE
+  )
+  WITH (
+  connector = 'kafka',
+  bootstrap_servers = 'localhost:9092',
+  type = 'sink',
+  topic = 'outputs',
+  format = 'avro'
+);
+
+INSERT INTO avro_decimals
+select cast(store_id as DECIMAL(10, 2)) as price,
+  cast(cast(store_id as DECIMAL(10, 2)) as DECIMAL(12, 3)) as precise_price,
+  total,
+  date
+from kafka_avro_schema;"
+}
+
 full_pipeline_codegen! {
   "raw_string_cast",
   "create table logs (
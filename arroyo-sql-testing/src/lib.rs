@@ -966,6 +966,191 @@ mod tests {
         },
         vec![Some(1i64), Some(2i64)]
     );
+
+    // array functions
+    single_test_codegen!(
+        "array_append",
+        "array_append(make_array(non_nullable_i64, 2), 3)",
+        arroyo_sql::TestStruct {
+            non_nullable_i64: 1,
+            ..Default::default()
+        },
+        vec![1i64, 2i64, 3i64]
+    );
+
+    single_test_codegen!(
+        "array_append_null_element",
+        "array_append(make_array(non_nullable_i64), nullable_i64)",
+        arroyo_sql::TestStruct {
+            non_nullable_i64: 1,
+            nullable_i64: None,
+            ..Default::default()
+        },
+        vec![Some(1i64), None]
+    );
+
+    single_test_codegen!(
+        "array_prepend_coerced",
+        "array_prepend(non_nullable_i32, make_array(non_nullable_i64))",
+        arroyo_sql::TestStruct {
+            non_nullable_i32: 1,
+            non_nullable_i64: 2,
+            ..Default::default()
+        },
+        vec![1i64, 2i64]
+    );
+
+    single_test_codegen!(
+        "array_concat",
+        "array_concat(make_array(1, 2), make_array(non_nullable_i64))",
+        arroyo_sql::TestStruct {
+            non_nullable_i64: 3,
+            ..Default::default()
+        },
+        vec![1i64, 2i64, 3i64]
+    );
+
+    single_test_codegen!(
+        "array_length",
+        "array_length(make_array(non_nullable_string, 'b', 'c'))",
+        arroyo_sql::TestStruct::default(),
+        3u64
+    );
+
+    single_test_codegen!(
+        "cardinality_nested",
+        "cardinality(make_array(make_array(1, 2), make_array(non_nullable_i64)))",
+        arroyo_sql::TestStruct {
+            non_nullable_i64: 3,
+            ..Default::default()
+        },
+        3u64
+    );
+
+    single_test_codegen!(
+        "array_ndims",
+        "array_ndims(make_array(make_array(1, 2), make_array(3)))",
+        arroyo_sql::TestStruct::default(),
+        2u64
+    );
+
+    single_test_codegen!(
+        "array_has",
+        "array_has(make_array('a', non_nullable_string), 'b')",
+        arroyo_sql::TestStruct {
+            non_nullable_string: "b".into(),
+            ..Default::default()
+        },
+        true
+    );
+
+    single_test_codegen!(
+        "array_has_nullable_items",
+        "array_has(make_array(nullable_string, 'a'), 'b')",
+        arroyo_sql::TestStruct {
+            nullable_string: None,
+            ..Default::default()
+        },
+        false
+    );
+
+    single_test_codegen!(
+        "array_has_all",
+        "array_has_all(make_array(1, 2, non_nullable_i64), make_array(2, 3))",
+        arroyo_sql::TestStruct {
+            non_nullable_i64: 3,
+            ..Default::default()
+        },
+        true
+    );
+
+    single_test_codegen!(
+        "array_position",
+        "array_position(make_array(1, 2, 3, 2), non_nullable_i64)",
+        arroyo_sql::TestStruct {
+            non_nullable_i64: 2,
+            ..Default::default()
+        },
+        Some(2u64)
+    );
+
+    single_test_codegen!(
+        "array_positions",
+        "array_positions(make_array(1, 2, 3, 2), 2)",
+        arroyo_sql::TestStruct::default(),
+        vec![2u64, 4u64]
+    );
+
+    single_test_codegen!(
+        "array_element_negative",
+        "array_element(make_array(1, 2, non_nullable_i64), -1)",
+        arroyo_sql::TestStruct {
+            non_nullable_i64: 3,
+            ..Default::default()
+        },
+        Some(3i64)
+    );
+
+    single_test_codegen!(
+        "array_slice",
+        "array_slice(make_array(1, 2, 3, 4), 2, 3)",
+        arroyo_sql::TestStruct::default(),
+        vec![2i64, 3i64]
+    );
+
+    single_test_codegen!(
+        "array_remove_all",
+        "array_remove_all(make_array(1, 2, 1), 1)",
+        arroyo_sql::TestStruct::default(),
+        vec![2i64]
+    );
+
+    single_test_codegen!(
+        "array_replace_n",
+        "array_replace_n(make_array(1, 2, 1, 1), 1, 5, 2)",
+        arroyo_sql::TestStruct::default(),
+        vec![5i64, 2i64, 5i64, 1i64]
+    );
+
+    single_test_codegen!(
+        "array_repeat",
+        "array_repeat(non_nullable_string, 2)",
+        arroyo_sql::TestStruct {
+            non_nullable_string: "a".into(),
+            ..Default::default()
+        },
+        vec!["a".to_string(), "a".to_string()]
+    );
+
+    single_test_codegen!(
+        "array_to_string_null_string",
+        "array_to_string(make_array(nullable_string, 'b'), ',', '*')",
+        arroyo_sql::TestStruct {
+            nullable_string: None,
+            ..Default::default()
+        },
+        "*,b".to_string()
+    );
+
+    single_test_codegen!(
+        "array_to_string_null_delimiter",
+        "array_to_string(make_array('a', 'b'), nullable_string)",
+        arroyo_sql::TestStruct {
+            nullable_string: None,
+            ..Default::default()
+        },
+        None
+    );
+
+    single_test_codegen!(
+        "flatten",
+        "flatten(make_array(make_array(1, 2), make_array(non_nullable_i64)))",
+        arroyo_sql::TestStruct {
+            non_nullable_i64: 3,
+            ..Default::default()
+        },
+        vec![1i64, 2i64, 3i64]
+    );
     // test get_first_json_object
     single_test_codegen!(
         "get_first_json_object",
@@ -666,6 +666,107 @@ CREATE TABLE aggregates (
 );
 INSERT INTO aggregates SELECT min(counter), max(counter), sum(counter), count(*), avg(counter)  FROM impulse_source"}
 
+// statistics are null without enough values, and skip NaNs even in non-null columns
+correctness_run_codegen! {"statistics", 10,
+"CREATE TABLE impulse_source (
+  timestamp TIMESTAMP,
+  counter bigint unsigned not null,
+  subtask_index bigint unsigned not null
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/impulse.json',
+  format = 'json',
+  type = 'source'
+);
+CREATE TABLE statistics (
+  variance DOUBLE,
+  stddev DOUBLE,
+  var_pop DOUBLE,
+  stddev_pop DOUBLE,
+  median DOUBLE
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'debezium_json',
+  type = 'sink'
+);
+INSERT INTO statistics
+SELECT var_samp(counter), stddev(counter), var_pop(value), stddev_pop(value), approx_median(value)
+FROM (
+SELECT timestamp, counter,
+  CASE WHEN counter % 2 = 0 THEN sqrt(-1.0 - counter) ELSE CAST(counter AS DOUBLE) END as value
+FROM impulse_source
+)"}
+
+correctness_run_codegen! {"sliding_statistics", 10,
+"CREATE TABLE impulse_source (
+  timestamp TIMESTAMP,
+  counter bigint unsigned not null,
+  subtask_index bigint unsigned not null
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/impulse.json',
+  format = 'json',
+  type = 'source',
+  event_time_field = 'timestamp',
+  watermark_field = 'timestamp'
+);
+CREATE TABLE sliding_statistics (
+  end TIMESTAMP,
+  variance DOUBLE,
+  var_pop DOUBLE,
+  median DOUBLE
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'json',
+  type = 'sink'
+);
+INSERT INTO sliding_statistics
+SELECT window.end, variance, var_pop, median FROM (
+SELECT HOP(INTERVAL '200' millisecond, INTERVAL '600' millisecond) as window,
+  var_samp(value) as variance, var_pop(value) as var_pop, approx_median(value) as median
+FROM (
+SELECT timestamp, counter,
+  CASE WHEN counter % 2 = 0 THEN sqrt(-1.0 - counter) ELSE CAST(counter AS DOUBLE) END as value
+FROM impulse_source
+)
+GROUP BY 1)"}
+
+// count(distinct) can't be computed in two phases, so this aggregates the window's values at once
+correctness_run_codegen! {"tumbling_statistics", 2000,
+"CREATE TABLE cars (
+  timestamp TIMESTAMP,
+  driver_id BIGINT,
+  event_type TEXT,
+  location TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/cars.json',
+  format = 'json',
+  type = 'source',
+  event_time_field = 'timestamp'
+);
+CREATE TABLE tumbling_statistics (
+  end TIMESTAMP,
+  drivers BIGINT,
+  variance DOUBLE,
+  nan_variance DOUBLE,
+  median DOUBLE
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'json',
+  type = 'sink'
+);
+INSERT INTO tumbling_statistics
+SELECT window.end, drivers, variance, nan_variance, median FROM (
+SELECT TUMBLE(INTERVAL '1' hour) as window, count(distinct driver_id) as drivers,
+  var_samp(driver_id) as variance, var_pop(sqrt(-1.0 - driver_id)) as nan_variance,
+  approx_median(driver_id) as median
+FROM cars
+GROUP BY 1)"}
+
 // test double negative UDF
 correctness_run_codegen! {"double_negative_udf", 10,
 "CREATE TABLE impulse_source (
@@ -717,6 +818,77 @@ SELECT double_negative(counter) FROM impulse_source",
   -2 * (x as i64)
 }"}
 
+// test table functions
+correctness_run_codegen! {"table_function", 10,
+"CREATE TABLE impulse_source (
+  timestamp TIMESTAMP,
+  counter bigint unsigned not null,
+  subtask_index bigint unsigned not null
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/impulse.json',
+  format = 'json',
+  type = 'source'
+);
+CREATE TABLE table_function (
+  number bigint,
+  digit bigint
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'json',
+  type = 'sink'
+);
+INSERT INTO table_function
+SELECT d.number, d.digit FROM impulse_source CROSS JOIN LATERAL digits(counter) AS d
+WHERE d.position > 0",
+"struct Digit {
+  number: i64,
+  digit: i64,
+  position: i64
+}
+pub fn digits(x: u64) -> Vec<Digit> {
+  x.to_string()
+    .chars()
+    .enumerate()
+    .map(|(position, c)| Digit {
+      number: x as i64,
+      digit: c.to_digit(10).unwrap() as i64,
+      position: position as i64,
+    })
+    .collect()
+}"}
+
+correctness_run_codegen! {"async_table_function", 10,
+"CREATE TABLE impulse_source (
+  timestamp TIMESTAMP,
+  counter bigint unsigned not null,
+  subtask_index bigint unsigned not null
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/impulse.json',
+  format = 'json',
+  type = 'source'
+);
+CREATE TABLE async_table_function (
+  value bigint
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'json',
+  type = 'sink'
+);
+INSERT INTO async_table_function
+SELECT r.value FROM impulse_source, LATERAL repeat_counter(counter) r",
+"struct Repeated {
+  value: i64
+}
+pub async fn repeat_counter(x: u64) -> Vec<Repeated> {
+  (0..x % 3)
+    .map(|i| Repeated { value: (x * 10 + i) as i64 })
+    .collect()
+}"}
+
 // test UDAF
 correctness_run_codegen! {"udaf", 10,
 "CREATE TABLE impulse_source (
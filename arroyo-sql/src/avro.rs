@@ -1,26 +1,39 @@
 use crate::types::{StructDef, StructField, TypeDef};
 use anyhow::{anyhow, bail};
 use apache_avro::Schema;
-use arrow_schema::{DataType, TimeUnit};
-use arroyo_rpc::formats::AvroFormat;
+use arrow_schema::{DataType, Field, TimeUnit, DECIMAL128_MAX_PRECISION};
+use arroyo_formats::avro::debezium_row_schema;
+use arroyo_rpc::formats::{AvroFormat, Format};
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
+use std::sync::Arc;
 
 pub const ROOT_NAME: &str = "ArroyoAvroRoot";
 
-pub fn convert_avro_schema(name: &str, schema: &str) -> anyhow::Result<Vec<StructField>> {
-    convert_avro_schema_helper(name, schema, false)
+/// Converts an avro schema into SQL fields. For debezium, the schema is that of the change-event
+/// envelope, and the fields are those of the rows within it.
+pub fn convert_avro_schema(
+    name: &str,
+    schema: &str,
+    debezium: bool,
+) -> anyhow::Result<Vec<StructField>> {
+    convert_avro_schema_helper(name, schema, debezium, false)
 }
 
 fn convert_avro_schema_helper(
     name: &str,
     schema: &str,
+    debezium: bool,
     for_generation: bool,
 ) -> anyhow::Result<Vec<StructField>> {
-    let schema =
+    let mut schema =
         Schema::parse_str(schema).map_err(|e| anyhow!("avro schema is not valid: {:?}", e))?;
 
-    let (typedef, _) = to_typedef(name, &schema, for_generation);
+    if debezium {
+        schema = debezium_row_schema(&schema)?;
+    }
+
+    let (typedef, _) = to_typedef(name, &schema, for_generation)?;
     match typedef {
         TypeDef::StructDef(sd, _) => Ok(sd.fields),
         TypeDef::DataType(_, _) => {
@@ -29,16 +42,19 @@ fn convert_avro_schema_helper(
     }
 }
 
-pub fn get_defs(name: &str, schema: &str) -> anyhow::Result<String> {
-    let fields = convert_avro_schema_helper(name, schema, true)?;
+pub fn get_defs(name: &str, schema: &str, format: &AvroFormat) -> anyhow::Result<String> {
+    let fields = convert_avro_schema_helper(name, schema, format.debezium, true)?;
 
-    let sd = StructDef::new(Some(ROOT_NAME.to_string()), true, fields, None);
+    // the structs are deserialized from the json representation of avro values, which for some
+    // logical types differs from what they otherwise use
+    let format = Some(Format::Avro(format.clone()));
+    let sd = StructDef::new(Some(ROOT_NAME.to_string()), true, fields, format.clone());
     let defs: Vec<_> = sd
         .all_structs_including_named()
         .iter()
         .map(|p| {
             vec![
-                syn::parse_str(&p.def(false)).unwrap(),
+                syn::parse_str(&p.with_format(format.clone()).def(false)).unwrap(),
                 p.generate_serializer_items(),
             ]
         })
@@ -60,41 +76,73 @@ fn to_typedef(
     source_name: &str,
     schema: &Schema,
     for_generation: bool,
-) -> (TypeDef, Option<String>) {
-    match schema {
-        Schema::Null => (TypeDef::DataType(DataType::Null, false), None),
+) -> anyhow::Result<(TypeDef, Option<String>)> {
+    Ok(match schema {
+        Schema::Null => bail!("null fields are not supported outside of unions"),
         Schema::Boolean => (TypeDef::DataType(DataType::Boolean, false), None),
         Schema::Int | Schema::TimeMillis => (TypeDef::DataType(DataType::Int32, false), None),
         Schema::Long
         | Schema::TimeMicros
-        | Schema::TimestampMillis
         | Schema::LocalTimestampMillis
         | Schema::LocalTimestampMicros => (TypeDef::DataType(DataType::Int64, false), None),
+        Schema::TimestampMillis => (
+            TypeDef::DataType(DataType::Timestamp(TimeUnit::Millisecond, None), false),
+            None,
+        ),
+        Schema::TimestampMicros => (
+            TypeDef::DataType(DataType::Timestamp(TimeUnit::Microsecond, None), false),
+            None,
+        ),
+        Schema::Date => (TypeDef::DataType(DataType::Date32, false), None),
+        Schema::Duration => (
+            TypeDef::DataType(DataType::Duration(TimeUnit::Microsecond), false),
+            None,
+        ),
         Schema::Float => (TypeDef::DataType(DataType::Float32, false), None),
         Schema::Double => (TypeDef::DataType(DataType::Float64, false), None),
-        Schema::Bytes | Schema::Fixed(_) | Schema::Decimal(_) => {
-            (TypeDef::DataType(DataType::Binary, false), None)
+        Schema::Decimal(decimal) => {
+            let precision = u8::try_from(decimal.precision)
+                .ok()
+                .filter(|p| *p <= DECIMAL128_MAX_PRECISION)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "decimals with precision {} are not supported (the maximum is {})",
+                        decimal.precision,
+                        DECIMAL128_MAX_PRECISION
+                    )
+                })?;
+            if decimal.scale > precision as usize {
+                bail!(
+                    "decimal scale {} is larger than its precision {}",
+                    decimal.scale,
+                    precision
+                );
+            }
+            (
+                TypeDef::DataType(DataType::Decimal128(precision, decimal.scale as i8), false),
+                None,
+            )
         }
+        Schema::Bytes | Schema::Fixed(_) => (TypeDef::DataType(DataType::Binary, false), None),
         Schema::String | Schema::Enum(_) | Schema::Uuid => {
             (TypeDef::DataType(DataType::Utf8, false), None)
         }
         Schema::Union(union) => {
-            // currently just support unions that have [t, null] as variants, which is the
-            // avro way to represent optional fields
+            // unions that have [t, null] as variants are the avro way to represent optional
+            // fields; other unions are passed through as json
 
             let (nulls, not_nulls): (Vec<_>, Vec<_>) = union
                 .variants()
                 .iter()
                 .partition(|v| matches!(v, Schema::Null));
 
-            if nulls.len() == 1 && not_nulls.len() == 1 {
-                let (dt, original) = to_typedef(source_name, not_nulls[0], for_generation);
-                (dt.to_optional(), original)
-            } else {
-                (
-                    TypeDef::DataType(DataType::Utf8, false),
-                    Some("json".to_string()),
-                )
+            match (nulls.len(), not_nulls.len()) {
+                (1, 1) => {
+                    let (dt, original) = to_typedef(source_name, not_nulls[0], for_generation)?;
+                    (dt.to_optional(), original)
+                }
+                (_, 0) => bail!("unions must have at least one non-null variant"),
+                _ => json_typedef(),
             }
         }
         Schema::Record(record) => {
@@ -102,10 +150,17 @@ fn to_typedef(
                 .fields
                 .iter()
                 .map(|f| {
-                    let (ft, original) = to_typedef(source_name, &f.schema, for_generation);
-                    StructField::with_rename(f.name.clone(), None, ft, None, original)
+                    let (ft, original) = to_typedef(source_name, &f.schema, for_generation)
+                        .map_err(|e| anyhow!("invalid type for field '{}': {}", f.name, e))?;
+                    Ok(StructField::with_rename(
+                        f.name.clone(),
+                        None,
+                        ft,
+                        None,
+                        original,
+                    ))
                 })
-                .collect();
+                .collect::<anyhow::Result<Vec<_>>>()?;
 
             let name = if for_generation {
                 // if we're generating the actual structs, we don't want to namespace
@@ -120,14 +175,47 @@ fn to_typedef(
                 None,
             )
         }
-        _ => (
-            TypeDef::DataType(DataType::Utf8, false),
-            Some("json".to_string()),
-        ),
+        Schema::Array(items) => match to_typedef(source_name, items, for_generation)? {
+            (TypeDef::DataType(item, nullable), None) if is_plain_list_item(&item) => (
+                TypeDef::DataType(
+                    DataType::List(Arc::new(Field::new("item", item, nullable))),
+                    false,
+                ),
+                None,
+            ),
+            // lists of records and of values that need custom deserialization can't be
+            // represented in the generated structs, so they're passed through as json
+            _ => json_typedef(),
+        },
+        // maps have no representation in the generated structs, and references to named types
+        // are not resolved, so these are passed through as json
+        Schema::Map(_) | Schema::Ref { .. } => json_typedef(),
+    })
+}
+
+fn json_typedef() -> (TypeDef, Option<String>) {
+    (
+        TypeDef::DataType(DataType::Utf8, false),
+        Some("json".to_string()),
+    )
+}
+
+/// Returns true if lists of this type can be deserialized without any field attributes
+fn is_plain_list_item(dt: &DataType) -> bool {
+    match dt {
+        DataType::Boolean
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Utf8 => true,
+        DataType::List(item) => is_plain_list_item(item.data_type()),
+        _ => false,
     }
 }
 
-/// Generates code that serializes an arroyo data struct into avro
+/// Generates code that serializes an arroyo data struct into avro, or returns an error if it has
+/// fields with types that can't be serialized (which sinks check for when planning)
 ///
 /// Note that this must align with the schemas constructed in
 /// `arroyo-formats::avro::arrow_to_avro_schema`!
@@ -136,8 +224,7 @@ pub fn generate_serializer_item(
     field: Option<&Ident>,
     name: Option<String>,
     td: &TypeDef,
-) -> TokenStream {
-    use DataType::*;
+) -> anyhow::Result<TokenStream> {
     let value = match field {
         Some(ident) => quote!(#record.#ident),
         None => quote!(#record),
@@ -159,13 +246,14 @@ pub fn generate_serializer_item(
                         Some(&field_ident),
                         Some(name.clone()),
                         &f.data_type,
-                    );
+                    )
+                    .map_err(|e| anyhow!("field '{}': {}", f.name(), e))?;
 
-                    quote! {
+                    Ok(quote! {
                         __avro_record.put(#name, #serializer);
-                    }
+                    })
                 })
-                .collect();
+                .collect::<anyhow::Result<_>>()?;
 
             let schema_extractor = name.map(|name| {
                 let nullable_handler = if *nullable {
@@ -206,55 +294,194 @@ pub fn generate_serializer_item(
                 *nullable,
             )
         }
-        TypeDef::DataType(dt, nullable) => (
-            match dt {
-                Null => unreachable!("null fields are not supported"),
-                Boolean => quote! { Boolean(*v) },
-                Int8 | Int16 | Int32 | UInt8 | UInt16 => quote! { Int(*v as i32) },
-                Int64 | UInt32 | UInt64 => quote! { Long(*v as i64) },
-                Float16 | Float32 => quote! { Float(*v as f32) },
-                Float64 => quote! { Double(*v as f64) },
-                Timestamp(t, tz) => match (t, tz) {
-                    (TimeUnit::Microsecond | TimeUnit::Nanosecond, None) => {
-                        quote! { TimestampMicros(arroyo_types::to_micros(*v) as i64) }
-                    }
-                    (TimeUnit::Microsecond | TimeUnit::Nanosecond, Some(_)) => {
-                        quote! { LocalTimestampMicros(arroyo_types::to_micros(*v) as i64) }
-                    }
-                    (TimeUnit::Millisecond | TimeUnit::Second, None) => {
-                        quote! { Long(arroyo_types::to_millis(*v) as i64) }
-                    }
-                    (TimeUnit::Millisecond | TimeUnit::Second, Some(_)) => {
-                        quote! { LocalTimestampMillis(arroyo_types::to_millis(*v) as i64) }
-                    }
-                },
-                Date32 | Date64 => quote! { Date(arroyo_types::days_since_epoch(*v)) },
-                Time32(_) => todo!("time32 is not supported"),
-                Time64(_) => todo!("time64 is not supported"),
-                Duration(_) => todo!("duration is not supported"),
-                Interval(_) => todo!("interval is not supported"),
-                Binary | FixedSizeBinary(_) | LargeBinary => quote! { Bytes(v.clone()) },
-                Utf8 | LargeUtf8 => quote! { String(v.clone()) },
-                List(_) | FixedSizeList(_, _) | LargeList(_) => {
-                    todo!("lists are not supported")
-                }
-                Struct(_) => unreachable!("typedefs should not contain structs"),
-                Union(_, _) => unimplemented!("unions are not supported"),
-                Dictionary(_, _) => unimplemented!("dictionaries are not supported"),
-                Decimal128(_, _) => unimplemented!("decimal128 is not supported"),
-                Decimal256(_, _) => unimplemented!("decimal256 is not supported"),
-                Map(_, _) => unimplemented!("maps are not supported"),
-                RunEndEncoded(_, _) => unimplemented!("run end encoded is not supported"),
-            },
-            *nullable,
-        ),
+        TypeDef::DataType(dt, nullable) => (data_type_serializer(dt)?, *nullable),
     };
 
-    if nullable {
+    Ok(if nullable {
         quote! {
             Union(#value.is_some() as u32, Box::new(#value.as_ref().map(|v| #inner).unwrap_or(Null)))
         }
     } else {
         quote! {{let v = &#value; #inner}}
+    })
+}
+
+/// Generates code that converts a value of the given type (bound as `v: &T`) into an avro value
+fn data_type_serializer(dt: &DataType) -> anyhow::Result<TokenStream> {
+    use DataType::*;
+    Ok(match dt {
+        Null => quote! { Null },
+        Boolean => quote! { Boolean(*v) },
+        Int8 | Int16 | Int32 | UInt8 | UInt16 => quote! { Int(*v as i32) },
+        Int64 | UInt32 | UInt64 => quote! { Long(*v as i64) },
+        Float16 | Float32 => quote! { Float(*v as f32) },
+        Float64 => quote! { Double(*v as f64) },
+        Timestamp(t, tz) => match (t, tz) {
+            (TimeUnit::Microsecond | TimeUnit::Nanosecond, None) => {
+                quote! { TimestampMicros(arroyo_types::to_micros(*v) as i64) }
+            }
+            (TimeUnit::Microsecond | TimeUnit::Nanosecond, Some(_)) => {
+                quote! { LocalTimestampMicros(arroyo_types::to_micros(*v) as i64) }
+            }
+            (TimeUnit::Millisecond | TimeUnit::Second, None) => {
+                quote! { Long(arroyo_types::to_millis(*v) as i64) }
+            }
+            (TimeUnit::Millisecond | TimeUnit::Second, Some(_)) => {
+                quote! { LocalTimestampMillis(arroyo_types::to_millis(*v) as i64) }
+            }
+        },
+        Date32 | Date64 => quote! { Date(arroyo_types::days_since_epoch(*v)) },
+        Duration(_) | Interval(_) => {
+            // durations are represented as std::time::Duration, which we split into whole days
+            // and the remaining millis
+            quote! {
+                arroyo_worker::apache_avro::types::Value::Duration(arroyo_worker::apache_avro::Duration::new(
+                    arroyo_worker::apache_avro::Months::new(0),
+                    arroyo_worker::apache_avro::Days::new((v.as_secs() / 86_400) as u32),
+                    arroyo_worker::apache_avro::Millis::new((v.as_millis() % 86_400_000) as u32),
+                ))
+            }
+        }
+        // decimals are represented by their unscaled value, which avro encodes as big-endian
+        // two's-complement bytes
+        Decimal128(_, _) => {
+            quote! { Decimal(arroyo_worker::apache_avro::Decimal::from(v.to_be_bytes().to_vec())) }
+        }
+        Binary | FixedSizeBinary(_) | LargeBinary => quote! { Bytes(v.clone()) },
+        Utf8 | LargeUtf8 => quote! { String(v.clone()) },
+        List(item) | FixedSizeList(item, _) | LargeList(item) => {
+            let inner = data_type_serializer(item.data_type())?;
+            if item.is_nullable() {
+                quote! {
+                    Array(v.iter().map(|v| Union(v.is_some() as u32, Box::new(v.as_ref().map(|v| #inner).unwrap_or(Null)))).collect())
+                }
+            } else {
+                quote! {
+                    Array(v.iter().map(|v| #inner).collect())
+                }
+            }
+        }
+        // structs are only serialized as StructDefs, and these types do not have a rust
+        // representation in generated structs
+        Time32(_)
+        | Time64(_)
+        | Struct(_)
+        | Union(_, _)
+        | Dictionary(_, _)
+        | Decimal256(_, _)
+        | Map(_, _)
+        | RunEndEncoded(_, _) => bail!("values of type {} cannot be serialized as avro", dt),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_avro_schema() {
+        let schema = r#"{
+            "type": "record",
+            "name": "Order",
+            "fields": [
+                {"name": "id", "type": "bytes"},
+                {"name": "price", "type": {"type": "bytes", "logicalType": "decimal", "precision": 10, "scale": 2}},
+                {"name": "discount", "type": ["null", {"type": "bytes", "logicalType": "decimal", "precision": 10, "scale": 2}]},
+                {"name": "items", "type": {"type": "array", "items": "string"}},
+                {"name": "attributes", "type": {"type": "map", "values": "long"}},
+                {"name": "value", "type": ["null", "string", "long"]}
+            ]
+        }"#;
+
+        let fields = convert_avro_schema("orders", schema, false).unwrap();
+
+        assert_eq!(
+            fields[0].data_type,
+            TypeDef::DataType(DataType::Binary, false)
+        );
+        assert_eq!(
+            fields[1].data_type,
+            TypeDef::DataType(DataType::Decimal128(10, 2), false)
+        );
+        assert_eq!(
+            fields[2].data_type,
+            TypeDef::DataType(DataType::Decimal128(10, 2), true)
+        );
+        assert_eq!(
+            fields[3].data_type,
+            TypeDef::DataType(
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, false))),
+                false
+            )
+        );
+        for field in &fields[4..] {
+            assert_eq!(field.original_type.as_deref(), Some("json"));
+        }
+
+        get_defs("orders", schema, &AvroFormat::new(false, false, false)).unwrap();
+
+        let null_field = r#"{
+            "type": "record",
+            "name": "Nulls",
+            "fields": [{"name": "nothing", "type": "null"}]
+        }"#;
+        assert!(convert_avro_schema("nulls", null_field, false).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_serializer_types() {
+        let struct_type = DataType::Struct(vec![Field::new("a", DataType::Int64, false)].into());
+        let unsupported = [
+            DataType::Time32(TimeUnit::Millisecond),
+            DataType::Time64(TimeUnit::Microsecond),
+            struct_type.clone(),
+            DataType::List(Arc::new(Field::new("item", struct_type, false))),
+            DataType::Union(
+                arrow_schema::UnionFields::empty(),
+                arrow_schema::UnionMode::Dense,
+            ),
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+            DataType::Decimal256(40, 2),
+            DataType::Map(
+                Arc::new(Field::new(
+                    "entries",
+                    DataType::Struct(
+                        vec![
+                            Field::new("key", DataType::Utf8, false),
+                            Field::new("value", DataType::Int64, true),
+                        ]
+                        .into(),
+                    ),
+                    false,
+                )),
+                false,
+            ),
+            DataType::RunEndEncoded(
+                Arc::new(Field::new("run_ends", DataType::Int32, false)),
+                Arc::new(Field::new("values", DataType::Utf8, true)),
+            ),
+        ];
+
+        for dt in unsupported {
+            assert!(
+                data_type_serializer(&dt).is_err(),
+                "{} should not be serializable",
+                dt
+            );
+        }
+
+        for dt in [
+            DataType::Decimal128(10, 2),
+            DataType::Binary,
+            DataType::Date32,
+            DataType::Duration(TimeUnit::Microsecond),
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+        ] {
+            assert!(
+                data_type_serializer(&dt).is_ok(),
+                "{} should be serializable",
+                dt
+            );
+        }
     }
 }
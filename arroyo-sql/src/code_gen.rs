@@ -3,6 +3,7 @@ use quote::ToTokens;
 use quote::{format_ident, quote};
 use syn::parse_quote;
 
+use crate::expressions::SortExpression;
 use crate::types::{data_type_as_syn_type, StructDef, TypeDef};
 
 pub trait CodeGenerator<Context, OutputValue, OutputType: ToTokens> {
@@ -335,6 +336,8 @@ pub enum BinType {
     Option(Box<BinType>),
     Tuple(Vec<BinType>),
     BTreeMap(Box<BinType>, Box<BinType>),
+    // the key of an aggregate's ORDER BY clause
+    SortKey(Vec<SortExpression>),
 }
 
 impl BinType {
@@ -362,6 +365,7 @@ impl BinType {
                 parse_quote!(std::collections::BTreeMap<#key_ident, #value_ident>)
             }
             BinType::Usize => parse_quote!(usize),
+            BinType::SortKey(order_by) => SortExpression::sort_tuple_type(order_by),
         }
     }
 }
@@ -379,14 +383,20 @@ impl ValueBinMergingContext {
         }
     }
 
+    // event time of the incoming record, used by aggregates like first_value.
+    pub fn timestamp_ident(&self) -> syn::Ident {
+        parse_quote!(timestamp)
+    }
+
     pub fn compile_closure_with_some_result<CG: CodeGenerator<Self, BinType, syn::Expr>>(
         &self,
         code_generator: &CG,
     ) -> syn::ExprClosure {
         let expr = code_generator.generate(self);
         let value_ident = self.value_context.variable_ident();
+        let timestamp_ident = self.timestamp_ident();
         let bin_ident = self.bin_context.current_bin_ident();
-        parse_quote!(|#value_ident, #bin_ident| { Some(#expr) })
+        parse_quote!(|#value_ident, #timestamp_ident, #bin_ident| { Some(#expr) })
     }
 
     pub fn compile_closure<CG: CodeGenerator<Self, BinType, syn::Expr>>(
@@ -395,8 +405,9 @@ impl ValueBinMergingContext {
     ) -> syn::ExprClosure {
         let expr = code_generator.generate(self);
         let value_ident = self.value_context.variable_ident();
+        let timestamp_ident = self.timestamp_ident();
         let bin_ident = self.bin_context.current_bin_ident();
-        parse_quote!(|#value_ident, #bin_ident| { #expr })
+        parse_quote!(|#value_ident, #timestamp_ident, #bin_ident| { #expr })
     }
 
     pub fn bin_syn_type<CG: CodeGenerator<Self, BinType, syn::Expr>>(
@@ -5,7 +5,10 @@ use crate::{
     operators::TwoPhaseAggregation,
     pipeline::{JoinType, SortDirection, SqlOperator},
     schemas::window_type_def,
-    types::{interval_month_day_nanos_to_duration, StructDef, StructField, TypeDef},
+    types::{
+        data_type_as_syn_type, interval_month_day_nanos_to_duration, StructDef, StructField,
+        TypeDef,
+    },
     ArroyoSchemaProvider,
 };
 use anyhow::{anyhow, bail, Ok, Result};
@@ -73,6 +76,12 @@ impl CodeGenerator<JoinPairContext, StructDef, syn::Expr> for JoinType {
         let left_ident = input_context.left_ident();
         let right_ident = input_context.right_ident();
 
+        match self {
+            JoinType::LeftSemi | JoinType::LeftAnti => return parse_quote!(#left_ident.clone()),
+            JoinType::RightSemi | JoinType::RightAnti => return parse_quote!(#right_ident.clone()),
+            _ => {}
+        }
+
         let mut assignments: Vec<_> = vec![];
 
         left_struct.fields.iter().for_each(|field| {
@@ -111,28 +120,7 @@ impl CodeGenerator<JoinPairContext, StructDef, syn::Expr> for JoinType {
     }
 
     fn expression_type(&self, input_context: &JoinPairContext) -> StructDef {
-        let left_struct = &input_context.left_struct;
-        let right_struct = &input_context.right_struct;
-        // input to join should always be two structs. Nullability determined by join type.
-        let mut fields = if self.left_nullable() {
-            left_struct
-                .fields
-                .iter()
-                .map(|field| field.as_nullable())
-                .collect()
-        } else {
-            left_struct.fields.clone()
-        };
-        if self.right_nullable() {
-            right_struct
-                .fields
-                .iter()
-                .map(|field| field.as_nullable())
-                .for_each(|field| fields.push(field))
-        } else {
-            fields.append(&mut right_struct.fields.clone());
-        }
-        StructDef::for_fields(fields)
+        self.output_struct(&input_context.left_struct, &input_context.right_struct)
     }
 }
 
@@ -224,6 +212,42 @@ impl CodeGenerator<JoinListsContext, StructDef, syn::Expr> for JoinType {
                     }
                 )
             }
+            JoinType::LeftSemi => {
+                parse_quote!({
+                    if #right_list_ident.is_empty() {
+                        vec![]
+                    } else {
+                        #left_list_ident.clone()
+                    }
+                })
+            }
+            JoinType::LeftAnti => {
+                parse_quote!({
+                    if #right_list_ident.is_empty() {
+                        #left_list_ident.clone()
+                    } else {
+                        vec![]
+                    }
+                })
+            }
+            JoinType::RightSemi => {
+                parse_quote!({
+                    if #left_list_ident.is_empty() {
+                        vec![]
+                    } else {
+                        #right_list_ident.clone()
+                    }
+                })
+            }
+            JoinType::RightAnti => {
+                parse_quote!({
+                    if #left_list_ident.is_empty() {
+                        #right_list_ident.clone()
+                    } else {
+                        vec![]
+                    }
+                })
+            }
         }
     }
 
@@ -480,7 +504,7 @@ impl Expression {
                 (&mut *e.struct_expression).traverse_mut(context, f);
             }
             Expression::Aggregation(e) => {
-                (&mut *e.producing_expression).traverse_mut(context, f);
+                e.expressions().for_each(|e| e.traverse_mut(context, f));
             }
             Expression::Cast(e) => {
                 (&mut *e.input).traverse_mut(context, f);
@@ -526,6 +550,11 @@ impl Expression {
                         e.traverse_mut(context, f);
                     }
                 }
+                DataStructureFunction::Array { function: _, args } => {
+                    for e in args {
+                        e.traverse_mut(context, f);
+                    }
+                }
             },
             Expression::Json(e) => {
                 (&mut *e.json_string).traverse_mut(context, f);
@@ -589,6 +618,38 @@ pub struct ExpressionContext<'a> {
 }
 
 impl<'a> ExpressionContext<'a> {
+    /// Decimals are computed on their unscaled values, which is only correct for comparisons,
+    /// addition and subtraction between decimals of the same scale
+    fn check_decimal_operands(
+        left: &Expression,
+        op: &datafusion_expr::Operator,
+        right: &Expression,
+    ) -> Result<()> {
+        let scale = |e: &Expression| match e.expression_type(&ValuePointerContext::new()) {
+            TypeDef::DataType(DataType::Decimal128(_, scale), _) => Some(scale),
+            _ => None,
+        };
+
+        match (scale(left), scale(right)) {
+            (None, None) => Ok(()),
+            (Some(left), Some(right))
+                if left == right
+                    && !matches!(
+                        op,
+                        datafusion_expr::Operator::Multiply
+                            | datafusion_expr::Operator::Divide
+                            | datafusion_expr::Operator::Modulo
+                    ) =>
+            {
+                Ok(())
+            }
+            _ => bail!(
+                "operator {} is not supported for these DECIMAL operands; cast them to DOUBLE or to DECIMALs of the same scale first",
+                op
+            ),
+        }
+    }
+
     pub fn compile_expr(&self, expression: &Expr) -> Result<Expression> {
         match expression {
             Expr::Alias(datafusion_expr::expr::Alias { expr, name: _ }) => self.compile_expr(expr),
@@ -607,20 +668,39 @@ impl<'a> ExpressionContext<'a> {
                 | datafusion_expr::Operator::IsDistinctFrom
                 | datafusion_expr::Operator::IsNotDistinctFrom
                 | datafusion_expr::Operator::And
-                | datafusion_expr::Operator::Or => Ok(BinaryComparisonExpression::new(
-                    Box::new(self.compile_expr(left)?),
-                    *op,
-                    Box::new(self.compile_expr(right)?),
-                )?),
+                | datafusion_expr::Operator::Or => {
+                    let (left, right) = (self.compile_expr(left)?, self.compile_expr(right)?);
+                    Self::check_decimal_operands(&left, op, &right)?;
+                    Ok(BinaryComparisonExpression::new(
+                        Box::new(left),
+                        *op,
+                        Box::new(right),
+                    )?)
+                }
                 datafusion_expr::Operator::Plus
                 | datafusion_expr::Operator::Minus
                 | datafusion_expr::Operator::Multiply
                 | datafusion_expr::Operator::Divide
-                | datafusion_expr::Operator::Modulo => BinaryMathExpression::new(
-                    Box::new(self.compile_expr(left)?),
-                    *op,
-                    Box::new(self.compile_expr(right)?),
-                ),
+                | datafusion_expr::Operator::Modulo => {
+                    let (left, right) = (self.compile_expr(left)?, self.compile_expr(right)?);
+                    Self::check_decimal_operands(&left, op, &right)?;
+                    let is_time = |e: &Expression| {
+                        matches!(
+                            e.expression_type(&ValuePointerContext::new()),
+                            TypeDef::DataType(
+                                DataType::Timestamp(_, _) | DataType::Date32 | DataType::Date64,
+                                _
+                            )
+                        )
+                    };
+                    if is_time(&left) && is_time(&right) {
+                        bail!(
+                            "operator {} is not supported between timestamps or dates",
+                            op
+                        );
+                    }
+                    BinaryMathExpression::new(Box::new(left), *op, Box::new(right))
+                }
                 datafusion_expr::Operator::StringConcat => {
                     Ok(Expression::String(StringFunction::Concat(vec![
                         self.compile_expr(left)?,
@@ -679,30 +759,9 @@ impl<'a> ExpressionContext<'a> {
             Expr::GetIndexedField(datafusion_expr::GetIndexedField { expr, field }) => {
                 StructFieldExpression::new(Box::new(self.compile_expr(expr)?), field)
             }
-            Expr::AggregateFunction(datafusion_expr::expr::AggregateFunction {
-                fun,
-                args,
-                distinct,
-                filter,
-                order_by,
-            }) => {
-                if args.len() != 1 {
-                    bail!("multiple aggregation parameters is not yet supported");
-                }
-
-                if filter.is_some() {
-                    bail!("filters in aggregations is not yet supported");
-                }
-                if order_by.is_some() {
-                    bail!("order by in aggregations is not yet supported");
-                }
-
-                Ok(AggregationExpression::new(
-                    Box::new(self.compile_expr(&args[0])?),
-                    fun.clone(),
-                    *distinct,
-                )?)
-            }
+            Expr::AggregateFunction(aggregate_function) => Ok(Expression::Aggregation(
+                AggregationExpression::try_from_aggregate_function(self, aggregate_function)?,
+            )),
             Expr::AggregateUDF { .. } => bail!("aggregate UDFs not supported"),
             Expr::Case(datafusion_expr::Case {
                 expr,
@@ -925,9 +984,10 @@ impl<'a> ExpressionContext<'a> {
                     | BuiltinScalarFunction::ArrayReplaceN
                     | BuiltinScalarFunction::ArrayReplaceAll
                     | BuiltinScalarFunction::ArraySlice
-                    | BuiltinScalarFunction::Flatten => {
-                        bail!("array functions not implemented yet")
-                    }
+                    | BuiltinScalarFunction::Flatten => DataStructureFunction::array(
+                        ArrayFunction::from_builtin(fun).unwrap(),
+                        arg_expressions,
+                    ),
                     BuiltinScalarFunction::Isnan => todo!(),
                     BuiltinScalarFunction::Iszero => todo!(),
                     BuiltinScalarFunction::Nanvl => todo!(),
@@ -1008,6 +1068,7 @@ impl<'a> ExpressionContext<'a> {
                         async_fn: def.async_fn,
                         opts: def.opts.clone(),
                         has_context: def.has_context,
+                        wasm: def.wasm.is_some(),
                     };
 
                     if def.async_fn {
@@ -1122,6 +1183,10 @@ impl ColumnExpression {
         )?;
         Ok(ColumnExpression { column_field })
     }
+
+    pub fn name(&self) -> &str {
+        &self.column_field.name
+    }
 }
 
 impl CodeGenerator<ValuePointerContext, TypeDef, syn::Expr> for ColumnExpression {
@@ -1211,7 +1276,10 @@ pub struct LiteralExpression {
 
 impl LiteralExpression {
     fn expression_type(&self, _input_context: &ValuePointerContext) -> TypeDef {
-        TypeDef::DataType(self.literal.get_datatype(), self.literal.is_null())
+        TypeDef::DataType(
+            TypeDef::get_literal_data_type(&self.literal),
+            self.literal.is_null(),
+        )
     }
 
     fn new(literal: ScalarValue) -> Expression {
@@ -1225,7 +1293,10 @@ impl CodeGenerator<ValuePointerContext, TypeDef, syn::Expr> for LiteralExpressio
     }
 
     fn expression_type(&self, _input_context: &ValuePointerContext) -> TypeDef {
-        TypeDef::DataType(self.literal.get_datatype(), self.literal.is_null())
+        TypeDef::DataType(
+            TypeDef::get_literal_data_type(&self.literal),
+            self.literal.is_null(),
+        )
     }
 }
 
@@ -1584,6 +1655,16 @@ impl AggregateComputation {
                     computation,
                 })
             }
+            Expr::AggregateUDF(aggregate_udf)
+                if aggregate_udf.fun.name == STRING_AGG
+                    && !ctx.schema_provider.udf_defs.contains_key(STRING_AGG) =>
+            {
+                let computation = AggregationExpression::try_from_string_agg(ctx, aggregate_udf)?;
+                Ok(Self::Builtin {
+                    column: Column::convert(column),
+                    computation,
+                })
+            }
             Expr::AggregateUDF(aggregate_udf) => {
                 let computation = RustUdafExpression::try_from_aggregate_udf(ctx, aggregate_udf)?;
                 Ok(Self::UDAF {
@@ -1633,6 +1714,17 @@ pub enum Aggregator {
     Max,
     Avg,
     CountDistinct,
+    Variance,
+    VariancePop,
+    Stddev,
+    StddevPop,
+    // the percentile is kept as the bits of an f64, so that the aggregator stays hashable
+    ApproxPercentileCont(u64),
+    FirstValue,
+    LastValue,
+    ArrayAgg,
+    StringAgg(String),
+    ApproxDistinct,
 }
 
 impl Aggregator {
@@ -1647,11 +1739,39 @@ impl Aggregator {
             (datafusion_expr::AggregateFunction::Max, false) => Ok(Self::Max),
             (datafusion_expr::AggregateFunction::Avg, false) => Ok(Self::Avg),
             (datafusion_expr::AggregateFunction::Count, true) => Ok(Self::CountDistinct),
+            (datafusion_expr::AggregateFunction::Variance, false) => Ok(Self::Variance),
+            (datafusion_expr::AggregateFunction::VariancePop, false) => Ok(Self::VariancePop),
+            (datafusion_expr::AggregateFunction::Stddev, false) => Ok(Self::Stddev),
+            (datafusion_expr::AggregateFunction::StddevPop, false) => Ok(Self::StddevPop),
+            (datafusion_expr::AggregateFunction::ApproxMedian, false) => {
+                Self::approx_percentile(0.5)
+            }
+            (datafusion_expr::AggregateFunction::FirstValue, false) => Ok(Self::FirstValue),
+            (datafusion_expr::AggregateFunction::LastValue, false) => Ok(Self::LastValue),
+            (datafusion_expr::AggregateFunction::ArrayAgg, false) => Ok(Self::ArrayAgg),
+            (datafusion_expr::AggregateFunction::ApproxDistinct, false) => Ok(Self::ApproxDistinct),
             (aggregator, true) => bail!("distinct not supported for {:?}", aggregator),
             (aggregator, false) => bail!("aggregator {:?} not supported yet", aggregator),
         }
     }
 
+    pub fn approx_percentile(percentile: f64) -> Result<Self> {
+        if !(0.0..=1.0).contains(&percentile) {
+            bail!(
+                "percentile for approx_percentile_cont must be between 0 and 1, not {}",
+                percentile
+            );
+        }
+        Ok(Self::ApproxPercentileCont(percentile.to_bits()))
+    }
+
+    pub fn percentile(&self) -> Option<f64> {
+        match self {
+            Aggregator::ApproxPercentileCont(bits) => Some(f64::from_bits(*bits)),
+            _ => None,
+        }
+    }
+
     pub fn return_data_type(&self, input_type: TypeDef) -> DataType {
         let (input_type, _) = match input_type {
             TypeDef::StructDef(_, _) => unreachable!("aggregates over structs not supported"),
@@ -1668,7 +1788,75 @@ impl Aggregator {
                 avg_return_type(&input_type).expect("data fusion should've validated types")
             }
             Aggregator::CountDistinct => DataType::Int64,
+            Aggregator::Variance
+            | Aggregator::VariancePop
+            | Aggregator::Stddev
+            | Aggregator::StddevPop => DataType::Float64,
+            Aggregator::ApproxPercentileCont(_)
+            | Aggregator::FirstValue
+            | Aggregator::LastValue => input_type,
+            Aggregator::ArrayAgg => list_type(input_type, true),
+            Aggregator::StringAgg(_) => DataType::Utf8,
+            Aggregator::ApproxDistinct => DataType::UInt64,
+        }
+    }
+
+    pub fn return_nullable(&self, input_nullable: bool) -> bool {
+        match self {
+            Aggregator::Count
+            | Aggregator::CountDistinct
+            | Aggregator::ArrayAgg
+            | Aggregator::ApproxDistinct => false,
+            // statistics ignore NaNs, and sample statistics are null for a single value, so
+            // these can be null even over non-null input
+            Aggregator::Variance
+            | Aggregator::VariancePop
+            | Aggregator::Stddev
+            | Aggregator::StddevPop
+            | Aggregator::ApproxPercentileCont(_) => true,
+            _ => input_nullable,
+        }
+    }
+
+    pub(crate) fn is_sample(&self) -> bool {
+        matches!(self, Aggregator::Variance | Aggregator::Stddev)
+    }
+
+    pub(crate) fn is_stddev(&self) -> bool {
+        matches!(self, Aggregator::Stddev | Aggregator::StddevPop)
+    }
+
+    // aggregators whose result depends on the order of the values, for ORDER BY clauses
+    fn is_order_sensitive(&self) -> bool {
+        matches!(
+            self,
+            Aggregator::FirstValue
+                | Aggregator::LastValue
+                | Aggregator::ArrayAgg
+                | Aggregator::StringAgg(_)
+        )
+    }
+
+    // aggregators that don't skip null values
+    fn keeps_nulls(&self) -> bool {
+        matches!(
+            self,
+            Aggregator::FirstValue | Aggregator::LastValue | Aggregator::ArrayAgg
+        )
+    }
+}
+
+// floats don't implement Hash, so approx_distinct hashes their bits instead
+pub(crate) fn hashable_value(value: syn::Expr, data_type: &DataType, nullable: bool) -> syn::Expr {
+    match (data_type, nullable) {
+        (DataType::Float16 | DataType::Float32 | DataType::Float64, true) => {
+            parse_quote!((#value).map(|value| value.to_bits()))
         }
+        (DataType::Float16 | DataType::Float32 | DataType::Float64, false) => {
+            parse_quote!(Some((#value).to_bits()))
+        }
+        (_, true) => value,
+        (_, false) => parse_quote!(Some(#value)),
     }
 }
 
@@ -1676,6 +1864,10 @@ impl Aggregator {
 pub struct AggregationExpression {
     pub producing_expression: Box<Expression>,
     pub aggregator: Aggregator,
+    pub order_by: Vec<SortExpression>,
+    // set when the aggregate has a FILTER clause, as the rows that don't match are passed
+    // in as nulls, which aggregators that otherwise keep nulls then need to skip.
+    pub ignore_nulls: bool,
 }
 
 impl TryFrom<AggregationExpression> for TwoPhaseAggregation {
@@ -1686,6 +1878,8 @@ impl TryFrom<AggregationExpression> for TwoPhaseAggregation {
             Ok(TwoPhaseAggregation {
                 incoming_expression: *aggregation_expression.producing_expression,
                 aggregator: aggregation_expression.aggregator,
+                order_by: aggregation_expression.order_by,
+                ignore_nulls: aggregation_expression.ignore_nulls,
             })
         } else {
             bail!(
@@ -1696,53 +1890,136 @@ impl TryFrom<AggregationExpression> for TwoPhaseAggregation {
     }
 }
 
+pub(crate) const STRING_AGG: &str = "string_agg";
+
 impl AggregationExpression {
-    fn new(
-        producing_expression: Box<Expression>,
-        aggregator: aggregate_function::AggregateFunction,
-        distinct: bool,
-    ) -> Result<Expression> {
-        let aggregator = Aggregator::from_datafusion(aggregator, distinct)?;
-        Ok(Expression::Aggregation(Self {
-            producing_expression,
-            aggregator,
-        }))
+    pub(crate) fn allows_two_phase(&self) -> bool {
+        !matches!(self.aggregator, Aggregator::CountDistinct)
     }
 
-    pub(crate) fn allows_two_phase(&self) -> bool {
-        match self.aggregator {
-            Aggregator::Count
-            | Aggregator::Sum
-            | Aggregator::Min
-            | Aggregator::Avg
-            | Aggregator::Max => true,
-            Aggregator::CountDistinct => false,
-        }
+    pub fn expressions(&mut self) -> impl Iterator<Item = &mut Expression> {
+        std::iter::once(&mut *self.producing_expression).chain(
+            self.order_by
+                .iter_mut()
+                .map(|sort_expression| sort_expression.expression()),
+        )
+    }
+
+    fn new(
+        ctx: &ExpressionContext,
+        arg: &Expr,
+        aggregator: Aggregator,
+        filter: Option<&Expr>,
+        order_by: Option<&Vec<Expr>>,
+    ) -> Result<Self> {
+        let mut producing_expression = ctx.compile_expr(arg)?;
+        // FILTER clauses are implemented as CASE WHEN <filter> THEN <arg> END
+        let ignore_nulls = match filter {
+            Some(filter) => {
+                producing_expression = Expression::Case(CaseExpression::When {
+                    condition_pairs: vec![(
+                        Box::new(ctx.compile_expr(filter)?),
+                        Box::new(producing_expression),
+                    )],
+                    default: None,
+                });
+                aggregator.keeps_nulls()
+            }
+            None => false,
+        };
+        let order_by = match order_by {
+            Some(order_by) if aggregator.is_order_sensitive() => order_by
+                .iter()
+                .map(|sort| {
+                    let Expr::Sort(sort) = sort else {
+                        bail!("unexpected expression {:?} in aggregate ORDER BY", sort);
+                    };
+                    let sort_expression = SortExpression::from_expression(ctx, sort)?;
+                    if sort_expression
+                        .value
+                        .expression_type(&ValuePointerContext::new())
+                        .is_float()
+                    {
+                        bail!("ordering aggregates by floating point values is not supported");
+                    }
+                    Ok(sort_expression)
+                })
+                .collect::<Result<Vec<_>>>()?,
+            // the order doesn't matter for the other aggregators
+            _ => vec![],
+        };
+        Ok(AggregationExpression {
+            producing_expression: Box::new(producing_expression),
+            aggregator,
+            order_by,
+            ignore_nulls,
+        })
     }
 
     pub fn try_from_aggregate_function(
-        ctx: &mut ExpressionContext,
+        ctx: &ExpressionContext,
         aggregate_function: &datafusion_expr::expr::AggregateFunction,
     ) -> Result<Self> {
-        if aggregate_function.filter.is_some() {
-            bail!("Not supporting aggregate filters right now");
-        }
-        if aggregate_function.order_by.is_some() {
-            bail!("Not supporting aggregate sorts right now");
-        }
-        let fun = &aggregate_function.fun;
-        let distinct = aggregate_function.distinct;
         let args = &aggregate_function.args;
+        let aggregator =
+            Self::aggregator_for(&aggregate_function.fun, aggregate_function.distinct, args)?;
+        Self::new(
+            ctx,
+            &args[0],
+            aggregator,
+            aggregate_function.filter.as_deref(),
+            aggregate_function.order_by.as_ref(),
+        )
+    }
+
+    // aggregates used as window functions, e.g. SUM(x) OVER (...), are computed over the window frame
+    pub fn try_from_window_aggregate(
+        ctx: &ExpressionContext,
+        fun: &aggregate_function::AggregateFunction,
+        args: &[Expr],
+    ) -> Result<Self> {
+        let aggregator = Self::aggregator_for(fun, false, args)?;
+        Self::new(ctx, &args[0], aggregator, None, None)
+    }
 
-        if args.len() != 1 {
-            bail!("unexpected arg length");
+    fn aggregator_for(
+        fun: &aggregate_function::AggregateFunction,
+        distinct: bool,
+        args: &[Expr],
+    ) -> Result<Aggregator> {
+        match (fun, args) {
+            (aggregate_function::AggregateFunction::ApproxPercentileCont, [_, percentile]) => {
+                if distinct {
+                    bail!("distinct not supported for {:?}", fun);
+                }
+                let Expr::Literal(ScalarValue::Float64(Some(percentile))) = percentile else {
+                    bail!("approx_percentile_cont requires a literal percentile");
+                };
+                Aggregator::approx_percentile(*percentile)
+            }
+            (_, [_]) => Aggregator::from_datafusion(fun.clone(), distinct),
+            _ => bail!("unexpected arg length"),
         }
-        let producing_expression = Box::new(ctx.compile_expr(&args[0])?);
-        let aggregator = Aggregator::from_datafusion(fun.clone(), distinct)?;
-        Ok(AggregationExpression {
-            producing_expression,
-            aggregator,
-        })
+    }
+
+    // string_agg isn't a builtin aggregate in datafusion, so it is registered as an aggregate UDF
+    pub fn try_from_string_agg(
+        ctx: &ExpressionContext,
+        aggregate_udf: &AggregateUDF,
+    ) -> Result<Self> {
+        let [arg, delimiter] = aggregate_udf.args.as_slice() else {
+            bail!("string_agg takes two arguments");
+        };
+        let Expr::Literal(ScalarValue::Utf8(Some(delimiter))) = delimiter else {
+            bail!("string_agg requires a literal delimiter");
+        };
+        Self::new(
+            ctx,
+            arg,
+            Aggregator::StringAgg(delimiter.clone()),
+            aggregate_udf.filter.as_deref(),
+            aggregate_udf.order_by.as_ref(),
+        )
     }
 }
 
@@ -1766,6 +2043,25 @@ impl CodeGenerator<VecOfPointersContext, TypeDef, syn::Expr> for AggregationExpr
         } else {
             Some(quote!(.unwrap()))
         };
+        let float_value: syn::Expr = if producing_expression_is_optional {
+            parse_quote!((#sub_expr).map(|value| value as f64))
+        } else {
+            parse_quote!(Some((#sub_expr) as f64))
+        };
+        let optional_value: syn::Expr = if producing_expression_is_optional {
+            parse_quote!(#sub_expr)
+        } else {
+            parse_quote!(Some(#sub_expr))
+        };
+        // with an ORDER BY, the values are sorted before being aggregated
+        let sort: Option<syn::Stmt> = (!self.order_by.is_empty()).then(|| {
+            let sort_key = SortExpression::sort_tuple_expression(&self.order_by);
+            parse_quote!(let #vec_ident = {
+                let mut sorted: Vec<_> = #vec_ident.iter().collect();
+                sorted.sort_by_key(|#single_value_ident| #sort_key);
+                sorted
+            };)
+        });
 
         match &self.aggregator {
             Aggregator::Count => {
@@ -1811,6 +2107,100 @@ impl CodeGenerator<VecOfPointersContext, TypeDef, syn::Expr> for AggregationExpr
                     .collect::<std::collections::HashSet<_>>()
                     .len() as i64
             }),
+            Aggregator::Variance
+            | Aggregator::VariancePop
+            | Aggregator::Stddev
+            | Aggregator::StddevPop => {
+                let sample = self.aggregator.is_sample();
+                let sqrt = self.aggregator.is_stddev().then(|| quote!(.map(f64::sqrt)));
+                parse_quote!({
+                    let state = #vec_ident.iter().fold(None, |state, #single_value_ident| {
+                        Some(arroyo_worker::operators::functions::aggregates::variance_add(state, #float_value))
+                    });
+                    arroyo_worker::operators::functions::aggregates::variance(&state.unwrap_or_default(), #sample)
+                        #sqrt
+                })
+            }
+            Aggregator::ApproxPercentileCont(_) => {
+                let percentile = self.aggregator.percentile().unwrap();
+                let return_type = data_type_as_syn_type(
+                    &self.aggregator.return_data_type(
+                        self.producing_expression
+                            .expression_type(&single_value_context),
+                    ),
+                );
+                parse_quote!({
+                    let sketch = #vec_ident.iter().fold(None, |sketch, #single_value_ident| {
+                        Some(arroyo_worker::operators::functions::aggregates::sketch_add(sketch, #float_value))
+                    });
+                    arroyo_worker::operators::functions::aggregates::sketch_quantile(&sketch.unwrap_or_default(), #percentile)
+                        .map(|value| value as #return_type)
+                })
+            }
+            // the values in a window are ordered by event time
+            Aggregator::FirstValue if self.ignore_nulls => parse_quote!({
+                #sort
+                #vec_ident.iter()
+                    .filter_map(|#single_value_ident| #sub_expr)
+                    .next()
+            }),
+            Aggregator::FirstValue => parse_quote!({
+                #sort
+                #vec_ident.first()
+                    .map(|#single_value_ident| #sub_expr)
+                    .unwrap()
+            }),
+            Aggregator::LastValue if self.ignore_nulls => parse_quote!({
+                #sort
+                #vec_ident.iter()
+                    .filter_map(|#single_value_ident| #sub_expr)
+                    .last()
+            }),
+            Aggregator::LastValue => parse_quote!({
+                #sort
+                #vec_ident.last()
+                    .map(|#single_value_ident| #sub_expr)
+                    .unwrap()
+            }),
+            Aggregator::ArrayAgg if self.ignore_nulls => parse_quote!({
+                #sort
+                #vec_ident.iter()
+                    .filter_map(|#single_value_ident| #sub_expr)
+                    .map(Some)
+                    .collect::<Vec<_>>()
+            }),
+            Aggregator::ArrayAgg => parse_quote!({
+                #sort
+                #vec_ident.iter()
+                    .map(|#single_value_ident| #optional_value)
+                    .collect::<Vec<_>>()
+            }),
+            Aggregator::StringAgg(delimiter) => parse_quote!({
+                #sort
+                let values: Vec<String> = #vec_ident.iter()
+                    .#map_type(|#single_value_ident| #sub_expr)
+                    .collect();
+                arroyo_worker::operators::functions::aggregates::string_agg(&values, #delimiter)
+                    #unwrap
+            }),
+            Aggregator::ApproxDistinct => {
+                let input_type = self
+                    .producing_expression
+                    .expression_type(&single_value_context);
+                let value = hashable_value(
+                    sub_expr,
+                    input_type
+                        .as_datatype()
+                        .expect("aggregates over structs not supported"),
+                    producing_expression_is_optional,
+                );
+                parse_quote!({
+                    let sketch = #vec_ident.iter().fold(None, |sketch, #single_value_ident| {
+                        Some(arroyo_worker::operators::functions::aggregates::hll_add(sketch, #value))
+                    });
+                    arroyo_worker::operators::functions::aggregates::hll_estimate(&sketch.unwrap_or_default())
+                })
+            }
         }
     }
 
@@ -1824,7 +2214,7 @@ impl CodeGenerator<VecOfPointersContext, TypeDef, syn::Expr> for AggregationExpr
                 let input_type = self
                     .producing_expression
                     .expression_type(&single_value_context);
-                let is_optional = input_type.is_optional();
+                let is_optional = aggregator.return_nullable(input_type.is_optional());
                 TypeDef::DataType(aggregator.return_data_type(input_type), is_optional)
             }
         }
@@ -1871,6 +2261,15 @@ impl CastExpression {
             && (Self::is_numeric(output_data_type) || Self::is_string(output_data_type))
         {
             true
+        // handle casts between decimals and numerics or strings.
+        } else if (Self::is_decimal(input_data_type)
+            && (Self::is_numeric(output_data_type)
+                || Self::is_string(output_data_type)
+                || Self::is_decimal(output_data_type)))
+            || ((Self::is_numeric(input_data_type) || Self::is_string(input_data_type))
+                && Self::is_decimal(output_data_type))
+        {
+            true
         // handle date to string casts.
         } else if Self::is_date(input_data_type) && Self::is_string(output_data_type) {
             true
@@ -1908,6 +2307,37 @@ impl CastExpression {
         matches!(data_type, DataType::Timestamp(_, None))
     }
 
+    fn is_float(data_type: &DataType) -> bool {
+        matches!(
+            data_type,
+            DataType::Float16 | DataType::Float32 | DataType::Float64
+        )
+    }
+
+    fn is_decimal(data_type: &DataType) -> bool {
+        matches!(data_type, DataType::Decimal128(_, _))
+    }
+
+    /// Converts an unscaled i128 decimal value from one scale to another, rounding half away from
+    /// zero when the scale is reduced
+    fn rescale_decimal(sub_expr: syn::Expr, from_scale: i8, to_scale: i8) -> syn::Expr {
+        let diff = to_scale as i32 - from_scale as i32;
+        let factor = 10i128.pow(diff.unsigned_abs());
+        if diff >= 0 {
+            parse_quote!((#sub_expr * #factor))
+        } else {
+            parse_quote!({
+                let v: i128 = #sub_expr;
+                let d = v / #factor;
+                if (v % #factor).abs() * 2 >= #factor {
+                    d + v.signum()
+                } else {
+                    d
+                }
+            })
+        }
+    }
+
     fn is_string(data_type: &DataType) -> bool {
         matches!(data_type, DataType::Utf8 | DataType::LargeUtf8)
     }
@@ -1915,6 +2345,42 @@ impl CastExpression {
         if input_type == output_type {
             return sub_expr;
         }
+        // decimals are represented by their unscaled i128 value
+        if let (DataType::Decimal128(_, from_scale), DataType::Decimal128(_, to_scale)) =
+            (input_type, output_type)
+        {
+            return Self::rescale_decimal(sub_expr, *from_scale, *to_scale);
+        }
+        if let DataType::Decimal128(_, scale) = input_type {
+            return if Self::is_string(output_type) {
+                parse_quote!(arroyo_types::format_decimal(#sub_expr, #scale))
+            } else {
+                let cast_type: syn::Type =
+                    parse_str(&StructField::data_type_name(output_type)).unwrap();
+                if Self::is_float(output_type) {
+                    let scale = *scale as i32;
+                    parse_quote!(((#sub_expr as f64) / 10f64.powi(#scale)) as #cast_type)
+                } else {
+                    // like arrow, casting decimals to integers truncates
+                    let factor = 10i128.pow(scale.unsigned_abs() as u32);
+                    if *scale >= 0 {
+                        parse_quote!((#sub_expr / #factor) as #cast_type)
+                    } else {
+                        parse_quote!((#sub_expr * #factor) as #cast_type)
+                    }
+                }
+            };
+        }
+        if let DataType::Decimal128(_, scale) = output_type {
+            return if Self::is_string(input_type) {
+                parse_quote!(arroyo_types::parse_decimal(&#sub_expr, #scale).unwrap())
+            } else if Self::is_float(input_type) {
+                let scale = *scale as i32;
+                parse_quote!(((#sub_expr as f64) * 10f64.powi(#scale)).round() as i128)
+            } else {
+                Self::rescale_decimal(parse_quote!((#sub_expr as i128)), 0, *scale)
+            };
+        }
         if Self::is_numeric(input_type) && Self::is_numeric(output_type) {
             let cast_type: syn::Type =
                 parse_str(&StructField::data_type_name(output_type)).unwrap();
@@ -2129,7 +2595,7 @@ impl SortExpression {
         &mut self.value
     }
 
-    pub fn from_expression(ctx: &mut ExpressionContext, sort: &Sort) -> Result<Self> {
+    pub fn from_expression(ctx: &ExpressionContext, sort: &Sort) -> Result<Self> {
         let value = ctx.compile_expr(&sort.expr)?;
 
         let direction = if sort.asc {
@@ -3364,6 +3830,10 @@ pub enum DataStructureFunction {
         expr: Box<Expression>,
         list: Vec<Expression>,
     },
+    Array {
+        function: ArrayFunction,
+        args: Vec<Expression>,
+    },
 }
 
 impl CodeGenerator<ValuePointerContext, TypeDef, syn::Expr> for DataStructureFunction {
@@ -3538,6 +4008,9 @@ impl CodeGenerator<ValuePointerContext, TypeDef, syn::Expr> for DataStructureFun
                     })
                 }
             }
+            DataStructureFunction::Array { function, args } => {
+                Self::generate_array(function, args, input_context)
+            }
         }
     }
     fn expression_type(&self, input_context: &ValuePointerContext) -> TypeDef {
@@ -3585,6 +4058,547 @@ impl CodeGenerator<ValuePointerContext, TypeDef, syn::Expr> for DataStructureFun
                 DataType::Boolean,
                 expr.expression_type(input_context).is_optional(),
             ),
+            DataStructureFunction::Array { function, args } => {
+                Self::array_expression_type(function, args, input_context)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd)]
+pub enum ArrayFunction {
+    Append,
+    Prepend,
+    Concat,
+    Dims,
+    Ndims,
+    Length,
+    Cardinality,
+    Empty,
+    Has,
+    HasAll,
+    HasAny,
+    Position,
+    Positions,
+    Element,
+    PopBack,
+    Remove,
+    RemoveN,
+    RemoveAll,
+    Replace,
+    ReplaceN,
+    ReplaceAll,
+    Repeat,
+    Slice,
+    ToString,
+    Flatten,
+}
+
+/// How an argument to an array function is coerced, and how nulls are handled
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArrayArg {
+    /// An array; if null the result is null, except for array_concat which skips it
+    Array,
+    /// A value that's compared against or inserted into an array; nulls are treated as values
+    Element,
+    /// An index or count; if null the result is null
+    Integer,
+    /// A string; if null the result is null
+    Text,
+    /// A string that may be null, like the null placeholder for array_to_string
+    OptionalText,
+}
+
+impl ArrayFunction {
+    fn from_builtin(fun: &BuiltinScalarFunction) -> Option<Self> {
+        Some(match fun {
+            BuiltinScalarFunction::ArrayAppend => Self::Append,
+            BuiltinScalarFunction::ArrayPrepend => Self::Prepend,
+            BuiltinScalarFunction::ArrayConcat => Self::Concat,
+            BuiltinScalarFunction::ArrayDims => Self::Dims,
+            BuiltinScalarFunction::ArrayNdims => Self::Ndims,
+            BuiltinScalarFunction::ArrayLength => Self::Length,
+            BuiltinScalarFunction::Cardinality => Self::Cardinality,
+            BuiltinScalarFunction::ArrayEmpty => Self::Empty,
+            BuiltinScalarFunction::ArrayHas => Self::Has,
+            BuiltinScalarFunction::ArrayHasAll => Self::HasAll,
+            BuiltinScalarFunction::ArrayHasAny => Self::HasAny,
+            BuiltinScalarFunction::ArrayPosition => Self::Position,
+            BuiltinScalarFunction::ArrayPositions => Self::Positions,
+            BuiltinScalarFunction::ArrayElement => Self::Element,
+            BuiltinScalarFunction::ArrayPopBack => Self::PopBack,
+            BuiltinScalarFunction::ArrayRemove => Self::Remove,
+            BuiltinScalarFunction::ArrayRemoveN => Self::RemoveN,
+            BuiltinScalarFunction::ArrayRemoveAll => Self::RemoveAll,
+            BuiltinScalarFunction::ArrayReplace => Self::Replace,
+            BuiltinScalarFunction::ArrayReplaceN => Self::ReplaceN,
+            BuiltinScalarFunction::ArrayReplaceAll => Self::ReplaceAll,
+            BuiltinScalarFunction::ArrayRepeat => Self::Repeat,
+            BuiltinScalarFunction::ArraySlice => Self::Slice,
+            BuiltinScalarFunction::ArrayToString => Self::ToString,
+            BuiltinScalarFunction::Flatten => Self::Flatten,
+            _ => return None,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Append => "array_append",
+            Self::Prepend => "array_prepend",
+            Self::Concat => "array_concat",
+            Self::Dims => "array_dims",
+            Self::Ndims => "array_ndims",
+            Self::Length => "array_length",
+            Self::Cardinality => "cardinality",
+            Self::Empty => "array_empty",
+            Self::Has => "array_has",
+            Self::HasAll => "array_has_all",
+            Self::HasAny => "array_has_any",
+            Self::Position => "array_position",
+            Self::Positions => "array_positions",
+            Self::Element => "array_element",
+            Self::PopBack => "array_pop_back",
+            Self::Remove => "array_remove",
+            Self::RemoveN => "array_remove_n",
+            Self::RemoveAll => "array_remove_all",
+            Self::Replace => "array_replace",
+            Self::ReplaceN => "array_replace_n",
+            Self::ReplaceAll => "array_replace_all",
+            Self::Repeat => "array_repeat",
+            Self::Slice => "array_slice",
+            Self::ToString => "array_to_string",
+            Self::Flatten => "flatten",
+        }
+    }
+
+    fn arg_roles(&self, count: usize) -> Result<Vec<ArrayArg>> {
+        use ArrayArg::*;
+        Ok(match (self, count) {
+            (Self::Append, 2) => vec![Array, Element],
+            (Self::Prepend, 2) => vec![Element, Array],
+            (Self::Concat, n) if n > 0 => vec![Array; n],
+            (
+                Self::Dims
+                | Self::Ndims
+                | Self::Length
+                | Self::Cardinality
+                | Self::Empty
+                | Self::PopBack
+                | Self::Flatten,
+                1,
+            ) => vec![Array],
+            (Self::Length | Self::Element, 2) => vec![Array, Integer],
+            (Self::Has | Self::Position | Self::Positions | Self::Remove | Self::RemoveAll, 2) => {
+                vec![Array, Element]
+            }
+            (Self::HasAll | Self::HasAny, 2) => vec![Array, Array],
+            (Self::Position | Self::RemoveN, 3) => vec![Array, Element, Integer],
+            (Self::Replace | Self::ReplaceAll, 3) => vec![Array, Element, Element],
+            (Self::ReplaceN, 4) => vec![Array, Element, Element, Integer],
+            (Self::Repeat, 2) => vec![Element, Integer],
+            (Self::Slice, 3) => vec![Array, Integer, Integer],
+            (Self::ToString, 2) => vec![Array, Text],
+            (Self::ToString, 3) => vec![Array, Text, OptionalText],
+            _ => bail!(
+                "wrong number of arguments for {} (found {})",
+                self.name(),
+                count
+            ),
+        })
+    }
+}
+
+/// Returns the number of nested lists in the type
+fn list_depth(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::List(field) => 1 + list_depth(field.data_type()),
+        _ => 0,
+    }
+}
+
+pub(crate) fn list_type(item_type: DataType, nullable: bool) -> DataType {
+    DataType::List(Arc::new(Field::new("items", item_type, nullable)))
+}
+
+impl DataStructureFunction {
+    fn array(function: ArrayFunction, mut args: Vec<Expression>) -> Result<Expression> {
+        let context = ValuePointerContext::new();
+        let name = function.name();
+
+        if function == ArrayFunction::Length && args.len() == 2 {
+            let Expression::Literal(LiteralExpression {
+                literal: ScalarValue::Int64(Some(1)),
+            }) = &args[1]
+            else {
+                bail!("array_length is only supported for the first dimension");
+            };
+            args.truncate(1);
+        }
+
+        let roles = function.arg_roles(args.len())?;
+
+        // elements are coerced to the item type of the array they're used with
+        let mut item_type: Option<DataType> = None;
+        for (arg, role) in args.iter().zip(&roles) {
+            if *role != ArrayArg::Array {
+                continue;
+            }
+            let TypeDef::DataType(DataType::List(field), _) = arg.expression_type(&context) else {
+                bail!(
+                    "{} expects an array argument, not {:?}",
+                    name,
+                    arg.expression_type(&context)
+                );
+            };
+            match &item_type {
+                Some(t) if t != field.data_type() => bail!(
+                    "the arrays passed to {} must have the same type, but found {:?} and {:?}",
+                    name,
+                    t,
+                    field.data_type()
+                ),
+                _ => item_type = Some(field.data_type().clone()),
+            }
+        }
+
+        let args = args
+            .into_iter()
+            .zip(&roles)
+            .map(|(arg, role)| match (role, arg.expression_type(&context)) {
+                (ArrayArg::Array, _) => Ok(arg),
+                (ArrayArg::Element, TypeDef::StructDef(..)) => {
+                    bail!("{} does not support struct values", name)
+                }
+                (ArrayArg::Element, TypeDef::DataType(data_type, _)) => match &item_type {
+                    Some(item_type) if *item_type != data_type => {
+                        CastExpression::new(Box::new(arg), item_type, &context, false)
+                    }
+                    _ => Ok(arg),
+                },
+                (ArrayArg::Integer, TypeDef::DataType(DataType::Int64, _)) => Ok(arg),
+                (
+                    ArrayArg::Integer,
+                    TypeDef::DataType(
+                        DataType::Int8
+                        | DataType::Int16
+                        | DataType::Int32
+                        | DataType::UInt8
+                        | DataType::UInt16
+                        | DataType::UInt32
+                        | DataType::UInt64,
+                        _,
+                    ),
+                ) => CastExpression::new(Box::new(arg), &DataType::Int64, &context, false),
+                (ArrayArg::Text | ArrayArg::OptionalText, TypeDef::DataType(DataType::Utf8, _)) => {
+                    Ok(arg)
+                }
+                (role, t) => bail!("invalid {:?} argument to {}: {:?}", role, name, t),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let item_type = item_type.unwrap_or(DataType::Null);
+        match function {
+            ArrayFunction::Dims if list_depth(&item_type) > 0 => {
+                bail!("array_dims is only supported for one-dimensional arrays")
+            }
+            ArrayFunction::Cardinality if list_depth(&item_type) > 1 => {
+                bail!("cardinality is only supported for arrays with up to two dimensions")
+            }
+            ArrayFunction::ToString
+                if !(CastExpression::is_numeric(&item_type)
+                    || matches!(item_type, DataType::Utf8 | DataType::Boolean)) =>
+            {
+                bail!(
+                    "array_to_string is not supported for arrays of {:?}",
+                    item_type
+                )
+            }
+            _ => {}
+        }
+
+        Ok(Expression::DataStructure(DataStructureFunction::Array {
+            function,
+            args,
+        }))
+    }
+
+    fn generate_array(
+        function: &ArrayFunction,
+        args: &[Expression],
+        input_context: &ValuePointerContext,
+    ) -> syn::Expr {
+        let roles = function
+            .arg_roles(args.len())
+            .expect("arguments are checked during planning");
+        let types: Vec<_> = args
+            .iter()
+            .map(|arg| arg.expression_type(input_context))
+            .collect();
+        let names: Vec<Ident> = (0..args.len()).map(|i| format_ident!("arg{}", i)).collect();
+        let bindings: Vec<TokenStream> = args
+            .iter()
+            .zip(&names)
+            .map(|(arg, name)| {
+                let expr = arg.generate(input_context);
+                quote!(let #name = #expr;)
+            })
+            .collect();
+
+        let item_field = |t: &TypeDef| match t {
+            TypeDef::DataType(DataType::List(field), _) => Some(field.clone()),
+            _ => None,
+        };
+        let first_item = types.iter().find_map(item_field);
+
+        // if either the array items or the elements they're compared with may be null, both
+        // are wrapped in Options
+        let items_nullable = roles
+            .iter()
+            .zip(&types)
+            .any(|(role, t)| *role == ArrayArg::Array && item_field(t).unwrap().is_nullable());
+        let lift = items_nullable
+            || roles
+                .iter()
+                .zip(&types)
+                .any(|(role, t)| *role == ArrayArg::Element && t.is_optional());
+
+        let values: Vec<TokenStream> = roles
+            .iter()
+            .zip(&types)
+            .zip(&names)
+            .map(|((role, t), name)| match role {
+                ArrayArg::Array if lift && !item_field(t).unwrap().is_nullable() => {
+                    quote!(#name.into_iter().map(Some).collect::<Vec<_>>())
+                }
+                ArrayArg::Element if lift && !t.is_optional() => quote!(Some(#name)),
+                ArrayArg::OptionalText if !t.is_optional() => quote!(Some(#name)),
+                _ => quote!(#name),
+            })
+            .collect();
+
+        let arrays = quote!(arroyo_worker::operators::functions::arrays);
+        let arg0 = &names[0];
+
+        if *function == ArrayFunction::Concat {
+            let extends: Vec<TokenStream> = types
+                .iter()
+                .zip(&names)
+                .zip(&values)
+                .map(|((t, name), value)| {
+                    if t.is_optional() {
+                        quote!(if let Some(#name) = #name {
+                            result.get_or_insert_with(Vec::new).extend(#value);
+                        })
+                    } else {
+                        quote!(result.get_or_insert_with(Vec::new).extend(#value);)
+                    }
+                })
+                .collect();
+            let result = if types.iter().all(|t| t.is_optional()) {
+                quote!(result)
+            } else {
+                quote!(result.unwrap_or_default())
+            };
+
+            return parse_quote!({
+                #(#bindings)*
+                let mut result: Option<Vec<_>> = None;
+                #(#extends)*
+                #result
+            });
+        }
+
+        let body: TokenStream = match function {
+            ArrayFunction::Append => quote!(#arrays::append(#(#values),*)),
+            ArrayFunction::Prepend => quote!(#arrays::prepend(#(#values),*)),
+            ArrayFunction::Concat => unreachable!(),
+            ArrayFunction::Dims => quote!(vec![#arg0.len() as u64]),
+            ArrayFunction::Ndims => {
+                let depth = list_depth(first_item.unwrap().data_type()) as u64 + 1;
+                quote!({
+                    let _ = #arg0;
+                    #depth
+                })
+            }
+            ArrayFunction::Length => quote!((#arg0.len() as u64)),
+            ArrayFunction::Cardinality => match first_item.unwrap().data_type() {
+                // null sub-arrays count as empty
+                DataType::List(_) if items_nullable => quote!(
+                    (#arg0.iter().map(|a| a.as_ref().map(|a| a.len()).unwrap_or(0)).sum::<usize>() as u64)
+                ),
+                DataType::List(_) => {
+                    quote!((#arg0.iter().map(|a| a.len()).sum::<usize>() as u64))
+                }
+                _ => quote!((#arg0.len() as u64)),
+            },
+            ArrayFunction::Empty => quote!(#arg0.is_empty()),
+            ArrayFunction::Has => {
+                let (array, element) = (&values[0], &values[1]);
+                quote!(#array.contains(&#element))
+            }
+            ArrayFunction::HasAll => quote!(#arrays::has_all(#(#values),*)),
+            ArrayFunction::HasAny => quote!(#arrays::has_any(#(#values),*)),
+            ArrayFunction::Position if values.len() == 2 => {
+                quote!(#arrays::position(#(#values),*, 1))
+            }
+            ArrayFunction::Position => quote!(#arrays::position(#(#values),*)),
+            ArrayFunction::Positions => quote!(#arrays::positions(#(#values),*)),
+            ArrayFunction::Element if items_nullable => {
+                quote!(#arrays::element(#(#values),*).flatten())
+            }
+            ArrayFunction::Element => quote!(#arrays::element(#(#values),*)),
+            ArrayFunction::PopBack => quote!(#arrays::pop_back(#arg0)),
+            ArrayFunction::Remove => quote!(#arrays::remove(#(#values),*, 1)),
+            ArrayFunction::RemoveN => quote!(#arrays::remove(#(#values),*)),
+            ArrayFunction::RemoveAll => quote!(#arrays::remove(#(#values),*, i64::MAX)),
+            ArrayFunction::Replace => quote!(#arrays::replace(#(#values),*, 1)),
+            ArrayFunction::ReplaceN => quote!(#arrays::replace(#(#values),*)),
+            ArrayFunction::ReplaceAll => quote!(#arrays::replace(#(#values),*, i64::MAX)),
+            ArrayFunction::Repeat => quote!(#arrays::repeat(#(#values),*)),
+            ArrayFunction::Slice => quote!(#arrays::slice(#(#values),*)),
+            ArrayFunction::ToString => {
+                let items = if items_nullable {
+                    quote!(#arg0.into_iter().map(|v| v.map(|v| v.to_string())).collect())
+                } else {
+                    quote!(#arg0.into_iter().map(|v| Some(v.to_string())).collect())
+                };
+                let delimiter = &values[1];
+                let null = values.get(2).cloned().unwrap_or_else(|| quote!(None));
+                quote!(#arrays::to_string(#items, #delimiter, #null))
+            }
+            ArrayFunction::Flatten => match first_item.unwrap().data_type() {
+                // null sub-arrays are skipped
+                DataType::List(_) if items_nullable => {
+                    quote!(#arg0.into_iter().flatten().flatten().collect::<Vec<_>>())
+                }
+                DataType::List(_) => quote!(#arg0.into_iter().flatten().collect::<Vec<_>>()),
+                _ => quote!(#arg0),
+            },
+        };
+
+        // nulls in arrays and numeric or string arguments make the result null
+        let propagating: Vec<&Ident> = roles
+            .iter()
+            .zip(&types)
+            .zip(&names)
+            .filter(|((role, t), _)| {
+                matches!(role, ArrayArg::Array | ArrayArg::Integer | ArrayArg::Text)
+                    && t.is_optional()
+            })
+            .map(|(_, name)| name)
+            .collect();
+
+        if propagating.is_empty() {
+            return parse_quote!({
+                #(#bindings)*
+                #body
+            });
+        }
+
+        let body_nullable = matches!(function, ArrayFunction::Position | ArrayFunction::Element);
+        let body = if body_nullable {
+            body
+        } else {
+            quote!(Some(#body))
+        };
+
+        let (pattern, values) = if propagating.len() == 1 {
+            let name = propagating[0];
+            (quote!(Some(#name)), quote!(#name))
+        } else {
+            (
+                quote!((#(Some(#propagating)),*)),
+                quote!((#(#propagating),*)),
+            )
+        };
+
+        parse_quote!({
+            #(#bindings)*
+            if let #pattern = #values {
+                #body
+            } else {
+                None
+            }
+        })
+    }
+
+    fn array_expression_type(
+        function: &ArrayFunction,
+        args: &[Expression],
+        input_context: &ValuePointerContext,
+    ) -> TypeDef {
+        let roles = function
+            .arg_roles(args.len())
+            .expect("arguments are checked during planning");
+        let types: Vec<_> = args
+            .iter()
+            .map(|arg| arg.expression_type(input_context))
+            .collect();
+
+        let item_fields: Vec<_> = roles
+            .iter()
+            .zip(&types)
+            .filter(|(role, _)| **role == ArrayArg::Array)
+            .map(|(_, t)| match t {
+                TypeDef::DataType(DataType::List(field), _) => field.clone(),
+                _ => unreachable!("{} should only be called on arrays", function.name()),
+            })
+            .collect();
+        let items_nullable = item_fields.iter().any(|f| f.is_nullable());
+        let lift = items_nullable
+            || roles
+                .iter()
+                .zip(&types)
+                .any(|(role, t)| *role == ArrayArg::Element && t.is_optional());
+        let nullable = roles.iter().zip(&types).any(|(role, t)| {
+            matches!(role, ArrayArg::Array | ArrayArg::Integer | ArrayArg::Text) && t.is_optional()
+        });
+        let item_type = || item_fields[0].data_type().clone();
+
+        match function {
+            ArrayFunction::Append
+            | ArrayFunction::Prepend
+            | ArrayFunction::Remove
+            | ArrayFunction::RemoveN
+            | ArrayFunction::RemoveAll
+            | ArrayFunction::Replace
+            | ArrayFunction::ReplaceN
+            | ArrayFunction::ReplaceAll => {
+                TypeDef::DataType(list_type(item_type(), lift), nullable)
+            }
+            ArrayFunction::Concat => TypeDef::DataType(
+                list_type(item_type(), items_nullable),
+                types.iter().all(|t| t.is_optional()),
+            ),
+            ArrayFunction::PopBack | ArrayFunction::Slice => {
+                TypeDef::DataType(list_type(item_type(), items_nullable), nullable)
+            }
+            ArrayFunction::Dims | ArrayFunction::Positions => {
+                TypeDef::DataType(list_type(DataType::UInt64, false), nullable)
+            }
+            ArrayFunction::Ndims | ArrayFunction::Length | ArrayFunction::Cardinality => {
+                TypeDef::DataType(DataType::UInt64, nullable)
+            }
+            ArrayFunction::Empty
+            | ArrayFunction::Has
+            | ArrayFunction::HasAll
+            | ArrayFunction::HasAny => TypeDef::DataType(DataType::Boolean, nullable),
+            ArrayFunction::Position => TypeDef::DataType(DataType::UInt64, true),
+            ArrayFunction::Element => TypeDef::DataType(item_type(), true),
+            ArrayFunction::Repeat => match &types[0] {
+                TypeDef::DataType(data_type, element_nullable) => {
+                    TypeDef::DataType(list_type(data_type.clone(), *element_nullable), nullable)
+                }
+                TypeDef::StructDef(..) => unreachable!("array_repeat does not support structs"),
+            },
+            ArrayFunction::ToString => TypeDef::DataType(DataType::Utf8, nullable),
+            ArrayFunction::Flatten => match item_type() {
+                DataType::List(inner) => TypeDef::DataType(
+                    list_type(inner.data_type().clone(), inner.is_nullable()),
+                    nullable,
+                ),
+                item_type => TypeDef::DataType(list_type(item_type, items_nullable), nullable),
+            },
         }
     }
 }
@@ -3689,9 +4703,11 @@ pub struct RustUdfExpression {
     pub name: String,
     pub args: Vec<(TypeDef, Expression)>,
     pub ret_type: TypeDef,
-    async_fn: bool,
+    pub async_fn: bool,
     pub has_context: bool,
     pub opts: UdfOpts,
+    // whether the UDF is called through its WASM module, see `wasm_udf_static`
+    pub wasm: bool,
 }
 
 impl RustUdfExpression {
@@ -3700,6 +4716,11 @@ impl RustUdfExpression {
     }
 }
 
+/// The static in the generated code that holds the module of a WASM UDF
+pub fn wasm_udf_static(name: &str) -> Ident {
+    format_ident!("WASM_UDF_{}", name.to_uppercase())
+}
+
 impl CodeGenerator<ValuePointerContext, TypeDef, syn::Expr> for RustUdfExpression {
     fn generate(&self, input_context: &ValuePointerContext) -> syn::Expr {
         let name = format_ident!("{}", &self.name);
@@ -3723,7 +4744,13 @@ impl CodeGenerator<ValuePointerContext, TypeDef, syn::Expr> for RustUdfExpressio
             })
             .unzip();
 
-        let mut ret = quote!(udfs::#name(#(#args, )*));
+        let mut ret = if self.wasm {
+            let module = wasm_udf_static(&self.name);
+            let ret_type = self.ret_type.return_type();
+            quote!(#module.call::<_, #ret_type>((#(#args, )*)))
+        } else {
+            quote!(udfs::#name(#(#args, )*))
+        };
 
         if self.expression_type(input_context).is_optional() && !self.ret_type.is_optional() {
             // we have to wrap the result in Some
@@ -3757,6 +4784,7 @@ pub struct RustUdafExpression {
     name: String,
     args: Vec<(TypeDef, Expression)>,
     ret_type: TypeDef,
+    filter: Option<Box<Expression>>,
 }
 
 impl RustUdafExpression {
@@ -3765,7 +4793,10 @@ impl RustUdafExpression {
     }
 
     pub fn expressions(&mut self) -> impl Iterator<Item = &mut Expression> {
-        self.args.iter_mut().map(|(_, e)| e)
+        self.args
+            .iter_mut()
+            .map(|(_, e)| e)
+            .chain(self.filter.iter_mut().map(|filter| &mut **filter))
     }
 
     fn try_from_aggregate_udf(
@@ -3796,11 +4827,17 @@ impl RustUdafExpression {
         if aggregate_udf.order_by.is_some() {
             bail!("Not supporting UDAF sorts right now, as datafusion doesn't");
         }
+        let filter = aggregate_udf
+            .filter
+            .as_ref()
+            .map(|filter| Ok(Box::new(ctx.compile_expr(filter)?)))
+            .transpose()?;
 
         Ok(RustUdafExpression {
             name: udf_name.to_string(),
             args: udf.args.clone().into_iter().zip(inputs).collect(),
             ret_type: udf.ret.clone(),
+            filter,
         })
     }
 }
@@ -3867,9 +4904,21 @@ impl CodeGenerator<VecOfPointersContext, TypeDef, syn::Expr> for RustUdafExpress
         let ret: syn::Expr = parse_quote!(udfs::#name(#(#vec_names),*));
 
         let vec_arg = input_context.variable_ident();
+        // rows that don't match the FILTER clause are left out of the aggregate
+        let filter = self.filter.as_ref().map(|filter| {
+            let filter_expr = filter.generate(&ValuePointerContext::new());
+            if filter
+                .expression_type(&ValuePointerContext::new())
+                .is_optional()
+            {
+                quote!(.filter(|#single_value_ident| (#filter_expr).unwrap_or(false)))
+            } else {
+                quote!(.filter(|#single_value_ident| #filter_expr))
+            }
+        });
         let tokens = quote!( {
             #(#arg_initialization)*
-            #vec_arg.iter().#map_func(|#single_value_ident| {
+            #vec_arg.iter()#filter.#map_func(|#single_value_ident| {
                #tuple_expr
             }).for_each ( |#single_value_ident| {
                 #(#vec_push)*
@@ -17,6 +17,7 @@ mod operators;
 mod optimizations;
 mod pipeline;
 mod plan_graph;
+pub mod protobuf;
 pub mod schemas;
 mod tables;
 pub mod types;
@@ -25,20 +26,24 @@ use datafusion::prelude::create_udf;
 
 use datafusion::sql::sqlparser::dialect::PostgreSqlDialect;
 use datafusion::sql::sqlparser::parser::Parser;
+use datafusion::sql::sqlparser::tokenizer::Tokenizer;
 use datafusion::sql::{planner::ContextProvider, TableReference};
 
 use datafusion_expr::{
     logical_plan::builder::LogicalTableSource, AggregateUDF, ScalarUDF, TableSource,
 };
 use datafusion_expr::{
-    AccumulatorFactoryFunction, LogicalPlan, ReturnTypeFunction, Signature, StateTypeFunction,
-    Volatility, WindowUDF,
+    AccumulatorFactoryFunction, LogicalPlan, PartitionEvaluatorFactory, ReturnTypeFunction,
+    Signature, StateTypeFunction, Volatility, WindowUDF,
 };
-use expressions::{Expression, ExpressionContext};
+use expressions::{Expression, ExpressionContext, STRING_AGG};
 use pipeline::{SqlOperator, SqlPipelineBuilder};
 use plan_graph::{get_program, PlanGraph};
 use schemas::window_arrow_struct;
-use tables::{schema_defs, ConnectorTable, Insert, Table};
+use tables::{
+    extract_temporal_tables, rewrite_metadata_columns, rewrite_table_functions, schema_defs,
+    ConnectorTable, Insert, Table, WindowOptions,
+};
 
 use crate::code_gen::{CodeGenerator, ValuePointerContext};
 use crate::types::{StructDef, StructField, TypeDef};
@@ -50,9 +55,13 @@ use regex::Regex;
 use std::collections::HashSet;
 
 use arroyo_rpc::{OperatorConfig, UdfOpts};
+use std::iter::once;
 use std::time::{Duration, SystemTime};
 use std::{collections::HashMap, sync::Arc};
-use syn::{parse_file, parse_quote, parse_str, FnArg, Item, ReturnType, Visibility};
+use syn::{
+    parse_file, parse_quote, parse_str, Fields, FnArg, ForeignItemFn, Item, ItemStruct, ReturnType,
+    Visibility,
+};
 use toml::Value;
 use tracing::warn;
 use unicase::UniCase;
@@ -71,6 +80,22 @@ pub struct UdfDef {
     opts: UdfOpts,
     async_fn: bool,
     has_context: bool,
+    // whether the function returns rows to be joined with its input, rather than a single value
+    table_function: bool,
+    // set for table functions that keep state and timers for the key in their first argument
+    process_function: Option<ProcessFunctionDef>,
+    // the prebuilt module of scalar UDFs that are called through the WASM UDF ABI, rather than
+    // compiled from their definition
+    wasm: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcessFunctionDef {
+    // the state kept for each key, and the name of the struct it's defined as in the UDF
+    state: StructDef,
+    state_struct: String,
+    // whether the UDF defines a `<name>_on_timer` function that handles the timers it schedules
+    handles_timers: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -78,6 +103,7 @@ pub struct CompiledSql {
     pub program: Program,
     pub connection_ids: Vec<i64>,
     pub schemas: HashMap<String, StructDef>,
+    pub used_udfs: HashSet<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -86,9 +112,12 @@ pub struct ArroyoSchemaProvider {
     tables: HashMap<UniCase<String>, Table>,
     pub functions: HashMap<String, Arc<ScalarUDF>>,
     pub aggregate_functions: HashMap<String, Arc<AggregateUDF>>,
+    pub window_functions: HashMap<String, Arc<WindowUDF>>,
     pub connections: HashMap<String, Connection>,
     profiles: HashMap<String, ConnectionProfile>,
     pub udf_defs: HashMap<String, UdfDef>,
+    // the placeholder tables that calls of table functions are planned as, and their functions
+    table_function_calls: HashMap<String, String>,
     config_options: datafusion::config::ConfigOptions,
 }
 
@@ -205,14 +234,33 @@ impl ArroyoSchemaProvider {
             )),
         );
 
+        let mut aggregate_functions = HashMap::new();
+        aggregate_functions.insert(
+            STRING_AGG.to_string(),
+            Arc::new({
+                let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Utf8)));
+                let accumulator: AccumulatorFactoryFunction = Arc::new(|_| unreachable!());
+                let state_type: StateTypeFunction = Arc::new(|_| unreachable!());
+                AggregateUDF::new(
+                    STRING_AGG,
+                    &Signature::exact(vec![DataType::Utf8, DataType::Utf8], Volatility::Immutable),
+                    &return_type,
+                    &accumulator,
+                    &state_type,
+                )
+            }),
+        );
+
         Self {
             tables,
             functions,
-            aggregate_functions: HashMap::new(),
+            aggregate_functions,
+            window_functions: HashMap::new(),
             source_defs: HashMap::new(),
             connections: HashMap::new(),
             profiles: HashMap::new(),
             udf_defs: HashMap::new(),
+            table_function_calls: HashMap::new(),
             config_options: datafusion::config::ConfigOptions::new(),
         }
     }
@@ -262,15 +310,120 @@ impl ArroyoSchemaProvider {
         None
     }
 
+    /// Returns the state type of a `&mut ProcessContext<S>` argument
+    fn process_context_state(ty: &syn::Type) -> Option<syn::Type> {
+        if let syn::Type::Reference(syn::TypeReference {
+            mutability: Some(_),
+            elem,
+            ..
+        }) = ty
+        {
+            if let syn::Type::Path(syn::TypePath { path, .. }) = &**elem {
+                if let Some(segment) = path.segments.last() {
+                    if segment.ident == "ProcessContext" {
+                        if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                            if args.args.len() == 1 {
+                                if let syn::GenericArgument::Type(inner_ty) = &args.args[0] {
+                                    return Some(inner_ty.clone());
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Converts the struct returned by a table function into the rows it produces
+    fn table_function_row(name: &str, row: &ItemStruct) -> Result<StructDef> {
+        let Fields::Named(fields) = &row.fields else {
+            bail!(
+                "Table function {} must return a struct with named fields",
+                name
+            );
+        };
+        if fields.named.is_empty() {
+            bail!(
+                "Table function {} must return a struct with at least one field",
+                name
+            );
+        }
+        Self::udf_struct(row, fields)
+    }
+
+    /// Converts the struct a process function keeps for each key into the state it's stored as
+    fn process_function_state(name: &str, state: &ItemStruct) -> Result<StructDef> {
+        let Fields::Named(fields) = &state.fields else {
+            bail!(
+                "Process function {} must keep a struct with named fields as its state",
+                name
+            );
+        };
+        Self::udf_struct(state, fields)
+    }
+
+    fn udf_struct(item: &ItemStruct, fields: &syn::FieldsNamed) -> Result<StructDef> {
+        let fields = fields
+            .named
+            .iter()
+            .map(|field| {
+                let field_name = field.ident.as_ref().unwrap().to_string();
+                let data_type: TypeDef = (&field.ty).try_into().map_err(|_| {
+                    anyhow!(
+                        "Could not convert field {} of {} into a SQL data type",
+                        field_name,
+                        item.ident
+                    )
+                })?;
+                Ok(StructField::new(field_name, None, data_type))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(StructDef::new(None, true, fields, None))
+    }
+
     pub fn add_rust_udf(&mut self, body: &str) -> Result<String> {
         let mut file = parse_file(body)?;
 
-        let mut functions = file.items.iter_mut().filter_map(|item| match item {
-            Item::Fn(function) => Some(function),
-            _ => None,
-        });
-
-        let function = match (functions.next(), functions.next()) {
+        // structs may be returned by table functions or kept as the state of process functions,
+        // so they need to be accessible
+        let structs: HashMap<String, ItemStruct> = file
+            .items
+            .iter_mut()
+            .filter_map(|item| match item {
+                Item::Struct(item_struct) => {
+                    item_struct.vis = Visibility::Public(Default::default());
+                    item_struct
+                        .fields
+                        .iter_mut()
+                        .for_each(|field| field.vis = Visibility::Public(Default::default()));
+                    Some((item_struct.ident.to_string(), item_struct.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut functions: Vec<_> = file
+            .items
+            .iter_mut()
+            .filter_map(|item| match item {
+                Item::Fn(function) => Some(function),
+                _ => None,
+            })
+            .collect();
+
+        // process functions may be defined along with a `<name>_on_timer` function
+        let timer_function = functions
+            .iter()
+            .position(|timer| {
+                functions
+                    .iter()
+                    .any(|function| timer.sig.ident == format!("{}_on_timer", function.sig.ident))
+            })
+            .map(|i| functions.remove(i));
+
+        let function = match (functions.pop(), functions.pop()) {
             (Some(function), None) => function,
             _ => bail!("UDF definition must contain exactly 1 function."),
         };
@@ -283,17 +436,32 @@ impl ArroyoSchemaProvider {
         let inputs = function.sig.inputs.iter();
         let mut skip = 0;
         let mut has_context = false;
-
-        if async_fn {
-            // skip the first argument if it is a context
-            if function.sig.inputs.len() >= 1 {
-                if let FnArg::Typed(t) = function.sig.inputs.first().unwrap() {
-                    if let syn::Pat::Ident(i) = &*t.pat {
-                        if i.ident == "context" {
-                            // TODO: how to ensure type is Arc<Context>?
-                            has_context = true;
-                            skip = 1
+        let mut process_state = None;
+
+        // skip the first argument if it is a context
+        if let Some(FnArg::Typed(t)) = function.sig.inputs.first() {
+            if let syn::Pat::Ident(i) = &*t.pat {
+                if i.ident == "context" {
+                    if let Some(state) = Self::process_context_state(&t.ty) {
+                        let state = match &state {
+                            syn::Type::Path(path) => path
+                                .path
+                                .get_ident()
+                                .and_then(|ident| structs.get(&ident.to_string())),
+                            _ => None,
                         }
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "The state of process function {} must be a struct defined in the UDF",
+                                name
+                            )
+                        })?;
+                        process_state = Some(state);
+                        skip = 1;
+                    } else if async_fn {
+                        // TODO: how to ensure type is Arc<Context>?
+                        has_context = true;
+                        skip = 1
                     }
                 }
             }
@@ -330,9 +498,41 @@ impl ArroyoSchemaProvider {
             }
         }
 
-        let ret: TypeDef = match &function.sig.output {
-            ReturnType::Default => bail!("Function {} return type must be specified", name),
-            ReturnType::Type(_, t) => (&**t).try_into().map_err(|_| {
+        // functions that return a vector of a struct defined alongside them are table functions
+        let row = match &function.sig.output {
+            ReturnType::Type(_, t) => Self::vec_inner_type(t)
+                .and_then(|inner| match inner {
+                    syn::Type::Path(path) => path
+                        .path
+                        .get_ident()
+                        .and_then(|ident| structs.get(&ident.to_string())),
+                    _ => None,
+                })
+                .map(|row| Self::table_function_row(&name, row))
+                .transpose()?,
+            ReturnType::Default => None,
+        };
+        let table_function = row.is_some();
+        let opts = parse_udf_opts(body)?;
+
+        let ret: TypeDef = match (&function.sig.output, row) {
+            (_, Some(_)) if opts.window => {
+                bail!("Window function {} must not return structs", name)
+            }
+            (_, Some(row)) => TypeDef::StructDef(row, false),
+            (ReturnType::Default, None) => {
+                bail!("Function {} return type must be specified", name)
+            }
+            // window functions return a value for each row of the partition
+            (ReturnType::Type(_, t), None) if opts.window => Self::vec_inner_type(t)
+                .and_then(|inner| (&inner).try_into().ok())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Window function {} must return a vector of a SQL data type",
+                        name
+                    )
+                })?,
+            (ReturnType::Type(_, t), None) => (&**t).try_into().map_err(|_| {
                 anyhow!(
                     "Could not convert function {} return type into a SQL data type",
                     name
@@ -342,7 +542,70 @@ impl ArroyoSchemaProvider {
         if vec_arguments > 0 && vec_arguments != args.len() {
             bail!("Function {} arguments must be vectors or none", name);
         }
-        if vec_arguments > 0 {
+        let process_function = match process_state {
+            Some(state) => {
+                if !table_function {
+                    bail!(
+                        "Process function {} must return a vector of a struct defined in the UDF",
+                        name
+                    );
+                }
+                if async_fn {
+                    bail!("Process function {} must not be async", name);
+                }
+                if args.is_empty() {
+                    bail!(
+                        "Process function {} must take the key it is partitioned by after its context",
+                        name
+                    );
+                }
+                if let Some(timer) = &timer_function {
+                    if timer.sig.inputs.len() != 2 {
+                        bail!(
+                            "Timer function {} must take the context and key of {}",
+                            timer.sig.ident,
+                            name
+                        );
+                    }
+                }
+
+                Some(ProcessFunctionDef {
+                    state: Self::process_function_state(&name, state)?,
+                    state_struct: state.ident.to_string(),
+                    handles_timers: timer_function.is_some(),
+                })
+            }
+            None if timer_function.is_some() => {
+                bail!("UDF definition must contain exactly 1 function.")
+            }
+            None => None,
+        };
+
+        if opts.window {
+            if args.is_empty() || vec_arguments != args.len() {
+                bail!("Window function {} arguments must be vectors", name);
+            }
+            if async_fn {
+                bail!("Window function {} must not be async", name);
+            }
+            let return_type = Arc::new(ret.as_datatype().unwrap().clone());
+            let signature = Signature::exact(
+                args.iter()
+                    .map(|t| t.as_datatype().unwrap().clone())
+                    .collect(),
+                Volatility::Volatile,
+            );
+            let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(return_type.clone()));
+            let partition_evaluator: PartitionEvaluatorFactory = Arc::new(|| unreachable!());
+            let udwf = WindowUDF::new(&name, &signature, &return_type, &partition_evaluator);
+            self.window_functions.insert(name.clone(), Arc::new(udwf));
+        } else if table_function {
+            if vec_arguments > 0 {
+                bail!("Table function {} arguments must not be vectors", name);
+            }
+            // table functions are only planned through the joins they appear in, see
+            // `add_table_function_call`
+        } else if vec_arguments > 0 {
             let return_type = Arc::new(ret.as_datatype().unwrap().clone());
             let name = function.sig.ident.to_string();
             let signature = Signature::exact(
@@ -359,32 +622,13 @@ impl ArroyoSchemaProvider {
             self.aggregate_functions
                 .insert(function.sig.ident.to_string(), Arc::new(udaf));
         } else {
-            let fn_impl = |args: &[ArrayRef]| Ok(Arc::new(args[0].clone()) as ArrayRef);
-
-            if self
-                .functions
-                .insert(
-                    function.sig.ident.to_string(),
-                    Arc::new(create_udf(
-                        &function.sig.ident.to_string(),
-                        args.iter()
-                            .map(|t| t.as_datatype().unwrap().clone())
-                            .collect(),
-                        Arc::new(ret.as_datatype().unwrap().clone()),
-                        Volatility::Volatile,
-                        make_scalar_function(fn_impl),
-                    )),
-                )
-                .is_some()
-            {
-                warn!(
-                    "Global UDF '{}' is being overwritten",
-                    function.sig.ident.to_string()
-                );
-            };
+            self.add_scalar_udf(&name, &args, &ret);
         }
 
         function.vis = Visibility::Public(Default::default());
+        if let Some(timer) = timer_function {
+            timer.vis = Visibility::Public(Default::default());
+        }
 
         self.udf_defs.insert(
             function.sig.ident.to_string(),
@@ -394,13 +638,159 @@ impl ArroyoSchemaProvider {
                 async_fn,
                 def: unparse(&file.clone()),
                 dependencies: parse_dependencies(&body)?,
-                opts: parse_udf_opts(&body)?,
+                opts,
                 has_context,
+                table_function,
+                process_function,
+                wasm: None,
             },
         );
 
         Ok(name)
     }
+
+    fn add_scalar_udf(&mut self, name: &str, args: &[TypeDef], ret: &TypeDef) {
+        let fn_impl = |args: &[ArrayRef]| Ok(Arc::new(args[0].clone()) as ArrayRef);
+
+        if self
+            .functions
+            .insert(
+                name.to_string(),
+                Arc::new(create_udf(
+                    name,
+                    args.iter()
+                        .map(|t| t.as_datatype().unwrap().clone())
+                        .collect(),
+                    Arc::new(ret.as_datatype().unwrap().clone()),
+                    Volatility::Volatile,
+                    make_scalar_function(fn_impl),
+                )),
+            )
+            .is_some()
+        {
+            warn!("Global UDF '{}' is being overwritten", name);
+        };
+    }
+
+    /// Adds a scalar UDF that is implemented by a prebuilt WASM module, so that it doesn't need
+    /// to be compiled with the pipeline. The definition is the signature of the function that
+    /// the module exports, like `fn my_udf(x: i64, y: Option<String>) -> f64;`, and its arguments
+    /// and return value must be booleans, integers, floats or strings (see
+    /// `arroyo_worker::operators::wasm_udf` for the ABI the module is called with).
+    pub fn add_wasm_udf(&mut self, definition: &str, wasm: &[u8]) -> Result<String> {
+        let function: ForeignItemFn = parse_str(definition)
+            .map_err(|e| anyhow!("WASM UDF definition must be a function signature: {}", e))?;
+
+        let name = function.sig.ident.to_string();
+        if function.sig.asyncness.is_some() {
+            bail!("WASM UDF {} must not be async", name);
+        }
+        if !wasm.starts_with(b"\0asm") {
+            bail!("WASM UDF {} does not have a valid WebAssembly module", name);
+        }
+
+        let wasm_type = |ty: &syn::Type| -> Option<TypeDef> {
+            let def: TypeDef = ty.try_into().ok()?;
+            match def.as_datatype()? {
+                DataType::Boolean
+                | DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64
+                | DataType::Float32
+                | DataType::Float64
+                | DataType::Utf8 => Some(def),
+                _ => None,
+            }
+        };
+
+        let args = function
+            .sig
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| match arg {
+                FnArg::Receiver(_) => bail!(
+                    "Function {} has a 'self' argument, which is not allowed",
+                    name
+                ),
+                FnArg::Typed(t) => wasm_type(&t.ty).ok_or_else(|| {
+                    anyhow!(
+                        "WASM UDF {} arg {} must be a boolean, integer, float or string",
+                        name,
+                        i
+                    )
+                }),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let ret = match &function.sig.output {
+            ReturnType::Default => bail!("Function {} return type must be specified", name),
+            ReturnType::Type(_, t) => wasm_type(t).ok_or_else(|| {
+                anyhow!(
+                    "WASM UDF {} must return a boolean, integer, float or string",
+                    name
+                )
+            })?,
+        };
+
+        self.add_scalar_udf(&name, &args, &ret);
+
+        self.udf_defs.insert(
+            name.clone(),
+            UdfDef {
+                args,
+                ret,
+                async_fn: false,
+                def: definition.to_string(),
+                dependencies: String::new(),
+                opts: parse_udf_opts(definition)?,
+                has_context: false,
+                table_function: false,
+                process_function: None,
+                wasm: Some(wasm.to_vec()),
+            },
+        );
+
+        Ok(name)
+    }
+
+    /// Registers the placeholder table that a call of a table function is planned as, along with
+    /// the function that marks the call's join with its input (see `rewrite_table_functions`)
+    fn add_table_function_call(&mut self, placeholder: String, function: String) -> Result<()> {
+        let def = self
+            .udf_defs
+            .get(&function)
+            .ok_or_else(|| anyhow!("no table function with name '{}'", function))?;
+        let TypeDef::StructDef(row, _) = &def.ret else {
+            unreachable!("table functions return structs");
+        };
+
+        // the marker is called on the arguments of the function and a column of its rows
+        let marker_args = def
+            .args
+            .iter()
+            .chain(once(&row.fields[0].data_type))
+            .map(|t| t.as_datatype().unwrap().clone())
+            .collect();
+        let fn_impl = |args: &[ArrayRef]| Ok(Arc::new(args[0].clone()) as ArrayRef);
+        self.functions.insert(
+            placeholder.clone(),
+            Arc::new(create_udf(
+                &placeholder,
+                marker_args,
+                Arc::new(DataType::Boolean),
+                Volatility::Volatile,
+                make_scalar_function(fn_impl),
+            )),
+        );
+        self.table_function_calls.insert(placeholder, function);
+        Ok(())
+    }
 }
 
 fn get_toml_value(definition: &str) -> Result<Option<Value>> {
@@ -459,6 +849,18 @@ impl ContextProvider for ArroyoSchemaProvider {
         &self,
         name: TableReference,
     ) -> datafusion_common::Result<Arc<dyn TableSource>> {
+        if let Some(function) = self.table_function_calls.get(&name.to_string()) {
+            let TypeDef::StructDef(row, _) = &self.udf_defs.get(function).unwrap().ret else {
+                unreachable!("table functions return structs");
+            };
+            return Ok(create_table_source(
+                row.fields
+                    .iter()
+                    .map(|field| field.clone().into())
+                    .collect(),
+            ));
+        }
+
         let table = self.get_table(name.to_string()).ok_or_else(|| {
             datafusion::error::DataFusionError::Plan(format!("Table {} not found", name))
         })?;
@@ -484,8 +886,8 @@ impl ContextProvider for ArroyoSchemaProvider {
         &self.config_options
     }
 
-    fn get_window_meta(&self, _name: &str) -> Option<Arc<WindowUDF>> {
-        None
+    fn get_window_meta(&self, name: &str) -> Option<Arc<WindowUDF>> {
+        self.window_functions.get(name).cloned()
     }
 }
 
@@ -525,7 +927,21 @@ pub fn parse_and_get_program_sync(
 ) -> Result<CompiledSql> {
     let dialect = PostgreSqlDialect {};
     let mut inserts = vec![];
-    for statement in Parser::parse_sql(&dialect, &query)? {
+    let tokens = rewrite_metadata_columns(Tokenizer::new(&dialect, &query).tokenize()?);
+    let (tokens, temporal_tables) = extract_temporal_tables(tokens);
+    let (tokens, table_function_calls) = rewrite_table_functions(tokens, &schema_provider)?;
+    for (placeholder, function) in table_function_calls {
+        schema_provider.add_table_function_call(placeholder, function)?;
+    }
+    let mut window_options = WindowOptions::default();
+    for statement in Parser::new(&dialect)
+        .with_tokens(tokens)
+        .parse_statements()?
+    {
+        if window_options.try_set_from_statement(&statement)? {
+            continue;
+        }
+
         if let Some(table) = Table::try_from_statement(&statement, &schema_provider)? {
             schema_provider.insert_table(table);
         } else {
@@ -537,10 +953,21 @@ pub fn parse_and_get_program_sync(
     }
 
     let mut sql_pipeline_builder = SqlPipelineBuilder::new(&mut schema_provider);
+    sql_pipeline_builder.temporal_tables = temporal_tables;
+    sql_pipeline_builder.window_options = window_options;
     for insert in inserts {
         sql_pipeline_builder.add_insert(insert)?;
     }
 
+    if let Some(sink) = &sql_pipeline_builder.window_options.late_data_sink {
+        if sql_pipeline_builder.late_sinks.is_empty() {
+            bail!(
+                "late data sink '{}' is set, but the query does not contain any windows",
+                sink
+            );
+        }
+    }
+
     let mut plan_graph = PlanGraph::new(config.clone());
 
     // if there are no insert nodes, return an error
@@ -572,6 +999,7 @@ pub fn parse_and_get_program_sync(
             event_time_field: None,
             watermark_field: None,
             idle_time: DEFAULT_IDLE_TIME,
+            lookup: None,
             inferred_fields: None,
         });
 
@@ -582,6 +1010,13 @@ pub fn parse_and_get_program_sync(
         plan_graph.add_sql_operator(output);
     }
 
+    for (name, late_sink) in sql_pipeline_builder.late_sinks.into_iter() {
+        // the late records are only planned if their window is part of the pipeline
+        if plan_graph.named_tables.contains_key(&name) {
+            plan_graph.add_sql_operator(late_sink);
+        }
+    }
+
     get_program(plan_graph, sql_pipeline_builder.schema_provider.clone())
 }
 
@@ -769,6 +1204,7 @@ pub fn get_test_expression(
                     read_mode: Some(arroyo_connectors::kafka::ReadMode::ReadUncommitted),
                     group_id: "test-consumer-group".to_string().try_into().unwrap(),
                 },
+                subject_strategy: None,
                 client_configs: HashMap::new(),
             },
             Some(&schema),
@@ -815,21 +1251,28 @@ pub fn get_test_expression(
 pub fn has_duplicate_udf_names<'a>(definitions: impl Iterator<Item = &'a String>) -> bool {
     let mut udf_names = HashSet::new();
     for definition in definitions {
-        let Ok(file) = syn::parse_file(definition) else {
+        let names: Vec<String> = if let Ok(file) = syn::parse_file(definition) {
+            file.items
+                .into_iter()
+                .filter_map(|item| match item {
+                    Item::Fn(function) => Some(function.sig.ident.to_string()),
+                    _ => None,
+                })
+                .collect()
+        } else if let Ok(function) = parse_str::<ForeignItemFn>(definition) {
+            // WASM UDFs are defined by their signature
+            vec![function.sig.ident.to_string()]
+        } else {
             warn!("Could not parse UDF definition: {}", definition);
             continue;
         };
 
-        for item in file.items {
-            let Item::Fn(function) = item else {
-                continue;
-            };
-
-            if udf_names.contains(&function.sig.ident.to_string()) {
+        for name in names {
+            if udf_names.contains(&name) {
                 return true;
             }
 
-            udf_names.insert(function.sig.ident.to_string());
+            udf_names.insert(name);
         }
     }
     false
@@ -8,12 +8,13 @@ use crate::{
         ValuePointerContext, VecAggregationContext,
     },
     expressions::{
-        AggregateComputation, AggregateResultExtraction, Aggregator, Column, Expression,
+        hashable_value, list_type, AggregateComputation, AggregateResultExtraction, Aggregator,
+        Column, Expression, SortExpression,
     },
     types::{data_type_as_syn_type, StructDef, StructField, TypeDef},
 };
 use anyhow::{bail, Result};
-use arrow_schema::DataType;
+use arrow_schema::{DataType, TimeUnit};
 use arroyo_datastream::duration_to_syn_expr;
 use arroyo_rpc::formats::Format;
 use datafusion_expr::type_coercion::aggregates::{avg_return_type, sum_return_type};
@@ -176,76 +177,215 @@ impl AsyncUdfProjection {
     }
 }
 
+/// Builds the invocation of an async UDF on the fields of the input, returning the statements
+/// that compute its arguments, the invocation itself and the type of the UDF context.
+///
+/// The invocation evaluates to a `Result` of the UDF's return type, which is wrapped in an
+/// `Option` if the UDF is not invoked for null arguments.
+fn async_udf_invocation(
+    async_udf: &RustUdfExpression,
+    input_context: &ValuePointerContext,
+) -> (Vec<TokenStream>, TokenStream, TokenStream) {
+    let mut may_not_invoke = false;
+    // definitions and identifiers for async udf invocation
+    let (initial_assignment, match_term_ids): (Vec<_>, Vec<_>) = async_udf
+        .args
+        .iter()
+        .enumerate()
+        .map(|(i, (def, expr))| {
+            let t = expr.generate(&input_context);
+            let id = format_ident!("__{}", i);
+            let (initial_assigment, match_term, id) = match (
+                expr.expression_type(&input_context).is_optional(),
+                def.is_optional(),
+            ) {
+                (true, true) => (quote!(let #id = #t), quote!(#id), id),
+                (true, false) => {
+                    may_not_invoke = true;
+                    (quote!(let #id = #t), quote!(Some(#id)), id)
+                }
+                (false, true) => (quote!(let #id = Some(#t)), quote!(#id), id),
+                (false, false) => (quote!(let #id = #t), quote!(#id), id),
+            };
+            (initial_assigment, (match_term, id))
+        })
+        .unzip();
+    let (match_terms, ids): (Vec<_>, Vec<_>) = match_term_ids.into_iter().unzip();
+
+    let function_name = format_ident!("{}", async_udf.name);
+    let timeout = duration_to_syn_expr(Duration::from_secs(async_udf.opts.async_timeout_seconds));
+
+    let mut context_t = quote! { EmptyContext };
+    let mut context_arg = quote!();
+
+    if async_udf.has_context {
+        context_t = quote! { udfs::Context };
+        context_arg = quote! {context.clone(), };
+    }
+
+    let args_pattern = quote!((#(#ids),*));
+    let args = quote!((#context_arg #(#ids),*));
+
+    let invocation = if may_not_invoke {
+        // turn ids into a tuple
+        let match_terms = quote!((#(#match_terms),*));
+        let suffix = if async_udf.ret_type.is_optional() {
+            None
+        } else {
+            Some(quote!(.map(|result| Some(result))))
+        };
+        quote!(
+            match #args_pattern {
+                #match_terms => {
+                    timeout(#timeout, udfs:: #function_name #args).await #suffix
+                }
+                _ => {
+                    Ok(None)
+                }
+            }
+        )
+    } else {
+        quote!(timeout(#timeout, udfs:: #function_name #args).await)
+    };
+
+    (initial_assignment, invocation, context_t)
+}
+
 impl CodeGenerator<ValuePointerContext, StructDef, syn::Expr> for AsyncUdfProjection {
     fn generate(&self, input_context: &ValuePointerContext) -> syn::Expr {
         let input_struct = self.input_struct.get_type();
         let output_type = self.expression_type(input_context).get_type();
         let output_struct = self.projection.generate(input_context);
         let input_name = input_context.variable_ident();
-        let mut may_not_invoke = false;
-        // definitions and identifiers for async udf invocation
-        let (initial_assignment, match_term_ids): (Vec<_>, Vec<_>) = self
-            .async_udf
-            .args
-            .iter()
-            .enumerate()
-            .map(|(i, (def, expr))| {
-                let t = expr.generate(&input_context);
-                let id = format_ident!("__{}", i);
-                let (initial_assigment, match_term, id) = match (
-                    expr.expression_type(&input_context).is_optional(),
-                    def.is_optional(),
-                ) {
-                    (true, true) => (quote!(let #id = #t), quote!(#id), id),
-                    (true, false) => {
-                        may_not_invoke = true;
-                        (quote!(let #id = #t), quote!(Some(#id)), id)
-                    }
-                    (false, true) => (quote!(let #id = Some(#t)), quote!(#id), id),
-                    (false, false) => (quote!(let #id = #t), quote!(#id), id),
-                };
-                (initial_assigment, (match_term, id))
-            })
-            .unzip();
-        let (match_terms, ids): (Vec<_>, Vec<_>) = match_term_ids.into_iter().unzip();
+        let (initial_assignment, invocation, context_t) =
+            async_udf_invocation(&self.async_udf, input_context);
 
-        let function_name = format_ident!("{}", self.async_udf.name);
-        let timeout = duration_to_syn_expr(Duration::from_secs(
-            self.async_udf.opts.async_timeout_seconds,
-        ));
+        parse_quote! {{
+            use tokio::time::error::Elapsed;
+            use tokio::time::{timeout, Duration};
+            use std::sync::Arc;
+            async fn wrapper(
+                index: usize,
+                #input_name: #input_struct,
+                context: Arc<#context_t>
+            ) -> (
+                usize,
+                Result<#output_type, Elapsed>,
+            ) {
+                #(#initial_assignment;)*
+                let udf_result = #invocation;
+                (index, udf_result.map(|async_result| #output_struct))
+            };
+            wrapper
+        }
+        }
+    }
 
-        let mut context_t = quote! { EmptyContext };
-        let mut context_arg = quote!();
+    fn expression_type(&self, input_context: &ValuePointerContext) -> StructDef {
+        self.projection.expression_type(input_context)
+    }
+}
 
-        if self.async_udf.has_context {
-            context_t = quote! { udfs::Context };
-            context_arg = quote! {context.clone(), };
-        }
+/// Flat-maps each record into the rows a table function returns for it, combining the fields
+/// of the record with those of each row
+#[derive(Debug, Clone)]
+pub struct TableFunctionProjection {
+    pub input_struct: StructDef,
+    pub function: RustUdfExpression,
+    pub row_struct: StructDef,
+    // filters that only reference the rows, which are applied before they are combined
+    pub row_filters: Vec<Expression>,
+    pub fields: Vec<(Column, Expression, UnnestFieldType)>,
+}
 
-        let args_pattern = quote!((#(#ids),*));
-        let args = quote!((#context_arg #(#ids),*));
+impl TableFunctionProjection {
+    pub fn output_struct(&self) -> StructDef {
+        self.expression_type(&ValuePointerContext::new())
+    }
 
-        let invocation = if may_not_invoke {
-            // turn ids into a tuple
-            let match_terms = quote!((#(#match_terms),*));
-            let suffix = if self.async_udf.ret_type.is_optional() {
-                None
-            } else {
-                Some(quote!(.map(|result| Some(result))))
-            };
-            quote!(
-                match #args_pattern {
-                    #match_terms => {
-                        timeout(#timeout, udfs:: #function_name #args).await #suffix
-                    }
-                    _ => {
-                        Ok(None)
-                    }
-                }
-            )
+    pub fn is_async(&self) -> bool {
+        self.function.async_fn
+    }
+
+    /// Converts the rows returned by the function, which may be missing if it wasn't invoked
+    /// because of null arguments, into the output structs
+    fn rows(&self, rows: syn::Expr, input_context: &ValuePointerContext) -> syn::Expr {
+        let handle_optional = if self.function.expression_type(input_context).is_optional() {
+            quote! { .flatten() }
         } else {
-            quote!(timeout(#timeout, udfs:: #function_name #args).await)
+            quote!()
         };
+
+        let row_context = ValuePointerContext::with_arg("___row");
+        let row_type = self.row_struct.get_type();
+        let row_assignments: Vec<_> = self
+            .row_struct
+            .fields
+            .iter()
+            .map(|field| {
+                let field_ident = field.field_ident();
+                let name = format_ident!("{}", field.name);
+                quote!(#field_ident: ___row.#name)
+            })
+            .collect();
+
+        let filters: Vec<_> = self
+            .row_filters
+            .iter()
+            .map(|filter| {
+                let unwrap = if filter.expression_type(&row_context).is_optional() {
+                    Some(quote!(.unwrap_or(false)))
+                } else {
+                    None
+                };
+                let filter = filter.generate(&row_context);
+                quote!(.filter(|___row| #filter #unwrap))
+            })
+            .collect();
+
+        let assignments: Vec<_> = self
+            .fields
+            .iter()
+            .map(|(col, expr, typ)| {
+                let ctx = match typ {
+                    UnnestFieldType::Default => input_context,
+                    UnnestFieldType::UnnestOuter => &row_context,
+                };
+                let field_ident = StructField::new(
+                    col.name.clone(),
+                    col.relation.clone(),
+                    expr.expression_type(ctx),
+                )
+                .field_ident();
+                let expr = expr.generate(ctx);
+                quote!(#field_ident: #expr)
+            })
+            .collect();
+        let output_type = self.expression_type(input_context).get_type();
+
+        parse_quote!(
+            #rows.into_iter()
+                #handle_optional
+                .map(|___row| #row_type { #(#row_assignments),* })
+                #(#filters)*
+                .map(|___row| {
+                    #output_type {
+                        #(#assignments),*
+                    }
+                })
+        )
+    }
+
+    /// Generates the async function that invokes an async table function for a record, which
+    /// returns all of the output rows for it
+    pub fn generate_async(&self, input_context: &ValuePointerContext) -> syn::Expr {
+        let input_struct = self.input_struct.get_type();
+        let output_type = self.expression_type(input_context).get_type();
+        let input_name = input_context.variable_ident();
+        let (initial_assignment, invocation, context_t) =
+            async_udf_invocation(&self.function, input_context);
+        let rows = self.rows(parse_quote!(rows), input_context);
+
         parse_quote! {{
             use tokio::time::error::Elapsed;
             use tokio::time::{timeout, Duration};
@@ -256,19 +396,35 @@ impl CodeGenerator<ValuePointerContext, StructDef, syn::Expr> for AsyncUdfProjec
                 context: Arc<#context_t>
             ) -> (
                 usize,
-                Result<#output_type, Elapsed>,
+                Result<Vec<#output_type>, Elapsed>,
             ) {
                 #(#initial_assignment;)*
                 let udf_result = #invocation;
-                (index, udf_result.map(|async_result| #output_struct))
+                (index, udf_result.map(|rows| #rows.collect()))
             };
             wrapper
         }
         }
     }
+}
+
+impl CodeGenerator<ValuePointerContext, StructDef, syn::Expr> for TableFunctionProjection {
+    fn generate(&self, input_context: &ValuePointerContext) -> syn::Expr {
+        let rows = self.function.generate(input_context);
+        self.rows(rows, input_context)
+    }
 
     fn expression_type(&self, input_context: &ValuePointerContext) -> StructDef {
-        self.projection.expression_type(input_context)
+        let fields: Vec<_> = self
+            .fields
+            .iter()
+            .map(|(col, computation, _)| {
+                let field_type = computation.expression_type(&input_context);
+                StructField::new(col.name.clone(), col.relation.clone(), field_type)
+            })
+            .collect();
+
+        StructDef::new(None, true, fields, None)
     }
 }
 
@@ -346,9 +502,14 @@ pub struct TwoPhaseAggregateProjection {
 
 impl TwoPhaseAggregateProjection {
     pub fn expressions(&mut self) -> impl Iterator<Item = &mut Expression> {
-        self.aggregates
-            .iter_mut()
-            .map(|(_, computation)| &mut computation.incoming_expression)
+        self.aggregates.iter_mut().flat_map(|(_, computation)| {
+            std::iter::once(&mut computation.incoming_expression).chain(
+                computation
+                    .order_by
+                    .iter_mut()
+                    .map(|sort_expression| sort_expression.expression()),
+            )
+        })
     }
 }
 
@@ -647,6 +808,8 @@ impl TwoPhaseAggregateProjection {
 pub struct TwoPhaseAggregation {
     pub incoming_expression: Expression,
     pub aggregator: Aggregator,
+    pub order_by: Vec<SortExpression>,
+    pub ignore_nulls: bool,
 }
 
 impl CodeGenerator<ValueBinMergingContext, BinType, syn::Expr> for TwoPhaseAggregation {
@@ -665,6 +828,17 @@ impl CodeGenerator<ValueBinMergingContext, BinType, syn::Expr> for TwoPhaseAggre
             .incoming_expression
             .expression_type(&input_context.value_context)
             .is_optional();
+        let timestamp_ident = input_context.timestamp_ident();
+        let float_value: syn::Expr = if input_nullable {
+            parse_quote!((#expr).map(|value| value as f64))
+        } else {
+            parse_quote!(Some((#expr) as f64))
+        };
+        let optional_value: syn::Expr = if input_nullable {
+            parse_quote!(#expr)
+        } else {
+            parse_quote!(Some(#expr))
+        };
         match (&self.aggregator, input_nullable) {
             (Aggregator::Count, true) => parse_quote!({
                 let  count = #current_bin_ident.unwrap_or(0);
@@ -729,6 +903,60 @@ impl CodeGenerator<ValueBinMergingContext, BinType, syn::Expr> for TwoPhaseAggre
                 }
             }),
             (Aggregator::CountDistinct, _) => unreachable!("no two phase for count distinct"),
+            (
+                Aggregator::Variance
+                | Aggregator::VariancePop
+                | Aggregator::Stddev
+                | Aggregator::StddevPop,
+                _,
+            ) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::variance_add(#current_bin_ident, #float_value)
+            }),
+            (Aggregator::ApproxPercentileCont(_), _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::sketch_add(#current_bin_ident, #float_value)
+            }),
+            (Aggregator::FirstValue, _) => {
+                let key = self.value_key(&timestamp_ident);
+                parse_quote!({
+                    let value = #expr;
+                    arroyo_worker::operators::functions::aggregates::first_value_add(#current_bin_ident, #key, value)
+                })
+            }
+            (Aggregator::LastValue, _) => {
+                let key = self.value_key(&timestamp_ident);
+                parse_quote!({
+                    let value = #expr;
+                    arroyo_worker::operators::functions::aggregates::last_value_add(#current_bin_ident, #key, value)
+                })
+            }
+            (Aggregator::ArrayAgg | Aggregator::StringAgg(_), _) if !self.order_by.is_empty() => {
+                let sort_key = SortExpression::sort_tuple_expression(&self.order_by);
+                let value = self.list_value(expr, input_nullable);
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::keyed_values_add(#current_bin_ident, #sort_key, #value)
+                })
+            }
+            (Aggregator::ArrayAgg, _) if self.ignore_nulls => {
+                let value = self.list_value(expr, input_nullable);
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::list_add_non_null(#current_bin_ident, #value)
+                })
+            }
+            (Aggregator::ArrayAgg, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::array_agg_add(#current_bin_ident, #optional_value)
+            }),
+            (Aggregator::StringAgg(_), _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::list_add_non_null(#current_bin_ident, #optional_value)
+            }),
+            (Aggregator::ApproxDistinct, nullable) => {
+                let input_type = self
+                    .incoming_expression
+                    .expression_type(&input_context.value_context);
+                let value = hashable_value(expr, input_type.as_datatype().unwrap(), nullable);
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::hll_add(#current_bin_ident, #value)
+                })
+            }
         }
     }
 
@@ -788,6 +1016,35 @@ impl CodeGenerator<CombiningContext, BinType, syn::Expr> for TwoPhaseAggregation
                 parse_quote!({ (#current_bin_ident.0 + #new_bin_ident.0, #current_bin_ident.1 + #new_bin_ident.1) })
             }
             (Aggregator::CountDistinct, _) => unreachable!("no two phase for count distinct"),
+            (
+                Aggregator::Variance
+                | Aggregator::VariancePop
+                | Aggregator::Stddev
+                | Aggregator::StddevPop,
+                _,
+            ) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::variance_combine(#current_bin_ident, #new_bin_ident)
+            }),
+            (Aggregator::ApproxPercentileCont(_), _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::sketch_combine(#current_bin_ident, #new_bin_ident)
+            }),
+            (Aggregator::FirstValue, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::first_value_combine(#current_bin_ident, #new_bin_ident)
+            }),
+            (Aggregator::LastValue, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::last_value_combine(#current_bin_ident, #new_bin_ident)
+            }),
+            (Aggregator::ArrayAgg | Aggregator::StringAgg(_), _) if !self.order_by.is_empty() => {
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::keyed_values_combine(#current_bin_ident, #new_bin_ident)
+                })
+            }
+            (Aggregator::ArrayAgg | Aggregator::StringAgg(_), _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::list_combine(#current_bin_ident, #new_bin_ident)
+            }),
+            (Aggregator::ApproxDistinct, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::hll_combine(#current_bin_ident, #new_bin_ident)
+            }),
         }
     }
 
@@ -839,6 +1096,31 @@ impl CodeGenerator<MemoryAddingContext, BinType, syn::Expr> for TwoPhaseAggregat
             }),
             (Aggregator::CountDistinct, true) => todo!(),
             (Aggregator::CountDistinct, false) => todo!(),
+            (
+                Aggregator::Variance
+                | Aggregator::VariancePop
+                | Aggregator::Stddev
+                | Aggregator::StddevPop,
+                _,
+            ) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::variance_combine(#memory_ident.unwrap_or_default(), #bin_value_ident)
+            }),
+            (Aggregator::ApproxPercentileCont(_), _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::sketch_combine(#memory_ident.unwrap_or_default(), #bin_value_ident)
+            }),
+            (Aggregator::FirstValue | Aggregator::LastValue, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::keyed_value_memory_add(#memory_ident, #bin_value_ident)
+            }),
+            (Aggregator::ArrayAgg | Aggregator::StringAgg(_), _) if !self.order_by.is_empty() => {
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::keyed_values_combine(#memory_ident.unwrap_or_default(), #bin_value_ident)
+                })
+            }
+            (Aggregator::ArrayAgg | Aggregator::StringAgg(_) | Aggregator::ApproxDistinct, _) => {
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::list_memory_add(#memory_ident, #bin_value_ident)
+                })
+            }
         }
     }
 
@@ -884,6 +1166,31 @@ impl CodeGenerator<MemoryRemovingContext, BinType, syn::Expr> for TwoPhaseAggreg
             }),
             (Aggregator::CountDistinct, true) => todo!(),
             (Aggregator::CountDistinct, false) => todo!(),
+            (
+                Aggregator::Variance
+                | Aggregator::VariancePop
+                | Aggregator::Stddev
+                | Aggregator::StddevPop,
+                _,
+            ) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::variance_remove(#memory_ident, #bin_value_ident)
+            }),
+            (Aggregator::ApproxPercentileCont(_), _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::sketch_remove(#memory_ident, #bin_value_ident)
+            }),
+            (Aggregator::FirstValue | Aggregator::LastValue, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::keyed_value_memory_remove(#memory_ident, #bin_value_ident)
+            }),
+            (Aggregator::ArrayAgg | Aggregator::StringAgg(_), _) if !self.order_by.is_empty() => {
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::keyed_values_remove(#memory_ident, #bin_value_ident)
+                })
+            }
+            (Aggregator::ArrayAgg | Aggregator::StringAgg(_) | Aggregator::ApproxDistinct, _) => {
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::list_memory_remove(#memory_ident, #bin_value_ident)
+                })
+            }
         }
     }
 
@@ -913,6 +1220,37 @@ impl CodeGenerator<BinAggregatingContext, TypeDef, syn::Expr> for TwoPhaseAggreg
             }
             (Aggregator::CountDistinct, true) => unimplemented!(),
             (Aggregator::CountDistinct, false) => unimplemented!(),
+            (
+                Aggregator::Variance
+                | Aggregator::VariancePop
+                | Aggregator::Stddev
+                | Aggregator::StddevPop
+                | Aggregator::ApproxPercentileCont(_),
+                _,
+            ) => self.statistic_result(&bin_name, &input_context.value_context),
+            (Aggregator::FirstValue | Aggregator::LastValue, _) => {
+                parse_quote!(#bin_name.1.clone())
+            }
+            (Aggregator::ArrayAgg, _) if !self.order_by.is_empty() => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::keyed_values(#bin_name)
+            }),
+            (Aggregator::ArrayAgg, _) => parse_quote!(#bin_name.clone()),
+            (Aggregator::StringAgg(delimiter), nullable) if !self.order_by.is_empty() => {
+                let unwrap = (!nullable).then(|| quote!(.unwrap()));
+                parse_quote!({
+                    let values = arroyo_worker::operators::functions::aggregates::keyed_values(#bin_name);
+                    arroyo_worker::operators::functions::aggregates::string_agg(&values, #delimiter)#unwrap
+                })
+            }
+            (Aggregator::StringAgg(delimiter), nullable) => {
+                let unwrap = (!nullable).then(|| quote!(.unwrap()));
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::string_agg(#bin_name, #delimiter)#unwrap
+                })
+            }
+            (Aggregator::ApproxDistinct, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::hll_estimate(#bin_name)
+            }),
         }
     }
 
@@ -962,6 +1300,42 @@ impl CodeGenerator<MemoryAggregatingContext, TypeDef, syn::Expr> for TwoPhaseAgg
             }
             (Aggregator::CountDistinct, true) => unimplemented!(),
             (Aggregator::CountDistinct, false) => unimplemented!(),
+            (
+                Aggregator::Variance
+                | Aggregator::VariancePop
+                | Aggregator::Stddev
+                | Aggregator::StddevPop
+                | Aggregator::ApproxPercentileCont(_),
+                _,
+            ) => self.statistic_result(&bin_name, &input_context.value_context),
+            (Aggregator::FirstValue, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::first_value_memory(#bin_name).unwrap()
+            }),
+            (Aggregator::LastValue, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::last_value_memory(#bin_name).unwrap()
+            }),
+            (Aggregator::ArrayAgg, _) if !self.order_by.is_empty() => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::keyed_values(#bin_name)
+            }),
+            (Aggregator::StringAgg(delimiter), nullable) if !self.order_by.is_empty() => {
+                let unwrap = (!nullable).then(|| quote!(.unwrap()));
+                parse_quote!({
+                    let values = arroyo_worker::operators::functions::aggregates::keyed_values(#bin_name);
+                    arroyo_worker::operators::functions::aggregates::string_agg(&values, #delimiter)#unwrap
+                })
+            }
+            (Aggregator::ArrayAgg, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::array_agg_memory(#bin_name)
+            }),
+            (Aggregator::StringAgg(delimiter), nullable) => {
+                let unwrap = (!nullable).then(|| quote!(.unwrap()));
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::string_agg_memory(#bin_name, #delimiter)#unwrap
+                })
+            }
+            (Aggregator::ApproxDistinct, _) => parse_quote!({
+                arroyo_worker::operators::functions::aggregates::hll_memory_estimate(#bin_name)
+            }),
         }
     }
 
@@ -987,8 +1361,18 @@ impl TwoPhaseAggregation {
             }
             Aggregator::Min | Aggregator::Max => data_type,
             Aggregator::CountDistinct => unimplemented!(),
+            Aggregator::Variance
+            | Aggregator::VariancePop
+            | Aggregator::Stddev
+            | Aggregator::StddevPop => DataType::Float64,
+            Aggregator::ApproxPercentileCont(_)
+            | Aggregator::FirstValue
+            | Aggregator::LastValue => data_type,
+            Aggregator::ArrayAgg => list_type(data_type, true),
+            Aggregator::StringAgg(_) => DataType::Utf8,
+            Aggregator::ApproxDistinct => DataType::UInt64,
         };
-        TypeDef::DataType(aggregate_type, nullable)
+        TypeDef::DataType(aggregate_type, self.aggregator.return_nullable(nullable))
     }
 
     // the data type to be used in intermediate aggregations. Mainly relevant for average.
@@ -1005,6 +1389,7 @@ impl TwoPhaseAggregation {
             }
             Aggregator::Min | Aggregator::Max => data_type,
             Aggregator::CountDistinct => unimplemented!(),
+            _ => return self.output_type_def(input_context),
         };
         TypeDef::DataType(aggregate_type, nullable)
     }
@@ -1034,6 +1419,44 @@ impl TwoPhaseAggregation {
                 BinType::DataType(aggregate_type),
             ]),
             (Aggregator::CountDistinct, _) => unimplemented!(),
+            (
+                Aggregator::Variance
+                | Aggregator::VariancePop
+                | Aggregator::Stddev
+                | Aggregator::StddevPop,
+                _,
+            ) => BinType::Tuple(vec![
+                BinType::DataType(DataType::Int64),
+                BinType::DataType(DataType::Float64),
+                BinType::DataType(DataType::Float64),
+            ]),
+            (Aggregator::ApproxPercentileCont(_), _) => BinType::BTreeMap(
+                Box::new(BinType::DataType(DataType::Int32)),
+                Box::new(BinType::DataType(DataType::Int64)),
+            ),
+            (Aggregator::FirstValue | Aggregator::LastValue, nullable) => {
+                let value_type = BinType::DataType(aggregate_type);
+                BinType::Tuple(vec![
+                    self.value_key_type(),
+                    if nullable {
+                        BinType::Option(Box::new(value_type))
+                    } else {
+                        value_type
+                    },
+                ])
+            }
+            (Aggregator::ArrayAgg, _) if !self.order_by.is_empty() => BinType::BTreeMap(
+                Box::new(BinType::SortKey(self.order_by.clone())),
+                Box::new(BinType::DataType(aggregate_type)),
+            ),
+            (Aggregator::StringAgg(_), _) if !self.order_by.is_empty() => BinType::BTreeMap(
+                Box::new(BinType::SortKey(self.order_by.clone())),
+                Box::new(BinType::DataType(list_type(DataType::Utf8, false))),
+            ),
+            (Aggregator::ArrayAgg, _) => BinType::DataType(aggregate_type),
+            (Aggregator::StringAgg(_), _) => BinType::DataType(list_type(DataType::Utf8, false)),
+            // the registers of the HyperLogLog sketch
+            (Aggregator::ApproxDistinct, _) => BinType::DataType(list_type(DataType::UInt8, false)),
         }
     }
 
@@ -1083,6 +1506,99 @@ impl TwoPhaseAggregation {
                 BinType::DataType(aggregate_data_type),
             ]),
             (Aggregator::CountDistinct, _) => unimplemented!(),
+            (
+                Aggregator::Variance
+                | Aggregator::VariancePop
+                | Aggregator::Stddev
+                | Aggregator::StddevPop
+                | Aggregator::ApproxPercentileCont(_),
+                _,
+            ) => self.bin_type(input_context),
+            (Aggregator::FirstValue | Aggregator::LastValue, nullable) => BinType::BTreeMap(
+                Box::new(self.value_key_type()),
+                Box::new(BinType::DataType(list_type(aggregate_data_type, nullable))),
+            ),
+            (Aggregator::ArrayAgg | Aggregator::StringAgg(_), _) if !self.order_by.is_empty() => {
+                self.bin_type(input_context)
+            }
+            (Aggregator::ArrayAgg, _) => BinType::DataType(list_type(aggregate_data_type, false)),
+            (Aggregator::StringAgg(_), _) => {
+                BinType::DataType(list_type(list_type(DataType::Utf8, false), false))
+            }
+            (Aggregator::ApproxDistinct, _) => {
+                BinType::DataType(list_type(list_type(DataType::UInt8, false), false))
+            }
+        }
+    }
+
+    // first_value and last_value pick the value with the lowest or highest key, which is the
+    // event time unless the aggregate has an ORDER BY. When ignoring nulls, the key is prefixed
+    // with a flag that puts null values behind all others.
+    fn value_key(&self, timestamp_ident: &syn::Ident) -> syn::Expr {
+        let key: syn::Expr = if self.order_by.is_empty() {
+            parse_quote!(#timestamp_ident)
+        } else {
+            SortExpression::sort_tuple_expression(&self.order_by)
+        };
+        match (&self.aggregator, self.ignore_nulls) {
+            (Aggregator::FirstValue, true) => parse_quote!((value.is_none(), #key)),
+            (Aggregator::LastValue, true) => parse_quote!((value.is_some(), #key)),
+            _ => key,
+        }
+    }
+
+    fn value_key_type(&self) -> BinType {
+        let key_type = if self.order_by.is_empty() {
+            BinType::DataType(DataType::Timestamp(TimeUnit::Nanosecond, None))
+        } else {
+            BinType::SortKey(self.order_by.clone())
+        };
+        if self.ignore_nulls {
+            BinType::Tuple(vec![BinType::DataType(DataType::Boolean), key_type])
+        } else {
+            key_type
+        }
+    }
+
+    // the value added to array_agg and string_agg lists that skip nulls
+    fn list_value(&self, expr: syn::Expr, input_nullable: bool) -> syn::Expr {
+        match (&self.aggregator, input_nullable) {
+            // the list items are nullable, so they are wrapped in another option
+            (Aggregator::ArrayAgg, true) if self.ignore_nulls => parse_quote!((#expr).map(Some)),
+            (Aggregator::ArrayAgg, true) => parse_quote!(Some(#expr)),
+            (Aggregator::ArrayAgg, false) => parse_quote!(Some(Some(#expr))),
+            (_, true) => expr,
+            (_, false) => parse_quote!(Some(#expr)),
+        }
+    }
+
+    // the result of the statistical aggregates, whose bins and memory have the same type
+    fn statistic_result(
+        &self,
+        state: &syn::Ident,
+        input_context: &ValuePointerContext,
+    ) -> syn::Expr {
+        let output_type = self.output_type_def(input_context);
+        match &self.aggregator {
+            Aggregator::Variance
+            | Aggregator::VariancePop
+            | Aggregator::Stddev
+            | Aggregator::StddevPop => {
+                let sample = self.aggregator.is_sample();
+                let sqrt = self.aggregator.is_stddev().then(|| quote!(.map(f64::sqrt)));
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::variance(#state, #sample)#sqrt
+                })
+            }
+            Aggregator::ApproxPercentileCont(_) => {
+                let percentile = self.aggregator.percentile().unwrap();
+                let return_type = data_type_as_syn_type(output_type.as_datatype().unwrap());
+                parse_quote!({
+                    arroyo_worker::operators::functions::aggregates::sketch_quantile(#state, #percentile)
+                        .map(|value| value as #return_type)
+                })
+            }
+            _ => unreachable!("{:?} is not a statistical aggregate", self.aggregator),
         }
     }
 }
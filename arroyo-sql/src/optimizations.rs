@@ -15,7 +15,7 @@ use tracing::debug;
 
 use crate::code_gen::ValueBinMergingContext;
 use crate::operators::{AggregateProjection, Projection, TwoPhaseAggregateProjection};
-use crate::pipeline::RecordTransform;
+use crate::pipeline::{RecordTransform, WindowFunction};
 use crate::plan_graph::{
     FusedRecordTransform, PlanEdge, PlanNode, PlanOperator, PlanType, WindowFunctionOperator,
 };
@@ -96,15 +96,13 @@ fn replace_run(
         graph.add_edge(last_node_index, new_node_index, edge);
         last_node_index = new_node_index;
     }
-    let downstream_edge = graph
+    let downstream_edges: Vec<_> = graph
         .edges_directed(*run.last().unwrap(), Outgoing)
-        .last()
-        .unwrap();
-    graph.add_edge(
-        last_node_index,
-        downstream_edge.target(),
-        downstream_edge.weight().clone(),
-    );
+        .map(|edge| (edge.target(), edge.weight().clone()))
+        .collect();
+    for (target, edge) in downstream_edges {
+        graph.add_edge(last_node_index, target, edge);
+    }
 
     let mut nodes_to_remove = vec![];
     for idx in run {
@@ -156,6 +154,10 @@ impl Optimizer for ExpressionFusionOptimizer {
                 &node.operator,
                 PlanOperator::RecordTransform(RecordTransform::AsyncUdfProjection(_))
             )
+            && !matches!(
+                &node.operator,
+                PlanOperator::RecordTransform(RecordTransform::TableFunctionProjection(_))
+            )
         {
             if matches!(
                 &node.operator,
@@ -170,6 +172,10 @@ impl Optimizer for ExpressionFusionOptimizer {
             }
             self.builder.fuse_node(&node);
             self.run.push(_node_index);
+            // the nodes of the run are replaced, so one that feeds several operators must end it
+            if graph.edges_directed(_node_index, Outgoing).count() > 1 {
+                return self.try_finish_optimization(graph);
+            }
             false
         } else if !self.run.is_empty() {
             self.try_finish_optimization(graph)
@@ -227,6 +233,9 @@ impl FusedExpressionOperatorBuilder {
                     RecordTransform::AsyncUdfProjection(_) => {
                         return false;
                     }
+                    RecordTransform::TableFunctionProjection(_) => {
+                        return false;
+                    }
                 }
                 true
             }
@@ -255,6 +264,24 @@ impl FusedExpressionOperatorBuilder {
 
 struct TwoPhaseOptimization {}
 
+impl TwoPhaseOptimization {
+    /// Returns true if the input of this node also feeds a late record filter
+    fn feeds_late_record_filter(
+        node_index: NodeIndex,
+        graph: &DiGraph<PlanNode, PlanEdge>,
+    ) -> bool {
+        graph
+            .neighbors_directed(node_index, Incoming)
+            .flat_map(|input| graph.neighbors_directed(input, Outgoing))
+            .any(|sibling| {
+                matches!(
+                    graph.node_weight(sibling).unwrap().operator,
+                    PlanOperator::LateRecordFilter { .. }
+                )
+            })
+    }
+}
+
 impl Optimizer for TwoPhaseOptimization {
     fn add_node(
         &mut self,
@@ -262,9 +289,18 @@ impl Optimizer for TwoPhaseOptimization {
         node: PlanNode,
         graph: &mut DiGraph<PlanNode, PlanEdge>,
     ) -> bool {
-        let PlanOperator::WindowAggregate { window, projection } = node.operator else {
+        let PlanOperator::WindowAggregate {
+            window,
+            projection,
+            allowed_lateness,
+        } = node.operator
+        else {
             return false;
         };
+        // late records update windows, which requires keeping their raw values
+        if !allowed_lateness.is_zero() {
+            return false;
+        }
         let (width, slide) = match window {
             WindowType::Tumbling { width } => (width, width),
             WindowType::Sliding { width, slide } => (width, slide),
@@ -274,6 +310,11 @@ impl Optimizer for TwoPhaseOptimization {
         if !slide.is_zero() && width.as_micros() % slide.as_micros() != 0 {
             return false;
         }
+        // the two-phase sliding aggregator drops records once their slide has passed, but the
+        // late record filter only emits them once every window they fall into has closed
+        if width != slide && Self::feeds_late_record_filter(node_index, graph) {
+            return false;
+        }
         let Ok(projection) = projection.try_into() else {
             return false;
         };
@@ -338,13 +379,26 @@ impl Optimizer for WindowTopNOptimization {
                 if let PlanOperator::RecordTransform(RecordTransform::KeyProjection(projection)) =
                     node.operator
                 {
+                    // the key projection is replaced, so it can't also feed other operators
+                    if graph.edges_directed(node_index, Outgoing).count() > 1 {
+                        return false;
+                    }
                     self.aggregate_key = Some(projection);
                     self.nodes.push(node_index);
                     self.search_target = SearchTarget::WindowAggregate;
                 }
             }
             SearchTarget::WindowAggregate => {
-                if let PlanOperator::WindowAggregate { window, projection } = node.operator {
+                if let PlanOperator::WindowAggregate {
+                    window,
+                    projection,
+                    allowed_lateness,
+                } = node.operator
+                {
+                    if !allowed_lateness.is_zero() {
+                        self.clear();
+                        return false;
+                    }
                     self.window_aggregate = Some((window, projection));
                     self.nodes.push(node_index);
                     self.search_target = SearchTarget::PartitionProjection;
@@ -376,7 +430,14 @@ impl Optimizer for WindowTopNOptimization {
                 }
             }
             SearchTarget::WindowFunctionOperator => {
-                if let PlanOperator::WindowFunction(window_function_operator) = node.operator {
+                // only row_number() can be turned into a top-n
+                if let PlanOperator::WindowFunction(
+                    window_function_operator @ WindowFunctionOperator {
+                        window_function: WindowFunction::RowNumber,
+                        ..
+                    },
+                ) = node.operator
+                {
                     let _field_name = window_function_operator.field_name.clone();
                     self.window_function_operator = Some(window_function_operator);
                     self.nodes.push(node_index);
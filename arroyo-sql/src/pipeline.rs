@@ -8,28 +8,39 @@ use std::unreachable;
 use anyhow::{anyhow, bail};
 use anyhow::{Ok, Result};
 use arrow_schema::DataType;
-use arroyo_datastream::{Operator, WindowType};
-use datafusion_common::{DFField, ScalarValue};
-use datafusion_expr::expr::ScalarUDF;
+use arroyo_datastream::{ConnectorOp, LookupJoin, Operator, WindowType};
+use arroyo_rpc::api_types::connections::ConnectionType;
+use datafusion::optimizer::utils::{conjunction, split_conjunction};
+use datafusion_common::{DFField, DFSchema, ScalarValue};
+use datafusion_expr::expr::{Between, ScalarUDF};
 use datafusion_expr::{
-    BinaryExpr, BuiltInWindowFunction, Expr, JoinConstraint, LogicalPlan, Window, WriteOp,
+    BinaryExpr, BuiltInWindowFunction, Expr, JoinConstraint, LogicalPlan, Window, WindowFrameUnits,
+    WriteOp,
 };
 
-use quote::{quote, ToTokens};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse_quote;
 
-use crate::code_gen::{CodeGenerator, ValuePointerContext, VecAggregationContext};
+use crate::code_gen::{
+    CodeGenerator, JoinPairContext, ValuePointerContext, VecAggregationContext,
+    VecOfPointersContext,
+};
 use crate::expressions::{
-    AggregateComputation, AggregateResultExtraction, ExpressionContext, RustUdfExpression,
+    AggregateComputation, AggregateResultExtraction, AggregationExpression, CastExpression,
+    ExpressionContext, RustUdfExpression,
 };
 use crate::external::{ProcessingMode, SqlSink, SqlSource};
-use crate::operators::{AsyncUdfProjection, UnnestFieldType, UnnestProjection};
+use crate::operators::{
+    AsyncUdfProjection, TableFunctionProjection, UnnestFieldType, UnnestProjection,
+};
 use crate::schemas::window_type_def;
-use crate::tables::{Insert, Table};
+use crate::tables::{ConnectorTable, Insert, LookupOptions, Table, WindowOptions};
 use crate::{
     expressions::{Column, ColumnExpression, Expression, SortExpression},
     operators::{AggregateProjection, Projection},
     types::{interval_month_day_nanos_to_duration, StructDef, StructField, TypeDef},
-    ArroyoSchemaProvider,
+    ArroyoSchemaProvider, ProcessFunctionDef,
 };
 
 #[derive(Debug, Clone)]
@@ -37,6 +48,15 @@ pub enum SqlOperator {
     Source(SourceOperator),
     Aggregator(Box<SqlOperator>, AggregateOperator),
     JoinOperator(Box<SqlOperator>, Box<SqlOperator>, JoinOperator),
+    LookupJoin(Box<SqlOperator>, LookupJoinOperator),
+    ProcessFunction(Box<SqlOperator>, ProcessFunctionOperator),
+    TemporalJoin(Box<SqlOperator>, Box<SqlOperator>, JoinOperator),
+    IntervalJoin(
+        Box<SqlOperator>,
+        Box<SqlOperator>,
+        JoinOperator,
+        IntervalBounds,
+    ),
     Window(Box<SqlOperator>, SqlWindowOperator),
     RecordTransform(Box<SqlOperator>, RecordTransform),
     Union(Vec<SqlOperator>),
@@ -52,6 +72,7 @@ pub enum RecordTransform {
     TimestampAssignment(Expression),
     Filter(Expression),
     AsyncUdfProjection(AsyncUdfProjection),
+    TableFunctionProjection(TableFunctionProjection),
 }
 
 #[derive(Debug, Clone)]
@@ -103,6 +124,15 @@ impl RecordTransform {
                     .chain(projection.async_udf.args.iter_mut().map(|(_, expr)| expr)),
             )
                 as Box<dyn Iterator<Item = &mut Expression>>,
+            RecordTransform::TableFunctionProjection(projection) => Box::new(
+                projection
+                    .fields
+                    .iter_mut()
+                    .map(|(_, expression, _)| expression)
+                    .chain(projection.function.args.iter_mut().map(|(_, expr)| expr))
+                    .chain(projection.row_filters.iter_mut()),
+            )
+                as Box<dyn Iterator<Item = &mut Expression>>,
         }
     }
 
@@ -119,6 +149,7 @@ impl RecordTransform {
             RecordTransform::AsyncUdfProjection(projection) => {
                 projection.expression_type(&ValuePointerContext::new())
             }
+            RecordTransform::TableFunctionProjection(projection) => projection.output_struct(),
         }
     }
 
@@ -193,6 +224,25 @@ impl RecordTransform {
                     a.async_udf.has_context,
                 )
             }
+            RecordTransform::TableFunctionProjection(p) => {
+                if is_updating {
+                    unreachable!("table functions are not supported for updating data");
+                }
+
+                if p.is_async() {
+                    let function_def = p.generate_async(&ValuePointerContext::new());
+                    MethodCompiler::async_map_operator(
+                        "async_table_function",
+                        p.function.opts.async_results_ordered,
+                        function_def.to_token_stream().to_string(),
+                        p.function.opts.async_max_concurrency,
+                        p.function.has_context,
+                    )
+                } else {
+                    let record_expression = ValuePointerContext::new().compile_flatmap_expr(p);
+                    MethodCompiler::flatmap_operator("table_function", record_expression)
+                }
+            }
         }
     }
 
@@ -204,6 +254,7 @@ impl RecordTransform {
             RecordTransform::TimestampAssignment(_) => "timestamp".into(),
             RecordTransform::UnnestProjection(_) => "unnest_project".into(),
             RecordTransform::AsyncUdfProjection(_) => "async_udf".into(),
+            RecordTransform::TableFunctionProjection(_) => "table_function".into(),
         }
     }
 }
@@ -213,6 +264,9 @@ pub struct AggregateOperator {
     pub key: Projection,
     pub window: WindowType,
     pub aggregating: AggregateProjection,
+    pub allowed_lateness: Duration,
+    // the named table that records which are too late for the window are planned as
+    pub late_records: Option<String>,
 }
 
 impl AggregateOperator {
@@ -222,9 +276,296 @@ impl AggregateOperator {
     }
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd)]
 pub enum WindowFunction {
     RowNumber,
+    Rank,
+    DenseRank,
+    PercentRank,
+    Lag {
+        expression: Box<Expression>,
+        offset: usize,
+        default: Option<Box<Expression>>,
+    },
+    Lead {
+        expression: Box<Expression>,
+        offset: usize,
+        default: Option<Box<Expression>>,
+    },
+    FirstValue(Box<Expression>, WindowFrame),
+    LastValue(Box<Expression>, WindowFrame),
+    Aggregate(AggregationExpression, WindowFrame),
+    // a Rust UDF called with the values of its arguments for all of the rows of the partition
+    Udf {
+        name: String,
+        args: Vec<(TypeDef, Expression)>,
+        ret_type: TypeDef,
+    },
+}
+
+impl WindowFunction {
+    fn try_from_window_expr(
+        ctx: &ExpressionContext,
+        window_expr: &datafusion_expr::expr::WindowFunction,
+    ) -> Result<Self> {
+        let args = window_expr.args.as_slice();
+        let mut window_function = match &window_expr.fun {
+            datafusion_expr::WindowFunction::AggregateFunction(fun) => WindowFunction::Aggregate(
+                AggregationExpression::try_from_window_aggregate(ctx, fun, args)?,
+                (&window_expr.window_frame).try_into()?,
+            ),
+            datafusion_expr::WindowFunction::BuiltInWindowFunction(fun) => match (fun, args) {
+                (BuiltInWindowFunction::RowNumber, []) => WindowFunction::RowNumber,
+                (BuiltInWindowFunction::Rank, []) => WindowFunction::Rank,
+                (BuiltInWindowFunction::DenseRank, []) => WindowFunction::DenseRank,
+                (BuiltInWindowFunction::PercentRank, []) => WindowFunction::PercentRank,
+                (
+                    BuiltInWindowFunction::Lag | BuiltInWindowFunction::Lead,
+                    [expression, rest @ ..],
+                ) => {
+                    let expression = Box::new(ctx.compile_expr(expression)?);
+                    let offset = match rest.first() {
+                        None => 1,
+                        Some(Expr::Literal(ScalarValue::Int64(Some(offset)))) if *offset >= 0 => {
+                            *offset as usize
+                        }
+                        Some(offset) => {
+                            bail!(
+                                "{} requires a non-negative literal offset, not {}",
+                                fun,
+                                offset
+                            )
+                        }
+                    };
+                    let default = rest
+                        .get(1)
+                        .map(|default| {
+                            let default = ctx.compile_expr(default)?;
+                            // the default has to have the same type as the expression
+                            let context = ValuePointerContext::new();
+                            match expression.expression_type(&context).as_datatype() {
+                                Some(data_type)
+                                    if default.expression_type(&context).as_datatype()
+                                        != Some(data_type) =>
+                                {
+                                    CastExpression::new(
+                                        Box::new(default),
+                                        data_type,
+                                        &context,
+                                        false,
+                                    )
+                                }
+                                _ => Ok(default),
+                            }
+                        })
+                        .transpose()?
+                        .map(Box::new);
+                    if *fun == BuiltInWindowFunction::Lag {
+                        WindowFunction::Lag {
+                            expression,
+                            offset,
+                            default,
+                        }
+                    } else {
+                        WindowFunction::Lead {
+                            expression,
+                            offset,
+                            default,
+                        }
+                    }
+                }
+                (BuiltInWindowFunction::FirstValue, [expression]) => WindowFunction::FirstValue(
+                    Box::new(ctx.compile_expr(expression)?),
+                    (&window_expr.window_frame).try_into()?,
+                ),
+                (BuiltInWindowFunction::LastValue, [expression]) => WindowFunction::LastValue(
+                    Box::new(ctx.compile_expr(expression)?),
+                    (&window_expr.window_frame).try_into()?,
+                ),
+                (fun, _) => bail!("Window function {} not yet supported", fun),
+            },
+            datafusion_expr::WindowFunction::AggregateUDF(_) => {
+                bail!("Window UDAFs not yet supported");
+            }
+            datafusion_expr::WindowFunction::WindowUDF(udf) => {
+                let def = ctx
+                    .schema_provider
+                    .udf_defs
+                    .get(&udf.name)
+                    .ok_or_else(|| anyhow!("no window UDF with name '{}'", udf.name))?;
+                if args.len() != def.args.len() {
+                    bail!(
+                        "wrong number of arguments for window UDF {} (found {}, expected {})",
+                        udf.name,
+                        args.len(),
+                        def.args.len()
+                    );
+                }
+                let args = def
+                    .args
+                    .iter()
+                    .zip(args)
+                    .map(|(arg_type, arg)| {
+                        let expression = ctx.compile_expr(arg)?;
+                        // the function gets a value for every row, so nulls can't be skipped
+                        if !arg_type.is_optional()
+                            && expression
+                                .expression_type(&ValuePointerContext::new())
+                                .is_optional()
+                        {
+                            bail!(
+                                "window UDF {} requires non-null arguments, but {} is nullable",
+                                udf.name,
+                                arg
+                            );
+                        }
+                        Ok((arg_type.clone(), expression))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                WindowFunction::Udf {
+                    name: udf.name.clone(),
+                    args,
+                    ret_type: def.ret.clone(),
+                }
+            }
+        };
+        for expression in window_function.expressions() {
+            SqlPipelineBuilder::assert_no_unnest_or_async_udf("window", expression)?;
+        }
+        Ok(window_function)
+    }
+
+    pub fn return_type(&self) -> TypeDef {
+        let context = ValuePointerContext::new();
+        match self {
+            WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => {
+                TypeDef::DataType(DataType::UInt64, false)
+            }
+            WindowFunction::PercentRank => TypeDef::DataType(DataType::Float64, false),
+            // rows without a row at the offset get the default, or null if there isn't one
+            WindowFunction::Lag {
+                expression,
+                default,
+                ..
+            }
+            | WindowFunction::Lead {
+                expression,
+                default,
+                ..
+            } => {
+                let expression_type = expression.expression_type(&context);
+                let default_nullable = default.as_ref().map_or(true, |default| {
+                    default.expression_type(&context).is_optional()
+                });
+                expression_type.with_nullity(expression_type.is_optional() || default_nullable)
+            }
+            // frames always contain the current row, so they are never empty
+            WindowFunction::FirstValue(expression, _)
+            | WindowFunction::LastValue(expression, _) => expression.expression_type(&context),
+            WindowFunction::Aggregate(aggregation, _) => {
+                aggregation.expression_type(&VecOfPointersContext)
+            }
+            WindowFunction::Udf { ret_type, .. } => ret_type.clone(),
+        }
+    }
+
+    pub fn expressions(&mut self) -> Box<dyn Iterator<Item = &mut Expression> + '_> {
+        match self {
+            WindowFunction::RowNumber
+            | WindowFunction::Rank
+            | WindowFunction::DenseRank
+            | WindowFunction::PercentRank => Box::new(std::iter::empty()),
+            WindowFunction::Lag {
+                expression,
+                default,
+                ..
+            }
+            | WindowFunction::Lead {
+                expression,
+                default,
+                ..
+            } => Box::new(
+                once(expression.as_mut()).chain(default.iter_mut().map(|default| default.as_mut())),
+            ),
+            WindowFunction::FirstValue(expression, _)
+            | WindowFunction::LastValue(expression, _) => Box::new(once(expression.as_mut())),
+            WindowFunction::Aggregate(aggregation, _) => Box::new(aggregation.expressions()),
+            WindowFunction::Udf { args, .. } => Box::new(args.iter_mut().map(|(_, arg)| arg)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd)]
+pub enum WindowFrameBound {
+    UnboundedPreceding,
+    Preceding(usize),
+    CurrentRow,
+    Following(usize),
+    UnboundedFollowing,
+}
+
+impl WindowFrameBound {
+    fn try_from_datafusion(bound: &datafusion_expr::WindowFrameBound, range: bool) -> Result<Self> {
+        match bound {
+            datafusion_expr::WindowFrameBound::CurrentRow => Ok(WindowFrameBound::CurrentRow),
+            datafusion_expr::WindowFrameBound::Preceding(value) if value.is_null() => {
+                Ok(WindowFrameBound::UnboundedPreceding)
+            }
+            datafusion_expr::WindowFrameBound::Following(value) if value.is_null() => {
+                Ok(WindowFrameBound::UnboundedFollowing)
+            }
+            _ if range => {
+                bail!("RANGE window frames only support UNBOUNDED and CURRENT ROW bounds")
+            }
+            datafusion_expr::WindowFrameBound::Preceding(value) => {
+                Ok(WindowFrameBound::Preceding(Self::offset(value)?))
+            }
+            datafusion_expr::WindowFrameBound::Following(value) => {
+                Ok(WindowFrameBound::Following(Self::offset(value)?))
+            }
+        }
+    }
+
+    fn offset(value: &ScalarValue) -> Result<usize> {
+        match value {
+            ScalarValue::UInt64(Some(offset)) => Ok(*offset as usize),
+            ScalarValue::Int64(Some(offset)) if *offset >= 0 => Ok(*offset as usize),
+            _ => bail!("unsupported window frame offset {}", value),
+        }
+    }
+}
+
+/// The rows of a partition that a row's value is computed over. In RANGE frames the current row
+/// extends to all of its peers, the rows with the same ORDER BY values.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd)]
+pub struct WindowFrame {
+    pub range: bool,
+    pub start: WindowFrameBound,
+    pub end: WindowFrameBound,
+}
+
+impl TryFrom<&datafusion_expr::WindowFrame> for WindowFrame {
+    type Error = anyhow::Error;
+
+    fn try_from(window_frame: &datafusion_expr::WindowFrame) -> Result<Self> {
+        let range = match window_frame.units {
+            WindowFrameUnits::Rows => false,
+            WindowFrameUnits::Range => true,
+            WindowFrameUnits::Groups => bail!("GROUPS window frames are not supported"),
+        };
+        let start = WindowFrameBound::try_from_datafusion(&window_frame.start_bound, range)?;
+        let end = WindowFrameBound::try_from_datafusion(&window_frame.end_bound, range)?;
+        if matches!(
+            start,
+            WindowFrameBound::Following(_) | WindowFrameBound::UnboundedFollowing
+        ) || matches!(
+            end,
+            WindowFrameBound::Preceding(_) | WindowFrameBound::UnboundedPreceding
+        ) {
+            bail!("window frames must contain the current row");
+        }
+        Ok(WindowFrame { range, start, end })
+    }
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -249,6 +590,306 @@ pub struct JoinOperator {
     pub join_type: JoinType,
 }
 
+/// Bounds on how far the event time of the right side of an interval join may be from that of
+/// the left side, in microseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntervalBounds {
+    pub lower_micros: i64,
+    pub upper_micros: i64,
+}
+
+impl IntervalBounds {
+    /// Finds the bounds on `right.ts - left.ts` in a join filter like
+    /// `b.ts BETWEEN a.ts - INTERVAL '5' MINUTE AND a.ts + INTERVAL '10' MINUTE`, returning None
+    /// if the filter doesn't bound it on both sides
+    fn from_filter(filter: &Expr, left: &DFSchema, right: &DFSchema) -> Result<Option<Self>> {
+        // splits `column [+/- interval]` into the column and its offset
+        fn time_offset(expr: &Expr) -> Option<(&datafusion_common::Column, i64)> {
+            match expr {
+                Expr::Column(column) => Some((column, 0)),
+                Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                    let Expr::Column(column) = left.as_ref() else {
+                        return None;
+                    };
+                    let offset = SqlPipelineBuilder::get_duration(right).ok()?.as_micros() as i64;
+                    match op {
+                        datafusion_expr::Operator::Plus => Some((column, offset)),
+                        datafusion_expr::Operator::Minus => Some((column, -offset)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+
+        // returns whether `a op b` is a lower bound on `right.ts - left.ts`, along with the bound
+        let bound = |a: &Expr, op: datafusion_expr::Operator, b: &Expr| -> Option<(bool, i64)> {
+            let (a_column, a_offset) = time_offset(a)?;
+            let (b_column, b_offset) = time_offset(b)?;
+            let is_lower = match op {
+                datafusion_expr::Operator::Gt | datafusion_expr::Operator::GtEq => true,
+                datafusion_expr::Operator::Lt | datafusion_expr::Operator::LtEq => false,
+                _ => return None,
+            };
+            if right.has_column(a_column) && left.has_column(b_column) {
+                Some((is_lower, b_offset - a_offset))
+            } else if left.has_column(a_column) && right.has_column(b_column) {
+                Some((!is_lower, a_offset - b_offset))
+            } else {
+                None
+            }
+        };
+
+        let mut lower: Option<i64> = None;
+        let mut upper: Option<i64> = None;
+        for expr in split_conjunction(filter) {
+            let bounds = match expr {
+                Expr::Between(Between {
+                    expr,
+                    negated: false,
+                    low,
+                    high,
+                }) => vec![
+                    bound(expr, datafusion_expr::Operator::GtEq, low),
+                    bound(expr, datafusion_expr::Operator::LtEq, high),
+                ],
+                Expr::BinaryExpr(BinaryExpr { left, op, right }) => vec![bound(left, *op, right)],
+                _ => vec![],
+            };
+
+            for (is_lower, value) in bounds.into_iter().flatten() {
+                if is_lower {
+                    lower = Some(lower.map_or(value, |lower| lower.max(value)));
+                } else {
+                    upper = Some(upper.map_or(value, |upper| upper.min(value)));
+                }
+            }
+        }
+
+        let (Some(lower_micros), Some(upper_micros)) = (lower, upper) else {
+            return Ok(None);
+        };
+
+        if lower_micros > upper_micros {
+            bail!(
+                "the time bounds of interval join {} can never be satisfied",
+                filter
+            );
+        }
+
+        Ok(Some(Self {
+            lower_micros,
+            upper_micros,
+        }))
+    }
+}
+
+/// Joins a stream against a lookup table, by fetching the row for each record's key from the
+/// external system rather than keeping the table in state
+#[derive(Debug, Clone)]
+pub struct LookupJoinOperator {
+    pub table_name: String,
+    pub id: Option<i64>,
+    pub connector: ConnectorOp,
+    pub input_struct: StructDef,
+    // the type of the rows read from the lookup table
+    pub lookup_struct: StructDef,
+    // maps rows of the lookup table onto the columns that are joined
+    pub projection: Projection,
+    // computed on the input to find the key to look up
+    pub key: Expression,
+    pub join_type: JoinType,
+    pub options: LookupOptions,
+}
+
+impl LookupJoinOperator {
+    pub fn output_struct(&self) -> StructDef {
+        self.join_type
+            .output_struct(&self.input_struct, &self.projection.output_struct())
+    }
+
+    pub fn to_operator(&self) -> Operator {
+        let key = self.key.generate(&ValuePointerContext::new());
+        let key_expression: syn::Expr = if self
+            .key
+            .expression_type(&ValuePointerContext::new())
+            .is_optional()
+        {
+            parse_quote!((#key).map(|key| key.to_string()))
+        } else {
+            parse_quote!(Some((#key).to_string()))
+        };
+
+        let row_context = ValuePointerContext::with_arg("row");
+        let projection = self.projection.generate(&row_context);
+        let merge = self.join_type.generate(&JoinPairContext::new(
+            self.input_struct.clone(),
+            self.projection.expression_type(&row_context),
+        ));
+        let merge_expression: syn::Expr = match self.join_type {
+            JoinType::Left => parse_quote!({
+                let right = lookup.map(|row| #projection);
+                #merge
+            }),
+            _ => parse_quote!({
+                let row = lookup.expect("inner lookup joins only merge matching rows");
+                let right = #projection;
+                #merge
+            }),
+        };
+
+        Operator::LookupJoin(LookupJoin {
+            connector: self.connector.clone(),
+            lookup_type: self.lookup_struct.struct_name(),
+            key_expression: quote!(#key_expression).to_string(),
+            merge_expression: quote!(#merge_expression).to_string(),
+            join_type: self.join_type.clone().into(),
+            cache_max_rows: self.options.cache_max_rows,
+            cache_ttl: self.options.cache_ttl,
+            batch_size: self.options.batch_size,
+            max_concurrency: self.options.max_concurrency,
+        })
+    }
+}
+
+/// Runs a process function over its input partitioned by the function's first argument, which
+/// keeps the function's state for each key and calls it back for the timers it schedules
+#[derive(Debug, Clone)]
+pub struct ProcessFunctionOperator {
+    // computes the first argument of the function, which its input is partitioned by
+    pub key: Projection,
+    pub function: RustUdfExpression,
+    pub process_function: ProcessFunctionDef,
+    pub row_struct: StructDef,
+    // filters that only reference the rows, which are applied before they are output
+    pub row_filters: Vec<Expression>,
+    // maps the rows onto the output columns
+    pub projection: Projection,
+}
+
+impl ProcessFunctionOperator {
+    pub fn output_struct(&self) -> StructDef {
+        self.projection.output_struct()
+    }
+
+    fn udf_arg(id: &syn::Ident, def: &TypeDef, value: TokenStream, optional: bool) -> TokenStream {
+        match (def.is_optional(), optional) {
+            (true, true) | (false, false) => quote!(let #id = #value),
+            (true, false) => quote!(let #id = Some(#value)),
+            (false, true) => quote!(let #id = (#value)?),
+        }
+    }
+
+    /// Wraps the invocation of the UDF, which is given the arguments in `defs` and `args`, with
+    /// the conversions between the generated state and rows and the structs of the UDF
+    fn invocation(
+        &self,
+        function: &syn::Ident,
+        defs: Vec<TokenStream>,
+        args: Vec<TokenStream>,
+    ) -> syn::Expr {
+        let state = &self.process_function.state;
+        let state_type = state.get_type();
+        let udf_state = format_ident!("{}", self.process_function.state_struct);
+        let (to_udf, from_udf): (Vec<_>, Vec<_>) = state
+            .fields
+            .iter()
+            .map(|field| {
+                let field_ident = field.field_ident();
+                let name = format_ident!("{}", field.name);
+                (
+                    quote!(#name: state.#field_ident),
+                    quote!(#field_ident: state.#name),
+                )
+            })
+            .unzip();
+
+        let row_type = self.row_struct.get_type();
+        let row_assignments: Vec<_> = self
+            .row_struct
+            .fields
+            .iter()
+            .map(|field| {
+                let field_ident = field.field_ident();
+                let name = format_ident!("{}", field.name);
+                quote!(#field_ident: ___row.#name)
+            })
+            .collect();
+
+        let row_context = ValuePointerContext::with_arg("___row");
+        let filters: Vec<_> = self
+            .row_filters
+            .iter()
+            .map(|filter| {
+                let unwrap = if filter.expression_type(&row_context).is_optional() {
+                    Some(quote!(.unwrap_or(false)))
+                } else {
+                    None
+                };
+                let filter = filter.generate(&row_context);
+                quote!(.filter(|___row| #filter #unwrap))
+            })
+            .collect();
+        let projection = self.projection.generate(&row_context);
+
+        parse_quote!({
+            let mut context = context.map_state(|state| udfs::#udf_state { #(#to_udf),* });
+            // the function isn't invoked if any of its required arguments are null
+            let rows = (|| {
+                #(#defs; )*
+                Some(udfs::#function(&mut context, #(#args),*))
+            })();
+            let rows = rows.into_iter()
+                .flatten()
+                .map(|___row| #row_type { #(#row_assignments),* })
+                #(#filters)*
+                .map(|___row| #projection)
+                .collect();
+            (context.map_state(|state| #state_type { #(#from_udf),* }), rows)
+        })
+    }
+
+    pub fn to_operator(&self) -> Operator {
+        let name = format_ident!("{}", self.function.name);
+        let input_context = ValuePointerContext::new();
+        let (defs, args): (Vec<_>, Vec<_>) = self
+            .function
+            .args
+            .iter()
+            .enumerate()
+            .map(|(i, (def, expr))| {
+                let id = format_ident!("__{}", i);
+                let value = expr.generate(&input_context).to_token_stream();
+                let optional = expr.expression_type(&input_context).is_optional();
+                (Self::udf_arg(&id, def, value, optional), quote!(#id))
+            })
+            .unzip();
+        let process_expression = self.invocation(&name, defs, args);
+
+        let timer_expression = self.process_function.handles_timers.then(|| {
+            let timer_name = format_ident!("{}_on_timer", self.function.name);
+            let key_field = self.key.output_struct().fields[0].clone();
+            let field_ident = key_field.field_ident();
+            let id = format_ident!("__0");
+            let def = Self::udf_arg(
+                &id,
+                &self.function.args[0].0,
+                quote!(key.#field_ident.clone()),
+                key_field.data_type.is_optional(),
+            );
+            let expression = self.invocation(&timer_name, vec![def], vec![quote!(#id)]);
+            quote!(#expression).to_string()
+        });
+
+        Operator::ProcessFunction {
+            name: self.function.name.clone(),
+            state_type: self.process_function.state.struct_name(),
+            process_expression: quote!(#process_expression).to_string(),
+            timer_expression,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InputsUpdating {
     pub left: bool,
@@ -265,6 +906,14 @@ pub enum JoinType {
     Right,
     /// Full Join
     Full,
+    /// Left Semi Join, only emits left rows that have a match on the right
+    LeftSemi,
+    /// Right Semi Join, only emits right rows that have a match on the left
+    RightSemi,
+    /// Left Anti Join, only emits left rows that have no match on the right
+    LeftAnti,
+    /// Right Anti Join, only emits right rows that have no match on the left
+    RightAnti,
 }
 
 impl From<JoinType> for arroyo_types::JoinType {
@@ -274,6 +923,10 @@ impl From<JoinType> for arroyo_types::JoinType {
             JoinType::Left => arroyo_types::JoinType::Left,
             JoinType::Right => arroyo_types::JoinType::Right,
             JoinType::Full => arroyo_types::JoinType::Full,
+            JoinType::LeftSemi => arroyo_types::JoinType::LeftSemi,
+            JoinType::RightSemi => arroyo_types::JoinType::RightSemi,
+            JoinType::LeftAnti => arroyo_types::JoinType::LeftAnti,
+            JoinType::RightAnti => arroyo_types::JoinType::RightAnti,
         }
     }
 }
@@ -287,16 +940,22 @@ impl TryFrom<datafusion_expr::JoinType> for JoinType {
             datafusion_expr::JoinType::Left => Ok(JoinType::Left),
             datafusion_expr::JoinType::Right => Ok(JoinType::Right),
             datafusion_expr::JoinType::Full => Ok(JoinType::Full),
-            datafusion_expr::JoinType::LeftSemi
-            | datafusion_expr::JoinType::RightSemi
-            | datafusion_expr::JoinType::LeftAnti
-            | datafusion_expr::JoinType::RightAnti => bail!("{:?} not yet supported", join_type),
+            datafusion_expr::JoinType::LeftSemi => Ok(JoinType::LeftSemi),
+            datafusion_expr::JoinType::RightSemi => Ok(JoinType::RightSemi),
+            datafusion_expr::JoinType::LeftAnti => Ok(JoinType::LeftAnti),
+            datafusion_expr::JoinType::RightAnti => Ok(JoinType::RightAnti),
         }
     }
 }
 
 impl JoinType {
     pub fn output_struct(&self, left_struct: &StructDef, right_struct: &StructDef) -> StructDef {
+        // semi and anti joins only return the rows of one side.
+        match self {
+            JoinType::LeftSemi | JoinType::LeftAnti => return left_struct.clone(),
+            JoinType::RightSemi | JoinType::RightAnti => return right_struct.clone(),
+            _ => {}
+        }
         // input to join should always be two structs. Nullability determined by join type.
         let mut fields = if self.left_nullable() {
             left_struct
@@ -336,16 +995,37 @@ impl JoinType {
 
     pub fn left_nullable(&self) -> bool {
         match self {
-            JoinType::Inner | JoinType::Left => false,
+            JoinType::Inner
+            | JoinType::Left
+            | JoinType::LeftSemi
+            | JoinType::RightSemi
+            | JoinType::LeftAnti
+            | JoinType::RightAnti => false,
             JoinType::Right | JoinType::Full => true,
         }
     }
     pub fn right_nullable(&self) -> bool {
         match self {
-            JoinType::Inner | JoinType::Right => false,
+            JoinType::Inner
+            | JoinType::Right
+            | JoinType::LeftSemi
+            | JoinType::RightSemi
+            | JoinType::LeftAnti
+            | JoinType::RightAnti => false,
             JoinType::Left | JoinType::Full => true,
         }
     }
+
+    pub fn is_semi_or_anti(&self) -> bool {
+        matches!(
+            self,
+            JoinType::LeftSemi | JoinType::RightSemi | JoinType::LeftAnti | JoinType::RightAnti
+        )
+    }
+
+    pub fn is_anti(&self) -> bool {
+        matches!(self, JoinType::LeftAnti | JoinType::RightAnti)
+    }
 }
 
 impl SqlOperator {
@@ -358,12 +1038,18 @@ impl SqlOperator {
             SqlOperator::JoinOperator(left, right, operator) => operator
                 .join_type
                 .output_struct(&left.return_type(), &right.return_type()),
+            SqlOperator::LookupJoin(_, lookup) => lookup.output_struct(),
+            SqlOperator::ProcessFunction(_, process) => process.output_struct(),
+            SqlOperator::TemporalJoin(left, right, operator)
+            | SqlOperator::IntervalJoin(left, right, operator, _) => operator
+                .join_type
+                .output_struct(&left.return_type(), &right.return_type()),
             SqlOperator::Window(input, window) => {
                 let mut input_struct = input.return_type();
                 input_struct.fields.push(StructField::new(
                     window.field_name.clone(),
                     None,
-                    TypeDef::DataType(DataType::UInt64, false),
+                    window.window_fn.return_type(),
                 ));
                 input_struct
             }
@@ -376,6 +1062,26 @@ impl SqlOperator {
         }
     }
 
+    /// Returns the name of the column that the event times of records are taken from, if they
+    /// are assigned from a column rather than by the source connector
+    pub fn event_time_field(&self) -> Option<String> {
+        match self {
+            SqlOperator::Source(source) => match &source.timestamp_override {
+                Some(Expression::Column(column)) => Some(column.name().to_string()),
+                _ => None,
+            },
+            SqlOperator::RecordTransform(_, RecordTransform::TimestampAssignment(expression)) => {
+                match expression {
+                    Expression::Column(column) => Some(column.name().to_string()),
+                    _ => None,
+                }
+            }
+            SqlOperator::RecordTransform(input, _) => input.event_time_field(),
+            SqlOperator::NamedTable(_, input) => input.event_time_field(),
+            _ => None,
+        }
+    }
+
     pub fn has_window(&self) -> bool {
         match self {
             SqlOperator::Source(_) => false,
@@ -383,6 +1089,9 @@ impl SqlOperator {
                 !matches!(aggregator.window, WindowType::Instant) || input.has_window()
             }
             SqlOperator::JoinOperator(left, right, _) => left.has_window() || right.has_window(),
+            SqlOperator::LookupJoin(input, _) => input.has_window(),
+            SqlOperator::ProcessFunction(input, _) => input.has_window(),
+            SqlOperator::TemporalJoin(..) | SqlOperator::IntervalJoin(..) => false,
             SqlOperator::Window(_, _) => true,
             SqlOperator::RecordTransform(input, _) => input.has_window(),
             SqlOperator::Sink(_, _, input) => input.has_window(),
@@ -400,14 +1109,22 @@ impl SqlOperator {
                         // non-windowed aggregates without aggregate functions are not updating, as
                         // they cannot have retractions
                         && !aggregate_operator.aggregating.aggregates.is_empty())
+                    // late records update windows that have already been emitted
+                    || !aggregate_operator.allowed_lateness.is_zero()
             }
             SqlOperator::JoinOperator(left, right, join_operator) => {
                 // the join will be updating if one of the sides is updating or if a non-window side is nullable.
+                // non-windowed anti joins retract rows once a match arrives.
                 left.is_updating()
                     || right.is_updating()
                     || (!left.has_window() && join_operator.join_type.left_nullable())
                     || (!right.has_window() && join_operator.join_type.right_nullable())
+                    || (!left.has_window() && join_operator.join_type.is_anti())
             }
+            SqlOperator::LookupJoin(input, _) => input.is_updating(),
+            SqlOperator::ProcessFunction(_, _) => false,
+            // each left row is joined once with the version that was valid at its event time
+            SqlOperator::TemporalJoin(..) | SqlOperator::IntervalJoin(..) => false,
             SqlOperator::Window(input, sql_window_operator) => {
                 input.is_updating() // TODO: figure out when this second case is supposed to be triggered.
                     || (!input.has_window() && sql_window_operator.window_type == WindowType::Instant)
@@ -429,6 +1146,9 @@ impl SqlOperator {
                 WindowType::Instant => input.get_window(),
             },
             SqlOperator::JoinOperator(left, _, _) => left.get_window(),
+            SqlOperator::LookupJoin(input, _) => input.get_window(),
+            SqlOperator::ProcessFunction(input, _) => input.get_window(),
+            SqlOperator::TemporalJoin(..) | SqlOperator::IntervalJoin(..) => None,
             SqlOperator::Window(_, sql_window_operator) => {
                 Some(sql_window_operator.window_type.clone())
             }
@@ -445,6 +1165,11 @@ pub struct SqlPipelineBuilder<'a> {
     pub schema_provider: &'a ArroyoSchemaProvider,
     pub planned_tables: HashMap<String, SqlOperator>,
     pub insert_nodes: Vec<SqlOperator>,
+    // tables that are joined against with `FOR SYSTEM_TIME AS OF`, along with the column named
+    pub temporal_tables: HashMap<String, String>,
+    pub window_options: WindowOptions,
+    // sinks for the late records of windows, along with the named tables they read from
+    pub late_sinks: Vec<(String, SqlOperator)>,
 }
 
 impl<'a> SqlPipelineBuilder<'a> {
@@ -453,6 +1178,9 @@ impl<'a> SqlPipelineBuilder<'a> {
             schema_provider,
             planned_tables: HashMap::new(),
             insert_nodes: vec![],
+            temporal_tables: HashMap::new(),
+            window_options: WindowOptions::default(),
+            late_sinks: vec![],
         }
     }
 
@@ -785,16 +1513,47 @@ impl<'a> SqlPipelineBuilder<'a> {
             bail!("updating aggregates only support two phase aggregations. Currently count distinct is not supported");
         }
 
+        let (allowed_lateness, late_records) = match &window {
+            WindowType::Instant => (Duration::ZERO, None),
+            _ => (
+                self.window_options.allowed_lateness,
+                self.plan_late_data_sink(&source)?,
+            ),
+        };
+
         Ok(SqlOperator::Aggregator(
             Box::new(source),
             AggregateOperator {
                 key,
                 window,
                 aggregating,
+                allowed_lateness,
+                late_records,
             },
         ))
     }
 
+    /// Plans writing the records that are too late for the windows of an aggregate over `input`
+    /// to the late data sink, if there is one, returning the name of the table they are read from
+    fn plan_late_data_sink(&mut self, input: &SqlOperator) -> Result<Option<String>> {
+        let Some(sink_name) = &self.window_options.late_data_sink else {
+            return Ok(None);
+        };
+
+        let Some(Table::ConnectorTable(sink)) = self.schema_provider.get_table(sink_name) else {
+            bail!("late data sink '{}' must be a connector table", sink_name);
+        };
+
+        let name = format!("__late_records_{}", self.late_sinks.len());
+        let late_records = SqlOperator::NamedTable(name.clone(), Box::new(input.clone()));
+        let late_sink = sink
+            .as_sql_sink(late_records)
+            .map_err(|e| anyhow!("failed to plan late data sink '{}': {}", sink_name, e))?;
+
+        self.late_sinks.push((name.clone(), late_sink));
+        Ok(Some(name))
+    }
+
     fn aggregation_key(
         &mut self,
         group_expressions: &[Expr],
@@ -895,6 +1654,19 @@ impl<'a> SqlPipelineBuilder<'a> {
     }
 
     fn insert_join(&mut self, join: &datafusion_expr::logical_plan::Join) -> Result<SqlOperator> {
+        if let Some(table) = self.lookup_table(&join.left) {
+            bail!(
+                "lookup table '{}' must be on the right side of the join",
+                table.name
+            );
+        }
+        if let Some(table) = self.lookup_table(&join.right) {
+            return self.insert_lookup_join(join, table);
+        }
+        if let Some((placeholder, row_filters)) = self.table_function_call(&join.right) {
+            return self.insert_table_function(join, placeholder, row_filters);
+        }
+
         let left_input = self.insert_sql_plan(&join.left)?;
         let right_input = self.insert_sql_plan(&join.right)?;
         match join.join_constraint {
@@ -902,6 +1674,18 @@ impl<'a> SqlPipelineBuilder<'a> {
             JoinConstraint::Using => bail!("don't support 'using' in joins"),
         };
         let join_type = join.join_type.try_into()?;
+
+        if let Some((table, column)) = self.temporal_table(&join.right) {
+            return self.insert_temporal_join(
+                join,
+                left_input,
+                right_input,
+                join_type,
+                table,
+                column,
+            );
+        }
+
         // check supported join types
         match (left_input.has_window(), right_input.has_window()) {
             (true, false) | (false, true) => {
@@ -910,15 +1694,170 @@ impl<'a> SqlPipelineBuilder<'a> {
             _ => {}
         }
 
+        if let Some(filter) = &join.filter {
+            if let Some(bounds) =
+                IntervalBounds::from_filter(filter, join.left.schema(), join.right.schema())?
+            {
+                return self.insert_interval_join(
+                    join,
+                    left_input,
+                    right_input,
+                    join_type,
+                    bounds,
+                    filter,
+                );
+            }
+        }
+
+        let (left_key, right_key) =
+            self.join_keys(join, join.filter.as_ref(), &left_input, &right_input)?;
+
+        Ok(SqlOperator::JoinOperator(
+            Box::new(left_input),
+            Box::new(right_input),
+            JoinOperator {
+                left_key,
+                right_key,
+                join_type,
+            },
+        ))
+    }
+
+    /// Returns the name of the table read by a join input if it was marked with
+    /// `FOR SYSTEM_TIME AS OF`, along with the column its versions are selected as of
+    fn temporal_table(&self, plan: &LogicalPlan) -> Option<(String, String)> {
+        match plan {
+            LogicalPlan::TableScan(table_scan) => {
+                let name = table_scan.table_name.to_string();
+                self.temporal_tables
+                    .get(&name.to_lowercase())
+                    .map(|column| (name, column.clone()))
+            }
+            _ => match plan.inputs().as_slice() {
+                [input] => self.temporal_table(input),
+                _ => None,
+            },
+        }
+    }
+
+    fn insert_temporal_join(
+        &mut self,
+        join: &datafusion_expr::logical_plan::Join,
+        left_input: SqlOperator,
+        right_input: SqlOperator,
+        join_type: JoinType,
+        table: String,
+        column: String,
+    ) -> Result<SqlOperator> {
+        if !matches!(join_type, JoinType::Inner | JoinType::Left) {
+            bail!(
+                "temporal joins against '{}' must be inner or left joins",
+                table
+            );
+        }
+        // versions are selected by the event time of the left records, so the query must say so
+        match left_input.event_time_field() {
+            Some(field) if field == column => {}
+            Some(field) => bail!(
+                "temporal join against '{}' must be FOR SYSTEM_TIME AS OF the event time field \
+                of the left side '{}', not '{}'",
+                table,
+                field,
+                column
+            ),
+            None => bail!(
+                "temporal join against '{}' requires the left side to have an event_time_field \
+                to select versions as of",
+                table
+            ),
+        }
+        if left_input.is_updating() {
+            bail!("the left side of a temporal join must not be updating");
+        }
+        if left_input.has_window() || right_input.has_window() {
+            bail!("temporal joins are not supported over windowed inputs");
+        }
+
+        let (left_key, right_key) =
+            self.join_keys(join, join.filter.as_ref(), &left_input, &right_input)?;
+
+        Ok(SqlOperator::TemporalJoin(
+            Box::new(left_input),
+            Box::new(right_input),
+            JoinOperator {
+                left_key,
+                right_key,
+                join_type,
+            },
+        ))
+    }
+
+    fn insert_interval_join(
+        &mut self,
+        join: &datafusion_expr::logical_plan::Join,
+        left_input: SqlOperator,
+        right_input: SqlOperator,
+        join_type: JoinType,
+        bounds: IntervalBounds,
+        filter: &Expr,
+    ) -> Result<SqlOperator> {
+        if !matches!(join_type, JoinType::Inner) {
+            bail!("interval joins must be inner joins");
+        }
+        if left_input.is_updating() || right_input.is_updating() {
+            bail!("interval joins are not supported over updating inputs");
+        }
+        if left_input.has_window() || right_input.has_window() {
+            bail!("interval joins are not supported over windowed inputs");
+        }
+        if join.on.is_empty() {
+            bail!("interval joins must have at least one equality condition between the two sides");
+        }
+
+        let (left_key, right_key) = self.join_keys(join, None, &left_input, &right_input)?;
+
+        let interval_join = SqlOperator::IntervalJoin(
+            Box::new(left_input),
+            Box::new(right_input),
+            JoinOperator {
+                left_key,
+                right_key,
+                join_type,
+            },
+            bounds,
+        );
+
+        // candidates are found by event time, so the filter is still applied to the columns
+        let return_type = interval_join.return_type();
+        let predicate = self.ctx(&return_type).compile_expr(filter)?;
+        Self::assert_no_unnest_or_async_udf("join", &predicate)?;
+
+        Ok(SqlOperator::RecordTransform(
+            Box::new(interval_join),
+            RecordTransform::Filter(predicate),
+        ))
+    }
+
+    /// Computes the key projections for each side of an equi-join, where `filter` may contain an
+    /// additional equality condition
+    fn join_keys(
+        &self,
+        join: &datafusion_expr::logical_plan::Join,
+        filter: Option<&Expr>,
+        left_input: &SqlOperator,
+        right_input: &SqlOperator,
+    ) -> Result<(Projection, Projection)> {
         let mut join_pairs = join.on.clone();
-        if let Some(Expr::BinaryExpr(BinaryExpr { left, op, right })) = &join.filter {
+        if let Some(Expr::BinaryExpr(BinaryExpr { left, op, right })) = filter {
             if *op != datafusion_expr::Operator::Eq {
                 bail!("only equality joins are supported");
             }
 
-            // check which side each column comes from. Assumes there's at least one field
+            // check which side each column comes from. Assumes there's at least one field.
+            // The inputs are used rather than the join schema, as semi and anti joins only output one side.
             let left_relation = join
-                .schema
+                .left
+                .schema()
                 .fields()
                 .first()
                 .unwrap()
@@ -927,7 +1866,8 @@ impl<'a> SqlPipelineBuilder<'a> {
                 .unwrap()
                 .to_string();
             let right_relation = join
-                .schema
+                .right
+                .schema()
                 .fields()
                 .last()
                 .unwrap()
@@ -948,16 +1888,22 @@ impl<'a> SqlPipelineBuilder<'a> {
             };
 
             join_pairs.push(pair);
-        } else if join.filter.is_some() {
-            bail!(
-                "only equality joins are supported, not filter {:?}",
-                join.filter
-            );
+        } else if filter.is_some() {
+            bail!("only equality joins are supported, not filter {:?}", filter);
         }
 
+        // keys on expressions other than columns, like the nested fields that IN subqueries are
+        // often planned on, are given generated names
         let join_projection_field_names: Vec<_> = join_pairs
             .iter()
-            .map(|(left, _right)| Column::convert_expr(left))
+            .enumerate()
+            .map(|(i, (left, _right))| match left {
+                Expr::Column(_) => Column::convert_expr(left),
+                _ => Ok(Column {
+                    relation: None,
+                    name: format!("_key_{}", i),
+                }),
+            })
             .collect::<Result<Vec<_>>>()?;
 
         let (left_computations, right_computations): (Vec<_>, Vec<_>) = join
@@ -1004,17 +1950,329 @@ impl<'a> SqlPipelineBuilder<'a> {
                 .collect(),
         );
 
-        Ok(SqlOperator::JoinOperator(
-            Box::new(left_input),
-            Box::new(right_input),
-            JoinOperator {
-                left_key,
-                right_key,
+        Ok((left_key, right_key))
+    }
+
+    /// Returns the lookup table that a join input reads, if it reads directly from one
+    fn lookup_table(&self, plan: &LogicalPlan) -> Option<ConnectorTable> {
+        match plan {
+            LogicalPlan::TableScan(table_scan) => {
+                match self
+                    .schema_provider
+                    .get_table(&table_scan.table_name.to_string())
+                {
+                    Some(Table::ConnectorTable(table))
+                        if matches!(table.connection_type, ConnectionType::Lookup) =>
+                    {
+                        Some(table.clone())
+                    }
+                    _ => None,
+                }
+            }
+            LogicalPlan::SubqueryAlias(subquery_alias) => self.lookup_table(&subquery_alias.input),
+            _ => None,
+        }
+    }
+
+    fn insert_lookup_join(
+        &mut self,
+        join: &datafusion_expr::logical_plan::Join,
+        table: ConnectorTable,
+    ) -> Result<SqlOperator> {
+        let input = self.insert_sql_plan(&join.left)?;
+        if input.is_updating() {
+            bail!("lookup joins are not supported over updating inputs");
+        }
+
+        let join_type = match join.join_type {
+            datafusion_expr::JoinType::Inner => JoinType::Inner,
+            datafusion_expr::JoinType::Left => JoinType::Left,
+            join_type => bail!(
+                "lookup joins must be inner or left joins, not {} joins",
+                join_type
+            ),
+        };
+
+        let ([(left, Expr::Column(_))], None) = (join.on.as_slice(), &join.filter) else {
+            bail!(
+                "lookup joins must be on a single equality between an expression and a column of lookup table '{}'",
+                table.name
+            );
+        };
+
+        let input_struct = input.return_type();
+        let key = self.ctx(&input_struct).compile_expr(left)?;
+        Self::assert_no_unnest_or_async_udf("lookup join", &key)?;
+        match key
+            .expression_type(&ValuePointerContext::new())
+            .as_datatype()
+        {
+            Some(
+                DataType::Utf8
+                | DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64,
+            ) => {}
+            _ => bail!("lookup join key {} must be a string or an integer", left),
+        }
+
+        let lookup_struct = table.lookup_struct_def()?;
+        let fields = join
+            .right
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| {
+                let field = lookup_struct
+                    .fields
+                    .iter()
+                    .find(|field| field.name == *f.name())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "column {} not found in lookup table '{}'",
+                            f.name(),
+                            table.name
+                        )
+                    })?;
+                Ok((
+                    Column {
+                        relation: f.qualifier().map(|q| q.to_string()),
+                        name: f.name().clone(),
+                    },
+                    Expression::Column(ColumnExpression::new(field.clone())),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(SqlOperator::LookupJoin(
+            Box::new(input),
+            LookupJoinOperator {
+                table_name: table.name.clone(),
+                id: table.id,
+                connector: table.connector_op(),
+                input_struct,
+                lookup_struct,
+                projection: Projection::new(fields),
+                key,
                 join_type,
+                options: table.lookup.clone().unwrap_or_default(),
             },
         ))
     }
 
+    /// Returns the placeholder table that a call of a table function was planned as, along with
+    /// the filters on its rows that were pushed down onto it
+    fn table_function_call<'p>(&self, plan: &'p LogicalPlan) -> Option<(String, Vec<&'p Expr>)> {
+        match plan {
+            LogicalPlan::TableScan(table_scan) => {
+                let name = table_scan.table_name.to_string();
+                self.schema_provider
+                    .table_function_calls
+                    .contains_key(&name)
+                    .then_some((name, vec![]))
+            }
+            LogicalPlan::SubqueryAlias(subquery_alias) => {
+                self.table_function_call(&subquery_alias.input)
+            }
+            LogicalPlan::Filter(filter) => {
+                let (name, mut filters) = self.table_function_call(&filter.input)?;
+                filters.push(&filter.predicate);
+                Some((name, filters))
+            }
+            LogicalPlan::Projection(projection)
+                if projection
+                    .expr
+                    .iter()
+                    .all(|expr| matches!(expr, Expr::Column(_))) =>
+            {
+                self.table_function_call(&projection.input)
+            }
+            _ => None,
+        }
+    }
+
+    fn insert_table_function(
+        &mut self,
+        join: &datafusion_expr::logical_plan::Join,
+        placeholder: String,
+        row_filters: Vec<&Expr>,
+    ) -> Result<SqlOperator> {
+        let input = self.insert_sql_plan(&join.left)?;
+        let name = self.schema_provider.table_function_calls[&placeholder].clone();
+        if input.is_updating() {
+            bail!(
+                "table function '{}' is not supported over updating inputs",
+                name
+            );
+        }
+        let def = self.schema_provider.udf_defs[&name].clone();
+        let TypeDef::StructDef(row_struct, _) = def.ret.clone() else {
+            unreachable!("table functions return structs");
+        };
+
+        // conditions of the query that reference both the input and the rows are merged into the
+        // join along with the call of the function, and are applied to its output
+        let mut predicates: Vec<_> = join
+            .on
+            .iter()
+            .map(|(left, right)| {
+                Expr::BinaryExpr(BinaryExpr::new(
+                    Box::new(left.clone()),
+                    datafusion_expr::Operator::Eq,
+                    Box::new(right.clone()),
+                ))
+            })
+            .collect();
+        let mut call_args = None;
+        for predicate in join.filter.iter().flat_map(split_conjunction) {
+            match predicate {
+                Expr::ScalarUDF(ScalarUDF { fun, args }) if fun.name == placeholder => {
+                    // the last argument of the marker is a column of the rows
+                    call_args = Some(&args[..args.len() - 1]);
+                }
+                predicate => predicates.push(predicate.clone()),
+            }
+        }
+        let call_args = call_args.ok_or_else(|| {
+            anyhow!(
+                "table function '{}' must be joined with CROSS JOIN LATERAL",
+                name
+            )
+        })?;
+
+        let input_struct = input.return_type();
+        let ctx = self.ctx(&input_struct);
+        let args = call_args
+            .iter()
+            .map(|arg| {
+                let arg = ctx.compile_expr(arg)?;
+                Self::assert_no_unnest_or_async_udf("table function arguments", &arg)?;
+                Ok(arg)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let row_ctx = self.ctx(&row_struct);
+        let row_filters = row_filters
+            .into_iter()
+            .map(|filter| {
+                let filter = row_ctx.compile_expr(filter)?;
+                Self::assert_no_unnest_or_async_udf("where", &filter)?;
+                Ok(filter)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let function = RustUdfExpression {
+            name,
+            args: def.args.into_iter().zip(args).collect(),
+            ret_type: def.ret,
+            async_fn: def.async_fn,
+            has_context: def.has_context,
+            opts: def.opts,
+            wasm: false,
+        };
+
+        let output = match def.process_function {
+            Some(process_function) => {
+                // timers only have the key of the records, so the output is made up of the rows
+                let projection = Projection::new(
+                    join.schema
+                        .fields()
+                        .iter()
+                        .filter(|field| {
+                            join.left
+                                .schema()
+                                .index_of_column(&field.qualified_column())
+                                .is_err()
+                        })
+                        .map(|field| {
+                            Ok((
+                                Column::convert(&field.qualified_column()),
+                                Expression::Column(ColumnExpression::new(
+                                    row_struct.get_field(None, field.name())?,
+                                )),
+                            ))
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                );
+                let key = Projection::new(vec![(
+                    Column {
+                        relation: None,
+                        name: "key".to_string(),
+                    },
+                    function.args[0].1.clone(),
+                )]);
+
+                SqlOperator::ProcessFunction(
+                    Box::new(input),
+                    ProcessFunctionOperator {
+                        key,
+                        function,
+                        process_function,
+                        row_struct,
+                        row_filters,
+                        projection,
+                    },
+                )
+            }
+            None => {
+                let fields = join
+                    .schema
+                    .fields()
+                    .iter()
+                    .map(|field| {
+                        let column = field.qualified_column();
+                        if join.left.schema().index_of_column(&column).is_ok() {
+                            Ok((
+                                Column::convert(&column),
+                                Expression::Column(ColumnExpression::from_column(
+                                    &column,
+                                    &input_struct,
+                                )?),
+                                UnnestFieldType::Default,
+                            ))
+                        } else {
+                            Ok((
+                                Column::convert(&column),
+                                Expression::Column(ColumnExpression::new(
+                                    row_struct.get_field(None, field.name())?,
+                                )),
+                                UnnestFieldType::UnnestOuter,
+                            ))
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                SqlOperator::RecordTransform(
+                    Box::new(input),
+                    RecordTransform::TableFunctionProjection(TableFunctionProjection {
+                        input_struct,
+                        function,
+                        row_struct,
+                        row_filters,
+                        fields,
+                    }),
+                )
+            }
+        };
+
+        let Some(predicate) = conjunction(predicates) else {
+            return Ok(output);
+        };
+        let output_struct = output.return_type();
+        let predicate = self.ctx(&output_struct).compile_expr(&predicate)?;
+        Self::assert_no_unnest_or_async_udf("where", &predicate)?;
+
+        Ok(SqlOperator::RecordTransform(
+            Box::new(output),
+            RecordTransform::Filter(predicate),
+        ))
+    }
+
     fn insert_table_scan(
         &mut self,
         table_scan: &datafusion::logical_expr::TableScan,
@@ -1068,27 +2326,11 @@ impl<'a> SqlPipelineBuilder<'a> {
                 Expr::WindowFunction(window_function) => window_function,
                 _ => bail!("expected window function"),
             };
-            let window_fn = match &w.fun {
-                datafusion_expr::WindowFunction::AggregateFunction(_) => {
-                    bail!("window aggregate functions not yet supported")
-                }
-                datafusion_expr::WindowFunction::BuiltInWindowFunction(
-                    BuiltInWindowFunction::RowNumber,
-                ) => WindowFunction::RowNumber,
-                datafusion_expr::WindowFunction::BuiltInWindowFunction(w) => {
-                    bail!("Window function {} not yet supported", w);
-                }
-                datafusion_expr::WindowFunction::AggregateUDF(_) => {
-                    bail!("Window UDAFs not yet supported");
-                }
-                datafusion_expr::WindowFunction::WindowUDF(_) => {
-                    bail!("Window UDFs not yet supported");
-                }
-            };
-
             let input_struct = input.return_type();
             let mut ctx = self.ctx(&input_struct);
 
+            let window_fn = WindowFunction::try_from_window_expr(&ctx, w)?;
+
             let order_by: Vec<_> = w
                 .order_by
                 .iter()
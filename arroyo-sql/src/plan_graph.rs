@@ -3,7 +3,6 @@ use std::{
     time::Duration,
 };
 
-use arrow_schema::DataType;
 use arroyo_datastream::{
     EdgeType, ExpressionReturnType, NonWindowAggregator, Operator, PeriodicWatermark, Program,
     ProgramUdf, SlidingAggregatingTopN, SlidingWindowAggregator, StreamEdge, StreamNode,
@@ -11,7 +10,8 @@ use arroyo_datastream::{
 };
 
 use petgraph::graph::{DiGraph, NodeIndex};
-use quote::{quote, ToTokens};
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote, ToTokens};
 use syn::{parse_quote, parse_str, Type};
 
 use crate::expressions::AggregateComputation;
@@ -20,16 +20,18 @@ use crate::{
     code_gen::{
         BinAggregatingContext, CodeGenerator, CombiningContext, JoinListsContext, JoinPairContext,
         MemoryAddingContext, MemoryAggregatingContext, MemoryRemovingContext,
-        ValueBinMergingContext, ValuePointerContext, VecAggregationContext,
+        ValueBinMergingContext, ValuePointerContext, VecAggregationContext, VecOfPointersContext,
     },
-    expressions::{Column, ColumnExpression, Expression, SortExpression},
+    expressions::{wasm_udf_static, Column, ColumnExpression, Expression, SortExpression},
     external::{ProcessingMode, SinkUpdateType, SqlSink, SqlSource},
     operators::{AggregateProjection, Projection, TwoPhaseAggregateProjection},
     optimizations::optimize,
     pipeline::{
-        JoinType, MethodCompiler, RecordTransform, SourceOperator, SqlOperator, WindowFunction,
+        IntervalBounds, JoinType, LookupJoinOperator, MethodCompiler, ProcessFunctionOperator,
+        RecordTransform, SourceOperator, SqlOperator, WindowFrame, WindowFrameBound,
+        WindowFunction,
     },
-    types::{StructDef, StructField, StructPair, TypeDef},
+    types::{StructDef, StructField, StructPair},
     ArroyoSchemaProvider, CompiledSql, SqlConfig,
 };
 use anyhow::Result;
@@ -45,6 +47,7 @@ pub enum PlanOperator {
     WindowAggregate {
         window: WindowType,
         projection: AggregateProjection,
+        allowed_lateness: Duration,
     },
     NonWindowAggregate {
         input_is_update: bool,
@@ -68,6 +71,20 @@ pub enum PlanOperator {
     },
     JoinListMerge(JoinType, StructPair),
     JoinPairMerge(JoinType, StructPair, InputsUpdating),
+    LookupJoin(LookupJoinOperator),
+    ProcessFunction(ProcessFunctionOperator),
+    TemporalJoin {
+        join_type: JoinType,
+        structs: StructPair,
+    },
+    IntervalJoin {
+        bounds: IntervalBounds,
+        structs: StructPair,
+    },
+    LateRecordFilter {
+        window: WindowType,
+        allowed_lateness: Duration,
+    },
     Flatten,
     // TODO: figure out naming of various things called 'window'
     WindowFunction(WindowFunctionOperator),
@@ -97,6 +114,13 @@ pub enum PlanOperator {
     Sink(String, SqlSink),
 }
 
+/// How the rows of the two sides of a join are matched, when it isn't over windows
+enum JoinKind {
+    Standard,
+    Temporal,
+    Interval(IntervalBounds),
+}
+
 #[derive(Debug, Clone)]
 pub struct WindowFunctionOperator {
     pub window_function: WindowFunction,
@@ -106,6 +130,222 @@ pub struct WindowFunctionOperator {
     pub field_name: String,
 }
 
+impl WindowFunctionOperator {
+    // computes the window function over the rows of a partition, which are passed in as `arg`.
+    fn window_expression(&self) -> syn::Expr {
+        let window_field = self.result_struct.fields.last().unwrap().field_ident();
+        let result_struct_name = self.result_struct.get_type();
+        let field_assignments: Vec<_> = self
+            .result_struct
+            .fields
+            .iter()
+            .take(self.result_struct.fields.len() - 1)
+            .map(|f| {
+                let ident = f.field_ident();
+                quote! { #ident: arg.#ident.clone() }
+            })
+            .collect();
+
+        let sort_tokens = SortExpression::sort_tuple_expression(&self.order_by);
+        let sort =
+            (!self.order_by.is_empty()).then(|| quote!(arg.sort_by_key(|arg| #sort_tokens);));
+        // rows with the same ORDER BY values are peers, which share a rank and a RANGE frame
+        let peers = self
+            .uses_peers()
+            .then(|| quote!(let peers: Vec<_> = arg.iter().map(|arg| #sort_tokens).collect();));
+
+        let context = ValuePointerContext::new();
+        let (prelude, row_statements, value): (
+            Option<TokenStream>,
+            Option<TokenStream>,
+            syn::Expr,
+        ) = match &self.window_function {
+            WindowFunction::RowNumber => (None, None, parse_quote!((index + 1) as u64)),
+            WindowFunction::Rank => (
+                None,
+                None,
+                parse_quote!((peers.partition_point(|peer| peer < &peers[index]) + 1) as u64),
+            ),
+            WindowFunction::DenseRank => (
+                Some(quote!(let mut dense_rank = 0u64;)),
+                Some(quote!(if index == 0 || peers[index] != peers[index - 1] {
+                    dense_rank += 1;
+                })),
+                parse_quote!(dense_rank),
+            ),
+            WindowFunction::PercentRank => (
+                None,
+                None,
+                parse_quote!(if rows.len() > 1 {
+                    peers.partition_point(|peer| peer < &peers[index]) as f64
+                        / (rows.len() - 1) as f64
+                } else {
+                    0.0
+                }),
+            ),
+            WindowFunction::Lag {
+                expression,
+                offset,
+                default,
+            }
+            | WindowFunction::Lead {
+                expression,
+                offset,
+                default,
+            } => {
+                let nullable = self.window_function.return_type().is_optional();
+                let wrap = |expression: &Expression| -> syn::Expr {
+                    let value = expression.generate(&context);
+                    if nullable && !expression.expression_type(&context).is_optional() {
+                        parse_quote!(Some(#value))
+                    } else {
+                        value
+                    }
+                };
+                let value = wrap(expression);
+                let default: syn::Expr = match default {
+                    Some(default) => wrap(default),
+                    None => parse_quote!(None),
+                };
+                let offset_row: syn::Expr =
+                    if matches!(self.window_function, WindowFunction::Lag { .. }) {
+                        parse_quote!(index.checked_sub(#offset).map(|index| &rows[index]))
+                    } else {
+                        parse_quote!(rows.get(index + #offset))
+                    };
+                (
+                    None,
+                    None,
+                    parse_quote!(match #offset_row {
+                        Some(arg) => #value,
+                        None => #default,
+                    }),
+                )
+            }
+            WindowFunction::FirstValue(expression, frame) => {
+                let value = expression.generate(&context);
+                (
+                    None,
+                    Some(Self::frame_bounds(frame)),
+                    parse_quote!({
+                        let arg = &rows[frame_start];
+                        #value
+                    }),
+                )
+            }
+            WindowFunction::LastValue(expression, frame) => {
+                let value = expression.generate(&context);
+                (
+                    None,
+                    Some(Self::frame_bounds(frame)),
+                    parse_quote!({
+                        let arg = &rows[frame_end - 1];
+                        #value
+                    }),
+                )
+            }
+            WindowFunction::Aggregate(aggregation, frame) => {
+                let value = aggregation.generate(&VecOfPointersContext);
+                (
+                    None,
+                    Some(Self::frame_bounds(frame)),
+                    parse_quote!({
+                        let arg = &rows[frame_start..frame_end];
+                        #value
+                    }),
+                )
+            }
+            WindowFunction::Udf { name, args, .. } => {
+                let function = format_ident!("{}", name);
+                let args = args.iter().map(|(arg_type, expression)| {
+                    let value = expression.generate(&context);
+                    if arg_type.is_optional() && !expression.expression_type(&context).is_optional()
+                    {
+                        quote!(rows.iter().map(|arg| Some(#value)).collect())
+                    } else {
+                        quote!(rows.iter().map(|arg| #value).collect())
+                    }
+                });
+                (
+                    Some(quote! {
+                        let values = udfs::#function(#(#args),*);
+                        assert_eq!(
+                            values.len(),
+                            rows.len(),
+                            "window UDF {} must return a value for each row",
+                            #name
+                        );
+                        let mut values = values.into_iter();
+                    }),
+                    None,
+                    parse_quote!(values.next().unwrap()),
+                )
+            }
+        };
+
+        parse_quote!({
+            #sort
+            #peers
+            let rows = &arg[..];
+            #prelude
+            let mut result = vec![];
+            for (index, arg) in rows.iter().enumerate() {
+                #row_statements
+                result.push(#result_struct_name {
+                    #(#field_assignments, )*
+                    #window_field: #value
+                });
+            }
+            result
+        })
+    }
+
+    fn uses_peers(&self) -> bool {
+        match &self.window_function {
+            WindowFunction::Rank | WindowFunction::DenseRank | WindowFunction::PercentRank => true,
+            WindowFunction::FirstValue(_, frame)
+            | WindowFunction::LastValue(_, frame)
+            | WindowFunction::Aggregate(_, frame) => frame.range,
+            WindowFunction::RowNumber
+            | WindowFunction::Lag { .. }
+            | WindowFunction::Lead { .. }
+            | WindowFunction::Udf { .. } => false,
+        }
+    }
+
+    // sets frame_start and frame_end (exclusive) to the bounds of the current row's frame
+    fn frame_bounds(frame: &WindowFrame) -> TokenStream {
+        let start: syn::Expr = match (&frame.start, frame.range) {
+            (WindowFrameBound::UnboundedPreceding, _) => parse_quote!(0),
+            (WindowFrameBound::Preceding(offset), _) => parse_quote!(index.saturating_sub(#offset)),
+            (WindowFrameBound::CurrentRow, false) => parse_quote!(index),
+            (WindowFrameBound::CurrentRow, true) => {
+                parse_quote!(peers.partition_point(|peer| peer < &peers[index]))
+            }
+            (WindowFrameBound::Following(_) | WindowFrameBound::UnboundedFollowing, _) => {
+                unreachable!("window frames always contain the current row")
+            }
+        };
+        let end: syn::Expr = match (&frame.end, frame.range) {
+            (WindowFrameBound::UnboundedFollowing, _) => parse_quote!(rows.len()),
+            (WindowFrameBound::Following(offset), _) => {
+                parse_quote!((index + #offset + 1).min(rows.len()))
+            }
+            (WindowFrameBound::CurrentRow, false) => parse_quote!(index + 1),
+            (WindowFrameBound::CurrentRow, true) => {
+                parse_quote!(peers.partition_point(|peer| peer <= &peers[index]))
+            }
+            (WindowFrameBound::Preceding(_) | WindowFrameBound::UnboundedPreceding, _) => {
+                unreachable!("window frames always contain the current row")
+            }
+        };
+        quote! {
+            let frame_start = #start;
+            let frame_end = #end;
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FusedRecordTransform {
     pub expressions: Vec<RecordTransform>,
@@ -184,6 +424,9 @@ impl FusedRecordTransform {
                 RecordTransform::AsyncUdfProjection(_) => {
                     unreachable!("async udf projection cannot be fused")
                 }
+                RecordTransform::TableFunctionProjection(_) => {
+                    unreachable!("table function projection cannot be fused")
+                }
             }
         }
         let combined: syn::Expr = parse_quote!({
@@ -308,6 +551,11 @@ impl PlanNode {
             }
             RecordTransform::UnnestProjection(p) => input_type.with_value(p.output_struct()),
             RecordTransform::AsyncUdfProjection(p) => input_type.with_value(p.output_struct()),
+            // async table functions return all of the rows for a record at once
+            RecordTransform::TableFunctionProjection(p) if p.is_async() => {
+                PlanType::UnkeyedList(p.output_struct())
+            }
+            RecordTransform::TableFunctionProjection(p) => input_type.with_value(p.output_struct()),
         };
         PlanNode {
             operator: PlanOperator::RecordTransform(record_transform),
@@ -333,6 +581,11 @@ impl PlanNode {
             PlanOperator::JoinWithExpiration { .. } => "join_with_expiration".to_string(),
             PlanOperator::JoinListMerge(_, _) => "join_list_merge".to_string(),
             PlanOperator::JoinPairMerge(_, _, _) => "join_pair_merge".to_string(),
+            PlanOperator::LookupJoin(_) => "lookup_join".to_string(),
+            PlanOperator::ProcessFunction(_) => "process_function".to_string(),
+            PlanOperator::TemporalJoin { .. } => "temporal_join".to_string(),
+            PlanOperator::IntervalJoin { .. } => "interval_join".to_string(),
+            PlanOperator::LateRecordFilter { .. } => "late_record_filter".to_string(),
             PlanOperator::Flatten => "flatten".to_string(),
             PlanOperator::WindowFunction { .. } => "window_function".to_string(),
             PlanOperator::StreamOperator(name, _) => name.to_string(),
@@ -354,7 +607,11 @@ impl PlanNode {
             PlanOperator::RecordTransform(record_transform) => {
                 record_transform.as_operator(self.output_type.is_updating())
             }
-            PlanOperator::WindowAggregate { window, projection } => {
+            PlanOperator::WindowAggregate {
+                window,
+                projection,
+                allowed_lateness,
+            } => {
                 let aggregating_context = VecAggregationContext::new();
                 let aggregate_expr = projection.generate(&aggregating_context);
                 arroyo_datastream::Operator::Window {
@@ -365,6 +622,7 @@ impl PlanNode {
                         expression: quote::quote! { #aggregate_expr }.to_string(),
                     }),
                     flatten: false,
+                    allowed_lateness: *allowed_lateness,
                 }
             }
             PlanOperator::TumblingWindowTwoPhaseAggregator {
@@ -477,64 +735,52 @@ impl PlanNode {
                 }
             }
 
-            PlanOperator::WindowFunction(WindowFunctionOperator {
-                window_function,
-                order_by,
-                window_type,
-                result_struct,
-                field_name: _,
-            }) => {
-                let window_field = result_struct.fields.last().unwrap().field_ident();
-                let result_struct_name = result_struct.get_type();
-                let mut field_assignments: Vec<_> = result_struct
-                    .fields
-                    .iter()
-                    .take(result_struct.fields.len() - 1)
-                    .map(|f| {
-                        let ident = f.field_ident();
-                        quote! { #ident: arg.#ident.clone() }
-                    })
-                    .collect();
-
-                match window_function {
-                    WindowFunction::RowNumber => {
-                        field_assignments.push(quote! {
-                            #window_field: i as u64
-                        });
-                    }
-                }
-
-                let output_expression = quote!(#result_struct_name {
-                    #(#field_assignments, )*
-                });
-
-                let sort = if !order_by.is_empty() {
-                    let sort_tokens = SortExpression::sort_tuple_expression(order_by);
-                    Some(quote!(arg.sort_by_key(|arg| #sort_tokens);))
-                } else {
-                    None
-                };
+            PlanOperator::WindowFunction(window_function_operator) => {
+                let expression = window_function_operator.window_expression();
                 arroyo_datastream::Operator::Window {
-                    typ: window_type.clone(),
+                    typ: window_function_operator.window_type.clone(),
                     agg: Some(WindowAgg::Expression {
                         name: "sql_window".to_string(),
-                        expression: quote! {
-                            {
-                                #sort
-                                let mut result = vec![];
-                                for (index, arg) in arg.iter().enumerate() {
-                                    let i = index + 1;
-                                    result.push(#output_expression);
-                                }
-                                result
-                            }
-                        }
-                        .to_string(),
+                        expression: quote!(#expression).to_string(),
                     }),
                     flatten: true,
+                    allowed_lateness: Duration::ZERO,
                 }
             }
             PlanOperator::StreamOperator(_, stream_operator) => stream_operator.clone(),
+            PlanOperator::LookupJoin(lookup_join) => lookup_join.to_operator(),
+            PlanOperator::ProcessFunction(process_function) => process_function.to_operator(),
+            PlanOperator::TemporalJoin { join_type, structs } => {
+                let context = JoinPairContext::new(structs.left.clone(), structs.right.clone());
+                let merge = join_type.generate(&context);
+                let merge_expression: syn::Expr = match join_type {
+                    JoinType::Left => parse_quote!({ #merge }),
+                    _ => parse_quote!({
+                        let right = right.expect("inner temporal joins only merge matching versions");
+                        #merge
+                    }),
+                };
+                Operator::TemporalJoin {
+                    join_type: join_type.clone().into(),
+                    merge_expression: quote!(#merge_expression).to_string(),
+                }
+            }
+            PlanOperator::IntervalJoin { bounds, structs } => {
+                let context = JoinPairContext::new(structs.left.clone(), structs.right.clone());
+                let merge = JoinType::Inner.generate(&context);
+                Operator::IntervalJoin {
+                    lower_bound_micros: bounds.lower_micros,
+                    upper_bound_micros: bounds.upper_micros,
+                    merge_expression: quote!(#merge).to_string(),
+                }
+            }
+            PlanOperator::LateRecordFilter {
+                window,
+                allowed_lateness,
+            } => Operator::LateRecordFilter {
+                window: window.clone(),
+                allowed_lateness: *allowed_lateness,
+            },
             PlanOperator::FusedRecordTransform(fused_record_transform) => {
                 fused_record_transform.to_operator()
             }
@@ -689,6 +935,7 @@ impl PlanNode {
                             #window_field: i as u64
                         });
                     }
+                    _ => unreachable!("top-n is only planned for row_number()"),
                 }
                 let output_expression = quote!(#output_struct {
                     #(#field_assignments, )*
@@ -770,6 +1017,7 @@ impl PlanNode {
 
                     let current_bin_ident = bin_merge_context.bin_context.current_bin_ident();
                     let arg_ident = bin_merge_context.value_context.variable_ident();
+                    let timestamp_ident = bin_merge_context.timestamp_ident();
 
                     let bin_merger_expr = projection.generate(&bin_merge_context);
                     let bin_type = projection
@@ -783,14 +1031,17 @@ impl PlanNode {
                     let memory_remove = projection.generate(&memory_removing_context);
                     let bin_ident = memory_removing_context.bin_value_ident();
                     let memory_ident = memory_removing_context.memory_value_ident();
-                    let bin_merger = quote!(|#arg_ident, #memory_ident| {
-                        let #current_bin_ident: Option<#bin_type> = None;
-                        let updating_bin = arg.map_over_inner(|#arg_ident| #bin_merger_expr);
+                    let bin_merger = quote!(|#arg_ident, #timestamp_ident, #memory_ident| {
+                        // each record is merged into an empty bin, which is then added to the memory
+                        let updating_bin = arg.map_over_inner(|#arg_ident| {
+                            let #current_bin_ident: Option<#bin_type> = None;
+                            #bin_merger_expr
+                        });
                         if let Some(updating_bin) = updating_bin {
                             match updating_bin {
                                 arroyo_types::UpdatingData::Retract(retract) => {
-                                    let #bin_ident = retract;
                                     let #memory_ident = #memory_ident.expect(&format!("retracting means there should be state for {:?}", retract)).clone();
+                                    let #bin_ident = retract;
                                     #memory_remove
                                 },
                                 arroyo_types::UpdatingData::Update { old, new } => {
@@ -854,6 +1105,17 @@ impl PlanNode {
             | PlanOperator::JoinListMerge(join_type, StructPair { left, right }) => {
                 output_types.insert(join_type.join_struct_type(left, right));
             }
+            PlanOperator::LookupJoin(lookup_join) => {
+                output_types.extend(lookup_join.lookup_struct.all_structs());
+                output_types.extend(lookup_join.projection.output_struct().all_structs());
+            }
+            PlanOperator::RecordTransform(RecordTransform::TableFunctionProjection(p)) => {
+                output_types.extend(p.row_struct.all_structs());
+            }
+            PlanOperator::ProcessFunction(p) => {
+                output_types.extend(p.row_struct.all_structs());
+                output_types.extend(p.process_function.state.all_structs());
+            }
             PlanOperator::FusedRecordTransform(fused_record_transform) => {
                 fused_record_transform.output_types.iter().for_each(|t| {
                     output_types.extend(t.get_all_types());
@@ -946,6 +1208,12 @@ impl PlanType {
                     JoinType::Full => {
                         parse_quote!(arroyo_types::UpdatingData<(Option<#left_type>,Option<#right_type>)>)
                     }
+                    JoinType::LeftSemi | JoinType::LeftAnti => {
+                        parse_quote!(arroyo_types::UpdatingData<#left_type>)
+                    }
+                    JoinType::RightSemi | JoinType::RightAnti => {
+                        parse_quote!(arroyo_types::UpdatingData<#right_type>)
+                    }
                 }
             }
             PlanType::KeyedListPair {
@@ -1195,6 +1463,9 @@ impl PlanGraph {
                 PlanOperator::Source(_, _) => {}
                 PlanOperator::Watermark(_) => {}
                 PlanOperator::RecordTransform(ref mut r) => {
+                    if let RecordTransform::TableFunctionProjection(p) = r {
+                        used_udfs.insert(p.function.name());
+                    }
                     r.expressions()
                         .for_each(|e| e.traverse_mut(used_udfs, &accumulate_udfs));
                 }
@@ -1214,8 +1485,8 @@ impl PlanGraph {
                             ..
                         } => {
                             computation
-                                .producing_expression
-                                .traverse_mut(used_udfs, &accumulate_udfs);
+                                .expressions()
+                                .for_each(|e| e.traverse_mut(used_udfs, &accumulate_udfs));
                         }
                         AggregateComputation::UDAF {
                             ref mut computation,
@@ -1247,11 +1518,29 @@ impl PlanGraph {
                 PlanOperator::JoinWithExpiration { .. } => {}
                 PlanOperator::JoinListMerge(_, _) => {}
                 PlanOperator::JoinPairMerge(_, _, _) => {}
+                PlanOperator::TemporalJoin { .. } => {}
+                PlanOperator::IntervalJoin { .. } => {}
+                PlanOperator::LateRecordFilter { .. } => {}
+                PlanOperator::LookupJoin(ref mut lookup_join) => {
+                    lookup_join.key.traverse_mut(used_udfs, &accumulate_udfs);
+                }
+                PlanOperator::ProcessFunction(ref mut process_function) => {
+                    used_udfs.insert(process_function.function.name());
+                    process_function
+                        .function
+                        .args
+                        .iter_mut()
+                        .for_each(|(_, e)| e.traverse_mut(used_udfs, &accumulate_udfs));
+                }
                 PlanOperator::Flatten => {}
                 PlanOperator::WindowFunction(w) => {
+                    if let WindowFunction::Udf { name, .. } = &w.window_function {
+                        used_udfs.insert(name.clone());
+                    }
                     w.order_by
                         .iter_mut()
                         .map(|o| o.expression())
+                        .chain(w.window_function.expressions())
                         .for_each(|e| e.traverse_mut(used_udfs, &accumulate_udfs));
                 }
                 PlanOperator::TumblingLocalAggregator {
@@ -1294,7 +1583,17 @@ impl PlanGraph {
             SqlOperator::Source(source_operator) => self.add_sql_source(source_operator),
             SqlOperator::Aggregator(input, projection) => self.add_aggregator(input, projection),
             SqlOperator::JoinOperator(left, right, join_operator) => {
-                self.add_join(left, right, join_operator)
+                self.add_join(left, right, join_operator, JoinKind::Standard)
+            }
+            SqlOperator::TemporalJoin(left, right, join_operator) => {
+                self.add_join(left, right, join_operator, JoinKind::Temporal)
+            }
+            SqlOperator::IntervalJoin(left, right, join_operator, bounds) => {
+                self.add_join(left, right, join_operator, JoinKind::Interval(bounds))
+            }
+            SqlOperator::LookupJoin(input, lookup_join) => self.add_lookup_join(input, lookup_join),
+            SqlOperator::ProcessFunction(input, process_function) => {
+                self.add_process_function(input, process_function)
             }
             SqlOperator::Window(input, window_operator) => self.add_window(input, window_operator),
             SqlOperator::RecordTransform(input, transform) => {
@@ -1470,16 +1769,44 @@ impl PlanGraph {
         let aggregate_projection = aggregate.aggregating;
         let aggregate_struct = aggregate_projection.expression_type(&VecAggregationContext::new());
 
+        if let Some(name) = aggregate.late_records {
+            // the late records are planned as a named table, so that they can be inserted into
+            // the late data sink
+            if !self.named_tables.contains_key(&name) {
+                let late_index = self.insert_operator(
+                    PlanOperator::LateRecordFilter {
+                        window: aggregate.window.clone(),
+                        allowed_lateness: aggregate.allowed_lateness,
+                    },
+                    self.get_plan_node(key_index).output_type.clone(),
+                );
+                self.graph.add_edge(
+                    key_index,
+                    late_index,
+                    PlanEdge {
+                        edge_type: EdgeType::Shuffle,
+                    },
+                );
+                self.named_tables.insert(name, late_index);
+            }
+        }
+
+        let output_type = PlanType::Keyed {
+            key: key_struct.clone(),
+            value: aggregate_struct.clone(),
+        };
         let aggregate_operator = PlanOperator::WindowAggregate {
             window: aggregate.window,
             projection: aggregate_projection,
+            allowed_lateness: aggregate.allowed_lateness,
         };
 
         let aggregate_index = self.insert_operator(
             aggregate_operator,
-            PlanType::Keyed {
-                key: key_struct.clone(),
-                value: aggregate_struct.clone(),
+            if aggregate.allowed_lateness.is_zero() {
+                output_type
+            } else {
+                PlanType::Updating(Box::new(output_type))
             },
         );
 
@@ -1498,6 +1825,7 @@ impl PlanGraph {
         left: Box<SqlOperator>,
         right: Box<SqlOperator>,
         join_operator: crate::pipeline::JoinOperator,
+        kind: JoinKind,
     ) -> NodeIndex {
         let left_type = left.return_type();
         let right_type = right.return_type();
@@ -1542,17 +1870,32 @@ impl PlanGraph {
             .add_edge(left_index, left_key_index, left_key_edge);
         self.graph
             .add_edge(right_index, right_key_index, right_key_edge);
-        if has_window {
-            self.add_post_window_join(
+        match kind {
+            JoinKind::Interval(bounds) => self.add_interval_join(
+                left_key_index,
+                right_key_index,
+                key_struct,
+                left_type,
+                right_type,
+                bounds,
+            ),
+            JoinKind::Temporal => self.add_temporal_join(
                 left_key_index,
                 right_key_index,
                 key_struct,
                 left_type,
                 right_type,
                 join_type,
-            )
-        } else {
-            self.add_join_with_expiration(
+            ),
+            JoinKind::Standard if has_window => self.add_post_window_join(
+                left_key_index,
+                right_key_index,
+                key_struct,
+                left_type,
+                right_type,
+                join_type,
+            ),
+            JoinKind::Standard => self.add_join_with_expiration(
                 left_key_index,
                 right_key_index,
                 key_struct,
@@ -1560,7 +1903,7 @@ impl PlanGraph {
                 right_type,
                 join_type,
                 inputs_updating,
-            )
+            ),
         }
     }
 
@@ -1621,6 +1964,90 @@ impl PlanGraph {
         flatten_index
     }
 
+    fn add_temporal_join(
+        &mut self,
+        left_index: NodeIndex,
+        right_index: NodeIndex,
+        key_struct: StructDef,
+        left_struct: StructDef,
+        right_struct: StructDef,
+        join_type: JoinType,
+    ) -> NodeIndex {
+        let merge_type = join_type.output_struct(&left_struct, &right_struct);
+        let join_node = PlanOperator::TemporalJoin {
+            join_type,
+            structs: StructPair {
+                left: left_struct,
+                right: right_struct,
+            },
+        };
+        let join_node_index = self.insert_operator(
+            join_node,
+            PlanType::Keyed {
+                key: key_struct,
+                value: merge_type,
+            },
+        );
+
+        self.graph.add_edge(
+            left_index,
+            join_node_index,
+            PlanEdge {
+                edge_type: EdgeType::ShuffleJoin(0),
+            },
+        );
+        self.graph.add_edge(
+            right_index,
+            join_node_index,
+            PlanEdge {
+                edge_type: EdgeType::ShuffleJoin(1),
+            },
+        );
+        join_node_index
+    }
+
+    fn add_interval_join(
+        &mut self,
+        left_index: NodeIndex,
+        right_index: NodeIndex,
+        key_struct: StructDef,
+        left_struct: StructDef,
+        right_struct: StructDef,
+        bounds: IntervalBounds,
+    ) -> NodeIndex {
+        let merge_type = JoinType::Inner.output_struct(&left_struct, &right_struct);
+        let join_node = PlanOperator::IntervalJoin {
+            bounds,
+            structs: StructPair {
+                left: left_struct,
+                right: right_struct,
+            },
+        };
+        let join_node_index = self.insert_operator(
+            join_node,
+            PlanType::Keyed {
+                key: key_struct,
+                value: merge_type,
+            },
+        );
+
+        self.graph.add_edge(
+            left_index,
+            join_node_index,
+            PlanEdge {
+                edge_type: EdgeType::ShuffleJoin(0),
+            },
+        );
+        self.graph.add_edge(
+            right_index,
+            join_node_index,
+            PlanEdge {
+                edge_type: EdgeType::ShuffleJoin(1),
+            },
+        );
+        join_node_index
+    }
+
     fn add_join_with_expiration(
         &mut self,
         left_index: NodeIndex,
@@ -1636,11 +2063,20 @@ impl PlanGraph {
             right_expiration: Duration::from_secs(24 * 60 * 60),
             join_type: join_type.clone(),
         };
-        let join_node_output_type = PlanType::KeyedPair {
-            key: key_struct.clone(),
-            left_value: left_struct.clone(),
-            right_value: right_struct.clone(),
-            join_type: join_type.clone(),
+        let semi_or_anti = join_type.is_semi_or_anti();
+        let join_node_output_type = if semi_or_anti {
+            // semi and anti joins emit the rows of one side directly, so there's nothing to merge.
+            PlanType::Updating(Box::new(PlanType::Keyed {
+                key: key_struct.clone(),
+                value: join_type.output_struct(&left_struct, &right_struct),
+            }))
+        } else {
+            PlanType::KeyedPair {
+                key: key_struct.clone(),
+                left_value: left_struct.clone(),
+                right_value: right_struct.clone(),
+                join_type: join_type.clone(),
+            }
         };
         let join_node_index = self.insert_operator(join_node, join_node_output_type);
 
@@ -1655,7 +2091,24 @@ impl PlanGraph {
         self.graph
             .add_edge(right_index, join_node_index, right_join_edge);
 
+        let updating = inputs_updating.left || inputs_updating.right;
         let merge_type = join_type.output_struct(&left_struct, &right_struct);
+
+        if semi_or_anti {
+            if updating || join_type.is_anti() {
+                return join_node_index;
+            }
+            // semi joins over append-only inputs can't produce retractions,
+            // so the output can be converted back to non-updating
+            let index =
+                self.insert_operator(PlanOperator::FromUpdating, PlanType::Unkeyed(merge_type));
+            let edge = PlanEdge {
+                edge_type: EdgeType::Forward,
+            };
+            self.graph.add_edge(join_node_index, index, edge);
+            return index;
+        }
+
         let merge_operator = PlanOperator::JoinPairMerge(
             join_type.clone(),
             StructPair {
@@ -1665,7 +2118,6 @@ impl PlanGraph {
             inputs_updating.clone(),
         );
 
-        let updating = inputs_updating.left || inputs_updating.right;
         let merge_output_type = match (join_type, updating) {
             (JoinType::Inner, false) => PlanType::Unkeyed(merge_type),
             _ => PlanType::Updating(Box::new(PlanType::Keyed {
@@ -1701,7 +2153,7 @@ impl PlanGraph {
         result_type.fields.push(StructField::new(
             window_operator.field_name.clone(),
             None,
-            TypeDef::DataType(DataType::UInt64, false),
+            window_operator.window_fn.return_type(),
         ));
         let partition_struct = window_operator.partition.output_struct();
 
@@ -1763,6 +2215,13 @@ impl PlanGraph {
     ) -> NodeIndex {
         let input_index = self.add_sql_operator(*input);
 
+        let flattened_type = match &transform {
+            RecordTransform::TableFunctionProjection(p) if p.is_async() => {
+                Some(PlanType::Unkeyed(p.output_struct()))
+            }
+            _ => None,
+        };
+
         let plan_node = PlanNode::from_record_transform(transform, self.get_plan_node(input_index));
 
         let plan_node_index = self.graph.add_node(plan_node);
@@ -1770,6 +2229,89 @@ impl PlanGraph {
             edge_type: EdgeType::Forward,
         };
         self.graph.add_edge(input_index, plan_node_index, edge);
+
+        if let Some(flattened_type) = flattened_type {
+            let flatten_index = self.insert_operator(PlanOperator::Flatten, flattened_type);
+            self.graph.add_edge(
+                plan_node_index,
+                flatten_index,
+                PlanEdge {
+                    edge_type: EdgeType::Forward,
+                },
+            );
+            return flatten_index;
+        }
+
+        plan_node_index
+    }
+
+    fn add_process_function(
+        &mut self,
+        input: Box<SqlOperator>,
+        process_function: ProcessFunctionOperator,
+    ) -> NodeIndex {
+        let input_index = self.add_sql_operator(*input);
+
+        let key_struct = process_function.key.output_struct();
+        let key_index = self.insert_operator(
+            PlanOperator::RecordTransform(RecordTransform::KeyProjection(
+                process_function.key.clone(),
+            )),
+            self.get_plan_node(input_index)
+                .output_type
+                .with_key(key_struct.clone()),
+        );
+        self.graph.add_edge(
+            input_index,
+            key_index,
+            PlanEdge {
+                edge_type: EdgeType::Forward,
+            },
+        );
+
+        let output_type = PlanType::Keyed {
+            key: key_struct,
+            value: process_function.output_struct(),
+        };
+        let process_index =
+            self.insert_operator(PlanOperator::ProcessFunction(process_function), output_type);
+        self.graph.add_edge(
+            key_index,
+            process_index,
+            PlanEdge {
+                edge_type: EdgeType::Shuffle,
+            },
+        );
+
+        process_index
+    }
+
+    fn add_lookup_join(
+        &mut self,
+        input: Box<SqlOperator>,
+        lookup_join: LookupJoinOperator,
+    ) -> NodeIndex {
+        let input_index = self.add_sql_operator(*input);
+        let output_type = self
+            .get_plan_node(input_index)
+            .output_type
+            .with_value(lookup_join.output_struct());
+
+        if let Some(connection_id) = lookup_join.id {
+            self.saved_connections_used.push(connection_id);
+        }
+        let table_name = lookup_join.table_name.clone();
+        let plan_node_index =
+            self.insert_operator(PlanOperator::LookupJoin(lookup_join), output_type);
+        self.connections.insert(table_name, plan_node_index);
+
+        self.graph.add_edge(
+            input_index,
+            plan_node_index,
+            PlanEdge {
+                edge_type: EdgeType::Forward,
+            },
+        );
         plan_node_index
     }
 
@@ -1997,7 +2539,7 @@ pub fn get_program(
     plan_graph.find_used_udfs(&mut used_udfs);
 
     // find all types that are produced by a source or consumed by a sink
-    let connector_types: HashSet<_> = plan_graph
+    let mut connector_types: HashSet<_> = plan_graph
         .graph
         .externals(Direction::Incoming)
         .chain(
@@ -2015,6 +2557,19 @@ pub fn get_program(
         .map(|t| t.struct_name())
         .collect();
 
+    // rows read by lookup joins are deserialized from the lookup connector
+    connector_types.extend(plan_graph.graph.node_weights().flat_map(|node| {
+        match &node.operator {
+            PlanOperator::LookupJoin(lookup_join) => lookup_join
+                .lookup_struct
+                .all_structs_including_named()
+                .into_iter()
+                .map(|t| t.struct_name())
+                .collect(),
+            _ => vec![],
+        }
+    }));
+
     let types: HashSet<_> = plan_graph
         .graph
         .node_weights()
@@ -2046,10 +2601,23 @@ pub fn get_program(
             .map(|(_, v)| v),
     );
 
-    // add only the used udfs to the program
+    // add only the used udfs to the program; WASM UDFs are embedded in the generated code
+    // instead of being compiled
     let mut udfs: HashMap<String, ProgramUdf> = HashMap::new();
     used_udfs.iter().for_each(|u| {
         let udf = schema_provider.udf_defs.get(u).unwrap();
+        if let Some(wasm) = &udf.wasm {
+            let module = wasm_udf_static(u);
+            let wasm = Literal::byte_string(wasm);
+            other_defs.push(
+                quote! {
+                    static #module: arroyo_worker::operators::wasm_udf::WasmUdf =
+                        arroyo_worker::operators::wasm_udf::WasmUdf::new(#u, #wasm);
+                }
+                .to_string(),
+            );
+            return;
+        }
         udfs.insert(
             u.clone(),
             ProgramUdf {
@@ -2078,5 +2646,6 @@ pub fn get_program(
         },
         connection_ids,
         schemas,
+        used_udfs,
     })
 }
@@ -0,0 +1,237 @@
+use crate::types::{StructDef, StructField, TypeDef};
+use anyhow::{anyhow, bail};
+use arrow_schema::{DataType, Field, TimeUnit};
+use arroyo_formats::proto::{compile_schema, get_message_descriptor, get_pool};
+use proc_macro2::Ident;
+use prost_reflect::{FieldDescriptor, Kind, MessageDescriptor};
+use quote::quote;
+use std::sync::Arc;
+
+pub const ROOT_NAME: &str = "ArroyoProtobufRoot";
+
+pub fn convert_protobuf_schema(
+    name: &str,
+    schema: &str,
+    message_name: Option<&str>,
+) -> anyhow::Result<Vec<StructField>> {
+    convert_protobuf_schema_helper(name, schema, message_name, false)
+}
+
+fn convert_protobuf_schema_helper(
+    name: &str,
+    schema: &str,
+    message_name: Option<&str>,
+    for_generation: bool,
+) -> anyhow::Result<Vec<StructField>> {
+    let pool = get_pool(&compile_schema(schema)?)?;
+    let message = get_message_descriptor(&pool, message_name)?;
+
+    match message_to_typedef(name, &message, for_generation, 0)? {
+        TypeDef::StructDef(sd, _) => Ok(sd.fields),
+        TypeDef::DataType(_, _) => {
+            bail!("top-level schema must be a message")
+        }
+    }
+}
+
+pub fn get_defs(name: &str, schema: &str, message_name: Option<&str>) -> anyhow::Result<String> {
+    let fields = convert_protobuf_schema_helper(name, schema, message_name, true)?;
+
+    let sd = StructDef::new(Some(ROOT_NAME.to_string()), true, fields, None);
+    let defs: Vec<_> = sd
+        .all_structs_including_named()
+        .iter()
+        .map(|p| {
+            vec![
+                syn::parse_str(&p.def(false)).unwrap(),
+                p.generate_serializer_items(),
+            ]
+        })
+        .flatten()
+        .collect();
+
+    let mod_ident: Ident = syn::parse_str(name).unwrap();
+    Ok(quote! {
+        mod #mod_ident {
+            use super::*;
+            #(#defs)
+            *
+        }
+    }
+    .to_string())
+}
+
+// protobuf allows recursive messages, which we can't represent as SQL types
+const MAX_DEPTH: usize = 16;
+
+fn message_to_typedef(
+    source_name: &str,
+    message: &MessageDescriptor,
+    for_generation: bool,
+    depth: usize,
+) -> anyhow::Result<TypeDef> {
+    if depth > MAX_DEPTH {
+        bail!(
+            "protobuf message {} is nested too deeply; recursive messages are not supported",
+            message.full_name()
+        );
+    }
+
+    let fields = message
+        .fields()
+        .map(|f| {
+            let (ft, original) = field_to_typedef(source_name, &f, for_generation, depth)?;
+            Ok(StructField::with_rename(
+                f.name().to_string(),
+                None,
+                ft,
+                None,
+                original,
+            ))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // nested messages may share names, so we use the fully-qualified name
+    let struct_name = message.full_name().replace('.', "_");
+    let name = if for_generation {
+        // if we're generating the actual structs, we don't want to namespace
+        struct_name
+    } else {
+        // otherwise, if we're getting the schema, we need the namespacing to find the proper structs
+        format!("{}::{}", source_name, struct_name)
+    };
+
+    Ok(TypeDef::StructDef(
+        StructDef::for_name(Some(name), fields),
+        false,
+    ))
+}
+
+fn primitive_type(kind: &Kind) -> Option<DataType> {
+    Some(match kind {
+        Kind::Double => DataType::Float64,
+        Kind::Float => DataType::Float32,
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => DataType::Int32,
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => DataType::Int64,
+        Kind::Uint32 | Kind::Fixed32 => DataType::UInt32,
+        Kind::Uint64 | Kind::Fixed64 => DataType::UInt64,
+        Kind::Bool => DataType::Boolean,
+        Kind::String | Kind::Enum(_) => DataType::Utf8,
+        Kind::Bytes => DataType::Binary,
+        Kind::Message(m) if m.full_name() == "google.protobuf.Timestamp" => {
+            DataType::Timestamp(TimeUnit::Microsecond, None)
+        }
+        Kind::Message(_) => return None,
+    })
+}
+
+fn field_to_typedef(
+    source_name: &str,
+    field: &FieldDescriptor,
+    for_generation: bool,
+    depth: usize,
+) -> anyhow::Result<(TypeDef, Option<String>)> {
+    if field.is_map() {
+        // maps are passed through as json
+        return Ok((
+            TypeDef::DataType(DataType::Utf8, false),
+            Some("json".to_string()),
+        ));
+    }
+
+    let kind = field.kind();
+
+    if field.is_list() {
+        return Ok(match primitive_type(&kind) {
+            Some(dt) => (
+                TypeDef::DataType(
+                    DataType::List(Arc::new(Field::new("item", dt, false))),
+                    false,
+                ),
+                None,
+            ),
+            // lists of structs are not currently supported as SQL types
+            None => (
+                TypeDef::DataType(DataType::Utf8, false),
+                Some("json".to_string()),
+            ),
+        });
+    }
+
+    // messages always have presence, as do proto2 optional fields and proto3 explicit optionals
+    let nullable = field.supports_presence();
+
+    let typedef = match primitive_type(&kind) {
+        Some(dt) => TypeDef::DataType(dt, false),
+        None => {
+            let Kind::Message(m) = &kind else {
+                return Err(anyhow!("unexpected protobuf kind {:?}", kind));
+            };
+            message_to_typedef(source_name, m, for_generation, depth + 1)?
+        }
+    };
+
+    Ok((
+        if nullable {
+            typedef.to_optional()
+        } else {
+            typedef
+        },
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_protobuf_schema() {
+        let schema = r#"
+syntax = "proto3";
+
+import "google/protobuf/timestamp.proto";
+
+message Event {
+  string id = 1;
+  optional int64 count = 2;
+  google.protobuf.Timestamp created_at = 3;
+  repeated string tags = 4;
+  map<string, string> labels = 5;
+  User user = 6;
+
+  message User {
+    uint32 age = 1;
+  }
+}
+"#;
+
+        let fields = convert_protobuf_schema("events", schema, None).unwrap();
+        let names: Vec<_> = fields.iter().map(|f| f.name()).collect();
+        assert_eq!(
+            names,
+            vec!["id", "count", "created_at", "tags", "labels", "user"]
+        );
+
+        assert_eq!(
+            fields[0].data_type,
+            TypeDef::DataType(DataType::Utf8, false)
+        );
+        assert_eq!(
+            fields[1].data_type,
+            TypeDef::DataType(DataType::Int64, true)
+        );
+        assert_eq!(
+            fields[2].data_type,
+            TypeDef::DataType(DataType::Timestamp(TimeUnit::Microsecond, None), true)
+        );
+        assert_eq!(fields[4].original_type.as_deref(), Some("json"));
+
+        let TypeDef::StructDef(user, true) = &fields[5].data_type else {
+            panic!("expected nullable struct for message field");
+        };
+        assert_eq!(user.name.as_deref(), Some("events::Event_User"));
+
+        get_defs("events", schema, Some("Event")).unwrap();
+    }
+}
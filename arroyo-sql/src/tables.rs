@@ -3,29 +3,39 @@ use std::{collections::HashMap, time::Duration};
 
 use anyhow::{anyhow, bail, Result};
 use arrow_schema::{DataType, Field};
+use arroyo_connectors::filesystem::FileSystemTable;
+use arroyo_connectors::kafka::KafkaTable;
 use arroyo_connectors::{connector_for_type, Connection};
 use arroyo_datastream::{ConnectorOp, Operator};
 use arroyo_rpc::api_types::connections::{
-    ConnectionProfile, ConnectionSchema, ConnectionType, SchemaDefinition, SourceField,
+    ConnectionProfile, ConnectionSchema, ConnectionType, PrimitiveType, SchemaDefinition,
+    SourceField,
 };
-use arroyo_rpc::formats::{BadData, Format, Framing};
+use arroyo_rpc::formats::{AvroFormat, BadData, DeadLetterSink, Format, Framing};
+use arroyo_rpc::{primitive_to_sql, OperatorConfig};
 use datafusion::sql::sqlparser::ast::Query;
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::tokenizer::Token;
 use datafusion::{
     optimizer::{analyzer::Analyzer, optimizer::Optimizer, OptimizerContext},
     sql::{
         planner::{PlannerContext, SqlToRel},
-        sqlparser::ast::{ColumnDef, ColumnOption, Statement, Value},
+        sqlparser::ast::{
+            ColumnDef, ColumnOption, Expr, FunctionArg, FunctionArgExpr, Statement, Value,
+        },
     },
 };
+use datafusion_common::tree_node::{TreeNode, VisitRecursion};
 use datafusion_common::{config::ConfigOptions, DFField, DFSchema};
 use datafusion_expr::{
     CreateMemoryTable, CreateView, DdlStatement, DmlStatement, LogicalPlan, WriteOp,
 };
+use quote::format_ident;
 
 use crate::code_gen::{CodeGenerator, ValuePointerContext};
 use crate::expressions::CastExpression;
 use crate::external::SinkUpdateType;
-use crate::{avro, DEFAULT_IDLE_TIME};
+use crate::{avro, protobuf, DEFAULT_IDLE_TIME};
 use crate::{
     expressions::{Column, ColumnExpression, Expression, ExpressionContext},
     external::{ProcessingMode, SqlSink, SqlSource},
@@ -50,10 +60,114 @@ pub struct ConnectorTable {
     pub event_time_field: Option<String>,
     pub watermark_field: Option<String>,
     pub idle_time: Option<Duration>,
+    pub lookup: Option<LookupOptions>,
 
     pub inferred_fields: Option<Vec<DFField>>,
 }
 
+/// Configures how lookup joins against a lookup table batch and cache their requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupOptions {
+    pub cache_max_rows: u64,
+    pub cache_ttl: Duration,
+    pub batch_size: u64,
+    pub max_concurrency: u64,
+}
+
+impl Default for LookupOptions {
+    fn default() -> Self {
+        Self {
+            cache_max_rows: 10_000,
+            cache_ttl: Duration::from_secs(60),
+            batch_size: 64,
+            max_concurrency: 8,
+        }
+    }
+}
+
+impl LookupOptions {
+    /// Removes the `lookup.*` options that control batching and caching, returning None if
+    /// none of them were set
+    fn from_opts(options: &mut HashMap<String, String>) -> Result<Option<Self>> {
+        fn pull_u64(name: &str, options: &mut HashMap<String, String>) -> Result<Option<u64>> {
+            options
+                .remove(name)
+                .map(|v| {
+                    u64::from_str(&v)
+                        .map_err(|_| anyhow!("{} must be set to a non-negative number", name))
+                })
+                .transpose()
+        }
+
+        let cache_max_rows = pull_u64("lookup.cache.max_rows", options)?;
+        let cache_ttl_secs = pull_u64("lookup.cache.ttl_secs", options)?;
+        let batch_size = pull_u64("lookup.batch_size", options)?;
+        let max_concurrency = pull_u64("lookup.max_concurrency", options)?;
+
+        if cache_max_rows.is_none()
+            && cache_ttl_secs.is_none()
+            && batch_size.is_none()
+            && max_concurrency.is_none()
+        {
+            return Ok(None);
+        }
+
+        let default = Self::default();
+        Ok(Some(Self {
+            cache_max_rows: cache_max_rows.unwrap_or(default.cache_max_rows),
+            cache_ttl: cache_ttl_secs
+                .map(Duration::from_secs)
+                .unwrap_or(default.cache_ttl),
+            batch_size: batch_size.unwrap_or(default.batch_size),
+            max_concurrency: max_concurrency.unwrap_or(default.max_concurrency),
+        }))
+    }
+}
+
+/// Query-level options for how windows handle records that arrive behind the watermark, which
+/// are set with `SET <option> = <value>` statements
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowOptions {
+    // how long windows keep accepting records after the watermark has passed their end
+    pub allowed_lateness: Duration,
+    // the sink that records which are too late for their windows are written to
+    pub late_data_sink: Option<String>,
+}
+
+impl WindowOptions {
+    /// Applies a `SET` statement to the options, returning false if the statement is not one
+    pub fn try_set_from_statement(&mut self, statement: &Statement) -> Result<bool> {
+        let Statement::SetVariable {
+            variable, value, ..
+        } = statement
+        else {
+            return Ok(false);
+        };
+
+        let value = match value.as_slice() {
+            [Expr::Value(Value::Number(number, _))] => number.clone(),
+            [Expr::Value(value)] => value_to_inner_string(value)?,
+            [Expr::Identifier(ident)] => ident.value.clone(),
+            _ => bail!("SET {} requires a single value", variable),
+        };
+
+        match variable.to_string().to_lowercase().as_str() {
+            "allowed_lateness_micros" => {
+                let micros = u64::from_str(&value).map_err(|_| {
+                    anyhow!("allowed_lateness_micros must be set to a non-negative number")
+                })?;
+                self.allowed_lateness = Duration::from_micros(micros);
+            }
+            "late_data_sink" => {
+                self.late_data_sink = Some(value);
+            }
+            _ => bail!("unknown option '{}' provided in SET statement", variable),
+        }
+
+        Ok(true)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum FieldSpec {
     StructField(StructField),
@@ -61,19 +175,28 @@ pub enum FieldSpec {
         field: StructField,
         expression: Expression,
     },
+    /// A field populated by the source from the metadata of each message, like its Kafka offset
+    MetadataField {
+        field: StructField,
+        key: String,
+    },
 }
 
 impl FieldSpec {
     fn is_virtual(&self) -> bool {
         match self {
-            FieldSpec::StructField(_) => false,
+            FieldSpec::StructField(_) | FieldSpec::MetadataField { .. } => false,
             FieldSpec::VirtualField { .. } => true,
         }
     }
+    fn is_metadata(&self) -> bool {
+        matches!(self, FieldSpec::MetadataField { .. })
+    }
     fn struct_field(&self) -> &StructField {
         match self {
             FieldSpec::StructField(f) => f,
             FieldSpec::VirtualField { field, .. } => field,
+            FieldSpec::MetadataField { field, .. } => field,
         }
     }
 }
@@ -84,6 +207,286 @@ impl From<StructField> for FieldSpec {
     }
 }
 
+/// Returns the key of a metadata column, which is declared as `METADATA FROM '<key>'` and
+/// parsed as a column generated by `metadata('<key>')`
+fn metadata_key(expr: &Expr) -> Option<String> {
+    let Expr::Function(function) = expr else {
+        return None;
+    };
+
+    if !function.name.to_string().eq_ignore_ascii_case("metadata") {
+        return None;
+    }
+
+    match function.args.as_slice() {
+        [FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Value(Value::SingleQuotedString(
+            key,
+        ))))] => Some(key.clone()),
+        _ => None,
+    }
+}
+
+/// Rewrites metadata column declarations (`METADATA FROM '<key>'`), which the SQL parser does
+/// not support, into columns generated by `metadata('<key>')`
+pub(crate) fn rewrite_metadata_columns(tokens: Vec<Token>) -> Vec<Token> {
+    let is_word = |token: &Token, word: &str| match token {
+        Token::Word(w) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word),
+        _ => false,
+    };
+
+    let mut rewritten = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_word(&tokens[i], "metadata") {
+            let next: Vec<_> = tokens[i + 1..]
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| !matches!(t, Token::Whitespace(_)))
+                .take(2)
+                .collect();
+
+            if let [(_, from), (offset, Token::SingleQuotedString(key))] = next.as_slice() {
+                if is_word(from, "from") {
+                    rewritten.extend([
+                        Token::make_keyword("GENERATED"),
+                        Token::make_keyword("ALWAYS"),
+                        Token::make_keyword("AS"),
+                        Token::LParen,
+                        Token::make_word("metadata", None),
+                        Token::LParen,
+                        Token::SingleQuotedString(key.clone()),
+                        Token::RParen,
+                        Token::RParen,
+                    ]);
+                    i += offset + 2;
+                    continue;
+                }
+            }
+        }
+
+        rewritten.push(tokens[i].clone());
+        i += 1;
+    }
+
+    rewritten
+}
+
+/// Removes the `FOR SYSTEM_TIME AS OF <column>` clauses that mark temporal joins, which the SQL
+/// planner does not support, returning a map from the (lowercased) names of the tables they were
+/// applied to onto the columns they named. The versions of those tables are selected by the event
+/// time of the other side of the join, so the planner checks that the column is its event time
+/// field.
+pub(crate) fn extract_temporal_tables(tokens: Vec<Token>) -> (Vec<Token>, HashMap<String, String>) {
+    let is_word = |token: &Token, word: &str| match token {
+        Token::Word(w) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word),
+        _ => false,
+    };
+
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut tables = HashMap::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_word(&tokens[i], "for") {
+            let next: Vec<_> = tokens[i + 1..]
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| !matches!(t, Token::Whitespace(_)))
+                .take(3)
+                .collect();
+
+            let table = rewritten
+                .iter()
+                .rev()
+                .find(|t| !matches!(t, Token::Whitespace(_)));
+
+            if let ([(_, system_time), (_, as_), (of_offset, of)], Some(Token::Word(table))) =
+                (next.as_slice(), table)
+            {
+                if is_word(system_time, "system_time") && is_word(as_, "as") && is_word(of, "of") {
+                    // take the column the versions are selected as of, which may be qualified
+                    let mut end = i + 1 + of_offset + 1;
+                    while matches!(tokens.get(end), Some(Token::Whitespace(_))) {
+                        end += 1;
+                    }
+                    if let Some(Token::Word(column)) = tokens.get(end) {
+                        let mut column = column;
+                        end += 1;
+                        while let (Some(Token::Period), Some(Token::Word(field))) =
+                            (tokens.get(end), tokens.get(end + 1))
+                        {
+                            column = field;
+                            end += 2;
+                        }
+
+                        let column = match column.quote_style {
+                            Some(_) => column.value.clone(),
+                            None => column.value.to_lowercase(),
+                        };
+                        tables.insert(table.value.to_lowercase(), column);
+                        i = end;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        rewritten.push(tokens[i].clone());
+        i += 1;
+    }
+
+    (rewritten, tables)
+}
+
+/// Rewrites the calls of table functions in `CROSS JOIN LATERAL <function>(<args>) [AS <alias>]`
+/// (or `, LATERAL ...`), which the SQL planner does not support, into inner joins against
+/// placeholder tables with the rows of the functions, returning the placeholders along with the
+/// functions they stand for.
+///
+/// The joins are on a call of a marker function with the same name as the placeholder, which
+/// takes the arguments of the table function and a column of its rows. This keeps the columns
+/// that the arguments are computed from in the plan, and the call from being pushed into its input.
+pub(crate) fn rewrite_table_functions(
+    tokens: Vec<Token>,
+    schema_provider: &ArroyoSchemaProvider,
+) -> Result<(Vec<Token>, Vec<(String, String)>)> {
+    let is_word = |token: &Token, word: &str| match token {
+        Token::Word(w) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word),
+        _ => false,
+    };
+    let next_token =
+        |from: usize| (from..tokens.len()).find(|i| !matches!(tokens[*i], Token::Whitespace(_)));
+
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut calls = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_word(&tokens[i], "lateral") {
+            let name = next_token(i + 1);
+            let open = name.and_then(|name| next_token(name + 1));
+
+            if let (Some(Token::Word(function)), Some(open)) = (
+                name.map(|name| &tokens[name]),
+                open.filter(|o| tokens[*o] == Token::LParen),
+            ) {
+                let Some(def) = schema_provider
+                    .udf_defs
+                    .get(&function.value)
+                    .filter(|def| def.table_function)
+                else {
+                    bail!("'{}' is not a table function", function.value);
+                };
+                let TypeDef::StructDef(row, _) = &def.ret else {
+                    unreachable!("table functions return structs");
+                };
+
+                let mut depth = 0;
+                let close = (open..tokens.len())
+                    .find(|j| {
+                        match tokens[*j] {
+                            Token::LParen => depth += 1,
+                            Token::RParen => depth -= 1,
+                            _ => {}
+                        };
+                        depth == 0
+                    })
+                    .ok_or_else(|| {
+                        anyhow!("unclosed call of table function '{}'", function.value)
+                    })?;
+                let args = &tokens[open + 1..close];
+
+                // the rows are referred to by the function name if they aren't given an alias
+                let (alias, end) = match next_token(close + 1) {
+                    Some(as_) if is_word(&tokens[as_], "as") => match next_token(as_ + 1) {
+                        Some(alias) if matches!(tokens[alias], Token::Word(_)) => {
+                            (tokens[alias].clone(), alias + 1)
+                        }
+                        _ => bail!(
+                            "expected an alias for table function '{}' after AS",
+                            function.value
+                        ),
+                    },
+                    Some(alias) if matches!(&tokens[alias], Token::Word(w) if w.keyword == Keyword::NoKeyword) => {
+                        (tokens[alias].clone(), alias + 1)
+                    }
+                    _ => (Token::make_word(&function.value, None), close + 1),
+                };
+
+                let previous: Vec<_> = rewritten
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|(_, t)| !matches!(t, Token::Whitespace(_)))
+                    .map(|(j, _)| j)
+                    .take(2)
+                    .collect();
+                match previous.as_slice() {
+                    [join, cross]
+                        if is_word(&rewritten[*join], "join")
+                            && is_word(&rewritten[*cross], "cross") =>
+                    {
+                        rewritten[*cross] = Token::make_keyword("INNER");
+                    }
+                    [comma, ..] if rewritten[*comma] == Token::Comma => {
+                        rewritten[*comma] = Token::make_keyword("JOIN");
+                    }
+                    _ => bail!(
+                        "table function '{}' must be joined with CROSS JOIN LATERAL",
+                        function.value
+                    ),
+                }
+
+                let placeholder = format!("__udtf_{}", calls.len());
+                rewritten.extend([
+                    Token::make_word(&placeholder, None),
+                    Token::make_keyword("AS"),
+                    alias.clone(),
+                    Token::make_keyword("ON"),
+                    Token::make_word(&placeholder, None),
+                    Token::LParen,
+                ]);
+                rewritten.extend(args.iter().cloned());
+                if args.iter().any(|t| !matches!(t, Token::Whitespace(_))) {
+                    rewritten.push(Token::Comma);
+                }
+                rewritten.extend([
+                    alias,
+                    Token::Period,
+                    Token::make_word(&row.fields[0].name, Some('"')),
+                    Token::RParen,
+                ]);
+
+                calls.push((placeholder, function.value.clone()));
+                i = end;
+                continue;
+            }
+        }
+
+        rewritten.push(tokens[i].clone());
+        i += 1;
+    }
+
+    Ok((rewritten, calls))
+}
+
+fn metadata_type_matches(expected: &PrimitiveType, data_type: &TypeDef) -> bool {
+    let TypeDef::DataType(data_type, _) = data_type else {
+        return false;
+    };
+
+    match expected {
+        PrimitiveType::String | PrimitiveType::Json | PrimitiveType::Bytes => {
+            matches!(data_type, DataType::Utf8 | DataType::Binary)
+        }
+        PrimitiveType::Int32 => matches!(data_type, DataType::Int32),
+        PrimitiveType::Int64 => matches!(data_type, DataType::Int64),
+        PrimitiveType::UnixMillis
+        | PrimitiveType::UnixMicros
+        | PrimitiveType::UnixNanos
+        | PrimitiveType::DateTime => matches!(data_type, DataType::Timestamp(..)),
+        _ => false,
+    }
+}
+
 fn schema_type(name: &str, schema: &ConnectionSchema) -> Option<String> {
     schema.struct_name.as_ref().cloned().or_else(|| {
         let def = &schema.definition.as_ref()?;
@@ -91,7 +494,9 @@ fn schema_type(name: &str, schema: &ConnectionSchema) -> Option<String> {
             SchemaDefinition::JsonSchema(_) => {
                 Some(format!("{}::{}", name, json_schema::ROOT_NAME))
             }
-            SchemaDefinition::ProtobufSchema(_) => todo!(),
+            SchemaDefinition::ProtobufSchema(_) => {
+                Some(format!("{}::{}", name, protobuf::ROOT_NAME))
+            }
             SchemaDefinition::AvroSchema(_) => Some(format!("{}::{}", name, avro::ROOT_NAME)),
             SchemaDefinition::RawSchema(_) => Some("arroyo_types::RawJson".to_string()),
         }
@@ -103,8 +508,20 @@ pub fn schema_defs(name: &str, schema: &ConnectionSchema) -> Option<String> {
 
     match def {
         SchemaDefinition::JsonSchema(s) => Some(json_schema::get_defs(&name, &s).unwrap()),
-        SchemaDefinition::ProtobufSchema(_) => todo!(),
-        SchemaDefinition::AvroSchema(s) => Some(avro::get_defs(&name, &s).unwrap()),
+        SchemaDefinition::ProtobufSchema(s) => {
+            let message_name = match &schema.format {
+                Some(Format::Protobuf(proto)) => proto.message_name.as_deref(),
+                _ => None,
+            };
+            Some(protobuf::get_defs(&name, &s, message_name).unwrap())
+        }
+        SchemaDefinition::AvroSchema(s) => {
+            let format = match &schema.format {
+                Some(Format::Avro(format)) => format.clone(),
+                _ => AvroFormat::new(false, false, false),
+            };
+            Some(avro::get_defs(&name, &s, &format).unwrap())
+        }
         SchemaDefinition::RawSchema(_) => None,
     }
 }
@@ -120,15 +537,56 @@ fn produce_optimized_plan(
 
     let optimizer_config = OptimizerContext::default();
     let analyzer = Analyzer::default();
-    let optimizer = Optimizer::new();
     let analyzed_plan =
         analyzer.execute_and_check(&plan, &ConfigOptions::default(), |_plan, _rule| {})?;
+    let optimizer = if has_filtered_or_ordered_aggregates(&analyzed_plan)? {
+        // DataFusion's common subexpression elimination drops aggregates with FILTER or ORDER BY
+        // clauses from the aggregation, so it's skipped for those plans
+        Optimizer::with_rules(
+            Optimizer::new()
+                .rules
+                .into_iter()
+                .filter(|rule| rule.name() != "common_sub_expression_eliminate")
+                .collect(),
+        )
+    } else {
+        Optimizer::new()
+    };
     let plan = optimizer.optimize(&analyzed_plan, &optimizer_config, |_plan, _rule| {})?;
     Ok(plan)
 }
 
+fn has_filtered_or_ordered_aggregates(plan: &LogicalPlan) -> Result<bool> {
+    let mut found = false;
+    plan.apply(&mut |plan| {
+        if let LogicalPlan::Aggregate(aggregate) = plan {
+            for expr in &aggregate.aggr_expr {
+                expr.apply(&mut |expr| {
+                    found |= match expr {
+                        datafusion_expr::Expr::AggregateFunction(f) => {
+                            f.filter.is_some() || f.order_by.is_some()
+                        }
+                        datafusion_expr::Expr::AggregateUDF(f) => {
+                            f.filter.is_some() || f.order_by.is_some()
+                        }
+                        _ => false,
+                    };
+                    Ok(VisitRecursion::Continue)
+                })?;
+            }
+        }
+        Ok(VisitRecursion::Continue)
+    })?;
+    Ok(found)
+}
+
 impl From<Connection> for ConnectorTable {
     fn from(value: Connection) -> Self {
+        let lookup = match value.connection_type {
+            ConnectionType::Lookup => Some(LookupOptions::default()),
+            ConnectionType::Source | ConnectionType::Sink => None,
+        };
+
         ConnectorTable {
             id: value.id,
             name: value.name.clone(),
@@ -150,6 +608,7 @@ impl From<Connection> for ConnectorTable {
             event_time_field: None,
             watermark_field: None,
             idle_time: DEFAULT_IDLE_TIME,
+            lookup,
             inferred_fields: None,
         }
     }
@@ -182,13 +641,36 @@ impl ConnectorTable {
                     FieldSpec::VirtualField { .. } => {
                         unreachable!("delta lake is only a sink, can't have virtual fields")
                     }
+                    FieldSpec::MetadataField { .. } => field_spec,
                 })
                 .collect();
         }
         let connector = connector_for_type(connector)
             .ok_or_else(|| anyhow!("Unknown connector '{}'", connector))?;
 
-        let format = Format::from_opts(options).map_err(|e| anyhow!("invalid format: '{e}'"))?;
+        let mut format =
+            Format::from_opts(options).map_err(|e| anyhow!("invalid format: '{e}'"))?;
+
+        if let Some(Format::Protobuf(proto)) = &mut format {
+            let schema = match (
+                options.remove("protobuf.schema"),
+                options.remove("protobuf.schema_file"),
+            ) {
+                (Some(schema), None) => Some(schema),
+                (None, Some(path)) => Some(std::fs::read_to_string(&path).map_err(|e| {
+                    anyhow!("could not read protobuf.schema_file '{}': {}", path, e)
+                })?),
+                (Some(_), Some(_)) => {
+                    bail!("only one of protobuf.schema and protobuf.schema_file may be set")
+                }
+                (None, None) => None,
+            };
+
+            // sinks without a schema will have one generated from the output type
+            if let Some(schema) = schema {
+                proto.add_compiled_schema(arroyo_formats::proto::compile_schema(&schema)?);
+            }
+        }
 
         let bad_data =
             BadData::from_opts(options).map_err(|e| anyhow!("Invalid bad_data: '{e}'"))?;
@@ -197,7 +679,7 @@ impl ConnectorTable {
 
         let schema_fields: Result<Vec<SourceField>> = fields
             .iter()
-            .filter(|f| !f.is_virtual())
+            .filter(|f| !f.is_virtual() && !f.is_metadata())
             .map(|f| {
                 let struct_field = f.struct_field();
                 struct_field.clone().try_into().map_err(|_| {
@@ -223,11 +705,49 @@ impl ConnectorTable {
         let connection =
             connector.from_options(name, options, Some(&schema), connection_profile)?;
 
+        for field in &fields {
+            if let FieldSpec::MetadataField { field, key } = field {
+                if !matches!(connection.connection_type, ConnectionType::Source) {
+                    bail!(
+                        "metadata field '{}' is not allowed; metadata fields are only supported in sources",
+                        field.name
+                    );
+                }
+
+                let def = connector
+                    .metadata_defs()
+                    .iter()
+                    .find(|def| def.key == key.as_str())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "connector '{}' does not provide metadata '{}'",
+                            connector.name(),
+                            key
+                        )
+                    })?;
+
+                if !metadata_type_matches(&def.data_type, &field.data_type) {
+                    bail!(
+                        "metadata field '{}' must have type {}",
+                        field.name,
+                        primitive_to_sql(def.data_type.clone())
+                    );
+                }
+            }
+        }
+
         let mut table: ConnectorTable = connection.into();
         if !fields.is_empty() {
             table.fields = fields;
         }
 
+        if let Some(lookup) = LookupOptions::from_opts(options)? {
+            let ConnectionType::Lookup = table.connection_type else {
+                bail!("lookup.* options may only be set on lookup tables");
+            };
+            table.lookup = Some(lookup);
+        }
+
         table.event_time_field = options.remove("event_time_field");
         table.watermark_field = options.remove("watermark_field");
 
@@ -269,7 +789,7 @@ impl ConnectorTable {
                 .iter()
                 .map(|field| {
                     match field {
-                        FieldSpec::StructField(struct_field) => Ok((Column{relation: None, name: struct_field.name.clone()}, Expression::Column(ColumnExpression::new(struct_field.clone())))),
+                        FieldSpec::StructField(struct_field) | FieldSpec::MetadataField { field: struct_field, .. } => Ok((Column{relation: None, name: struct_field.name.clone()}, Expression::Column(ColumnExpression::new(struct_field.clone())))),
                         FieldSpec::VirtualField { field, expression } => {
                             let expression_type_def = expression.expression_type(&ValuePointerContext::new());
                             let expression_return_type = expression_type_def.as_datatype().expect("virtual fields shouldn't return structs");
@@ -367,7 +887,7 @@ impl ConnectorTable {
         }
     }
 
-    fn connector_op(&self) -> ConnectorOp {
+    pub(crate) fn connector_op(&self) -> ConnectorOp {
         ConnectorOp {
             operator: self.operator.clone(),
             config: self.config.clone(),
@@ -383,37 +903,105 @@ impl ConnectorTable {
         }
     }
 
-    pub fn as_sql_source(&self) -> Result<SqlOperator> {
+    /// For sources that write bad data to a dead-letter table, returns the operator config with
+    /// that table's config embedded, so that the source is able to write to it
+    fn resolve_dead_letter(&self, schema_provider: &ArroyoSchemaProvider) -> Result<String> {
+        let mut config: OperatorConfig = serde_json::from_str(&self.config)?;
+        let Some(BadData::DeadLetter { table, sink, .. }) = &mut config.bad_data else {
+            return Ok(self.config.clone());
+        };
+
+        let Some(Table::ConnectorTable(dead_letter)) = schema_provider.get_table(table.as_str())
+        else {
+            bail!("dead-letter table '{}' does not exist", table);
+        };
+
+        if !matches!(dead_letter.connection_type, ConnectionType::Sink) {
+            bail!("dead-letter table '{}' must be a sink", table);
+        }
+
+        let dead_letter_config: OperatorConfig = serde_json::from_str(&dead_letter.config)?;
+        let connector =
+            if serde_json::from_value::<KafkaTable>(dead_letter_config.table.clone()).is_ok() {
+                "kafka"
+            } else if serde_json::from_value::<FileSystemTable>(dead_letter_config.table).is_ok() {
+                "filesystem"
+            } else {
+                bail!(
+                    "dead-letter table '{}' must be a kafka or filesystem table",
+                    table
+                );
+            };
+
+        *sink = Some(DeadLetterSink {
+            connector: connector.to_string(),
+            config: dead_letter.config.clone(),
+        });
+
+        Ok(serde_json::to_string(&config)?)
+    }
+
+    fn struct_def(&self) -> StructDef {
+        StructDef::new(
+            self.type_name.clone(),
+            self.type_name.is_none(),
+            self.fields
+                .iter()
+                .filter_map(|field| match field {
+                    FieldSpec::StructField(struct_field)
+                    | FieldSpec::MetadataField {
+                        field: struct_field,
+                        ..
+                    } => Some(struct_field.clone()),
+                    FieldSpec::VirtualField { .. } => None,
+                })
+                .collect(),
+            self.format.clone(),
+        )
+    }
+
+    /// The type of the rows read from a lookup table
+    pub(crate) fn lookup_struct_def(&self) -> Result<StructDef> {
+        if self.has_virtual_fields() {
+            bail!("virtual fields are not supported in lookup tables");
+        }
+
+        Ok(self.struct_def())
+    }
+
+    pub fn as_sql_source(&self, schema_provider: &ArroyoSchemaProvider) -> Result<SqlOperator> {
         match self.connection_type {
             ConnectionType::Source => {}
             ConnectionType::Sink => {
                 bail!("cannot read from sink")
             }
+            ConnectionType::Lookup => {
+                bail!(
+                    "lookup table '{}' can only be read by joining against it",
+                    self.name
+                )
+            }
         };
 
         if self.is_update() && self.has_virtual_fields() {
             bail!("can't read from a source with virtual fields and update mode.")
         }
 
+        if self.type_name.is_some() && self.fields.iter().any(|f| f.is_metadata()) {
+            bail!("metadata fields are not supported for sources with a schema definition")
+        }
+
         let virtual_field_projection = self.virtual_field_projection()?;
         let timestamp_override = self.timestamp_override()?;
         let watermark_column = self.watermark_column()?;
 
         let source = SqlSource {
             id: self.id,
-            struct_def: StructDef::new(
-                self.type_name.clone(),
-                self.type_name.is_none(),
-                self.fields
-                    .iter()
-                    .filter_map(|field| match field {
-                        FieldSpec::StructField(struct_field) => Some(struct_field.clone()),
-                        FieldSpec::VirtualField { .. } => None,
-                    })
-                    .collect(),
-                self.format.clone(),
-            ),
-            operator: Operator::ConnectorSource(self.connector_op()),
+            struct_def: self.struct_def(),
+            operator: Operator::ConnectorSource(ConnectorOp {
+                config: self.resolve_dead_letter(schema_provider)?,
+                ..self.connector_op()
+            }),
             processing_mode: self.processing_mode(),
             idle_time: self.idle_time,
         };
@@ -432,6 +1020,9 @@ impl ConnectorTable {
             ConnectionType::Source => {
                 bail!("inserting into a source is not allowed")
             }
+            ConnectionType::Lookup => {
+                bail!("inserting into a lookup table is not allowed")
+            }
             ConnectionType::Sink => {}
         }
 
@@ -440,6 +1031,10 @@ impl ConnectorTable {
             bail!("virtual fields are not currently supported in sinks");
         }
 
+        if self.fields.iter().any(|f| f.is_metadata()) {
+            bail!("metadata fields are only supported in sources");
+        }
+
         let updating_type = if self.is_update() {
             SinkUpdateType::Force
         } else {
@@ -452,6 +1047,38 @@ impl ConnectorTable {
 
         if let Some(format) = &self.format {
             let output_struct: StructDef = input.return_type();
+
+            if let Format::Avro(_) = format {
+                // check that the output can be represented in avro before we generate code for it
+                let fields: Vec<Field> = output_struct
+                    .fields
+                    .iter()
+                    .map(|f| f.clone().into())
+                    .collect();
+                arroyo_formats::avro::arrow_to_avro_schema(
+                    &output_struct.struct_name_ident(),
+                    &fields.into(),
+                )
+                .map_err(|e| anyhow!("cannot write to sink '{}' as avro: {}", self.name, e))?;
+
+                avro::generate_serializer_item(
+                    &format_ident!("record"),
+                    None,
+                    None,
+                    &TypeDef::StructDef(output_struct.clone(), false),
+                )
+                .map_err(|e| anyhow!("cannot write to sink '{}' as avro: {}", self.name, e))?;
+            }
+
+            if let Format::Parquet(_) = format {
+                if !output_struct.supports_record_batches() {
+                    bail!(
+                        "cannot write to sink '{}' as parquet: it has columns with types that are not supported in parquet",
+                        self.name
+                    );
+                }
+            }
+
             // we may need to copy the record into a new struct, that has the appropriate annotations
             // for serializing into our format
             let mut projection = Projection::new(
@@ -534,10 +1161,10 @@ impl Table {
                     .iter()
                     .any(|option| matches!(option.option, ColumnOption::NotNull));
 
-                let struct_field =
+                let mut struct_field =
                     StructField::new(name, None, TypeDef::DataType(data_type, nullable));
 
-                let generating_expression = column.options.iter().find_map(|option| {
+                let mut generating_expression = column.options.iter().find_map(|option| {
                     if let ColumnOption::Generated {
                         generation_expr, ..
                     } = &option.option
@@ -547,6 +1174,13 @@ impl Table {
                         None
                     }
                 });
+
+                // metadata fields are physical fields, populated by the source
+                if let Some(key) = generating_expression.as_ref().and_then(metadata_key) {
+                    struct_field = struct_field.with_metadata_key(key);
+                    generating_expression = None;
+                }
+
                 Ok((struct_field, generating_expression))
             })
             .collect::<Result<Vec<_>>>()?;
@@ -599,6 +1233,11 @@ impl Table {
                     field: struct_field,
                     expression,
                 });
+            } else if let Some(key) = struct_field.metadata_key.clone() {
+                field_specs.push(FieldSpec::MetadataField {
+                    field: struct_field,
+                    key,
+                });
             } else {
                 field_specs.push(FieldSpec::StructField(struct_field));
             }
@@ -636,6 +1275,10 @@ impl Table {
                         bail!("Virtual fields are not supported in memory tables; instead write a query");
                     }
 
+                    if fields.iter().any(|f| f.is_metadata()) {
+                        bail!("Metadata fields are only supported in connector tables");
+                    }
+
                     if !with_map.is_empty() {
                         if connector.is_some() {
                             bail!("Memory tables do not allow with options");
@@ -763,7 +1406,7 @@ impl Table {
 
     pub fn as_sql_source(&self, builder: &mut SqlPipelineBuilder) -> Result<SqlOperator> {
         match self {
-            Table::ConnectorTable(cn) => cn.as_sql_source(),
+            Table::ConnectorTable(cn) => cn.as_sql_source(builder.schema_provider),
             Table::MemoryTable { name, .. } => Ok(builder
                 .planned_tables
                 .get(name)
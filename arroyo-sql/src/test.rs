@@ -117,6 +117,143 @@ async fn test_window_function() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn test_analytic_window_functions() {
+    for function in [
+        "RANK() OVER (PARTITION BY window ORDER BY count DESC)",
+        "DENSE_RANK() OVER (PARTITION BY window ORDER BY count DESC)",
+        "PERCENT_RANK() OVER (PARTITION BY window ORDER BY count DESC)",
+        "LAG(count) OVER (PARTITION BY window ORDER BY auction)",
+        "LEAD(count, 2, 0) OVER (PARTITION BY window ORDER BY auction)",
+        "FIRST_VALUE(auction) OVER (PARTITION BY window ORDER BY count DESC)",
+        "LAST_VALUE(auction) OVER (PARTITION BY window ORDER BY count DESC)",
+        "SUM(count) OVER (PARTITION BY window ORDER BY auction)",
+        "AVG(count) OVER (PARTITION BY window ORDER BY auction ROWS BETWEEN 2 PRECEDING AND 2 FOLLOWING)",
+        "MAX(count) OVER (PARTITION BY window)",
+    ] {
+        let sql = format!(
+            "SELECT * FROM (
+            SELECT *, {} as value
+            FROM (SELECT bid.auction as auction, count(*) as count,
+                tumble(interval '1 minute') as window
+                FROM nexmark
+                WHERE bid is not null
+                GROUP BY 1, 3)) WHERE value IS NOT NULL",
+            function
+        );
+
+        parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+            .await
+            .unwrap_or_else(|e| panic!("failed to plan {}: {}", function, e));
+    }
+
+    // only frames that contain the current row are supported
+    let sql = "SELECT *, SUM(count) OVER (
+        PARTITION BY window ORDER BY auction ROWS BETWEEN 2 PRECEDING AND 1 PRECEDING) as value
+    FROM (SELECT bid.auction as auction, count(*) as count,
+        tumble(interval '1 minute') as window
+        FROM nexmark
+        WHERE bid is not null
+        GROUP BY 1, 3)";
+
+    let err = parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "window frames must contain the current row"
+    );
+}
+
+#[tokio::test]
+async fn test_window_udf() {
+    let mut schema_provider = get_test_schema_provider();
+    schema_provider
+        .add_rust_udf(
+            "/*
+[udfs]
+window = true
+*/
+
+fn moving_average(counts: Vec<i64>) -> Vec<f64> {
+    let mut average = 0.0;
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            average += (count as f64 - average) / (i + 1) as f64;
+            average
+        })
+        .collect()
+}",
+        )
+        .unwrap();
+    assert!(schema_provider
+        .window_functions
+        .contains_key("moving_average"));
+    assert!(!schema_provider
+        .aggregate_functions
+        .contains_key("moving_average"));
+
+    let sql = "SELECT *, moving_average(count) OVER (PARTITION BY window ORDER BY auction) as value
+    FROM (SELECT bid.auction as auction, count(*) as count,
+        tumble(interval '1 minute') as window
+        FROM nexmark
+        WHERE bid is not null
+        GROUP BY 1, 3)";
+    parse_and_get_program(sql, schema_provider.clone(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    // window functions take the values of all of the rows, and return a value for each of them
+    for udf in [
+        "/*
+[udfs]
+window = true
+*/
+fn not_vec(count: i64) -> Vec<i64> { vec![count] }",
+        "/*
+[udfs]
+window = true
+*/
+fn not_vec_return(counts: Vec<i64>) -> i64 { counts.len() as i64 }",
+    ] {
+        schema_provider.add_rust_udf(udf).unwrap_err();
+    }
+}
+
+#[tokio::test]
+async fn test_wasm_udf() {
+    let wasm = b"\0asm\x01\0\0\0";
+    let mut schema_provider = get_test_schema_provider();
+    schema_provider
+        .add_wasm_udf("fn discount(price: i64, rate: Option<f64>) -> f64;", wasm)
+        .unwrap();
+
+    let sql = "SELECT discount(bid.price, 0.1) FROM nexmark WHERE bid is not null";
+    let compiled = parse_and_get_program(sql, schema_provider.clone(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    // the module is embedded in the program rather than compiled as a UDF crate
+    assert!(compiled.used_udfs.contains("discount"));
+    assert!(compiled.program.udfs.is_empty());
+    assert!(compiled
+        .program
+        .other_defs
+        .iter()
+        .any(|def| def.contains("WASM_UDF_DISCOUNT")));
+
+    for (definition, wasm) in [
+        ("fn discount(price: i64) -> f64 { 1.0 }", &wasm[..]),
+        ("fn discount(price: i64) -> f64;", &b"not wasm"[..]),
+        ("fn discount(time: SystemTime) -> f64;", &wasm[..]),
+        ("fn discount(price: i64);", &wasm[..]),
+    ] {
+        schema_provider.add_wasm_udf(definition, wasm).unwrap_err();
+    }
+}
+
 #[tokio::test]
 async fn test_no_updating_window_functions() {
     let schema_provider = get_test_schema_provider();
@@ -281,3 +418,733 @@ async fn test_no_float_join_key() {
         .await
         .unwrap_err();
 }
+
+#[tokio::test]
+async fn test_dead_letter_table() {
+    let sql = "create table source (
+         a TEXT
+    ) with (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'input',
+        format = 'json',
+        bad_data = 'dead_letter',
+        \"bad_data.table\" = 'dead_letters',
+        \"bad_data.max_per_minute\" = '100'
+    );
+
+    create table dead_letters (
+        value TEXT
+    ) with (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'sink',
+        topic = 'dead_letters',
+        format = 'raw_string'
+    );
+
+    select a from source";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    let sql = "create table source (
+         a TEXT
+    ) with (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'input',
+        format = 'json',
+        bad_data = 'dead_letter',
+        \"bad_data.table\" = 'missing'
+    );
+
+    select a from source";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn test_metadata_fields() {
+    let sql = "create table source (
+        value TEXT,
+        msg_key TEXT METADATA FROM 'key',
+        msg_partition INT METADATA FROM 'partition',
+        msg_offset BIGINT METADATA FROM 'offset',
+        msg_timestamp TIMESTAMP METADATA FROM 'timestamp'
+    ) with (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'input',
+        format = 'raw_string',
+        event_time_field = 'msg_timestamp'
+    );
+
+    select value, msg_key, msg_partition + 1, msg_offset from source";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    let sql = "create table source (
+        value TEXT,
+        msg_key TEXT METADATA FROM 'shard_id'
+    ) with (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'input',
+        format = 'raw_string'
+    );
+
+    select value, msg_key from source";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+
+    let sql = "create table source (
+        value TEXT,
+        msg_offset TEXT METADATA FROM 'offset'
+    ) with (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'input',
+        format = 'raw_string'
+    );
+
+    select value, msg_offset from source";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn test_statistical_aggregates() {
+    let sql = "
+    SELECT
+        bid.auction as auction,
+        tumble(INTERVAL '1' minute) as window,
+        stddev(bid.price) as stddev,
+        var_pop(bid.price) as variance,
+        approx_percentile_cont(bid.price, 0.99) as p99,
+        approx_median(bid.price) as median,
+        first_value(bid.bidder) as first_bidder,
+        last_value(bid.bidder) as last_bidder,
+        array_agg(bid.price) as prices,
+        string_agg(bid.channel, ',') as channels
+    FROM nexmark
+    WHERE bid is not null
+    GROUP BY 1, 2";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    let sql = "
+    SELECT bid.auction, approx_percentile_cont(bid.price, 1.5)
+    FROM nexmark
+    GROUP BY 1";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn test_approx_distinct() {
+    let sql = "
+    SELECT
+        bid.auction as auction,
+        hop(INTERVAL '1' minute, INTERVAL '1' hour) as window,
+        approx_distinct(bid.bidder) as bidders,
+        approx_distinct(bid.price * 1.5) as prices
+    FROM nexmark
+    WHERE bid is not null
+    GROUP BY 1, 2";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    let sql = "
+    SELECT bid.auction, approx_distinct(bid.bidder) as bidders
+    FROM nexmark
+    GROUP BY 1";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_aggregate_filter_and_order_by() {
+    let sql = "
+    SELECT
+        bid.auction as auction,
+        hop(INTERVAL '1' minute, INTERVAL '10' minute) as window,
+        count(*) FILTER (WHERE bid.price > 100) as expensive_bids,
+        sum(bid.price) FILTER (WHERE bid.channel = 'web') as web_volume,
+        first_value(bid.bidder ORDER BY bid.price DESC) as top_bidder,
+        last_value(bid.bidder) FILTER (WHERE bid.price > 100) as last_expensive_bidder,
+        array_agg(bid.price ORDER BY bid.bidder) FILTER (WHERE bid.price > 10) as prices
+    FROM nexmark
+    WHERE bid is not null
+    GROUP BY 1, 2";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    let sql = "
+    SELECT
+        bid.auction,
+        tumble(INTERVAL '1' minute) as window,
+        count(*) FILTER (WHERE bid.price > 100) as expensive_bids,
+        count(distinct bid.bidder) FILTER (WHERE bid.price > 100) as expensive_bidders
+    FROM nexmark
+    WHERE bid is not null
+    GROUP BY 1, 2";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_semi_and_anti_joins() {
+    let sql = "
+    SELECT auction.id, auction.seller
+    FROM nexmark
+    WHERE auction is not null AND auction.id IN (
+        SELECT bid.auction FROM nexmark WHERE bid is not null
+    )";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    let sql = "
+    SELECT auction.id, auction.seller
+    FROM nexmark
+    WHERE auction is not null AND auction.id NOT IN (
+        SELECT bid.auction FROM nexmark WHERE bid is not null
+    )";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    let sql = "
+    WITH bids as (
+        SELECT bid.auction as auction, tumble(INTERVAL '1' minute) as window, count(*) as num
+        FROM nexmark WHERE bid is not null GROUP BY 1, 2),
+    auctions as (
+        SELECT auction.id as id, tumble(INTERVAL '1' minute) as window, count(*) as num
+        FROM nexmark WHERE auction is not null GROUP BY 1, 2)
+    SELECT auctions.id, auctions.num
+    FROM auctions
+    WHERE NOT EXISTS (
+        SELECT 1 FROM bids WHERE bids.auction = auctions.id AND bids.window = auctions.window
+    )";
+
+    parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_lookup_join() {
+    let lookup_table = "create table users (
+        id TEXT NOT NULL,
+        name TEXT
+    ) with (
+        connector = 'redis',
+        address = 'redis://localhost:6379',
+        type = 'lookup',
+        lookup = 'string',
+        \"lookup.key_prefix\" = 'users:',
+        \"lookup.cache.max_rows\" = '1000',
+        format = 'json'
+    );";
+
+    for join in ["JOIN", "LEFT JOIN"] {
+        let sql = format!(
+            "{}
+            select bid.price, users.name from nexmark {} users on CAST(bid.bidder as TEXT) = users.id
+            where bid is not null",
+            lookup_table, join
+        );
+
+        parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+            .await
+            .unwrap();
+    }
+
+    // lookup tables can't be read on their own
+    let sql = format!("{} select name from users", lookup_table);
+    parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+
+    // or joined with a condition other than an equality on the key
+    let sql = format!(
+        "{} select users.name from nexmark join users on bid.extra = users.name and bid.url = users.id",
+        lookup_table
+    );
+    parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn test_temporal_join() {
+    let tables = "CREATE TABLE rates (
+        currency TEXT,
+        rate FLOAT
+    ) WITH (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'rates',
+        format = 'debezium_json'
+    );
+    CREATE TABLE orders (
+        currency TEXT,
+        amount FLOAT,
+        created_at TIMESTAMP,
+        ts TIMESTAMP
+    ) WITH (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'orders',
+        format = 'json',
+        event_time_field = 'ts'
+    );";
+
+    for join in ["JOIN", "LEFT JOIN"] {
+        let sql = format!(
+            "{}
+            SELECT o.amount * r.rate FROM orders AS o
+            {} rates FOR SYSTEM_TIME AS OF o.ts AS r ON o.currency = r.currency",
+            tables, join
+        );
+
+        let program = parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+            .await
+            .unwrap();
+        assert!(program.program.features().contains("temporal join"));
+    }
+
+    // the versioned side must be on the right of an inner or left join
+    let sql = format!(
+        "{}
+        SELECT orders.amount FROM orders
+        RIGHT JOIN rates FOR SYSTEM_TIME AS OF orders.ts ON orders.currency = rates.currency",
+        tables
+    );
+    parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+
+    // versions can only be selected as of the event time of the left side
+    let sql = format!(
+        "{}
+        SELECT orders.amount FROM orders
+        JOIN rates FOR SYSTEM_TIME AS OF orders.created_at ON orders.currency = rates.currency",
+        tables
+    );
+    let err = parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("event time field"), "{}", err);
+
+    let sql = format!(
+        "{}
+        SELECT bid.price * r.rate FROM nexmark
+        JOIN rates FOR SYSTEM_TIME AS OF nexmark.bid.datetime AS r ON bid.channel = r.currency
+        WHERE bid is not null",
+        tables
+    );
+    let err = parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("event_time_field"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_interval_join() {
+    let tables = "CREATE TABLE impressions (
+        id BIGINT,
+        ts TIMESTAMP
+    ) WITH (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'impressions',
+        format = 'json',
+        event_time_field = 'ts'
+    );
+    CREATE TABLE clicks (
+        id BIGINT,
+        ts TIMESTAMP
+    ) WITH (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'clicks',
+        format = 'json',
+        event_time_field = 'ts'
+    );";
+
+    let sql = format!(
+        "{}
+        SELECT a.id, a.ts as impression_time, b.ts as click_time FROM impressions a
+        JOIN clicks b ON a.id = b.id
+        AND b.ts BETWEEN a.ts - INTERVAL '5' MINUTE AND a.ts + INTERVAL '10' MINUTE",
+        tables
+    );
+    let program = parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+    assert!(program.program.features().contains("interval join"));
+
+    // outer joins would need to emit misses once the interval has passed, which isn't supported
+    let sql = format!(
+        "{}
+        SELECT a.id FROM impressions a
+        LEFT JOIN clicks b ON a.id = b.id
+        AND b.ts BETWEEN a.ts - INTERVAL '5' MINUTE AND a.ts + INTERVAL '10' MINUTE",
+        tables
+    );
+    parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+
+    // bounds that can never be satisfied
+    let sql = format!(
+        "{}
+        SELECT a.id FROM impressions a
+        JOIN clicks b ON a.id = b.id
+        AND b.ts > a.ts + INTERVAL '10' MINUTE AND b.ts < a.ts",
+        tables
+    );
+    parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn test_allowed_lateness() {
+    let tables = "CREATE TABLE clicks (
+        id BIGINT,
+        ts TIMESTAMP
+    ) WITH (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'source',
+        topic = 'clicks',
+        format = 'json',
+        event_time_field = 'ts'
+    );
+    CREATE TABLE late_clicks (
+        id BIGINT,
+        ts TIMESTAMP
+    ) WITH (
+        connector = 'kafka',
+        bootstrap_servers = 'localhost:9092',
+        type = 'sink',
+        topic = 'late_clicks',
+        format = 'json'
+    );";
+
+    let sql = format!(
+        "{}
+        SET allowed_lateness_micros = 60000000;
+        SET late_data_sink = 'late_clicks';
+        SELECT id, count(*) FROM clicks GROUP BY id, tumble(INTERVAL '1' minute)",
+        tables
+    );
+    let program = parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+    let features = program.program.features();
+    assert!(features.contains("allowed lateness"));
+    assert!(features.contains("late record filter"));
+
+    // late records can be written out for windows that don't allow lateness
+    let sql = format!(
+        "{}
+        SET late_data_sink = 'late_clicks';
+        SELECT count(*) FROM clicks GROUP BY session(INTERVAL '1' minute)",
+        tables
+    );
+    let program = parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+    assert!(program.program.features().contains("late record filter"));
+
+    // records stay in sliding windows until every window they fall into has closed, so the
+    // two-phase aggregator (which drops them once their slide has passed) isn't used
+    let sliding =
+        "SELECT count(*) FROM clicks GROUP BY hop(INTERVAL '10' second, INTERVAL '1' minute)";
+    let program = parse_and_get_program(
+        &format!("{}\n{}", tables, sliding),
+        get_test_schema_provider(),
+        SqlConfig::default(),
+    )
+    .await
+    .unwrap();
+    assert!(program
+        .program
+        .features()
+        .contains("sliding window aggregator"));
+    let program = parse_and_get_program(
+        &format!(
+            "{}\nSET late_data_sink = 'late_clicks';\n{}",
+            tables, sliding
+        ),
+        get_test_schema_provider(),
+        SqlConfig::default(),
+    )
+    .await
+    .unwrap();
+    let features = program.program.features();
+    assert!(features.contains("late record filter"));
+    assert!(!features.contains("sliding window aggregator"));
+
+    // late records can re-open and merge session windows
+    let sql = format!(
+        "{}
+        SET allowed_lateness_micros = 60000000;
+        SET late_data_sink = 'late_clicks';
+        SELECT id, count(*) FROM clicks GROUP BY id, session(INTERVAL '1' minute)",
+        tables
+    );
+    let program = parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+    let features = program.program.features();
+    assert!(features.contains("allowed lateness"));
+    assert!(features.contains("late record filter"));
+
+    for sql in [
+        // there are no windows for records to be late for
+        "SET late_data_sink = 'late_clicks';
+        SELECT id FROM clicks",
+        "SET late_data_sink = 'missing';
+        SELECT count(*) FROM clicks GROUP BY tumble(INTERVAL '1' minute)",
+        "SET unknown_option = 1;
+        SELECT count(*) FROM clicks GROUP BY tumble(INTERVAL '1' minute)",
+    ] {
+        parse_and_get_program(
+            &format!("{}\n{}", tables, sql),
+            get_test_schema_provider(),
+            SqlConfig::default(),
+        )
+        .await
+        .unwrap_err();
+    }
+}
+
+#[tokio::test]
+async fn test_avro_sink_types() {
+    let sink = |columns: &str| {
+        format!(
+            "CREATE TABLE avro_sink {} WITH (
+                connector = 'kafka',
+                bootstrap_servers = 'localhost:9092',
+                type = 'sink',
+                topic = 'outputs',
+                format = 'avro'
+            );",
+            if columns.is_empty() {
+                String::new()
+            } else {
+                format!("({})", columns)
+            }
+        )
+    };
+
+    for (columns, query) in [
+        (
+            "price DECIMAL(10, 2), description TEXT",
+            "SELECT CAST(bid.price AS DECIMAL(10, 2)) as price,
+                CAST(CAST(bid.price AS DECIMAL(10, 2)) AS TEXT) as description
+            FROM nexmark",
+        ),
+        (
+            "price DECIMAL(10, 2), low DECIMAL(12, 3)",
+            "SELECT CAST(bid.price AS DECIMAL(10, 2)) as price,
+                CAST(CAST(bid.price AS DECIMAL(10, 2)) AS DECIMAL(12, 3)) as low
+            FROM nexmark",
+        ),
+    ] {
+        let sql = format!("{}\nINSERT INTO avro_sink {}", sink(columns), query);
+        parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+            .await
+            .unwrap();
+    }
+
+    for (columns, query) in [
+        // times have no rust representation
+        (
+            "t TIME",
+            "SELECT CAST('12:00:00' AS TIME) as t FROM nexmark",
+        ),
+        // lists of structs are not supported
+        (
+            "",
+            "SELECT array_agg(bid) as bids FROM nexmark GROUP BY tumble(INTERVAL '1' second)",
+        ),
+    ] {
+        let sql = format!("{}\nINSERT INTO avro_sink {}", sink(columns), query);
+        parse_and_get_program(&sql, get_test_schema_provider(), SqlConfig::default())
+            .await
+            .unwrap_err();
+    }
+}
+
+#[tokio::test]
+async fn test_table_function() {
+    let mut schema_provider = get_test_schema_provider();
+
+    schema_provider
+        .add_rust_udf(
+            "struct Word { word: String, position: i64 }
+
+            fn split_words(text: String) -> Vec<Word> {
+                text.split_whitespace()
+                    .enumerate()
+                    .map(|(position, word)| Word { word: word.to_string(), position: position as i64 })
+                    .collect()
+            }",
+        )
+        .unwrap();
+    schema_provider
+        .add_rust_udf(
+            "struct Part { part: String }
+
+            async fn fetch_parts(id: i64) -> Vec<Part> {
+                vec![Part { part: id.to_string() }]
+            }",
+        )
+        .unwrap();
+    schema_provider
+        .add_rust_udf("fn my_sqr(x: i64) -> i64 { x * x }")
+        .unwrap();
+
+    let def = schema_provider.udf_defs.get("split_words").unwrap();
+    assert!(def.table_function);
+
+    let operators = |program: crate::CompiledSql| -> Vec<String> {
+        program
+            .program
+            .graph
+            .node_weights()
+            .map(|node| format!("{:?}", node.operator))
+            .collect()
+    };
+
+    let sql = "SELECT bid.auction, w.word
+    FROM nexmark CROSS JOIN LATERAL split_words(bid.extra) AS w
+    WHERE w.position > 0";
+    let program = parse_and_get_program(sql, schema_provider.clone(), SqlConfig::default())
+        .await
+        .unwrap();
+    assert!(operators(program).contains(&"flat_map<table_function>".to_string()));
+
+    let sql = "SELECT p.part FROM nexmark, LATERAL fetch_parts(bid.auction) p";
+    let program = parse_and_get_program(sql, schema_provider.clone(), SqlConfig::default())
+        .await
+        .unwrap();
+    let operators = operators(program);
+    assert!(operators.contains(&"async_map<async_table_function>".to_string()));
+    assert!(operators.contains(&"flatten<flatten>".to_string()));
+
+    for sql in [
+        // table functions don't return single values
+        "SELECT split_words(bid.extra) FROM nexmark",
+        "SELECT * FROM nexmark CROSS JOIN LATERAL my_sqr(bid.auction)",
+        "SELECT * FROM nexmark LEFT JOIN LATERAL split_words(bid.extra) w ON true",
+    ] {
+        parse_and_get_program(sql, schema_provider.clone(), SqlConfig::default())
+            .await
+            .unwrap_err();
+    }
+}
+
+#[tokio::test]
+async fn test_process_function() {
+    let mut schema_provider = get_test_schema_provider();
+
+    schema_provider
+        .add_rust_udf(
+            "use types::ProcessContext;
+            use std::time::Duration;
+
+            struct BidCount { count: i64 }
+            struct AuctionBids { auction: i64, bids: i64 }
+
+            fn count_bids(context: &mut ProcessContext<BidCount>, auction: i64) -> Vec<AuctionBids> {
+                let state = context.get_or_insert_state_with(|| BidCount { count: 0 });
+                state.count += 1;
+                if state.count == 1 {
+                    context.schedule_timer(context.timestamp() + Duration::from_secs(60));
+                }
+                vec![]
+            }
+
+            fn count_bids_on_timer(context: &mut ProcessContext<BidCount>, auction: i64) -> Vec<AuctionBids> {
+                let bids = context.clear_state().map(|state| state.count).unwrap_or_default();
+                vec![AuctionBids { auction, bids }]
+            }",
+        )
+        .unwrap();
+
+    let def = schema_provider.udf_defs.get("count_bids").unwrap();
+    assert!(def.table_function);
+    assert!(def.process_function.as_ref().unwrap().handles_timers);
+
+    let sql = "SELECT c.auction, c.bids
+    FROM nexmark CROSS JOIN LATERAL count_bids(bid.auction) AS c
+    WHERE c.bids > 10";
+    let program = parse_and_get_program(sql, schema_provider.clone(), SqlConfig::default())
+        .await
+        .unwrap();
+    assert!(program
+        .program
+        .graph
+        .node_weights()
+        .any(|node| format!("{:?}", node.operator) == "process_function<count_bids>"));
+
+    // the rows are only combined with the key of the records, so other columns can't be selected
+    let sql = "SELECT bid.price, c.bids FROM nexmark CROSS JOIN LATERAL count_bids(bid.auction) c";
+    parse_and_get_program(sql, schema_provider.clone(), SqlConfig::default())
+        .await
+        .unwrap_err();
+
+    for udf in [
+        // timer functions are only allowed alongside process functions
+        "fn my_double(x: i64) -> i64 { x * 2 }
+        fn my_double_on_timer(x: i64) -> i64 { x * 2 }",
+        // process functions are partitioned by their first argument
+        "struct S { count: i64 }
+        struct R { count: i64 }
+        fn no_key(context: &mut ProcessContext<S>) -> Vec<R> { vec![] }",
+        "struct S { count: i64 }
+        struct R { count: i64 }
+        async fn async_process(context: &mut ProcessContext<S>, key: i64) -> Vec<R> { vec![] }",
+    ] {
+        schema_provider.add_rust_udf(udf).unwrap_err();
+    }
+}
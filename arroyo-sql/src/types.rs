@@ -8,10 +8,7 @@ use std::{
 use anyhow::Result;
 use anyhow::{anyhow, bail};
 use arrow::datatypes::{DataType, IntervalMonthDayNanoType};
-use arrow::{
-    array::Decimal128Array,
-    datatypes::{Field, IntervalDayTimeType},
-};
+use arrow::datatypes::{Field, IntervalDayTimeType};
 use arrow_schema::{IntervalUnit, TimeUnit, DECIMAL128_MAX_PRECISION, DECIMAL_DEFAULT_SCALE};
 use arroyo_rpc::{
     formats::{Format, JsonFormat, TimestampFormat},
@@ -21,7 +18,7 @@ use datafusion::sql::sqlparser::ast::{DataType as SQLDataType, ExactNumberInfo,
 
 use crate::avro;
 use arroyo_rpc::api_types::connections::{
-    FieldType, PrimitiveType, SourceField, SourceFieldType, StructType,
+    DecimalType, FieldType, ListType, PrimitiveType, SourceField, SourceFieldType, StructType,
 };
 use datafusion_common::{DFField, DFSchemaRef, ScalarValue};
 use proc_macro2::{Ident, TokenStream};
@@ -332,7 +329,12 @@ impl StructDef {
             None,
             None,
             &TypeDef::StructDef(self.clone(), false),
-        );
+        )
+        .unwrap_or_else(|e| {
+            // sinks with types that can't be serialized are rejected when the query is planned
+            let message = e.to_string();
+            quote!(unreachable!(#message))
+        });
         parse_quote! {
             fn to_avro(&self, schema: &arroyo_worker::apache_avro::Schema) -> arroyo_worker::apache_avro::types::Value {
                 use arroyo_worker::apache_avro::types::Value::*;
@@ -380,10 +382,45 @@ impl StructDef {
             quote! { unimplemented!("to_raw_string is not implemented for this type") }
         };
 
-        let reader_type = self.parquet_reader_type();
+        let iterator_from_record_batch = if self.supports_record_batches() {
+            let reader_type = self.parquet_reader_type();
+            quote!(Ok(Box::new(#reader_type::new(record_batch)?)))
+        } else {
+            quote! {
+                let _ = record_batch;
+                anyhow::bail!("{} has columns that can't be read from record batches", #name)
+            }
+        };
 
         let avro_writer = self.generate_avro_writer();
 
+        let metadata_fields: Vec<_> = self
+            .fields
+            .iter()
+            .filter_map(|f| Some((f.field_ident(), f.name(), f.metadata_key.as_ref()?)))
+            .collect();
+
+        let metadata_methods = if metadata_fields.is_empty() {
+            quote!()
+        } else {
+            let names = metadata_fields.iter().map(|(_, name, _)| name);
+            let assignments = metadata_fields.iter().map(|(ident, _, key)| {
+                quote! {
+                    self.#ident = arroyo_types::FromMetadata::read(metadata, #key);
+                }
+            });
+
+            quote! {
+                fn is_metadata_field(name: &str) -> bool {
+                    matches!(name, #(#names)|*)
+                }
+
+                fn set_metadata(&mut self, metadata: &dyn arroyo_types::SourceMetadata) {
+                    #(#assignments)*
+                }
+            }
+        };
+
         Some(quote! {
             impl arroyo_formats::SchemaData for #struct_type {
                 fn name() -> &'static str {
@@ -401,10 +438,12 @@ impl StructDef {
 
                 #avro_writer
 
+                #metadata_methods
+
                 fn iterator_from_record_batch(
                     record_batch: arrow_array::RecordBatch,
                 ) -> anyhow::Result<Box<dyn Iterator<Item = Self> + Send>> {
-                    Ok(Box::new(#reader_type::new(record_batch)?))
+                    #iterator_from_record_batch
                 }
             }
         })
@@ -415,7 +454,12 @@ impl StructDef {
         let nullable_reader_type = self.parquet_nullable_reader_type();
         let struct_type = self.get_type();
         let fields = &self.fields;
-        let field_definitions: Vec<TokenStream> = fields
+        // metadata columns are set by the source, so aren't read from the record batch
+        let data_fields: Vec<_> = fields
+            .iter()
+            .filter(|field| field.metadata_key.is_none())
+            .collect();
+        let field_definitions: Vec<TokenStream> = data_fields
             .iter()
             .map(|field| {
                 let field_ident = field.field_ident();
@@ -435,7 +479,7 @@ impl StructDef {
             })
             .collect();
 
-        let array_initializations: Vec<TokenStream> = fields
+        let array_initializations: Vec<TokenStream> = data_fields
             .iter()
             .map(|field| {
                 let field_string: String = field.name();
@@ -460,6 +504,9 @@ impl StructDef {
             .iter()
             .map(|field| {
                 let field_ident = field.field_ident();
+                if field.metadata_key.is_some() {
+                    return quote!(let #field_ident = arroyo_types::FromMetadata::missing(););
+                }
                 let field_read_assignment = field.parquet_read_assigmment();
                 quote!(let #field_ident = #field_read_assignment;)
             })
@@ -544,8 +591,42 @@ impl StructDef {
         )
     }
 
+    /// Whether every field has a type that the generated record batch builders and parquet readers
+    /// can handle; structs with other fields (like lists) can't be written to or read from parquet
+    pub fn supports_record_batches(&self) -> bool {
+        self.fields.iter().all(|field| match &field.data_type {
+            TypeDef::StructDef(def, _) => def.supports_record_batches(),
+            TypeDef::DataType(data_type, _) => matches!(
+                data_type,
+                DataType::Boolean
+                    | DataType::Int8
+                    | DataType::Int16
+                    | DataType::Int32
+                    | DataType::Int64
+                    | DataType::UInt8
+                    | DataType::UInt16
+                    | DataType::UInt32
+                    | DataType::UInt64
+                    | DataType::Float16
+                    | DataType::Float32
+                    | DataType::Float64
+                    | DataType::Timestamp(
+                        TimeUnit::Millisecond | TimeUnit::Microsecond | TimeUnit::Nanosecond,
+                        None
+                    )
+                    | DataType::Utf8
+            ),
+        })
+    }
+
     pub fn generate_serializer_items(&self) -> TokenStream {
         let schema_data_impl = self.generate_schema_data();
+        if !self.supports_record_batches() {
+            return quote! {
+                #schema_data_impl
+            };
+        }
+
         let builder_items = self.generate_builder_items();
 
         let parquet_reader_items = self.generate_parquet_reader_items();
@@ -579,6 +660,8 @@ pub struct StructField {
     pub data_type: TypeDef,
     pub renamed_from: Option<String>,
     pub original_type: Option<String>,
+    /// For metadata columns, the metadata key that the source populates this field from
+    pub metadata_key: Option<String>,
 }
 
 impl TryFrom<&DFField> for StructField {
@@ -629,6 +712,7 @@ impl StructField {
             ident,
             renamed_from: Some(qualified_name),
             original_type: None,
+            metadata_key: None,
         }
     }
 
@@ -649,9 +733,15 @@ impl StructField {
             data_type,
             renamed_from,
             original_type,
+            metadata_key: None,
         }
     }
 
+    pub fn with_metadata_key(mut self, key: String) -> Self {
+        self.metadata_key = Some(key);
+        self
+    }
+
     fn parquet_read_assigmment(&self) -> TokenStream {
         let field_name = self.field_ident();
         match &self.data_type {
@@ -786,28 +876,40 @@ impl From<StructField> for Field {
     }
 }
 
+fn field_type_to_arrow(field_type: FieldType) -> DataType {
+    match field_type {
+        FieldType::Primitive(pt) => match pt {
+            PrimitiveType::Int32 => DataType::Int32,
+            PrimitiveType::Int64 => DataType::Int64,
+            PrimitiveType::UInt32 => DataType::UInt32,
+            PrimitiveType::UInt64 => DataType::UInt64,
+            PrimitiveType::F32 => DataType::Float32,
+            PrimitiveType::F64 => DataType::Float64,
+            PrimitiveType::Bool => DataType::Boolean,
+            PrimitiveType::String => DataType::Utf8,
+            PrimitiveType::Bytes => DataType::Binary,
+            PrimitiveType::UnixMillis => DataType::Timestamp(TimeUnit::Millisecond, None),
+            PrimitiveType::UnixMicros => DataType::Timestamp(TimeUnit::Microsecond, None),
+            PrimitiveType::UnixNanos => DataType::Timestamp(TimeUnit::Nanosecond, None),
+            PrimitiveType::DateTime => DataType::Timestamp(TimeUnit::Microsecond, None),
+            PrimitiveType::Json => DataType::Utf8,
+            PrimitiveType::Date => DataType::Date32,
+            PrimitiveType::Duration => DataType::Duration(TimeUnit::Microsecond),
+        },
+        FieldType::Decimal(d) => DataType::Decimal128(d.precision, d.scale),
+        FieldType::List(l) => DataType::List(Arc::new(Field::new(
+            "item",
+            field_type_to_arrow(l.items.r#type),
+            l.items_nullable,
+        ))),
+        // lists of structs are rejected when the schema is validated
+        FieldType::Struct(_) => unreachable!("structs are not supported as list items"),
+    }
+}
+
 impl From<SourceField> for StructField {
     fn from(f: SourceField) -> Self {
         let t = match f.field_type.r#type {
-            FieldType::Primitive(pt) => TypeDef::DataType(
-                match pt {
-                    PrimitiveType::Int32 => DataType::Int32,
-                    PrimitiveType::Int64 => DataType::Int64,
-                    PrimitiveType::UInt32 => DataType::UInt32,
-                    PrimitiveType::UInt64 => DataType::UInt64,
-                    PrimitiveType::F32 => DataType::Float32,
-                    PrimitiveType::F64 => DataType::Float64,
-                    PrimitiveType::Bool => DataType::Boolean,
-                    PrimitiveType::String => DataType::Utf8,
-                    PrimitiveType::Bytes => DataType::Binary,
-                    PrimitiveType::UnixMillis => DataType::Timestamp(TimeUnit::Millisecond, None),
-                    PrimitiveType::UnixMicros => DataType::Timestamp(TimeUnit::Microsecond, None),
-                    PrimitiveType::UnixNanos => DataType::Timestamp(TimeUnit::Nanosecond, None),
-                    PrimitiveType::DateTime => DataType::Timestamp(TimeUnit::Microsecond, None),
-                    PrimitiveType::Json => DataType::Utf8,
-                },
-                f.nullable,
-            ),
             FieldType::Struct(s) => TypeDef::StructDef(
                 StructDef::for_name(
                     s.name.clone(),
@@ -815,6 +917,7 @@ impl From<SourceField> for StructField {
                 ),
                 f.nullable,
             ),
+            field_type => TypeDef::DataType(field_type_to_arrow(field_type), f.nullable),
         };
 
         StructField::new(f.field_name, None, t)
@@ -961,13 +1064,8 @@ impl TypeDef {
             ScalarValue::Boolean(Some(value)) => parse_quote!(#value),
             ScalarValue::Float32(Some(value)) => parse_quote!(#value),
             ScalarValue::Float64(Some(value)) => parse_quote!(#value),
-            ScalarValue::Decimal128(Some(value), precision, scale) => parse_str(
-                &Decimal128Array::from_value(*value, 1)
-                    .with_precision_and_scale(*precision, *scale)
-                    .unwrap()
-                    .value_as_string(0),
-            )
-            .unwrap(),
+            // decimals are represented by their unscaled value
+            ScalarValue::Decimal128(Some(value), _, _) => parse_quote!(#value),
             ScalarValue::Int8(Some(value)) => parse_quote!(#value),
             ScalarValue::Int16(Some(value)) => parse_quote!(#value),
             ScalarValue::Int32(Some(value)) => parse_quote!(#value),
@@ -979,9 +1077,21 @@ impl TypeDef {
             ScalarValue::Utf8(Some(value)) | ScalarValue::LargeUtf8(Some(value)) => {
                 parse_quote!(#value.to_string())
             }
-            ScalarValue::Binary(Some(bin)) => parse_str(&format!("{:?}", bin)).unwrap(),
+            ScalarValue::Binary(Some(bin)) => parse_quote!(vec![#(#bin),*]),
             ScalarValue::LargeBinary(_) => todo!(),
-            ScalarValue::List(_, _) => todo!(),
+            ScalarValue::List(Some(values), _) => {
+                // like make_array, the items are only Options if some of them are null
+                let nullable = values.iter().any(|value| value.is_null());
+                let items = values.iter().map(|value| {
+                    let literal = Self::get_literal(value);
+                    if nullable && !value.is_null() {
+                        parse_quote!(Some(#literal))
+                    } else {
+                        literal
+                    }
+                });
+                parse_quote!(vec![#(#items),*])
+            }
             ScalarValue::Date32(Some(val)) => parse_str(&format!(
                 "std::time::UNIX_EPOCH + std::time::Duration::from_days({})",
                 val
@@ -1026,6 +1136,22 @@ impl TypeDef {
         }
     }
 
+    /// The type of the code generated for a literal by `get_literal`
+    pub fn get_literal_data_type(scalar: &ScalarValue) -> DataType {
+        match scalar {
+            ScalarValue::List(Some(values), field) => {
+                let item_type = values
+                    .iter()
+                    .find(|value| !value.is_null())
+                    .map(Self::get_literal_data_type)
+                    .unwrap_or_else(|| field.data_type().clone());
+                let nullable = values.iter().any(|value| value.is_null());
+                DataType::List(Arc::new(Field::new(field.name(), item_type, nullable)))
+            }
+            _ => scalar.get_datatype(),
+        }
+    }
+
     pub(crate) fn as_nullable(&self) -> Self {
         self.with_nullity(true)
     }
@@ -1055,18 +1181,18 @@ impl TypeDef {
             | DataType::LargeBinary
             | DataType::Utf8
             | DataType::LargeUtf8
-            | DataType::Timestamp(_, None) => Ok(TypeDef::DataType(data_type.clone(), nullable)),
+            | DataType::Timestamp(_, None)
+            | DataType::Date32
+            | DataType::Duration(_)
+            | DataType::Decimal128(_, _) => Ok(TypeDef::DataType(data_type.clone(), nullable)),
 
             DataType::Timestamp(_, Some(_))
-            | DataType::Date32
             | DataType::Date64
             | DataType::Time32(_)
             | DataType::Time64(_)
-            | DataType::Duration(_)
             | DataType::FixedSizeBinary(_)
             | DataType::Union(_, _)
             | DataType::Dictionary(_, _)
-            | DataType::Decimal128(_, _)
             | DataType::Decimal256(_, _)
             | DataType::Map(_, _)
             | DataType::RunEndEncoded(_, _)
@@ -1136,36 +1262,94 @@ impl StructField {
             });
         }
 
-        if let TypeDef::DataType(DataType::Timestamp(_, _), nullable) = self.data_type {
-            match format.as_ref().map(|t| &*t) {
-                Some(Format::Json(JsonFormat {
-                    timestamp_format: TimestampFormat::UnixMillis,
-                    ..
-                })) => {
-                    if nullable {
-                        attributes.push(quote! {
-                            #[serde(default)]
-                            #[serde(with = "arroyo_formats::json::opt_timestamp_as_millis")]
-                        });
-                    } else {
-                        attributes.push(quote! {
-                            #[serde(with = "arroyo_formats::json::timestamp_as_millis")]
-                        });
-                    }
+        if self.metadata_key.is_some() {
+            // metadata columns aren't part of the message; they're set by the source once the
+            // message has been deserialized
+            attributes.push(quote! {
+                #[serde(skip_deserializing, default = "arroyo_types::FromMetadata::missing")]
+            });
+        }
+
+        // metadata columns already have a default
+        let default = if self.nullable() && self.metadata_key.is_none() {
+            quote!(#[serde(default)])
+        } else {
+            quote!()
+        };
+
+        let is_avro = matches!(format, Some(Format::Avro(_)));
+
+        if let TypeDef::DataType(DataType::Timestamp(unit, _), nullable) = &self.data_type {
+            let module = match (format.as_ref().map(|t| &*t), unit, nullable) {
+                (
+                    Some(Format::Json(JsonFormat {
+                        timestamp_format: TimestampFormat::UnixMillis,
+                        ..
+                    })),
+                    _,
+                    true,
+                )
+                | (Some(Format::Avro(_)), TimeUnit::Millisecond, true) => {
+                    "arroyo_formats::json::opt_timestamp_as_millis"
                 }
-                _ => {
-                    if nullable {
-                        attributes.push(quote! {
-                            #[serde(default)]
-                            #[serde(with = "arroyo_formats::json::opt_timestamp_as_rfc3339")]
-                        });
-                    } else {
-                        attributes.push(quote!(
-                            #[serde(with = "arroyo_formats::json::timestamp_as_rfc3339")]
-                        ));
-                    }
+                (
+                    Some(Format::Json(JsonFormat {
+                        timestamp_format: TimestampFormat::UnixMillis,
+                        ..
+                    })),
+                    _,
+                    false,
+                )
+                | (Some(Format::Avro(_)), TimeUnit::Millisecond, false) => {
+                    "arroyo_formats::json::timestamp_as_millis"
                 }
-            }
+                (Some(Format::Avro(_)), TimeUnit::Microsecond, true) => {
+                    "arroyo_formats::avro::opt_timestamp_as_micros"
+                }
+                (Some(Format::Avro(_)), TimeUnit::Microsecond, false) => {
+                    "arroyo_formats::avro::timestamp_as_micros"
+                }
+                (_, _, true) => "arroyo_formats::json::opt_timestamp_as_rfc3339",
+                (_, _, false) => "arroyo_formats::json::timestamp_as_rfc3339",
+            };
+
+            attributes.push(quote!(#default #[serde(with = #module)]));
+        } else if let TypeDef::DataType(DataType::Date32, nullable) = &self.data_type {
+            let module = if *nullable {
+                "arroyo_formats::json::opt_date"
+            } else {
+                "arroyo_formats::json::date"
+            };
+            attributes.push(quote!(#default #[serde(with = #module)]));
+        } else if let (TypeDef::DataType(DataType::Duration(_), nullable), true) =
+            (&self.data_type, is_avro)
+        {
+            let module = if *nullable {
+                "arroyo_formats::avro::opt_duration"
+            } else {
+                "arroyo_formats::avro::duration"
+            };
+            attributes.push(quote!(#default #[serde(with = #module)]));
+        } else if let TypeDef::DataType(DataType::Binary, nullable) = &self.data_type {
+            let deserializer = if *nullable {
+                "arroyo_formats::deserialize_bytes_opt"
+            } else {
+                "arroyo_formats::deserialize_bytes"
+            };
+            attributes.push(quote!(#default #[serde(deserialize_with = #deserializer)]));
+        } else if let TypeDef::DataType(DataType::Decimal128(_, scale), nullable) = &self.data_type
+        {
+            let module = if *nullable {
+                "arroyo_formats::json::opt_decimal"
+            } else {
+                "arroyo_formats::json::decimal"
+            };
+            let serializer = format!("{}::serialize::<{{ {} }}, _>", module, scale);
+            let deserializer = format!("{}::deserialize::<{{ {} }}, _>", module, scale);
+            attributes.push(quote!(
+                #default
+                #[serde(serialize_with = #serializer, deserialize_with = #deserializer)]
+            ));
         } else if let Some("json") = self.original_type.as_ref().map(|i| i.as_str()) {
             if self.nullable() {
                 attributes.push(quote!(
@@ -1243,18 +1427,28 @@ impl StructField {
                 ))
             }
             DataType::Timestamp(_, _) => todo!(),
-            DataType::Date32 => todo!(),
-            DataType::Date64 => todo!(),
+            DataType::Date32 => quote!(arrow::datatypes::DataType::Date32),
+            DataType::Date64 => quote!(arrow::datatypes::DataType::Date64),
             DataType::Time32(_) => todo!(),
             DataType::Time64(_) => todo!(),
-            DataType::Duration(_) => todo!(),
+            DataType::Duration(time_unit) => {
+                let time_unit = format_ident!("{}", format!("{:?}", time_unit));
+                quote!(arrow::datatypes::DataType::Duration(
+                    arrow::datatypes::TimeUnit::#time_unit
+                ))
+            }
             DataType::Interval(_) => todo!(),
-            DataType::Binary => todo!(),
-            DataType::FixedSizeBinary(_) => todo!(),
-            DataType::LargeBinary => todo!(),
+            DataType::Binary => quote!(arrow::datatypes::DataType::Binary),
+            DataType::FixedSizeBinary(size) => {
+                quote!(arrow::datatypes::DataType::FixedSizeBinary(#size))
+            }
+            DataType::LargeBinary => quote!(arrow::datatypes::DataType::LargeBinary),
             DataType::Utf8 => quote!(arrow::datatypes::DataType::Utf8),
             DataType::LargeUtf8 => todo!(),
-            DataType::List(_) => todo!(),
+            DataType::List(field) => {
+                let field = Self::get_field_literal(field, parent_nullable);
+                quote!(arrow::datatypes::DataType::List(std::sync::Arc::new(#field)))
+            }
             DataType::FixedSizeList(_, _) => todo!(),
             DataType::LargeList(_) => todo!(),
             DataType::Struct(struct_fields) => {
@@ -1273,7 +1467,9 @@ impl StructField {
             }
             DataType::Union(_, _) => todo!(),
             DataType::Dictionary(_, _) => todo!(),
-            DataType::Decimal128(_, _) => todo!(),
+            DataType::Decimal128(precision, scale) => {
+                quote!(arrow::datatypes::DataType::Decimal128(#precision, #scale))
+            }
             DataType::Decimal256(_, _) => todo!(),
             DataType::Map(_, _) => todo!(),
             DataType::RunEndEncoded(_, _) => todo!(),
@@ -1356,9 +1552,9 @@ impl StructField {
             DataType::Time32(_) => todo!(),
             DataType::Time64(_) => todo!(),
             DataType::Duration(_) | DataType::Interval(_) => "std::time::Duration".to_string(),
-            DataType::Binary => todo!(),
-            DataType::FixedSizeBinary(_) => todo!(),
-            DataType::LargeBinary => todo!(),
+            DataType::Binary | DataType::FixedSizeBinary(_) | DataType::LargeBinary => {
+                "Vec<u8>".to_string()
+            }
             DataType::Utf8 => "String".to_string(),
             DataType::LargeUtf8 => todo!(),
             DataType::List(field) => {
@@ -1374,7 +1570,8 @@ impl StructField {
             DataType::Struct(_) => unreachable!(),
             DataType::Union(_, _) => todo!(),
             DataType::Dictionary(_, _) => todo!(),
-            DataType::Decimal128(_, _) => todo!(),
+            // decimals are represented by their unscaled value
+            DataType::Decimal128(_, _) => "i128".to_string(),
             DataType::Decimal256(_, _) => todo!(),
             DataType::Map(_, _) => todo!(),
             DataType::RunEndEncoded(_, _) => todo!(),
@@ -1595,9 +1792,9 @@ pub(crate) fn data_type_as_syn_type(data_type: &DataType) -> syn::Type {
         DataType::Duration(_) | DataType::Interval(_) => {
             parse_quote!(std::time::Duration)
         }
-        DataType::Binary => todo!(),
-        DataType::FixedSizeBinary(_) => todo!(),
-        DataType::LargeBinary => todo!(),
+        DataType::Binary | DataType::FixedSizeBinary(_) | DataType::LargeBinary => {
+            parse_quote!(Vec<u8>)
+        }
         DataType::Utf8 => parse_quote!(String),
         DataType::LargeUtf8 => todo!(),
         DataType::List(field) => {
@@ -1613,7 +1810,7 @@ pub(crate) fn data_type_as_syn_type(data_type: &DataType) -> syn::Type {
         DataType::Struct(_) => unreachable!(),
         DataType::Union(_, _) => todo!(),
         DataType::Dictionary(_, _) => todo!(),
-        DataType::Decimal128(_, _) => todo!(),
+        DataType::Decimal128(_, _) => parse_quote!(i128),
         DataType::Decimal256(_, _) => todo!(),
         DataType::Map(_, _) => todo!(),
         DataType::RunEndEncoded(_, _) => todo!(),
@@ -1728,26 +1925,8 @@ impl TryFrom<StructField> for SourceField {
                 (FieldType::Struct(st), name)
             }
             TypeDef::DataType(dt, _) => {
-                let pt = match dt {
-                    DataType::Boolean => Ok(PrimitiveType::Bool),
-                    DataType::Int32 => Ok(PrimitiveType::Int32),
-                    DataType::Int64 => Ok(PrimitiveType::Int64),
-                    DataType::UInt32 => Ok(PrimitiveType::UInt32),
-                    DataType::UInt64 => Ok(PrimitiveType::UInt64),
-                    DataType::Float32 => Ok(PrimitiveType::F32),
-                    DataType::Float64 => Ok(PrimitiveType::F64),
-                    DataType::Binary | DataType::LargeBinary => Ok(PrimitiveType::Bytes),
-                    DataType::Timestamp(TimeUnit::Millisecond, _) => Ok(PrimitiveType::UnixMillis),
-                    DataType::Timestamp(TimeUnit::Microsecond, _) => Ok(PrimitiveType::UnixMicros),
-                    DataType::Timestamp(TimeUnit::Nanosecond, _) => Ok(PrimitiveType::UnixNanos),
-                    DataType::Utf8 => Ok(PrimitiveType::String),
-                    dt => Err(format!("Unsupported data type {:?}", dt)),
-                }?;
-
-                (
-                    FieldType::Primitive(pt.clone()),
-                    Some(primitive_to_sql(pt).to_string()),
-                )
+                let field_type = arrow_to_source_field_type(&dt)?;
+                (field_type.r#type, field_type.sql_name)
             }
         };
 
@@ -1761,3 +1940,55 @@ impl TryFrom<StructField> for SourceField {
         })
     }
 }
+
+fn arrow_to_source_field_type(data_type: &DataType) -> Result<SourceFieldType, String> {
+    let (field_type, sql_name) = match data_type {
+        DataType::Decimal128(precision, scale) => (
+            FieldType::Decimal(DecimalType {
+                precision: *precision,
+                scale: *scale,
+            }),
+            format!("DECIMAL({}, {})", precision, scale),
+        ),
+        DataType::List(field) => {
+            let items = arrow_to_source_field_type(field.data_type())?;
+            let sql_name = format!("{}[]", items.sql_name.as_deref().unwrap_or_default());
+            (
+                FieldType::List(ListType {
+                    items: Box::new(items),
+                    items_nullable: field.is_nullable(),
+                }),
+                sql_name,
+            )
+        }
+        dt => {
+            let pt = match dt {
+                DataType::Boolean => Ok(PrimitiveType::Bool),
+                DataType::Int32 => Ok(PrimitiveType::Int32),
+                DataType::Int64 => Ok(PrimitiveType::Int64),
+                DataType::UInt32 => Ok(PrimitiveType::UInt32),
+                DataType::UInt64 => Ok(PrimitiveType::UInt64),
+                DataType::Float32 => Ok(PrimitiveType::F32),
+                DataType::Float64 => Ok(PrimitiveType::F64),
+                DataType::Binary | DataType::LargeBinary => Ok(PrimitiveType::Bytes),
+                DataType::Timestamp(TimeUnit::Millisecond, _) => Ok(PrimitiveType::UnixMillis),
+                DataType::Timestamp(TimeUnit::Microsecond, _) => Ok(PrimitiveType::UnixMicros),
+                DataType::Timestamp(TimeUnit::Nanosecond, _) => Ok(PrimitiveType::UnixNanos),
+                DataType::Utf8 => Ok(PrimitiveType::String),
+                DataType::Date32 => Ok(PrimitiveType::Date),
+                DataType::Duration(_) => Ok(PrimitiveType::Duration),
+                dt => Err(format!("Unsupported data type {:?}", dt)),
+            }?;
+
+            (
+                FieldType::Primitive(pt.clone()),
+                primitive_to_sql(pt).to_string(),
+            )
+        }
+    };
+
+    Ok(SourceFieldType {
+        r#type: field_type,
+        sql_name: Some(sql_name),
+    })
+}
@@ -1,6 +1,6 @@
 use crate::metrics::TABLE_SIZE_GAUGE;
 use crate::{BackingStore, DataOperation, StateBackend, BINCODE_CONFIG};
-use arroyo_rpc::grpc::{CheckpointMetadata, TableDescriptor, TableType};
+use arroyo_rpc::grpc::{CheckpointMetadata, TableDeleteBehavior, TableDescriptor, TableType};
 use arroyo_types::{from_micros, Data, Key, TaskInfo};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::SystemTime;
@@ -125,11 +125,15 @@ impl<K: Key, V: Data> KeyTimeMultiMapCache<K, V> {
         .await
         .expect("expect lookup to succeed")
         .expect("expect metadata for restoring from checkpoint");
-        let min_valid_time = operator_metadata
-            .min_watermark
-            .map_or(SystemTime::UNIX_EPOCH, |min_watermark| {
+        let min_valid_time = match operator_metadata.min_watermark {
+            Some(min_watermark)
+                if table_descriptor.delete_behavior()
+                    == TableDeleteBehavior::NoReadsBeforeWatermark =>
+            {
                 from_micros(min_watermark - table_descriptor.retention_micros)
-            });
+            }
+            _ => SystemTime::UNIX_EPOCH,
+        };
 
         for tuple in backing_store.get_data_tuples(table).await {
             if tuple.timestamp < min_valid_time {
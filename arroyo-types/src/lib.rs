@@ -262,6 +262,72 @@ pub fn days_since_epoch(time: SystemTime) -> i32 {
         .div_euclid(86400) as i32
 }
 
+/// Formats the unscaled value of a decimal (the representation used for decimal columns) as a
+/// decimal string with the given scale
+pub fn format_decimal(value: i128, scale: i8) -> String {
+    if scale <= 0 {
+        return if value == 0 {
+            "0".to_string()
+        } else {
+            format!("{}{}", value, "0".repeat(scale.unsigned_abs() as usize))
+        };
+    }
+
+    let scale = scale as usize;
+    let digits = format!("{:0>width$}", value.unsigned_abs(), width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    let sign = if value < 0 { "-" } else { "" };
+    format!("{}{}.{}", sign, integer, fraction)
+}
+
+/// Parses a decimal string into its unscaled value for the given scale, rounding half away from
+/// zero if it has more fractional digits than the scale. Returns None if the string is not a
+/// decimal or the value does not fit.
+pub fn parse_decimal(s: &str, scale: i8) -> Option<i128> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if (integer.is_empty() && fraction.is_empty())
+        || !integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    // split the digits into those that are kept at this scale and the first one that's dropped
+    let digits = format!("{}{}", integer, fraction);
+    let kept_len = integer.len() as isize + scale as isize;
+    let (kept, dropped) = if kept_len < 0 {
+        ("", None)
+    } else if kept_len == 0 {
+        ("", digits.chars().next())
+    } else if kept_len as usize >= digits.len() {
+        (digits.as_str(), None)
+    } else {
+        let (kept, rest) = digits.split_at(kept_len as usize);
+        (kept, rest.chars().next())
+    };
+
+    let padding = (kept_len.max(0) as usize).saturating_sub(kept.len());
+    let mut value = if kept.is_empty() {
+        0
+    } else {
+        kept.parse::<i128>()
+            .ok()?
+            .checked_mul(10i128.checked_pow(padding as u32)?)?
+    };
+    if matches!(dropped, Some('5'..='9')) {
+        value = value.checked_add(1)?;
+    }
+
+    Some(if negative { -value } else { value })
+}
+
 pub fn string_to_map(s: &str) -> Option<HashMap<String, String>> {
     if s.trim().is_empty() {
         return Some(HashMap::new());
@@ -326,16 +392,36 @@ impl UserError {
 
 #[derive(Debug, Clone)]
 pub enum SourceError {
-    BadData { details: String },
-    Other { name: String, details: String },
+    BadData {
+        details: String,
+        /// The raw bytes that could not be deserialized, if known
+        raw: Option<Vec<u8>>,
+    },
+    Other {
+        name: String,
+        details: String,
+    },
 }
 
 impl SourceError {
     pub fn bad_data(details: impl Into<String>) -> SourceError {
         SourceError::BadData {
             details: details.into(),
+            raw: None,
         }
     }
+
+    /// Attaches the raw bytes that failed to deserialize to a bad data error
+    pub fn with_raw(self, bytes: &[u8]) -> SourceError {
+        match self {
+            SourceError::BadData { details, raw: None } => SourceError::BadData {
+                details,
+                raw: Some(bytes.to_vec()),
+            },
+            e => e,
+        }
+    }
+
     pub fn other(name: impl Into<String>, details: impl Into<String>) -> SourceError {
         SourceError::Other {
             name: name.into(),
@@ -344,6 +430,115 @@ impl SourceError {
     }
 }
 
+/// A value describing a message read by a source, like its key or offset, which can be exposed
+/// in SQL as a metadata column
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    String(String),
+    Bytes(Vec<u8>),
+    Int32(i32),
+    Int64(i64),
+    Timestamp(SystemTime),
+}
+
+/// The metadata of a message read by a source, looked up by key
+pub trait SourceMetadata {
+    fn get(&self, key: &str) -> Option<MetadataValue>;
+}
+
+/// Types that metadata columns can be read into
+pub trait FromMetadata: Sized {
+    fn from_metadata(value: MetadataValue) -> Option<Self>;
+
+    /// The value used when the metadata is not available for a message
+    fn missing() -> Self;
+
+    fn read(metadata: &dyn SourceMetadata, key: &str) -> Self {
+        metadata
+            .get(key)
+            .and_then(Self::from_metadata)
+            .unwrap_or_else(Self::missing)
+    }
+}
+
+impl FromMetadata for String {
+    fn from_metadata(value: MetadataValue) -> Option<Self> {
+        match value {
+            MetadataValue::String(s) => Some(s),
+            MetadataValue::Bytes(b) => Some(String::from_utf8_lossy(&b).into_owned()),
+            _ => None,
+        }
+    }
+
+    fn missing() -> Self {
+        String::new()
+    }
+}
+
+impl FromMetadata for Vec<u8> {
+    fn from_metadata(value: MetadataValue) -> Option<Self> {
+        match value {
+            MetadataValue::String(s) => Some(s.into_bytes()),
+            MetadataValue::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn missing() -> Self {
+        vec![]
+    }
+}
+
+impl FromMetadata for i32 {
+    fn from_metadata(value: MetadataValue) -> Option<Self> {
+        match value {
+            MetadataValue::Int32(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    fn missing() -> Self {
+        0
+    }
+}
+
+impl FromMetadata for i64 {
+    fn from_metadata(value: MetadataValue) -> Option<Self> {
+        match value {
+            MetadataValue::Int32(i) => Some(i as i64),
+            MetadataValue::Int64(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    fn missing() -> Self {
+        0
+    }
+}
+
+impl FromMetadata for SystemTime {
+    fn from_metadata(value: MetadataValue) -> Option<Self> {
+        match value {
+            MetadataValue::Timestamp(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    fn missing() -> Self {
+        UNIX_EPOCH
+    }
+}
+
+impl<T: FromMetadata> FromMetadata for Option<T> {
+    fn from_metadata(value: MetadataValue) -> Option<Self> {
+        Some(T::from_metadata(value))
+    }
+
+    fn missing() -> Self {
+        None
+    }
+}
+
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Serialize, Deserialize)]
 pub enum UpdatingData<T: Data> {
     Retract(T),
@@ -376,6 +571,9 @@ pub struct Debezium<T: Data> {
     pub before: Option<T>,
     pub after: Option<T>,
     pub op: DebeziumOp,
+    /// The time (in millis since the epoch) at which the change was processed by Debezium
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ts_ms: Option<i64>,
 }
 
 // Use a shadow type to perform post-deserialization validation that the expected fields
@@ -385,6 +583,8 @@ struct DebeziumShadow<T: Data> {
     before: Option<T>,
     after: Option<T>,
     op: DebeziumOp,
+    #[serde(default)]
+    ts_ms: Option<i64>,
 }
 
 impl<T: Data> TryFrom<DebeziumShadow<T>> for Debezium<T> {
@@ -408,6 +608,7 @@ impl<T: Data> TryFrom<DebeziumShadow<T>> for Debezium<T> {
                 before: value.before,
                 after: value.after,
                 op: value.op,
+                ts_ms: value.ts_ms,
             }),
         }
     }
@@ -439,7 +640,8 @@ impl<'de> Deserialize<'de> for DebeziumOp {
     {
         let s = String::deserialize(deserializer)?;
         match s.as_str() {
-            "c" => Ok(DebeziumOp::Create),
+            // snapshot reads ("r") are treated as creates
+            "c" | "r" => Ok(DebeziumOp::Create),
             "u" => Ok(DebeziumOp::Update),
             "d" => Ok(DebeziumOp::Delete),
             _ => Err(serde::de::Error::custom(format!(
@@ -530,16 +732,19 @@ impl<T: Data> From<UpdatingData<T>> for Debezium<T> {
                 before: Some(before),
                 after: None,
                 op: DebeziumOp::Delete,
+                ts_ms: None,
             },
             UpdatingData::Update { old, new } => Debezium {
                 before: Some(old),
                 after: Some(new),
                 op: DebeziumOp::Update,
+                ts_ms: None,
             },
             UpdatingData::Append(after) => Debezium {
                 before: None,
                 after: Some(after),
                 op: DebeziumOp::Create,
+                ts_ms: None,
             },
         }
     }
@@ -551,6 +756,7 @@ impl<T: Data> From<T> for Debezium<T> {
             before: None,
             after: Some(value),
             op: DebeziumOp::Create,
+            ts_ms: None,
         }
     }
 }
@@ -578,6 +784,14 @@ pub enum JoinType {
     Right,
     /// Full Join
     Full,
+    /// Left Semi Join
+    LeftSemi,
+    /// Right Semi Join
+    RightSemi,
+    /// Left Anti Join
+    LeftAnti,
+    /// Right Anti Join
+    RightAnti,
 }
 
 pub trait RecordBatchBuilder: Default + Debug + Sync + Send {
@@ -800,6 +1014,8 @@ pub static BYTES_SENT: &str = "arroyo_worker_bytes_sent";
 pub static TX_QUEUE_SIZE: &str = "arroyo_worker_tx_queue_size";
 pub static TX_QUEUE_REM: &str = "arroyo_worker_tx_queue_rem";
 pub static DESERIALIZATION_ERRORS: &str = "arroyo_worker_deserialization_errors";
+pub static LATE_RECORDS: &str = "arroyo_worker_late_records";
+pub static DROPPED_LATE_RECORDS: &str = "arroyo_worker_dropped_late_records";
 
 #[derive(Debug, Copy, Clone, Encode, Decode)]
 pub struct CheckpointBarrier {
@@ -932,6 +1148,30 @@ mod tests {
             "u64::MAX is not in the correct range"
         );
     }
+
+    #[test]
+    fn test_decimals() {
+        for (value, scale, s) in [
+            (12345, 2, "123.45"),
+            (-5, 2, "-0.05"),
+            (7, 0, "7"),
+            (12, -2, "1200"),
+            (0, 3, "0.000"),
+        ] {
+            assert_eq!(format_decimal(value, scale), s);
+            assert_eq!(parse_decimal(s, scale), Some(value));
+        }
+
+        assert_eq!(parse_decimal("12", 2), Some(1200));
+        assert_eq!(parse_decimal(" +1.005 ", 2), Some(101));
+        assert_eq!(parse_decimal("-1.004", 2), Some(-100));
+        assert_eq!(parse_decimal(".5", 0), Some(1));
+        assert_eq!(parse_decimal("149", -2), Some(1));
+        assert_eq!(parse_decimal("1e5", 2), None);
+        assert_eq!(parse_decimal("-", 2), None);
+        assert_eq!(parse_decimal("1.2.3", 2), None);
+        assert_eq!(parse_decimal(&"9".repeat(40), 0), None);
+    }
 }
 
 #[async_trait]
@@ -939,3 +1179,77 @@ pub trait UdfContext: Sync {
     async fn init(&self) {}
     async fn close(&self) {}
 }
+
+/// The keyed state and event-time timers available to a process function UDF while it handles a
+/// record or a timer for a key
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessContext<S> {
+    state: Option<S>,
+    timers: Vec<SystemTime>,
+    timestamp: SystemTime,
+    watermark: Option<SystemTime>,
+}
+
+impl<S> ProcessContext<S> {
+    pub fn new(state: Option<S>, timestamp: SystemTime, watermark: Option<SystemTime>) -> Self {
+        Self {
+            state,
+            timers: vec![],
+            timestamp,
+            watermark,
+        }
+    }
+
+    /// The state stored for the current key, if any
+    pub fn state(&self) -> Option<&S> {
+        self.state.as_ref()
+    }
+
+    pub fn state_mut(&mut self) -> Option<&mut S> {
+        self.state.as_mut()
+    }
+
+    pub fn get_or_insert_state_with(&mut self, f: impl FnOnce() -> S) -> &mut S {
+        self.state.get_or_insert_with(f)
+    }
+
+    pub fn set_state(&mut self, state: S) {
+        self.state = Some(state);
+    }
+
+    /// Removes the state of the current key, which is no longer stored once the function returns
+    pub fn clear_state(&mut self) -> Option<S> {
+        self.state.take()
+    }
+
+    /// The event time of the record or timer being handled
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    pub fn watermark(&self) -> Option<SystemTime> {
+        self.watermark
+    }
+
+    /// Schedules a timer for the current key, which fires once the watermark passes `time`
+    pub fn schedule_timer(&mut self, time: SystemTime) {
+        self.timers.push(time);
+    }
+
+    pub fn timers(&self) -> &[SystemTime] {
+        &self.timers
+    }
+
+    pub fn map_state<S2>(self, f: impl FnOnce(S) -> S2) -> ProcessContext<S2> {
+        ProcessContext {
+            state: self.state.map(f),
+            timers: self.timers,
+            timestamp: self.timestamp,
+            watermark: self.watermark,
+        }
+    }
+
+    pub fn into_state(self) -> Option<S> {
+        self.state
+    }
+}
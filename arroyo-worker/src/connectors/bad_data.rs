@@ -0,0 +1,302 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, bail, Result};
+use arroyo_rpc::formats::{BadData, DeadLetterSink};
+use arroyo_rpc::OperatorConfig;
+use arroyo_storage::StorageProvider;
+use arroyo_types::{to_millis, TaskInfo, UserError};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+use serde_json::json;
+use tracing::info;
+
+use crate::connectors::{filesystem, kafka};
+use crate::RateLimiter;
+
+/// Where a source read a record from, reported along with records that can't be deserialized
+#[derive(Debug, Clone)]
+pub struct RecordPosition {
+    /// The partition (or shard, file, etc.) the record was read from
+    pub partition: String,
+    /// The position of the record within its partition
+    pub offset: String,
+}
+
+impl RecordPosition {
+    pub fn new(partition: impl ToString, offset: impl ToString) -> Self {
+        Self {
+            partition: partition.to_string(),
+            offset: offset.to_string(),
+        }
+    }
+}
+
+/// Limits the rate at which a source may encounter bad data before the job fails
+struct ErrorBudget {
+    max_per_minute: u32,
+    window_start: Instant,
+    count: u32,
+}
+
+impl ErrorBudget {
+    fn new(max_per_minute: u32) -> Self {
+        Self {
+            max_per_minute,
+            window_start: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// Records a bad record, returning false if the budget has been exceeded
+    fn spend(&mut self) -> bool {
+        if self.window_start.elapsed() >= Duration::from_secs(60) {
+            self.window_start = Instant::now();
+            self.count = 0;
+        }
+
+        self.count += 1;
+        self.count <= self.max_per_minute
+    }
+}
+
+enum DeadLetterQueue {
+    Kafka {
+        producer: FutureProducer,
+        topic: String,
+    },
+    FileSystem {
+        provider: StorageProvider,
+        written: usize,
+    },
+}
+
+impl DeadLetterQueue {
+    async fn new(sink: &DeadLetterSink) -> Result<Self> {
+        let config: OperatorConfig = serde_json::from_str(&sink.config)?;
+
+        match sink.connector.as_str() {
+            "kafka" => {
+                let connection: kafka::KafkaConfig = serde_json::from_value(config.connection)?;
+                let table: kafka::KafkaTable = serde_json::from_value(config.table)?;
+
+                let mut client_config = ClientConfig::new();
+                client_config.set(
+                    "bootstrap.servers",
+                    connection.bootstrap_servers.to_string(),
+                );
+                for (key, value) in kafka::client_configs(&connection, &table) {
+                    client_config.set(key, value);
+                }
+
+                Ok(DeadLetterQueue::Kafka {
+                    producer: client_config.create()?,
+                    topic: table.topic,
+                })
+            }
+            "filesystem" => {
+                let table: filesystem::FileSystemTable = serde_json::from_value(config.table)?;
+                let filesystem::TableType::Sink {
+                    write_path,
+                    storage_options,
+                    ..
+                } = table.table_type
+                else {
+                    bail!("dead-letter table must be a filesystem sink");
+                };
+
+                Ok(DeadLetterQueue::FileSystem {
+                    provider: StorageProvider::for_url_with_options(&write_path, storage_options)
+                        .await?,
+                    written: 0,
+                })
+            }
+            c => bail!("unsupported connector for dead-letter table: '{}'", c),
+        }
+    }
+
+    async fn write(
+        &mut self,
+        task_info: &TaskInfo,
+        details: &str,
+        raw: &[u8],
+        position: Option<&RecordPosition>,
+        timestamp: SystemTime,
+    ) -> Result<()> {
+        match self {
+            DeadLetterQueue::Kafka { producer, topic } => {
+                // the raw bytes are written as-is so that they can be replayed, with everything
+                // else in the headers
+                let timestamp = to_millis(timestamp).to_string();
+                let mut headers = OwnedHeaders::new()
+                    .insert(header("arroyo.error", details))
+                    .insert(header("arroyo.connector", &task_info.operator_name))
+                    .insert(header("arroyo.operator_id", &task_info.operator_id))
+                    .insert(header("arroyo.timestamp", &timestamp));
+                if let Some(position) = position {
+                    headers = headers
+                        .insert(header("arroyo.partition", &position.partition))
+                        .insert(header("arroyo.offset", &position.offset));
+                }
+
+                producer
+                    .send(
+                        FutureRecord::<(), [u8]>::to(topic)
+                            .payload(raw)
+                            .headers(headers),
+                        Duration::from_secs(30),
+                    )
+                    .await
+                    .map_err(|(e, _)| anyhow!("failed to write to dead-letter topic: {}", e))?;
+            }
+            DeadLetterQueue::FileSystem { provider, written } => {
+                let record = json!({
+                    "error": details,
+                    "connector": task_info.operator_name,
+                    "operator_id": task_info.operator_id,
+                    "task_index": task_info.task_index,
+                    "partition": position.map(|p| &p.partition),
+                    "offset": position.map(|p| &p.offset),
+                    "timestamp": to_millis(timestamp),
+                    "value": STANDARD.encode(raw),
+                });
+
+                let path = format!(
+                    "{}-{}-{}-{}.json",
+                    task_info.operator_id,
+                    task_info.task_index,
+                    to_millis(timestamp),
+                    written
+                );
+                provider.put(path, record.to_string().into_bytes()).await?;
+                *written += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn header<'a>(key: &'a str, value: &'a str) -> Header<'a, &'a str> {
+    Header {
+        key,
+        value: Some(value),
+    }
+}
+
+/// Implements a source's `bad_data` setting, tracking its error budget and writing to its
+/// dead-letter table
+pub struct BadDataHandler {
+    pub(crate) bad_data: Option<BadData>,
+    pub(crate) rate_limiter: RateLimiter,
+    budget: Option<ErrorBudget>,
+    dead_letter: Option<DeadLetterQueue>,
+}
+
+impl BadDataHandler {
+    pub fn new(bad_data: Option<BadData>) -> Self {
+        let budget = match &bad_data {
+            Some(BadData::Drop { max_per_minute })
+            | Some(BadData::DeadLetter { max_per_minute, .. }) => {
+                max_per_minute.map(ErrorBudget::new)
+            }
+            Some(BadData::Fail {}) | None => None,
+        };
+
+        Self {
+            bad_data,
+            rate_limiter: RateLimiter::new(),
+            budget,
+            dead_letter: None,
+        }
+    }
+
+    /// Counts a bad record against the error budget, failing if it has been exceeded
+    pub(crate) fn spend_budget(&mut self, details: &str) -> Result<(), UserError> {
+        let Some(budget) = &mut self.budget else {
+            return Ok(());
+        };
+
+        if budget.spend() {
+            Ok(())
+        } else {
+            Err(UserError::new(
+                "Too much invalid data",
+                format!(
+                    "more than {} records per minute could not be deserialized; last error: {}",
+                    budget.max_per_minute, details
+                ),
+            ))
+        }
+    }
+
+    /// Writes an undecodable record to the dead-letter table
+    pub(crate) async fn dead_letter(
+        &mut self,
+        task_info: &TaskInfo,
+        details: &str,
+        raw: &[u8],
+        position: Option<&RecordPosition>,
+        timestamp: SystemTime,
+    ) -> Result<(), UserError> {
+        let Some(BadData::DeadLetter { table, sink, .. }) = &self.bad_data else {
+            unreachable!("dead_letter called without a dead-letter table");
+        };
+
+        if self.dead_letter.is_none() {
+            let sink = sink.as_ref().ok_or_else(|| {
+                UserError::new(
+                    "Invalid dead-letter table",
+                    format!("dead-letter table '{}' was not resolved", table),
+                )
+            })?;
+
+            info!("Writing invalid data to dead-letter table '{}'", table);
+            self.dead_letter = Some(DeadLetterQueue::new(sink).await.map_err(|e| {
+                UserError::new(
+                    "Invalid dead-letter table",
+                    format!(
+                        "could not connect to dead-letter table '{}': {:?}",
+                        table, e
+                    ),
+                )
+            })?);
+        }
+
+        self.dead_letter
+            .as_mut()
+            .unwrap()
+            .write(task_info, details, raw, position, timestamp)
+            .await
+            .map_err(|e| UserError::new("Failed to write to dead-letter table", format!("{:?}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_budget() {
+        let mut handler = BadDataHandler::new(Some(BadData::Drop {
+            max_per_minute: Some(2),
+        }));
+
+        assert!(handler.spend_budget("bad").is_ok());
+        assert!(handler.spend_budget("bad").is_ok());
+        assert!(handler.spend_budget("bad").is_err());
+
+        // the window resets after a minute
+        handler.budget.as_mut().unwrap().window_start = Instant::now() - Duration::from_secs(61);
+        assert!(handler.spend_budget("bad").is_ok());
+
+        let mut handler = BadDataHandler::new(Some(BadData::Drop {
+            max_per_minute: None,
+        }));
+        for _ in 0..1000 {
+            assert!(handler.spend_budget("bad").is_ok());
+        }
+    }
+}
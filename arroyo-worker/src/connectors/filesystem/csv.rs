@@ -0,0 +1,232 @@
+use std::{
+    fs::File,
+    io::Write,
+    marker::PhantomData,
+    time::{Instant, SystemTime},
+};
+
+use anyhow::anyhow;
+use arroyo_formats::{DataSerializer, SchemaData};
+use arroyo_rpc::formats::{CsvFormat, CsvHeader, Format};
+
+use super::{
+    local::{CurrentFileRecovery, LocalWriter},
+    BatchBufferingWriter, FileSettings, FileSystemTable, FormatSettings, MultiPartWriterStats,
+    TableType,
+};
+
+fn serializer_from_table<D: SchemaData>(config: &FileSystemTable) -> DataSerializer<D> {
+    let TableType::Sink {
+        format_settings:
+            Some(FormatSettings::Csv {
+                delimiter,
+                quote,
+                escape,
+                include_header,
+                null_string,
+            }),
+        ..
+    } = &config.table_type
+    else {
+        unreachable!("CSV writer requires CSV format settings");
+    };
+
+    let to_char = |s: &str| {
+        s.chars()
+            .next()
+            .unwrap_or_else(|| panic!("invalid CSV format setting '{}'", s))
+    };
+
+    let default = CsvFormat::default();
+    let format = CsvFormat {
+        delimiter: to_char(delimiter),
+        quote: quote.as_deref().map(to_char).unwrap_or(default.quote),
+        escape: escape.as_deref().map(to_char),
+        header: if include_header.unwrap_or(false) {
+            CsvHeader::Use
+        } else {
+            CsvHeader::None
+        },
+        null_string: null_string.clone(),
+    };
+
+    DataSerializer::new(Format::Csv(format), None)
+}
+
+pub struct CsvWriter<D: SchemaData> {
+    current_buffer: Vec<u8>,
+    target_part_size: usize,
+    serializer: DataSerializer<D>,
+    // the header is written along with the first record, so that empty files stay empty
+    header: Option<Vec<u8>>,
+    phantom: PhantomData<D>,
+}
+
+impl<D: SchemaData> BatchBufferingWriter for CsvWriter<D> {
+    type BatchData = D;
+
+    fn new(config: &FileSystemTable) -> Self {
+        let target_part_size = if let TableType::Sink {
+            file_settings:
+                Some(FileSettings {
+                    target_part_size: Some(target_part_size),
+                    ..
+                }),
+            ..
+        } = config.table_type
+        {
+            target_part_size as usize
+        } else {
+            5 * 1024 * 1024
+        };
+        let serializer = serializer_from_table(config);
+        Self {
+            current_buffer: Vec::new(),
+            target_part_size,
+            header: serializer.header(),
+            serializer,
+            phantom: PhantomData,
+        }
+    }
+
+    fn suffix() -> String {
+        "csv".to_string()
+    }
+
+    fn add_batch_data(&mut self, data: Self::BatchData) -> anyhow::Result<Option<Vec<u8>>> {
+        if let Some(header) = self.header.take() {
+            self.current_buffer.extend(header);
+            self.current_buffer.extend(b"\n");
+        }
+        if let Some(row) = self
+            .serializer
+            .to_vec(&data)
+            .map_err(|e| anyhow!("{}: {}", e.name, e.details))?
+        {
+            self.current_buffer.extend(row);
+            self.current_buffer.extend(b"\n");
+        }
+        if self.buffer_length() > self.target_part_size {
+            Ok(Some(self.evict_current_buffer()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn buffer_length(&self) -> usize {
+        self.current_buffer.len()
+    }
+
+    fn evict_current_buffer(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.current_buffer)
+    }
+
+    fn get_trailing_bytes_for_checkpoint(&mut self) -> Option<Vec<u8>> {
+        if self.current_buffer.is_empty() {
+            None
+        } else {
+            Some(self.current_buffer.clone())
+        }
+    }
+
+    fn close(&mut self, final_batch: Option<Self::BatchData>) -> anyhow::Result<Option<Vec<u8>>> {
+        if let Some(final_batch) = final_batch {
+            if let Some(final_batch) = self.add_batch_data(final_batch)? {
+                return Ok(Some(final_batch));
+            }
+        }
+        if self.current_buffer.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(self.evict_current_buffer()))
+        }
+    }
+}
+
+pub struct CsvLocalWriter<D: SchemaData> {
+    tmp_path: String,
+    final_path: String,
+    file: File,
+    serializer: DataSerializer<D>,
+    header: Option<Vec<u8>>,
+    stats: Option<MultiPartWriterStats>,
+}
+
+impl<D: SchemaData> LocalWriter<D> for CsvLocalWriter<D> {
+    fn new(tmp_path: String, final_path: String, table_properties: &FileSystemTable) -> Self {
+        let file = File::create(&tmp_path).unwrap();
+        let serializer = serializer_from_table(table_properties);
+        CsvLocalWriter {
+            tmp_path,
+            final_path,
+            file,
+            header: serializer.header(),
+            serializer,
+            stats: None,
+        }
+    }
+
+    fn file_suffix() -> &'static str {
+        "csv"
+    }
+
+    fn write(&mut self, value: D, timestamp: SystemTime) -> anyhow::Result<()> {
+        if self.stats.is_none() {
+            self.stats = Some(MultiPartWriterStats {
+                bytes_written: 0,
+                parts_written: 0,
+                first_write_at: Instant::now(),
+                last_write_at: Instant::now(),
+                representative_timestamp: timestamp,
+            });
+        } else {
+            self.stats.as_mut().unwrap().last_write_at = Instant::now();
+        }
+        if let Some(header) = self.header.take() {
+            self.file.write_all(&header)?;
+            self.file.write_all(b"\n")?;
+        }
+        if let Some(row) = self
+            .serializer
+            .to_vec(&value)
+            .map_err(|e| anyhow!("{}: {}", e.name, e.details))?
+        {
+            self.file.write_all(&row)?;
+            self.file.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> anyhow::Result<usize> {
+        self.file.flush()?;
+        let size = self.file.metadata()?.len() as usize;
+        self.stats.as_mut().unwrap().bytes_written = size;
+        Ok(size)
+    }
+
+    fn close(&mut self) -> anyhow::Result<super::local::FilePreCommit> {
+        LocalWriter::<D>::sync(self)?;
+        Ok(super::local::FilePreCommit {
+            tmp_file: self.tmp_path.clone(),
+            destination: self.final_path.clone(),
+        })
+    }
+
+    fn checkpoint(&mut self) -> anyhow::Result<Option<CurrentFileRecovery>> {
+        let bytes_written = LocalWriter::<D>::sync(self)?;
+        if bytes_written > 0 {
+            Ok(Some(CurrentFileRecovery {
+                tmp_file: self.tmp_path.clone(),
+                bytes_written,
+                suffix: None,
+                destination: self.final_path.clone(),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn stats(&self) -> MultiPartWriterStats {
+        self.stats.clone().unwrap()
+    }
+}
@@ -74,14 +74,13 @@ impl<D: Data + Serialize> BatchBufferingWriter for JsonWriter<D> {
         "json".to_string()
     }
 
-    fn add_batch_data(&mut self, data: Self::BatchData) -> Option<Vec<u8>> {
-        self.current_buffer
-            .extend(serde_json::to_vec(&data).unwrap());
+    fn add_batch_data(&mut self, data: Self::BatchData) -> anyhow::Result<Option<Vec<u8>>> {
+        self.current_buffer.extend(serde_json::to_vec(&data)?);
         self.current_buffer.extend(b"\n");
         if self.buffer_length() > self.target_part_size {
-            Some(self.evict_current_buffer())
+            Ok(Some(self.evict_current_buffer()))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -103,16 +102,16 @@ impl<D: Data + Serialize> BatchBufferingWriter for JsonWriter<D> {
         }
     }
 
-    fn close(&mut self, final_batch: Option<Self::BatchData>) -> Option<Vec<u8>> {
+    fn close(&mut self, final_batch: Option<Self::BatchData>) -> anyhow::Result<Option<Vec<u8>>> {
         if let Some(final_batch) = final_batch {
-            if let Some(final_batch) = self.add_batch_data(final_batch) {
-                return Some(final_batch);
+            if let Some(final_batch) = self.add_batch_data(final_batch)? {
+                return Ok(Some(final_batch));
             }
         }
         if self.current_buffer.is_empty() {
-            None
+            Ok(None)
         } else {
-            Some(self.evict_current_buffer())
+            Ok(Some(self.evict_current_buffer()))
         }
     }
 }
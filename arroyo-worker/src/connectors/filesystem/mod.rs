@@ -26,6 +26,7 @@ import_types!(schema = "../connector-schemas/filesystem/table.json");
 
 use arroyo_types::*;
 pub mod arrow;
+pub mod csv;
 mod delta;
 pub mod json;
 pub mod local;
@@ -36,6 +37,7 @@ pub mod source;
 use arroyo_formats::SchemaData;
 
 use self::{
+    csv::{CsvLocalWriter, CsvWriter},
     json::{JsonLocalWriter, JsonWriter, PassThrough},
     local::{LocalFileSystemWriter, LocalWriter},
     parquet::{FixedSizeRecordBatchBuilder, ParquetLocalWriter, RecordBatchBufferingWriter},
@@ -68,6 +70,11 @@ pub type LocalParquetFileSystemSink<K, T, R> = LocalFileSystemWriter<K, T, Parqu
 
 pub type LocalJsonFileSystemSink<K, T> = LocalFileSystemWriter<K, T, JsonLocalWriter>;
 
+pub type CsvFileSystemSink<K, T> =
+    FileSystemSink<K, T, BatchMultipartWriter<PassThrough<T>, CsvWriter<T>>>;
+
+pub type LocalCsvFileSystemSink<K, T> = LocalFileSystemWriter<K, T, CsvLocalWriter<T>>;
+
 impl<K: Key, T: Data + Sync + SchemaData + Serialize, V: LocalWriter<T>>
     LocalFileSystemWriter<K, T, V>
 {
@@ -1304,11 +1311,11 @@ pub trait BatchBufferingWriter: Send {
     type BatchData;
     fn new(config: &FileSystemTable) -> Self;
     fn suffix() -> String;
-    fn add_batch_data(&mut self, data: Self::BatchData) -> Option<Vec<u8>>;
+    fn add_batch_data(&mut self, data: Self::BatchData) -> Result<Option<Vec<u8>>>;
     fn buffer_length(&self) -> usize;
     fn evict_current_buffer(&mut self) -> Vec<u8>;
     fn get_trailing_bytes_for_checkpoint(&mut self) -> Option<Vec<u8>>;
-    fn close(&mut self, final_batch: Option<Self::BatchData>) -> Option<Vec<u8>>;
+    fn close(&mut self, final_batch: Option<Self::BatchData>) -> Result<Option<Vec<u8>>>;
 }
 pub struct BatchMultipartWriter<
     BB: BatchBuilder,
@@ -1372,7 +1379,7 @@ impl<BB: BatchBuilder, BBW: BatchBufferingWriter<BatchData = BB::BatchData>> Mul
 
         if let Some(batch) = self.batch_builder.insert(value.clone()) {
             let prev_size = self.batch_buffering_writer.buffer_length();
-            if let Some(bytes) = self.batch_buffering_writer.add_batch_data(batch) {
+            if let Some(bytes) = self.batch_buffering_writer.add_batch_data(batch)? {
                 stats.bytes_written += bytes.len() - prev_size;
                 stats.parts_written += 1;
                 self.multipart_manager.write_next_part(bytes)
@@ -1442,7 +1449,7 @@ impl<BB: BatchBuilder, BBW: BatchBufferingWriter<BatchData = BB::BatchData>>
         } else {
             None
         };
-        if let Some(bytes) = self.batch_buffering_writer.close(final_batch) {
+        if let Some(bytes) = self.batch_buffering_writer.close(final_batch)? {
             self.multipart_manager.write_next_part(bytes)
         } else if self.multipart_manager.all_uploads_finished() {
             // Return a finished file future
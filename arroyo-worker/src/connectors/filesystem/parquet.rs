@@ -180,14 +180,14 @@ impl<R: RecordBatchBuilder> BatchBufferingWriter for RecordBatchBufferingWriter<
         "parquet".to_string()
     }
 
-    fn add_batch_data(&mut self, data: Self::BatchData) -> Option<Vec<u8>> {
+    fn add_batch_data(&mut self, data: Self::BatchData) -> anyhow::Result<Option<Vec<u8>>> {
         let writer = self.writer.as_mut().unwrap();
         writer.write(&data).unwrap();
         writer.flush().unwrap();
         if self.buffer_length() > self.target_part_size {
-            Some(self.evict_current_buffer())
+            Ok(Some(self.evict_current_buffer()))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -216,14 +216,14 @@ impl<R: RecordBatchBuilder> BatchBufferingWriter for RecordBatchBufferingWriter<
         Some(copied_bytes)
     }
 
-    fn close(&mut self, final_batch: Option<Self::BatchData>) -> Option<Vec<u8>> {
+    fn close(&mut self, final_batch: Option<Self::BatchData>) -> anyhow::Result<Option<Vec<u8>>> {
         let mut writer = self.writer.take().unwrap();
         if let Some(batch) = final_batch {
             writer.write(&batch).unwrap();
         }
         writer.close().unwrap();
         let buffer = self.shared_buffer.buffer.try_lock().unwrap();
-        Some(buffer.to_vec())
+        Ok(Some(buffer.to_vec()))
     }
 }
 
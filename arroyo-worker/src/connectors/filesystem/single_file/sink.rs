@@ -34,6 +34,7 @@ impl<K: Key, T: SchemaData + Serialize> FileSink<K, T> {
             file: None,
             serializer: DataSerializer::new(
                 config.format.expect("Format must be defined for FileSinks"),
+                config.framing,
             ),
             _phantom: PhantomData,
         }
@@ -69,17 +70,28 @@ impl<K: Key, T: SchemaData + Serialize> FileSink<K, T> {
         );
         if offset > 0 {
             self.file.as_mut().unwrap().set_len(offset).await.unwrap();
+        } else if let Some(header) = self.serializer.header() {
+            let file = self.file.as_mut().unwrap();
+            file.write_all(&header).await.unwrap();
+            file.write_all(b"\n").await.unwrap();
         }
     }
 
-    async fn process_element(&mut self, record: &Record<K, T>, _ctx: &mut Context<(), ()>) {
-        let Some(row) = self.serializer.to_vec(&record.value) else {
-            return;
+    async fn process_element(&mut self, record: &Record<K, T>, ctx: &mut Context<(), ()>) {
+        let row = match self.serializer.to_vec(&record.value) {
+            Ok(Some(row)) => row,
+            Ok(None) => return,
+            Err(e) => {
+                ctx.report_user_error(e).await;
+                return;
+            }
         };
-        // row as a line
         let file = self.file.as_mut().unwrap();
         file.write_all(&row).await.unwrap();
-        file.write_all(b"\n").await.unwrap();
+        if !self.serializer.has_framing() {
+            // without framing, write each row as a line
+            file.write_all(b"\n").await.unwrap();
+        }
     }
 
     async fn handle_checkpoint(
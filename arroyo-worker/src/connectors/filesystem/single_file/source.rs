@@ -1,28 +1,33 @@
 use std::{marker::PhantomData, time::SystemTime};
 
+use arroyo_formats::csv::{has_unterminated_quote, parse_header};
+use arroyo_formats::{DataDeserializer, SchemaData};
 use arroyo_macro::{source_fn, StreamNode};
+use arroyo_rpc::formats::{CsvHeader, Format};
 use arroyo_rpc::{grpc::StopMode, ControlMessage, OperatorConfig};
-use arroyo_types::{Data, Record};
-use serde::de::DeserializeOwned;
+use arroyo_types::{Data, SourceError, UserError};
 use tokio::{
     fs::File,
     io::{AsyncBufReadExt, BufReader},
 };
 use tracing::info;
 
+use crate::connectors::bad_data::{BadDataHandler, RecordPosition};
 use crate::{engine::Context, SourceFinishType};
 
 use super::SingleFileTable;
 
 #[derive(StreamNode)]
-pub struct FileSourceFunc<K: Data, T: DeserializeOwned + Data> {
+pub struct FileSourceFunc<K: Data, T: SchemaData + Data> {
     input_file: String,
     lines_read: usize,
+    deserializer: DataDeserializer<T>,
+    bad_data: BadDataHandler,
     _t: PhantomData<(K, T)>,
 }
 
 #[source_fn(out_t = T)]
-impl<K: Data, T: DeserializeOwned + Data> FileSourceFunc<K, T> {
+impl<K: Data, T: SchemaData + Data> FileSourceFunc<K, T> {
     pub fn from_config(config_str: &str) -> Self {
         let config: OperatorConfig =
             serde_json::from_str(config_str).expect("Invalid config for FileSourceFunc");
@@ -31,6 +36,13 @@ impl<K: Data, T: DeserializeOwned + Data> FileSourceFunc<K, T> {
         Self {
             input_file: table.path,
             lines_read: 0,
+            deserializer: DataDeserializer::new(
+                config
+                    .format
+                    .expect("Format must be defined for FileSourceFunc"),
+                config.framing,
+            ),
+            bad_data: BadDataHandler::new(config.bad_data),
             _t: PhantomData,
         }
     }
@@ -43,8 +55,19 @@ impl<K: Data, T: DeserializeOwned + Data> FileSourceFunc<K, T> {
     }
 
     async fn run(&mut self, ctx: &mut Context<(), T>) -> SourceFinishType {
+        match self.run_int(ctx).await {
+            Ok(r) => r,
+            Err(e) => {
+                ctx.report_error(e.name.clone(), e.details.clone()).await;
+
+                panic!("{}: {}", e.name, e.details);
+            }
+        }
+    }
+
+    async fn run_int(&mut self, ctx: &mut Context<(), T>) -> Result<SourceFinishType, UserError> {
         if ctx.task_info.task_index != 0 {
-            return SourceFinishType::Final;
+            return Ok(SourceFinishType::Final);
         }
         self.lines_read = ctx
             .state
@@ -54,20 +77,78 @@ impl<K: Data, T: DeserializeOwned + Data> FileSourceFunc<K, T> {
             .map(|v| *v)
             .unwrap_or_default();
 
-        let file = File::open(&self.input_file).await.unwrap();
+        let file = File::open(&self.input_file).await.map_err(|e| {
+            UserError::new(
+                "failed to open file",
+                format!("could not open {}: {}", self.input_file, e),
+            )
+        })?;
         let mut lines = BufReader::new(file).lines();
 
+        let csv = match &*self.deserializer.get_format() {
+            Format::Csv(csv) => Some(csv.clone()),
+            _ => None,
+        };
+
         let mut i = 0;
+        let mut pending: Option<String> = None;
+
+        while let Some(s) = lines.next_line().await.map_err(|e| {
+            UserError::new(
+                "failed to read file",
+                format!("could not read {}: {}", self.input_file, e),
+            )
+        })? {
+            let s = if let Some(csv) = &csv {
+                // quoted fields may contain newlines, so we join lines until the quotes balance
+                let s = match pending.take() {
+                    Some(prev) => format!("{}\n{}", prev, s),
+                    None => s,
+                };
+
+                if has_unterminated_quote(csv, &s) {
+                    pending = Some(s);
+                    continue;
+                }
+
+                // the header needs to be read even if we're restoring past it
+                if i == 0 && csv.header != CsvHeader::None {
+                    if csv.header == CsvHeader::Use {
+                        match parse_header(csv, s.as_bytes()) {
+                            Ok(headers) => self.deserializer.set_csv_headers(headers),
+                            Err(e) => {
+                                ctx.collect_source_record(
+                                    SystemTime::now(),
+                                    Err(SourceError::other("invalid CSV header", e)),
+                                    &mut self.bad_data,
+                                    || Some(RecordPosition::new(&self.input_file, 0)),
+                                )
+                                .await?;
+                            }
+                        }
+                    }
+                    i += 1;
+                    self.lines_read = self.lines_read.max(i);
+                    continue;
+                }
+
+                s
+            } else {
+                s
+            };
 
-        while let Some(s) = lines.next_line().await.unwrap() {
             if i < self.lines_read {
                 i += 1;
                 continue;
             }
-            let value = serde_json::from_str(&s).unwrap();
-            ctx.collector
-                .collect(Record::<(), T>::from_value(SystemTime::now(), value).unwrap())
-                .await;
+            let value = self
+                .deserializer
+                .deserialize_single(s.as_bytes())
+                .map_err(|e| e.with_raw(s.as_bytes()));
+            ctx.collect_source_record(SystemTime::now(), value, &mut self.bad_data, || {
+                Some(RecordPosition::new(&self.input_file, i))
+            })
+            .await?;
 
             self.lines_read += 1;
             i += 1;
@@ -82,7 +163,7 @@ impl<K: Data, T: DeserializeOwned + Data> FileSourceFunc<K, T> {
                         .await;
                     // checkpoint our state
                     if self.checkpoint(c, ctx).await {
-                        return SourceFinishType::Immediate;
+                        return Ok(SourceFinishType::Immediate);
                     }
                 }
                 Some(ControlMessage::Stop { mode }) => {
@@ -90,10 +171,10 @@ impl<K: Data, T: DeserializeOwned + Data> FileSourceFunc<K, T> {
 
                     match mode {
                         StopMode::Graceful => {
-                            return SourceFinishType::Graceful;
+                            return Ok(SourceFinishType::Graceful);
                         }
                         StopMode::Immediate => {
-                            return SourceFinishType::Immediate;
+                            return Ok(SourceFinishType::Immediate);
                         }
                     }
                 }
@@ -104,6 +185,6 @@ impl<K: Data, T: DeserializeOwned + Data> FileSourceFunc<K, T> {
             }
         }
         info!("file source finished");
-        SourceFinishType::Final
+        Ok(SourceFinishType::Final)
     }
 }
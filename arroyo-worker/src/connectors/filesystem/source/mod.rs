@@ -22,24 +22,42 @@ use tokio_stream::wrappers::LinesStream;
 use tokio_stream::Stream;
 use tracing::{info, warn};
 
-use arroyo_formats::{DataDeserializer, SchemaData};
+use arroyo_formats::csv::{has_unterminated_quote, parse_header};
+use arroyo_formats::{DataDeserializer, FrameReader, SchemaData};
 use arroyo_macro::{source_fn, StreamNode};
-use arroyo_rpc::formats::BadData;
+use arroyo_rpc::formats::{CsvHeader, Format, FramingMethod};
 use arroyo_rpc::{grpc::StopMode, ControlMessage, OperatorConfig};
 use arroyo_storage::StorageProvider;
-use arroyo_types::{Data, SourceError, UserError};
+use arroyo_types::{Data, MetadataValue, SourceError, SourceMetadata, UserError};
 use typify::import_types;
 
-use crate::{engine::Context, RateLimiter, SourceFinishType};
+use crate::connectors::bad_data::{BadDataHandler, RecordPosition};
+use crate::{engine::Context, SourceFinishType};
 
 import_types!(schema = "../connector-schemas/filesystem/table.json");
 
+/// Exposes the path of the file a record was read from and its position within it to metadata
+/// columns
+struct FileMetadata<'a> {
+    file_path: &'a str,
+    line_number: i64,
+}
+
+impl<'a> SourceMetadata for FileMetadata<'a> {
+    fn get(&self, key: &str) -> Option<MetadataValue> {
+        match key {
+            "file_path" => Some(MetadataValue::String(self.file_path.to_string())),
+            "line_number" => Some(MetadataValue::Int64(self.line_number)),
+            _ => None,
+        }
+    }
+}
+
 #[derive(StreamNode)]
 pub struct FileSystemSourceFunc<K: Data, T: SchemaData + Data> {
     table: TableType,
     deserializer: DataDeserializer<T>,
-    bad_data: Option<BadData>,
-    rate_limiter: RateLimiter,
+    bad_data: BadDataHandler,
     file_states: HashMap<String, FileReadState>,
     _t: PhantomData<(K, T)>,
 }
@@ -64,8 +82,7 @@ impl<K: Data, T: SchemaData> FileSystemSourceFunc<K, T> {
         Self {
             table: table.table_type,
             deserializer: DataDeserializer::new(format, config.framing),
-            bad_data: config.bad_data,
-            rate_limiter: RateLimiter::new(),
+            bad_data: BadDataHandler::new(config.bad_data),
             file_states: HashMap::new(),
             _t: PhantomData,
         }
@@ -188,23 +205,48 @@ impl<K: Data, T: SchemaData> FileSystemSourceFunc<K, T> {
         path: String,
     ) -> Result<Box<dyn Stream<Item = Result<T, SourceError>> + Unpin + Send>, UserError> {
         let format = self.deserializer.get_format().clone();
-        match *format {
-            arroyo_rpc::formats::Format::Json(_) => {
+
+        if let Some(framing) = self.deserializer.get_framing() {
+            if !matches!(framing.method, FramingMethod::Newline(_))
+                && !matches!(*format, Format::Parquet(_))
+            {
+                // records in framed files can't be read line-by-line, so they're split as the
+                // file is read
+                let frames = FrameReader::new(
+                    framing,
+                    BufReader::new(self.get_reader(storage_provider, path).await),
+                );
                 let deserializer = self.deserializer.clone();
-                let stream_reader = storage_provider.get_as_stream(path).await.unwrap();
 
-                let compression_reader: Box<dyn AsyncRead + Unpin + Send> =
-                    match self.get_compression_format() {
-                        CompressionFormat::Zstd => {
-                            Box::new(ZstdDecoder::new(BufReader::new(stream_reader)))
-                        }
-                        CompressionFormat::Gzip => {
-                            Box::new(GzipDecoder::new(BufReader::new(stream_reader)))
+                let records =
+                    futures::stream::unfold(Some((frames, deserializer)), |state| async move {
+                        let (mut frames, mut deserializer) = state?;
+                        match frames.next_frame().await {
+                            Ok(Some(frame)) => {
+                                let records = deserializer.deserialize_frame(&frame).await;
+                                Some((records, Some((frames, deserializer))))
+                            }
+                            Ok(None) => None,
+                            // stop reading the file after an error
+                            Err(err) => Some((
+                                vec![Err(SourceError::other(
+                                    "could not read record from file",
+                                    err.to_string(),
+                                ))],
+                                None,
+                            )),
                         }
-                        CompressionFormat::None => Box::new(BufReader::new(stream_reader)),
-                    };
-                // use line iterators
-                let lines = LinesStream::new(BufReader::new(compression_reader).lines());
+                    })
+                    .flat_map(futures::stream::iter);
+
+                return Ok(Box::new(Box::pin(records)));
+            }
+        }
+
+        match *format {
+            arroyo_rpc::formats::Format::Json(_) => {
+                let deserializer = self.deserializer.clone();
+                let lines = self.get_lines(storage_provider, path).await;
                 let x = Box::new(lines.map(move |res| match res {
                     Ok(line) => deserializer.deserialize_single(line.as_bytes()),
                     Err(err) => Err(SourceError::other(
@@ -215,7 +257,68 @@ impl<K: Data, T: SchemaData> FileSystemSourceFunc<K, T> {
                     as Box<dyn Stream<Item = Result<T, SourceError>> + Unpin + Send>;
                 Ok(x as Box<dyn Stream<Item = Result<T, SourceError>> + Unpin + Send>)
             }
+            arroyo_rpc::formats::Format::Csv(ref csv) => {
+                let csv = csv.clone();
+                let mut deserializer = self.deserializer.clone();
+                let lines = self.get_lines(storage_provider, path).await;
+
+                let mut pending: Option<String> = None;
+                let mut header_read = csv.header == CsvHeader::None;
+                let x = Box::new(lines.filter_map(move |res| {
+                    let line = match res {
+                        Ok(line) => line,
+                        Err(err) => {
+                            return ready(Some(Err(SourceError::other(
+                                "could not read line from stream",
+                                err.to_string(),
+                            ))))
+                        }
+                    };
+
+                    // quoted fields may contain newlines, so we join lines until the quotes balance
+                    let line = match pending.take() {
+                        Some(mut prev) => {
+                            prev.push('\n');
+                            prev.push_str(&line);
+                            prev
+                        }
+                        None => line,
+                    };
+
+                    if has_unterminated_quote(&csv, &line) {
+                        pending = Some(line);
+                        return ready(None);
+                    }
+
+                    if line.is_empty() {
+                        return ready(None);
+                    }
+
+                    if !header_read {
+                        header_read = true;
+                        if csv.header == CsvHeader::Use {
+                            if let Err(e) = parse_header(&csv, line.as_bytes())
+                                .map(|headers| deserializer.set_csv_headers(headers))
+                            {
+                                return ready(Some(Err(SourceError::other(
+                                    "invalid CSV header",
+                                    e,
+                                ))));
+                            }
+                        }
+                        return ready(None);
+                    }
+
+                    ready(Some(deserializer.deserialize_single(line.as_bytes())))
+                }))
+                    as Box<dyn Stream<Item = Result<T, SourceError>> + Unpin + Send>;
+                Ok(x)
+            }
             arroyo_rpc::formats::Format::Avro(_) => todo!(),
+            arroyo_rpc::formats::Format::Protobuf(_) => Err(UserError::new(
+                "unsupported format",
+                "the filesystem source does not support protobuf; use json, csv or parquet",
+            )),
             arroyo_rpc::formats::Format::Parquet(_) => {
                 let object_meta = storage_provider
                     .get_backing_store()
@@ -270,6 +373,29 @@ impl<K: Data, T: SchemaData> FileSystemSourceFunc<K, T> {
         }
     }
 
+    async fn get_reader(
+        &mut self,
+        storage_provider: &StorageProvider,
+        path: String,
+    ) -> Box<dyn AsyncRead + Unpin + Send> {
+        let stream_reader = storage_provider.get_as_stream(path).await.unwrap();
+
+        match self.get_compression_format() {
+            CompressionFormat::Zstd => Box::new(ZstdDecoder::new(BufReader::new(stream_reader))),
+            CompressionFormat::Gzip => Box::new(GzipDecoder::new(BufReader::new(stream_reader))),
+            CompressionFormat::None => Box::new(BufReader::new(stream_reader)),
+        }
+    }
+
+    async fn get_lines(
+        &mut self,
+        storage_provider: &StorageProvider,
+        path: String,
+    ) -> LinesStream<BufReader<Box<dyn AsyncRead + Unpin + Send>>> {
+        // use line iterators
+        LinesStream::new(BufReader::new(self.get_reader(storage_provider, path).await).lines())
+    }
+
     async fn read_file(
         &mut self,
         ctx: &mut Context<(), T>,
@@ -311,7 +437,16 @@ impl<K: Data, T: SchemaData> FileSystemSourceFunc<K, T> {
                 item = reader.next() => {
                     match item {
                         Some(value) => {
-                            ctx.collect_source_record(SystemTime::now(), value, &self.bad_data, &mut self.rate_limiter).await?;
+                            let metadata = FileMetadata {
+                                file_path: obj_key,
+                                line_number: records_read as i64 + 1,
+                            };
+                            let value = value.map(|mut v| {
+                                v.set_metadata(&metadata);
+                                v
+                            });
+                            ctx.collect_source_record(SystemTime::now(), value, &mut self.bad_data,
+                                || Some(RecordPosition::new(obj_key, records_read))).await?;
                             records_read += 1;
                         }
                         None => {
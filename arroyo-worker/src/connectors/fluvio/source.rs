@@ -1,5 +1,6 @@
+use crate::connectors::bad_data::{BadDataHandler, RecordPosition};
 use crate::engine::{Context, StreamNode};
-use crate::{RateLimiter, SourceFinishType};
+use crate::SourceFinishType;
 use anyhow::anyhow;
 use arroyo_formats::{DataDeserializer, SchemaData};
 use arroyo_macro::source_fn;
@@ -33,8 +34,7 @@ where
     endpoint: Option<String>,
     offset_mode: SourceOffset,
     deserializer: DataDeserializer<T>,
-    bad_data: Option<BadData>,
-    rate_limiter: RateLimiter,
+    bad_data: BadDataHandler,
     _t: PhantomData<K>,
 }
 
@@ -67,8 +67,7 @@ where
             endpoint: endpoint.map(|e| e.to_string()),
             offset_mode,
             deserializer: DataDeserializer::new(format, framing),
-            rate_limiter: RateLimiter::new(),
-            bad_data,
+            bad_data: BadDataHandler::new(bad_data),
             _t: PhantomData,
         }
     }
@@ -90,8 +89,7 @@ where
                 config.format.expect("Format must be specified for fluvio"),
                 config.framing,
             ),
-            bad_data: config.bad_data,
-            rate_limiter: RateLimiter::new(),
+            bad_data: BadDataHandler::new(config.bad_data),
             _t: PhantomData,
         }
     }
@@ -209,7 +207,8 @@ where
                             let timestamp = from_millis(msg.timestamp().max(0) as u64);
                             let iter = self.deserializer.deserialize_slice(msg.value()).await;
                             for value in iter {
-                                ctx.collect_source_record(timestamp, value, &self.bad_data, &mut self.rate_limiter).await?;
+                                ctx.collect_source_record(timestamp, value, &mut self.bad_data,
+                                    || Some(RecordPosition::new(msg.partition(), msg.offset()))).await?;
                             }
                             offsets.insert(msg.partition(), msg.offset());
                         },
@@ -1,5 +1,5 @@
 use crate::engine::{Context, StreamNode};
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use arroyo_formats::DataSerializer;
 use arroyo_formats::SchemaData;
 use arroyo_macro::process_fn;
@@ -12,6 +12,7 @@ use std::marker::PhantomData;
 
 use tracing::{error, warn};
 
+use rdkafka::message::{Header, OwnedHeaders};
 use rdkafka::producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer};
 use rdkafka::util::Timeout;
 
@@ -21,9 +22,10 @@ use arroyo_types::CheckpointBarrier;
 use rdkafka::error::KafkaError;
 use rdkafka_sys::RDKafkaErrorCode;
 use serde::Serialize;
+use serde_json::Value;
 use std::time::{Duration, SystemTime};
 
-use super::{client_configs, KafkaConfig, KafkaTable, SinkCommitMode, TableType};
+use super::{client_configs, KafkaConfig, KafkaTable, KeyFormat, SinkCommitMode, TableType};
 
 #[cfg(test)]
 mod test;
@@ -37,6 +39,9 @@ pub struct KafkaSinkFunc<K: Key + Serialize, T: SchemaData> {
     write_futures: Vec<DeliveryFuture>,
     client_config: HashMap<String, String>,
     serializer: DataSerializer<T>,
+    key_field: Option<String>,
+    key_format: KeyFormat,
+    headers_field: Option<String>,
     _t: PhantomData<K>,
 }
 
@@ -77,7 +82,10 @@ impl<K: Key + Serialize, T: SchemaData> KafkaSinkFunc<K, T> {
                 .iter()
                 .map(|(key, value)| (key.to_string(), value.to_string()))
                 .collect(),
-            serializer: DataSerializer::new(format),
+            serializer: DataSerializer::new(format, None),
+            key_field: None,
+            key_format: KeyFormat::RawString,
+            headers_field: None,
             _t: PhantomData,
         }
     }
@@ -89,7 +97,14 @@ impl<K: Key + Serialize, T: SchemaData> KafkaSinkFunc<K, T> {
             .expect("Invalid connection config for KafkaSink");
         let table: KafkaTable =
             serde_json::from_value(config.table).expect("Invalid table config for KafkaSource");
-        let TableType::Sink { commit_mode } = table.type_ else {
+        let client_config = client_configs(&connection, &table);
+        let TableType::Sink {
+            commit_mode,
+            key_field,
+            key_format,
+            headers_field,
+        } = table.type_
+        else {
             panic!("found non-sink kafka config in sink operator");
         };
 
@@ -98,14 +113,85 @@ impl<K: Key + Serialize, T: SchemaData> KafkaSinkFunc<K, T> {
             producer: None,
             consistency_mode: commit_mode.into(),
             write_futures: vec![],
-            client_config: client_configs(&connection, &table),
+            client_config,
             topic: table.topic,
             serializer: DataSerializer::new(
                 config.format.expect("Format must be defined for KafkaSink"),
+                config.framing,
             ),
+            key_field,
+            key_format: key_format.unwrap_or(KeyFormat::RawString),
+            headers_field,
             _t: PhantomData,
         }
     }
+
+    /// Returns the key and headers for a record from its key and headers columns, if configured
+    fn key_and_headers(&self, value: &T) -> Result<(Option<Vec<u8>>, Option<OwnedHeaders>)> {
+        if self.key_field.is_none() && self.headers_field.is_none() {
+            return Ok((None, None));
+        }
+
+        let row = serde_json::to_value(value)?;
+        let column = |name: &String| {
+            row.get(name)
+                .ok_or_else(|| anyhow!("column '{}' not found in output", name))
+        };
+
+        let key = match &self.key_field {
+            Some(field) => key_from_value(column(field)?, &self.key_format),
+            None => None,
+        };
+
+        let headers = match &self.headers_field {
+            Some(field) => headers_from_value(column(field)?)?,
+            None => None,
+        };
+
+        Ok((key, headers))
+    }
+}
+
+fn key_from_value(value: &Value, format: &KeyFormat) -> Option<Vec<u8>> {
+    match (value, format) {
+        (Value::Null, _) => None,
+        (Value::String(s), KeyFormat::RawString) => Some(s.as_bytes().to_vec()),
+        (value, _) => Some(value.to_string().into_bytes()),
+    }
+}
+
+/// Converts a JSON object (or a string containing one) into Kafka headers, with non-string
+/// values written as JSON
+fn headers_from_value(value: &Value) -> Result<Option<OwnedHeaders>> {
+    let parsed;
+    let map = match value {
+        Value::Null => return Ok(None),
+        Value::Object(map) => map,
+        Value::String(s) => {
+            parsed = serde_json::from_str::<Value>(s)
+                .map_err(|e| anyhow!("headers column is not valid JSON: {}", e))?;
+            let Value::Object(map) = &parsed else {
+                bail!("headers column must contain a JSON object");
+            };
+            map
+        }
+        _ => bail!("headers column must contain a JSON object"),
+    };
+
+    let mut headers = OwnedHeaders::new_with_capacity(map.len());
+    for (key, value) in map {
+        let value = match value {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            value => Some(value.to_string()),
+        };
+        headers = headers.insert(Header {
+            key,
+            value: value.as_deref(),
+        });
+    }
+
+    Ok(Some(headers))
 }
 
 #[process_fn(in_k = K, in_t = T)]
@@ -226,14 +312,19 @@ impl<K: Key + Serialize, T: SchemaData + Serialize> KafkaSinkFunc<K, T> {
         Ok(())
     }
 
-    async fn publish(&mut self, k: Option<String>, v: Vec<u8>) -> Result<(), UserError> {
-        let mut rec = {
-            if let Some(k) = k.as_ref() {
-                FutureRecord::to(&self.topic).key(k).payload(&v)
-            } else {
-                FutureRecord::to(&self.topic).payload(&v)
-            }
-        };
+    async fn publish(
+        &mut self,
+        k: Option<Vec<u8>>,
+        headers: Option<OwnedHeaders>,
+        v: Vec<u8>,
+    ) -> Result<(), UserError> {
+        let mut rec = FutureRecord::<Vec<u8>, Vec<u8>>::to(&self.topic).payload(&v);
+        if let Some(k) = k.as_ref() {
+            rec = rec.key(k);
+        }
+        if let Some(headers) = headers {
+            rec = rec.headers(headers);
+        }
 
         loop {
             match self.producer.as_mut().unwrap().send_result(rec) {
@@ -257,15 +348,28 @@ impl<K: Key + Serialize, T: SchemaData + Serialize> KafkaSinkFunc<K, T> {
         }
     }
 
+    async fn write(&mut self, record: &Record<K, T>, v: Vec<u8>) -> Result<(), UserError> {
+        let (key, headers) = self
+            .key_and_headers(&record.value)
+            .map_err(|e| UserError::new("Could not write to Kafka", format!("{:?}", e)))?;
+
+        // a key column takes precedence over the key of the stream
+        let key = key.or_else(|| record.key.as_ref().map(|k| serde_json::to_vec(k).unwrap()));
+
+        self.publish(key, headers, v).await
+    }
+
     async fn process_element(&mut self, record: &Record<K, T>, ctx: &mut Context<(), ()>) {
-        let k = record
-            .key
-            .as_ref()
-            .map(|k| serde_json::to_string(k).unwrap());
-        let v = self.serializer.to_vec(&record.value);
+        let v = match self.serializer.to_vec(&record.value) {
+            Ok(v) => v,
+            Err(e) => {
+                ctx.report_user_error(e).await;
+                return;
+            }
+        };
 
         if let Some(v) = v {
-            match self.publish(k, v).await {
+            match self.write(record, v).await {
                 Ok(_) => {}
                 Err(e) => {
                     ctx.control_tx
@@ -14,7 +14,7 @@ use rdkafka::producer::Producer;
 use rdkafka::{ClientConfig, Message};
 use tokio::sync::mpsc::channel;
 
-use super::KafkaSinkFunc;
+use super::{headers_from_value, key_from_value, KafkaSinkFunc, KeyFormat};
 
 pub struct KafkaTopicTester {
     topic: String,
@@ -231,3 +231,40 @@ async fn test_kafka() {
         assert_eq!(record.value, result);
     }
 }
+
+#[test]
+fn test_keys_and_headers() {
+    use rdkafka::message::Headers;
+    use serde_json::json;
+
+    assert_eq!(
+        key_from_value(&json!("user_1"), &KeyFormat::RawString),
+        Some(b"user_1".to_vec())
+    );
+    assert_eq!(
+        key_from_value(&json!("user_1"), &KeyFormat::Json),
+        Some(b"\"user_1\"".to_vec())
+    );
+    assert_eq!(
+        key_from_value(&json!(5), &KeyFormat::RawString),
+        Some(b"5".to_vec())
+    );
+    assert_eq!(key_from_value(&json!(null), &KeyFormat::Json), None);
+
+    let headers = headers_from_value(&json!(r#"{"source": "web", "count": 3, "empty": null}"#))
+        .unwrap()
+        .unwrap();
+    let mut headers: Vec<_> = headers.iter().map(|h| (h.key, h.value)).collect();
+    headers.sort();
+    assert_eq!(
+        headers,
+        vec![
+            ("count", Some(&b"3"[..])),
+            ("empty", None),
+            ("source", Some(&b"web"[..])),
+        ]
+    );
+
+    assert!(headers_from_value(&json!(null)).unwrap().is_none());
+    assert!(headers_from_value(&json!("[1, 2]")).is_err());
+}
@@ -1,5 +1,6 @@
+use crate::connectors::bad_data::{BadDataHandler, RecordPosition};
 use crate::engine::{Context, StreamNode};
-use crate::{RateLimiter, SourceFinishType};
+use crate::SourceFinishType;
 use arroyo_formats::{DataDeserializer, SchemaData};
 use arroyo_macro::source_fn;
 use arroyo_rpc::formats::{BadData, Format, Framing};
@@ -12,8 +13,10 @@ use arroyo_types::*;
 use bincode::{Decode, Encode};
 use governor::{Quota, RateLimiter as GovernorRateLimiter};
 use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::Headers;
 use rdkafka::{ClientConfig, Message as KMessage, Offset, TopicPartitionList};
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::num::NonZeroU32;
@@ -38,8 +41,7 @@ where
     group_id: Option<String>,
     offset_mode: super::SourceOffset,
     deserializer: DataDeserializer<T>,
-    bad_data: Option<BadData>,
-    rate_limiter: RateLimiter,
+    bad_data: BadDataHandler,
     client_configs: HashMap<String, String>,
     messages_per_second: NonZeroU32,
     _t: PhantomData<K>,
@@ -51,6 +53,38 @@ pub struct KafkaState {
     offset: i64,
 }
 
+/// Exposes the key, headers, partition, offset and timestamp of a message to metadata columns
+struct KafkaMetadata<'a, M: KMessage>(&'a M);
+
+impl<'a, M: KMessage> SourceMetadata for KafkaMetadata<'a, M> {
+    fn get(&self, key: &str) -> Option<MetadataValue> {
+        let msg = self.0;
+        match key {
+            "key" => msg.key().map(|k| MetadataValue::Bytes(k.to_vec())),
+            "headers" => msg.headers().map(|headers| {
+                let headers: serde_json::Map<String, Value> = headers
+                    .iter()
+                    .map(|h| {
+                        let value = h
+                            .value
+                            .map(|v| Value::String(String::from_utf8_lossy(v).into_owned()))
+                            .unwrap_or(Value::Null);
+                        (h.key.to_string(), value)
+                    })
+                    .collect();
+                MetadataValue::String(Value::Object(headers).to_string())
+            }),
+            "partition" => Some(MetadataValue::Int32(msg.partition())),
+            "offset" => Some(MetadataValue::Int64(msg.offset())),
+            "timestamp" => msg
+                .timestamp()
+                .to_millis()
+                .map(|t| MetadataValue::Timestamp(from_millis(t as u64))),
+            _ => None,
+        }
+    }
+}
+
 pub fn tables() -> Vec<TableDescriptor> {
     vec![arroyo_state::global_table("k", "kafka source state")]
 }
@@ -68,7 +102,6 @@ where
         offset_mode: super::SourceOffset,
         format: Format,
         bad_data: Option<BadData>,
-        rate_limiter: RateLimiter,
         framing: Option<Framing>,
         messages_per_second: u32,
         client_configs: Vec<(&str, &str)>,
@@ -78,8 +111,7 @@ where
             bootstrap_servers: servers.to_string(),
             group_id: group,
             offset_mode,
-            bad_data,
-            rate_limiter,
+            bad_data: BadDataHandler::new(bad_data),
             deserializer: DataDeserializer::new(format, framing),
             client_configs: client_configs
                 .iter()
@@ -140,8 +172,7 @@ where
                 config.framing,
                 schema_resolver,
             ),
-            bad_data: config.bad_data,
-            rate_limiter: RateLimiter::new(),
+            bad_data: BadDataHandler::new(config.bad_data),
             client_configs,
             messages_per_second: NonZeroU32::new(
                 config
@@ -278,13 +309,17 @@ where
                                         "The message read from Kafka did not contain a message timestamp"))?;
 
                                 let iter = self.deserializer.deserialize_slice(v).await;
+                                let metadata = KafkaMetadata(&msg);
 
                                 for value in iter {
                                     ctx.collect_source_record(
                                         from_millis(timestamp as u64),
-                                        value,
-                                        &self.bad_data,
-                                        &mut self.rate_limiter,
+                                        value.map(|mut v| {
+                                            v.set_metadata(&metadata);
+                                            v
+                                        }),
+                                        &mut self.bad_data,
+                                        || Some(RecordPosition::new(msg.partition(), msg.offset())),
                                     ).await?;
                                 }
 
@@ -49,6 +49,7 @@ impl<K: Key + Serialize, T: SchemaData> KinesisSinkFunc<K, T> {
                 config
                     .format
                     .expect("Format must be defined for KinesisSink"),
+                config.framing,
             ),
             flush_config,
             _phantom: PhantomData,
@@ -76,15 +77,20 @@ impl<K: Key + Serialize, T: SchemaData> KinesisSinkFunc<K, T> {
         }
     }
 
-    async fn process_element(&mut self, record: &Record<K, T>, _ctx: &mut Context<(), ()>) {
+    async fn process_element(&mut self, record: &Record<K, T>, ctx: &mut Context<(), ()>) {
         let k = record
             .key
             .as_ref()
             .map(|k| serde_json::to_string(k).unwrap())
             .unwrap_or_else(|| Uuid::new_v4().to_string());
 
-        let Some(v) = self.serializer.to_vec(&record.value) else {
-            return;
+        let v = match self.serializer.to_vec(&record.value) {
+            Ok(Some(v)) => v,
+            Ok(None) => return,
+            Err(e) => {
+                ctx.report_user_error(e).await;
+                return;
+            }
         };
 
         let mut batch_preparer = match self.in_progress_batch.take() {
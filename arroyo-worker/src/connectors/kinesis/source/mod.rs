@@ -10,13 +10,12 @@ use std::{
 use anyhow::{anyhow, bail, Context as AnyhowContext, Result};
 use arroyo_formats::{DataDeserializer, SchemaData};
 use arroyo_macro::{source_fn, StreamNode};
-use arroyo_rpc::formats::BadData;
 use arroyo_rpc::{
     grpc::{StopMode, TableDescriptor},
     ControlMessage, OperatorConfig,
 };
 use arroyo_state::tables::global_keyed_map::GlobalKeyedState;
-use arroyo_types::{from_nanos, Data, UserError};
+use arroyo_types::{from_nanos, Data, MetadataValue, SourceMetadata, UserError};
 use aws_config::from_env;
 use aws_sdk_kinesis::{
     client::fluent_builders::GetShardIterator,
@@ -34,7 +33,8 @@ use tokio::{
 };
 use tracing::{debug, info, warn};
 
-use crate::{engine::Context, RateLimiter, SourceFinishType};
+use crate::connectors::bad_data::{BadDataHandler, RecordPosition};
+use crate::{engine::Context, SourceFinishType};
 
 use super::{KinesisTable, SourceOffset, TableType};
 
@@ -49,8 +49,7 @@ pub enum KinesisOffset {
 pub struct KinesisSourceFunc<K: Data, T: SchemaData> {
     stream_name: String,
     deserializer: DataDeserializer<T>,
-    bad_data: Option<BadData>,
-    rate_limiter: RateLimiter,
+    bad_data: BadDataHandler,
     kinesis_client: Option<KinesisClient>,
     aws_region: Option<String>,
     shards: HashMap<String, ShardState>,
@@ -150,6 +149,24 @@ impl ShardState {
     }
 }
 
+/// Exposes the shard, sequence number and arrival time of a record to metadata columns
+struct KinesisMetadata<'a> {
+    shard_id: &'a str,
+    sequence_number: &'a str,
+    arrival_time: SystemTime,
+}
+
+impl<'a> SourceMetadata for KinesisMetadata<'a> {
+    fn get(&self, key: &str) -> Option<MetadataValue> {
+        match key {
+            "shard_id" => Some(MetadataValue::String(self.shard_id.to_string())),
+            "sequence_number" => Some(MetadataValue::String(self.sequence_number.to_string())),
+            "arrival_time" => Some(MetadataValue::Timestamp(self.arrival_time)),
+            _ => None,
+        }
+    }
+}
+
 struct AsyncNamedResult<T: Debug> {
     name: String,
     result: Result<T>,
@@ -196,8 +213,7 @@ impl<K: Data, T: SchemaData> KinesisSourceFunc<K, T> {
                     .expect("format must be set for kinesis source"),
                 config.framing,
             ),
-            bad_data: config.bad_data,
-            rate_limiter: RateLimiter::new(),
+            bad_data: BadDataHandler::new(config.bad_data),
             _phantom: PhantomData,
         }
     }
@@ -356,7 +372,7 @@ impl<K: Data, T: SchemaData> KinesisSourceFunc<K, T> {
                 .map(|record| record.sequence_number().unwrap().to_owned())
         });
 
-        let next_shard_iterator = self.process_records(get_records, ctx).await?;
+        let next_shard_iterator = self.process_records(&shard_id, get_records, ctx).await?;
         let shard_state = self.shards.get_mut(&shard_id).unwrap();
 
         if let Some(last_sequence_number) = last_sequence_number {
@@ -472,6 +488,7 @@ impl<K: Data, T: SchemaData> KinesisSourceFunc<K, T> {
 
     async fn process_records(
         &mut self,
+        shard_id: &str,
         get_records_output: GetRecordsOutput,
         ctx: &mut Context<(), T>,
     ) -> Result<Option<String>, UserError> {
@@ -481,12 +498,21 @@ impl<K: Data, T: SchemaData> KinesisSourceFunc<K, T> {
 
             let timestamp = record.approximate_arrival_timestamp.unwrap();
             let iter = self.deserializer.deserialize_slice(&data).await;
+            let sequence_number = record.sequence_number.as_deref().unwrap_or_default();
+            let metadata = KinesisMetadata {
+                shard_id,
+                sequence_number,
+                arrival_time: from_nanos(timestamp.as_nanos() as u128),
+            };
             for value in iter {
                 ctx.collect_source_record(
                     from_nanos(timestamp.as_nanos() as u128),
-                    value,
-                    &self.bad_data,
-                    &mut self.rate_limiter,
+                    value.map(|mut v| {
+                        v.set_metadata(&metadata);
+                        v
+                    }),
+                    &mut self.bad_data,
+                    || Some(RecordPosition::new(shard_id, sequence_number)),
                 )
                 .await?;
             }
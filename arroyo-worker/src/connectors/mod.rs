@@ -1,3 +1,4 @@
+pub mod bad_data;
 pub mod blackhole;
 pub mod filesystem;
 pub mod fluvio;
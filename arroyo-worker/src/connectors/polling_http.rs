@@ -1,31 +1,34 @@
 use bincode::{Decode, Encode};
 use bytes::Bytes;
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 use std::borrow::Cow;
 use std::str::FromStr;
 use std::time::SystemTime;
 use std::{marker::PhantomData, time::Duration};
 
+use anyhow::{anyhow, bail};
 use arroyo_macro::source_fn;
 use arroyo_rpc::ControlMessage;
 use arroyo_rpc::{grpc::TableDescriptor, OperatorConfig};
-use arroyo_types::{string_to_map, Message, UserError, Watermark};
+use arroyo_types::{string_to_map, Data, Message, UserError, Watermark};
+use async_trait::async_trait;
 
 use serde::{Deserialize, Serialize};
 use tokio::select;
 use tokio::time::MissedTickBehavior;
 
 use arroyo_formats::{DataDeserializer, SchemaData};
-use arroyo_rpc::formats::BadData;
 use arroyo_rpc::grpc::StopMode;
 use arroyo_rpc::var_str::VarStr;
 use arroyo_state::tables::global_keyed_map::GlobalKeyedState;
 use tracing::{debug, info, warn};
 use typify::import_types;
 
+use crate::connectors::bad_data::BadDataHandler;
+use crate::operators::lookup_join::LookupConnector;
 use crate::{
     engine::{Context, StreamNode},
-    RateLimiter, SourceFinishType,
+    SourceFinishType,
 };
 
 import_types!(
@@ -34,6 +37,43 @@ import_types!(
 );
 const DEFAULT_POLLING_INTERVAL: Duration = Duration::from_secs(1);
 const MAX_BODY_SIZE: usize = 5 * 1024 * 1024; // 5M ought to be enough for anybody
+const DEFAULT_LOOKUP_MAX_REQUESTS: usize = 16;
+
+fn construct_client(table: &PollingHttpTable) -> reqwest::Client {
+    let headers = string_to_map(
+        &table
+            .headers
+            .as_ref()
+            .map(|t| t.sub_env_vars().expect("Failed to substitute env vars"))
+            .unwrap_or("".to_string()),
+    )
+    .expect("Invalid header map")
+    .into_iter()
+    .map(|(k, v)| {
+        (
+            (&k).try_into()
+                .expect(&format!("invalid header name {}", k)),
+            (&v).try_into()
+                .expect(&format!("invalid header value {}", v)),
+        )
+    })
+    .collect();
+
+    reqwest::ClientBuilder::new()
+        .default_headers(headers)
+        .timeout(Duration::from_secs(5))
+        .build()
+        .expect("could not construct http client")
+}
+
+fn request_method(table: &PollingHttpTable) -> reqwest::Method {
+    match table.method {
+        None | Some(Method::Get) => reqwest::Method::GET,
+        Some(Method::Post) => reqwest::Method::POST,
+        Some(Method::Put) => reqwest::Method::PUT,
+        Some(Method::Patch) => reqwest::Method::PATCH,
+    }
+}
 
 #[derive(StreamNode)]
 pub struct PollingHttpSourceFunc<K, T>
@@ -49,8 +89,7 @@ where
     polling_interval: Duration,
     emit_behavior: EmitBehavior,
     deserializer: DataDeserializer<T>,
-    bad_data: Option<BadData>,
-    rate_limiter: RateLimiter,
+    bad_data: BadDataHandler,
     _t: PhantomData<(K, T)>,
 }
 
@@ -71,25 +110,6 @@ where
         let table: PollingHttpTable =
             serde_json::from_value(config.table).expect("Invalid table config for WebhookSink");
 
-        let headers = string_to_map(
-            &table
-                .headers
-                .as_ref()
-                .map(|t| t.sub_env_vars().expect("Failed to substitute env vars"))
-                .unwrap_or("".to_string()),
-        )
-        .expect("Invalid header map")
-        .into_iter()
-        .map(|(k, v)| {
-            (
-                (&k).try_into()
-                    .expect(&format!("invalid header name {}", k)),
-                (&v).try_into()
-                    .expect(&format!("invalid header value {}", v)),
-            )
-        })
-        .collect();
-
         let deserializer = DataDeserializer::new(
             config
                 .format
@@ -99,18 +119,9 @@ where
 
         Self {
             state: PollingHttpSourceState { last_message: None },
-            client: reqwest::ClientBuilder::new()
-                .default_headers(headers)
-                .timeout(Duration::from_secs(5))
-                .build()
-                .expect("could not construct http client"),
+            client: construct_client(&table),
             endpoint: url::Url::from_str(&table.endpoint).expect("invalid endpoint"),
-            method: match table.method {
-                None | Some(Method::Get) => reqwest::Method::GET,
-                Some(Method::Post) => reqwest::Method::POST,
-                Some(Method::Put) => reqwest::Method::PUT,
-                Some(Method::Patch) => reqwest::Method::PATCH,
-            },
+            method: request_method(&table),
             body: table.body.map(|b| b.into()),
             polling_interval: table
                 .poll_interval_ms
@@ -118,8 +129,7 @@ where
                 .unwrap_or(DEFAULT_POLLING_INTERVAL),
             emit_behavior: table.emit_behavior.unwrap_or(EmitBehavior::All),
             deserializer,
-            bad_data: config.bad_data,
-            rate_limiter: RateLimiter::new(),
+            bad_data: BadDataHandler::new(config.bad_data),
             _t: PhantomData,
         }
     }
@@ -286,7 +296,7 @@ where
                                 let iter = self.deserializer.deserialize_slice(&buf).await;
 
                                 for record in iter {
-                                    ctx.collect_source_record(SystemTime::now(), record, &self.bad_data, &mut self.rate_limiter).await?;
+                                    ctx.collect_source_record(SystemTime::now(), record, &mut self.bad_data, || None).await?;
                                 }
 
                                 self.state.last_message = Some(buf);
@@ -315,3 +325,96 @@ where
         }
     }
 }
+
+/// Looks up rows by requesting the endpoint with `{key}` replaced by each join key. Responses
+/// with a 404 status are treated as missing keys.
+pub struct HttpLookup<V: SchemaData> {
+    client: reqwest::Client,
+    endpoint: String,
+    method: reqwest::Method,
+    body: Option<String>,
+    max_requests: usize,
+    deserializer: DataDeserializer<V>,
+}
+
+impl<V: SchemaData> HttpLookup<V> {
+    pub fn from_config(config: &str) -> Self {
+        let config: OperatorConfig =
+            serde_json::from_str(config).expect("Invalid config for HttpLookup");
+        let table: PollingHttpTable =
+            serde_json::from_value(config.table).expect("Invalid table config for HttpLookup");
+
+        let deserializer = DataDeserializer::new(
+            config
+                .format
+                .expect("http lookup table must have a format configured"),
+            config.framing,
+        );
+
+        Self {
+            client: construct_client(&table),
+            method: request_method(&table),
+            endpoint: table.endpoint,
+            body: table.body,
+            max_requests: table
+                .lookup_max_requests
+                .map(|n| n.max(1) as usize)
+                .unwrap_or(DEFAULT_LOOKUP_MAX_REQUESTS),
+            deserializer,
+        }
+    }
+
+    async fn lookup_key(&self, key: &str) -> anyhow::Result<Option<V>> {
+        let encoded: String = url::form_urlencoded::byte_serialize(key.as_bytes()).collect();
+        let endpoint = self.endpoint.replace("{key}", &encoded);
+
+        let mut request = self.client.request(self.method.clone(), &endpoint);
+        if let Some(body) = &self.body {
+            request = request.body(body.replace("{key}", key));
+        }
+
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("failed to execute HTTP request to {}: {}", endpoint, e))?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !resp.status().is_success() {
+            bail!(
+                "http server responded to {} with {}",
+                endpoint,
+                resp.status().as_u16()
+            );
+        }
+
+        let body = resp
+            .bytes()
+            .await
+            .map_err(|e| anyhow!("failed while reading body: {}", e))?;
+        if body.len() > MAX_BODY_SIZE {
+            bail!("response body exceeds max length {}", MAX_BODY_SIZE);
+        }
+
+        let mut deserializer = self.deserializer.clone();
+        let mut iter = deserializer.deserialize_slice(&body).await;
+        iter.next()
+            .transpose()
+            .map_err(|e| anyhow!("failed to deserialize response from {}: {:?}", endpoint, e))
+    }
+}
+
+#[async_trait]
+impl<V: SchemaData + Data + Sync> LookupConnector<V> for HttpLookup<V> {
+    async fn lookup(&self, keys: &[String]) -> anyhow::Result<Vec<Option<V>>> {
+        // one request per key, with at most `max_requests` in flight; `buffered` preserves the
+        // order of the keys
+        let requests: Vec<_> = keys.iter().map(|key| self.lookup_key(key)).collect();
+        futures::stream::iter(requests)
+            .buffered(self.max_requests)
+            .try_collect()
+            .await
+    }
+}
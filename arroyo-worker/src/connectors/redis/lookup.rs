@@ -0,0 +1,125 @@
+use crate::connectors::redis::sink::GeneralConnection;
+use crate::connectors::redis::{LookupSource, RedisClient, RedisConfig, RedisTable, TableType};
+use crate::operators::lookup_join::LookupConnector;
+
+use anyhow::anyhow;
+use arroyo_formats::{DataDeserializer, SchemaData};
+use arroyo_rpc::OperatorConfig;
+use arroyo_types::Data;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::OnceCell;
+
+/// Looks up rows stored in Redis, either as serialized values under `<prefix><key>` or as
+/// hashes whose fields hold the columns of the row
+pub struct RedisLookup<V: SchemaData> {
+    client: RedisClient,
+    connection: OnceCell<GeneralConnection>,
+    source: LookupSource,
+    deserializer: DataDeserializer<V>,
+}
+
+impl<V: SchemaData> RedisLookup<V> {
+    pub fn from_config(config: &str) -> Self {
+        let config: OperatorConfig =
+            serde_json::from_str(config).expect("Invalid config for RedisLookup");
+        let profile: RedisConfig = serde_json::from_value(config.connection)
+            .expect("Invalid connection profile for RedisLookup");
+        let table: RedisTable =
+            serde_json::from_value(config.table).expect("Invalid table config for Redis");
+
+        let TableType::Lookup(source) = table.connector_type else {
+            panic!("Redis sink table cannot be used for lookups");
+        };
+
+        Self {
+            client: RedisClient::new(&profile).expect("Unable to construct redis client"),
+            connection: OnceCell::new(),
+            source,
+            deserializer: DataDeserializer::new(
+                config.format.expect("redis table must have a format"),
+                config.framing,
+            ),
+        }
+    }
+
+    async fn connection(&self) -> anyhow::Result<GeneralConnection> {
+        Ok(self
+            .connection
+            .get_or_try_init(|| self.client.get_connection())
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Redis: {:?}", e))?
+            .clone())
+    }
+
+    async fn deserialize(&self, bytes: &[u8]) -> anyhow::Result<Option<V>> {
+        let mut deserializer = self.deserializer.clone();
+        let mut iter = deserializer.deserialize_slice(bytes).await;
+        iter.next()
+            .transpose()
+            .map_err(|e| anyhow!("Failed to deserialize value from Redis: {:?}", e))
+    }
+}
+
+#[async_trait]
+impl<V: SchemaData + Data + Sync> LookupConnector<V> for RedisLookup<V> {
+    async fn lookup(&self, keys: &[String]) -> anyhow::Result<Vec<Option<V>>> {
+        let mut connection = self.connection().await?;
+        let mut pipeline = redis::pipe();
+
+        match &self.source {
+            LookupSource::KeyPrefix(key_prefix) => {
+                for key in keys {
+                    pipeline.get(format!("{}{}", key_prefix, key));
+                }
+
+                let values: Vec<Option<Vec<u8>>> = pipeline
+                    .query_async(&mut connection)
+                    .await
+                    .map_err(|e| anyhow!("Failed to read from Redis: {:?}", e))?;
+
+                let mut results = Vec::with_capacity(values.len());
+                for value in values {
+                    results.push(match value {
+                        Some(bytes) => self.deserialize(&bytes).await?,
+                        None => None,
+                    });
+                }
+                Ok(results)
+            }
+            LookupSource::HashKeyPrefix(hash_key_prefix) => {
+                for key in keys {
+                    pipeline.hgetall(format!("{}{}", hash_key_prefix, key));
+                }
+
+                let hashes: Vec<HashMap<String, String>> = pipeline
+                    .query_async(&mut connection)
+                    .await
+                    .map_err(|e| anyhow!("Failed to read from Redis: {:?}", e))?;
+
+                let mut results = Vec::with_capacity(hashes.len());
+                for hash in hashes {
+                    if hash.is_empty() {
+                        results.push(None);
+                        continue;
+                    }
+
+                    // hash fields are always strings, so we parse those that hold json values
+                    // (like numbers) before deserializing the row
+                    let row: serde_json::Map<String, Value> = hash
+                        .into_iter()
+                        .map(|(field, value)| {
+                            let value =
+                                serde_json::from_str(&value).unwrap_or(Value::String(value));
+                            (field, value)
+                        })
+                        .collect();
+
+                    results.push(self.deserialize(&serde_json::to_vec(&row)?).await?);
+                }
+                Ok(results)
+            }
+        }
+    }
+}
@@ -7,6 +7,7 @@ use redis::{Client, ConnectionInfo, IntoConnectionInfo};
 use serde::{Deserialize, Serialize};
 use typify::import_types;
 
+pub mod lookup;
 pub mod sink;
 
 import_types!(schema = "../connector-schemas/redis/connection.json",
@@ -61,6 +61,7 @@ enum RedisCmd {
     Flush(u32),
 }
 
+#[derive(Clone)]
 pub enum GeneralConnection {
     Standard(ConnectionManager),
     Clustered(ClusterConnection),
@@ -241,13 +242,20 @@ where
         let table: RedisTable =
             serde_json::from_value(config.table).expect("Invalid table config for Redis");
 
+        if let TableType::Lookup(_) = table.connector_type {
+            panic!("Redis lookup table cannot be used as a sink");
+        }
+
         let client = RedisClient::new(&profile).expect("Unable to construct redis client");
 
         let (tx, cmd_rx) = tokio::sync::mpsc::channel(128);
         let (cmd_tx, rx) = tokio::sync::mpsc::channel(128);
 
         Self {
-            serializer: DataSerializer::new(config.format.expect("redis table must have a format")),
+            serializer: DataSerializer::new(
+                config.format.expect("redis table must have a format"),
+                config.framing,
+            ),
             table,
             client,
             cmd_q: Some((cmd_tx, cmd_rx)),
@@ -298,6 +306,9 @@ where
                                 }
                             }
                             TableType::Target(Target::HashTable { .. }) => RedisBehavior::Hash,
+                            TableType::Lookup(_) => {
+                                unreachable!("lookup tables cannot be used as sinks")
+                            }
                         },
                     }
                     .start();
@@ -330,9 +341,16 @@ where
         key
     }
 
-    async fn process_element(&mut self, record: &Record<K, T>, _ctx: &mut Context<(), ()>) {
+    async fn process_element(&mut self, record: &Record<K, T>, ctx: &mut Context<(), ()>) {
         let value = serde_json::to_value(&record.value).unwrap();
-        let data = self.serializer.to_vec(&record.value).unwrap();
+        let data = match self.serializer.to_vec(&record.value) {
+            Ok(Some(data)) => data,
+            Ok(None) => return,
+            Err(e) => {
+                ctx.report_user_error(e).await;
+                return;
+            }
+        };
         match &self.table.connector_type {
             TableType::Target(target) => match &target {
                 Target::StringTable {
@@ -381,6 +399,7 @@ where
                         .expect("Redis writer panicked");
                 }
             },
+            TableType::Lookup(_) => unreachable!("lookup tables cannot be used as sinks"),
         };
     }
 
@@ -1,5 +1,6 @@
+use crate::connectors::bad_data::BadDataHandler;
 use crate::engine::Context;
-use crate::{RateLimiter, SourceFinishType};
+use crate::SourceFinishType;
 use arroyo_formats::{DataDeserializer, SchemaData};
 use arroyo_macro::{source_fn, StreamNode};
 use arroyo_rpc::formats::{BadData, Format, Framing};
@@ -38,8 +39,7 @@ where
     headers: Vec<(String, String)>,
     events: Vec<String>,
     deserializer: DataDeserializer<T>,
-    bad_data: Option<BadData>,
-    rate_limiter: RateLimiter,
+    bad_data: BadDataHandler,
     state: SSESourceState,
     _t: PhantomData<K>,
 }
@@ -66,8 +66,7 @@ where
                 .collect(),
             events: events.into_iter().map(|s| s.to_string()).collect(),
             deserializer: DataDeserializer::new(format, framing),
-            bad_data,
-            rate_limiter: RateLimiter::new(),
+            bad_data: BadDataHandler::new(bad_data),
             state: SSESourceState::default(),
             _t: PhantomData,
         }
@@ -98,8 +97,7 @@ where
                 config.format.expect("SSESource requires a format"),
                 config.framing,
             ),
-            bad_data: config.bad_data,
-            rate_limiter: RateLimiter::new(),
+            bad_data: BadDataHandler::new(config.bad_data),
             state: SSESourceState::default(),
             _t: PhantomData,
         }
@@ -203,7 +201,7 @@ where
                                             let iter = self.deserializer.deserialize_slice(&event.data.as_bytes()).await;
 
                                             for v in iter {
-                                                ctx.collect_source_record(SystemTime::now(), v, &self.bad_data, &mut self.rate_limiter).await?;
+                                                ctx.collect_source_record(SystemTime::now(), v, &mut self.bad_data, || None).await?;
                                             }
                                         }
                                     }
@@ -73,6 +73,7 @@ where
                 config
                     .format
                     .expect("No format configured for webhook sink"),
+                config.framing,
             ),
             last_reported_error_at: Arc::new(Mutex::new(SystemTime::UNIX_EPOCH)),
             _t: PhantomData,
@@ -95,8 +96,13 @@ where
             .await
             .expect("websink semaphore closed");
 
-        let Some(body) = self.serializer.to_vec(&record.value) else {
-            return;
+        let body = match self.serializer.to_vec(&record.value) {
+            Ok(Some(body)) => body,
+            Ok(None) => return,
+            Err(e) => {
+                ctx.report_user_error(e).await;
+                return;
+            }
         };
 
         let body: bytes::Bytes = body.into();
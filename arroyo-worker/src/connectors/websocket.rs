@@ -1,13 +1,13 @@
 use std::str::FromStr;
 use std::{marker::PhantomData, time::SystemTime};
 
+use crate::connectors::bad_data::BadDataHandler;
 use crate::{
     engine::{Context, StreamNode},
-    header_map, RateLimiter, SourceFinishType,
+    header_map, SourceFinishType,
 };
 use arroyo_formats::{DataDeserializer, SchemaData};
 use arroyo_macro::source_fn;
-use arroyo_rpc::formats::BadData;
 use arroyo_rpc::{
     grpc::{StopMode, TableDescriptor},
     var_str::VarStr,
@@ -44,8 +44,7 @@ where
     headers: Vec<(String, String)>,
     subscription_messages: Vec<String>,
     deserializer: DataDeserializer<T>,
-    bad_data: Option<BadData>,
-    rate_limiter: RateLimiter,
+    bad_data: BadDataHandler,
     state: WebsocketSourceState,
     _t: PhantomData<K>,
 }
@@ -94,8 +93,7 @@ where
                 config.format.expect("WebsocketSource requires a format"),
                 config.framing,
             ),
-            bad_data: config.bad_data,
-            rate_limiter: RateLimiter::new(),
+            bad_data: BadDataHandler::new(config.bad_data),
             state: WebsocketSourceState::default(),
             _t: PhantomData,
         }
@@ -164,13 +162,8 @@ where
     ) -> Result<(), UserError> {
         let iter = self.deserializer.deserialize_slice(msg).await;
         for value in iter {
-            ctx.collect_source_record(
-                SystemTime::now(),
-                value,
-                &self.bad_data,
-                &mut self.rate_limiter,
-            )
-            .await?;
+            ctx.collect_source_record(SystemTime::now(), value, &mut self.bad_data, || None)
+                .await?;
         }
 
         Ok(())
@@ -32,10 +32,11 @@ use rand::Rng;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tokio::task::JoinHandle;
 
+use crate::connectors::bad_data::{BadDataHandler, RecordPosition};
 use crate::metrics::{register_queue_gauges, QueueGauges, TaskCounters};
 use crate::network_manager::{NetworkManager, Quad, Senders};
+use crate::TIMER_TABLE;
 use crate::{LogicalEdge, LogicalNode, METRICS_PUSH_INTERVAL, PROMETHEUS_PUSH_GATEWAY};
-use crate::{RateLimiter, TIMER_TABLE};
 use arroyo_state::{hash_key, BackingStore, StateBackend, StateStore};
 
 const QUEUE_SIZE: usize = 4 * 1024;
@@ -336,6 +337,13 @@ impl<K: Key, T: Data> Context<K, T> {
     }
 
     pub fn new_for_test() -> (Self, Receiver<QueueItem>) {
+        futures::executor::block_on(Self::new_for_test_with_tables(vec![]))
+    }
+
+    /// Constructs a context for testing operators directly, with state for the given tables
+    pub async fn new_for_test_with_tables(
+        tables: Vec<TableDescriptor>,
+    ) -> (Self, Receiver<QueueItem>) {
         let (_, control_rx) = channel(128);
         let (command_tx, _) = channel(128);
         let (data_tx, data_rx) = channel(128);
@@ -351,15 +359,16 @@ impl<K: Key, T: Data> Context<K, T> {
             key_range: 0..=0,
         };
 
-        let ctx = futures::executor::block_on(Context::new(
+        let ctx = Context::new(
             task_info,
             None,
             control_rx,
             command_tx,
             1,
             vec![vec![out_queue]],
-            vec![],
-        ));
+            tables,
+        )
+        .await;
 
         (ctx, data_rx)
     }
@@ -446,13 +455,14 @@ impl<K: Key, T: Data> Context<K, T> {
     }
 
     /// Collects a source record, handling errors and rate limiting.
-    /// Considers the `bad_data` option to determine whether to drop or fail on bad data.
+    /// Considers the `bad_data` option to determine whether to drop, dead-letter or fail on
+    /// bad data.
     pub async fn collect_source_record(
         &mut self,
         timestamp: SystemTime,
         value: Result<T, SourceError>,
-        bad_data: &Option<BadData>,
-        rate_limiter: &mut RateLimiter,
+        bad_data: &mut BadDataHandler,
+        position: impl FnOnce() -> Option<RecordPosition>,
     ) -> Result<(), UserError> {
         match value {
             Ok(value) => Ok(self
@@ -463,9 +473,11 @@ impl<K: Key, T: Data> Context<K, T> {
                     value,
                 })
                 .await),
-            Err(SourceError::BadData { details }) => match bad_data {
-                Some(BadData::Drop {}) => {
-                    rate_limiter
+            Err(SourceError::BadData { details, raw }) => match bad_data.bad_data {
+                Some(BadData::Drop { .. }) => {
+                    bad_data.spend_budget(&details)?;
+                    bad_data
+                        .rate_limiter
                         .rate_limit(|| async {
                             warn!("Dropping invalid data: {}", details.clone());
                             self.report_user_error(UserError::new(
@@ -480,6 +492,22 @@ impl<K: Key, T: Data> Context<K, T> {
                         .inc();
                     return Ok(());
                 }
+                Some(BadData::DeadLetter { .. }) => {
+                    bad_data.spend_budget(&details)?;
+                    bad_data
+                        .dead_letter(
+                            &self.task_info,
+                            &details,
+                            raw.as_deref().unwrap_or_default(),
+                            position().as_ref(),
+                            timestamp,
+                        )
+                        .await?;
+                    TaskCounters::DeserializationErrors
+                        .for_task(&self.task_info)
+                        .inc();
+                    Ok(())
+                }
                 Some(BadData::Fail {}) | None => {
                     Err(UserError::new("Deserialization error", details))
                 }
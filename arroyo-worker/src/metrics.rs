@@ -1,7 +1,8 @@
 use crate::engine::OutQueue;
 use arroyo_metrics::gauge_for_task;
 use arroyo_types::{
-    TaskInfo, BYTES_RECV, BYTES_SENT, DESERIALIZATION_ERRORS, MESSAGES_RECV, MESSAGES_SENT,
+    TaskInfo, BYTES_RECV, BYTES_SENT, DESERIALIZATION_ERRORS, DROPPED_LATE_RECORDS, LATE_RECORDS,
+    MESSAGES_RECV, MESSAGES_SENT,
 };
 use lazy_static::lazy_static;
 use prometheus::{labels, register_int_counter_vec, IntCounter, IntCounterVec, IntGauge};
@@ -39,6 +40,18 @@ lazy_static! {
         &TASK_METRIC_LABELS
     )
     .unwrap();
+    pub static ref LATE_RECORDS_COUNTER: IntCounterVec = register_int_counter_vec!(
+        LATE_RECORDS,
+        "Count of records that arrived behind the watermark but within the allowed lateness",
+        &TASK_METRIC_LABELS
+    )
+    .unwrap();
+    pub static ref DROPPED_LATE_RECORDS_COUNTER: IntCounterVec = register_int_counter_vec!(
+        DROPPED_LATE_RECORDS,
+        "Count of records that were dropped for arriving after their windows had closed",
+        &TASK_METRIC_LABELS
+    )
+    .unwrap();
 }
 
 pub enum TaskCounters {
@@ -47,6 +60,8 @@ pub enum TaskCounters {
     BytesReceived,
     BytesSent,
     DeserializationErrors,
+    LateRecords,
+    DroppedLateRecords,
 }
 
 impl TaskCounters {
@@ -78,6 +93,16 @@ impl TaskCounters {
                     &task_info.task_index.to_string(),
                     &task_info.operator_name,
                 ]),
+            TaskCounters::LateRecords => LATE_RECORDS_COUNTER.with_label_values(&[
+                &task_info.operator_id,
+                &task_info.task_index.to_string(),
+                &task_info.operator_name,
+            ]),
+            TaskCounters::DroppedLateRecords => DROPPED_LATE_RECORDS_COUNTER.with_label_values(&[
+                &task_info.operator_id,
+                &task_info.task_index.to_string(),
+                &task_info.operator_name,
+            ]),
         }
     }
 }
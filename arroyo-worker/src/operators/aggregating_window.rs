@@ -5,6 +5,7 @@ use std::{
 };
 
 use crate::engine::{Context, StreamNode};
+use crate::metrics::TaskCounters;
 use arroyo_macro::process_fn;
 use arroyo_rpc::grpc::{TableDeleteBehavior, TableDescriptor, TableType, TableWriteBehavior};
 use arroyo_state::tables::time_key_map::TimeKeyMap;
@@ -16,7 +17,7 @@ pub struct AggregatingWindowFunc<K: Key, T: Data, BinA: Data, MemA: Data, OutT:
     width: Duration,
     slide: Duration,
     aggregator: fn(&K, Window, &MemA) -> OutT,
-    bin_merger: fn(&T, Option<&BinA>) -> BinA,
+    bin_merger: fn(&T, SystemTime, Option<&BinA>) -> BinA,
     in_memory_add: fn(Option<MemA>, BinA) -> MemA,
     in_memory_remove: fn(MemA, BinA) -> Option<MemA>,
     memory_view: HashMap<K, MemA>,
@@ -45,7 +46,7 @@ impl<K: Key, T: Data, BinA: Data, MemA: Data, OutT: Data>
         width: Duration,
         slide: Duration,
         aggregator: fn(&K, Window, &MemA) -> OutT,
-        bin_merger: fn(&T, Option<&BinA>) -> BinA,
+        bin_merger: fn(&T, SystemTime, Option<&BinA>) -> BinA,
         in_memory_add: fn(Option<MemA>, BinA) -> MemA,
         in_memory_remove: fn(MemA, BinA) -> Option<MemA>,
     ) -> Self {
@@ -83,6 +84,9 @@ impl<K: Key, T: Data, BinA: Data, MemA: Data, OutT: Data>
 
         let watermark = ctx.last_present_watermark();
         if watermark.is_some() && bin_start < self.bin_start(watermark.unwrap()) {
+            TaskCounters::DroppedLateRecords
+                .for_task(&ctx.task_info)
+                .inc();
             return;
         }
         self.state = match self.state {
@@ -101,7 +105,7 @@ impl<K: Key, T: Data, BinA: Data, MemA: Data, OutT: Data>
         let mut aggregating_map = ctx.state.get_time_key_map('a', watermark).await;
         let mut key = record.key.clone().unwrap();
         let bin_aggregate = aggregating_map.get(bin_start, &mut key);
-        let new_value = (self.bin_merger)(&record.value, bin_aggregate);
+        let new_value = (self.bin_merger)(&record.value, record.timestamp, bin_aggregate);
         aggregating_map.insert(bin_start, key, new_value);
     }
 
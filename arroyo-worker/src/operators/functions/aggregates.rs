@@ -0,0 +1,613 @@
+// Helpers for the SQL aggregate functions that don't fit in a single primitive value. Each
+// aggregate has a bin (built up from individual values, and combinable with other bins) and,
+// for sliding windows and updating aggregates, an in-memory state that bins can be added to
+// and removed from.
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+/// (count, mean, sum of squared differences from the mean), as in Welford's algorithm
+pub type VarianceState = (i64, f64, f64);
+
+pub fn variance_add(current: Option<VarianceState>, value: Option<f64>) -> VarianceState {
+    let (count, mean, m2) = current.unwrap_or_default();
+    let Some(value) = value.filter(|v| !v.is_nan()) else {
+        return (count, mean, m2);
+    };
+    let count = count + 1;
+    let delta = value - mean;
+    let new_mean = mean + delta / count as f64;
+    (count, new_mean, m2 + delta * (value - new_mean))
+}
+
+pub fn variance_combine(current: VarianceState, new: VarianceState) -> VarianceState {
+    let (count_a, mean_a, m2_a) = current;
+    let (count_b, mean_b, m2_b) = new;
+    if count_a == 0 {
+        return new;
+    }
+    if count_b == 0 {
+        return current;
+    }
+    let count = count_a + count_b;
+    let delta = mean_b - mean_a;
+    (
+        count,
+        mean_a + delta * count_b as f64 / count as f64,
+        m2_a + m2_b + delta * delta * (count_a as f64 * count_b as f64) / count as f64,
+    )
+}
+
+/// Inverse of [variance_combine], removing the values in `bin` from `current`
+pub fn variance_remove(current: VarianceState, bin: VarianceState) -> Option<VarianceState> {
+    let (count, mean, m2) = current;
+    let (count_b, mean_b, m2_b) = bin;
+    if count_b == 0 {
+        return Some(current);
+    }
+    let count_a = count - count_b;
+    if count_a <= 0 {
+        return Some(VarianceState::default());
+    }
+    let mean_a = (count as f64 * mean - count_b as f64 * mean_b) / count_a as f64;
+    let delta = mean_b - mean_a;
+    let m2_a = m2 - m2_b - delta * delta * (count_a as f64 * count_b as f64) / count as f64;
+    Some((count_a, mean_a, m2_a.max(0.0)))
+}
+
+pub fn variance(state: &VarianceState, sample: bool) -> Option<f64> {
+    let (count, _, m2) = *state;
+    let divisor = if sample { count - 1 } else { count };
+    if divisor <= 0 {
+        return None;
+    }
+    Some(m2 / divisor as f64)
+}
+
+// Quantile sketches, following DDSketch: values are counted in logarithmically-sized buckets,
+// which gives quantiles with a bounded relative error. Unlike a t-digest, counts can be
+// subtracted, so values can be removed again for sliding windows and retractions.
+// Buckets are keyed so that the key order matches the order of the values: zero is 0,
+// positive values are above BUCKET_OFFSET and negative values are below -BUCKET_OFFSET.
+const RELATIVE_ACCURACY: f64 = 0.01;
+const BUCKET_OFFSET: i32 = 1 << 16;
+
+fn gamma() -> f64 {
+    (1.0 + RELATIVE_ACCURACY) / (1.0 - RELATIVE_ACCURACY)
+}
+
+fn sketch_key(value: f64) -> i32 {
+    if value == 0.0 {
+        return 0;
+    }
+    let index = (value.abs().ln() / gamma().ln()).ceil();
+    let index = index.clamp(-(BUCKET_OFFSET - 1) as f64, (BUCKET_OFFSET - 1) as f64) as i32;
+    if value > 0.0 {
+        index + BUCKET_OFFSET
+    } else {
+        -(index + BUCKET_OFFSET)
+    }
+}
+
+fn sketch_value(key: i32) -> f64 {
+    if key == 0 {
+        return 0.0;
+    }
+    let gamma = gamma();
+    let value = 2.0 * gamma.powi(key.abs() - BUCKET_OFFSET) / (gamma + 1.0);
+    if key > 0 {
+        value
+    } else {
+        -value
+    }
+}
+
+pub fn sketch_add(current: Option<BTreeMap<i32, i64>>, value: Option<f64>) -> BTreeMap<i32, i64> {
+    let mut sketch = current.unwrap_or_default();
+    if let Some(value) = value.filter(|v| !v.is_nan()) {
+        *sketch.entry(sketch_key(value)).or_default() += 1;
+    }
+    sketch
+}
+
+pub fn sketch_combine(
+    mut current: BTreeMap<i32, i64>,
+    new: BTreeMap<i32, i64>,
+) -> BTreeMap<i32, i64> {
+    for (key, count) in new {
+        *current.entry(key).or_default() += count;
+    }
+    current
+}
+
+pub fn sketch_remove(
+    mut current: BTreeMap<i32, i64>,
+    bin: BTreeMap<i32, i64>,
+) -> Option<BTreeMap<i32, i64>> {
+    for (key, count) in bin {
+        if let Some(current_count) = current.get_mut(&key) {
+            *current_count -= count;
+            if *current_count <= 0 {
+                current.remove(&key);
+            }
+        }
+    }
+    Some(current)
+}
+
+/// Returns the approximate `quantile` (between 0 and 1) of the values in the sketch
+pub fn sketch_quantile(sketch: &BTreeMap<i32, i64>, quantile: f64) -> Option<f64> {
+    let total: i64 = sketch.values().sum();
+    if total == 0 {
+        return None;
+    }
+    let rank = quantile * (total - 1) as f64;
+    let mut seen = 0;
+    for (key, count) in sketch {
+        seen += count;
+        if seen as f64 > rank {
+            return Some(sketch_value(*key));
+        }
+    }
+    sketch.keys().last().map(|key| sketch_value(*key))
+}
+
+// first_value and last_value keep the key the values are ordered by (the event time, unless
+// there's an ORDER BY in the aggregate), so that bins can be combined regardless of the order
+// records arrive in.
+pub fn first_value_add<K: Ord, T>(current: Option<(K, T)>, key: K, value: T) -> (K, T) {
+    match current {
+        Some(current) if current.0 <= key => current,
+        _ => (key, value),
+    }
+}
+
+pub fn last_value_add<K: Ord, T>(current: Option<(K, T)>, key: K, value: T) -> (K, T) {
+    match current {
+        Some(current) if current.0 > key => current,
+        _ => (key, value),
+    }
+}
+
+pub fn first_value_combine<K: Ord, T>(current: (K, T), new: (K, T)) -> (K, T) {
+    if new.0 < current.0 {
+        new
+    } else {
+        current
+    }
+}
+
+pub fn last_value_combine<K: Ord, T>(current: (K, T), new: (K, T)) -> (K, T) {
+    if new.0 >= current.0 {
+        new
+    } else {
+        current
+    }
+}
+
+pub fn keyed_value_memory_add<K: Ord, T>(
+    current: Option<BTreeMap<K, Vec<T>>>,
+    bin: (K, T),
+) -> BTreeMap<K, Vec<T>> {
+    keyed_values_add(current, bin.0, Some(bin.1))
+}
+
+pub fn keyed_value_memory_remove<K: Ord + Clone, T: PartialEq>(
+    mut current: BTreeMap<K, Vec<T>>,
+    bin: (K, T),
+) -> Option<BTreeMap<K, Vec<T>>> {
+    let (key, value) = bin;
+    // retractions may not carry the timestamp of the original value, so fall back to
+    // removing the value from wherever it is.
+    let key = if current
+        .get(&key)
+        .is_some_and(|values| values.contains(&value))
+    {
+        Some(key)
+    } else {
+        current
+            .iter()
+            .find(|(_, values)| values.contains(&value))
+            .map(|(key, _)| key.clone())
+    };
+    if let Some(key) = key {
+        let values = current.get_mut(&key).unwrap();
+        let position = values.iter().position(|v| *v == value).unwrap();
+        values.remove(position);
+        if values.is_empty() {
+            current.remove(&key);
+        }
+    }
+    Some(current)
+}
+
+pub fn first_value_memory<K: Ord, T: Clone>(memory: &BTreeMap<K, Vec<T>>) -> Option<T> {
+    memory
+        .first_key_value()
+        .and_then(|(_, values)| values.first())
+        .cloned()
+}
+
+pub fn last_value_memory<K: Ord, T: Clone>(memory: &BTreeMap<K, Vec<T>>) -> Option<T> {
+    memory
+        .last_key_value()
+        .and_then(|(_, values)| values.last())
+        .cloned()
+}
+
+// Aggregates with an ORDER BY (array_agg and string_agg) keep their values keyed by the sort key
+pub fn keyed_values_add<K: Ord, T>(
+    current: Option<BTreeMap<K, Vec<T>>>,
+    key: K,
+    value: Option<T>,
+) -> BTreeMap<K, Vec<T>> {
+    let mut values = current.unwrap_or_default();
+    if let Some(value) = value {
+        values.entry(key).or_default().push(value);
+    }
+    values
+}
+
+pub fn keyed_values_combine<K: Ord, T>(
+    mut current: BTreeMap<K, Vec<T>>,
+    new: BTreeMap<K, Vec<T>>,
+) -> BTreeMap<K, Vec<T>> {
+    for (key, values) in new {
+        current.entry(key).or_default().extend(values);
+    }
+    current
+}
+
+pub fn keyed_values_remove<K: Ord + Clone, T: PartialEq>(
+    current: BTreeMap<K, Vec<T>>,
+    bin: BTreeMap<K, Vec<T>>,
+) -> Option<BTreeMap<K, Vec<T>>> {
+    bin.into_iter()
+        .flat_map(|(key, values)| values.into_iter().map(move |value| (key.clone(), value)))
+        .try_fold(current, keyed_value_memory_remove)
+}
+
+pub fn keyed_values<K, T: Clone>(values: &BTreeMap<K, Vec<T>>) -> Vec<T> {
+    values.values().flatten().cloned().collect()
+}
+
+pub fn array_agg_add<T>(current: Option<Vec<T>>, value: T) -> Vec<T> {
+    let mut values = current.unwrap_or_default();
+    values.push(value);
+    values
+}
+
+pub fn list_add_non_null<T>(current: Option<Vec<T>>, value: Option<T>) -> Vec<T> {
+    let mut values = current.unwrap_or_default();
+    values.extend(value);
+    values
+}
+
+pub fn list_combine<T>(mut current: Vec<T>, new: Vec<T>) -> Vec<T> {
+    current.extend(new);
+    current
+}
+
+pub fn list_memory_add<T>(current: Option<Vec<Vec<T>>>, bin: Vec<T>) -> Vec<Vec<T>> {
+    let mut memory = current.unwrap_or_default();
+    memory.push(bin);
+    memory
+}
+
+pub fn list_memory_remove<T: PartialEq>(
+    mut current: Vec<Vec<T>>,
+    bin: Vec<T>,
+) -> Option<Vec<Vec<T>>> {
+    if let Some(position) = current.iter().position(|b| *b == bin) {
+        current.remove(position);
+    }
+    Some(current)
+}
+
+pub fn array_agg_memory<T: Clone>(memory: &[Vec<T>]) -> Vec<T> {
+    memory.concat()
+}
+
+pub fn string_agg(values: &[String], delimiter: &str) -> Option<String> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.join(delimiter))
+    }
+}
+
+pub fn string_agg_memory(memory: &[Vec<String>], delimiter: &str) -> Option<String> {
+    string_agg(&memory.concat(), delimiter)
+}
+
+// approx_distinct is backed by a HyperLogLog sketch with 2^HLL_PRECISION one-byte registers,
+// for a standard error of about 1.6%. Sketches are merged by taking the maximum of each
+// register, which can't be undone, so the in-memory state keeps the sketches of each bin.
+const HLL_PRECISION: u32 = 12;
+const HLL_REGISTERS: usize = 1 << HLL_PRECISION;
+
+// Updating aggregates keep a sketch per record, so sketches with few non-zero registers are
+// stored sparsely as (index: u16, rank: u8) entries sorted by index, and only become dense
+// once the entries would take more than a few hundred bytes. A dense sketch is exactly
+// HLL_REGISTERS bytes long, which is never a multiple of the entry size.
+const HLL_SPARSE_ENTRY: usize = 3;
+const HLL_MAX_SPARSE_ENTRIES: usize = HLL_REGISTERS / 32;
+
+// The registers are stored in checkpoints, so values need a hash that is stable across
+// builds, unlike the std DefaultHasher. This is FNV-1a with a final avalanche step, as the
+// sketch relies on the high bits of the hash being well distributed.
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = self.0;
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+        hash ^ (hash >> 33)
+    }
+}
+
+pub fn hll_add<T: Hash>(current: Option<Vec<u8>>, value: Option<T>) -> Vec<u8> {
+    let mut registers = current.unwrap_or_default();
+    if let Some(value) = value {
+        let mut hasher = StableHasher::default();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rank = ((hash << HLL_PRECISION) | (1 << (HLL_PRECISION - 1))).leading_zeros() + 1;
+        hll_set_register(&mut registers, index, rank as u8);
+    }
+    registers
+}
+
+fn hll_is_dense(registers: &[u8]) -> bool {
+    registers.len() == HLL_REGISTERS
+}
+
+/// The (index, rank) pairs of the non-zero registers of a sketch
+fn hll_entries(registers: &[u8]) -> Vec<(usize, u8)> {
+    if hll_is_dense(registers) {
+        registers
+            .iter()
+            .enumerate()
+            .filter(|(_, rank)| **rank > 0)
+            .map(|(index, rank)| (index, *rank))
+            .collect()
+    } else {
+        registers
+            .chunks_exact(HLL_SPARSE_ENTRY)
+            .map(|entry| (u16::from_be_bytes([entry[0], entry[1]]) as usize, entry[2]))
+            .collect()
+    }
+}
+
+fn hll_set_register(registers: &mut Vec<u8>, index: usize, rank: u8) {
+    if hll_is_dense(registers) {
+        registers[index] = registers[index].max(rank);
+        return;
+    }
+
+    let entries = registers.len() / HLL_SPARSE_ENTRY;
+    let (mut low, mut high) = (0, entries);
+    while low < high {
+        let mid = (low + high) / 2;
+        let offset = mid * HLL_SPARSE_ENTRY;
+        let mid_index = u16::from_be_bytes([registers[offset], registers[offset + 1]]) as usize;
+        match mid_index.cmp(&index) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => {
+                registers[offset + 2] = registers[offset + 2].max(rank);
+                return;
+            }
+        }
+    }
+
+    if entries < HLL_MAX_SPARSE_ENTRIES {
+        let [high_byte, low_byte] = (index as u16).to_be_bytes();
+        let offset = low * HLL_SPARSE_ENTRY;
+        registers.splice(offset..offset, [high_byte, low_byte, rank]);
+    } else {
+        let mut dense = vec![0; HLL_REGISTERS];
+        for (index, rank) in hll_entries(registers) {
+            dense[index] = rank;
+        }
+        dense[index] = rank;
+        *registers = dense;
+    }
+}
+
+pub fn hll_combine(mut current: Vec<u8>, mut new: Vec<u8>) -> Vec<u8> {
+    // merge the smaller sketch into the larger one, which keeps dense sketches dense
+    if current.len() < new.len() {
+        std::mem::swap(&mut current, &mut new);
+    }
+    for (index, rank) in hll_entries(&new) {
+        hll_set_register(&mut current, index, rank);
+    }
+    current
+}
+
+pub fn hll_estimate(registers: &[u8]) -> u64 {
+    if registers.is_empty() {
+        return 0;
+    }
+    let m = HLL_REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let entries = hll_entries(registers);
+    // each zero register contributes 2^0 to the sum
+    let zeros = HLL_REGISTERS - entries.len();
+    let sum: f64 = zeros as f64
+        + entries
+            .iter()
+            .map(|(_, r)| 2f64.powi(-(*r as i32)))
+            .sum::<f64>();
+    let estimate = alpha * m * m / sum;
+    // small cardinalities are better estimated by linear counting of the empty registers
+    if estimate <= 2.5 * m && zeros > 0 {
+        (m * (m / zeros as f64).ln()).round() as u64
+    } else {
+        estimate.round() as u64
+    }
+}
+
+pub fn hll_memory_estimate(memory: &[Vec<u8>]) -> u64 {
+    let registers = memory.iter().cloned().fold(Vec::new(), hll_combine);
+    hll_estimate(&registers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_variance() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let left = values[..3]
+            .iter()
+            .fold(None, |state, v| Some(variance_add(state, Some(*v))))
+            .unwrap();
+        let right = values[3..]
+            .iter()
+            .fold(None, |state, v| Some(variance_add(state, Some(*v))))
+            .unwrap();
+        let right = variance_add(Some(right), None);
+
+        let combined = variance_combine(left, right);
+        assert_eq!(combined.0, 8);
+        assert!((variance(&combined, false).unwrap() - 4.0).abs() < 1e-9);
+        assert!((variance(&combined, true).unwrap() - 32.0 / 7.0).abs() < 1e-9);
+
+        let removed = variance_remove(combined, left).unwrap();
+        assert_eq!(removed.0, right.0);
+        assert!((removed.1 - right.1).abs() < 1e-9);
+        assert!((removed.2 - right.2).abs() < 1e-9);
+
+        assert_eq!(variance(&variance_add(None, Some(1.0)), true), None);
+        assert_eq!(variance(&VarianceState::default(), false), None);
+    }
+
+    #[test]
+    fn test_sketch() {
+        let sketch = (1..=1000).fold(BTreeMap::new(), |sketch, v| {
+            sketch_add(Some(sketch), Some(v as f64))
+        });
+        let median = sketch_quantile(&sketch, 0.5).unwrap();
+        assert!((median - 500.0).abs() / 500.0 <= RELATIVE_ACCURACY);
+        let p99 = sketch_quantile(&sketch, 0.99).unwrap();
+        assert!((p99 - 990.0).abs() / 990.0 <= RELATIVE_ACCURACY);
+
+        let negative = sketch_add(Some(sketch_add(None, Some(-10.0))), Some(0.0));
+        assert!((sketch_quantile(&negative, 0.0).unwrap() + 10.0).abs() <= 0.1);
+        assert_eq!(sketch_quantile(&negative, 1.0), Some(0.0));
+
+        let combined = sketch_combine(sketch.clone(), negative.clone());
+        assert_eq!(sketch_remove(combined, negative), Some(sketch));
+        assert_eq!(sketch_quantile(&BTreeMap::new(), 0.5), None);
+    }
+
+    #[test]
+    fn test_first_and_last_value() {
+        let t = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        let first = first_value_add(Some(first_value_add(None, t(5), "b")), t(2), "a");
+        let last = last_value_add(Some(last_value_add(None, t(5), "b")), t(2), "a");
+        assert_eq!(first, (t(2), "a"));
+        assert_eq!(last, (t(5), "b"));
+        assert_eq!(first_value_combine(last, first), first);
+        assert_eq!(last_value_combine(last, first), last);
+
+        let memory = keyed_value_memory_add(Some(keyed_value_memory_add(None, first)), last);
+        assert_eq!(first_value_memory(&memory), Some("a"));
+        assert_eq!(last_value_memory(&memory), Some("b"));
+
+        let memory = keyed_value_memory_remove(memory, (t(10), "a")).unwrap();
+        assert_eq!(first_value_memory(&memory), Some("b"));
+    }
+
+    #[test]
+    fn test_keyed_values() {
+        let left = keyed_values_add(Some(keyed_values_add(None, 3, Some("c"))), 1, Some("a"));
+        let right = keyed_values_add(Some(keyed_values_add(None, 2, Some("b"))), 4, None);
+        let combined = keyed_values_combine(left.clone(), right.clone());
+        assert_eq!(keyed_values(&combined), vec!["a", "b", "c"]);
+
+        let memory = keyed_values_combine(BTreeMap::new(), combined);
+        let memory = keyed_values_remove(memory, left).unwrap();
+        assert_eq!(memory, right);
+    }
+
+    #[test]
+    fn test_lists() {
+        let memory = list_memory_add(None, vec!["a".to_string(), "b".to_string()]);
+        let memory = list_memory_add(Some(memory), vec!["c".to_string()]);
+        assert_eq!(
+            string_agg_memory(&memory, ", "),
+            Some("a, b, c".to_string())
+        );
+
+        let memory = list_memory_remove(memory, vec!["a".to_string(), "b".to_string()]).unwrap();
+        assert_eq!(array_agg_memory(&memory), vec!["c".to_string()]);
+        assert_eq!(string_agg(&[], ","), None);
+    }
+
+    #[test]
+    fn test_hll() {
+        let left = (0..5000).fold(Vec::new(), |hll, v| hll_add(Some(hll), Some(v)));
+        let right = (2500..10000).fold(Vec::new(), |hll, v| hll_add(Some(hll), Some(v)));
+        let right = hll_add(Some(right), None::<i64>);
+
+        let estimate = hll_estimate(&left) as f64;
+        assert!((estimate - 5000.0).abs() / 5000.0 < 0.05);
+
+        let memory = list_memory_add(Some(list_memory_add(None, left.clone())), right);
+        let estimate = hll_memory_estimate(&memory) as f64;
+        assert!((estimate - 10000.0).abs() / 10000.0 < 0.05);
+
+        let memory = list_memory_remove(memory, left.clone()).unwrap();
+        let estimate = hll_memory_estimate(&memory) as f64;
+        assert!((estimate - 7500.0).abs() / 7500.0 < 0.05);
+
+        let small = ["a", "b", "c", "a"]
+            .iter()
+            .fold(Vec::new(), |hll, v| hll_add(Some(hll), Some(v.to_string())));
+        assert_eq!(hll_estimate(&small), 3);
+        assert_eq!(hll_estimate(&hll_add(None, None::<String>)), 0);
+
+        // small sketches stay sparse, and give the same estimates as dense ones
+        assert_eq!(small.len(), 3 * HLL_SPARSE_ENTRY);
+        assert_eq!(hll_add(None, Some(1)).len(), HLL_SPARSE_ENTRY);
+        assert_eq!(left.len(), HLL_REGISTERS);
+        let mut dense = vec![0; HLL_REGISTERS];
+        for (index, rank) in hll_entries(&small) {
+            dense[index] = rank;
+        }
+        assert_eq!(hll_combine(dense.clone(), small.clone()), dense);
+        assert_eq!(hll_combine(small.clone(), dense.clone()), dense);
+        assert_eq!(hll_estimate(&dense), 3);
+
+        // the per-record sketches of an updating aggregate merge to the same registers
+        let per_record: Vec<_> = (0..5000).map(|v| hll_add(None, Some(v))).collect();
+        assert_eq!(
+            per_record.iter().cloned().fold(Vec::new(), hll_combine),
+            left
+        );
+        assert_eq!(hll_memory_estimate(&per_record), hll_estimate(&left));
+    }
+}
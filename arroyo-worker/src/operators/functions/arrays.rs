@@ -0,0 +1,163 @@
+// Helpers for the SQL array functions. Indices are 1-based, as in SQL, and negative indices
+// count back from the end of the array.
+
+/// Converts a 1-based (or negative) SQL index into an offset into an array of length `len`
+fn offset(index: i64, len: usize) -> Option<usize> {
+    match index {
+        0 => None,
+        i if i > 0 => Some(i as usize - 1),
+        i => len.checked_sub(i.unsigned_abs() as usize),
+    }
+}
+
+pub fn append<T>(mut array: Vec<T>, element: T) -> Vec<T> {
+    array.push(element);
+    array
+}
+
+pub fn prepend<T>(element: T, mut array: Vec<T>) -> Vec<T> {
+    array.insert(0, element);
+    array
+}
+
+pub fn has_all<T: PartialEq>(array: Vec<T>, elements: Vec<T>) -> bool {
+    elements.iter().all(|e| array.contains(e))
+}
+
+pub fn has_any<T: PartialEq>(array: Vec<T>, elements: Vec<T>) -> bool {
+    elements.iter().any(|e| array.contains(e))
+}
+
+pub fn position<T: PartialEq>(array: Vec<T>, element: T, from: i64) -> Option<u64> {
+    let start = offset(from, array.len())?;
+    array
+        .iter()
+        .skip(start)
+        .position(|e| *e == element)
+        .map(|i| (start + i + 1) as u64)
+}
+
+pub fn positions<T: PartialEq>(array: Vec<T>, element: T) -> Vec<u64> {
+    array
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| **e == element)
+        .map(|(i, _)| i as u64 + 1)
+        .collect()
+}
+
+pub fn element<T>(array: Vec<T>, index: i64) -> Option<T> {
+    let offset = offset(index, array.len())?;
+    array.into_iter().nth(offset)
+}
+
+pub fn pop_back<T>(mut array: Vec<T>) -> Vec<T> {
+    array.pop();
+    array
+}
+
+/// Removes the first `n` occurrences of `element`
+pub fn remove<T: PartialEq>(array: Vec<T>, element: T, n: i64) -> Vec<T> {
+    let mut remaining = n;
+    array
+        .into_iter()
+        .filter(|e| {
+            if remaining > 0 && *e == element {
+                remaining -= 1;
+                false
+            } else {
+                true
+            }
+        })
+        .collect()
+}
+
+/// Replaces the first `n` occurrences of `from` with `to`
+pub fn replace<T: PartialEq + Clone>(array: Vec<T>, from: T, to: T, n: i64) -> Vec<T> {
+    let mut remaining = n;
+    array
+        .into_iter()
+        .map(|e| {
+            if remaining > 0 && e == from {
+                remaining -= 1;
+                to.clone()
+            } else {
+                e
+            }
+        })
+        .collect()
+}
+
+pub fn repeat<T: Clone>(element: T, count: i64) -> Vec<T> {
+    vec![element; count.max(0) as usize]
+}
+
+/// Returns the elements between `from` and `to`, inclusive
+pub fn slice<T>(array: Vec<T>, from: i64, to: i64) -> Vec<T> {
+    let len = array.len();
+    let start = offset(from, len).unwrap_or(0);
+    let Some(end) = offset(to, len) else {
+        return vec![];
+    };
+
+    array.into_iter().take(end + 1).skip(start).collect()
+}
+
+pub fn to_string(array: Vec<Option<String>>, delimiter: String, null: Option<String>) -> String {
+    array
+        .into_iter()
+        .filter_map(|e| e.or_else(|| null.clone()))
+        .collect::<Vec<_>>()
+        .join(&delimiter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indices() {
+        let array = vec![1, 2, 3, 2, 1];
+
+        assert_eq!(element(array.clone(), 2), Some(2));
+        assert_eq!(element(array.clone(), -1), Some(1));
+        assert_eq!(element(array.clone(), 0), None);
+        assert_eq!(element(array.clone(), 6), None);
+
+        assert_eq!(slice(array.clone(), 2, 4), vec![2, 3, 2]);
+        assert_eq!(slice(array.clone(), -2, -1), vec![2, 1]);
+        assert_eq!(slice(array.clone(), 4, 2), Vec::<i32>::new());
+        assert_eq!(slice(array.clone(), 3, 100), vec![3, 2, 1]);
+
+        assert_eq!(position(array.clone(), 2, 1), Some(2));
+        assert_eq!(position(array.clone(), 2, 3), Some(4));
+        assert_eq!(position(array.clone(), 5, 1), None);
+        assert_eq!(positions(array, 1), vec![1, 5]);
+    }
+
+    #[test]
+    fn test_remove_and_replace() {
+        let array = vec![Some(1), None, Some(1), Some(1)];
+
+        assert_eq!(
+            remove(array.clone(), Some(1), 1),
+            vec![None, Some(1), Some(1)]
+        );
+        assert_eq!(remove(array.clone(), None, i64::MAX), vec![Some(1); 3]);
+        assert_eq!(
+            replace(array, Some(1), Some(2), 2),
+            vec![Some(2), None, Some(2), Some(1)]
+        );
+    }
+
+    #[test]
+    fn test_to_string() {
+        let array = vec![Some("a".to_string()), None, Some("c".to_string())];
+
+        assert_eq!(to_string(array.clone(), ",".to_string(), None), "a,c");
+        assert_eq!(
+            to_string(array, ", ".to_string(), Some("*".to_string())),
+            "a, *, c"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub mod aggregates;
+pub mod arrays;
 pub mod datetime;
 pub mod hash;
 pub mod json;
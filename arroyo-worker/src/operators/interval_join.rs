@@ -0,0 +1,321 @@
+use std::{
+    marker::PhantomData,
+    time::{Duration, SystemTime},
+};
+
+use arroyo_macro::{co_process_fn, StreamNode};
+use arroyo_rpc::grpc::{TableDeleteBehavior, TableDescriptor, TableType, TableWriteBehavior};
+use arroyo_state::tables::key_time_multi_map::KeyTimeMultiMap;
+use arroyo_types::*;
+
+use crate::engine::Context;
+
+/// Joins records of two streams whose event times are within bounds of each other, such that
+/// `left.timestamp + lower_bound <= right.timestamp <= left.timestamp + upper_bound`.
+///
+/// Each side only keeps the records that could still be matched by the other side, which are
+/// evicted as the watermark advances, so the results don't depend on when records arrive.
+#[derive(StreamNode)]
+pub struct IntervalJoin<K: Key, T1: Data, T2: Data, OutT: Data> {
+    lower_bound_micros: i64,
+    upper_bound_micros: i64,
+    merge_fn: Box<dyn Fn(&T1, &T2) -> OutT + Send>,
+    _t: PhantomData<K>,
+}
+
+fn offset(time: SystemTime, micros: i64) -> SystemTime {
+    if micros >= 0 {
+        time + Duration::from_micros(micros as u64)
+    } else {
+        time.checked_sub(Duration::from_micros(micros.unsigned_abs()))
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .max(SystemTime::UNIX_EPOCH)
+    }
+}
+
+#[co_process_fn(in_k1=K, in_t1=T1, in_k2=K, in_t2=T2, out_k=K, out_t=OutT)]
+impl<K: Key, T1: Data, T2: Data, OutT: Data> IntervalJoin<K, T1, T2, OutT> {
+    fn name(&self) -> String {
+        "IntervalJoin".to_string()
+    }
+
+    pub fn new(
+        lower_bound_micros: i64,
+        upper_bound_micros: i64,
+        merge_fn: Box<dyn Fn(&T1, &T2) -> OutT + Send>,
+    ) -> Self {
+        assert!(
+            lower_bound_micros <= upper_bound_micros,
+            "interval join lower bound must not be greater than its upper bound"
+        );
+        Self {
+            lower_bound_micros,
+            upper_bound_micros,
+            merge_fn,
+            _t: PhantomData,
+        }
+    }
+
+    fn tables(&self) -> Vec<TableDescriptor> {
+        vec![
+            TableDescriptor {
+                name: "l".to_string(),
+                description: "interval join left state".to_string(),
+                table_type: TableType::KeyTimeMultiMap as i32,
+                delete_behavior: TableDeleteBehavior::NoReadsBeforeWatermark as i32,
+                write_behavior: TableWriteBehavior::NoWritesBeforeWatermark as i32,
+                retention_micros: self.upper_bound_micros.max(0) as u64,
+            },
+            TableDescriptor {
+                name: "r".to_string(),
+                description: "interval join right state".to_string(),
+                table_type: TableType::KeyTimeMultiMap as i32,
+                delete_behavior: TableDeleteBehavior::NoReadsBeforeWatermark as i32,
+                write_behavior: TableWriteBehavior::NoWritesBeforeWatermark as i32,
+                retention_micros: (-self.lower_bound_micros).max(0) as u64,
+            },
+        ]
+    }
+
+    async fn process_left(&mut self, record: &Record<K, T1>, ctx: &mut Context<K, OutT>) {
+        if let Some(watermark) = ctx.last_present_watermark() {
+            if record.timestamp < watermark {
+                return;
+            }
+        };
+
+        let mut key = record.key.clone().unwrap();
+        let start = offset(record.timestamp, self.lower_bound_micros);
+        let end = offset(record.timestamp, self.upper_bound_micros);
+
+        let out_records: Vec<_> = {
+            let mut right_state: KeyTimeMultiMap<K, T2, _> =
+                ctx.state.get_key_time_multi_map('r').await;
+            right_state
+                .get_all_values_with_timestamps(&mut key)
+                .await
+                .map(|rows| {
+                    rows.filter(|(timestamp, _)| start <= *timestamp && *timestamp <= end)
+                        .map(|(timestamp, right)| Record {
+                            timestamp: record.timestamp.max(timestamp),
+                            key: Some(key.clone()),
+                            value: (self.merge_fn)(&record.value, right),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        for out in out_records {
+            ctx.collect(out).await;
+        }
+
+        let mut left_state: KeyTimeMultiMap<K, T1, _> = ctx.state.get_key_time_multi_map('l').await;
+        left_state
+            .insert(record.timestamp, key, record.value.clone())
+            .await;
+    }
+
+    async fn process_right(&mut self, record: &Record<K, T2>, ctx: &mut Context<K, OutT>) {
+        if let Some(watermark) = ctx.last_present_watermark() {
+            if record.timestamp < watermark {
+                return;
+            }
+        };
+
+        let mut key = record.key.clone().unwrap();
+        let start = offset(record.timestamp, -self.upper_bound_micros);
+        let end = offset(record.timestamp, -self.lower_bound_micros);
+
+        let out_records: Vec<_> = {
+            let mut left_state: KeyTimeMultiMap<K, T1, _> =
+                ctx.state.get_key_time_multi_map('l').await;
+            left_state
+                .get_all_values_with_timestamps(&mut key)
+                .await
+                .map(|rows| {
+                    rows.filter(|(timestamp, _)| start <= *timestamp && *timestamp <= end)
+                        .map(|(timestamp, left)| Record {
+                            timestamp: record.timestamp.max(timestamp),
+                            key: Some(key.clone()),
+                            value: (self.merge_fn)(left, &record.value),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        for out in out_records {
+            ctx.collect(out).await;
+        }
+
+        let mut right_state: KeyTimeMultiMap<K, T2, _> =
+            ctx.state.get_key_time_multi_map('r').await;
+        right_state
+            .insert(record.timestamp, key, record.value.clone())
+            .await;
+    }
+
+    async fn handle_watermark(&mut self, watermark: Watermark, ctx: &mut Context<K, OutT>) {
+        if let Watermark::EventTime(watermark) = watermark {
+            // records at or after the watermark can only match left records at or after
+            // `watermark - upper_bound` and right records at or after `watermark + lower_bound`
+            let mut left_state: KeyTimeMultiMap<K, T1, _> =
+                ctx.state.get_key_time_multi_map('l').await;
+            left_state
+                .expire_entries_before(offset(watermark, -self.upper_bound_micros))
+                .await;
+
+            let mut right_state: KeyTimeMultiMap<K, T2, _> =
+                ctx.state.get_key_time_multi_map('r').await;
+            right_state
+                .expire_entries_before(offset(watermark, self.lower_bound_micros))
+                .await;
+        }
+
+        ctx.broadcast(arroyo_types::Message::Watermark(watermark))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{offset, IntervalJoin};
+    use std::time::{Duration, SystemTime};
+
+    use arroyo_state::tables::key_time_multi_map::KeyTimeMultiMap;
+    use arroyo_types::{from_millis, to_millis, Message, Record, Watermark};
+    use tokio::sync::mpsc::Receiver;
+
+    use crate::engine::{Context, QueueItem};
+
+    type TestJoin = IntervalJoin<u32, String, String, String>;
+
+    // right records match left records from 5ms before them until 10ms after them
+    fn join() -> TestJoin {
+        IntervalJoin::new(
+            -5_000,
+            10_000,
+            Box::new(|left: &String, right: &String| format!("{}:{}", left, right)),
+        )
+    }
+
+    fn record(millis: u64, value: &str) -> Record<u32, String> {
+        Record {
+            timestamp: from_millis(millis),
+            key: Some(1),
+            value: value.to_string(),
+        }
+    }
+
+    fn drain(rx: &mut Receiver<QueueItem>) -> Vec<(u64, String)> {
+        let mut out = vec![];
+        while let Ok(item) = rx.try_recv() {
+            if let Message::Record(record) = Message::<u32, String>::from(item) {
+                out.push((to_millis(record.timestamp), record.value));
+            }
+        }
+        out
+    }
+
+    fn joined(rows: &[(u64, &str)]) -> Vec<(u64, String)> {
+        rows.iter()
+            .map(|(millis, value)| (*millis, value.to_string()))
+            .collect()
+    }
+
+    async fn stored(ctx: &mut Context<u32, String>, table: char) -> Vec<u64> {
+        let mut state: KeyTimeMultiMap<u32, String, _> =
+            ctx.state.get_key_time_multi_map(table).await;
+        state
+            .get_all_values_with_timestamps(&mut 1)
+            .await
+            .map(|rows| rows.map(|(timestamp, _)| to_millis(timestamp)).collect())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_bounds_are_inclusive() {
+        let mut op = join();
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        op.process_left(&record(100, "a"), &mut ctx).await;
+        for millis in [94, 95, 110, 111] {
+            op.process_right(&record(millis, &format!("r{}", millis)), &mut ctx)
+                .await;
+        }
+        assert_eq!(drain(&mut rx), joined(&[(100, "a:r95"), (110, "a:r110")]));
+
+        for millis in [194, 195, 210, 211] {
+            op.process_right(&record(millis, &format!("r{}", millis)), &mut ctx)
+                .await;
+        }
+        op.process_left(&record(200, "b"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), joined(&[(200, "b:r195"), (210, "b:r210")]));
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_arrival() {
+        let mut op = join();
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        op.process_right(&record(112, "x"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        op.process_left(&record(105, "a"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), joined(&[(112, "a:x")]));
+
+        // earlier than the previous left record, and too far before the right one
+        op.process_left(&record(100, "b"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        // earlier than every record seen so far
+        op.process_right(&record(96, "y"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), joined(&[(100, "b:y")]));
+    }
+
+    #[tokio::test]
+    async fn test_state_eviction() {
+        let mut op = join();
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        op.process_left(&record(100, "a"), &mut ctx).await;
+        op.process_left(&record(112, "b"), &mut ctx).await;
+        op.process_right(&record(96, "x"), &mut ctx).await;
+        op.process_right(&record(118, "y"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), joined(&[(100, "a:x"), (118, "b:y")]));
+
+        // future right records are at or after 115, so can only match left records from 105,
+        // and future left records can only match right records from 110
+        let watermark = Watermark::EventTime(from_millis(115));
+        ctx.watermarks.set(0, watermark);
+        op.handle_watermark(watermark, &mut ctx).await;
+        assert_eq!(stored(&mut ctx, 'l').await, vec![112]);
+        assert_eq!(stored(&mut ctx, 'r').await, vec![118]);
+
+        // records behind the watermark are dropped
+        op.process_right(&record(114, "late"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+        assert_eq!(stored(&mut ctx, 'r').await, vec![118]);
+
+        op.process_right(&record(116, "z"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), joined(&[(116, "b:z")]));
+
+        op.process_left(&record(120, "c"), &mut ctx).await;
+        assert_eq!(drain(&mut rx), joined(&[(120, "c:z"), (120, "c:y")]));
+    }
+
+    #[test]
+    fn test_offset() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+        assert_eq!(
+            offset(time, 5_000_000),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(65)
+        );
+        assert_eq!(
+            offset(time, -5_000_000),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(55)
+        );
+        assert_eq!(offset(time, -120_000_000), SystemTime::UNIX_EPOCH);
+    }
+}
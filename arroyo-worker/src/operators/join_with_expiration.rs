@@ -43,6 +43,17 @@ pub trait JoinProcessor<K: Key, T1: Data, T2: Data, Output: Data>: Send + 'stati
         left: Option<(SystemTime, &T1)>,
         right_count: usize,
     ) -> Option<(SystemTime, Output)>;
+
+    /// Whether `process_left` should be called for every matching right row. Semi and anti joins
+    /// only care whether any match exists, so they are called with at most the first one.
+    fn process_left_per_match(&self) -> bool {
+        true
+    }
+
+    /// Whether `process_right` should be called for every matching left row.
+    fn process_right_per_match(&self) -> bool {
+        true
+    }
 }
 
 pub trait IncomingDataProcessor<In: Data, Out: Data>: Send + 'static {
@@ -513,6 +524,216 @@ impl<K: Key, T1: Data, T2: Data> JoinProcessor<K, T1, T2, UpdatingData<(T1, T2)>
     }
 }
 
+/// Emits left rows that have at least one match on the right side.
+pub struct LeftSemiJoinProcessor<K: Key, T1: Data, T2: Data> {
+    pub(crate) _t: PhantomData<(K, T1, T2)>,
+}
+
+impl<K: Key, T1: Data, T2: Data> LeftSemiJoinProcessor<K, T1, T2> {
+    pub fn new() -> Self {
+        Self { _t: PhantomData }
+    }
+}
+
+impl<K: Key, T1: Data, T2: Data> JoinProcessor<K, T1, T2, UpdatingData<T1>>
+    for LeftSemiJoinProcessor<K, T1, T2>
+{
+    fn process_left(
+        &self,
+        left_timestamp: SystemTime,
+        left_update: UpdatingData<T1>,
+        right: Option<(SystemTime, &T2)>,
+        _left_count: usize,
+    ) -> Option<(SystemTime, UpdatingData<T1>)> {
+        right.map(|(right_timestamp, _)| (left_timestamp.max(right_timestamp), left_update))
+    }
+
+    fn process_right(
+        &self,
+        right_timestamp: SystemTime,
+        right_update: UpdatingData<T2>,
+        left: Option<(SystemTime, &T1)>,
+        right_count: usize,
+    ) -> Option<(SystemTime, UpdatingData<T1>)> {
+        let (left_timestamp, left) = left?;
+
+        let timestamp = left_timestamp.max(right_timestamp);
+
+        match right_update {
+            UpdatingData::Append(_) if right_count == 0 => {
+                Some((timestamp, UpdatingData::Append(left.clone())))
+            }
+            UpdatingData::Retract(_) if right_count == 1 => {
+                Some((timestamp, UpdatingData::Retract(left.clone())))
+            }
+            _ => None,
+        }
+    }
+
+    fn process_left_per_match(&self) -> bool {
+        false
+    }
+}
+
+/// Emits right rows that have at least one match on the left side.
+pub struct RightSemiJoinProcessor<K: Key, T1: Data, T2: Data> {
+    pub(crate) _t: PhantomData<(K, T1, T2)>,
+}
+
+impl<K: Key, T1: Data, T2: Data> RightSemiJoinProcessor<K, T1, T2> {
+    pub fn new() -> Self {
+        Self { _t: PhantomData }
+    }
+}
+
+impl<K: Key, T1: Data, T2: Data> JoinProcessor<K, T1, T2, UpdatingData<T2>>
+    for RightSemiJoinProcessor<K, T1, T2>
+{
+    fn process_left(
+        &self,
+        left_timestamp: SystemTime,
+        left_update: UpdatingData<T1>,
+        right: Option<(SystemTime, &T2)>,
+        left_count: usize,
+    ) -> Option<(SystemTime, UpdatingData<T2>)> {
+        let (right_timestamp, right) = right?;
+
+        let timestamp = left_timestamp.max(right_timestamp);
+
+        match left_update {
+            UpdatingData::Append(_) if left_count == 0 => {
+                Some((timestamp, UpdatingData::Append(right.clone())))
+            }
+            UpdatingData::Retract(_) if left_count == 1 => {
+                Some((timestamp, UpdatingData::Retract(right.clone())))
+            }
+            _ => None,
+        }
+    }
+
+    fn process_right(
+        &self,
+        right_timestamp: SystemTime,
+        right_update: UpdatingData<T2>,
+        left: Option<(SystemTime, &T1)>,
+        _right_count: usize,
+    ) -> Option<(SystemTime, UpdatingData<T2>)> {
+        left.map(|(left_timestamp, _)| (right_timestamp.max(left_timestamp), right_update))
+    }
+
+    fn process_right_per_match(&self) -> bool {
+        false
+    }
+}
+
+/// Emits left rows that have no match on the right side, retracting them once a match arrives.
+pub struct LeftAntiJoinProcessor<K: Key, T1: Data, T2: Data> {
+    pub(crate) _t: PhantomData<(K, T1, T2)>,
+}
+
+impl<K: Key, T1: Data, T2: Data> LeftAntiJoinProcessor<K, T1, T2> {
+    pub fn new() -> Self {
+        Self { _t: PhantomData }
+    }
+}
+
+impl<K: Key, T1: Data, T2: Data> JoinProcessor<K, T1, T2, UpdatingData<T1>>
+    for LeftAntiJoinProcessor<K, T1, T2>
+{
+    fn process_left(
+        &self,
+        left_timestamp: SystemTime,
+        left_update: UpdatingData<T1>,
+        right: Option<(SystemTime, &T2)>,
+        _left_count: usize,
+    ) -> Option<(SystemTime, UpdatingData<T1>)> {
+        match right {
+            Some(_) => None,
+            None => Some((left_timestamp, left_update)),
+        }
+    }
+
+    fn process_right(
+        &self,
+        right_timestamp: SystemTime,
+        right_update: UpdatingData<T2>,
+        left: Option<(SystemTime, &T1)>,
+        right_count: usize,
+    ) -> Option<(SystemTime, UpdatingData<T1>)> {
+        let (left_timestamp, left) = left?;
+
+        let timestamp = left_timestamp.max(right_timestamp);
+
+        match right_update {
+            UpdatingData::Append(_) if right_count == 0 => {
+                Some((timestamp, UpdatingData::Retract(left.clone())))
+            }
+            UpdatingData::Retract(_) if right_count == 1 => {
+                Some((timestamp, UpdatingData::Append(left.clone())))
+            }
+            _ => None,
+        }
+    }
+
+    fn process_left_per_match(&self) -> bool {
+        false
+    }
+}
+
+/// Emits right rows that have no match on the left side, retracting them once a match arrives.
+pub struct RightAntiJoinProcessor<K: Key, T1: Data, T2: Data> {
+    pub(crate) _t: PhantomData<(K, T1, T2)>,
+}
+
+impl<K: Key, T1: Data, T2: Data> RightAntiJoinProcessor<K, T1, T2> {
+    pub fn new() -> Self {
+        Self { _t: PhantomData }
+    }
+}
+
+impl<K: Key, T1: Data, T2: Data> JoinProcessor<K, T1, T2, UpdatingData<T2>>
+    for RightAntiJoinProcessor<K, T1, T2>
+{
+    fn process_left(
+        &self,
+        left_timestamp: SystemTime,
+        left_update: UpdatingData<T1>,
+        right: Option<(SystemTime, &T2)>,
+        left_count: usize,
+    ) -> Option<(SystemTime, UpdatingData<T2>)> {
+        let (right_timestamp, right) = right?;
+
+        let timestamp = left_timestamp.max(right_timestamp);
+
+        match left_update {
+            UpdatingData::Append(_) if left_count == 0 => {
+                Some((timestamp, UpdatingData::Retract(right.clone())))
+            }
+            UpdatingData::Retract(_) if left_count == 1 => {
+                Some((timestamp, UpdatingData::Append(right.clone())))
+            }
+            _ => None,
+        }
+    }
+
+    fn process_right(
+        &self,
+        right_timestamp: SystemTime,
+        right_update: UpdatingData<T2>,
+        left: Option<(SystemTime, &T1)>,
+        _right_count: usize,
+    ) -> Option<(SystemTime, UpdatingData<T2>)> {
+        match left {
+            Some(_) => None,
+            None => Some((right_timestamp, right_update)),
+        }
+    }
+
+    fn process_right_per_match(&self) -> bool {
+        false
+    }
+}
+
 #[co_process_fn(in_k1=K, in_t1=InT1, in_k2=K, in_t2=InT2, out_k=K, out_t=Output)]
 impl<
         K: Key,
@@ -603,7 +824,25 @@ impl<
             ctx.state.get_key_time_multi_map('r').await;
 
         let mut out_records = vec![];
-        if let Some(right_rows) = right_state.get_all_values_with_timestamps(&mut key).await {
+        if !self.processor.process_left_per_match() {
+            let first_right = match right_state.get_all_values_with_timestamps(&mut key).await {
+                Some(mut right_rows) => right_rows.next(),
+                None => None,
+            };
+            if let Some((timestamp, value)) = self.processor.process_left(
+                left_record.timestamp,
+                left_update.clone(),
+                first_right,
+                left_count,
+            ) {
+                out_records.push(Record {
+                    timestamp,
+                    key: Some(key.clone()),
+                    value,
+                });
+            }
+        } else if let Some(right_rows) = right_state.get_all_values_with_timestamps(&mut key).await
+        {
             out_records.extend(
                 right_rows
                     .filter_map(|(timestamp, value)| {
@@ -664,7 +903,24 @@ impl<
         let mut left_state: KeyTimeMultiMap<K, T1, _> = ctx.state.get_key_time_multi_map('l').await;
 
         let mut out_records = vec![];
-        if let Some(left_rows) = left_state.get_all_values_with_timestamps(&mut key).await {
+        if !self.processor.process_right_per_match() {
+            let first_left = match left_state.get_all_values_with_timestamps(&mut key).await {
+                Some(mut left_rows) => left_rows.next(),
+                None => None,
+            };
+            if let Some((timestamp, value)) = self.processor.process_right(
+                right_record.timestamp,
+                right_update.clone(),
+                first_left,
+                right_count,
+            ) {
+                out_records.push(Record {
+                    timestamp,
+                    key: Some(key.clone()),
+                    value,
+                });
+            }
+        } else if let Some(left_rows) = left_state.get_all_values_with_timestamps(&mut key).await {
             out_records.extend(
                 left_rows
                     .filter_map(|(timestamp, value)| {
@@ -728,3 +984,174 @@ impl<
             .await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use arroyo_types::{from_millis, Message, Record, UpdatingData};
+    use tokio::sync::mpsc::Receiver;
+
+    use crate::engine::{Context, QueueItem};
+    use crate::operators::joiners::{
+        left_anti_join_right_updating, left_semi_join_right_updating,
+        right_anti_join_left_updating, right_semi_join_left_updating,
+    };
+
+    fn record<T: arroyo_types::Data>(millis: u64, value: T) -> Record<u32, T> {
+        Record {
+            timestamp: from_millis(millis),
+            key: Some(1),
+            value,
+        }
+    }
+
+    fn append(s: &str) -> UpdatingData<String> {
+        UpdatingData::Append(s.to_string())
+    }
+
+    fn retract(s: &str) -> UpdatingData<String> {
+        UpdatingData::Retract(s.to_string())
+    }
+
+    fn drain(rx: &mut Receiver<QueueItem>) -> Vec<UpdatingData<String>> {
+        let mut out = vec![];
+        while let Ok(item) = rx.try_recv() {
+            if let Message::Record(record) = Message::<u32, UpdatingData<String>>::from(item) {
+                out.push(record.value);
+            }
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn test_left_semi_join() {
+        let mut op = left_semi_join_right_updating::<u32, String, String>(
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        // no match yet, so nothing is emitted
+        op.process_left(&record(1, "a".to_string()), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        // the first match emits the waiting left row; further matches don't duplicate it
+        op.process_right(&record(2, append("x")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![append("a")]);
+        op.process_right(&record(3, append("y")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        // a left row arriving with several matches is emitted once
+        op.process_left(&record(4, "b".to_string()), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![append("b")]);
+
+        // the left rows are only retracted once their last match is
+        op.process_right(&record(2, retract("x")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+        op.process_right(&record(3, retract("y")), &mut ctx).await;
+        let out = drain(&mut rx);
+        assert_eq!(out.len(), 2);
+        assert!(out.contains(&retract("a")));
+        assert!(out.contains(&retract("b")));
+    }
+
+    #[tokio::test]
+    async fn test_right_semi_join() {
+        let mut op = right_semi_join_left_updating::<u32, String, String>(
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        op.process_right(&record(1, "x".to_string()), &mut ctx)
+            .await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        op.process_left(&record(2, append("a")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![append("x")]);
+        op.process_left(&record(3, append("b")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        op.process_right(&record(4, "y".to_string()), &mut ctx)
+            .await;
+        assert_eq!(drain(&mut rx), vec![append("y")]);
+
+        op.process_left(&record(2, retract("a")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+        op.process_left(&record(3, retract("b")), &mut ctx).await;
+        let out = drain(&mut rx);
+        assert_eq!(out.len(), 2);
+        assert!(out.contains(&retract("x")));
+        assert!(out.contains(&retract("y")));
+    }
+
+    #[tokio::test]
+    async fn test_left_anti_join() {
+        let mut op = left_anti_join_right_updating::<u32, String, String>(
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        // left rows without a match are emitted immediately
+        op.process_left(&record(1, "a".to_string()), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![append("a")]);
+        op.process_left(&record(2, "b".to_string()), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![append("b")]);
+
+        // and retracted when the first match arrives
+        op.process_right(&record(3, append("x")), &mut ctx).await;
+        let out = drain(&mut rx);
+        assert_eq!(out.len(), 2);
+        assert!(out.contains(&retract("a")));
+        assert!(out.contains(&retract("b")));
+
+        op.process_right(&record(4, append("y")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        // a left row arriving while there are matches is never emitted
+        op.process_left(&record(5, "c".to_string()), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        // once the last match is retracted, the left rows are emitted again
+        op.process_right(&record(3, retract("x")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+        op.process_right(&record(4, retract("y")), &mut ctx).await;
+        let out = drain(&mut rx);
+        assert_eq!(out.len(), 3);
+        assert!(out.contains(&append("a")));
+        assert!(out.contains(&append("b")));
+        assert!(out.contains(&append("c")));
+    }
+
+    #[tokio::test]
+    async fn test_right_anti_join() {
+        let mut op = right_anti_join_left_updating::<u32, String, String>(
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        op.process_right(&record(1, "x".to_string()), &mut ctx)
+            .await;
+        assert_eq!(drain(&mut rx), vec![append("x")]);
+
+        op.process_left(&record(2, append("a")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![retract("x")]);
+        op.process_left(&record(3, append("b")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        op.process_right(&record(4, "y".to_string()), &mut ctx)
+            .await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        op.process_left(&record(2, retract("a")), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+        op.process_left(&record(3, retract("b")), &mut ctx).await;
+        let out = drain(&mut rx);
+        assert_eq!(out.len(), 2);
+        assert!(out.contains(&append("x")));
+        assert!(out.contains(&append("y")));
+    }
+}
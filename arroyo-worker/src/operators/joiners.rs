@@ -1,6 +1,7 @@
 use crate::operators::join_with_expiration::{
-    Coercer, FullJoinProcessor, InnerJoinProcessor, JoinWithExpiration, LeftJoinProcessor,
-    NoOpProcessor, RightJoinProcessor,
+    Coercer, FullJoinProcessor, InnerJoinProcessor, JoinWithExpiration, LeftAntiJoinProcessor,
+    LeftJoinProcessor, LeftSemiJoinProcessor, NoOpProcessor, RightAntiJoinProcessor,
+    RightJoinProcessor, RightSemiJoinProcessor,
 };
 use arroyo_types::*;
 use std::time::Duration;
@@ -276,3 +277,339 @@ pub fn full_join_both_updating<K: Key, T1: Data, T2: Data>(
 > {
     JoinWithExpiration::new(left_expiration, right_expiration, FullJoinProcessor::new())
 }
+
+pub fn left_semi_join<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    T1,
+    T2,
+    Coercer<T1>,
+    Coercer<T2>,
+    T1,
+    T2,
+    UpdatingData<T1>,
+    LeftSemiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        LeftSemiJoinProcessor::new(),
+    )
+}
+
+pub fn left_semi_join_left_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    UpdatingData<T1>,
+    T2,
+    NoOpProcessor<T1>,
+    Coercer<T2>,
+    T1,
+    T2,
+    UpdatingData<T1>,
+    LeftSemiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        LeftSemiJoinProcessor::new(),
+    )
+}
+
+pub fn left_semi_join_right_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    T1,
+    UpdatingData<T2>,
+    Coercer<T1>,
+    NoOpProcessor<T2>,
+    T1,
+    T2,
+    UpdatingData<T1>,
+    LeftSemiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        LeftSemiJoinProcessor::new(),
+    )
+}
+
+pub fn left_semi_join_both_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    UpdatingData<T1>,
+    UpdatingData<T2>,
+    NoOpProcessor<T1>,
+    NoOpProcessor<T2>,
+    T1,
+    T2,
+    UpdatingData<T1>,
+    LeftSemiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        LeftSemiJoinProcessor::new(),
+    )
+}
+
+pub fn right_semi_join<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    T1,
+    T2,
+    Coercer<T1>,
+    Coercer<T2>,
+    T1,
+    T2,
+    UpdatingData<T2>,
+    RightSemiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        RightSemiJoinProcessor::new(),
+    )
+}
+
+pub fn right_semi_join_left_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    UpdatingData<T1>,
+    T2,
+    NoOpProcessor<T1>,
+    Coercer<T2>,
+    T1,
+    T2,
+    UpdatingData<T2>,
+    RightSemiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        RightSemiJoinProcessor::new(),
+    )
+}
+
+pub fn right_semi_join_right_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    T1,
+    UpdatingData<T2>,
+    Coercer<T1>,
+    NoOpProcessor<T2>,
+    T1,
+    T2,
+    UpdatingData<T2>,
+    RightSemiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        RightSemiJoinProcessor::new(),
+    )
+}
+
+pub fn right_semi_join_both_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    UpdatingData<T1>,
+    UpdatingData<T2>,
+    NoOpProcessor<T1>,
+    NoOpProcessor<T2>,
+    T1,
+    T2,
+    UpdatingData<T2>,
+    RightSemiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        RightSemiJoinProcessor::new(),
+    )
+}
+
+pub fn left_anti_join<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    T1,
+    T2,
+    Coercer<T1>,
+    Coercer<T2>,
+    T1,
+    T2,
+    UpdatingData<T1>,
+    LeftAntiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        LeftAntiJoinProcessor::new(),
+    )
+}
+
+pub fn left_anti_join_left_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    UpdatingData<T1>,
+    T2,
+    NoOpProcessor<T1>,
+    Coercer<T2>,
+    T1,
+    T2,
+    UpdatingData<T1>,
+    LeftAntiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        LeftAntiJoinProcessor::new(),
+    )
+}
+
+pub fn left_anti_join_right_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    T1,
+    UpdatingData<T2>,
+    Coercer<T1>,
+    NoOpProcessor<T2>,
+    T1,
+    T2,
+    UpdatingData<T1>,
+    LeftAntiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        LeftAntiJoinProcessor::new(),
+    )
+}
+
+pub fn left_anti_join_both_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    UpdatingData<T1>,
+    UpdatingData<T2>,
+    NoOpProcessor<T1>,
+    NoOpProcessor<T2>,
+    T1,
+    T2,
+    UpdatingData<T1>,
+    LeftAntiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        LeftAntiJoinProcessor::new(),
+    )
+}
+
+pub fn right_anti_join<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    T1,
+    T2,
+    Coercer<T1>,
+    Coercer<T2>,
+    T1,
+    T2,
+    UpdatingData<T2>,
+    RightAntiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        RightAntiJoinProcessor::new(),
+    )
+}
+
+pub fn right_anti_join_left_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    UpdatingData<T1>,
+    T2,
+    NoOpProcessor<T1>,
+    Coercer<T2>,
+    T1,
+    T2,
+    UpdatingData<T2>,
+    RightAntiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        RightAntiJoinProcessor::new(),
+    )
+}
+
+pub fn right_anti_join_right_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    T1,
+    UpdatingData<T2>,
+    Coercer<T1>,
+    NoOpProcessor<T2>,
+    T1,
+    T2,
+    UpdatingData<T2>,
+    RightAntiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        RightAntiJoinProcessor::new(),
+    )
+}
+
+pub fn right_anti_join_both_updating<K: Key, T1: Data, T2: Data>(
+    left_expiration: Duration,
+    right_expiration: Duration,
+) -> JoinWithExpiration<
+    K,
+    UpdatingData<T1>,
+    UpdatingData<T2>,
+    NoOpProcessor<T1>,
+    NoOpProcessor<T2>,
+    T1,
+    T2,
+    UpdatingData<T2>,
+    RightAntiJoinProcessor<K, T1, T2>,
+> {
+    JoinWithExpiration::new(
+        left_expiration,
+        right_expiration,
+        RightAntiJoinProcessor::new(),
+    )
+}
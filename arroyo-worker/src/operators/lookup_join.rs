@@ -0,0 +1,532 @@
+use crate::engine::{Context, StreamNode};
+use arroyo_macro::process_fn;
+use arroyo_rpc::grpc::TableDescriptor;
+use arroyo_types::{CheckpointBarrier, Data, Key, Message, Record, Watermark};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::stream::FuturesOrdered;
+use futures::StreamExt;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+const MAX_LOOKUP_RETRIES: usize = 5;
+
+/// A connector that can fetch rows from an external table by key, used to enrich a stream
+/// without reading the whole table into state.
+#[async_trait]
+pub trait LookupConnector<V: Data>: Send + Sync + 'static {
+    /// Fetches the rows for a batch of keys, returning one entry per key in the same order;
+    /// keys that don't exist in the external table are returned as `None`.
+    async fn lookup(&self, keys: &[String]) -> anyhow::Result<Vec<Option<V>>>;
+}
+
+struct CacheEntry<V> {
+    value: Option<V>,
+    inserted: Instant,
+    last_used: u64,
+}
+
+/// An LRU cache of lookup results, where entries also expire after a fixed time-to-live.
+/// Misses are cached as well, so that keys missing from the external table aren't refetched
+/// for every record.
+pub struct LookupCache<V> {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<String, CacheEntry<V>>,
+    recency: BTreeMap<u64, String>,
+    counter: u64,
+}
+
+impl<V: Clone> LookupCache<V> {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            counter: 0,
+        }
+    }
+
+    /// Returns the cached result for the key, where `Some(None)` means the key is known to be
+    /// missing from the external table and `None` means it must be fetched.
+    pub fn get(&mut self, key: &str, now: Instant) -> Option<Option<V>> {
+        let entry = self.entries.get_mut(key)?;
+
+        if now.saturating_duration_since(entry.inserted) >= self.ttl {
+            self.recency.remove(&entry.last_used);
+            self.entries.remove(key);
+            return None;
+        }
+
+        self.counter += 1;
+        self.recency.remove(&entry.last_used);
+        entry.last_used = self.counter;
+        self.recency.insert(self.counter, key.to_string());
+
+        Some(entry.value.clone())
+    }
+
+    pub fn insert(&mut self, key: String, value: Option<V>, now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.counter += 1;
+        let entry = CacheEntry {
+            value,
+            inserted: now,
+            last_used: self.counter,
+        };
+
+        if let Some(previous) = self.entries.insert(key.clone(), entry) {
+            self.recency.remove(&previous.last_used);
+        }
+        self.recency.insert(self.counter, key);
+
+        while self.entries.len() > self.capacity {
+            let Some((_, evicted)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&evicted);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+type LookupFuture<V> = BoxFuture<'static, (usize, anyhow::Result<Vec<(String, Option<V>)>>)>;
+
+struct PendingBatch<K: Key, InT: Data, V: Data> {
+    records: Vec<Record<K, InT>>,
+    // results that were already in the cache when the batch was sent
+    cached: HashMap<String, Option<V>>,
+}
+
+/// Joins each record against an external table by calling a [LookupConnector] for its key.
+///
+/// Records are buffered into batches, which are sent once they reach `batch_size` or on the
+/// next tick, with at most `max_concurrency` batches in flight at once. Keys found in the cache
+/// are not refetched. Batches complete in order, and watermarks are held back until all of the
+/// records that arrived before them have been emitted.
+#[derive(StreamNode)]
+pub struct LookupJoinOperator<K: Key, InT: Data, V: Data, OutT: Data, C: LookupConnector<V>> {
+    connector: Arc<C>,
+    key_fn: Box<dyn Fn(&InT) -> Option<String> + Send>,
+    merge_fn: Box<dyn Fn(&InT, Option<&V>) -> OutT + Send>,
+    // whether records without a match are emitted (left join) or dropped (inner join)
+    emit_misses: bool,
+    cache: LookupCache<V>,
+    batch_size: usize,
+    max_concurrency: u64,
+
+    batch: Vec<Record<K, InT>>,
+    futures: FuturesOrdered<LookupFuture<V>>,
+    in_flight: VecDeque<PendingBatch<K, InT, V>>,
+    next_batch: usize,
+    completed_batches: usize,
+    // watermarks along with the number of batches that must complete before they can be sent
+    watermarks: VecDeque<(usize, Watermark)>,
+    _t: PhantomData<(K, OutT)>,
+}
+
+#[process_fn(in_k = K, in_t = InT, out_k = K, out_t = OutT, futures = "futures", tick_ms = 50)]
+impl<K: Key, InT: Data, V: Data, OutT: Data, C: LookupConnector<V>>
+    LookupJoinOperator<K, InT, V, OutT, C>
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        connector: C,
+        key_fn: Box<dyn Fn(&InT) -> Option<String> + Send>,
+        merge_fn: Box<dyn Fn(&InT, Option<&V>) -> OutT + Send>,
+        emit_misses: bool,
+        cache_max_rows: usize,
+        cache_ttl: Duration,
+        batch_size: usize,
+        max_concurrency: u64,
+    ) -> Self {
+        Self {
+            connector: Arc::new(connector),
+            key_fn,
+            merge_fn,
+            emit_misses,
+            cache: LookupCache::new(cache_max_rows, cache_ttl),
+            batch_size: batch_size.max(1),
+            max_concurrency: max_concurrency.max(1),
+            batch: vec![],
+            futures: FuturesOrdered::new(),
+            in_flight: VecDeque::new(),
+            next_batch: 0,
+            completed_batches: 0,
+            watermarks: VecDeque::new(),
+            _t: PhantomData,
+        }
+    }
+
+    fn name(&self) -> String {
+        "LookupJoin".to_string()
+    }
+
+    fn tables(&self) -> Vec<TableDescriptor> {
+        vec![arroyo_state::global_table(
+            "l",
+            "lookup join pending records",
+        )]
+    }
+
+    async fn on_start(&mut self, ctx: &mut Context<K, OutT>) {
+        let gs = ctx
+            .state
+            .get_global_keyed_state::<usize, Vec<Record<K, InT>>>('l')
+            .await;
+
+        let restored: Vec<_> = gs
+            .get_key_values()
+            .into_iter()
+            .filter(|(task_index, _)| {
+                **task_index % ctx.task_info.parallelism == ctx.task_info.task_index
+            })
+            .flat_map(|(_, records)| records.clone())
+            .collect();
+
+        self.batch.extend(restored);
+        self.flush();
+    }
+
+    async fn process_element(&mut self, record: &Record<K, InT>, _ctx: &mut Context<K, OutT>) {
+        self.batch.push(record.clone());
+        if self.batch.len() >= self.batch_size {
+            self.flush();
+        }
+    }
+
+    async fn handle_tick(&mut self, _: u64, _ctx: &mut Context<K, OutT>) {
+        self.flush();
+    }
+
+    async fn handle_watermark(&mut self, watermark: Watermark, ctx: &mut Context<K, OutT>) {
+        self.flush();
+        if self.in_flight.is_empty() && self.batch.is_empty() {
+            ctx.broadcast(Message::Watermark(watermark)).await;
+        } else {
+            // records that couldn't be sent yet will go out as the next batches
+            let unsent = self.batch.len().div_ceil(self.batch_size);
+            self.watermarks
+                .push_back((self.next_batch + unsent, watermark));
+        }
+    }
+
+    async fn handle_checkpoint(&mut self, _: &CheckpointBarrier, ctx: &mut Context<K, OutT>) {
+        let pending: Vec<_> = self
+            .in_flight
+            .iter()
+            .flat_map(|batch| batch.records.iter().cloned())
+            .chain(self.batch.iter().cloned())
+            .collect();
+
+        let mut gs = ctx
+            .state
+            .get_global_keyed_state::<usize, Vec<Record<K, InT>>>('l')
+            .await;
+        gs.insert(ctx.task_info.task_index, pending).await;
+    }
+
+    async fn on_close(
+        &mut self,
+        ctx: &mut Context<K, OutT>,
+        final_message: &Option<Message<K, OutT>>,
+    ) {
+        if let Some(Message::EndOfData) = final_message {
+            self.flush();
+            debug!(
+                "LookupJoin end of data with {} pending batches",
+                self.futures.len()
+            );
+            // completing a batch sends any records that were waiting for capacity
+            while let Some((id, result)) = self.futures.next().await {
+                self.handle_future(id, result, ctx).await;
+            }
+        }
+    }
+
+    /// Sends buffered records in batches of up to `batch_size`, as long as fewer than
+    /// `max_concurrency` batches are in flight
+    fn flush(&mut self) {
+        while !self.batch.is_empty() && self.futures.len() < self.max_concurrency as usize {
+            let count = self.batch.len().min(self.batch_size);
+            let records: Vec<_> = self.batch.drain(..count).collect();
+            self.send_batch(records);
+        }
+    }
+
+    /// Sends a batch, fetching any keys that aren't in the cache
+    fn send_batch(&mut self, records: Vec<Record<K, InT>>) {
+        let now = Instant::now();
+
+        let mut cached = HashMap::new();
+        let mut missing = vec![];
+        let mut seen = HashSet::new();
+        for key in records.iter().filter_map(|r| (self.key_fn)(&r.value)) {
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            match self.cache.get(&key, now) {
+                Some(value) => {
+                    cached.insert(key, value);
+                }
+                None => missing.push(key),
+            }
+        }
+
+        let id = self.next_batch;
+        self.next_batch += 1;
+
+        let connector = self.connector.clone();
+        self.futures.push_back(Box::pin(async move {
+            if missing.is_empty() {
+                return (id, Ok(vec![]));
+            }
+
+            let mut attempts = 0;
+            loop {
+                match connector.lookup(&missing).await {
+                    Ok(values) => {
+                        return (id, Ok(missing.into_iter().zip(values).collect()));
+                    }
+                    Err(e) if attempts < MAX_LOOKUP_RETRIES => {
+                        attempts += 1;
+                        warn!("lookup failed (attempt {}): {:?}", attempts, e);
+                        tokio::time::sleep(Duration::from_millis(100 * (1 << attempts))).await;
+                    }
+                    Err(e) => {
+                        return (id, Err(e));
+                    }
+                }
+            }
+        }));
+
+        self.in_flight.push_back(PendingBatch { records, cached });
+    }
+
+    async fn handle_future(
+        &mut self,
+        id: usize,
+        result: anyhow::Result<Vec<(String, Option<V>)>>,
+        ctx: &mut Context<K, OutT>,
+    ) {
+        let fetched = match result {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                ctx.report_error("Lookup failed", format!("{:?}", e)).await;
+                panic!("lookup for batch {} failed: {:?}", id, e);
+            }
+        };
+
+        let PendingBatch {
+            records,
+            mut cached,
+        } = self
+            .in_flight
+            .pop_front()
+            .expect("lookup completed without a pending batch");
+
+        let now = Instant::now();
+        for (key, value) in fetched {
+            self.cache.insert(key.clone(), value.clone(), now);
+            cached.insert(key, value);
+        }
+
+        for record in records {
+            let value = {
+                let right = (self.key_fn)(&record.value)
+                    .and_then(|key| cached.get(&key))
+                    .and_then(|value| value.as_ref());
+
+                if right.is_none() && !self.emit_misses {
+                    continue;
+                }
+
+                (self.merge_fn)(&record.value, right)
+            };
+
+            ctx.collector
+                .collect(Record {
+                    timestamp: record.timestamp,
+                    key: record.key,
+                    value,
+                })
+                .await;
+        }
+
+        self.completed_batches += 1;
+        while let Some((batch, _)) = self.watermarks.front() {
+            if *batch > self.completed_batches {
+                break;
+            }
+            let (_, watermark) = self.watermarks.pop_front().unwrap();
+            ctx.broadcast(Message::Watermark(watermark)).await;
+        }
+
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LookupCache, LookupConnector, LookupJoinOperator};
+    use crate::engine::{Context, QueueItem};
+    use arroyo_types::{from_millis, Message, Record, Watermark};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+    use tokio::sync::mpsc::Receiver;
+
+    struct MockLookup {
+        rows: HashMap<String, String>,
+    }
+
+    #[async_trait]
+    impl LookupConnector<String> for MockLookup {
+        async fn lookup(&self, keys: &[String]) -> anyhow::Result<Vec<Option<String>>> {
+            Ok(keys.iter().map(|k| self.rows.get(k).cloned()).collect())
+        }
+    }
+
+    type TestOperator =
+        LookupJoinOperator<(), String, String, (String, Option<String>), MockLookup>;
+
+    fn operator(emit_misses: bool, batch_size: usize, max_concurrency: u64) -> TestOperator {
+        LookupJoinOperator::new(
+            MockLookup {
+                rows: [("a".to_string(), "A".to_string())].into_iter().collect(),
+            },
+            Box::new(|v: &String| Some(v.clone())),
+            Box::new(|v: &String, right: Option<&String>| (v.clone(), right.cloned())),
+            emit_misses,
+            100,
+            Duration::from_secs(60),
+            batch_size,
+            max_concurrency,
+        )
+    }
+
+    fn record(value: &str) -> Record<(), String> {
+        Record {
+            timestamp: from_millis(1),
+            key: None,
+            value: value.to_string(),
+        }
+    }
+
+    fn drain(rx: &mut Receiver<QueueItem>) -> Vec<String> {
+        let mut out = vec![];
+        while let Ok(item) = rx.try_recv() {
+            match Message::<(), (String, Option<String>)>::from(item) {
+                Message::Record(r) => out.push(format!("{}={:?}", r.value.0, r.value.1)),
+                Message::Watermark(_) => out.push("watermark".to_string()),
+                _ => {}
+            }
+        }
+        out
+    }
+
+    async fn complete_next(op: &mut TestOperator, ctx: &mut Context<(), (String, Option<String>)>) {
+        let (id, result) = op.futures.next().await.expect("no lookup in flight");
+        op.handle_future(id, result, ctx).await;
+    }
+
+    #[tokio::test]
+    async fn test_inner_and_left_lookup_joins() {
+        for (emit_misses, expected) in [
+            (false, vec!["a=Some(\"A\")", "watermark"]),
+            (true, vec!["a=Some(\"A\")", "b=None", "watermark"]),
+        ] {
+            let mut op = operator(emit_misses, 10, 2);
+            let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+            op.process_element(&record("a"), &mut ctx).await;
+            op.process_element(&record("b"), &mut ctx).await;
+
+            // the watermark sends the batch, but can't be emitted until the batch completes
+            op.handle_watermark(Watermark::EventTime(from_millis(2)), &mut ctx)
+                .await;
+            assert_eq!(op.futures.len(), 1);
+            assert!(drain(&mut rx).is_empty());
+
+            complete_next(&mut op, &mut ctx).await;
+            assert_eq!(drain(&mut rx), expected);
+
+            // with no records outstanding, watermarks pass straight through
+            op.handle_watermark(Watermark::EventTime(from_millis(3)), &mut ctx)
+                .await;
+            assert_eq!(drain(&mut rx), vec!["watermark"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_join_concurrency_and_watermarks() {
+        let mut op = operator(true, 1, 1);
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        op.process_element(&record("a"), &mut ctx).await;
+        op.process_element(&record("b"), &mut ctx).await;
+
+        // only one batch may be in flight, so the second record waits
+        assert_eq!(op.futures.len(), 1);
+        assert_eq!(op.batch.len(), 1);
+
+        op.handle_watermark(Watermark::EventTime(from_millis(2)), &mut ctx)
+            .await;
+        assert_eq!(op.futures.len(), 1);
+        assert!(drain(&mut rx).is_empty());
+
+        // completing the first batch sends the second, and the watermark waits for it
+        complete_next(&mut op, &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec!["a=Some(\"A\")"]);
+        assert_eq!(op.futures.len(), 1);
+        assert!(op.batch.is_empty());
+
+        complete_next(&mut op, &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec!["b=None", "watermark"]);
+    }
+
+    #[test]
+    fn test_lookup_cache_evicts_least_recently_used() {
+        let now = Instant::now();
+        let mut cache = LookupCache::new(2, Duration::from_secs(60));
+
+        cache.insert("a".to_string(), Some(1), now);
+        cache.insert("b".to_string(), None, now);
+        assert_eq!(cache.get("a", now), Some(Some(1)));
+
+        cache.insert("c".to_string(), Some(3), now);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b", now), None);
+        assert_eq!(cache.get("a", now), Some(Some(1)));
+        assert_eq!(cache.get("c", now), Some(Some(3)));
+    }
+
+    #[test]
+    fn test_lookup_cache_expires_entries() {
+        let now = Instant::now();
+        let mut cache = LookupCache::new(10, Duration::from_secs(5));
+
+        cache.insert("a".to_string(), Some(1), now);
+        cache.insert("b".to_string(), None, now + Duration::from_secs(3));
+
+        let later = now + Duration::from_secs(6);
+        assert_eq!(cache.get("a", later), None);
+        assert_eq!(cache.get("b", later), Some(None));
+        assert_eq!(cache.len(), 1);
+    }
+}
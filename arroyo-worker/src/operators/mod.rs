@@ -22,14 +22,19 @@ use wasmtime::{
 pub mod aggregating_window;
 pub mod async_map;
 pub mod functions;
+pub mod interval_join;
 pub mod join_with_expiration;
 pub mod joiners;
 pub mod joins;
+pub mod lookup_join;
+pub mod process_function;
 pub mod sinks;
 pub mod sliding_top_n_aggregating_window;
+pub mod temporal_join;
 pub mod tumbling_aggregating_window;
 pub mod tumbling_top_n_window;
 pub mod updating_aggregate;
+pub mod wasm_udf;
 pub mod windows;
 
 #[cfg(test)]
@@ -0,0 +1,158 @@
+use std::{marker::PhantomData, time::SystemTime};
+
+use arroyo_macro::{process_fn, StreamNode};
+use arroyo_rpc::grpc::{TableDeleteBehavior, TableDescriptor, TableType, TableWriteBehavior};
+use arroyo_state::tables::keyed_map::KeyedState;
+use arroyo_types::*;
+
+use crate::engine::Context;
+
+pub type ProcessElementFn<T, S, OutT> =
+    Box<dyn Fn(&T, ProcessContext<S>) -> (ProcessContext<S>, Vec<OutT>) + Send>;
+pub type ProcessTimerFn<K, S, OutT> =
+    Box<dyn Fn(&K, ProcessContext<S>) -> (ProcessContext<S>, Vec<OutT>) + Send>;
+
+/// Runs a user-defined process function over a keyed stream, which keeps state for each key and
+/// may schedule event-time timers for it, emitting any number of records for each input record
+/// and fired timer.
+///
+/// The state of a key is only stored while the function leaves it set, and timers are delivered
+/// to the timer function once the watermark passes them.
+#[derive(StreamNode)]
+pub struct ProcessFunctionOperator<K: Key, T: Data, S: Data, OutT: Data> {
+    name: String,
+    process_fn: ProcessElementFn<T, S, OutT>,
+    timer_fn: Option<ProcessTimerFn<K, S, OutT>>,
+    _t: PhantomData<K>,
+}
+
+#[process_fn(in_k = K, in_t = T, out_k = K, out_t = OutT, timer_t = SystemTime)]
+impl<K: Key, T: Data, S: Data, OutT: Data> ProcessFunctionOperator<K, T, S, OutT> {
+    fn name(&self) -> String {
+        format!("process_function<{}>", self.name)
+    }
+
+    pub fn new(
+        name: String,
+        process_fn: ProcessElementFn<T, S, OutT>,
+        timer_fn: Option<ProcessTimerFn<K, S, OutT>>,
+    ) -> Self {
+        Self {
+            name,
+            process_fn,
+            timer_fn,
+            _t: PhantomData,
+        }
+    }
+
+    fn tables(&self) -> Vec<TableDescriptor> {
+        vec![TableDescriptor {
+            name: "s".to_string(),
+            description: "process function state".to_string(),
+            table_type: TableType::TimeKeyMap as i32,
+            delete_behavior: TableDeleteBehavior::None as i32,
+            write_behavior: TableWriteBehavior::DefaultWrites as i32,
+            retention_micros: 0,
+        }]
+    }
+
+    /// Builds the context for the key from its current state
+    async fn load_context(
+        key: K,
+        timestamp: SystemTime,
+        ctx: &mut Context<K, OutT>,
+    ) -> ProcessContext<S> {
+        let state: KeyedState<'_, K, S, _> = ctx.state.get_key_state('s').await;
+        ProcessContext::new(
+            state.get(&key).cloned(),
+            timestamp,
+            ctx.last_present_watermark(),
+        )
+    }
+
+    /// Writes back the state and timers that the function left in the context, and emits its
+    /// outputs for the key
+    async fn finish(
+        mut key: K,
+        had_state: bool,
+        process_context: ProcessContext<S>,
+        outputs: Vec<OutT>,
+        ctx: &mut Context<K, OutT>,
+    ) {
+        let timestamp = process_context.timestamp();
+        for time in process_context.timers().to_vec() {
+            ctx.schedule_timer(&mut key, time, time).await;
+        }
+
+        {
+            let mut state: KeyedState<'_, K, S, _> = ctx.state.get_key_state('s').await;
+            match process_context.into_state() {
+                Some(value) => state.insert(timestamp, key.clone(), value).await,
+                None if had_state => state.remove(&mut key).await,
+                None => {}
+            }
+        }
+
+        for value in outputs {
+            ctx.collect(Record {
+                timestamp,
+                key: Some(key.clone()),
+                value,
+            })
+            .await;
+        }
+    }
+
+    async fn process_element(&mut self, record: &Record<K, T>, ctx: &mut Context<K, OutT>) {
+        if let Some(watermark) = ctx.last_present_watermark() {
+            if record.timestamp < watermark {
+                return;
+            }
+        }
+
+        let key = record.key.clone().unwrap();
+        let process_context = Self::load_context(key.clone(), record.timestamp, ctx).await;
+        let had_state = process_context.state().is_some();
+        let (process_context, outputs) = (self.process_fn)(&record.value, process_context);
+        Self::finish(key, had_state, process_context, outputs, ctx).await;
+    }
+
+    async fn handle_timer(&mut self, key: K, timestamp: SystemTime, ctx: &mut Context<K, OutT>) {
+        if self.timer_fn.is_none() {
+            return;
+        }
+
+        let process_context = Self::load_context(key.clone(), timestamp, ctx).await;
+        let had_state = process_context.state().is_some();
+        let (process_context, outputs) = (self.timer_fn.as_ref().unwrap())(&key, process_context);
+        Self::finish(key, had_state, process_context, outputs, ctx).await;
+    }
+
+    async fn handle_checkpoint(
+        &mut self,
+        _checkpoint: &CheckpointBarrier,
+        ctx: &mut Context<K, OutT>,
+    ) {
+        ctx.flush_timers::<SystemTime>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arroyo_types::ProcessContext;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_process_context() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+        let mut context: ProcessContext<i64> = ProcessContext::new(None, time, None);
+
+        *context.get_or_insert_state_with(|| 0) += 5;
+        context.schedule_timer(time + Duration::from_secs(10));
+
+        let context = context.map_state(|count| count.to_string());
+        assert_eq!(context.timestamp(), time);
+        assert_eq!(context.timers(), &[time + Duration::from_secs(10)]);
+        assert_eq!(context.into_state(), Some("5".to_string()));
+    }
+}
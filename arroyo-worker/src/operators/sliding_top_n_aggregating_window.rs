@@ -5,6 +5,7 @@ use std::{
 };
 
 use crate::engine::{Context, StreamNode};
+use crate::metrics::TaskCounters;
 use arroyo_macro::process_fn;
 use arroyo_rpc::grpc::{TableDeleteBehavior, TableDescriptor, TableType, TableWriteBehavior};
 use arroyo_state::tables::time_key_map::TimeKeyMap;
@@ -122,6 +123,9 @@ impl<
 
         let watermark = ctx.last_present_watermark();
         if watermark.is_some() && bin_start < self.bin_start(watermark.unwrap()) {
+            TaskCounters::DroppedLateRecords
+                .for_task(&ctx.task_info)
+                .inc();
             return;
         }
         self.state = match self.state {
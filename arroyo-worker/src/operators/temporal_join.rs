@@ -0,0 +1,416 @@
+use std::{
+    marker::PhantomData,
+    time::{Duration, SystemTime},
+};
+
+use arroyo_macro::{co_process_fn, StreamNode};
+use arroyo_rpc::grpc::{TableDeleteBehavior, TableDescriptor, TableType, TableWriteBehavior};
+use arroyo_state::tables::key_time_multi_map::KeyTimeMultiMap;
+use arroyo_types::*;
+
+use crate::engine::Context;
+use crate::operators::join_with_expiration::IncomingDataProcessor;
+
+/// Joins each record of an append-only stream with the version of the right side that was valid
+/// at the record's event time.
+///
+/// The right side is kept as a history of versions per key, where retractions are stored as
+/// `None`. Left records are buffered until the watermark passes them, so that every version up to
+/// their event time has been seen. Versions that have been superseded before the watermark can no
+/// longer be selected and are cleaned up, while the latest version of each key is kept
+/// indefinitely.
+#[derive(StreamNode)]
+pub struct TemporalJoin<
+    K: Key,
+    T1: Data,
+    InT2: Data,
+    P2: IncomingDataProcessor<InT2, T2>,
+    T2: Data,
+    OutT: Data,
+> {
+    emit_misses: bool,
+    merge_fn: Box<dyn Fn(&T1, Option<&T2>) -> OutT + Send>,
+    _t: PhantomData<(K, InT2, P2)>,
+}
+
+#[co_process_fn(in_k1=K, in_t1=T1, in_k2=K, in_t2=InT2, out_k=K, out_t=OutT, timer_t=SystemTime)]
+impl<K: Key, T1: Data, InT2: Data, P2: IncomingDataProcessor<InT2, T2>, T2: Data, OutT: Data>
+    TemporalJoin<K, T1, InT2, P2, T2, OutT>
+{
+    fn name(&self) -> String {
+        "TemporalJoin".to_string()
+    }
+
+    pub fn new(emit_misses: bool, merge_fn: Box<dyn Fn(&T1, Option<&T2>) -> OutT + Send>) -> Self {
+        Self {
+            emit_misses,
+            merge_fn,
+            _t: PhantomData,
+        }
+    }
+
+    fn tables(&self) -> Vec<TableDescriptor> {
+        vec![
+            TableDescriptor {
+                name: "l".to_string(),
+                description: "temporal join pending left records".to_string(),
+                table_type: TableType::KeyTimeMultiMap as i32,
+                delete_behavior: TableDeleteBehavior::NoReadsBeforeWatermark as i32,
+                write_behavior: TableWriteBehavior::NoWritesBeforeWatermark as i32,
+                retention_micros: 0,
+            },
+            TableDescriptor {
+                name: "r".to_string(),
+                description: "temporal join right versions".to_string(),
+                table_type: TableType::KeyTimeMultiMap as i32,
+                // the current version of a key stays valid however far the watermark advances
+                delete_behavior: TableDeleteBehavior::None as i32,
+                write_behavior: TableWriteBehavior::NoWritesBeforeWatermark as i32,
+                retention_micros: 0,
+            },
+        ]
+    }
+
+    /// Removes the versions of a key that have been superseded by a version at or before the
+    /// watermark, as no record that is still to be joined can select them
+    async fn clean_up_versions(key: &mut K, ctx: &mut Context<K, OutT>) {
+        let Some(watermark) = ctx.last_present_watermark() else {
+            return;
+        };
+
+        let mut right_state: KeyTimeMultiMap<K, Option<T2>, _> =
+            ctx.state.get_key_time_multi_map('r').await;
+        let Some(current) = right_state
+            .get_all_values_with_timestamps(key)
+            .await
+            .and_then(|versions| {
+                versions
+                    .take_while(|(timestamp, _)| *timestamp <= watermark)
+                    .last()
+                    .map(|(timestamp, value)| (timestamp, value.is_none()))
+            })
+        else {
+            return;
+        };
+
+        match current {
+            // a retraction that is currently valid can be dropped along with everything before it
+            (timestamp, true) => {
+                right_state
+                    .clear_time_range(
+                        key,
+                        SystemTime::UNIX_EPOCH,
+                        timestamp + Duration::from_nanos(1),
+                    )
+                    .await
+            }
+            (timestamp, false) => {
+                right_state
+                    .clear_time_range(key, SystemTime::UNIX_EPOCH, timestamp)
+                    .await
+            }
+        }
+    }
+
+    async fn process_left(&mut self, record: &Record<K, T1>, ctx: &mut Context<K, OutT>) {
+        if let Some(watermark) = ctx.last_present_watermark() {
+            if record.timestamp < watermark {
+                return;
+            }
+        };
+
+        let mut key = record.key.clone().unwrap();
+        // versions at the record's timestamp may still arrive until the watermark passes it
+        ctx.schedule_timer(
+            &mut key,
+            record.timestamp + Duration::from_nanos(1),
+            record.timestamp,
+        )
+        .await;
+
+        let mut left_state: KeyTimeMultiMap<K, T1, _> = ctx.state.get_key_time_multi_map('l').await;
+        left_state
+            .insert(record.timestamp, key, record.value.clone())
+            .await;
+    }
+
+    async fn process_right(&mut self, record: &Record<K, InT2>, ctx: &mut Context<K, OutT>) {
+        if let Some(watermark) = ctx.last_present_watermark() {
+            if record.timestamp < watermark {
+                return;
+            }
+        };
+
+        let mut key = record.key.clone().unwrap();
+        let version = match P2::ensure_updating(record.value.clone()) {
+            UpdatingData::Append(value) | UpdatingData::Update { new: value, .. } => Some(value),
+            UpdatingData::Retract(_) => None,
+        };
+
+        {
+            let mut right_state: KeyTimeMultiMap<K, Option<T2>, _> =
+                ctx.state.get_key_time_multi_map('r').await;
+            right_state
+                .insert(record.timestamp, key.clone(), version)
+                .await;
+        }
+
+        Self::clean_up_versions(&mut key, ctx).await;
+    }
+
+    async fn handle_timer(
+        &mut self,
+        mut key: K,
+        _timestamp: SystemTime,
+        ctx: &mut Context<K, OutT>,
+    ) {
+        let Some(watermark) = ctx.last_present_watermark() else {
+            return;
+        };
+
+        let left: Vec<(SystemTime, T1)> = {
+            let mut left_state: KeyTimeMultiMap<K, T1, _> =
+                ctx.state.get_key_time_multi_map('l').await;
+            let left = left_state
+                .get_all_values_with_timestamps(&mut key)
+                .await
+                .map(|rows| {
+                    rows.take_while(|(timestamp, _)| *timestamp < watermark)
+                        .map(|(timestamp, value)| (timestamp, value.clone()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            left_state
+                .clear_time_range(&mut key, SystemTime::UNIX_EPOCH, watermark)
+                .await;
+            left
+        };
+
+        let records: Vec<_> = {
+            let mut right_state: KeyTimeMultiMap<K, Option<T2>, _> =
+                ctx.state.get_key_time_multi_map('r').await;
+            let versions: Vec<(SystemTime, Option<&T2>)> = right_state
+                .get_all_values_with_timestamps(&mut key)
+                .await
+                .map(|versions| {
+                    versions
+                        .map(|(timestamp, value)| (timestamp, value.as_ref()))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            left.iter()
+                .filter_map(|(timestamp, value)| {
+                    // the last version at or before the record is the one that was valid for it
+                    let version = versions
+                        .iter()
+                        .take_while(|(version_time, _)| version_time <= timestamp)
+                        .last()
+                        .and_then(|(_, version)| *version);
+                    if version.is_none() && !self.emit_misses {
+                        return None;
+                    }
+                    Some(Record {
+                        timestamp: *timestamp,
+                        key: Some(key.clone()),
+                        value: (self.merge_fn)(value, version),
+                    })
+                })
+                .collect()
+        };
+
+        for record in records {
+            ctx.collect(record).await;
+        }
+
+        Self::clean_up_versions(&mut key, ctx).await;
+    }
+
+    async fn handle_checkpoint(
+        &mut self,
+        _checkpoint: &CheckpointBarrier,
+        ctx: &mut Context<K, OutT>,
+    ) {
+        ctx.flush_timers::<SystemTime>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use arroyo_state::tables::key_time_multi_map::KeyTimeMultiMap;
+    use arroyo_types::{from_millis, Message, Record, UpdatingData, Watermark};
+    use tokio::sync::mpsc::Receiver;
+
+    use super::TemporalJoin;
+    use crate::engine::{Context, QueueItem};
+    use crate::operators::join_with_expiration::NoOpProcessor;
+
+    type TestJoin =
+        TemporalJoin<u32, String, UpdatingData<String>, NoOpProcessor<String>, String, String>;
+
+    fn join(emit_misses: bool) -> TestJoin {
+        TemporalJoin::new(
+            emit_misses,
+            Box::new(|left: &String, right: Option<&String>| {
+                format!("{}:{}", left, right.map(|r| r.as_str()).unwrap_or("null"))
+            }),
+        )
+    }
+
+    fn record<T: arroyo_types::Data>(millis: u64, value: T) -> Record<u32, T> {
+        Record {
+            timestamp: from_millis(millis),
+            key: Some(1),
+            value,
+        }
+    }
+
+    fn append(s: &str) -> UpdatingData<String> {
+        UpdatingData::Append(s.to_string())
+    }
+
+    fn drain(rx: &mut Receiver<QueueItem>) -> Vec<String> {
+        let mut out = vec![];
+        while let Ok(item) = rx.try_recv() {
+            if let Message::Record(record) = Message::<u32, String>::from(item) {
+                out.push(record.value);
+            }
+        }
+        out
+    }
+
+    /// Advances the watermark and fires the timer for the test key, as the engine would
+    async fn advance(op: &mut TestJoin, ctx: &mut Context<u32, String>, millis: u64) {
+        ctx.watermarks
+            .set(0, Watermark::EventTime(from_millis(millis)));
+        op.handle_timer(1, from_millis(millis), ctx).await;
+    }
+
+    async fn versions(ctx: &mut Context<u32, String>) -> Vec<(SystemTime, Option<String>)> {
+        let mut right_state: KeyTimeMultiMap<u32, Option<String>, _> =
+            ctx.state.get_key_time_multi_map('r').await;
+        right_state
+            .get_all_values_with_timestamps(&mut 1)
+            .await
+            .map(|versions| {
+                versions
+                    .map(|(timestamp, value)| (timestamp, value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_versions() {
+        for (emit_misses, expected) in [
+            (false, vec!["x:a", "y:b"]),
+            (true, vec!["z:null", "x:a", "y:b"]),
+        ] {
+            let mut op = join(emit_misses);
+            let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+            op.process_right(&record(20, append("b")), &mut ctx).await;
+            op.process_right(&record(10, append("a")), &mut ctx).await;
+
+            op.process_left(&record(15, "x".to_string()), &mut ctx)
+                .await;
+            op.process_left(&record(25, "y".to_string()), &mut ctx)
+                .await;
+            op.process_left(&record(5, "z".to_string()), &mut ctx).await;
+
+            // nothing is joined until the watermark has passed the left records
+            assert_eq!(drain(&mut rx), Vec::<String>::new());
+
+            advance(&mut op, &mut ctx, 30).await;
+            assert_eq!(drain(&mut rx), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retractions() {
+        for (emit_misses, expected) in [
+            (false, vec!["x:a", "z:c"]),
+            (true, vec!["x:a", "y:null", "z:c"]),
+        ] {
+            let mut op = join(emit_misses);
+            let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+            op.process_right(&record(10, append("a")), &mut ctx).await;
+            op.process_right(
+                &record(20, UpdatingData::Retract("a".to_string())),
+                &mut ctx,
+            )
+            .await;
+            op.process_right(
+                &record(
+                    30,
+                    UpdatingData::Update {
+                        old: "a".to_string(),
+                        new: "c".to_string(),
+                    },
+                ),
+                &mut ctx,
+            )
+            .await;
+
+            op.process_left(&record(15, "x".to_string()), &mut ctx)
+                .await;
+            op.process_left(&record(25, "y".to_string()), &mut ctx)
+                .await;
+            op.process_left(&record(35, "z".to_string()), &mut ctx)
+                .await;
+
+            advance(&mut op, &mut ctx, 40).await;
+            assert_eq!(drain(&mut rx), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watermark_pruning() {
+        let mut op = join(true);
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+
+        op.process_right(&record(10, append("a")), &mut ctx).await;
+        op.process_right(&record(20, append("b")), &mut ctx).await;
+        op.process_right(&record(30, append("c")), &mut ctx).await;
+
+        // versions superseded at or before the watermark are removed
+        advance(&mut op, &mut ctx, 25).await;
+        assert_eq!(
+            versions(&mut ctx).await,
+            vec![
+                (from_millis(20), Some("b".to_string())),
+                (from_millis(30), Some("c".to_string())),
+            ]
+        );
+
+        // versions behind the watermark are dropped as late
+        op.process_right(&record(22, append("late")), &mut ctx)
+            .await;
+        assert_eq!(versions(&mut ctx).await.len(), 2);
+
+        // the latest version is kept however far the watermark advances
+        let later = Duration::from_secs(7 * 24 * 60 * 60).as_millis() as u64;
+        advance(&mut op, &mut ctx, later).await;
+        assert_eq!(
+            versions(&mut ctx).await,
+            vec![(from_millis(30), Some("c".to_string()))]
+        );
+
+        op.process_left(&record(later + 1, "x".to_string()), &mut ctx)
+            .await;
+        advance(&mut op, &mut ctx, later + 2).await;
+        assert_eq!(drain(&mut rx), vec!["x:c"]);
+
+        // a retraction that has become valid removes the key's history entirely
+        op.process_right(
+            &record(later + 3, UpdatingData::Retract("c".to_string())),
+            &mut ctx,
+        )
+        .await;
+        advance(&mut op, &mut ctx, later + 4).await;
+        assert_eq!(versions(&mut ctx).await, vec![]);
+    }
+}
@@ -1,6 +1,7 @@
 use std::time::SystemTime;
 
 use crate::engine::{Context, StreamNode};
+use crate::metrics::TaskCounters;
 use arroyo_macro::process_fn;
 use arroyo_rpc::grpc::{TableDeleteBehavior, TableDescriptor, TableType, TableWriteBehavior};
 use arroyo_state::tables::time_key_map::TimeKeyMap;
@@ -11,7 +12,7 @@ use tracing::debug;
 pub struct TumblingAggregatingWindowFunc<K: Key, T: Data, BinA: Data, OutT: Data> {
     width: Duration,
     aggregator: fn(&K, Window, &BinA) -> OutT,
-    bin_merger: fn(&T, Option<&BinA>) -> BinA,
+    bin_merger: fn(&T, SystemTime, Option<&BinA>) -> BinA,
     state: TumblingWindowState,
 }
 
@@ -33,7 +34,7 @@ impl<K: Key, T: Data, BinA: Data, OutT: Data> TumblingAggregatingWindowFunc<K, T
         width: Duration,
         // TODO: this can consume the bin, as we drop it right after.
         aggregator: fn(&K, Window, &BinA) -> OutT,
-        bin_merger: fn(&T, Option<&BinA>) -> BinA,
+        bin_merger: fn(&T, SystemTime, Option<&BinA>) -> BinA,
     ) -> Self {
         TumblingAggregatingWindowFunc {
             width,
@@ -70,6 +71,9 @@ impl<K: Key, T: Data, BinA: Data, OutT: Data> TumblingAggregatingWindowFunc<K, T
 
         if let Some(watermark) = ctx.last_present_watermark() {
             if bin_start < self.bin_start(watermark) {
+                TaskCounters::DroppedLateRecords
+                    .for_task(&ctx.task_info)
+                    .inc();
                 return;
             }
         }
@@ -92,7 +96,7 @@ impl<K: Key, T: Data, BinA: Data, OutT: Data> TumblingAggregatingWindowFunc<K, T
 
         let mut key = record.key.clone().unwrap();
         let bin_aggregate = aggregating_map.get(bin_start, &mut key);
-        let new_value = (self.bin_merger)(&record.value, bin_aggregate);
+        let new_value = (self.bin_merger)(&record.value, record.timestamp, bin_aggregate);
         aggregating_map.insert(bin_start, key, new_value);
     }
 
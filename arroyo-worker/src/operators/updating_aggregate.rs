@@ -5,13 +5,13 @@ use arroyo_macro::process_fn;
 use arroyo_rpc::grpc::{TableDeleteBehavior, TableDescriptor, TableType, TableWriteBehavior};
 use arroyo_state::tables::keyed_map::KeyedState;
 use arroyo_types::*;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 #[derive(StreamNode)]
 pub struct UpdatingAggregateOperator<K: Key, T: Data, BinA: Data, OutT: Data> {
     expiration: Duration,
     aggregator: fn(&K, &BinA) -> OutT,
-    bin_merger: fn(&T, Option<&BinA>) -> Option<BinA>,
+    bin_merger: fn(&T, SystemTime, Option<&BinA>) -> Option<BinA>,
     _t: PhantomData<K>,
 }
 
@@ -36,7 +36,7 @@ impl<K: Key, T: Data, BinA: Data, OutT: Data> UpdatingAggregateOperator<K, T, Bi
         expiration: Duration,
         // TODO: this can consume the bin, as we drop it right after.
         aggregator: fn(&K, &BinA) -> OutT,
-        bin_merger: fn(&T, Option<&BinA>) -> Option<BinA>,
+        bin_merger: fn(&T, SystemTime, Option<&BinA>) -> Option<BinA>,
     ) -> Self {
         UpdatingAggregateOperator {
             expiration,
@@ -75,7 +75,8 @@ impl<K: Key, T: Data, BinA: Data, OutT: Data> UpdatingAggregateOperator<K, T, Bi
             match bin_aggregate {
                 Some(bin_aggregate) => {
                     let old_aggregate = (self.aggregator)(&key, bin_aggregate);
-                    let new_bin = (self.bin_merger)(&record.value, Some(bin_aggregate));
+                    let new_bin =
+                        (self.bin_merger)(&record.value, record.timestamp, Some(bin_aggregate));
                     match new_bin {
                         Some(new_bin) => {
                             let new_aggregate = (self.aggregator)(&key, &new_bin);
@@ -109,7 +110,7 @@ impl<K: Key, T: Data, BinA: Data, OutT: Data> UpdatingAggregateOperator<K, T, Bi
                     }
                 }
                 None => {
-                    let new_bin = (self.bin_merger)(&record.value, None);
+                    let new_bin = (self.bin_merger)(&record.value, record.timestamp, None);
                     match new_bin {
                         Some(new_bin) => {
                             let new_aggregate = (self.aggregator)(&key, &new_bin);
@@ -0,0 +1,208 @@
+//! Scalar UDFs that are uploaded as prebuilt WebAssembly modules, which are called from the
+//! generated code of a pipeline instead of being compiled into it.
+//!
+//! A module implementing a UDF must follow this ABI:
+//!
+//! * It exports its linear memory as `memory`, and imports nothing.
+//! * It exports `arroyo_alloc(len: i32) -> i32`, which allocates `len` bytes and returns a pointer
+//!   to them, and `arroyo_dealloc(ptr: i32, len: i32)`, which frees them.
+//! * It exports the UDF under its name as `<name>(ptr: i32, len: i32) -> i64`. The arguments are
+//!   written as a tuple into a buffer allocated with `arroyo_alloc`, which the function takes
+//!   ownership of. The function writes its result into a buffer that it allocates and returns
+//!   `(ptr << 32) | len` for it, which is freed with `arroyo_dealloc` once it has been read.
+//!
+//! Arguments and results are encoded with bincode's standard configuration: integers are
+//! variable-length encoded (signed integers zig-zag encoded first), floats are little-endian,
+//! booleans are a single byte, strings are their length followed by their UTF-8 bytes, and
+//! nullable values are a 0 byte for null or a 1 byte followed by the value. Tuples are their
+//! elements encoded in order.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use bincode::{config, Decode, Encode};
+use once_cell::sync::Lazy;
+use wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc};
+
+static ENGINE: Lazy<Engine> = Lazy::new(Engine::default);
+
+// modules are compiled once per process, and instantiated once per thread that calls them
+static MODULES: Lazy<Mutex<HashMap<&'static str, Module>>> = Lazy::new(Default::default);
+
+thread_local! {
+    static INSTANCES: RefCell<HashMap<&'static str, WasmUdfInstance>> = RefCell::new(HashMap::new());
+}
+
+/// The module of a WASM UDF, which is embedded in the generated code of the pipelines that use it
+pub struct WasmUdf {
+    name: &'static str,
+    wasm: &'static [u8],
+}
+
+impl WasmUdf {
+    pub const fn new(name: &'static str, wasm: &'static [u8]) -> Self {
+        Self { name, wasm }
+    }
+
+    /// Calls the UDF with a tuple of its arguments.
+    ///
+    /// Panics if the module can't be instantiated or the call fails, for example because the UDF
+    /// trapped or returned a result that could not be decoded. This fails the task that called
+    /// it, which is then restarted from its last checkpoint.
+    pub fn call<Args: Encode, Ret: Decode>(&self, args: Args) -> Ret {
+        INSTANCES.with(|instances| {
+            let mut instances = instances.borrow_mut();
+            if !instances.contains_key(self.name) {
+                let instance = WasmUdfInstance::new(self.name, self.wasm).unwrap_or_else(|e| {
+                    panic!("Failed to instantiate WASM UDF {}: {:?}", self.name, e)
+                });
+                instances.insert(self.name, instance);
+            }
+
+            instances
+                .get_mut(self.name)
+                .unwrap()
+                .call(args)
+                .unwrap_or_else(|e| panic!("WASM UDF {} failed: {:?}", self.name, e))
+        })
+    }
+}
+
+struct WasmUdfInstance {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    dealloc: TypedFunc<(i32, i32), ()>,
+    function: TypedFunc<(i32, i32), i64>,
+}
+
+impl WasmUdfInstance {
+    fn new(name: &'static str, wasm: &'static [u8]) -> Result<Self> {
+        let module = {
+            let mut modules = MODULES.lock().unwrap();
+            match modules.get(name) {
+                Some(module) => module.clone(),
+                None => {
+                    let module = Module::from_binary(&ENGINE, wasm)?;
+                    modules.insert(name, module.clone());
+                    module
+                }
+            }
+        };
+
+        let mut store = Store::new(&ENGINE, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("module does not export its memory"))?;
+        let alloc = instance.get_typed_func(&mut store, "arroyo_alloc")?;
+        let dealloc = instance.get_typed_func(&mut store, "arroyo_dealloc")?;
+        let function = instance.get_typed_func(&mut store, name)?;
+
+        Ok(Self {
+            store,
+            memory,
+            alloc,
+            dealloc,
+            function,
+        })
+    }
+
+    fn call<Args: Encode, Ret: Decode>(&mut self, args: Args) -> Result<Ret> {
+        let bytes = bincode::encode_to_vec(args, config::standard())?;
+        let ptr = self.alloc.call(&mut self.store, bytes.len() as i32)?;
+        self.memory.write(&mut self.store, ptr as usize, &bytes)?;
+
+        let result = self
+            .function
+            .call(&mut self.store, (ptr, bytes.len() as i32))? as u64;
+        let (ptr, len) = ((result >> 32) as usize, (result & 0xffff_ffff) as usize);
+
+        let result_bytes = self
+            .memory
+            .data(&self.store)
+            .get(ptr..ptr + len)
+            .ok_or_else(|| anyhow!("result is outside of the module's memory"))?;
+        let (value, _) = bincode::decode_from_slice(result_bytes, config::standard())?;
+
+        self.dealloc
+            .call(&mut self.store, (ptr as i32, len as i32))?;
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use once_cell::sync::Lazy;
+
+    use super::WasmUdf;
+
+    // a bump allocator that never frees, which is enough for a few calls
+    static WASM: Lazy<Vec<u8>> = Lazy::new(|| {
+        wat::parse_str(
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (global $next (mut i32) (i32.const 1024))
+              (func $alloc (export "arroyo_alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $next))
+                (global.set $next (i32.add (global.get $next) (local.get $len)))
+                (local.get $ptr))
+              (func (export "arroyo_dealloc") (param i32 i32))
+
+              ;; returns its arguments, so a single argument is returned as the result
+              (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                  (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                  (i64.extend_i32_u (local.get $len))))
+
+              ;; returns whether its first argument, which must be nullable, is null
+              (func (export "is_null") (param $ptr i32) (param $len i32) (result i64)
+                (local $out i32)
+                (local.set $out (call $alloc (i32.const 1)))
+                (i32.store8 (local.get $out) (i32.eqz (i32.load8_u (local.get $ptr))))
+                (i64.or
+                  (i64.shl (i64.extend_i32_u (local.get $out)) (i64.const 32))
+                  (i64.const 1)))
+
+              (func (export "trap") (param i32 i32) (result i64)
+                unreachable))
+            "#,
+        )
+        .unwrap()
+    });
+
+    #[test]
+    fn test_string_results() {
+        let echo = WasmUdf::new("echo", WASM.as_slice());
+
+        let result: String = echo.call(("hello wasm".to_string(),));
+        assert_eq!(result, "hello wasm");
+
+        let result: Option<String> = echo.call((Some("hello again".to_string()),));
+        assert_eq!(result, Some("hello again".to_string()));
+
+        let result: Option<String> = echo.call((None::<String>,));
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_nullable_args() {
+        let is_null = WasmUdf::new("is_null", WASM.as_slice());
+
+        assert!(is_null.call::<_, bool>((None::<i64>, 5i64)));
+        assert!(!is_null.call::<_, bool>((Some(-3i64), 5i64)));
+        assert!(!is_null.call::<_, bool>((Some("".to_string()),)));
+    }
+
+    #[test]
+    #[should_panic(expected = "WASM UDF trap failed")]
+    fn test_failures_panic() {
+        WasmUdf::new("trap", WASM.as_slice()).call::<_, i64>((1i64,));
+    }
+}
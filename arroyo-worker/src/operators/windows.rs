@@ -1,11 +1,13 @@
 use std::{marker::PhantomData, time::SystemTime};
 
 use crate::engine::{Context, StreamNode};
+use crate::metrics::TaskCounters;
 use arroyo_macro::process_fn;
 use arroyo_rpc::grpc::{TableDeleteBehavior, TableDescriptor, TableType, TableWriteBehavior};
 use arroyo_state::tables::key_time_multi_map::KeyTimeMultiMap;
 use arroyo_state::tables::keyed_map::KeyedState;
 use arroyo_types::*;
+use bincode::{Decode, Encode};
 use std::time::Duration;
 
 use super::{
@@ -172,7 +174,11 @@ impl<K: Key, T: Data, OutT: Data, W: TimeWindowAssigner<K, T>> KeyedWindowFunc<K
             }
         }
 
-        if has_window {
+        if !has_window {
+            TaskCounters::DroppedLateRecords
+                .for_task(&ctx.task_info)
+                .inc();
+        } else {
             let key = record.key.as_ref().unwrap().clone();
             let value = record.value.clone();
             ctx.state
@@ -205,6 +211,260 @@ impl<K: Key, T: Data, OutT: Data, W: TimeWindowAssigner<K, T>> KeyedWindowFunc<K
     }
 }
 
+/// Evaluates tumbling and sliding windows that keep accepting records for `allowed_lateness`
+/// after the watermark has passed their end.
+///
+/// Each window is emitted once the watermark passes its end, and records that arrive late but
+/// within the allowed lateness cause the windows they fall into to be re-evaluated and emitted
+/// as updates.
+#[derive(StreamNode)]
+pub struct UpdatingWindowFunc<K: Key, T: Data, OutT: Data, W: TimeWindowAssigner<K, T>> {
+    assigner: W,
+    allowed_lateness: Duration,
+    aggregator: fn(&K, Window, Vec<&T>) -> OutT,
+    _phantom: PhantomData<(K, T, OutT)>,
+}
+
+#[process_fn(in_k = K, in_t = T, out_k = K, out_t = UpdatingData<OutT>, timer_t = Window)]
+impl<K: Key, T: Data, OutT: Data, W: TimeWindowAssigner<K, T>> UpdatingWindowFunc<K, T, OutT, W> {
+    pub fn tumbling_window(
+        size: Duration,
+        allowed_lateness: Duration,
+        aggregator: fn(&K, Window, Vec<&T>) -> OutT,
+    ) -> UpdatingWindowFunc<K, T, OutT, TumblingWindowAssigner> {
+        UpdatingWindowFunc {
+            assigner: TumblingWindowAssigner { size },
+            allowed_lateness,
+            aggregator,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn sliding_window(
+        size: Duration,
+        slide: Duration,
+        allowed_lateness: Duration,
+        aggregator: fn(&K, Window, Vec<&T>) -> OutT,
+    ) -> UpdatingWindowFunc<K, T, OutT, SlidingWindowAssigner> {
+        UpdatingWindowFunc {
+            assigner: SlidingWindowAssigner { size, slide },
+            allowed_lateness,
+            aggregator,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn name(&self) -> String {
+        "UpdatingKeyWindow".to_string()
+    }
+
+    fn retention(&self) -> Duration {
+        self.assigner.safe_retention_duration().unwrap() + self.allowed_lateness
+    }
+
+    fn tables(&self) -> Vec<TableDescriptor> {
+        vec![TableDescriptor {
+            name: "w".to_string(),
+            description: "window state".to_string(),
+            table_type: TableType::KeyTimeMultiMap as i32,
+            delete_behavior: TableDeleteBehavior::NoReadsBeforeWatermark as i32,
+            write_behavior: TableWriteBehavior::NoWritesBeforeWatermark as i32,
+            retention_micros: self.retention().as_micros() as u64,
+        }]
+    }
+
+    /// Evaluates the window over the records currently in state, returning None if it is empty
+    async fn aggregate(
+        aggregator: fn(&K, Window, Vec<&T>) -> OutT,
+        key: &mut K,
+        window: Window,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) -> Option<OutT> {
+        let mut state: KeyTimeMultiMap<K, T, _> = ctx.state.get_key_time_multi_map('w').await;
+        let vs: Vec<&T> = state.get_time_range(key, window.start, window.end).await;
+        if vs.is_empty() {
+            None
+        } else {
+            Some((aggregator)(key, window, vs))
+        }
+    }
+
+    async fn process_element(
+        &mut self,
+        record: &Record<K, T>,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) {
+        let watermark = ctx
+            .last_present_watermark()
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut key = record.key.clone().unwrap();
+
+        let mut has_window = false;
+        let mut late_windows = vec![];
+        for w in self.assigner.windows(record.timestamp) {
+            if w.end > watermark {
+                has_window = true;
+                ctx.schedule_timer(&mut key, w.end, w).await;
+            } else if w.end + self.allowed_lateness > watermark {
+                has_window = true;
+                late_windows.push(w);
+            }
+        }
+
+        if !has_window {
+            TaskCounters::DroppedLateRecords
+                .for_task(&ctx.task_info)
+                .inc();
+            return;
+        }
+
+        if !late_windows.is_empty() {
+            TaskCounters::LateRecords.for_task(&ctx.task_info).inc();
+        }
+
+        // windows that were empty when the watermark passed them have never been emitted
+        let mut previous = Vec::with_capacity(late_windows.len());
+        for w in &late_windows {
+            previous.push(Self::aggregate(self.aggregator, &mut key, *w, ctx).await);
+        }
+
+        ctx.state
+            .get_key_time_multi_map('w')
+            .await
+            .insert(record.timestamp, key.clone(), record.value.clone())
+            .await;
+
+        for (w, old) in late_windows.into_iter().zip(previous) {
+            let new = Self::aggregate(self.aggregator, &mut key, w, ctx)
+                .await
+                .expect("window must contain the late record");
+
+            let value = match old {
+                Some(old) if old == new => continue,
+                Some(old) => UpdatingData::Update { old, new },
+                None => UpdatingData::Append(new),
+            };
+
+            ctx.collect(Record {
+                timestamp: w.end - Duration::from_nanos(1),
+                key: Some(key.clone()),
+                value,
+            })
+            .await;
+        }
+    }
+
+    async fn handle_timer(
+        &mut self,
+        mut key: K,
+        window: Window,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) {
+        // the window's records are kept until its allowed lateness has passed, so that late
+        // records can update it
+        if let Some(value) = Self::aggregate(self.aggregator, &mut key, window, ctx).await {
+            ctx.collect(Record {
+                timestamp: window.end - Duration::from_nanos(1),
+                key: Some(key),
+                value: UpdatingData::Append(value),
+            })
+            .await;
+        }
+    }
+
+    async fn handle_watermark(
+        &mut self,
+        watermark: Watermark,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) {
+        if let Watermark::EventTime(watermark) = watermark {
+            // records before this can no longer fall into a window that accepts late records
+            if let Some(cutoff) = watermark.checked_sub(self.retention()) {
+                let mut state: KeyTimeMultiMap<K, T, _> =
+                    ctx.state.get_key_time_multi_map('w').await;
+                state.expire_entries_before(cutoff).await;
+            }
+        }
+
+        ctx.broadcast(arroyo_types::Message::Watermark(watermark))
+            .await;
+    }
+
+    async fn handle_checkpoint(
+        &mut self,
+        _checkpoint_barrier: &arroyo_types::CheckpointBarrier,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) {
+        ctx.flush_timers::<Window>().await;
+    }
+}
+
+/// Emits the records that arrive too late to be added to any window of a window operator with
+/// the same configuration, so that they can be written to a sink rather than being dropped.
+#[derive(StreamNode)]
+pub struct LateRecordFilter<K: Key, T: Data> {
+    // the watermark at which a record with the given timestamp becomes too late
+    deadline: Box<dyn Fn(SystemTime) -> SystemTime + Send>,
+    _t: PhantomData<(K, T)>,
+}
+
+#[process_fn(in_k = K, in_t = T, out_k = K, out_t = T)]
+impl<K: Key, T: Data> LateRecordFilter<K, T> {
+    fn for_assigner<W: TimeWindowAssigner<K, T>>(assigner: W, allowed_lateness: Duration) -> Self {
+        Self {
+            deadline: Box::new(move |timestamp| {
+                assigner
+                    .windows(timestamp)
+                    .iter()
+                    .map(|w| w.end)
+                    .max()
+                    .unwrap_or(timestamp)
+                    + allowed_lateness
+            }),
+            _t: PhantomData,
+        }
+    }
+
+    pub fn tumbling_window(size: Duration, allowed_lateness: Duration) -> Self {
+        Self::for_assigner(TumblingWindowAssigner { size }, allowed_lateness)
+    }
+
+    pub fn sliding_window(size: Duration, slide: Duration, allowed_lateness: Duration) -> Self {
+        Self::for_assigner(SlidingWindowAssigner { size, slide }, allowed_lateness)
+    }
+
+    pub fn instant_window() -> Self {
+        Self::for_assigner(InstantWindowAssigner {}, Duration::ZERO)
+    }
+
+    pub fn session_window(gap_size: Duration, allowed_lateness: Duration) -> Self {
+        Self {
+            deadline: Box::new(move |timestamp| {
+                if allowed_lateness.is_zero() {
+                    timestamp
+                } else {
+                    // a late record can still start a session of its own, which is kept until
+                    // its allowed lateness has passed
+                    timestamp + gap_size + allowed_lateness
+                }
+            }),
+            _t: PhantomData,
+        }
+    }
+
+    fn name(&self) -> String {
+        "LateRecordFilter".to_string()
+    }
+
+    async fn process_element(&mut self, record: &Record<K, T>, ctx: &mut Context<K, T>) {
+        if let Some(watermark) = ctx.last_present_watermark() {
+            if watermark >= (self.deadline)(record.timestamp) {
+                ctx.collect(record.clone()).await;
+            }
+        }
+    }
+}
+
 #[derive(StreamNode)]
 pub struct SessionWindowFunc<K: Key, T: Data, OutT: Data> {
     operation: WindowOperation<K, T, OutT>,
@@ -352,6 +612,9 @@ impl<K: Key, T: Data, OutT: Data> SessionWindowFunc<K, T, OutT> {
 
         if watermark >= record.timestamp {
             // drop late data
+            TaskCounters::DroppedLateRecords
+                .for_task(&ctx.task_info)
+                .inc();
             return;
         }
 
@@ -443,15 +706,302 @@ impl<K: Key, T: Data, OutT: Data> SessionWindowFunc<K, T, OutT> {
     }
 }
 
+/// A session of an [UpdatingSessionWindowFunc]. Sessions are kept after they have been emitted,
+/// until their allowed lateness has passed, so that late records can update them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode)]
+struct Session {
+    window: Window,
+    emitted: bool,
+}
+
+/// A session window that accepts records up to `allowed_lateness` after the watermark has passed
+/// their session. Late records can re-open an emitted session or merge it with its neighbours, in
+/// which case the emitted results are updated or retracted.
+#[derive(StreamNode)]
+pub struct UpdatingSessionWindowFunc<K: Key, T: Data, OutT: Data> {
+    gap_size: Duration,
+    allowed_lateness: Duration,
+    aggregator: fn(&K, Window, Vec<&T>) -> OutT,
+    _t: PhantomData<K>,
+}
+
+#[process_fn(in_k = K, in_t = T, out_k = K, out_t = UpdatingData<OutT>)]
+impl<K: Key, T: Data, OutT: Data> UpdatingSessionWindowFunc<K, T, OutT> {
+    pub fn new(
+        gap_size: Duration,
+        allowed_lateness: Duration,
+        aggregator: fn(&K, Window, Vec<&T>) -> OutT,
+    ) -> Self {
+        Self {
+            gap_size,
+            allowed_lateness,
+            aggregator,
+            _t: PhantomData,
+        }
+    }
+
+    fn name(&self) -> String {
+        "UpdatingSessionWindow".to_string()
+    }
+
+    fn tables(&self) -> Vec<TableDescriptor> {
+        vec![
+            TableDescriptor {
+                name: "w".to_string(),
+                description: "window state".to_string(),
+                table_type: TableType::KeyTimeMultiMap as i32,
+                delete_behavior: TableDeleteBehavior::NoReadsBeforeWatermark as i32,
+                write_behavior: TableWriteBehavior::NoWritesBeforeWatermark as i32,
+                retention_micros: (MAX_SESSION_SIZE + self.allowed_lateness).as_micros() as u64,
+            },
+            TableDescriptor {
+                name: "s".to_string(),
+                description: "sessions".to_string(),
+                table_type: TableType::TimeKeyMap as i32,
+                delete_behavior: TableDeleteBehavior::NoReadsBeforeWatermark as i32,
+                write_behavior: TableWriteBehavior::NoWritesBeforeWatermark as i32,
+                // sessions are written at the end of the allowed lateness of the last one
+                retention_micros: 0,
+            },
+        ]
+    }
+
+    /// Each key has a single timer, which fires when its first open session closes or when the
+    /// allowed lateness of its first emitted session has passed
+    fn next_timer(&self, sessions: &[Session]) -> Option<SystemTime> {
+        sessions
+            .iter()
+            .map(|s| {
+                if s.emitted {
+                    s.window.end + self.allowed_lateness
+                } else {
+                    s.window.end
+                }
+            })
+            .min()
+    }
+
+    async fn sessions(key: &mut K, ctx: &mut Context<K, UpdatingData<OutT>>) -> Vec<Session> {
+        let t: KeyedState<'_, K, Vec<Session>, _> = ctx.state.get_key_state('s').await;
+        t.get(key).cloned().unwrap_or_default()
+    }
+
+    /// Writes the sessions for the key, moving its timer if `scheduled` is no longer the next one
+    async fn update_sessions(
+        &mut self,
+        key: &mut K,
+        scheduled: Option<SystemTime>,
+        sessions: Vec<Session>,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) {
+        let next = self.next_timer(&sessions);
+        if next != scheduled {
+            if let Some(t) = scheduled {
+                let _: Option<()> = ctx.cancel_timer(key, t).await;
+            }
+            if let Some(t) = next {
+                ctx.schedule_timer(key, t, ()).await;
+            }
+        }
+
+        let mut t: KeyedState<'_, K, Vec<Session>, _> = ctx.state.get_key_state('s').await;
+        match sessions.iter().map(|s| s.window.end).max() {
+            Some(end) => {
+                t.insert(end + self.allowed_lateness, key.clone(), sessions)
+                    .await;
+            }
+            None => t.remove(key).await,
+        }
+    }
+
+    /// Evaluates the window over the records currently in state, returning None if it is empty
+    async fn aggregate(
+        aggregator: fn(&K, Window, Vec<&T>) -> OutT,
+        key: &mut K,
+        window: Window,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) -> Option<OutT> {
+        let mut state: KeyTimeMultiMap<K, T, _> = ctx.state.get_key_time_multi_map('w').await;
+        let vs: Vec<&T> = state.get_time_range(key, window.start, window.end).await;
+        if vs.is_empty() {
+            None
+        } else {
+            Some((aggregator)(key, window, vs))
+        }
+    }
+
+    async fn process_element(
+        &mut self,
+        record: &Record<K, T>,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) {
+        let watermark = ctx
+            .last_present_watermark()
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut key = record.key.clone().unwrap();
+        let timestamp = record.timestamp;
+
+        let sessions = Self::sessions(&mut key, ctx).await;
+        let scheduled = self.next_timer(&sessions);
+        let before: Vec<Window> = sessions.iter().map(|s| s.window).collect();
+
+        let mut windows = WindowGroup {
+            windows: before.clone(),
+            gap_size: self.gap_size,
+        };
+        windows.handle_event(timestamp);
+        let after = windows.windows;
+
+        // the sessions that the record changes or is added to, before and after adding it
+        let affected = |from: &[Window], other: &[Window]| -> Vec<Window> {
+            from.iter()
+                .filter(|w| !other.contains(w) || w.contains(timestamp))
+                .copied()
+                .collect()
+        };
+        let old_windows = affected(&before, &after);
+        let new_windows = affected(&after, &before);
+
+        if new_windows
+            .iter()
+            .any(|w| w.end + self.allowed_lateness <= watermark)
+        {
+            TaskCounters::DroppedLateRecords
+                .for_task(&ctx.task_info)
+                .inc();
+            return;
+        }
+
+        if timestamp <= watermark {
+            TaskCounters::LateRecords.for_task(&ctx.task_info).inc();
+        }
+
+        // sessions that the watermark has passed have already been emitted
+        let mut retracted = vec![];
+        for w in old_windows.iter().filter(|w| w.end <= watermark) {
+            let value = Self::aggregate(self.aggregator, &mut key, *w, ctx)
+                .await
+                .expect("emitted sessions must not be empty");
+            retracted.push((*w, value));
+        }
+
+        ctx.state
+            .get_key_time_multi_map('w')
+            .await
+            .insert(timestamp, key.clone(), record.value.clone())
+            .await;
+
+        let mut appended = vec![];
+        for w in new_windows.iter().filter(|w| w.end <= watermark) {
+            let value = Self::aggregate(self.aggregator, &mut key, *w, ctx)
+                .await
+                .expect("sessions must not be empty");
+            appended.push((*w, value));
+        }
+
+        let updates: Vec<_> = if retracted.len() == 1 && appended.len() == 1 {
+            let (_, old) = retracted.pop().unwrap();
+            let (w, new) = appended.pop().unwrap();
+            if old == new {
+                vec![]
+            } else {
+                vec![(w, UpdatingData::Update { old, new })]
+            }
+        } else {
+            retracted
+                .into_iter()
+                .map(|(w, v)| (w, UpdatingData::Retract(v)))
+                .chain(
+                    appended
+                        .into_iter()
+                        .map(|(w, v)| (w, UpdatingData::Append(v))),
+                )
+                .collect()
+        };
+
+        for (w, value) in updates {
+            ctx.collect(Record {
+                timestamp: w.end - Duration::from_nanos(1),
+                key: Some(key.clone()),
+                value,
+            })
+            .await;
+        }
+
+        if after != before {
+            let sessions = after
+                .into_iter()
+                .map(|window| Session {
+                    window,
+                    emitted: window.end <= watermark,
+                })
+                .collect();
+            self.update_sessions(&mut key, scheduled, sessions, ctx)
+                .await;
+        }
+    }
+
+    async fn handle_timer(&mut self, mut key: K, _: (), ctx: &mut Context<K, UpdatingData<OutT>>) {
+        let watermark = ctx
+            .last_present_watermark()
+            .expect("timers only fire once there is a watermark");
+        let mut sessions = Self::sessions(&mut key, ctx).await;
+
+        for session in sessions
+            .iter_mut()
+            .filter(|s| !s.emitted && s.window.end <= watermark)
+        {
+            if let Some(value) =
+                Self::aggregate(self.aggregator, &mut key, session.window, ctx).await
+            {
+                ctx.collect(Record {
+                    timestamp: session.window.end - Duration::from_nanos(1),
+                    key: Some(key.clone()),
+                    value: UpdatingData::Append(value),
+                })
+                .await;
+            }
+            session.emitted = true;
+        }
+
+        // sessions whose allowed lateness has passed can no longer be updated
+        let mut state: KeyTimeMultiMap<K, T, _> = ctx.state.get_key_time_multi_map('w').await;
+        for session in &sessions {
+            if session.window.end + self.allowed_lateness <= watermark {
+                state
+                    .clear_time_range(&mut key, session.window.start, session.window.end)
+                    .await;
+            }
+        }
+        sessions.retain(|s| s.window.end + self.allowed_lateness > watermark);
+
+        // the timer that fired has already been removed
+        self.update_sessions(&mut key, None, sessions, ctx).await;
+    }
+
+    async fn handle_checkpoint(
+        &mut self,
+        _checkpoint_barrier: &arroyo_types::CheckpointBarrier,
+        ctx: &mut Context<K, UpdatingData<OutT>>,
+    ) {
+        ctx.flush_timers::<()>().await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
     use std::time::{Duration, SystemTime};
 
-    use arroyo_types::{from_millis, Window};
+    use arroyo_types::{from_millis, Message, Record, TaskInfo, UpdatingData, Watermark, Window};
+    use tokio::sync::mpsc::Receiver;
 
+    use crate::engine::{Context, QueueItem};
+    use crate::metrics::TaskCounters;
     use crate::operators::windows::{HandleResult, MAX_SESSION_SIZE};
+    use crate::operators::TumblingWindowAssigner;
 
-    use super::WindowGroup;
+    use super::{LateRecordFilter, UpdatingSessionWindowFunc, UpdatingWindowFunc, WindowGroup};
 
     #[test]
     fn test_no_windows() {
@@ -649,4 +1199,199 @@ mod tests {
             ]
         );
     }
+
+    fn sum(_: &u32, _: Window, values: Vec<&u64>) -> u64 {
+        values.into_iter().sum()
+    }
+
+    fn record(millis: u64, value: u64) -> Record<u32, u64> {
+        Record {
+            timestamp: from_millis(millis),
+            key: Some(1),
+            value,
+        }
+    }
+
+    fn drain(rx: &mut Receiver<QueueItem>) -> Vec<(SystemTime, UpdatingData<u64>)> {
+        let mut out = vec![];
+        while let Ok(item) = rx.try_recv() {
+            if let Message::Record(record) = Message::<u32, UpdatingData<u64>>::from(item) {
+                out.push((record.timestamp, record.value));
+            }
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn test_updating_window_late_records() {
+        let mut op = UpdatingWindowFunc::<u32, u64, u64, TumblingWindowAssigner>::tumbling_window(
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+            sum,
+        );
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+        // the late record counters are global, so give this task its own labels
+        ctx.task_info = Arc::new(TaskInfo {
+            operator_id: "updating-window-late-records".to_string(),
+            ..(*ctx.task_info).clone()
+        });
+        let first = Window {
+            start: from_millis(0),
+            end: from_millis(10),
+        };
+        let end_of = |millis: u64| from_millis(millis) - Duration::from_nanos(1);
+
+        op.process_element(&record(1, 1), &mut ctx).await;
+        op.process_element(&record(5, 2), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        // the watermark passes the first window, which is emitted; the second is still empty
+        let watermark = Watermark::EventTime(from_millis(25));
+        ctx.watermarks.set(0, watermark);
+        op.handle_timer(1, first, &mut ctx).await;
+        op.handle_watermark(watermark, &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![(end_of(10), UpdatingData::Append(3))]);
+
+        // late records within the allowed lateness update emitted windows, and are appended to
+        // windows that were empty
+        op.process_element(&record(7, 4), &mut ctx).await;
+        assert_eq!(
+            drain(&mut rx),
+            vec![(end_of(10), UpdatingData::Update { old: 3, new: 7 })]
+        );
+        op.process_element(&record(15, 10), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![(end_of(20), UpdatingData::Append(10))]);
+
+        // once the allowed lateness of the first window has passed, its records are dropped
+        let watermark = Watermark::EventTime(from_millis(35));
+        ctx.watermarks.set(0, watermark);
+        op.handle_watermark(watermark, &mut ctx).await;
+        op.process_element(&record(8, 100), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+        op.process_element(&record(15, 5), &mut ctx).await;
+        assert_eq!(
+            drain(&mut rx),
+            vec![(end_of(20), UpdatingData::Update { old: 10, new: 15 })]
+        );
+
+        assert_eq!(TaskCounters::LateRecords.for_task(&ctx.task_info).get(), 3);
+        assert_eq!(
+            TaskCounters::DroppedLateRecords
+                .for_task(&ctx.task_info)
+                .get(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_updating_session_window_late_records() {
+        let mut op = UpdatingSessionWindowFunc::<u32, u64, u64>::new(
+            Duration::from_millis(10),
+            Duration::from_millis(30),
+            sum,
+        );
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(op.tables()).await;
+        ctx.task_info = Arc::new(TaskInfo {
+            operator_id: "updating-session-window-late-records".to_string(),
+            ..(*ctx.task_info).clone()
+        });
+        let end_of = |millis: u64| from_millis(millis) - Duration::from_nanos(1);
+
+        op.process_element(&record(1, 1), &mut ctx).await;
+        op.process_element(&record(5, 2), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        let advance = |watermark: u64, ctx: &mut Context<u32, UpdatingData<u64>>| {
+            let watermark = Watermark::EventTime(from_millis(watermark));
+            ctx.watermarks.set(0, watermark);
+            watermark
+        };
+
+        let watermark = advance(16, &mut ctx);
+        op.handle_watermark_int(watermark, &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![(end_of(15), UpdatingData::Append(3))]);
+
+        // a late record inside the emitted session updates it
+        op.process_element(&record(3, 4), &mut ctx).await;
+        assert_eq!(
+            drain(&mut rx),
+            vec![(end_of(15), UpdatingData::Update { old: 3, new: 7 })]
+        );
+
+        // one that extends it past the watermark re-opens it
+        op.process_element(&record(14, 8), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![(end_of(15), UpdatingData::Retract(7))]);
+        op.process_element(&record(30, 16), &mut ctx).await;
+        let watermark = advance(45, &mut ctx);
+        op.handle_watermark_int(watermark, &mut ctx).await;
+        assert_eq!(
+            drain(&mut rx),
+            vec![
+                (end_of(24), UpdatingData::Append(15)),
+                (end_of(40), UpdatingData::Append(16))
+            ]
+        );
+
+        // one that fills the gap between two emitted sessions merges them
+        op.process_element(&record(23, 32), &mut ctx).await;
+        assert_eq!(
+            drain(&mut rx),
+            vec![
+                (end_of(24), UpdatingData::Retract(15)),
+                (end_of(40), UpdatingData::Retract(16)),
+                (end_of(40), UpdatingData::Append(63))
+            ]
+        );
+
+        // once the allowed lateness has passed, the session's state is cleared and records that
+        // would have been added to it are dropped
+        let watermark = advance(71, &mut ctx);
+        op.handle_watermark_int(watermark, &mut ctx).await;
+        assert!(
+            UpdatingSessionWindowFunc::<u32, u64, u64>::sessions(&mut 1, &mut ctx)
+                .await
+                .is_empty()
+        );
+        op.process_element(&record(20, 64), &mut ctx).await;
+        assert_eq!(drain(&mut rx), vec![]);
+
+        assert_eq!(TaskCounters::LateRecords.for_task(&ctx.task_info).get(), 3);
+        assert_eq!(
+            TaskCounters::DroppedLateRecords
+                .for_task(&ctx.task_info)
+                .get(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sliding_late_record_filter() {
+        let mut filter = LateRecordFilter::<u32, u64>::sliding_window(
+            Duration::from_millis(30),
+            Duration::from_millis(10),
+            Duration::ZERO,
+        );
+        let (mut ctx, mut rx) = Context::new_for_test_with_tables(vec![]).await;
+        let mut emitted = || {
+            let mut out = vec![];
+            while let Ok(item) = rx.try_recv() {
+                if let Message::Record(record) = Message::<u32, u64>::from(item) {
+                    out.push(record.value);
+                }
+            }
+            out
+        };
+
+        // a record at 1005 falls into the windows ending at 1010, 1020 and 1030, so it is only
+        // late once all of them have closed
+        for (watermark, late) in [(1010, false), (1020, false), (1029, false), (1030, true)] {
+            ctx.watermarks
+                .set(0, Watermark::EventTime(from_millis(watermark)));
+            filter
+                .process_element(&record(1005, watermark), &mut ctx)
+                .await;
+            let expected = if late { vec![watermark] } else { vec![] };
+            assert_eq!(emitted(), expected, "watermark {}", watermark);
+        }
+    }
 }